@@ -4,6 +4,7 @@ mod shader_compiler;
 mod spirv_transformer;
 pub mod android;
 pub use shader_compiler::*;
+pub use spirv_transformer::{spirv_reflect, spirv_remove_debug_info, spirv_remove_debug_info_keep_entry_names, Binding, ReflectedBinding, ShaderReflection};
 pub mod build_script_logger;
 
 pub fn copy_directory_rec<F>(from: &Path, to: &Path, file_filter: &F)
@@ -1,6 +1,7 @@
 use core::panic;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ffi::{c_char, CStr, c_void};
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::fs::*;
 use std::io::{Read, Write};
 use std::path::*;
@@ -193,6 +194,7 @@ fn read_metadata(
     let mut uses_bindless_texture_set = false;
     let mut stage_input_count = 0u32;
     let mut max_stage_input = 0u32;
+    let depth_compare_variables = spirv_find_depth_compare_variables(spirv);
 
     // Generate metadata
     let mut context: spirv_cross_sys::spvc_context = std::ptr::null_mut();
@@ -334,7 +336,8 @@ fn read_metadata(
         resource_type: gpu::ResourceType,
         can_be_writable: bool,
         resources: &mut [Vec<gpu::Resource>; gpu::NON_BINDLESS_SET_COUNT as usize],
-        uses_bindless_texture_set: &mut bool
+        uses_bindless_texture_set: &mut bool,
+        depth_compare_variables: &HashSet<u32>
     ) {
         let mut spv_resources_ptr: spirv_cross_sys::spvc_resources = std::ptr::null_mut();
         spirv_cross_sys::spvc_compiler_create_shader_resources(
@@ -462,6 +465,11 @@ fn read_metadata(
                 }
             }
 
+            // Separate images and samplers that get used for depth comparisons only show that through the way they get combined.
+            if depth_compare_variables.contains(&resource.id) {
+                sampling_type = gpu::SamplingType::Depth;
+            }
+
             set.push(gpu::Resource {
                 name: name,
                 set: set_index,
@@ -485,7 +493,8 @@ fn read_metadata(
             gpu::ResourceType::SampledTexture,
             false,
             &mut resources,
-            &mut uses_bindless_texture_set
+            &mut uses_bindless_texture_set,
+            &depth_compare_variables
         );
         read_resources(
             compiler,
@@ -493,7 +502,8 @@ fn read_metadata(
             gpu::ResourceType::Sampler,
             false,
             &mut resources,
-            &mut uses_bindless_texture_set
+            &mut uses_bindless_texture_set,
+            &depth_compare_variables
         );
         read_resources(
             compiler,
@@ -501,7 +511,8 @@ fn read_metadata(
             gpu::ResourceType::CombinedTextureSampler,
             false,
             &mut resources,
-            &mut uses_bindless_texture_set
+            &mut uses_bindless_texture_set,
+            &depth_compare_variables
         );
         read_resources(
             compiler,
@@ -509,7 +520,8 @@ fn read_metadata(
             gpu::ResourceType::SubpassInput,
             false,
             &mut resources,
-            &mut uses_bindless_texture_set
+            &mut uses_bindless_texture_set,
+            &depth_compare_variables
         );
         read_resources(
             compiler,
@@ -517,7 +529,8 @@ fn read_metadata(
             gpu::ResourceType::UniformBuffer,
             false,
             &mut resources,
-            &mut uses_bindless_texture_set
+            &mut uses_bindless_texture_set,
+            &depth_compare_variables
         );
         read_resources(
             compiler,
@@ -525,7 +538,8 @@ fn read_metadata(
             gpu::ResourceType::StorageBuffer,
             true,
             &mut resources,
-            &mut uses_bindless_texture_set
+            &mut uses_bindless_texture_set,
+            &depth_compare_variables
         );
         read_resources(
             compiler,
@@ -533,7 +547,8 @@ fn read_metadata(
             gpu::ResourceType::StorageTexture,
             true,
             &mut resources,
-            &mut uses_bindless_texture_set
+            &mut uses_bindless_texture_set,
+            &depth_compare_variables
         );
         read_resources(
             compiler,
@@ -541,7 +556,8 @@ fn read_metadata(
             gpu::ResourceType::AccelerationStructure,
             false,
             &mut resources,
-            &mut uses_bindless_texture_set
+            &mut uses_bindless_texture_set,
+            &depth_compare_variables
         );
     }
 
@@ -870,7 +886,7 @@ fn write_shader(
     output_dir: &Path,
     output_shading_language: ShadingLanguage,
     shader: CompiledShaderType
-) {
+) -> PathBuf {
     let mut compiled_file_name = input_shader_path.file_stem().unwrap().to_str().unwrap().to_string();
     match &shader {
         CompiledShaderType::Packed(_) => compiled_file_name.push_str(".json"),
@@ -888,19 +904,108 @@ fn write_shader(
 
     match shader {
         CompiledShaderType::Bytecode(bytecode) => {
-            let mut file = std::fs::File::create(compiled_file_path).expect("Failed to open file");
+            let mut file = std::fs::File::create(&compiled_file_path).expect("Failed to open file");
             file.write_all(bytecode).expect("Failed to write shader file");
         }
         CompiledShaderType::Source(source) => {
-            let mut file = std::fs::File::create(compiled_file_path).expect("Failed to open file");
+            let mut file = std::fs::File::create(&compiled_file_path).expect("Failed to open file");
             write!(file, "{}", source).expect("Failed to write shader file");
         }
         CompiledShaderType::Packed(packed_shader) => {
             let serialized_str = serde_json::to_string(&packed_shader).expect("Failed to serialize");
-            let mut file = std::fs::File::create(compiled_file_path).expect("Failed to open file");
+            let mut file = std::fs::File::create(&compiled_file_path).expect("Failed to open file");
             write!(file, "{}", serialized_str).expect("Failed to write shader file");
         }
     }
+    compiled_file_path
+}
+
+/// Collects the shader and every file it includes, directly or indirectly.
+fn collect_shader_sources(file_path: &Path, sources: &mut Vec<PathBuf>, visited: &mut HashSet<PathBuf>) {
+    if !visited.insert(file_path.to_path_buf()) {
+        return;
+    }
+    sources.push(file_path.to_path_buf());
+    let source = if let Ok(source) = std::fs::read_to_string(file_path) {
+        source
+    } else {
+        return;
+    };
+    let dir = file_path.parent().unwrap_or(Path::new(""));
+    for line in source.lines() {
+        let line = line.trim_start();
+        if !line.starts_with("#include") {
+            continue;
+        }
+        let include = line["#include".len()..].trim();
+        let include = include.trim_start_matches(|c| c == '"' || c == '<');
+        let include = include.split(|c| c == '"' || c == '>').next().unwrap_or("");
+        if !include.is_empty() {
+            collect_shader_sources(&dir.join(include), sources, visited);
+        }
+    }
+}
+
+/// Hashes everything that affects the compiled output of a shader.
+/// DefaultHasher::new() always uses the same keys, so the hash is stable between builds made with the same toolchain.
+fn compute_shader_hash(
+    file_path: &Path,
+    output_shading_languages: ShadingLanguage,
+    output_file_type: CompiledShaderFileType,
+    include_debug_info: bool,
+    arguments: &HashMap<String, String>,
+) -> u64 {
+    let mut sources = Vec::<PathBuf>::new();
+    collect_shader_sources(file_path, &mut sources, &mut HashSet::new());
+
+    let mut hasher = DefaultHasher::new();
+    for source in &sources {
+        println!("cargo:rerun-if-changed={}", source.to_str().unwrap());
+        source.hash(&mut hasher);
+        // A missing include makes glslang fail, so it doesn't need to be part of the hash.
+        std::fs::read(source).unwrap_or_default().hash(&mut hasher);
+    }
+    let mut sorted_arguments: Vec<(&String, &String)> = arguments.iter().collect();
+    sorted_arguments.sort();
+    sorted_arguments.hash(&mut hasher);
+    output_shading_languages.bits().hash(&mut hasher);
+    (output_file_type == CompiledShaderFileType::Packed).hash(&mut hasher);
+    include_debug_info.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The cache manifest stores the hash of the shader inputs followed by the files that were produced from them.
+fn shader_cache_manifest_path(file_path: &Path, output_dir: &Path, output_file_type: CompiledShaderFileType) -> PathBuf {
+    let mut manifest_file_name = file_path.file_stem().unwrap().to_str().unwrap().to_string();
+    match output_file_type {
+        CompiledShaderFileType::Packed => manifest_file_name.push_str(".packed.cache"),
+        CompiledShaderFileType::Bytecode => manifest_file_name.push_str(".bytecode.cache"),
+    }
+    output_dir.join(manifest_file_name)
+}
+
+fn is_shader_cache_valid(manifest_path: &Path, hash: u64) -> bool {
+    let manifest = if let Ok(manifest) = std::fs::read_to_string(manifest_path) {
+        manifest
+    } else {
+        return false;
+    };
+    let mut lines = manifest.lines();
+    if lines.next() != Some(format!("{:016x}", hash).as_str()) {
+        return false;
+    }
+    lines.all(|output_file| Path::new(output_file).exists())
+}
+
+fn write_shader_cache_manifest(manifest_path: &Path, hash: u64, output_files: &[PathBuf]) {
+    let mut manifest = format!("{:016x}\n", hash);
+    for output_file in output_files {
+        manifest.push_str(output_file.to_str().unwrap());
+        manifest.push('\n');
+    }
+    if let Err(e) = std::fs::write(manifest_path, manifest) {
+        error!("Failed to write shader cache manifest: {:?} {:?}", manifest_path, e);
+    }
 }
 
 fn compile_msl_to_air(
@@ -1071,6 +1176,16 @@ pub fn compile_shader(
 
     let shader_name = &file_path.file_stem().unwrap().to_string_lossy();
 
+    let hash = compute_shader_hash(file_path, output_shading_languages, output_file_type, include_debug_info, arguments);
+    let manifest_path = shader_cache_manifest_path(file_path, output_dir, output_file_type);
+    if is_shader_cache_valid(&manifest_path, hash) {
+        info!("Shader is up to date: {:?}", file_path);
+        return;
+    }
+    // Only cache the result if every requested output was produced.
+    let mut output_files = Vec::<PathBuf>::new();
+    let mut complete = true;
+
     // Compile GLSL to SPIR-V
     //
     let spirv_bytecode_res = compile_shader_glsl(file_path, output_dir, shader_type, include_debug_info, arguments);
@@ -1089,7 +1204,9 @@ pub fn compile_shader(
         }
         let source = compile_shader_spirv_cross(&spirv_bytecode_boxed, shader_name, shader_type, &metadata, ShadingLanguage::Msl);
         if let Ok(source) = source {
-            write_shader(file_path, output_dir, ShadingLanguage::Msl, CompiledShaderType::Source(&source));
+            output_files.push(write_shader(file_path, output_dir, ShadingLanguage::Msl, CompiledShaderType::Source(&source)));
+        } else {
+            complete = false;
         }
     }
     if output_shading_languages.contains(ShadingLanguage::Hlsl) {
@@ -1098,7 +1215,9 @@ pub fn compile_shader(
         }
         let source = compile_shader_spirv_cross(&spirv_bytecode_boxed, shader_name, shader_type, &metadata, ShadingLanguage::Hlsl);
         if let Ok(source) = source {
-            write_shader(file_path, output_dir, ShadingLanguage::Hlsl, CompiledShaderType::Source(&source));
+            output_files.push(write_shader(file_path, output_dir, ShadingLanguage::Hlsl, CompiledShaderType::Source(&source)));
+        } else {
+            complete = false;
         }
     }
     if output_shading_languages.contains(ShadingLanguage::Air) {
@@ -1106,10 +1225,12 @@ pub fn compile_shader(
         let bytecode = msl.and_then(|msl| compile_msl_to_air(msl, shader_name, &std::env::temp_dir(), include_debug_info));
         if let Ok(bytecode) = bytecode {
             if output_file_type == CompiledShaderFileType::Bytecode {
-                write_shader(file_path, output_dir, ShadingLanguage::Air, CompiledShaderType::Bytecode(&bytecode));
+                output_files.push(write_shader(file_path, output_dir, ShadingLanguage::Air, CompiledShaderType::Bytecode(&bytecode)));
             } else if output_file_type == CompiledShaderFileType::Packed {
                 metadata.shader_air = bytecode;
             }
+        } else {
+            complete = false;
         }
     }
     if output_shading_languages.contains(ShadingLanguage::Dxil) {
@@ -1118,15 +1239,17 @@ pub fn compile_shader(
         let bytecode = Result::<Box<[u8]>, ()>::Err(());
         if let Ok(bytecode) = bytecode {
             if output_file_type == CompiledShaderFileType::Bytecode {
-                write_shader(file_path, output_dir, ShadingLanguage::Dxil, CompiledShaderType::Bytecode(&bytecode));
+                output_files.push(write_shader(file_path, output_dir, ShadingLanguage::Dxil, CompiledShaderType::Bytecode(&bytecode)));
             } else if output_file_type == CompiledShaderFileType::Packed {
                 metadata.shader_dxil = bytecode;
             }
+        } else {
+            complete = false;
         }
     }
     if output_shading_languages.contains(ShadingLanguage::Wgsl) {
         let mut prepared_spirv = spirv_bytecode_boxed.clone().into_vec();
-        spirv_remove_debug_info(&mut prepared_spirv);
+        spirv_remove_debug_info_keep_entry_names(&mut prepared_spirv);
         spirv_remap_bindings(&mut prepared_spirv, |binding| Binding {
             descriptor_set: binding.descriptor_set,
             binding: if binding.descriptor_set == gpu::BindingFrequency::VeryFrequent as u32 { binding.binding + 1 } else { binding.binding }
@@ -1137,22 +1260,28 @@ pub fn compile_shader(
         let wgsl = compile_shader_naga(shader_name, &prepared_spirv);
         if let Ok(bytecode) = wgsl {
             if output_file_type == CompiledShaderFileType::Bytecode {
-                write_shader(file_path, output_dir, ShadingLanguage::Wgsl, CompiledShaderType::Source(&bytecode));
+                output_files.push(write_shader(file_path, output_dir, ShadingLanguage::Wgsl, CompiledShaderType::Source(&bytecode)));
             } else if output_file_type == CompiledShaderFileType::Packed {
                 metadata.shader_wgsl = bytecode;
             }
+        } else {
+            complete = false;
         }
     }
     if output_shading_languages.contains(ShadingLanguage::SpirV) {
         if output_file_type == CompiledShaderFileType::Bytecode {
-            write_shader(file_path, output_dir, ShadingLanguage::SpirV, CompiledShaderType::Bytecode(&spirv_bytecode_boxed));
+            output_files.push(write_shader(file_path, output_dir, ShadingLanguage::SpirV, CompiledShaderType::Bytecode(&spirv_bytecode_boxed)));
         } else if output_file_type == CompiledShaderFileType::Packed {
             metadata.shader_spirv = spirv_bytecode_boxed;
         }
     }
 
     if output_file_type == CompiledShaderFileType::Packed {
-        write_shader(file_path, output_dir, output_shading_languages, CompiledShaderType::Packed(&metadata));
+        output_files.push(write_shader(file_path, output_dir, output_shading_languages, CompiledShaderType::Packed(&metadata)));
+    }
+
+    if complete {
+        write_shader_cache_manifest(&manifest_path, hash, &output_files);
     }
 }
 
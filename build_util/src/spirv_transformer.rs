@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fs::File, io::Write, ops::Range, process::Command, u32};
+use std::{collections::{HashMap, HashSet}, fs::File, io::Write, ops::Range, process::Command, u32};
 
 #[derive(Debug, Clone)]
 struct Instruction {
@@ -85,10 +85,21 @@ fn build_instruction_description(instruction: &Instruction) -> u32 {
 const OP_CODE_OP_DECORATE: u16 = 71;
 const OP_CODE_OP_TYPE_POINTER: u16 = 32;
 const OP_CODE_OP_TYPE_VARIABLE: u16 = 59;
+const OP_CODE_OP_TYPE_BOOL: u16 = 20;
+const OP_CODE_OP_TYPE_INT: u16 = 21;
+const OP_CODE_OP_TYPE_FLOAT: u16 = 22;
+const OP_CODE_OP_TYPE_VECTOR: u16 = 23;
+const OP_CODE_OP_TYPE_MATRIX: u16 = 24;
 const OP_CODE_OP_TYPE_IMAGE: u16 = 25;
 const OP_CODE_OP_TYPE_SAMPLER: u16 = 26;
 const OP_CODE_OP_TYPE_SAMPLED_IMAGE: u16 = 27;
+const OP_CODE_OP_TYPE_ARRAY: u16 = 28;
+const OP_CODE_OP_TYPE_RUNTIME_ARRAY: u16 = 29;
+const OP_CODE_OP_TYPE_STRUCT: u16 = 30;
 const OP_CODE_OP_TYPE_FUNCTION: u16 = 33;
+const OP_CODE_OP_TYPE_ACCELERATION_STRUCTURE: u16 = 5341;
+const OP_CODE_OP_CONSTANT: u16 = 43;
+const OP_CODE_OP_MEMBER_DECORATE: u16 = 72;
 const OP_CODE_OP_FUNCTION: u16 = 54;
 const OP_CODE_OP_FUNCTION_PARAMETER: u16 = 55;
 const OP_CODE_OP_FUNCTION_CALL: u16 = 57;
@@ -108,14 +119,22 @@ const OP_CODE_OP_LINE: u16 = 8;
 const OP_CODE_OP_NO_LINE: u16 = 317;
 const OP_CODE_OP_MODULE_PROCESSED: u16 = 330;
 
-const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_ROW_MAJOR: u32 = 4;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
 const DECORATION_BINDING: u32 = 33;
 const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+const DIM_SUBPASS_DATA: u32 = 6;
 
 const STORAGE_CLASS_UNIFORM_CONSTANT: u32 = 0;
 const STORAGE_CLASS_FUNCTION: u32 = 7;
 const STORAGE_CLASS_UNIFORM: u32 = 2;
 const STORAGE_CLASS_PUSH_CONSTANT: u32 = 9;
+const STORAGE_CLASS_STORAGE_BUFFER: u32 = 12;
+
 fn parse_op_type_pointer(words: &[u32]) -> OpTypePointer {
     OpTypePointer {
         result_id: words[0],
@@ -193,6 +212,26 @@ fn parse_op_function_call(words: &[u32]) -> OpFunctionCall {
     }
 }
 
+/// Whether the instruction takes a sampled image as its third operand (the image operand of every image instruction).
+/// Fetches, reads and size queries take a plain image, shaders use them on combined image samplers through OpImage.
+fn is_sampled_image_op(opcode: u16) -> bool {
+    // OpImageSampleImplicitLod ..= OpImageSampleProjDrefExplicitLod, OpImageGather, OpImageDrefGather (not OpImageFetch)
+    (opcode >= 87 && opcode <= 97 && opcode != 95)
+        // OpImage
+        || opcode == 100
+        // OpImageQueryLod
+        || opcode == 105
+        // OpImageSparseSampleImplicitLod ..= OpImageSparseDrefGather (not OpImageSparseFetch)
+        || (opcode >= 305 && opcode <= 315 && opcode != 313)
+        // OpImageSampleWeightedQCOM, OpImageBoxFilterQCOM
+        || opcode == 4480
+        || opcode == 4481
+        // OpImageBlockMatchWindowSSDQCOM ..= OpImageBlockMatchGatherSADQCOM
+        || (opcode >= 4500 && opcode <= 4503)
+        // OpImageSampleFootprintNV
+        || opcode == 5283
+}
+
 fn cast_to_words<'a>(spirv: &'a mut [u8]) -> &'a mut [u32] {
     assert_eq!(spirv.len() % std::mem::size_of::<u32>(), 0);
     assert_eq!(spirv.as_ptr() as usize % std::mem::align_of::<u32>(), 0);
@@ -225,9 +264,11 @@ fn remove_words(spirv: &mut Vec<u8>, word_range: Range<usize>) {
     spirv.drain(byte_start .. byte_end);
 }
 
-fn spirv_pass(spirv: &mut [u8], mut process_word: impl FnMut(usize, Instruction, &mut [u32]) -> bool) {
-    let words = cast_to_words(spirv);
+fn spirv_pass(spirv: &mut [u8], process_word: impl FnMut(usize, Instruction, &mut [u32]) -> bool) {
+    spirv_words_pass(cast_to_words(spirv), process_word);
+}
 
+fn spirv_words_pass(words: &mut [u32], mut process_word: impl FnMut(usize, Instruction, &mut [u32]) -> bool) {
     let mut index = 0usize;
     assert_eq!(words[0], 0x07230203);
     index += 5;
@@ -307,7 +348,7 @@ pub fn spirv_turn_push_const_into_ubo_pass(spirv: &mut Vec<u8>, descriptor_set:
             word_count: 4, opcode: OP_CODE_OP_DECORATE
         }),
         target_id,
-        DECORATION_LOCATION,
+        DECORATION_BINDING,
         index,
     ]);
 
@@ -315,8 +356,29 @@ pub fn spirv_turn_push_const_into_ubo_pass(spirv: &mut Vec<u8>, descriptor_set:
 }
 
 pub fn spirv_remove_debug_info(spirv: &mut Vec<u8>) {
+    remove_debug_info(spirv, false);
+}
+
+/// Removes the debug info except for the names of the entry point functions.
+/// Translators like naga name the generated functions after those.
+pub fn spirv_remove_debug_info_keep_entry_names(spirv: &mut Vec<u8>) {
+    remove_debug_info(spirv, true);
+}
+
+fn remove_debug_info(spirv: &mut Vec<u8>, keep_entry_names: bool) {
     let mut ranges: Vec<Range<usize>> = Vec::new();
-    spirv_pass(spirv, |word_index, instruction, _operand_words| {
+    let mut entry_point_function_ids = Vec::<u32>::new();
+    spirv_pass(spirv, |word_index, instruction, operand_words| {
+        // Entry points always come before the debug instructions
+        if instruction.opcode == OP_CODE_OP_ENTRY_POINT {
+            entry_point_function_ids.push(operand_words[1]);
+            return true;
+        }
+        if keep_entry_names
+            && instruction.opcode == OP_CODE_OP_NAME
+            && entry_point_function_ids.contains(&operand_words[0]) {
+            return true;
+        }
         if instruction.opcode == OP_CODE_OP_SOURCE_CONTINUED
             || instruction.opcode == OP_CODE_OP_SOURCE
             || instruction.opcode == OP_CODE_OP_SOURCE_EXTENSION
@@ -325,7 +387,6 @@ pub fn spirv_remove_debug_info(spirv: &mut Vec<u8>) {
             || instruction.opcode == OP_CODE_OP_STRING
             || instruction.opcode == OP_CODE_OP_LINE
             || instruction.opcode == OP_CODE_OP_NO_LINE
-            || instruction.opcode == OP_CODE_OP_NAME
             || instruction.opcode == OP_CODE_OP_MODULE_PROCESSED {
             ranges.push(Range {
                 start: word_index, end: word_index + (instruction.word_count as usize)
@@ -347,8 +408,9 @@ pub struct Binding {
     pub binding: u32
 }
 pub fn spirv_remap_bindings(spirv: &mut Vec<u8>, callback: impl Fn(&Binding) -> Binding) {
-    let mut bindings = HashMap::<u32, Binding>::new();
-    spirv_pass(spirv, |_word_index, instruction, operand_words| {
+    // Target id -> (binding, word index of the descriptor set value, word index of the binding value)
+    let mut bindings = HashMap::<u32, (Binding, usize, usize)>::new();
+    spirv_pass(spirv, |word_index, instruction, operand_words| {
         if instruction.opcode != OP_CODE_OP_DECORATE {
             return true;
         }
@@ -356,35 +418,44 @@ pub fn spirv_remap_bindings(spirv: &mut Vec<u8>, callback: impl Fn(&Binding) ->
         if decorate.decoration_id == DECORATION_DESCRIPTOR_SET {
             let entry = bindings
                 .entry(decorate.target_id)
-                .or_insert(Binding {
+                .or_insert((Binding {
                     descriptor_set: u32::MAX,
                     binding: u32::MAX
-                });
-            entry.descriptor_set = decorate.value.unwrap();
-            if entry.descriptor_set != u32::MAX && entry.binding != u32::MAX {
-                *entry = callback(entry);
-            }
+                }, usize::MAX, usize::MAX));
+            entry.0.descriptor_set = decorate.value.unwrap();
+            entry.1 = word_index + 3;
             return true;
         }
         if decorate.decoration_id == DECORATION_BINDING {
             let entry = bindings
                 .entry(decorate.target_id)
-                .or_insert(Binding {
+                .or_insert((Binding {
                     descriptor_set: u32::MAX,
                     binding: u32::MAX
-                });
-            entry.binding = decorate.value.unwrap();
-            if entry.descriptor_set != u32::MAX && entry.binding != u32::MAX {
-                *entry = callback(entry);
-            }
+                }, usize::MAX, usize::MAX));
+            entry.0.binding = decorate.value.unwrap();
+            entry.2 = word_index + 3;
             return true;
         }
         return true;
     });
 
+    // The callback needs both values, so the decorations can only be rewritten once all of them are known.
+    let words = cast_to_words(spirv);
+    for (binding, descriptor_set_word_index, binding_word_index) in bindings.values() {
+        if binding.descriptor_set == u32::MAX || binding.binding == u32::MAX {
+            continue;
+        }
+        let remapped_binding = callback(binding);
+        words[*descriptor_set_word_index] = remapped_binding.descriptor_set;
+        words[*binding_word_index] = remapped_binding.binding;
+    }
+
     println!("Done remapping bindings");
 }
 
+/// The bindings of a combined image sampler after it was split into a separate image and sampler.
+#[derive(Clone, Debug)]
 pub struct ImageSamplerPair {
     pub image: Binding,
     pub sampler: Binding
@@ -401,6 +472,9 @@ struct SampledImageTypeMappings {
     pub sampler_type_ptr_type: u32,
     pub sampler_type_ptr_var: u32,
 }
+/// Splits every combined image sampler into a separate image and sampler.
+/// The sampler gets the binding picked by `decide_binding` or the next free binding in the set of the image.
+/// Returns the image and sampler binding of every split so the pipeline layout can be built to match.
 pub fn spirv_separate_combined_image_samplers(spirv: &mut Vec<u8>, decide_binding: Option<impl Fn(&Binding) -> Binding>) -> Vec<ImageSamplerPair> {
     let mut sampled_img_types = Vec::<(usize, Instruction, OpTypeSampledImage)>::new();
     let mut img_types = Vec::<(usize, Instruction, OpTypeImage)>::new();
@@ -480,18 +554,7 @@ pub fn spirv_separate_combined_image_samplers(spirv: &mut Vec<u8>, decide_bindin
             loads.push((word_index, instruction, load));
             return true;
         }
-        if false
-            || (instruction.opcode >= 87
-                && instruction.opcode != 95
-                && instruction.opcode <= 97)
-            || instruction.opcode == 100
-            || instruction.opcode == 105
-            || (instruction.opcode >= 305
-                && instruction.opcode != 313
-                && instruction.opcode <= 315)
-            || (instruction.opcode >= 4500
-                && instruction.opcode <= 4503)
-            || instruction.opcode == 5283 {
+        if is_sampled_image_op(instruction.opcode) {
             sampling_ops_indices.push(word_index);
         }
         if instruction.opcode == OP_CODE_OP_ENTRY_POINT {
@@ -681,18 +744,25 @@ pub fn spirv_separate_combined_image_samplers(spirv: &mut Vec<u8>, decide_bindin
 
         // Add sampler to entry point
 
+        // Only entry points that list the image in their interface need the sampler.
+        // Before SPIR-V 1.4 the interface only contains inputs and outputs, so this doesn't add it anywhere.
+
         for entry_point_ in &entry_point_indices {
             let entry_point_instruction = parse_instruction_description(words[*entry_point_]);
-            for word in &words[entry_point_ + 3 .. entry_point_ + entry_point_instruction.word_count as usize] {
-                if *word == mapping.image_type_ptr_var {
-                    insertions.push((entry_point_ + entry_point_instruction.word_count as usize, vec![
-                        mapping.sampler_type_ptr_var
-                    ]));
-                    word_count_increases.push(*entry_point_);
-                    break;
-                }
+            let entry_point_end = entry_point_ + entry_point_instruction.word_count as usize;
+            // The interface follows the execution model, the function id and the null terminated name.
+            let name_start = entry_point_ + 3;
+            let name_len = words[name_start .. entry_point_end].iter()
+                .position(|word| word.to_le_bytes().contains(&0))
+                .map(|position| position + 1)
+                .unwrap_or(entry_point_end - name_start);
+            let interface = &words[name_start + name_len .. entry_point_end];
+            if interface.contains(&mapping.image_type_ptr_var) {
+                insertions.push((entry_point_end, vec![
+                    mapping.sampler_type_ptr_var
+                ]));
+                word_count_increases.push(*entry_point_);
             }
-            words[*entry_point_] = build_instruction_description(&entry_point_instruction);
         }
 
         // Find highest binding for descriptor set of image and add decorations for the sampler 1 above that (the binding before is important for consistency)
@@ -732,15 +802,8 @@ pub fn spirv_separate_combined_image_samplers(spirv: &mut Vec<u8>, decide_bindin
         });
     }
 
-    // Insert prepared words
-    // Has to be done at the end to avoid screwing up collected indices
+    // Grow the entry points before inserting anything, the collected indices are only valid until then
 
-    let mut insertion_offset = 0usize;
-    insertions.sort_by_key(|(idx, _)| *idx);
-    for (insertion_index, words) in insertions {
-        insert_words(spirv, insertion_index + insertion_offset, &words);
-        insertion_offset += words.len();
-    }
     {
         let words = cast_to_words(spirv);
         for word_count_increase_ in word_count_increases {
@@ -750,6 +813,16 @@ pub fn spirv_separate_combined_image_samplers(spirv: &mut Vec<u8>, decide_bindin
         }
     }
 
+    // Insert prepared words
+    // Has to be done at the end to avoid screwing up collected indices
+
+    let mut insertion_offset = 0usize;
+    insertions.sort_by_key(|(idx, _)| *idx);
+    for (insertion_index, words) in insertions {
+        insert_words(spirv, insertion_index + insertion_offset, &words);
+        insertion_offset += words.len();
+    }
+
     // Increase max id
     {
         let words = cast_to_words(spirv);
@@ -761,6 +834,253 @@ pub fn spirv_separate_combined_image_samplers(spirv: &mut Vec<u8>, decide_bindin
     result
 }
 
+#[derive(Clone, Debug)]
+pub struct ReflectedBinding {
+    pub binding: Binding,
+    pub resource_type: sourcerenderer_core::gpu::ResourceType,
+    /// 1 for single resources and 0 for runtime sized arrays
+    pub array_size: u32,
+    /// The declared size of uniform and storage buffer blocks, 0 for everything else
+    pub block_size: u32
+}
+
+/// The final descriptor layout of a transformed shader.
+#[derive(Clone, Debug, Default)]
+pub struct ShaderReflection {
+    /// Sorted by descriptor set and binding
+    pub bindings: Vec<ReflectedBinding>,
+    pub push_constant_size: u32
+}
+
+struct ReflectionTypes {
+    /// Result id -> opcode and operands of types and constants
+    types: HashMap<u32, (u16, Vec<u32>)>,
+    decorations: HashMap<(u32, u32), u32>,
+    member_decorations: HashMap<(u32, u32, u32), u32>
+}
+
+impl ReflectionTypes {
+    fn constant(&self, id: u32) -> Option<u32> {
+        self.types.get(&id)
+            .filter(|(opcode, _)| *opcode == OP_CODE_OP_CONSTANT)
+            .map(|(_, operands)| operands[2])
+    }
+
+    /// The size of the type using the offsets and strides of the explicit layout.
+    fn size(&self, type_id: u32) -> u32 {
+        let (opcode, operands) = if let Some(ty) = self.types.get(&type_id) { ty } else { return 0; };
+        match *opcode {
+            OP_CODE_OP_TYPE_BOOL => 4,
+            OP_CODE_OP_TYPE_INT | OP_CODE_OP_TYPE_FLOAT => operands[1] / 8,
+            OP_CODE_OP_TYPE_VECTOR => operands[2] * self.size(operands[1]),
+            OP_CODE_OP_TYPE_MATRIX => operands[2] * self.size(operands[1]),
+            OP_CODE_OP_TYPE_ARRAY => {
+                let length = self.constant(operands[2]).unwrap_or(0);
+                let stride = self.decorations.get(&(type_id, DECORATION_ARRAY_STRIDE)).copied()
+                    .unwrap_or_else(|| self.size(operands[1]));
+                length * stride
+            },
+            OP_CODE_OP_TYPE_STRUCT => {
+                let mut size = 0u32;
+                for (member_index, member_type_id) in operands[1..].iter().enumerate() {
+                    let member_index = member_index as u32;
+                    let offset = self.member_decorations.get(&(type_id, member_index, DECORATION_OFFSET)).copied().unwrap_or(0);
+                    let member_size = match (self.types.get(member_type_id), self.member_decorations.get(&(type_id, member_index, DECORATION_MATRIX_STRIDE))) {
+                        (Some((OP_CODE_OP_TYPE_MATRIX, matrix_operands)), Some(matrix_stride)) => {
+                            if self.member_decorations.contains_key(&(type_id, member_index, DECORATION_ROW_MAJOR)) {
+                                let column_type = &self.types.get(&matrix_operands[1]).unwrap().1;
+                                column_type[2] * matrix_stride
+                            } else {
+                                matrix_operands[2] * matrix_stride
+                            }
+                        },
+                        _ => self.size(*member_type_id)
+                    };
+                    size = size.max(offset + member_size);
+                }
+                size
+            },
+            OP_CODE_OP_TYPE_POINTER => 8,
+            _ => 0
+        }
+    }
+}
+
+/// Reads the descriptor bindings and the push constant size of a shader.
+/// Meant to be used after the transformations above, so the layout matches the shader that actually gets used.
+pub fn spirv_reflect(spirv: &[u8]) -> ShaderReflection {
+    assert_eq!(spirv.len() % std::mem::size_of::<u32>(), 0);
+    let mut words: Vec<u32> = spirv
+        .chunks_exact(std::mem::size_of::<u32>())
+        .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+        .collect();
+
+    let mut types = ReflectionTypes {
+        types: HashMap::new(),
+        decorations: HashMap::new(),
+        member_decorations: HashMap::new()
+    };
+    let mut vars = Vec::<OpVariable>::new();
+    spirv_words_pass(&mut words, |_word_index, instruction, operand_words| {
+        match instruction.opcode {
+            OP_CODE_OP_DECORATE => {
+                let decoration = parse_op_decorate(operand_words);
+                types.decorations.insert((decoration.target_id, decoration.decoration_id), decoration.value.unwrap_or(0));
+            },
+            OP_CODE_OP_MEMBER_DECORATE => {
+                types.member_decorations.insert((operand_words[0], operand_words[1], operand_words[2]), operand_words.get(3).copied().unwrap_or(0));
+            },
+            OP_CODE_OP_TYPE_VARIABLE => {
+                vars.push(parse_op_variable(operand_words));
+            },
+            OP_CODE_OP_CONSTANT => {
+                types.types.insert(operand_words[1], (instruction.opcode, operand_words.to_vec()));
+            },
+            OP_CODE_OP_TYPE_BOOL
+            | OP_CODE_OP_TYPE_INT
+            | OP_CODE_OP_TYPE_FLOAT
+            | OP_CODE_OP_TYPE_VECTOR
+            | OP_CODE_OP_TYPE_MATRIX
+            | OP_CODE_OP_TYPE_IMAGE
+            | OP_CODE_OP_TYPE_SAMPLER
+            | OP_CODE_OP_TYPE_SAMPLED_IMAGE
+            | OP_CODE_OP_TYPE_ARRAY
+            | OP_CODE_OP_TYPE_RUNTIME_ARRAY
+            | OP_CODE_OP_TYPE_STRUCT
+            | OP_CODE_OP_TYPE_POINTER
+            | OP_CODE_OP_TYPE_ACCELERATION_STRUCTURE => {
+                types.types.insert(operand_words[0], (instruction.opcode, operand_words.to_vec()));
+            },
+            _ => {}
+        }
+        true
+    });
+
+    let mut reflection = ShaderReflection::default();
+    for var in &vars {
+        let pointee_type_id = match types.types.get(&var.result_type_id) {
+            Some((OP_CODE_OP_TYPE_POINTER, operands)) => operands[2],
+            _ => continue
+        };
+
+        if var.storage_class == STORAGE_CLASS_PUSH_CONSTANT {
+            reflection.push_constant_size = reflection.push_constant_size.max(types.size(pointee_type_id));
+            continue;
+        }
+        if var.storage_class != STORAGE_CLASS_UNIFORM_CONSTANT
+            && var.storage_class != STORAGE_CLASS_UNIFORM
+            && var.storage_class != STORAGE_CLASS_STORAGE_BUFFER {
+            continue;
+        }
+
+        let descriptor_set = types.decorations.get(&(var.result_id, DECORATION_DESCRIPTOR_SET));
+        let binding = types.decorations.get(&(var.result_id, DECORATION_BINDING));
+        if descriptor_set.is_none() || binding.is_none() {
+            log::warn!("Resource %{} is missing a descriptor set or binding decoration.", var.result_id);
+            continue;
+        }
+
+        let (array_size, type_id) = match types.types.get(&pointee_type_id) {
+            Some((OP_CODE_OP_TYPE_ARRAY, operands)) => (types.constant(operands[2]).unwrap_or(1), operands[1]),
+            Some((OP_CODE_OP_TYPE_RUNTIME_ARRAY, operands)) => (0, operands[1]),
+            _ => (1, pointee_type_id)
+        };
+
+        let (resource_type, block_size) = match types.types.get(&type_id) {
+            Some((OP_CODE_OP_TYPE_IMAGE, operands)) => {
+                let image = parse_op_type_image(operands);
+                if image.dim == DIM_SUBPASS_DATA {
+                    (sourcerenderer_core::gpu::ResourceType::SubpassInput, 0)
+                } else if image.sampled == 2 {
+                    (sourcerenderer_core::gpu::ResourceType::StorageTexture, 0)
+                } else {
+                    (sourcerenderer_core::gpu::ResourceType::SampledTexture, 0)
+                }
+            },
+            Some((OP_CODE_OP_TYPE_SAMPLER, _)) => (sourcerenderer_core::gpu::ResourceType::Sampler, 0),
+            Some((OP_CODE_OP_TYPE_SAMPLED_IMAGE, _)) => (sourcerenderer_core::gpu::ResourceType::CombinedTextureSampler, 0),
+            Some((OP_CODE_OP_TYPE_ACCELERATION_STRUCTURE, _)) => (sourcerenderer_core::gpu::ResourceType::AccelerationStructure, 0),
+            Some((OP_CODE_OP_TYPE_STRUCT, _)) => {
+                // Storage buffers used to be uniform blocks decorated with BufferBlock before SPIR-V 1.3
+                let is_storage_buffer = var.storage_class == STORAGE_CLASS_STORAGE_BUFFER
+                    || types.decorations.contains_key(&(type_id, DECORATION_BUFFER_BLOCK));
+                if is_storage_buffer {
+                    (sourcerenderer_core::gpu::ResourceType::StorageBuffer, types.size(type_id))
+                } else {
+                    (sourcerenderer_core::gpu::ResourceType::UniformBuffer, types.size(type_id))
+                }
+            },
+            _ => {
+                log::warn!("Resource %{} has an unsupported type.", var.result_id);
+                continue;
+            }
+        };
+
+        reflection.bindings.push(ReflectedBinding {
+            binding: Binding {
+                descriptor_set: *descriptor_set.unwrap(),
+                binding: *binding.unwrap()
+            },
+            resource_type,
+            array_size,
+            block_size
+        });
+    }
+    reflection.bindings.sort_by_key(|binding| (binding.binding.descriptor_set, binding.binding.binding));
+    reflection
+}
+
+/// Finds the image and sampler variables that get combined into a depth sampled image,
+/// that's what GLSL turns sampler2DShadow(texture, samplerShadow) and friends into.
+/// APIs like WebGPU need to know about those when creating the bind group layout.
+/// Only catches variables that get loaded directly, not ones passed through function parameters.
+pub fn spirv_find_depth_compare_variables(spirv: &[u8]) -> HashSet<u32> {
+    assert_eq!(spirv.len() % std::mem::size_of::<u32>(), 0);
+    let mut words: Vec<u32> = spirv
+        .chunks_exact(std::mem::size_of::<u32>())
+        .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+        .collect();
+
+    let mut depth_image_types = HashSet::<u32>::new();
+    let mut depth_sampled_image_types = HashSet::<u32>::new();
+    let mut loaded_variables = HashMap::<u32, u32>::new();
+    let mut compared_loads = Vec::<u32>::new();
+    spirv_words_pass(&mut words, |_word_index, instruction, operand_words| {
+        match instruction.opcode {
+            OP_CODE_OP_TYPE_IMAGE => {
+                let image = parse_op_type_image(operand_words);
+                if image.depth == 1 {
+                    depth_image_types.insert(image.result_id);
+                }
+            },
+            OP_CODE_OP_TYPE_SAMPLED_IMAGE => {
+                let sampled_image = parse_op_type_sampled_image(operand_words);
+                if depth_image_types.contains(&sampled_image.image_type_id) {
+                    depth_sampled_image_types.insert(sampled_image.result_id);
+                }
+            },
+            OP_CODE_OP_LOAD => {
+                let load = parse_op_load(operand_words);
+                loaded_variables.insert(load.result_id, load.pointer_id);
+            },
+            OP_CODE_OP_SAMPLED_IMAGE => {
+                // Result type, result, image, sampler
+                if depth_sampled_image_types.contains(&operand_words[0]) {
+                    compared_loads.push(operand_words[2]);
+                    compared_loads.push(operand_words[3]);
+                }
+            },
+            _ => {}
+        }
+        true
+    });
+
+    compared_loads
+        .iter()
+        .filter_map(|load| loaded_variables.get(load).copied())
+        .collect()
+}
+
 pub fn spirv_validate(spirv: &[u8]) -> Result<(), String> {
     {
         let mut file = File::create("tmp.spv").unwrap();
@@ -786,3 +1106,190 @@ pub fn spirv_validate(spirv: &[u8]) -> Result<(), String> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use sourcerenderer_core::gpu::ResourceType;
+
+    fn instruction(word_count: u32, opcode: u16) -> u32 {
+        (word_count << 16) | opcode as u32
+    }
+
+    fn to_bytes(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    /// Opcode and operands of every instruction after the header.
+    fn instructions(spirv: &[u8]) -> Vec<(u16, Vec<u32>)> {
+        let words: Vec<u32> = spirv.chunks(4).map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])).collect();
+        let mut instructions = Vec::new();
+        let mut index = 5;
+        while index < words.len() {
+            let description = parse_instruction_description(words[index]);
+            instructions.push((description.opcode, words[index + 1 .. index + description.word_count as usize].to_vec()));
+            index += description.word_count as usize;
+        }
+        instructions
+    }
+
+    /// layout(set = 0, binding = 2) uniform sampler2D tex;
+    /// void main() { vec4 color = texture(tex, vec2(0.0)); }
+    fn fragment_shader() -> Vec<u32> {
+        vec![0x07230203, 0x00010000, 0, 16, 0,
+            instruction(2, 17), 1, // OpCapability Shader
+            instruction(3, 14), 0, 1, // OpMemoryModel Logical GLSL450
+            instruction(6, 15), 4, 12, u32::from_le_bytes(*b"main"), 0, 9, // OpEntryPoint Fragment %12 "main" %9
+            instruction(4, 71), 9, DECORATION_DESCRIPTOR_SET, 0,
+            instruction(4, 71), 9, DECORATION_BINDING, 2,
+            instruction(2, 19), 1, // %1 = OpTypeVoid
+            instruction(3, 33), 2, 1, // %2 = OpTypeFunction %1
+            instruction(3, 22), 3, 32, // %3 = OpTypeFloat 32
+            instruction(4, 23), 4, 3, 2, // %4 = OpTypeVector %3 2
+            instruction(4, 23), 5, 3, 4, // %5 = OpTypeVector %3 4
+            instruction(9, 25), 6, 3, 1, 0, 0, 0, 1, 0, // %6 = OpTypeImage %3 2D
+            instruction(3, 27), 7, 6, // %7 = OpTypeSampledImage %6
+            instruction(4, 32), 8, STORAGE_CLASS_UNIFORM_CONSTANT, 7, // %8 = OpTypePointer UniformConstant %7
+            instruction(4, 59), 8, 9, STORAGE_CLASS_UNIFORM_CONSTANT, // %9 = OpVariable %8 UniformConstant
+            instruction(4, 43), 3, 10, 0, // %10 = OpConstant %3 0.0
+            instruction(5, 44), 4, 11, 10, 10, // %11 = OpConstantComposite %4 %10 %10
+            instruction(5, 54), 1, 12, 0, 2, // %12 = OpFunction %1 None %2
+            instruction(2, 248), 13, // OpLabel
+            instruction(4, 61), 7, 14, 9, // %14 = OpLoad %7 %9
+            instruction(5, 87), 5, 15, 14, 11, // %15 = OpImageSampleImplicitLod %5 %14 %11
+            instruction(1, 253), // OpReturn
+            instruction(1, 56), // OpFunctionEnd
+        ]
+    }
+
+    /// layout(local_size_x = 8) in;
+    /// layout(set = 0, binding = 1) uniform sampler2D tex;
+    /// void main() { vec4 color = textureLod(tex, vec2(0.0), 0.0); vec4 texel = texelFetch(tex, ivec2(0), 0); }
+    fn compute_shader() -> Vec<u32> {
+        vec![0x07230203, 0x00010400, 0, 22, 0,
+            instruction(2, 17), 1, // OpCapability Shader
+            instruction(3, 14), 0, 1, // OpMemoryModel Logical GLSL450
+            instruction(6, 15), 5, 12, u32::from_le_bytes(*b"main"), 0, 9, // OpEntryPoint GLCompute %12 "main" %9
+            instruction(6, 16), 12, 17, 8, 1, 1, // OpExecutionMode %12 LocalSize 8 1 1
+            instruction(4, 71), 9, DECORATION_DESCRIPTOR_SET, 0,
+            instruction(4, 71), 9, DECORATION_BINDING, 1,
+            instruction(2, 19), 1, // %1 = OpTypeVoid
+            instruction(3, 33), 2, 1, // %2 = OpTypeFunction %1
+            instruction(3, 22), 3, 32, // %3 = OpTypeFloat 32
+            instruction(4, 23), 4, 3, 2, // %4 = OpTypeVector %3 2
+            instruction(4, 23), 5, 3, 4, // %5 = OpTypeVector %3 4
+            instruction(9, 25), 6, 3, 1, 0, 0, 0, 1, 0, // %6 = OpTypeImage %3 2D
+            instruction(3, 27), 7, 6, // %7 = OpTypeSampledImage %6
+            instruction(4, 32), 8, STORAGE_CLASS_UNIFORM_CONSTANT, 7, // %8 = OpTypePointer UniformConstant %7
+            instruction(4, 59), 8, 9, STORAGE_CLASS_UNIFORM_CONSTANT, // %9 = OpVariable %8 UniformConstant
+            instruction(4, 43), 3, 10, 0, // %10 = OpConstant %3 0.0
+            instruction(5, 44), 4, 11, 10, 10, // %11 = OpConstantComposite %4 %10 %10
+            instruction(4, 21), 16, 32, 1, // %16 = OpTypeInt 32 1
+            instruction(4, 23), 17, 16, 2, // %17 = OpTypeVector %16 2
+            instruction(4, 43), 16, 18, 0, // %18 = OpConstant %16 0
+            instruction(5, 44), 17, 19, 18, 18, // %19 = OpConstantComposite %17 %18 %18
+            instruction(5, 54), 1, 12, 0, 2, // %12 = OpFunction %1 None %2
+            instruction(2, 248), 13, // OpLabel
+            instruction(4, 61), 7, 14, 9, // %14 = OpLoad %7 %9
+            instruction(7, 88), 5, 15, 14, 11, 2, 10, // %15 = OpImageSampleExplicitLod %5 %14 %11 Lod %10
+            instruction(4, 100), 6, 20, 14, // %20 = OpImage %6 %14
+            instruction(7, 95), 5, 21, 20, 19, 2, 18, // %21 = OpImageFetch %5 %20 %19 Lod %18
+            instruction(1, 253), // OpReturn
+            instruction(1, 56), // OpFunctionEnd
+        ]
+    }
+
+    #[test]
+    fn separate_one_combined_sampler() {
+        let mut spirv = to_bytes(&fragment_shader());
+        let pairs = spirv_separate_combined_image_samplers(&mut spirv, Option::<fn(&Binding) -> Binding>::None);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].image.descriptor_set, pairs[0].image.binding), (0, 2));
+        // The sampler gets the next free binding in the set of the image.
+        assert_eq!((pairs[0].sampler.descriptor_set, pairs[0].sampler.binding), (0, 3));
+
+        let reflection = spirv_reflect(&spirv);
+        let bindings: Vec<(u32, u32, ResourceType)> = reflection.bindings.iter()
+            .map(|binding| (binding.binding.descriptor_set, binding.binding.binding, binding.resource_type))
+            .collect();
+        assert_eq!(bindings, vec![(0, 2, ResourceType::SampledTexture), (0, 3, ResourceType::Sampler)]);
+
+        // The sampling op now uses a sampled image that gets built from the separate image and sampler.
+        let instructions = instructions(&spirv);
+        let sampled_image = instructions.iter().find(|(opcode, _)| *opcode == OP_CODE_OP_SAMPLED_IMAGE).unwrap();
+        let sample = instructions.iter().find(|(opcode, _)| *opcode == 87).unwrap();
+        assert_eq!(sample.1[2], sampled_image.1[1]);
+
+        let mut spirv = to_bytes(&fragment_shader());
+        let pairs = spirv_separate_combined_image_samplers(&mut spirv, Some(|_: &Binding| Binding {
+            descriptor_set: 1,
+            binding: 7
+        }));
+        assert_eq!((pairs[0].sampler.descriptor_set, pairs[0].sampler.binding), (1, 7));
+    }
+
+    #[test]
+    fn separate_combined_sampler_in_compute_shader() {
+        let mut spirv = to_bytes(&compute_shader());
+        let pairs = spirv_separate_combined_image_samplers(&mut spirv, Option::<fn(&Binding) -> Binding>::None);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].image.descriptor_set, pairs[0].image.binding), (0, 1));
+        assert_eq!((pairs[0].sampler.descriptor_set, pairs[0].sampler.binding), (0, 2));
+
+        let instructions = instructions(&spirv);
+        let sampled_images: Vec<u32> = instructions.iter()
+            .filter(|(opcode, _)| *opcode == OP_CODE_OP_SAMPLED_IMAGE)
+            .map(|(_, operands)| operands[1])
+            .collect();
+        // The sampled image gets created once after the load and is shared by the sampling op and OpImage.
+        assert_eq!(sampled_images.len(), 1);
+        let sample = instructions.iter().find(|(opcode, _)| *opcode == 88).unwrap();
+        assert_eq!(sample.1[2], sampled_images[0]);
+        let image = instructions.iter().find(|(opcode, _)| *opcode == 100).unwrap();
+        assert_eq!(image.1[2], sampled_images[0]);
+        // The fetch keeps using the result of OpImage.
+        let fetch = instructions.iter().find(|(opcode, _)| *opcode == 95).unwrap();
+        assert_eq!(fetch.1[2], 20);
+
+        // SPIR-V 1.4 entry points list every global variable they use, that includes the new sampler.
+        let entry_point = instructions.iter().find(|(opcode, _)| *opcode == OP_CODE_OP_ENTRY_POINT).unwrap();
+        assert_eq!(entry_point.1.len(), 6);
+        let sampler_variable = entry_point.1[5];
+        let sampler_binding = instructions.iter()
+            .find(|(opcode, operands)| *opcode == OP_CODE_OP_DECORATE && operands[0] == sampler_variable && operands[1] == DECORATION_BINDING)
+            .unwrap();
+        assert_eq!(sampler_binding.1[2], 2);
+    }
+
+    /// The compute shader with the debug info glslang emits for it.
+    fn compute_shader_with_debug_info() -> Vec<u32> {
+        let mut spirv = compute_shader();
+        let execution_mode_index = spirv.iter().position(|word| *word == instruction(6, 16)).unwrap();
+        let debug_info = [
+            instruction(3, 3), 2, 450, // OpSource GLSL 450
+            instruction(4, 5), 12, u32::from_le_bytes(*b"main"), 0, // OpName %12 "main"
+            instruction(3, 5), 9, u32::from_le_bytes(*b"tex\0"), // OpName %9 "tex"
+        ];
+        spirv.splice(execution_mode_index + 6 .. execution_mode_index + 6, debug_info);
+        spirv
+    }
+
+    #[test]
+    fn remove_debug_info_keeps_entry_names() {
+        let mut spirv = to_bytes(&compute_shader_with_debug_info());
+        spirv_remove_debug_info_keep_entry_names(&mut spirv);
+        let instructions = instructions(&spirv);
+        let names: Vec<&Vec<u32>> = instructions.iter()
+            .filter(|(opcode, _)| *opcode == OP_CODE_OP_NAME)
+            .map(|(_, operands)| operands)
+            .collect();
+        assert_eq!(names, vec![&vec![12, u32::from_le_bytes(*b"main"), 0]]);
+        assert!(!instructions.iter().any(|(opcode, _)| *opcode == OP_CODE_OP_SOURCE));
+        assert_eq!(spirv.len(), to_bytes(&compute_shader()).len() + 4 * 4);
+
+        let mut spirv = to_bytes(&compute_shader_with_debug_info());
+        spirv_remove_debug_info(&mut spirv);
+        assert_eq!(spirv, to_bytes(&compute_shader()));
+    }
+}
@@ -1,4 +1,4 @@
-use std::{collections::{HashMap, VecDeque}, sync::{Mutex, MutexGuard}};
+use std::{any::Any, collections::{HashMap, VecDeque}, fmt::Display, str::FromStr, sync::{Arc, Mutex, MutexGuard}};
 
 use smallvec::SmallVec;
 use smartstring::alias::String;
@@ -8,15 +8,148 @@ pub struct Command {
   args: SmallVec::<[String; 4]>
 }
 
+impl Command {
+  pub fn name(&self) -> &str {
+    &self.cmd
+  }
+
+  pub fn args(&self) -> &[String] {
+    &self.args
+  }
+}
+
+pub type CommandHandler = dyn Fn(&[&str]) + Send + Sync;
+
+/// A value that can be stored in a console variable.
+pub trait CVarValue: FromStr + Display + Clone + Send + Sync + 'static {}
+impl<T: FromStr + Display + Clone + Send + Sync + 'static> CVarValue for T {}
+
+trait CVar: Send + Sync {
+  fn set_from_str(&mut self, value: &str) -> Result<(), std::string::String>;
+  fn value_string(&self) -> std::string::String;
+  fn as_any(&self) -> &dyn Any;
+  fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+struct TypedCVar<T: CVarValue> {
+  value: T
+}
+
+impl<T: CVarValue> CVar for TypedCVar<T> {
+  fn set_from_str(&mut self, value: &str) -> Result<(), std::string::String> {
+    self.value = value.parse::<T>().map_err(|_| format!("Invalid value: {}", value))?;
+    Ok(())
+  }
+
+  fn value_string(&self) -> std::string::String {
+    self.value.to_string()
+  }
+
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    self
+  }
+}
+
 pub struct Console {
   cmds: Mutex<HashMap<String, VecDeque<Command>>>,
+  handlers: Mutex<HashMap<String, Arc<CommandHandler>>>,
+  cvars: Mutex<HashMap<String, Box<dyn CVar>>>,
 }
 
 impl Console {
   pub fn new() -> Self {
     Self {
       cmds: Mutex::new(HashMap::new()),
+      handlers: Mutex::new(HashMap::new()),
+      cvars: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Registers a command that gets called with its arguments by `execute`.
+  pub fn register_command(&self, name: &str, handler: impl Fn(&[&str]) + Send + Sync + 'static) {
+    let mut handlers = self.handlers.lock().unwrap();
+    handlers.insert(name.into(), Arc::new(handler));
+  }
+
+  /// Registers a variable, keeps the current value if it already exists with the same type.
+  pub fn register_cvar<T: CVarValue>(&self, name: &str, default: T) {
+    let mut cvars = self.cvars.lock().unwrap();
+    if cvars.get(name).map(|cvar| cvar.as_any().is::<TypedCVar<T>>()).unwrap_or(false) {
+      return;
     }
+    cvars.insert(name.into(), Box::new(TypedCVar { value: default }));
+  }
+
+  /// Returns None if the variable doesn't exist or has a different type.
+  pub fn cvar<T: CVarValue>(&self, name: &str) -> Option<T> {
+    let cvars = self.cvars.lock().unwrap();
+    cvars.get(name)
+      .and_then(|cvar| cvar.as_any().downcast_ref::<TypedCVar<T>>())
+      .map(|cvar| cvar.value.clone())
+  }
+
+  pub fn set_cvar<T: CVarValue>(&self, name: &str, value: T) -> Result<(), std::string::String> {
+    let mut cvars = self.cvars.lock().unwrap();
+    let cvar = cvars.get_mut(name)
+      .ok_or_else(|| format!("Unknown variable: {}", name))?
+      .as_any_mut()
+      .downcast_mut::<TypedCVar<T>>()
+      .ok_or_else(|| format!("Variable {} has a different type", name))?;
+    cvar.value = value;
+    Ok(())
+  }
+
+  /// Runs a line typed into the console.
+  /// Registered commands get called, variables get printed or set and prefixed commands ("r.tonemap aces") get queued for `get_cmds`.
+  /// Returns the text to print, if there is any.
+  pub fn execute(&self, line: &str) -> Result<Option<std::string::String>, std::string::String> {
+    let tokens = tokenize(line);
+    let (name, args) = match tokens.split_first() {
+      Some((name, args)) => (*name, args),
+      None => return Ok(None),
+    };
+
+    // Clone the handler so it can use the console itself.
+    let handler = self.handlers.lock().unwrap().get(name).cloned();
+    if let Some(handler) = handler {
+      handler(args);
+      return Ok(None);
+    }
+
+    {
+      let mut cvars = self.cvars.lock().unwrap();
+      if let Some(cvar) = cvars.get_mut(name) {
+        return match args.first() {
+          Some(value) => {
+            cvar.set_from_str(value)?;
+            Ok(None)
+          }
+          None => Ok(Some(format!("{} = {}", name, cvar.value_string())))
+        };
+      }
+    }
+
+    if name.contains('.') {
+      self.write_cmd(line.trim());
+      return Ok(None);
+    }
+    Err(format!("Unknown command: {}", name))
+  }
+
+  /// Returns the sorted names of all commands and variables that start with the prefix.
+  pub fn complete(&self, prefix: &str) -> Vec<std::string::String> {
+    let mut names: Vec<std::string::String> = self.handlers.lock().unwrap().keys()
+      .chain(self.cvars.lock().unwrap().keys())
+      .filter(|name| name.starts_with(prefix))
+      .map(|name| name.to_string())
+      .collect();
+    names.sort();
+    names.dedup();
+    names
   }
 
   pub fn write_cmd(&self, cmd: &str) {
@@ -31,7 +164,7 @@ impl Console {
       return;
     }
     let dot_index = dot_index.unwrap();
-    let mut prefix = String::from(cmd);
+    let mut prefix = String::from(&base_cmd[..dot_index]);
     prefix.make_ascii_lowercase();
     let mut args = SmallVec::<[String; 4]>::new();
     for arg in words {
@@ -74,3 +207,21 @@ impl<'a, 'b, 'c> Iterator for ConsoleIter<'a, 'b> {
     }
   }
 }
+
+/// Splits the line at whitespace, text in double quotes stays together.
+fn tokenize(line: &str) -> Vec<&str> {
+  let mut tokens = Vec::new();
+  let mut rest = line.trim_start();
+  while !rest.is_empty() {
+    let (token, remainder) = if let Some(quoted) = rest.strip_prefix('"') {
+      let end = quoted.find('"').unwrap_or(quoted.len());
+      (&quoted[..end], quoted.get(end + 1..).unwrap_or(""))
+    } else {
+      let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+      (&rest[..end], &rest[end..])
+    };
+    tokens.push(token);
+    rest = remainder.trim_start();
+  }
+  tokens
+}
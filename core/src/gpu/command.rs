@@ -99,11 +99,39 @@ pub struct BufferCopyRegion {
   pub size: u64
 }
 
+/// The layout of a single draw in the buffer of the indirect draw functions.
+/// Vulkan, WebGPU and Metal all use the same layout.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DrawIndirectCommand {
+  pub vertex_count: u32,
+  pub instance_count: u32,
+  pub first_vertex: u32,
+  pub first_instance: u32
+}
+
+/// The layout of a single draw in the buffer of the indexed indirect draw functions.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DrawIndexedIndirectCommand {
+  pub index_count: u32,
+  pub instance_count: u32,
+  pub first_index: u32,
+  pub vertex_offset: i32,
+  pub first_instance: u32
+}
+
 pub trait CommandPool<B: GPUBackend> : Send {
   unsafe fn create_command_buffer(&mut self) -> B::CommandBuffer;
   unsafe fn reset(&mut self);
 }
 
+#[derive(Debug)]
+pub struct PushConstantsTooLargeError {
+  pub size: u32,
+  pub max_size: u32
+}
+
 pub trait CommandBuffer<B: GPUBackend> : Send {
   unsafe fn set_pipeline(&mut self, pipeline: PipelineBinding<B>);
   unsafe fn set_vertex_buffer(&mut self, index: u32, vertex_buffer: &B::Buffer, offset: u64);
@@ -114,8 +142,12 @@ pub trait CommandBuffer<B: GPUBackend> : Send {
     where T: 'static + Send + Sync + Sized + Clone;
   unsafe fn draw(&mut self, vertices: u32, offset: u32);
   unsafe fn draw_indexed(&mut self, instances: u32, first_instance: u32, indices: u32, first_index: u32, vertex_offset: i32);
-  unsafe fn draw_indexed_indirect(&mut self, draw_buffer: &B::Buffer, draw_buffer_offset: u32, count_buffer: &B::Buffer, count_buffer_offset: u32, max_draw_count: u32, stride: u32);
-  unsafe fn draw_indirect(&mut self, draw_buffer: &B::Buffer, draw_buffer_offset: u32, count_buffer: &B::Buffer, count_buffer_offset: u32, max_draw_count: u32, stride: u32);
+  unsafe fn draw_indexed_indirect(&mut self, draw_buffer: &B::Buffer, draw_buffer_offset: u32, draw_count: u32, stride: u32);
+  unsafe fn draw_indirect(&mut self, draw_buffer: &B::Buffer, draw_buffer_offset: u32, draw_count: u32, stride: u32);
+  /// Reads the draw count from count_buffer on the GPU.
+  /// Backends that can't do that issue max_draw_count draws, unused draws need to have an index or vertex count of 0 then.
+  unsafe fn draw_indexed_indirect_count(&mut self, draw_buffer: &B::Buffer, draw_buffer_offset: u32, count_buffer: &B::Buffer, count_buffer_offset: u32, max_draw_count: u32, stride: u32);
+  unsafe fn draw_indirect_count(&mut self, draw_buffer: &B::Buffer, draw_buffer_offset: u32, count_buffer: &B::Buffer, count_buffer_offset: u32, max_draw_count: u32, stride: u32);
   unsafe fn bind_sampling_view(&mut self, frequency: BindingFrequency, binding: u32, texture: &B::TextureView);
   unsafe fn bind_sampling_view_and_sampler(&mut self, frequency: BindingFrequency, binding: u32, texture: &B::TextureView, sampler: &B::Sampler);
   unsafe fn bind_sampling_view_and_sampler_array(&mut self, frequency: BindingFrequency, binding: u32, textures_and_samplers: &[(&B::TextureView, &B::Sampler)]);
@@ -128,6 +160,9 @@ pub trait CommandBuffer<B: GPUBackend> : Send {
   unsafe fn finish_binding(&mut self);
   unsafe fn begin_label(&mut self, label: &str);
   unsafe fn end_label(&mut self);
+  /// Has to be recorded outside of a render pass.
+  unsafe fn reset_queries(&mut self, query_pool: &B::QueryPool, first_query: u32, query_count: u32);
+  unsafe fn write_timestamp(&mut self, query_pool: &B::QueryPool, query_index: u32);
   unsafe fn dispatch(&mut self, group_count_x: u32, group_count_y: u32, group_count_z: u32);
   unsafe fn blit(&mut self, src_texture: &B::Texture, src_array_layer: u32, src_mip_level: u32, dst_texture: &B::Texture, dst_array_layer: u32, dst_mip_level: u32);
 
@@ -135,6 +170,8 @@ pub trait CommandBuffer<B: GPUBackend> : Send {
   unsafe fn finish(&mut self);
 
   unsafe fn copy_buffer_to_texture(&mut self, src: &B::Buffer, dst: &B::Texture, region: &BufferTextureCopyRegion);
+  /// The texture has to be in the CopySrc layout.
+  unsafe fn copy_texture_to_buffer(&mut self, src: &B::Texture, dst: &B::Buffer, region: &BufferTextureCopyRegion);
   unsafe fn copy_buffer(&mut self, src: &B::Buffer, dst: &B::Buffer, region: &BufferCopyRegion);
 
   unsafe fn clear_storage_texture(&mut self, view: &B::Texture, array_layer: u32, mip_level: u32, values: [u32; 4]);
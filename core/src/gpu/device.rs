@@ -14,6 +14,7 @@ pub enum AdapterType {
 }
 
 pub trait Adapter<B: GPUBackend> {
+  fn name(&self) -> &str;
   fn adapter_type(&self) -> AdapterType;
   fn create_device(&self, surface: &B::Surface) -> B::Device;
 }
@@ -52,6 +53,13 @@ pub trait Device<B: GPUBackend> {
   unsafe fn create_graphics_pipeline(&self, info: &GraphicsPipelineInfo<B>, name: Option<&str>) -> B::GraphicsPipeline;
   unsafe fn wait_for_idle(&self);
   unsafe fn create_fence(&self, is_cpu_accessible: bool) -> B::Fence;
+  unsafe fn create_timestamp_query_pool(&self, query_count: u32, name: Option<&str>) -> B::QueryPool;
+  /// Merges a blob previously returned by `pipeline_cache_data` into the pipeline cache.
+  /// Has to be called before any pipelines are created.
+  /// Returns false if the blob is invalid or was created by a different device or driver.
+  unsafe fn load_pipeline_cache(&self, data: &[u8]) -> bool;
+  /// None if the backend doesn't have a pipeline cache that can be persisted.
+  fn pipeline_cache_data(&self) -> Option<Vec<u8>>;
   unsafe fn memory_infos(&self) -> Vec<MemoryInfo>;
   unsafe fn memory_type_infos(&self) -> &[MemoryTypeInfo];
   unsafe fn create_heap(&self, memory_type_index: u32, size: u64) -> Result<B::Heap, OutOfMemoryError>;
@@ -68,6 +76,9 @@ pub trait Device<B: GPUBackend> {
   fn supports_indirect(&self) -> bool;
   fn supports_min_max_filter(&self) -> bool;
   fn supports_barycentrics(&self) -> bool; // TODO turn into flags
+  fn supports_timestamp_queries(&self) -> bool;
+  /// The maximum amount of push constant data in bytes, backends without push constants emulate them.
+  fn max_push_constant_size(&self) -> u32;
   unsafe fn get_bottom_level_acceleration_structure_size(&self, info: &BottomLevelAccelerationStructureInfo<B>) -> AccelerationStructureSizes;
   unsafe fn get_top_level_acceleration_structure_size(&self, info: &TopLevelAccelerationStructureInfo<B>) -> AccelerationStructureSizes;
   fn get_top_level_instances_buffer_size(&self, instances: &[AccelerationStructureInstance<B>]) -> u64;
@@ -33,6 +33,7 @@ pub enum Format {
   R16UInt,
   R16SNorm,
   R16SInt,
+  RGB10A2UNorm,
 
   D16,
   D16S8,
@@ -13,6 +13,7 @@ pub use self::queue::*;
 pub use self::backend::*;
 pub use self::heap::*;
 pub use self::shader_metadata::*;
+pub use self::query::*;
 
 mod device;
 mod instance;
@@ -29,6 +30,7 @@ mod rt;
 mod descriptor_heap;
 mod queue;
 mod shader_metadata;
+mod query;
 
 // TODO: find a better place for this
 pub trait Resettable {
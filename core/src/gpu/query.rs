@@ -0,0 +1,6 @@
+pub trait QueryPool {
+  fn query_count(&self) -> u32;
+  /// Reads the results of timestamp queries and converts them to nanoseconds.
+  /// Returns false if any of the results isn't available yet.
+  unsafe fn read_timestamps(&self, first_query: u32, results: &mut [u64]) -> bool;
+}
@@ -32,6 +32,7 @@ pub struct Resource {
     pub writable: bool,
     pub texture_dimension: TextureDimension,
     pub is_multisampled: bool,
+    /// Depth on a sampler means that it's a comparison sampler.
     pub sampling_type: SamplingType,
     pub storage_format: Format
 }
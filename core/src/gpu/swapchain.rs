@@ -10,6 +10,33 @@ pub enum SwapchainError {
   NeedsRecreation
 }
 
+/// How presented images are queued up for the display.
+/// Backends pick the closest supported mode if the requested one isn't available.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum PresentMode {
+  /// Vsync, always supported.
+  #[default]
+  Fifo,
+  /// Vsync, but late images are presented immediately and can tear.
+  FifoRelaxed,
+  /// No tearing, the newest image replaces the queued one without waiting for vsync.
+  Mailbox,
+  /// No vsync, images are presented immediately and can tear.
+  Immediate
+}
+
+/// How the values written to the backbuffer are interpreted by the display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum ColorSpace {
+  /// SDR, 8 bit per channel with the sRGB transfer function.
+  #[default]
+  Srgb,
+  /// HDR10, Rec.2020 primaries with the ST2084 (PQ) transfer function in a 10 bit format.
+  Hdr10,
+  /// Linear extended sRGB in a 16 bit float format, values above 1 are brighter than SDR white.
+  ScRgbLinear
+}
+
 pub trait Backbuffer {
   fn key(&self) -> u64;
 }
@@ -19,6 +46,12 @@ pub trait Swapchain<B: GPUBackend> : Sized {
 
   unsafe fn next_backbuffer(&mut self) -> Result<Self::Backbuffer, SwapchainError>;
   unsafe fn recreate(&mut self);
+  /// Recreates the swapchain with the closest supported present mode.
+  unsafe fn set_present_mode(&mut self, present_mode: PresentMode);
+  /// The present mode that is actually used, can differ from the requested one.
+  fn present_mode(&self) -> PresentMode;
+  /// The color space that is actually used, falls back to sRGB if the requested HDR color space isn't supported.
+  fn color_space(&self) -> ColorSpace;
   unsafe fn texture_for_backbuffer<'a>(&'a self, backbuffer: &'a Self::Backbuffer) -> &'a B::Texture;
   fn format(&self) -> Format;
   fn surface(&self) -> &B::Surface;
@@ -143,10 +143,14 @@ pub struct SamplerInfo {
   pub address_mode_u: AddressMode,
   pub address_mode_v: AddressMode,
   pub address_mode_w: AddressMode,
+  /// Gets added to the computed LOD. Unsupported by Metal and WebGPU samplers, shaders have to apply it there.
   pub mip_bias: f32,
   pub max_anisotropy: f32,
+  /// Makes it a comparison sampler, shaders have to use it as a samplerShadow or through a sampler*Shadow.
   pub compare_op: Option<CompareFunc>,
+  /// The LOD gets clamped to min_lod..=max_lod. Raising min_lod keeps sampling away from mips that aren't loaded yet.
   pub min_lod: f32,
+  /// None means no upper limit.
   pub max_lod: Option<f32>,
 }
 
@@ -1,3 +1,7 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::platform::{Event, Platform};
+
 #[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, PartialOrd, Ord)]
 #[repr(u32)]
 pub enum Key {
@@ -11,3 +15,220 @@ pub enum Key {
   LCtrl,
   LShift,
 }
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GamepadButton {
+  /// The bottom face button (A on Xbox controllers)
+  South,
+  /// The right face button (B on Xbox controllers)
+  East,
+  /// The left face button (X on Xbox controllers)
+  West,
+  /// The top face button (Y on Xbox controllers)
+  North,
+  Select,
+  Mode,
+  Start,
+  LeftStick,
+  RightStick,
+  LeftShoulder,
+  RightShoulder,
+  DPadUp,
+  DPadDown,
+  DPadLeft,
+  DPadRight,
+}
+
+/// Sticks range from -1 to 1 with the Y axes pointing up, triggers range from 0 to 1.
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GamepadAxis {
+  LeftStickX,
+  LeftStickY,
+  RightStickX,
+  RightStickY,
+  LeftTrigger,
+  RightTrigger,
+}
+
+/// The touch screen controls, the joystick ranges from -1 to 1 with the Y axis pointing forward,
+/// the look axes are the distance in pixels the look finger moved during the frame.
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TouchAxis {
+  JoystickX,
+  JoystickY,
+  LookX,
+  LookY,
+}
+
+/// A physical input that can trigger an action.
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq)]
+pub enum InputBinding {
+  Key(Key),
+  MouseButton(u32),
+  GamepadButton(GamepadButton),
+  GamepadAxis(GamepadAxis),
+  Touch(TouchAxis),
+}
+
+/// Axes count as pressed once they are pushed further than this.
+pub const AXIS_PRESS_THRESHOLD: f32 = 0.5f32;
+
+/// Maps named actions to physical inputs, so consumers don't have to hardcode keys and the bindings can be changed at runtime.
+/// The input of all gamepads is treated as coming from a single one.
+///
+/// Feed it every event with `process_event` and call `end_frame` once the frame is done.
+pub struct InputMap {
+  actions: HashMap<String, Vec<InputBinding>>,
+  pressed: HashSet<InputBinding>,
+  just_pressed: HashSet<InputBinding>,
+  axes: HashMap<GamepadAxis, f32>,
+  touch_axes: HashMap<TouchAxis, f32>,
+}
+
+impl InputMap {
+  pub fn new() -> Self {
+    Self {
+      actions: HashMap::new(),
+      pressed: HashSet::new(),
+      just_pressed: HashSet::new(),
+      axes: HashMap::new(),
+      touch_axes: HashMap::new(),
+    }
+  }
+
+  pub fn bind(&mut self, action: &str, binding: InputBinding) {
+    let bindings = self.actions.entry(action.to_string()).or_default();
+    if !bindings.contains(&binding) {
+      bindings.push(binding);
+    }
+  }
+
+  pub fn unbind(&mut self, action: &str, binding: InputBinding) {
+    if let Some(bindings) = self.actions.get_mut(action) {
+      bindings.retain(|b| *b != binding);
+    }
+  }
+
+  /// Replaces all bindings of the action.
+  pub fn set_bindings(&mut self, action: &str, bindings: &[InputBinding]) {
+    self.actions.insert(action.to_string(), bindings.to_vec());
+  }
+
+  pub fn bindings(&self, action: &str) -> &[InputBinding] {
+    self.actions.get(action).map(|bindings| &bindings[..]).unwrap_or(&[])
+  }
+
+  pub fn actions(&self) -> impl Iterator<Item = &str> {
+    self.actions.keys().map(|action| action.as_str())
+  }
+
+  pub fn process_event<P: Platform>(&mut self, event: &Event<P>) {
+    match event {
+      Event::KeyDown(key) => self.set_pressed(InputBinding::Key(*key), true),
+      Event::KeyUp(key) => self.set_pressed(InputBinding::Key(*key), false),
+      Event::MouseButtonDown(button) => self.set_pressed(InputBinding::MouseButton(*button), true),
+      Event::MouseButtonUp(button) => self.set_pressed(InputBinding::MouseButton(*button), false),
+      Event::GamepadButtonDown { button, .. } => self.set_pressed(InputBinding::GamepadButton(*button), true),
+      Event::GamepadButtonUp { button, .. } => self.set_pressed(InputBinding::GamepadButton(*button), false),
+      Event::GamepadAxis { axis, value, .. } => {
+        self.axes.insert(*axis, *value);
+        self.set_pressed(InputBinding::GamepadAxis(*axis), value.abs() >= AXIS_PRESS_THRESHOLD);
+      }
+      Event::GamepadDisconnected(_) => {
+        // Don't leave inputs of the disconnected gamepad stuck
+        self.pressed.retain(|binding| !matches!(binding, InputBinding::GamepadButton(_) | InputBinding::GamepadAxis(_)));
+        self.axes.clear();
+      }
+      _ => {}
+    }
+  }
+
+  /// The platform only reports raw finger events, so the touch controls get interpreted by the engine
+  /// and updated every frame.
+  pub fn set_touch_axis(&mut self, axis: TouchAxis, value: f32) {
+    self.touch_axes.insert(axis, value);
+    self.set_pressed(InputBinding::Touch(axis), value.abs() >= AXIS_PRESS_THRESHOLD);
+  }
+
+  /// Resets the inputs that were only pressed during this frame.
+  pub fn end_frame(&mut self) {
+    self.just_pressed.clear();
+  }
+
+  pub fn is_pressed(&self, action: &str) -> bool {
+    self.bindings(action).iter().any(|binding| self.pressed.contains(binding))
+  }
+
+  /// Whether any binding of the action got pressed during the current frame.
+  pub fn just_pressed(&self, action: &str) -> bool {
+    self.bindings(action).iter().any(|binding| self.just_pressed.contains(binding))
+  }
+
+  /// The strongest value of all bindings of the action, buttons and keys are either 0 or 1.
+  pub fn axis_value(&self, action: &str) -> f32 {
+    let mut value = 0f32;
+    for binding in self.bindings(action) {
+      let binding_value = match binding {
+        InputBinding::GamepadAxis(axis) => self.axes.get(axis).copied().unwrap_or(0f32),
+        InputBinding::Touch(axis) => self.touch_axes.get(axis).copied().unwrap_or(0f32),
+        _ => if self.pressed.contains(binding) { 1f32 } else { 0f32 }
+      };
+      if binding_value.abs() > value.abs() {
+        value = binding_value;
+      }
+    }
+    value
+  }
+
+  fn set_pressed(&mut self, binding: InputBinding, is_pressed: bool) {
+    if is_pressed {
+      if self.pressed.insert(binding) {
+        self.just_pressed.insert(binding);
+      }
+    } else {
+      self.pressed.remove(&binding);
+    }
+  }
+}
+
+impl Default for InputMap {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn axis_press_threshold() {
+    let mut input = InputMap::new();
+    input.bind("forward", InputBinding::Key(Key::W));
+    input.bind("forward", InputBinding::GamepadAxis(GamepadAxis::LeftStickY));
+
+    input.axes.insert(GamepadAxis::LeftStickY, 0.25f32);
+    input.set_pressed(InputBinding::GamepadAxis(GamepadAxis::LeftStickY), false);
+    assert_eq!(input.axis_value("forward"), 0.25f32);
+    assert!(!input.is_pressed("forward"));
+
+    input.set_pressed(InputBinding::Key(Key::W), true);
+    assert_eq!(input.axis_value("forward"), 1f32);
+  }
+
+  #[test]
+  fn touch_axes() {
+    let mut input = InputMap::new();
+    input.bind("forward", InputBinding::Key(Key::W));
+    input.bind("forward", InputBinding::Touch(TouchAxis::JoystickY));
+
+    input.set_touch_axis(TouchAxis::JoystickY, -0.75f32);
+    assert_eq!(input.axis_value("forward"), -0.75f32);
+    assert!(input.just_pressed("forward"));
+    input.end_frame();
+
+    input.set_touch_axis(TouchAxis::JoystickY, 0.25f32);
+    assert_eq!(input.axis_value("forward"), 0.25f32);
+    assert!(!input.is_pressed("forward"));
+  }
+}
@@ -15,7 +15,7 @@ pub mod pool;
 pub mod input;
 mod console;
 
-pub use console::{Console, Command};
+pub use console::{Console, Command, CommandHandler, CVarValue};
 
 pub mod atomic_refcell;
 
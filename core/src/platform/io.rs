@@ -1,6 +1,6 @@
 use std::future::Future;
 use std::io::Result as IOResult;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crossbeam_channel::Sender;
 use futures_io::{AsyncRead, AsyncSeek};
@@ -18,4 +18,7 @@ pub trait IO {
   fn open_external_asset<P: AsRef<Path> + Send>(path: P) -> impl Future<Output = IOResult<Self::File>> + Send;
   fn external_asset_exists<P: AsRef<Path> + Send>(path: P) -> impl Future<Output = bool> + Send;
   fn new_file_watcher(sender: Sender<String>) -> Self::FileWatcher;
+  /// A writable directory for files the engine can regenerate, like the pipeline cache.
+  /// None if the platform can't store files.
+  fn cache_dir() -> Option<PathBuf>;
 }
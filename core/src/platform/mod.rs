@@ -1,8 +1,8 @@
 use std::error::Error;
 use std::marker::PhantomData;
 
-use crate::{Vec2, Vec2I, Vec2UI, gpu::GPUBackend};
-use crate::input::Key;
+use crate::{Vec2, Vec2I, Vec2UI, gpu::{ColorSpace, GPUBackend, PresentMode}};
+use crate::input::{Key, GamepadAxis, GamepadButton};
 
 mod io;
 pub use io::IO;
@@ -55,11 +55,32 @@ pub enum Event<P: Platform> {
   WindowRestored(Vec2UI),
   WindowSizeChanged(Vec2UI),
   MouseMoved(Vec2I),
+  MouseButtonDown(u32),
+  MouseButtonUp(u32),
   FingerDown(u32),
   FingerUp(u32),
   FingerMoved {
     index: u32,
     position: Vec2
+  },
+  GamepadConnected {
+    gamepad: u32,
+    name: String
+  },
+  GamepadDisconnected(u32),
+  GamepadButtonDown {
+    gamepad: u32,
+    button: GamepadButton
+  },
+  GamepadButtonUp {
+    gamepad: u32,
+    button: GamepadButton
+  },
+  /// The platform applies the dead zone before dispatching it
+  GamepadAxis {
+    gamepad: u32,
+    axis: GamepadAxis,
+    value: f32
   }
 }
 
@@ -74,16 +95,27 @@ impl<P: Platform> Clone for Event<P> {
             Self::WindowRestored(size) => Self::WindowRestored(*size),
             Self::WindowSizeChanged(size) => Self::WindowSizeChanged(*size),
             Self::MouseMoved(mouse_pos) => Self::MouseMoved(*mouse_pos),
+            Self::MouseButtonDown(button) => Self::MouseButtonDown(*button),
+            Self::MouseButtonUp(button) => Self::MouseButtonUp(*button),
             Self::FingerDown(finger_index) => Self::FingerDown(*finger_index),
             Self::FingerUp(finger_index) => Self::FingerUp(*finger_index),
             Self::FingerMoved { index, position } => Self::FingerMoved { index: *index, position: *position },
+            Self::GamepadConnected { gamepad, name } => Self::GamepadConnected { gamepad: *gamepad, name: name.clone() },
+            Self::GamepadDisconnected(gamepad) => Self::GamepadDisconnected(*gamepad),
+            Self::GamepadButtonDown { gamepad, button } => Self::GamepadButtonDown { gamepad: *gamepad, button: *button },
+            Self::GamepadButtonUp { gamepad, button } => Self::GamepadButtonUp { gamepad: *gamepad, button: *button },
+            Self::GamepadAxis { gamepad, axis, value } => Self::GamepadAxis { gamepad: *gamepad, axis: *axis, value: *value },
         }
     }
 }
 
 pub trait Window<P: Platform> {
   fn create_surface(&self, graphics_instance: &<P::GPUBackend as GPUBackend>::Instance) -> <P::GPUBackend as GPUBackend>::Surface;
-  fn create_swapchain(&self, vsync: bool, device: &<P::GPUBackend as GPUBackend>::Device, surface: <P::GPUBackend as GPUBackend>::Surface) -> <P::GPUBackend as GPUBackend>::Swapchain;
+  fn create_swapchain(&self, present_mode: PresentMode, color_space: ColorSpace, device: &<P::GPUBackend as GPUBackend>::Device, surface: <P::GPUBackend as GPUBackend>::Surface) -> <P::GPUBackend as GPUBackend>::Swapchain;
+  /// The width of the drawable area in pixels
   fn width(&self) -> u32;
+  /// The height of the drawable area in pixels
   fn height(&self) -> u32;
+  /// The ratio between pixels and logical window units, 2 on a typical HiDPI display
+  fn scale_factor(&self) -> f32;
 }
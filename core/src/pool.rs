@@ -113,3 +113,195 @@ impl<T> Recyclable<T> {
     (unsafe { std::mem::replace(&mut r_mut.item, MaybeUninit::uninit()).assume_init() }, r_mut.sender.clone())
   }
 }
+
+/// Identifies a value in a `SlotMap`.
+/// The generation makes sure that a handle doesn't resolve to a different value after the slot got reused.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Handle {
+  index: u32,
+  generation: u32
+}
+
+impl Handle {
+  pub fn index(&self) -> u32 {
+    self.index
+  }
+
+  pub fn generation(&self) -> u32 {
+    self.generation
+  }
+}
+
+struct Slot<T> {
+  value: Option<T>,
+  generation: u32
+}
+
+/// Stores values in a Vec and hands out generational handles for them.
+/// Removed slots get reused, handles that point to the old value return None afterwards.
+pub struct SlotMap<T> {
+  slots: Vec<Slot<T>>,
+  free_slots: Vec<u32>,
+  len: usize
+}
+
+impl<T> Default for SlotMap<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T> SlotMap<T> {
+  pub fn new() -> Self {
+    Self {
+      slots: Vec::new(),
+      free_slots: Vec::new(),
+      len: 0
+    }
+  }
+
+  pub fn insert(&mut self, value: T) -> Handle {
+    self.len += 1;
+    if let Some(index) = self.free_slots.pop() {
+      let slot = &mut self.slots[index as usize];
+      debug_assert!(slot.value.is_none());
+      slot.value = Some(value);
+      return Handle {
+        index,
+        generation: slot.generation
+      };
+    }
+
+    let index = u32::try_from(self.slots.len()).expect("Too many slots");
+    self.slots.push(Slot {
+      value: Some(value),
+      generation: 0
+    });
+    Handle {
+      index,
+      generation: 0
+    }
+  }
+
+  pub fn get(&self, handle: Handle) -> Option<&T> {
+    self.slots.get(handle.index as usize)
+      .filter(|slot| slot.generation == handle.generation)
+      .and_then(|slot| slot.value.as_ref())
+  }
+
+  pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+    self.slots.get_mut(handle.index as usize)
+      .filter(|slot| slot.generation == handle.generation)
+      .and_then(|slot| slot.value.as_mut())
+  }
+
+  pub fn contains(&self, handle: Handle) -> bool {
+    self.get(handle).is_some()
+  }
+
+  /// Returns None if the handle is stale or got removed already.
+  pub fn remove(&mut self, handle: Handle) -> Option<T> {
+    let slot = self.slots.get_mut(handle.index as usize)?;
+    if slot.generation != handle.generation {
+      return None;
+    }
+    let value = slot.value.take()?;
+    // A slot whose generation would wrap around gets retired instead of risking a collision with old handles.
+    if let Some(generation) = slot.generation.checked_add(1) {
+      slot.generation = generation;
+      self.free_slots.push(handle.index);
+    }
+    self.len -= 1;
+    Some(value)
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  pub fn clear(&mut self) {
+    self.free_slots.clear();
+    for (index, slot) in self.slots.iter_mut().enumerate() {
+      if slot.value.take().is_none() && slot.generation != u32::MAX {
+        // Already free.
+        self.free_slots.push(index as u32);
+        continue;
+      }
+      if let Some(generation) = slot.generation.checked_add(1) {
+        slot.generation = generation;
+        self.free_slots.push(index as u32);
+      }
+    }
+    self.len = 0;
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (Handle, &T)> {
+    self.slots.iter().enumerate().filter_map(|(index, slot)| {
+      slot.value.as_ref().map(|value| (Handle {
+        index: index as u32,
+        generation: slot.generation
+      }, value))
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn insert_and_remove() {
+    let mut map = SlotMap::new();
+    let a = map.insert(1);
+    let b = map.insert(2);
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get(a), Some(&1));
+    assert_eq!(map.get(b), Some(&2));
+
+    assert_eq!(map.remove(a), Some(1));
+    assert_eq!(map.len(), 1);
+    assert_eq!(map.get(a), None);
+    assert_eq!(map.remove(a), None);
+    assert_eq!(map.get(b), Some(&2));
+  }
+
+  #[test]
+  fn reinsert_doesnt_resolve_old_handle() {
+    let mut map = SlotMap::new();
+    let old = map.insert(1);
+    map.remove(old);
+    let new = map.insert(2);
+    assert_eq!(new.index(), old.index());
+    assert_ne!(new.generation(), old.generation());
+    assert_eq!(map.get(old), None);
+    assert!(!map.contains(old));
+    assert_eq!(map.get(new), Some(&2));
+  }
+
+  #[test]
+  fn clear_bumps_generations() {
+    let mut map = SlotMap::new();
+    let a = map.insert(1);
+    let b = map.insert(2);
+    map.remove(b);
+    map.clear();
+    assert!(map.is_empty());
+    assert_eq!(map.get(a), None);
+    assert_eq!(map.iter().count(), 0);
+
+    let c = map.insert(3);
+    let d = map.insert(4);
+    assert!(c != a && c != b);
+    assert!(d != a && d != b);
+    assert_eq!(map.get(a), None);
+    assert_eq!(map.get(b), None);
+    assert_eq!(map.get(c), Some(&3));
+    assert_eq!(map.get(d), Some(&4));
+    assert_eq!(map.len(), 2);
+  }
+}
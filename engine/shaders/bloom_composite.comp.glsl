@@ -0,0 +1,27 @@
+#version 450
+#extension GL_GOOGLE_include_directive : enable
+
+layout(local_size_x = 8,
+       local_size_y = 8,
+       local_size_z = 1) in;
+
+#include "descriptor_sets.inc.glsl"
+
+layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 0, rgba16f) uniform image2D frame;
+layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 1) uniform sampler2D bloom;
+
+layout(push_constant) uniform BloomCompositeConstants {
+  float intensity;
+};
+
+void main() {
+  ivec2 texSize = imageSize(frame);
+  ivec2 storageTexCoord = ivec2(gl_GlobalInvocationID.xy);
+  if (storageTexCoord.x >= texSize.x || storageTexCoord.y >= texSize.y) {
+    return;
+  }
+  vec2 texCoord = (vec2(storageTexCoord) + 0.5) / vec2(texSize);
+  vec4 color = imageLoad(frame, storageTexCoord);
+  color.rgb += texture(bloom, texCoord).rgb * intensity;
+  imageStore(frame, storageTexCoord, color);
+}
@@ -0,0 +1,86 @@
+#version 450
+#extension GL_GOOGLE_include_directive : enable
+
+layout(local_size_x = 8,
+       local_size_y = 8,
+       local_size_z = 1) in;
+
+#include "descriptor_sets.inc.glsl"
+
+layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 0) uniform writeonly image2D outputTexture;
+layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 1) uniform sampler2D inputTexture;
+
+layout(push_constant) uniform BloomDownsampleConstants {
+  float threshold;
+  float exposure;
+  uint isFirstMip;
+};
+
+float luminance(vec3 color) {
+  return dot(color, vec3(0.2126, 0.7152, 0.0722));
+}
+
+// Karis average, weighs each block by its inverse luminance so single very bright pixels don't flicker.
+float karisWeight(vec3 color) {
+  return 1.0 / (1.0 + luminance(color * exposure));
+}
+
+vec3 applyThreshold(vec3 color) {
+  float brightness = max(color.r, max(color.g, color.b)) * exposure;
+  float contribution = max(brightness - threshold, 0.0) / max(brightness, 0.0001);
+  return color * contribution;
+}
+
+void main() {
+  ivec2 texSize = imageSize(outputTexture);
+  ivec2 storageTexCoord = ivec2(gl_GlobalInvocationID.xy);
+  if (storageTexCoord.x >= texSize.x || storageTexCoord.y >= texSize.y) {
+    return;
+  }
+  vec2 texCoord = (vec2(storageTexCoord) + 0.5) / vec2(texSize);
+  vec2 texelSize = 1.0 / vec2(textureSize(inputTexture, 0));
+
+  // 13 tap filter from "Next Generation Post Processing in Call of Duty: Advanced Warfare"
+  // a - b - c
+  // - j - k -
+  // d - e - f
+  // - l - m -
+  // g - h - i
+  vec3 a = texture(inputTexture, texCoord + texelSize * vec2(-2.0, -2.0)).rgb;
+  vec3 b = texture(inputTexture, texCoord + texelSize * vec2( 0.0, -2.0)).rgb;
+  vec3 c = texture(inputTexture, texCoord + texelSize * vec2( 2.0, -2.0)).rgb;
+  vec3 d = texture(inputTexture, texCoord + texelSize * vec2(-2.0,  0.0)).rgb;
+  vec3 e = texture(inputTexture, texCoord).rgb;
+  vec3 f = texture(inputTexture, texCoord + texelSize * vec2( 2.0,  0.0)).rgb;
+  vec3 g = texture(inputTexture, texCoord + texelSize * vec2(-2.0,  2.0)).rgb;
+  vec3 h = texture(inputTexture, texCoord + texelSize * vec2( 0.0,  2.0)).rgb;
+  vec3 i = texture(inputTexture, texCoord + texelSize * vec2( 2.0,  2.0)).rgb;
+  vec3 j = texture(inputTexture, texCoord + texelSize * vec2(-1.0, -1.0)).rgb;
+  vec3 k = texture(inputTexture, texCoord + texelSize * vec2( 1.0, -1.0)).rgb;
+  vec3 l = texture(inputTexture, texCoord + texelSize * vec2(-1.0,  1.0)).rgb;
+  vec3 m = texture(inputTexture, texCoord + texelSize * vec2( 1.0,  1.0)).rgb;
+
+  vec3 color;
+  if (isFirstMip != 0) {
+    vec3 block0 = (j + k + l + m) * 0.25;
+    vec3 block1 = (a + b + d + e) * 0.25;
+    vec3 block2 = (b + c + e + f) * 0.25;
+    vec3 block3 = (d + e + g + h) * 0.25;
+    vec3 block4 = (e + f + h + i) * 0.25;
+    float weight0 = 0.5 * karisWeight(block0);
+    float weight1 = 0.125 * karisWeight(block1);
+    float weight2 = 0.125 * karisWeight(block2);
+    float weight3 = 0.125 * karisWeight(block3);
+    float weight4 = 0.125 * karisWeight(block4);
+    color = (block0 * weight0 + block1 * weight1 + block2 * weight2 + block3 * weight3 + block4 * weight4)
+      / (weight0 + weight1 + weight2 + weight3 + weight4);
+    color = applyThreshold(color);
+  } else {
+    color = e * 0.125;
+    color += (a + c + g + i) * 0.03125;
+    color += (b + d + f + h) * 0.0625;
+    color += (j + k + l + m) * 0.125;
+  }
+
+  imageStore(outputTexture, storageTexCoord, vec4(color, 1.0));
+}
@@ -0,0 +1,40 @@
+#version 450
+#extension GL_GOOGLE_include_directive : enable
+
+layout(local_size_x = 8,
+       local_size_y = 8,
+       local_size_z = 1) in;
+
+#include "descriptor_sets.inc.glsl"
+
+layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 0) uniform writeonly image2D outputTexture;
+layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 1) uniform sampler2D lowerMip;
+layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 2) uniform sampler2D downsampledMip;
+
+void main() {
+  ivec2 texSize = imageSize(outputTexture);
+  ivec2 storageTexCoord = ivec2(gl_GlobalInvocationID.xy);
+  if (storageTexCoord.x >= texSize.x || storageTexCoord.y >= texSize.y) {
+    return;
+  }
+  vec2 texCoord = (vec2(storageTexCoord) + 0.5) / vec2(texSize);
+  vec2 texelSize = 1.0 / vec2(textureSize(lowerMip, 0));
+
+  // 3x3 tent filter
+  // 1 2 1
+  // 2 4 2
+  // 1 2 1
+  vec3 upsampled = texture(lowerMip, texCoord).rgb * 4.0;
+  upsampled += texture(lowerMip, texCoord + texelSize * vec2( 0.0, -1.0)).rgb * 2.0;
+  upsampled += texture(lowerMip, texCoord + texelSize * vec2(-1.0,  0.0)).rgb * 2.0;
+  upsampled += texture(lowerMip, texCoord + texelSize * vec2( 1.0,  0.0)).rgb * 2.0;
+  upsampled += texture(lowerMip, texCoord + texelSize * vec2( 0.0,  1.0)).rgb * 2.0;
+  upsampled += texture(lowerMip, texCoord + texelSize * vec2(-1.0, -1.0)).rgb;
+  upsampled += texture(lowerMip, texCoord + texelSize * vec2( 1.0, -1.0)).rgb;
+  upsampled += texture(lowerMip, texCoord + texelSize * vec2(-1.0,  1.0)).rgb;
+  upsampled += texture(lowerMip, texCoord + texelSize * vec2( 1.0,  1.0)).rgb;
+  upsampled *= 1.0 / 16.0;
+
+  vec3 color = texture(downsampledMip, texCoord).rgb + upsampled;
+  imageStore(outputTexture, storageTexCoord, vec4(color, 1.0));
+}
@@ -17,20 +17,6 @@ layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 2) uniform sampler2D ssr;
 
 // #include "frame_set.inc.glsl"
 
-layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 3, std140) uniform ConfigUBO {
-  float gamma;
-  float exposure;
-};
-
-vec3 aces(vec3 x) {
-    float a = 2.51;
-    float b = 0.03;
-    float c = 2.43;
-    float d = 0.59;
-    float e = 0.14;
-    return clamp((x*(a*x+b))/(x*(c*x+d)+e), 0.0, 1.0);
-}
-
 void main() {
   ivec2 texSize = imageSize(outputTexture);
   ivec2 storageTexCoord = ivec2(int(gl_GlobalInvocationID.x), int(gl_GlobalInvocationID.y));
@@ -42,9 +28,6 @@ void main() {
   vec4 reflection = texture(ssr, texCoord);
   color = mix(color, reflection.xyz, reflection.w);
 
-  color *= exposure;
-  vec3 toneMapped = aces(color);
-  vec3 gammaCorrected = pow(toneMapped, vec3(1.0 / gamma));
-
-  imageStore(outputTexture, storageTexCoord, vec4(gammaCorrected, 1.0));
+  // Tonemapping happens in a separate pass, the output is still HDR.
+  imageStore(outputTexture, storageTexCoord, vec4(color, 1.0));
 }
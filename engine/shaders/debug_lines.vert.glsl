@@ -0,0 +1,16 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+layout(location = 0) in vec3 in_pos;
+layout(location = 1) in vec4 in_color;
+
+layout(location = 0) out vec4 out_color;
+
+layout(push_constant) uniform VeryHighFrequencyUbo {
+    mat4 viewProj;
+};
+
+void main(void) {
+    out_color = in_color;
+    gl_Position = viewProj * vec4(in_pos, 1);
+}
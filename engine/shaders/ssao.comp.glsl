@@ -2,6 +2,9 @@
 #extension GL_GOOGLE_include_directive : enable
 // #extension GL_EXT_debug_printf : enable
 
+// Inputs: the depth buffer of the prepass and the camera.
+// Normals are reconstructed from the depth buffer, so the prepass doesn't need to write them.
+
 layout(local_size_x = 8,
        local_size_y = 8,
        local_size_z = 1) in;
@@ -9,8 +12,10 @@ layout(local_size_x = 8,
 #include "descriptor_sets.inc.glsl"
 #include "camera.inc.glsl"
 
+#define MAX_SAMPLES 64
+
 layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 0, std140) uniform SSAOKernel {
-  vec4 samples[16];
+  vec4 samples[MAX_SAMPLES];
 };
 layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 1) uniform sampler2D noise;
 layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 2) uniform sampler2D depthMap;
@@ -19,6 +24,12 @@ layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 3, std140) uniform CameraUB
 };
 layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 4, r16f) uniform writeonly image2D outputTexture;
 
+layout(push_constant) uniform SSAOConstants {
+  float radius;
+  float intensity;
+  uint sampleCount;
+};
+
 #define CS
 #include "util.inc.glsl"
 
@@ -49,11 +60,10 @@ void main() {
   float bias = 0.025;
   float occlusion = 0.0;
 
-  const uint kernelSize = 64;
-  const float radius = 0.5;
-
+  // The kernel gets longer towards the end, spread the used samples over the whole kernel.
+  uint kernelSize = clamp(sampleCount, 1, MAX_SAMPLES);
   for (uint i = 0; i < kernelSize; i++) {
-    vec3 samplePos = TBN * samples[i].xyz;
+    vec3 samplePos = TBN * samples[(i * MAX_SAMPLES) / kernelSize].xyz;
     samplePos = fragPos + samplePos * radius;
 
     vec4 offset = vec4(samplePos, 1.0);
@@ -68,7 +78,7 @@ void main() {
     float rangeCheck = smoothstep(0.0, 1.0, radius / abs(fragPos.z - sampleZ));
     occlusion += (sampleZ <= samplePos.z - bias ? 1.0 : 0.0) * rangeCheck;
   }
-  occlusion = 1.0 - (occlusion / kernelSize);
+  occlusion = clamp(1.0 - (occlusion / float(kernelSize)) * intensity, 0.0, 1.0);
   ivec2 storageTexCoord = ivec2(int(gl_GlobalInvocationID.x), int(gl_GlobalInvocationID.y));
   imageStore(outputTexture, storageTexCoord, vec4(occlusion, 0.0, 0.0, 0.0));
 }
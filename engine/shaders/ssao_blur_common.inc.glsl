@@ -2,6 +2,9 @@ layout(local_size_x = 8,
        local_size_y = 8,
        local_size_z = 1) in;
 
+// Vertical half of the separable SSAO blur, the horizontal half runs before it in ssao_blur_horizontal.comp.glsl.
+// Also blends the result with the reprojected result of the last frame.
+
 #include "descriptor_sets.inc.glsl"
 
 layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 0, r16f) uniform writeonly image2D outputTexture;
@@ -16,6 +19,10 @@ layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 4, rg16) readonly uniform i
 #include "vis_buf.inc.glsl"
 #endif
 
+layout(push_constant) uniform BlurConstants {
+  int blurRadius;
+};
+
 void main() {
   ivec2 inputTexSize = textureSize(inputTexture, 0);
   ivec2 outputTexSize = imageSize(outputTexture);
@@ -25,15 +32,11 @@ void main() {
   vec2 texCoord = vec2((float(gl_GlobalInvocationID.x) + 0.5) / float(outputTexSize.x), (float(gl_GlobalInvocationID.y) + 0.5) / float(outputTexSize.y));
   vec2 texel = vec2(1.0 / float(inputTexSize.x), 1.0 / float(inputTexSize.y));
   float sum = 0.0;
-  const int kernelSize = 4;
   // TODO: reduce samples using shared memory
-  for (int x = 0; x < kernelSize; x++) {
-    for (int y = 0; y < kernelSize; y++) {
-      vec2 offset = vec2(float(x - kernelSize / 2), float(y - kernelSize / 2));
-      sum += texture(inputTexture, texCoord + offset * texel).r;
-    }
+  for (int y = -blurRadius; y <= blurRadius; y++) {
+    sum += texture(inputTexture, texCoord + vec2(0.0, float(y)) * texel).r;
   }
-  sum /= kernelSize * kernelSize;
+  sum /= float(blurRadius * 2 + 1);
 
   sum *= 0.3;
 
@@ -0,0 +1,33 @@
+#version 450
+#extension GL_GOOGLE_include_directive : enable
+
+// Horizontal half of the separable SSAO blur.
+
+layout(local_size_x = 8,
+       local_size_y = 8,
+       local_size_z = 1) in;
+
+#include "descriptor_sets.inc.glsl"
+
+layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 0, r16f) uniform writeonly image2D outputTexture;
+layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 1) uniform sampler2D inputTexture;
+
+layout(push_constant) uniform BlurConstants {
+  int blurRadius;
+};
+
+void main() {
+  ivec2 texSize = imageSize(outputTexture);
+  ivec2 storageTexCoord = ivec2(gl_GlobalInvocationID.xy);
+  if (storageTexCoord.x >= texSize.x || storageTexCoord.y >= texSize.y) {
+    return;
+  }
+  vec2 texCoord = (vec2(storageTexCoord) + 0.5) / vec2(texSize);
+  vec2 texel = 1.0 / vec2(textureSize(inputTexture, 0));
+  float sum = 0.0;
+  for (int x = -blurRadius; x <= blurRadius; x++) {
+    sum += texture(inputTexture, texCoord + vec2(float(x), 0.0) * texel).r;
+  }
+  sum /= float(blurRadius * 2 + 1);
+  imageStore(outputTexture, storageTexCoord, vec4(sum, 0.0, 0.0, 0.0));
+}
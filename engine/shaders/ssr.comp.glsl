@@ -5,6 +5,9 @@ layout(local_size_x = 8,
        local_size_y = 8,
        local_size_z = 1) in;
 
+// Inputs: the lit scene color, the depth buffer, the visibility buffer for the material roughness,
+// the SSR result of the last frame and optionally an environment cube map for rays that miss.
+
 #include "descriptor_sets.inc.glsl"
 #include "camera.inc.glsl"
 
@@ -24,6 +27,18 @@ layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 2) uniform sampler2D depthT
 layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 3, r32ui) readonly uniform uimage2D primitiveIds;
 layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 4, rg16) readonly uniform image2D barycentrics;
 #endif
+layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 5) uniform sampler2D history;
+layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 6) uniform samplerCube environmentMap;
+
+layout(push_constant) uniform SSRConstants {
+  float intensity;
+  float roughnessCutoff;
+  uint maxSteps;
+  uint hasEnvironmentMap;
+};
+
+// How much of the new frame ends up in the result, the rest comes from the reprojected history.
+const float HISTORY_BLEND = 0.2;
 
 void main() {
   ivec2 texSize = imageSize(outputTexture);
@@ -41,13 +56,38 @@ void main() {
   float roughness = 1.0;
 #endif
 
-  SSRConfig config = SSRConfig(30, 0.5, 10, 0.2);
+  SSRConfig config = SSRConfig(30, 0.5, 10, 0.2, maxSteps, roughnessCutoff);
   vec2 reflectionTexCoord;
-  float reflectionIntensity = reflectScreenspace(depthTexture, texCoord, camera, config, roughness, reflectionTexCoord);
+  vec3 reflectionDir;
+  float fallbackWeight;
+  float reflectionIntensity = reflectScreenspace(depthTexture, texCoord, camera, config, roughness, reflectionTexCoord, reflectionDir, fallbackWeight);
+  if (fallbackWeight <= 0.0 || intensity <= 0.0) {
+    // Too rough, skip the history as well so reflections don't linger on surfaces that became rough.
+    imageStore(outputTexture, storageTexCoord, vec4(0.0, 0.0, 0.0, 0.0));
+    return;
+  }
+
+  vec4 result = vec4(0.0);
+  if (hasEnvironmentMap != 0) {
+    vec3 worldReflectionDir = normalize(mat3(camera.invView) * reflectionDir);
+    result = vec4(textureLod(environmentMap, worldReflectionDir, 0).xyz, fallbackWeight);
+  }
   if (reflectionIntensity > 0.01) {
     vec3 reflection = textureLod(colorTexture, reflectionTexCoord, 0).xyz;
-    imageStore(outputTexture, storageTexCoord, vec4(reflection, reflectionIntensity));
-  } else {
-    imageStore(outputTexture, storageTexCoord, vec4(0.0, 0.0, 0.0, 0.0));
+    result.xyz = hasEnvironmentMap != 0 ? mix(result.xyz, reflection, clamp(reflectionIntensity / fallbackWeight, 0.0, 1.0)) : reflection;
+    result.w = max(result.w, reflectionIntensity);
+  }
+  result.w *= intensity;
+
+#ifdef VISIBILITY_BUFFER
+  vec2 barycentricsXY = imageLoad(barycentrics, storageTexCoord).xy;
+  vec2 motion = getMotionVector(id, vec3(barycentricsXY, 1.0 - barycentricsXY.x - barycentricsXY.y), camera, oldCamera);
+#else
+  vec2 motion = vec2(0.0);
+#endif
+  vec2 historyTexCoord = texCoord - motion;
+  if (historyTexCoord.x >= 0.0 && historyTexCoord.x <= 1.0 && historyTexCoord.y >= 0.0 && historyTexCoord.y <= 1.0) {
+    result = mix(textureLod(history, historyTexCoord, 0), result, HISTORY_BLEND);
   }
+  imageStore(outputTexture, storageTexCoord, result);
 }
@@ -5,10 +5,17 @@ struct SSRConfig {
   float resolution;
   uint steps;
   float thickness;
+  uint maxSteps;
+  float roughnessCutoff;
 };
 
-float reflectScreenspace(sampler2D depthTex, vec2 texCoord, Camera camera, SSRConfig config, float roughness, out vec2 outReflectionTexCoords) {
-  if (roughness > 0.3) {
+// Returns the weight of the screen space reflection, 0 if the ray missed or left the screen.
+// outReflectionDir is the view space reflection direction and outFallbackWeight the weight a reflection
+// from another source should have, both are 0 for surfaces that are too rough for reflections.
+float reflectScreenspace(sampler2D depthTex, vec2 texCoord, Camera camera, SSRConfig config, float roughness, out vec2 outReflectionTexCoords, out vec3 outReflectionDir, out float outFallbackWeight) {
+  outReflectionDir = vec3(0.0);
+  outFallbackWeight = 0.0;
+  if (roughness > config.roughnessCutoff) {
     outReflectionTexCoords = texCoord;
     return 0.0;
   }
@@ -24,6 +31,10 @@ float reflectScreenspace(sampler2D depthTex, vec2 texCoord, Camera camera, SSRCo
   vec3 normal = reconstructViewSpaceNormalFS(texCoord, startDepth, camera.invProj);
   #endif
   vec3 pivot = normalize(reflect(unitPositionFrom, normal));
+  outReflectionDir = pivot;
+  // Fade out towards the roughness cutoff instead of cutting reflections off abruptly.
+  float roughnessFade = 1.0 - roughness / max(config.roughnessCutoff, 0.0001);
+  outFallbackWeight = (1 - max(dot(-unitPositionFrom, pivot), 0)) * roughnessFade;
 
   vec4 startView = vec4(positionFrom.xyz, 1.0);
   vec4 endView = vec4(positionFrom.xyz + (pivot * config.maxDistance), 1.0);
@@ -43,6 +54,7 @@ float reflectScreenspace(sampler2D depthTex, vec2 texCoord, Camera camera, SSRCo
   vec2 delta = endFrag.xy - startFrag.xy;
   bool useX      = abs(delta.x) >= abs(delta.y);
   float deltaVal = max(min(texSize.x, abs(delta.x)), min(texSize.y, abs(delta.y))) * clamp(config.resolution, 0.0, 1.0);
+  deltaVal = min(deltaVal, float(config.maxSteps));
   vec2 increment = delta / max(deltaVal, 0.001);
 
   vec2 frag = startFrag.xy;
@@ -108,10 +120,13 @@ float reflectScreenspace(sampler2D depthTex, vec2 texCoord, Camera camera, SSRCo
 
   vec3 positionTo = viewSpacePosition(uv, sampleDepth, camera.invProj);
   outReflectionTexCoords = uv;
-  return (1 - max(dot(-unitPositionFrom, pivot), 0))
+  // Fade out hits close to the screen edges, the fallback takes over there.
+  vec2 edgeDistance = min(uv, 1.0 - uv);
+  float edgeFade = clamp(min(edgeDistance.x, edgeDistance.y) / 0.05, 0.0, 1.0);
+  return outFallbackWeight
     * (1 - clamp(sampleZDiff / config.thickness, 0, 1))
     * (1 - clamp(length(positionTo - positionFrom) / config.maxDistance, 0, 1))
-    * roughness;
+    * edgeFade;
 }
 
 // References:
@@ -17,6 +17,19 @@ layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 5, rg16) readonly uniform i
 #include "frame_set.inc.glsl"
 #include "vis_buf.inc.glsl"
 #endif
+layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 6) uniform sampler2D depthHistory;
+
+layout(push_constant) uniform TAAConstants {
+  float zNear;
+  float zFar;
+  uint logarithmicDepth;
+  // Set after camera cuts or when TAA is disabled, the output is the current frame then.
+  uint resetHistory;
+};
+
+// History samples whose linear depth differs more than this fraction from the current depth
+// belong to a surface that was hidden in the last frame.
+const float DISOCCLUSION_THRESHOLD = 0.1;
 
 #define CS
 #include "util.inc.glsl"
@@ -60,12 +73,12 @@ vec3 historyClamp(vec3 color, vec2 texCoord, ivec2 textureSize, vec3 historyColo
 }
 
 vec2 chooseTexCoordClosestToCamera(sampler2D depthMap, vec2 texCoord) {
-  uvec2 texSize = textureSize(depthMap, 0);
+  vec2 texel = 1.0 / vec2(textureSize(depthMap, 0));
   float minDepth = 1;
   vec2 minTexCoord = texCoord;
-  for (uint x = -1; x <= 1; x++) {
-    for (uint y = -1; y <= 1; y++) {
-      vec2 samplePos = texCoord + vec2(float(x), float(y)) * vec2(texSize);
+  for (int x = -1; x <= 1; x++) {
+    for (int y = -1; y <= 1; y++) {
+      vec2 samplePos = texCoord + vec2(float(x), float(y)) * texel;
       float depthSample = textureLod(depthMap, samplePos, 0).x;
       if (depthSample < minDepth) {
         minDepth = depthSample;
@@ -141,13 +154,19 @@ void main() {
     vec2 texCoord = vec2((float(gl_GlobalInvocationID.x) + 0.5) / float(texSize.x), (float(gl_GlobalInvocationID.y) + 0.5) / float(texSize.y));
     ivec2 storageTexCoord = ivec2(int(gl_GlobalInvocationID.x), int(gl_GlobalInvocationID.y));
     vec3 color = textureLod(frame, texCoord, 0).xyz;
+    if (resetHistory != 0) {
+      imageStore(outputTexture, storageTexCoord, vec4(color, 1.0));
+      return;
+    }
 
+    // Use the motion of the closest surface around the pixel so the edges of moving objects don't ghost.
     vec2 motionTexCoord = chooseTexCoordClosestToCamera(depthMap, texCoord);
 #ifndef VISIBILITY_BUFFER
     vec2 motion = textureLod(motionTex, motionTexCoord, 0).xy;
 #else
-  uint id = imageLoad(primitiveIds, storageTexCoord).x;
-  vec2 barycentricsXY = imageLoad(barycentrics, storageTexCoord).xy;
+  ivec2 motionStorageTexCoord = clamp(ivec2(motionTexCoord * vec2(texSize)), ivec2(0), texSize - 1);
+  uint id = imageLoad(primitiveIds, motionStorageTexCoord).x;
+  vec2 barycentricsXY = imageLoad(barycentrics, motionStorageTexCoord).xy;
   vec3 barycentrics = vec3(barycentricsXY, 1.0 - barycentricsXY.x - barycentricsXY.y);
   vec2 motion = getMotionVector(id, barycentrics, camera, oldCamera);
 #endif
@@ -158,6 +177,13 @@ void main() {
       return;
     }
 
+    float currentZ = linearizeDepthWithMode(textureLod(depthMap, motionTexCoord, 0).x, zNear, zFar, logarithmicDepth != 0);
+    float historyZ = linearizeDepthWithMode(textureLod(depthHistory, historyTexCoord, 0).x, zNear, zFar, logarithmicDepth != 0);
+    if (abs(currentZ - historyZ) > DISOCCLUSION_THRESHOLD * max(abs(currentZ), zNear)) {
+      imageStore(outputTexture, storageTexCoord, vec4(color, 1.0));
+      return;
+    }
+
     vec3 historyColor = catmullRom(history, historyTexCoord);
     vec3 clampedHistoryColor = historyClamp(color, texCoord, texSize, historyColor);
     vec3 clampDiff = abs(clampedHistoryColor) / abs(historyColor);
@@ -0,0 +1,122 @@
+#version 450
+#extension GL_GOOGLE_include_directive : enable
+
+layout(local_size_x = 8,
+       local_size_y = 8,
+       local_size_z = 1) in;
+
+#include "descriptor_sets.inc.glsl"
+
+layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 0) uniform writeonly image2D outputTexture;
+layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 1) uniform sampler2D frame;
+
+#define CS
+#include "util.inc.glsl"
+
+#define TONEMAP_REINHARD 0
+#define TONEMAP_ACES 1
+#define TONEMAP_AGX 2
+
+layout(set = DESCRIPTOR_SET_VERY_FREQUENT, binding = 2, std140) uniform ConfigUBO {
+  float gamma;
+  float exposure;
+  uint tonemapOperator;
+  uint pqOutput;
+};
+
+vec3 reinhard(vec3 x) {
+  return x / (1.0 + x);
+}
+
+vec3 aces(vec3 x) {
+  float a = 2.51;
+  float b = 0.03;
+  float c = 2.43;
+  float d = 0.59;
+  float e = 0.14;
+  return clamp((x*(a*x+b))/(x*(c*x+d)+e), 0.0, 1.0);
+}
+
+// Polynomial fit of the AgX base contrast curve by Benjamin Wrensch.
+vec3 agxContrast(vec3 x) {
+  vec3 x2 = x * x;
+  vec3 x4 = x2 * x2;
+  return 15.5 * x4 * x2
+    - 40.14 * x4 * x
+    + 31.96 * x4
+    - 6.868 * x2 * x
+    + 0.4298 * x2
+    + 0.1191 * x
+    - 0.00232;
+}
+
+vec3 agx(vec3 x) {
+  const mat3 agxInset = mat3(
+    0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+    0.0784335999999992, 0.878468636469772, 0.0784336,
+    0.0792237451477643, 0.0791661274605434, 0.879142973793104
+  );
+  const mat3 agxOutset = mat3(
+    1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+    -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+    -0.0990297440797205, -0.0989611768448433, 1.15107367264116
+  );
+  const float minEv = -12.47393;
+  const float maxEv = 4.026069;
+
+  x = agxInset * x;
+  x = clamp(log2(max(x, vec3(1e-10))), minEv, maxEv);
+  x = (x - minEv) / (maxEv - minEv);
+  x = agxContrast(x);
+  x = agxOutset * x;
+  // The outset already produces display encoded values, undo that so the gamma correction below applies to all operators.
+  return pow(max(x, vec3(0.0)), vec3(2.2));
+}
+
+// SMPTE ST 2084, the input is the luminance normalized to 10000 nits.
+vec3 pq(vec3 x) {
+  const float m1 = 0.1593017578125;
+  const float m2 = 78.84375;
+  const float c1 = 0.8359375;
+  const float c2 = 18.8515625;
+  const float c3 = 18.6875;
+  vec3 xm1 = pow(max(x, vec3(0.0)), vec3(m1));
+  return pow((c1 + c2 * xm1) / (1.0 + c3 * xm1), vec3(m2));
+}
+
+const mat3 bt709ToBt2020 = mat3(
+  0.6274, 0.0691, 0.0164,
+  0.3293, 0.9195, 0.0880,
+  0.0433, 0.0114, 0.8956
+);
+
+// Brightness of SDR white on the HDR display.
+const float PAPER_WHITE_NITS = 200.0;
+
+void main() {
+  ivec2 texSize = imageSize(outputTexture);
+  ivec2 storageTexCoord = ivec2(int(gl_GlobalInvocationID.x), int(gl_GlobalInvocationID.y));
+  if (storageTexCoord.x >= texSize.x || storageTexCoord.y >= texSize.y) {
+    return;
+  }
+  vec2 texCoord = vec2((float(storageTexCoord.x) + 0.5) / float(texSize.x), (float(storageTexCoord.y) + 0.5) / float(texSize.y));
+  vec3 color = texture(frame, texCoord).xyz * exposure;
+
+  vec3 toneMapped;
+  if (tonemapOperator == TONEMAP_REINHARD) {
+    toneMapped = reinhard(color);
+  } else if (tonemapOperator == TONEMAP_AGX) {
+    toneMapped = agx(color);
+  } else {
+    toneMapped = aces(color);
+  }
+
+  vec3 encoded;
+  if (pqOutput != 0) {
+    encoded = pq(bt709ToBt2020 * toneMapped * (PAPER_WHITE_NITS / 10000.0));
+  } else {
+    encoded = pow(toneMapped, vec3(1.0 / gamma));
+  }
+
+  imageStore(outputTexture, storageTexCoord, vec4(encoded, 1.0));
+}
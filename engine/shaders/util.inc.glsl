@@ -6,6 +6,19 @@ float linearizeDepth(float d, float zNear, float zFar) {
   return zNear * zFar / (zFar + d * (zNear - zFar));
 }
 
+// Logarithmic depth: d = log2(1 + w) / log2(1 + zFar), logDepthFactor = 1 / log2(1 + zFar)
+float logDepth(float w, float logDepthFactor) {
+  return log2(max(1e-6, 1.0 + w)) * logDepthFactor;
+}
+
+float linearizeLogDepth(float d, float zFar) {
+  return exp2(d * log2(zFar + 1.0)) - 1.0;
+}
+
+float linearizeDepthWithMode(float d, float zNear, float zFar, bool logarithmic) {
+  return logarithmic ? linearizeLogDepth(d, zFar) : linearizeDepth(d, zNear, zFar);
+}
+
 vec3 worldSpacePosition(vec2 uv, float depth, mat4 invViewProj) {
   vec4 clipSpacePosition = vec4(uv * 2.0 - 1.0, depth, 1.0);
   clipSpacePosition.y = -clipSpacePosition.y;
@@ -3,6 +3,7 @@
 #extension GL_GOOGLE_include_directive : enable
 
 #include "descriptor_sets.inc.glsl"
+#include "util.inc.glsl"
 
 layout(location = 0) in vec3 in_pos;
 layout(location = 1) in vec3 in_normal;
@@ -17,6 +18,10 @@ layout(location = 3) out vec2 out_lightmap_uv;
 
 layout(set = DESCRIPTOR_SET_FRAME, binding = 0) uniform CameraUBO {
   mat4 viewProj;
+  float zNear;
+  float zFar;
+  // 0 when logarithmic depth is disabled
+  float logDepthFactor;
 } camera;
 
 layout(push_constant) uniform VeryHighFrequencyUbo {
@@ -34,4 +39,7 @@ void main(void) {
   out_normal = normalize((model * vec4(in_normal, 0)).xyz);
 
   gl_Position = mvp * pos;
+  if (camera.logDepthFactor != 0.0) {
+    gl_Position.z = logDepth(gl_Position.w, camera.logDepthFactor) * gl_Position.w;
+  }
 }
@@ -0,0 +1,82 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_GOOGLE_include_directive : enable
+
+#include "descriptor_sets.inc.glsl"
+#include "util.inc.glsl"
+
+layout(location = 0) in vec3 in_worldPosition;
+layout(location = 1) in vec2 in_uv;
+layout(location = 2) in vec4 in_clipPosition;
+
+layout(location = 0) out vec4 out_color;
+
+layout(set = DESCRIPTOR_SET_FRAME, binding = 0) uniform CameraUBO {
+  mat4 viewProj;
+  float zNear;
+  float zFar;
+  float logDepthFactor;
+} camera;
+
+layout(set = DESCRIPTOR_SET_FRAME, binding = 1) uniform sampler2D reflectionTex;
+layout(set = DESCRIPTOR_SET_FRAME, binding = 2) uniform sampler2D refractionTex;
+// The refraction view always uses standard depth
+layout(set = DESCRIPTOR_SET_FRAME, binding = 3) uniform sampler2D refractionDepth;
+
+layout(set = DESCRIPTOR_SET_FREQUENT, binding = 0) uniform sampler2D normalMap;
+layout(set = DESCRIPTOR_SET_FREQUENT, binding = 1) uniform WaterUBO {
+  vec4 cameraPosition;
+  vec4 reflectTint;
+  vec4 refractTint;
+  vec4 fogColor;
+  float fogEnd;
+  float time;
+  uint hasNormalMap;
+} water;
+
+const float DISTORTION_STRENGTH = 0.03;
+const float WATER_F0 = 0.02;
+
+vec3 flowingNormal() {
+  if (water.hasNormalMap == 0) {
+    return vec3(0.0, 1.0, 0.0);
+  }
+
+  // Two layers scrolling in different directions hide the tiling of the normal map
+  vec2 uv0 = in_uv + water.time * vec2(0.02, 0.01);
+  vec2 uv1 = in_uv * 0.7 - water.time * vec2(0.015, 0.02);
+  vec3 n0 = texture(normalMap, uv0).xyz * 2.0 - 1.0;
+  vec3 n1 = texture(normalMap, uv1).xyz * 2.0 - 1.0;
+  vec3 tangentSpaceNormal = normalize(n0 + n1);
+
+  // Water surfaces are horizontal, so tangent space maps directly to world space
+  return normalize(vec3(tangentSpaceNormal.x, tangentSpaceNormal.z, tangentSpaceNormal.y));
+}
+
+void main(void) {
+  vec2 ndc = in_clipPosition.xy / in_clipPosition.w;
+  vec2 screenUV = vec2(ndc.x, -ndc.y) * 0.5 + 0.5;
+  float surfaceDepth = in_clipPosition.w;
+
+  vec3 normal = flowingNormal();
+  vec2 distortion = normal.xz * DISTORTION_STRENGTH;
+
+  vec3 reflection = texture(reflectionTex, screenUV + distortion).rgb * water.reflectTint.rgb;
+
+  // Don't refract geometry that is in front of the water surface
+  vec2 refractionUV = screenUV + distortion;
+  float sceneDepth = linearizeDepth(texture(refractionDepth, refractionUV).r, camera.zNear, camera.zFar);
+  if (sceneDepth < surfaceDepth) {
+    refractionUV = screenUV;
+    sceneDepth = linearizeDepth(texture(refractionDepth, refractionUV).r, camera.zNear, camera.zFar);
+  }
+  float thickness = max(sceneDepth - surfaceDepth, 0.0);
+  float fogFactor = clamp(thickness / max(water.fogEnd, 0.001), 0.0, 1.0);
+  vec3 refraction = texture(refractionTex, refractionUV).rgb * water.refractTint.rgb;
+  refraction = mix(refraction, water.fogColor.rgb, fogFactor);
+
+  vec3 viewDir = normalize(water.cameraPosition.xyz - in_worldPosition);
+  float fresnel = WATER_F0 + (1.0 - WATER_F0) * pow(1.0 - max(dot(viewDir, normal), 0.0), 5.0);
+
+  out_color = vec4(mix(refraction, reflection, fresnel), 1.0);
+}
@@ -0,0 +1,41 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_GOOGLE_include_directive : enable
+
+#include "descriptor_sets.inc.glsl"
+#include "util.inc.glsl"
+
+layout(location = 0) in vec3 in_pos;
+layout(location = 1) in vec3 in_normal;
+layout(location = 2) in vec2 in_uv;
+
+layout(location = 0) out vec3 out_worldPosition;
+layout(location = 1) out vec2 out_uv;
+layout(location = 2) out vec4 out_clipPosition;
+
+layout(set = DESCRIPTOR_SET_FRAME, binding = 0) uniform CameraUBO {
+  mat4 viewProj;
+  float zNear;
+  float zFar;
+  // 0 when logarithmic depth is disabled
+  float logDepthFactor;
+} camera;
+
+layout(push_constant) uniform VeryHighFrequencyUbo {
+  mat4 model;
+};
+
+void main(void) {
+  vec4 pos = vec4(in_pos, 1);
+
+  mat4 mvp = camera.viewProj * model;
+
+  out_worldPosition = (model * pos).xyz;
+  out_uv = in_uv;
+
+  gl_Position = mvp * pos;
+  out_clipPosition = gl_Position;
+  if (camera.logDepthFactor != 0.0) {
+    gl_Position.z = logDepth(gl_Position.w, camera.logDepthFactor) * gl_Position.w;
+  }
+}
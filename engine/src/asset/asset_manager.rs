@@ -118,15 +118,27 @@ impl<T> ErasedAssetContainer for T
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct AssetLoadFailure {
+    pub path: String,
+    pub error: String,
+}
+
 pub struct AssetLoaderProgress {
     expected: AtomicU32,
     finished: AtomicU32,
+    failures: Mutex<Vec<AssetLoadFailure>>,
 }
 
 impl AssetLoaderProgress {
     pub fn is_done(&self) -> bool {
         self.finished.load(Ordering::SeqCst) == self.expected.load(Ordering::SeqCst)
     }
+
+    /// The requests that failed to load, failed requests also count as finished.
+    pub fn failures(&self) -> Vec<AssetLoadFailure> {
+        self.failures.lock().unwrap().clone()
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
@@ -175,13 +187,79 @@ impl<T, P: Platform> ErasedAssetLoader<P> for T
     }
 }
 
+struct PendingAssetRequest {
+    asset_type: AssetType,
+    /// How many loads of the path are running. A refresh while the asset is loading starts another one.
+    running_loads: u32,
+    /// The progress of later requests for the same path, they finish together with the last running load.
+    coalesced_progress: Vec<Arc<AssetLoaderProgress>>,
+}
+
+enum PendingRequestState {
+    /// The request needs a load of its own.
+    Load,
+    /// The request got attached to the load that's already running.
+    Coalesced,
+    /// The path is already loading as a different asset type.
+    TypeMismatch,
+}
+
+/// Keeps track of the running loads, so concurrent requests for the same path share a single load.
+#[derive(Default)]
+struct PendingAssetRequests {
+    requests: HashMap<String, PendingAssetRequest>,
+}
+
+impl PendingAssetRequests {
+    /// Attaches the progress to a running load of the path if there is one.
+    /// Refreshes always need a load of their own because the running load might not have seen the change that caused them.
+    fn coalesce(&mut self, path: &str, asset_type: AssetType, progress: &Arc<AssetLoaderProgress>, refresh: bool) -> PendingRequestState {
+        let Some(request) = self.requests.get_mut(path) else {
+            return PendingRequestState::Load;
+        };
+        if request.asset_type != asset_type {
+            return PendingRequestState::TypeMismatch;
+        }
+        if refresh {
+            return PendingRequestState::Load;
+        }
+        request.coalesced_progress.push(progress.clone());
+        PendingRequestState::Coalesced
+    }
+
+    fn start_load(&mut self, path: &str, asset_type: AssetType) {
+        let request = self.requests.entry(path.to_string()).or_insert_with(|| PendingAssetRequest {
+            asset_type,
+            running_loads: 0,
+            coalesced_progress: Vec::new(),
+        });
+        request.running_loads += 1;
+    }
+
+    /// Returns the progress of the coalesced requests once the last running load of the path is done.
+    fn finish_load(&mut self, path: &str) -> Vec<Arc<AssetLoaderProgress>> {
+        let Some(request) = self.requests.get_mut(path) else {
+            return Vec::new();
+        };
+        request.running_loads = request.running_loads.saturating_sub(1);
+        if request.running_loads != 0 {
+            return Vec::new();
+        }
+        self.requests.remove(path).map(|request| request.coalesced_progress).unwrap_or_default()
+    }
+
+    fn paths(&self) -> impl Iterator<Item = &String> {
+        self.requests.keys()
+    }
+}
+
 pub struct AssetManager<P: Platform> {
     device: Arc<crate::graphics::Device<P::GPUBackend>>,
     containers: async_rwlock::RwLock<Vec<Box<dyn ErasedAssetContainer>>>,
     pending_containers_count: AtomicU32,
     loaders: async_rwlock::RwLock<Vec<Box<dyn ErasedAssetLoader<P>>>>,
     pending_loaders_count: AtomicU32,
-    requested_assets: Mutex<HashMap<String, AssetType>>,
+    requested_assets: Mutex<PendingAssetRequests>,
     unintegrated_assets: Mutex<HashMap<String, AssetData>>,
     renderer: RendererAssets<P>,
 }
@@ -196,7 +274,7 @@ impl<P: Platform> AssetManager<P> {
             containers: async_rwlock::RwLock::new(Vec::new()),
             unintegrated_assets: Mutex::new(HashMap::new()),
             renderer: RendererAssets::<P>::new(device),
-            requested_assets: Mutex::new(HashMap::new()),
+            requested_assets: Mutex::new(PendingAssetRequests::default()),
             pending_containers_count: AtomicU32::new(0u32),
             pending_loaders_count: AtomicU32::new(0u32)
         });
@@ -265,10 +343,22 @@ impl<P: Platform> AssetManager<P> {
         self.renderer.request_graphics_pipeline(self, info)
     }
 
+    /// Like [`Self::request_graphics_pipeline`] but the renderer doesn't wait for the pipeline
+    /// before it starts rendering.
+    pub fn request_graphics_pipeline_async(self: &Arc<Self>, info: &GraphicsPipelineInfo) -> GraphicsPipelineHandle {
+        self.renderer.request_graphics_pipeline_async(self, info)
+    }
+
     pub fn request_compute_pipeline(self: &Arc<Self>, shader_path: &str) -> ComputePipelineHandle {
         self.renderer.request_compute_pipeline(self, shader_path)
     }
 
+    /// Like [`Self::request_compute_pipeline`] but the renderer doesn't wait for the pipeline
+    /// before it starts rendering.
+    pub fn request_compute_pipeline_async(self: &Arc<Self>, shader_path: &str) -> ComputePipelineHandle {
+        self.renderer.request_compute_pipeline_async(self, shader_path)
+    }
+
     pub fn request_ray_tracing_pipeline(self: &Arc<Self>, info: &RayTracingPipelineInfo) -> RayTracingPipelineHandle {
         self.renderer.request_ray_tracing_pipeline(self, info)
     }
@@ -395,6 +485,41 @@ impl<P: Platform> AssetManager<P> {
         }
     }
 
+    /// Makes the given mip level and every smaller one of a streamed texture resident.
+    /// Textures that weren't requested with a high priority only get their smallest mip levels uploaded at first.
+    /// Requesting an already loaded texture with a high priority again makes all of its mips resident.
+    /// The texture gets reloaded from its file and replaced once the higher mips are available.
+    /// Calling it for mips that are already resident marks the texture as recently used,
+    /// the least recently used textures lose their high mips when the streaming budget runs out.
+    pub fn request_texture_mips(self: &Arc<Self>, path: &str, first_mip: u32) {
+        let reload = self.renderer.texture_streamer().request_first_mip(path, first_mip);
+        self.reload_streamed_textures(&reload);
+    }
+
+    /// Starts a new frame of texture mip requests.
+    /// Textures requested within the same frame don't evict each other, requests that don't fit into the budget get fewer mips.
+    pub(crate) fn begin_texture_streaming_frame(&self) {
+        self.renderer.texture_streamer().begin_frame();
+    }
+
+    /// Limits how much memory the mips of streamed textures may take up on top of their initially uploaded ones.
+    pub fn set_texture_streaming_budget(self: &Arc<Self>, byte_size: u64) {
+        let reload = self.renderer.texture_streamer().set_budget(byte_size);
+        self.reload_streamed_textures(&reload);
+    }
+
+    /// The largest mip level of the texture that is currently uploaded, None if the texture doesn't get streamed.
+    pub fn texture_resident_first_mip(&self, path: &str) -> Option<u32> {
+        self.renderer.texture_streamer().resident_first_mip(path)
+    }
+
+    fn reload_streamed_textures(self: &Arc<Self>, paths: &[String]) {
+        for path in paths {
+            trace!("Streaming texture: {}", path);
+            self.request_asset_internal(path, AssetType::Texture, AssetLoadPriority::Low, None, true);
+        }
+    }
+
     pub fn request_asset(
         self: &Arc<Self>,
         path: &str,
@@ -428,6 +553,7 @@ impl<P: Platform> AssetManager<P> {
                 Arc::new(AssetLoaderProgress {
                     expected: AtomicU32::new(0),
                     finished: AtomicU32::new(0),
+                    failures: Mutex::new(Vec::new()),
                 })
             },
             |p| p.clone(),
@@ -439,26 +565,32 @@ impl<P: Platform> AssetManager<P> {
             let _ = self.take_any_unintegrated_asset_data_of_type(AssetType::Level);
         }
 
-        let mut skip = false;
         {
             // Already requested?
-            let requests = self.requested_assets.lock().unwrap();
-            if let Some(requested_asset_type) = requests.get(path) {
-                if *requested_asset_type != asset_type {
+            let mut requests = self.requested_assets.lock().unwrap();
+            match requests.coalesce(path, asset_type, &progress, refresh) {
+                PendingRequestState::TypeMismatch => {
                     error!("Requested an asset with the same path as a previously requested asset but with a different asset type. Path: {}", path);
-                    skip = true;
+                    progress.finished.fetch_add(1, Ordering::SeqCst);
+                    return progress;
                 }
+                PendingRequestState::Coalesced => {
+                    trace!("Asset is already being loaded, waiting for the running request. Path: {}", path);
+                    return progress;
+                }
+                PendingRequestState::Load => {}
             }
-        };
-        if !skip {
             if !refresh && self.contains(path, asset_type) {
                 trace!("Skipping asset request because it is already loaded and request did not specify that it should be refreshed. Path: {}", path);
-                skip = true;
+                progress.finished.fetch_add(1, Ordering::SeqCst);
+                if asset_type == AssetType::Texture && priority == AssetLoadPriority::High {
+                    // Requesting a streamed texture again with a high priority makes all of its mips resident.
+                    drop(requests);
+                    self.request_texture_mips(path, 0);
+                }
+                return progress;
             }
-        }
-        if skip {
-            progress.finished.fetch_add(1, Ordering::SeqCst);
-            return progress;
+            requests.start_load(path, asset_type);
         }
 
         let load_request = AssetLoadRequest {
@@ -473,8 +605,7 @@ impl<P: Platform> AssetManager<P> {
             trace!("Loading file for {}", &load_request.path);
             let file_opt = asset_mgr.load_file(&load_request.path).await;
             if file_opt.is_none() {
-                error!("Could not find file at path: {}", &load_request.path);
-                load_request.progress.finished.fetch_add(1, Ordering::SeqCst);
+                asset_mgr.fail_request(&load_request.path, &load_request.progress, "Could not find file");
                 return;
             }
             let file = file_opt.unwrap();
@@ -488,6 +619,38 @@ impl<P: Platform> AssetManager<P> {
         progress
     }
 
+    /// Returns the paths of all assets that were requested and haven't finished loading yet.
+    pub fn pending_assets(&self) -> Vec<String> {
+        let requests = self.requested_assets.lock().unwrap();
+        requests.paths().cloned().collect()
+    }
+
+    /// Finishes one load of the path. Once no other load of it is running,
+    /// the progress of the requests that were coalesced into it gets finished with the result of this one.
+    fn finish_request(&self, path: &str, failure: Option<&AssetLoadFailure>) {
+        let coalesced_progress = {
+            let mut requests = self.requested_assets.lock().unwrap();
+            requests.finish_load(path)
+        };
+        for progress in coalesced_progress {
+            if let Some(failure) = failure {
+                progress.failures.lock().unwrap().push(failure.clone());
+            }
+            progress.finished.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn fail_request(&self, path: &str, progress: &Arc<AssetLoaderProgress>, error: &str) {
+        error!("{}: {:?}", error, path);
+        let failure = AssetLoadFailure {
+            path: path.to_string(),
+            error: error.to_string(),
+        };
+        self.finish_request(path, Some(&failure));
+        progress.failures.lock().unwrap().push(failure);
+        progress.finished.fetch_add(1, Ordering::SeqCst);
+    }
+
     pub(crate) fn take_unintegrated_asset_data(self: &Arc<Self>, path: &str) -> Option<AssetData> {
         let mut unintegrated = self.unintegrated_assets.lock().unwrap();
         unintegrated.remove(path)
@@ -569,16 +732,10 @@ impl<P: Platform> AssetManager<P> {
         priority: AssetLoadPriority,
         progress: &Arc<AssetLoaderProgress>,
     ) -> Result<(), ()> {
-        {
-            let mut requests = self.requested_assets.lock().unwrap();
-            requests.remove(&file.path);
-        }
-
         let loaders = self.loaders.read().await;
         let loader_opt: Option<&dyn ErasedAssetLoader<P>> = AssetManager::find_loader(&mut file, loaders.as_ref(), &self.pending_loaders_count).await;
         if loader_opt.is_none() {
-            progress.finished.fetch_add(1, Ordering::SeqCst);
-            error!("Could not find loader for file: {:?}", &file.path);
+            self.fail_request(&file.path, progress, "Could not find loader for file");
             return Err(());
         }
         let loader = loader_opt.unwrap();
@@ -586,14 +743,20 @@ impl<P: Platform> AssetManager<P> {
         let path = file.path.clone();
         let assets_opt = loader.load(file, self, priority, progress).await;
         if assets_opt.is_err() {
-            progress.finished.fetch_add(1, Ordering::SeqCst);
-            error!("Could not load file: {:?}", &path);
+            self.fail_request(&path, progress, "Could not load file");
             return Err(());
         }
         if !self.contains(&path, asset_type) {
             error!("Loader did not load requested asset from file: {:?}", &path);
+            let failure = AssetLoadFailure {
+                path: path.clone(),
+                error: "Loader did not load requested asset from file".to_string(),
+            };
+            self.finish_request(&path, Some(&failure));
+            progress.failures.lock().unwrap().push(failure);
             return Err(());
         }
+        self.finish_request(&path, None);
         Ok(())
     }
 
@@ -635,3 +798,76 @@ impl<P: Platform> AssetManager<P> {
         self.renderer.flush(self);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_progress() -> Arc<AssetLoaderProgress> {
+        Arc::new(AssetLoaderProgress {
+            expected: AtomicU32::new(1),
+            finished: AtomicU32::new(0),
+            failures: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Mirrors what request_asset_internal does with the pending requests and returns whether the request starts a load.
+    fn request(requests: &mut PendingAssetRequests, path: &str, progress: &Arc<AssetLoaderProgress>, refresh: bool) -> bool {
+        match requests.coalesce(path, AssetType::Texture, progress, refresh) {
+            PendingRequestState::Load => {
+                requests.start_load(path, AssetType::Texture);
+                true
+            }
+            PendingRequestState::Coalesced => false,
+            PendingRequestState::TypeMismatch => panic!("Unexpected type mismatch"),
+        }
+    }
+
+    #[test]
+    fn concurrent_requests_load_once() {
+        let mut requests = PendingAssetRequests::default();
+        let first = new_progress();
+        let second = new_progress();
+        let mut loads = 0;
+        for progress in [&first, &second] {
+            if request(&mut requests, "textures/a.png", progress, false) {
+                loads += 1;
+            }
+        }
+        assert_eq!(loads, 1);
+        assert_eq!(requests.paths().collect::<Vec<_>>(), vec!["textures/a.png"]);
+
+        let coalesced = requests.finish_load("textures/a.png");
+        assert_eq!(coalesced.len(), 1);
+        assert!(Arc::ptr_eq(&coalesced[0], &second));
+        assert_eq!(requests.paths().count(), 0);
+
+        // Requests after the load finished start a new one, other paths never get coalesced.
+        assert!(request(&mut requests, "textures/a.png", &first, false));
+        assert!(request(&mut requests, "textures/b.png", &second, false));
+    }
+
+    #[test]
+    fn refresh_while_loading() {
+        let mut requests = PendingAssetRequests::default();
+        let coalesced_progress = new_progress();
+        assert!(request(&mut requests, "textures/a.png", &new_progress(), false));
+        assert!(!request(&mut requests, "textures/a.png", &coalesced_progress, false));
+        assert!(request(&mut requests, "textures/a.png", &new_progress(), true));
+
+        // The coalesced request waits for the refresh, it might have requested the asset because of the change.
+        assert!(requests.finish_load("textures/a.png").is_empty());
+        assert!(!request(&mut requests, "textures/a.png", &new_progress(), false));
+        let coalesced = requests.finish_load("textures/a.png");
+        assert_eq!(coalesced.len(), 2);
+        assert!(Arc::ptr_eq(&coalesced[0], &coalesced_progress));
+        assert!(requests.finish_load("textures/a.png").is_empty());
+    }
+
+    #[test]
+    fn different_asset_type() {
+        let mut requests = PendingAssetRequests::default();
+        requests.start_load("models/a.mdl", AssetType::Model);
+        assert!(matches!(requests.coalesce("models/a.mdl", AssetType::Mesh, &new_progress(), false), PendingRequestState::TypeMismatch));
+    }
+}
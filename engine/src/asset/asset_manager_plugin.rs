@@ -29,6 +29,9 @@ impl<P: Platform> Plugin for AssetManagerPlugin<P> {
 
         asset_manager.add_loader(GltfLoader::new());
         asset_manager.add_loader(ImageLoader::new());
+        asset_manager.add_loader(VTFTextureLoader::new());
+        asset_manager.add_loader(VMTMaterialLoader::new());
+        asset_manager.add_loader(ObjLoader::new());
         app.insert_resource(AssetManagerECSResource(asset_manager));
         app.add_systems(PreUpdate, load_level_system::<P>);
     }
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+struct CacheEntry<V> {
+    value: V,
+    byte_size: u64,
+    last_use: u64,
+    /// The frame in which the entry was last inserted or touched.
+    frame: u64,
+}
+
+/// A least recently used cache that is bounded by the combined byte size of its entries instead of the entry count.
+/// The values don't need to own the memory they represent, the size is just what the caller says it is.
+///
+/// Entries that were used in the current frame don't get evicted by inserts,
+/// so entries that are all needed at the same time can't keep evicting each other.
+pub(crate) struct FixedByteSizeCache<K: Hash + Eq + Clone, V> {
+    entries: HashMap<K, CacheEntry<V>>,
+    byte_size: u64,
+    max_byte_size: u64,
+    use_counter: u64,
+    frame: u64,
+}
+
+impl<K: Hash + Eq + Clone, V> FixedByteSizeCache<K, V> {
+    pub(crate) fn new(max_byte_size: u64) -> Self {
+        Self {
+            entries: HashMap::new(),
+            byte_size: 0,
+            max_byte_size,
+            use_counter: 0,
+            frame: 0,
+        }
+    }
+
+    /// Starts a new frame, the entries used in the previous one can be evicted again.
+    pub(crate) fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Inserts or replaces the entry and returns the entries that had to be evicted to stay within the budget.
+    /// The inserted entry itself and the entries used in the current frame never get evicted,
+    /// even if that means going over the budget. Use `available_byte_size` to avoid that.
+    pub(crate) fn insert(&mut self, key: K, value: V, byte_size: u64) -> Vec<(K, V)> {
+        self.use_counter += 1;
+        if let Some(old_entry) = self.entries.remove(&key) {
+            self.byte_size -= old_entry.byte_size;
+        }
+        self.byte_size += byte_size;
+        self.entries.insert(key, CacheEntry {
+            value,
+            byte_size,
+            last_use: self.use_counter,
+            frame: self.frame,
+        });
+        self.evict(true)
+    }
+
+    /// Marks the entry as recently used and used in the current frame.
+    pub(crate) fn touch(&mut self, key: &K) -> bool {
+        self.use_counter += 1;
+        if let Some(entry) = self.entries.get_mut(key) {
+            entry.last_use = self.use_counter;
+            entry.frame = self.frame;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn remove(&mut self, key: &K) -> Option<V> {
+        let entry = self.entries.remove(key)?;
+        self.byte_size -= entry.byte_size;
+        Some(entry.value)
+    }
+
+    /// The largest size the entry can be inserted with without going over the budget.
+    /// Everything except for the entries used in the current frame can be evicted to make room for it.
+    pub(crate) fn available_byte_size(&self, key: &K) -> u64 {
+        let used_byte_size: u64 = self.entries
+            .iter()
+            .filter(|(entry_key, entry)| entry.frame == self.frame && *entry_key != key)
+            .map(|(_, entry)| entry.byte_size)
+            .sum();
+        self.max_byte_size.saturating_sub(used_byte_size)
+    }
+
+    /// Changes the budget and returns the entries that had to be evicted to stay within it.
+    /// Entries used in the current frame can get evicted too.
+    pub(crate) fn set_max_byte_size(&mut self, max_byte_size: u64) -> Vec<(K, V)> {
+        self.max_byte_size = max_byte_size;
+        self.evict(false)
+    }
+
+    fn evict(&mut self, keep_current_frame: bool) -> Vec<(K, V)> {
+        let mut evicted = Vec::new();
+        while self.byte_size > self.max_byte_size {
+            let oldest_key = self.entries
+                .iter()
+                .filter(|(_, entry)| !keep_current_frame || entry.frame != self.frame)
+                .min_by_key(|(_, entry)| entry.last_use)
+                .map(|(key, _)| key.clone());
+            let Some(oldest_key) = oldest_key else {
+                break;
+            };
+            let entry = self.entries.remove(&oldest_key).unwrap();
+            self.byte_size -= entry.byte_size;
+            evicted.push((oldest_key, entry.value));
+        }
+        evicted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evicted_keys(evicted: Vec<(&'static str, ())>) -> Vec<&'static str> {
+        evicted.into_iter().map(|(key, _)| key).collect()
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = FixedByteSizeCache::<&'static str, ()>::new(100);
+        assert!(cache.insert("a", (), 40).is_empty());
+        cache.next_frame();
+        assert!(cache.insert("b", (), 40).is_empty());
+        cache.next_frame();
+        assert!(cache.touch(&"a"));
+        cache.next_frame();
+
+        assert_eq!(evicted_keys(cache.insert("c", (), 40)), vec!["b"]);
+        assert_eq!(cache.byte_size, 80);
+        assert_eq!(evicted_keys(cache.set_max_byte_size(40)), vec!["a"]);
+        assert_eq!(cache.byte_size, 40);
+        assert!(cache.entries.contains_key("c"));
+    }
+
+    #[test]
+    fn keeps_inserted_entry() {
+        let mut cache = FixedByteSizeCache::<&'static str, ()>::new(100);
+        assert!(cache.insert("a", (), 40).is_empty());
+        cache.next_frame();
+
+        assert_eq!(evicted_keys(cache.insert("b", (), 150)), vec!["a"]);
+        assert_eq!(cache.byte_size, 150);
+        assert_eq!(cache.entries.len(), 1);
+
+        // Replacing an entry only counts its new size.
+        assert!(cache.insert("b", (), 60).is_empty());
+        assert_eq!(cache.byte_size, 60);
+    }
+
+    #[test]
+    fn keeps_entries_of_current_frame() {
+        let mut cache = FixedByteSizeCache::<&'static str, ()>::new(100);
+        assert!(cache.insert("a", (), 40).is_empty());
+        assert!(cache.insert("b", (), 40).is_empty());
+        assert_eq!(cache.available_byte_size(&"c"), 20);
+        assert_eq!(cache.available_byte_size(&"a"), 60);
+
+        assert!(cache.insert("c", (), 40).is_empty());
+        assert_eq!(cache.byte_size, 120);
+
+        cache.next_frame();
+        assert!(cache.touch(&"c"));
+        assert_eq!(cache.available_byte_size(&"d"), 60);
+        assert_eq!(evicted_keys(cache.insert("d", (), 60)), vec!["a", "b"]);
+        assert_eq!(cache.byte_size, 100);
+    }
+}
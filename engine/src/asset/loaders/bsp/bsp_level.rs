@@ -458,7 +458,7 @@ impl<P: Platform> AssetLoader<P> for BspLevelLoader {
             entities,
         };
 
-        let pakfile_container = PakFileContainer::new(pakfile);
+        let pakfile_container = pakfile.map(PakFileContainer::new);
 
         let mut world = LoadedLevel::new(4096, 64);
         let mut materials_to_load = HashSet::<String>::new();
@@ -615,7 +615,9 @@ impl<P: Platform> AssetLoader<P> for BspLevelLoader {
             manager.request_asset(&material, AssetType::Material, AssetLoadPriority::Low);
         }
 
-        manager.add_container_sync(pakfile_container);
+        if let Some(pakfile_container) = pakfile_container {
+            manager.add_container_sync(pakfile_container);
+        }
 
         let lightmap_info = TextureInfo {
             dimension: TextureDimension::Dim2D,
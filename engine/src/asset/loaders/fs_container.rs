@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::path::{
     Path,
     PathBuf,
@@ -8,12 +9,14 @@ use std::sync::{
     Weak,
 };
 use std::thread;
+use std::time::Duration;
 
 use bevy_tasks::futures_lite::io::Cursor;
 use bevy_tasks::futures_lite::AsyncReadExt;
 use crossbeam_channel::{
     unbounded,
     Receiver,
+    RecvTimeoutError,
 };
 use log::trace;
 use sourcerenderer_core::platform::{
@@ -28,6 +31,10 @@ use crate::asset::asset_manager::{
 };
 use crate::asset::AssetManager;
 
+/// Editors frequently write a file multiple times when saving it,
+/// so changes only get reloaded after the file hasn't changed for this long.
+const HOT_RELOAD_DEBOUNCE: Duration = Duration::from_millis(100);
+
 pub struct FSContainer<P: Platform> {
     path: PathBuf,
     external: bool,
@@ -94,21 +101,27 @@ impl<P: Platform> AssetContainer for FSContainer<P> {
 
 impl<P: Platform> FSContainer<P> {
     pub fn new(asset_manager: &Arc<AssetManager<P>>) -> Self {
-        let (sender, receiver) = unbounded();
-        let file_watcher = <P::IO as IO>::new_file_watcher(sender);
-        let asset_mgr_weak = Arc::downgrade(asset_manager);
+        let mut watcher = None;
+        if cfg!(any(debug_assertions, feature = "hot_reload")) {
+            let (sender, receiver) = unbounded();
+            let file_watcher = <P::IO as IO>::new_file_watcher(sender);
+            let asset_mgr_weak = Arc::downgrade(asset_manager);
 
-        if cfg!(feature = "threading") {
-            let mut thread_builder = thread::Builder::new();
-            thread_builder = thread_builder.name("AssetManagerWatchThread".to_string());
-            let _ = thread_builder.spawn(move || {
-                fs_container_watch_thread_fn(asset_mgr_weak, receiver)
-            }).unwrap();
+            // The watch thread doesn't use the task pools, so it doesn't depend on the threading feature.
+            // Wasm can't spawn threads, the web file watcher never reports changes anyway.
+            if cfg!(not(target_arch = "wasm32")) {
+                let mut thread_builder = thread::Builder::new();
+                thread_builder = thread_builder.name("AssetManagerWatchThread".to_string());
+                let _ = thread_builder.spawn(move || {
+                    fs_container_watch_thread_fn(asset_mgr_weak, receiver)
+                }).unwrap();
+            }
+            watcher = Some(Mutex::new(file_watcher));
         }
         Self {
             path: PathBuf::from(""),
             external: false,
-            watcher: Some(Mutex::new(file_watcher)),
+            watcher,
         }
     }
 
@@ -128,18 +141,41 @@ fn fs_container_watch_thread_fn<P: Platform>(
 ) {
     'watch_loop: loop {
         let changed = receiver.recv();
+        let mut changed_paths = Vec::<String>::new();
         match changed {
             Err(_) => {
                 break 'watch_loop;
             }
             Ok(path) => {
-                let mgr_opt = asset_manager.upgrade();
-                if mgr_opt.is_none() {
-                    break 'watch_loop;
+                changed_paths.push(path);
+            }
+        }
+
+        // Wait until the files stop changing and only reload every file once.
+        let mut disconnected = false;
+        loop {
+            match receiver.recv_timeout(HOT_RELOAD_DEBOUNCE) {
+                Ok(path) => changed_paths.push(path),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    disconnected = true;
+                    break;
                 }
-                let mgr = mgr_opt.unwrap();
-                mgr.request_asset_update(&path);
             }
         }
+        let mut reloaded_paths = HashSet::<String>::new();
+        changed_paths.retain(|path| reloaded_paths.insert(path.clone()));
+
+        let mgr_opt = asset_manager.upgrade();
+        if mgr_opt.is_none() {
+            break 'watch_loop;
+        }
+        let mgr = mgr_opt.unwrap();
+        for path in &changed_paths {
+            mgr.request_asset_update(path);
+        }
+        if disconnected {
+            break 'watch_loop;
+        }
     }
 }
@@ -14,6 +14,10 @@ use gltf::buffer::{
     Source,
     View,
 };
+use gltf::accessor::{
+    DataType,
+    Dimensions,
+};
 use gltf::material::AlphaMode;
 use gltf::texture::WrappingMode;
 use gltf::{
@@ -458,6 +462,35 @@ impl GltfLoader {
                 .await
                 .unwrap();
 
+            // Vertex colors are optional
+            let mut colors = Vec::<[u8; 4]>::new();
+            if let Some(colors_accessor) = primitive.get(&Semantic::Colors(0)) {
+                assert!(colors_accessor.sparse().is_none());
+                let colors_view = colors_accessor.view().unwrap();
+                let colors_data = load_buffer(
+                    gltf_file_name,
+                    gltf_path,
+                    asset_mgr,
+                    buffer_cache,
+                    &colors_view,
+                ).await;
+                let colors_stride = if let Some(stride) = colors_view.stride() {
+                    stride
+                } else {
+                    colors_accessor.size()
+                };
+                colors.reserve(colors_accessor.count());
+                for i in 0..colors_accessor.count() {
+                    let start = colors_accessor.offset() + i * colors_stride;
+                    let color = colors_data
+                        .get(start..start + colors_accessor.size())
+                        .and_then(|color_data| {
+                            read_color(color_data, colors_accessor.data_type(), colors_accessor.dimensions())
+                        });
+                    colors.push(color.unwrap_or([255, 255, 255, 255]));
+                }
+            }
+
             assert_eq!(positions.count(), normals.count());
             for i in 0..positions.count() {
                 positions_buffer_cursor
@@ -510,7 +543,7 @@ impl GltfLoader {
                         position,
                         normal,
                         tex_coord: *texcoord_vec_ptr,
-                        color: [255, 255, 255, 255],
+                        color: colors.get(i).copied().unwrap_or([255, 255, 255, 255]),
                     });
                 }
 
@@ -642,7 +675,7 @@ impl GltfLoader {
             });
 
         if let Some(albedo_path) = albedo_path {
-            asset_mgr.request_asset(&albedo_path, AssetType::Material, AssetLoadPriority::Low);
+            asset_mgr.request_asset(&albedo_path, AssetType::Texture, AssetLoadPriority::Low);
             asset_mgr.add_material_data(
                 &material_path,
                 &albedo_path,
@@ -710,6 +743,32 @@ impl<P: Platform> AssetLoader<P> for GltfLoader {
     }
 }
 
+/// Converts a COLOR_0 element to RGBA8, glTF stores colors as floats or normalized integers.
+fn read_color(data: &[u8], data_type: DataType, dimensions: Dimensions) -> Option<[u8; 4]> {
+    let component_count = match dimensions {
+        Dimensions::Vec3 => 3,
+        Dimensions::Vec4 => 4,
+        _ => return None,
+    };
+    let mut color = [255u8; 4];
+    for (i, component) in color.iter_mut().enumerate().take(component_count) {
+        *component = match data_type {
+            DataType::U8 => *data.get(i)?,
+            DataType::U16 => {
+                let value = u16::from_le_bytes([*data.get(i * 2)?, *data.get(i * 2 + 1)?]);
+                (value >> 8) as u8
+            }
+            DataType::F32 => {
+                let bytes = data.get(i * 4..i * 4 + 4)?;
+                let value = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                (value.clamp(0f32, 1f32) * 255f32).round() as u8
+            }
+            _ => return None,
+        };
+    }
+    Some(color)
+}
+
 // glTF uses a right-handed coordinate system. glTF defines +Y as up, +Z as forward, and -X as right; the front of a glTF asset faces +Z.
 // We use a left-handed coordinate system with +Y as up, +Z as forward and +X as right. => flip X
 fn fixup_vec(vec: &Vec3) -> Vec3 {
@@ -1,9 +1,15 @@
 mod fs_container;
 mod gltf;
 mod image_loader;
+mod obj_loader;
 mod shader_loader;
+mod vmt_loader;
+mod vtf_loader;
 
 pub use self::fs_container::FSContainer;
 pub use self::image_loader::ImageLoader;
+pub use self::obj_loader::ObjLoader;
 pub use self::shader_loader::ShaderLoader;
+pub use self::vmt_loader::VMTMaterialLoader;
+pub use self::vtf_loader::VTFTextureLoader;
 pub use self::gltf::{GltfContainer, GltfLoader};
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::slice;
+
+use bevy_tasks::futures_lite::AsyncReadExt;
+use log::warn;
+use sourcerenderer_core::{
+    Platform,
+    Vec2,
+    Vec3,
+    Vec4,
+};
+
+use crate::asset::asset_manager::{AssetFile, AssetLoader};
+use crate::asset::{
+    AssetData, AssetLoadPriority, AssetLoaderProgress, AssetManager, AssetType, MeshData, MeshRange, ModelData, Vertex
+};
+use crate::math::BoundingBox;
+
+/// Loads Wavefront OBJ files as a model.
+/// Materials are read from the referenced MTL files, only the diffuse color and texture are used.
+/// Not optimized in any way, it's meant for small test models.
+pub struct ObjLoader {}
+
+impl ObjLoader {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+struct ObjMaterial {
+    diffuse_color: Vec4,
+    diffuse_texture: Option<String>,
+}
+
+struct ObjPart {
+    material: Option<String>,
+    indices: Vec<u32>,
+}
+
+/// Resolves a 1 based OBJ index, negative indices are relative to the end of the list.
+fn resolve_index(index: &str, count: usize) -> Option<usize> {
+    let index = index.parse::<i64>().ok()?;
+    let resolved = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+    (resolved >= 0 && (resolved as usize) < count).then_some(resolved as usize)
+}
+
+fn parse_floats<const N: usize>(parts: &mut std::str::SplitWhitespace) -> Option<[f32; N]> {
+    let mut values = [0f32; N];
+    for value in &mut values {
+        *value = parts.next()?.parse::<f32>().ok()?;
+    }
+    Some(values)
+}
+
+fn parse_mtl(text: &str, directory: &str, materials: &mut HashMap<String, ObjMaterial>) {
+    let mut current: Option<String> = None;
+    for line in text.lines() {
+        let line = line.trim();
+        let mut parts = line.split_whitespace();
+        match parts.next() {
+            Some("newmtl") => {
+                let name = line["newmtl".len()..].trim().to_string();
+                materials.insert(name.clone(), ObjMaterial {
+                    diffuse_color: Vec4::new(1f32, 1f32, 1f32, 1f32),
+                    diffuse_texture: None,
+                });
+                current = Some(name);
+            }
+            Some("Kd") => {
+                if let (Some(material), Some([r, g, b])) = (current.as_ref().and_then(|name| materials.get_mut(name)), parse_floats::<3>(&mut parts)) {
+                    material.diffuse_color = Vec4::new(r, g, b, material.diffuse_color.w);
+                }
+            }
+            Some("d") => {
+                if let (Some(material), Some([alpha])) = (current.as_ref().and_then(|name| materials.get_mut(name)), parse_floats::<1>(&mut parts)) {
+                    material.diffuse_color.w = alpha;
+                }
+            }
+            Some("map_Kd") => {
+                // Options like -s or -o come before the file name, the file name is always last.
+                if let (Some(material), Some(texture)) = (current.as_ref().and_then(|name| materials.get_mut(name)), line.split_whitespace().last()) {
+                    material.diffuse_texture = Some(directory.to_string() + &texture.replace('\\', "/"));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn into_bytes<T>(data: Vec<T>) -> Box<[u8]> {
+    let count = data.len();
+    let ptr = Box::into_raw(data.into_boxed_slice());
+    let data_ptr = unsafe {
+        slice::from_raw_parts_mut(
+            ptr as *mut u8,
+            count * std::mem::size_of::<T>(),
+        ) as *mut [u8]
+    };
+    unsafe { Box::from_raw(data_ptr) }
+}
+
+impl<P: Platform> AssetLoader<P> for ObjLoader {
+    fn matches(&self, file: &mut AssetFile) -> bool {
+        file.path.ends_with(".obj")
+    }
+
+    async fn load(
+        &self,
+        mut file: AssetFile,
+        manager: &Arc<AssetManager<P>>,
+        priority: AssetLoadPriority,
+        progress: &Arc<AssetLoaderProgress>,
+    ) -> Result<(), ()> {
+        let path = file.path.clone();
+        let directory = path.rfind('/').map_or("", |index| &path[..index + 1]).to_string();
+        let mut text = String::new();
+        file.read_to_string(&mut text).await.map_err(|_| ())?;
+
+        let mut positions = Vec::<Vec3>::new();
+        let mut tex_coords = Vec::<Vec2>::new();
+        let mut normals = Vec::<Vec3>::new();
+        let mut materials = HashMap::<String, ObjMaterial>::new();
+
+        let mut vertices = Vec::<Vertex>::new();
+        let mut vertex_indices = HashMap::<(usize, Option<usize>, Option<usize>), u32>::new();
+        let mut parts = vec![ObjPart { material: None, indices: Vec::new() }];
+        let mut bounding_box = Option::<BoundingBox>::None;
+
+        for line in text.lines() {
+            let line = line.trim();
+            let mut line_parts = line.split_whitespace();
+            match line_parts.next() {
+                Some("v") => {
+                    let [x, y, z] = parse_floats::<3>(&mut line_parts).ok_or(())?;
+                    // Right hand -> left hand coordinate system conversion
+                    positions.push(Vec3::new(-x, y, z));
+                }
+                Some("vt") => {
+                    let [u, v] = parse_floats::<2>(&mut line_parts).ok_or(())?;
+                    // OBJ texture coordinates start at the bottom left
+                    tex_coords.push(Vec2::new(u, 1f32 - v));
+                }
+                Some("vn") => {
+                    let [x, y, z] = parse_floats::<3>(&mut line_parts).ok_or(())?;
+                    normals.push(Vec3::new(-x, y, z).normalize_or_zero());
+                }
+                Some("usemtl") => {
+                    let material = line["usemtl".len()..].trim().to_string();
+                    let part = parts.last_mut().unwrap();
+                    if part.indices.is_empty() {
+                        part.material = Some(material);
+                    } else {
+                        parts.push(ObjPart { material: Some(material), indices: Vec::new() });
+                    }
+                }
+                Some("mtllib") => {
+                    let mtl_path = directory.clone() + line["mtllib".len()..].trim();
+                    let Some(mut mtl_file) = manager.load_file(&mtl_path).await else {
+                        warn!("Could not find material library: {}", &mtl_path);
+                        continue;
+                    };
+                    let mut mtl_text = String::new();
+                    if mtl_file.read_to_string(&mut mtl_text).await.is_err() {
+                        warn!("Could not read material library: {}", &mtl_path);
+                        continue;
+                    }
+                    parse_mtl(&mtl_text, &directory, &mut materials);
+                }
+                Some("f") => {
+                    let mut face = Vec::<u32>::new();
+                    for corner in line_parts {
+                        let mut corner_indices = corner.split('/');
+                        let position_index = resolve_index(corner_indices.next().unwrap_or(""), positions.len()).ok_or(())?;
+                        let tex_coord_index = corner_indices.next().filter(|index| !index.is_empty())
+                            .map(|index| resolve_index(index, tex_coords.len()).ok_or(())).transpose()?;
+                        let normal_index = corner_indices.next().filter(|index| !index.is_empty())
+                            .map(|index| resolve_index(index, normals.len()).ok_or(())).transpose()?;
+
+                        let key = (position_index, tex_coord_index, normal_index);
+                        let vertex_index = *vertex_indices.entry(key).or_insert_with(|| {
+                            let position = positions[position_index];
+                            if let Some(bounding_box) = &mut bounding_box {
+                                bounding_box.min = bounding_box.min.min(position);
+                                bounding_box.max = bounding_box.max.max(position);
+                            } else {
+                                bounding_box = Some(BoundingBox::new(position, position));
+                            }
+                            vertices.push(Vertex {
+                                position,
+                                tex_coord: tex_coord_index.map_or(Vec2::new(0f32, 0f32), |index| tex_coords[index]),
+                                normal: normal_index.map_or(Vec3::new(0f32, 0f32, 0f32), |index| normals[index]),
+                                color: [255, 255, 255, 255],
+                            });
+                            (vertices.len() - 1) as u32
+                        });
+                        face.push(vertex_index);
+                    }
+                    if face.len() < 3 {
+                        warn!("Skipping face with less than 3 vertices in: {}", &path);
+                        continue;
+                    }
+
+                    // Triangulate polygons with a fan, the winding order gets flipped by the coordinate system conversion.
+                    let indices = &mut parts.last_mut().unwrap().indices;
+                    for i in 1..face.len() - 1 {
+                        indices.push(face[0]);
+                        indices.push(face[i + 1]);
+                        indices.push(face[i]);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        parts.retain(|part| !part.indices.is_empty());
+        if vertices.is_empty() || parts.is_empty() {
+            warn!("OBJ file does not contain any faces: {}", &path);
+            return Err(());
+        }
+
+        let mut indices = Vec::<u32>::new();
+        let mut ranges = Vec::<MeshRange>::with_capacity(parts.len());
+        let mut material_paths = Vec::<String>::with_capacity(parts.len());
+        let mut added_materials = HashMap::<String, String>::new();
+        for part in &parts {
+            ranges.push(MeshRange {
+                start: indices.len() as u32,
+                count: part.indices.len() as u32,
+            });
+            indices.extend_from_slice(&part.indices);
+
+            let material_name = part.material.clone().unwrap_or_default();
+            let material_path = added_materials.entry(material_name.clone()).or_insert_with(|| {
+                let material_path = format!("{}/material/{}", &path, &material_name);
+                match materials.get(&material_name) {
+                    Some(ObjMaterial { diffuse_texture: Some(texture), .. }) => {
+                        manager.request_asset(texture, AssetType::Texture, AssetLoadPriority::Low);
+                        manager.add_material_data(&material_path, texture, 1f32, 0f32);
+                    }
+                    Some(ObjMaterial { diffuse_color, .. }) => {
+                        manager.add_material_data_color(&material_path, *diffuse_color, 1f32, 0f32);
+                    }
+                    None => {
+                        if part.material.is_some() {
+                            warn!("Could not find material {} used by: {}", &material_name, &path);
+                        }
+                        manager.add_material_data_color(&material_path, Vec4::new(1f32, 1f32, 1f32, 1f32), 1f32, 0f32);
+                    }
+                }
+                material_path
+            });
+            material_paths.push(material_path.clone());
+        }
+
+        let mesh_path = path.clone() + "/mesh";
+        let vertex_count = vertices.len() as u32;
+        manager.add_asset_data(
+            &mesh_path,
+            AssetData::Mesh(MeshData {
+                indices: Some(into_bytes(indices)),
+                vertices: into_bytes(vertices),
+                parts: ranges.into_boxed_slice(),
+                bounding_box,
+                vertex_count,
+            }),
+            priority,
+        );
+        manager.add_asset_data_with_progress(
+            &path,
+            AssetData::Model(ModelData {
+                mesh_path,
+                material_paths,
+            }),
+            Some(progress),
+            priority,
+        );
+        Ok(())
+    }
+}
@@ -1,7 +1,5 @@
-use std::io::{
-    Seek,
-    SeekFrom,
-};
+use std::collections::HashMap;
+use std::io::Read;
 use std::sync::Arc;
 
 use log::warn;
@@ -9,20 +7,17 @@ use sourcerenderer_core::{
     Platform,
     Vec4,
 };
-use sourcerenderer_vmt::VMTMaterial;
+use sourcerenderer_vmt::{VMTError, VMTMaterial};
 
-use crate::asset::asset_manager::{
-    AssetFile,
+use crate::asset::asset_manager::{AssetFile, AssetLoader};
+use crate::asset::{
+    AssetData,
     AssetLoadPriority,
     AssetLoaderProgress,
-    DirectlyLoadedAsset,
-};
-use crate::asset::{
-    Asset,
-    AssetLoader,
     AssetManager,
     AssetType,
-    Material,
+    MaterialData,
+    MaterialValue,
 };
 
 pub struct VMTMaterialLoader {}
@@ -38,83 +33,125 @@ impl<P: Platform> AssetLoader<P> for VMTMaterialLoader {
         file.path.starts_with("materials/") && file.path.ends_with(".vmt")
     }
 
-    fn load(
+    async fn load(
         &self,
-        mut asset_file: AssetFile,
+        mut file: AssetFile,
         manager: &Arc<AssetManager<P>>,
         priority: AssetLoadPriority,
         progress: &Arc<AssetLoaderProgress>,
     ) -> Result<(), ()> {
-        let path = asset_file.path.clone();
-        let mut vmt_material = {
-            let current = asset_file.seek(SeekFrom::Current(0)).unwrap();
-            let len = asset_file.seek(SeekFrom::End(0)).unwrap();
-            asset_file.seek(SeekFrom::Start(current)).unwrap();
-            VMTMaterial::new(&mut asset_file, len as u32)
-        }
-        .map_err(|_| ())?;
-
-        if vmt_material.is_patch() {
-            let base_path = vmt_material
-                .get_patch_base()
-                .unwrap()
-                .replace('\\', "/")
-                .to_lowercase();
-            let base_file = manager.load_file(&base_path);
-            if base_file.is_none() {
-                return Err(());
-            }
-            let mut base_file = base_file.unwrap();
-            let mut base_material = {
-                let current = base_file.seek(SeekFrom::Current(0)).unwrap();
-                let len = base_file.seek(SeekFrom::End(0)).unwrap();
-                base_file.seek(SeekFrom::Start(current)).unwrap();
-                VMTMaterial::new(&mut base_file, len as u32)
+        let path = file.path.clone();
+        let length = file.data.get_ref().len() as u32;
+        let vmt_material = VMTMaterial::new(&mut file, length)
+            .map_err(|e| warn!("Could not parse material {}: {:?}", &path, e))?;
+
+        // The bases of patch materials have to be loaded asynchronously before the patch can be resolved.
+        // Missing bases and cycles are left to resolve to report.
+        let mut bases = HashMap::<String, Vec<u8>>::new();
+        let mut base_path = vmt_material.patch_base_path();
+        while let Some(current_base_path) = base_path.take() {
+            if bases.contains_key(&current_base_path) {
+                break;
             }
-            .map_err(|_| ())?;
-            base_material.apply_patch(&vmt_material);
-            vmt_material = base_material
+            let Some(mut base_file) = manager.load_file(&current_base_path).await else {
+                break;
+            };
+            let mut data = Vec::<u8>::new();
+            base_file.read_to_end(&mut data).map_err(|_| ())?;
+            base_path = VMTMaterial::new(&mut &data[..], data.len() as u32)
+                .ok()
+                .and_then(|base| base.patch_base_path());
+            bases.insert(current_base_path, data);
         }
 
-        let albedo_opt = vmt_material.get_base_texture_name();
-        if let Some(albedo) = albedo_opt {
-            let albedo_path = "materials/".to_string()
-                + albedo
-                    .to_lowercase()
-                    .replace('\\', "/")
-                    .as_str()
-                    .trim_matches('/')
-                    .trim_end_matches(".vtf")
-                + ".vtf";
-            let material = Material::new_pbr(&albedo_path, 0f32, 0f32);
-
-            manager.request_asset_with_progress(
-                &albedo_path,
-                AssetType::Texture,
-                priority,
-                progress,
-            );
-            manager.add_asset_with_progress(
-                &path,
-                Asset::Material(material),
-                Some(progress),
-                priority,
-            );
-        } else {
-            if vmt_material.get_shader() != sourcerenderer_vmt::SHADER_WATER {
-                warn!("Unsupported material shader: {}", vmt_material.get_shader());
-                return Err(());
+        let vmt_material = vmt_material.resolve(|base_path| bases.get(base_path).cloned())
+            .map_err(|e| warn!("Could not resolve patch material {}: {:?}", &path, e))?;
+
+        if let Err(VMTError::UnsupportedShader(shader_name)) = vmt_material.check_shader_supported() {
+            warn!("Unsupported material shader: {}", shader_name);
+            return Err(());
+        }
+
+        if vmt_material.get_shader() == sourcerenderer_vmt::SHADER_WATER {
+            let mut material = MaterialData {
+                shader_name: "water".to_string(),
+                properties: HashMap::new(),
+            };
+            if let Some(normal_map) = vmt_material.get_value("normalmap") {
+                let normal_map_path = texture_path(normal_map);
+                manager.request_asset_with_progress(
+                    &normal_map_path,
+                    AssetType::Texture,
+                    priority,
+                    progress,
+                );
+                material.properties.insert(
+                    "normalmap".to_string(),
+                    MaterialValue::Texture(normal_map_path),
+                );
             }
+            let reflect_tint = vmt_material
+                .get_vec3("reflecttint")
+                .map(|color| color.extend(1f32))
+                .unwrap_or(Vec4::new(1f32, 1f32, 1f32, 1f32));
+            let refract_tint = vmt_material
+                .get_vec3("refracttint")
+                .map(|color| color.extend(1f32))
+                .unwrap_or(Vec4::new(1f32, 1f32, 1f32, 1f32));
+            let fog_color = vmt_material
+                .get_vec3("fogcolor")
+                .map(|color| color.extend(1f32))
+                .unwrap_or(Vec4::new(0.1f32, 0.15f32, 0.15f32, 1f32));
+            let fog_end = vmt_material
+                .get_f32("fogend")
+                .unwrap_or(200f32);
+            material.properties.insert("reflecttint".to_string(), MaterialValue::Vec4(reflect_tint));
+            material.properties.insert("refracttint".to_string(), MaterialValue::Vec4(refract_tint));
+            material.properties.insert("fogcolor".to_string(), MaterialValue::Vec4(fog_color));
+            material.properties.insert("fogend".to_string(), MaterialValue::Float(fog_end));
 
-            let material = Material::new_pbr_color(Vec4::new(0f32, 0f32, 0f32, 1f32), 0f32, 1f32);
-            manager.add_asset_with_progress(
+            manager.add_asset_data_with_progress(
                 &path,
-                Asset::Material(material),
+                AssetData::Material(material),
                 Some(progress),
                 priority,
             );
+            return Ok(());
+        }
+
+        let albedo_opt = vmt_material.get_base_texture_name();
+        if albedo_opt.is_none() {
+            warn!("Material has no base texture: {}", &path);
+            return Err(());
         }
 
+        let albedo_path = texture_path(albedo_opt.unwrap());
+        let material = MaterialData::new_pbr(&albedo_path, 0f32, 0f32);
+
+        manager.request_asset_with_progress(
+            &albedo_path,
+            AssetType::Texture,
+            priority,
+            progress,
+        );
+        manager.add_asset_data_with_progress(
+            &path,
+            AssetData::Material(material),
+            Some(progress),
+            priority,
+        );
+
         Ok(())
     }
 }
+
+fn texture_path(name: &str) -> String {
+    "materials/".to_string()
+        + name
+            .to_lowercase()
+            .replace('\\', "/")
+            .as_str()
+            .trim_matches('/')
+            .trim_end_matches(".vtf")
+        + ".vtf"
+}
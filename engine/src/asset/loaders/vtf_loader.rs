@@ -1,4 +1,3 @@
-use std::io::BufReader;
 use std::sync::Arc;
 
 use sourcerenderer_core::Platform;
@@ -9,17 +8,9 @@ use sourcerenderer_vtf::{
 
 use crate::graphics::*;
 
-use crate::asset::asset_manager::{
-    AssetFile,
-    AssetLoadPriority,
-    AssetLoaderProgress,
-    DirectlyLoadedAsset,
-    Texture,
-};
+use crate::asset::asset_manager::{AssetFile, AssetLoader};
 use crate::asset::{
-    Asset,
-    AssetLoader,
-    AssetManager,
+    AssetData, AssetLoadPriority, AssetLoaderProgress, AssetManager, TextureData
 };
 
 pub struct VTFTextureLoader {}
@@ -38,7 +29,7 @@ impl<P: Platform> AssetLoader<P> for VTFTextureLoader {
         VtfTexture::<AssetFile>::check_file(file).unwrap_or(false)
     }
 
-    fn load(
+    async fn load(
         &self,
         file: AssetFile,
         manager: &Arc<AssetManager<P>>,
@@ -46,45 +37,53 @@ impl<P: Platform> AssetLoader<P> for VTFTextureLoader {
         progress: &Arc<AssetLoaderProgress>,
     ) -> Result<(), ()> {
         let path = file.path.clone();
-        let mut vtf_texture = VtfTexture::new(BufReader::new(file)).unwrap();
-        let mut data = Vec::<Box<[u8]>>::new();
-        for i in 0..vtf_texture.header().mipmap_count {
-            let reversed_mip = vtf_texture.header().mipmap_count - 1 - i;
-            let mipmap = &vtf_texture.read_mip_map(reversed_mip as u32).unwrap();
-            data.push(mipmap.frames[0].faces[0].slices[0].data.clone());
+        let mut vtf_texture = VtfTexture::new(file).map_err(|_| ())?;
+        let format = convert_vtf_texture_format(vtf_texture.header().high_res_image_format).ok_or(())?;
+        let mip_levels = vtf_texture.mip_count();
+        let (width, height) = vtf_texture.mip_dimensions(0);
+
+        // Mip 0 is the full resolution image, that's the order the texture data expects.
+        let mut data = Vec::<Box<[u8]>>::with_capacity(mip_levels as usize);
+        for mip in 0..mip_levels {
+            let mut mipmap = vtf_texture.read_mip_map(mip).ok_or(())?;
+            data.push(std::mem::take(&mut mipmap.frames[0].faces[0].slices[0].data));
         }
-        let mipmap = &vtf_texture
-            .read_mip_map(vtf_texture.header().mipmap_count as u32 - 1)
-            .unwrap();
-        let texture = Texture {
-            info: TextureInfo {
-                dimension: TextureDimension::Dim2D,
-                format: convert_vtf_texture_format(mipmap.format),
-                width: mipmap.width,
-                height: mipmap.height,
-                depth: 1,
-                mip_levels: vtf_texture.header().mipmap_count as u32,
-                array_length: 1,
-                samples: SampleCount::Samples1,
-                usage: TextureUsage::SAMPLED | TextureUsage::BLIT_DST,
-                supports_srgb: false,
-            },
-            data: data.into_boxed_slice(),
-        };
 
-        manager.add_asset_with_progress(&path, Asset::Texture(texture), Some(progress), priority);
+        manager.add_asset_data_with_progress(
+            &path,
+            AssetData::Texture(TextureData {
+                info: TextureInfo {
+                    dimension: TextureDimension::Dim2D,
+                    format,
+                    width,
+                    height,
+                    depth: 1,
+                    mip_levels,
+                    array_length: 1,
+                    samples: SampleCount::Samples1,
+                    usage: TextureUsage::SAMPLED | TextureUsage::INITIAL_COPY,
+                    supports_srgb: false,
+                },
+                data: data.into_boxed_slice(),
+            }),
+            Some(progress),
+            priority,
+        );
 
         Ok(())
     }
 }
 
-fn convert_vtf_texture_format(texture_format: VTFTextureFormat) -> Format {
+fn convert_vtf_texture_format(texture_format: VTFTextureFormat) -> Option<Format> {
     match texture_format {
-        VTFTextureFormat::DXT1 => Format::BC1,
-        VTFTextureFormat::DXT1OneBitAlpha => Format::BC1Alpha,
-        VTFTextureFormat::DXT3 => Format::BC2,
-        VTFTextureFormat::DXT5 => Format::BC3,
-        VTFTextureFormat::RGBA8888 => Format::RGBA8UNorm,
-        _ => panic!("VTF format {:?} is not supported", texture_format),
+        VTFTextureFormat::DXT1 => Some(Format::BC1),
+        VTFTextureFormat::DXT1OneBitAlpha => Some(Format::BC1Alpha),
+        VTFTextureFormat::DXT3 => Some(Format::BC2),
+        VTFTextureFormat::DXT5 => Some(Format::BC3),
+        VTFTextureFormat::RGBA8888 => Some(Format::RGBA8UNorm),
+        _ => {
+            log::warn!("VTF format {:?} is not supported", texture_format);
+            None
+        }
     }
 }
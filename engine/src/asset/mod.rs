@@ -5,6 +5,7 @@ mod handle_map;
 mod asset_types;
 mod asset_data;
 mod asset_manager_plugin;
+mod fixed_byte_size_cache;
 
 #[derive(Clone, Debug)]
 pub struct Vertex {
@@ -15,6 +16,7 @@ pub struct Vertex {
 }
 
 pub use asset_manager::{
+    AssetLoadFailure,
     AssetLoadPriority,
     AssetLoader,
     AssetLoaderProgress,
@@ -23,6 +25,7 @@ pub use asset_manager::{
 };
 pub use self::asset_types::*;
 pub(crate) use self::handle_map::*;
+pub(crate) use self::fixed_byte_size_cache::FixedByteSizeCache;
 pub use self::asset_data::*;
 pub use self::asset_manager_plugin::*;
 
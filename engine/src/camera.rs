@@ -1,9 +1,22 @@
 use bevy_ecs::{component::Component, entity::Entity, system::Resource};
 
+/// How the camera maps view space to clip space.
+/// Both modes use the same depth convention: z_near maps to 0 and z_far maps to 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// fov is the horizontal field of view in radians.
+    Perspective { fov: f32 },
+    /// height is the vertical extent of the view volume in world units.
+    /// The width is derived from the aspect ratio of the view.
+    Orthographic { height: f32 },
+}
+
 #[derive(Component)]
 pub struct Camera {
-    pub fov: f32,
+    pub projection: Projection,
     pub interpolate_rotation: bool,
+    pub z_near: f32,
+    pub z_far: f32,
 }
 
 #[derive(Resource)]
@@ -1,17 +1,28 @@
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::sync::Arc;
 use std::thread;
 use web_time::Duration;
 
-use bevy_input::keyboard::KeyboardInput;
+use bevy_input::keyboard::{KeyCode, KeyboardInput};
 use bevy_app::*;
+use bevy_ecs::entity::Entity;
 use bevy_ecs::system::Resource;
 use bevy_core::{FrameCountPlugin, TaskPoolPlugin};
+use bevy_input::gamepad::{
+    GamepadAxis,
+    GamepadButton,
+    GamepadConnection,
+    GamepadConnectionEvent,
+    RawGamepadAxisChangedEvent,
+    RawGamepadButtonChangedEvent,
+    RawGamepadEvent,
+};
 use bevy_input::mouse::MouseMotion;
-use bevy_input::InputPlugin;
+use bevy_input::{ButtonState, InputPlugin};
 use bevy_log::LogPlugin;
 use bevy_tasks::{ComputeTaskPool, IoTaskPool};
-use bevy_time::{Fixed, Time, TimePlugin};
+use bevy_time::{Fixed, Time, TimePlugin, Virtual};
 use bevy_transform::TransformPlugin;
 use bevy_hierarchy::HierarchyPlugin;
 
@@ -25,19 +36,28 @@ use sourcerenderer_core::{
     Console,
     Vec2I, Vec2UI,
 };
+use sourcerenderer_core::input::{
+    GamepadAxis as PlatformGamepadAxis,
+    GamepadButton as PlatformGamepadButton,
+    Key as PlatformKey,
+};
 
 use crate::asset::loaders::{
     FSContainer, GltfLoader, ImageLoader, ShaderLoader
 };
 use crate::asset::{AssetContainer, AssetLoader, AssetManager, AssetManagerECSResource, AssetManagerPlugin};
 use crate::graphics::*;
-use crate::input::Input;
+use crate::input::{end_input_map_frame, end_touch_frame, feed_touch_input, Input, InputMapResource, TouchState};
+use crate::metrics::Metrics;
 use crate::renderer::{Renderer, RendererPlugin};
 use crate::transform::InterpolationPlugin;
 
 #[derive(Resource)]
 pub struct ConsoleResource(pub Arc<Console>);
 
+#[derive(Resource)]
+pub struct MetricsResource(pub Arc<Metrics>);
+
 pub enum WindowState {
     Minimized,
     Window(Vec2UI),
@@ -46,23 +66,76 @@ pub enum WindowState {
 
 pub const TICK_RATE: u32 = 5;
 
+/// Settings that need to be known when the engine starts.
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    /// How many fixed updates (gameplay, physics) run per second.
+    /// Rendering runs as fast as the display allows and interpolates transforms between the last two fixed updates.
+    pub tick_rate: f64,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            tick_rate: TICK_RATE as f64,
+        }
+    }
+}
+
 
 #[cfg(all(feature = "threading", target_arch = "wasm32"))]
 compile_error!("Threads are not supported on WebAssembly.");
 
 pub struct Engine{
     app: App,
-    is_running: bool
+    is_running: bool,
+    /// Headless engines don't have a renderer, see `Engine::run_headless`.
+    is_headless: bool,
+    /// Maps the platform gamepad ids to the entities Bevy uses to identify gamepads
+    gamepads: HashMap<u32, Entity>
 }
 
 impl Engine {
     pub fn run<P: Platform, M>(platform: &P, game_plugins: impl Plugins<M>) -> Self {
-        let console = Arc::new(Console::new());
-        let console_resource = ConsoleResource(console);
+        Self::run_with_config(platform, &EngineConfig::default(), game_plugins)
+    }
 
+    pub fn run_with_config<P: Platform, M>(platform: &P, config: &EngineConfig, game_plugins: impl Plugins<M>) -> Self {
         let mut app = App::new();
         initialize_graphics(platform, &mut app);
 
+        Self::add_core_plugins(&mut app, config, Vec2UI::new(platform.window().width(), platform.window().height()));
+        app
+            .add_plugins(AssetManagerPlugin::<P>::default())
+            .add_plugins(RendererPlugin::<P>::new())
+            .add_plugins(game_plugins);
+
+        Self::finish_setup(app, false)
+    }
+
+    /// Runs the engine without a window, a GPU device or a swapchain.
+    /// Meant for automated tests and server side simulation, use `Engine::run_fixed_updates` to step the simulation.
+    ///
+    /// Headless mode does not install the asset manager and the renderer because both need a GPU device.
+    /// That means `AssetManagerECSResource`, `GPUDeviceResource` and `GPUSwapchainResource` don't exist,
+    /// `Engine::get_asset_manager` panics and levels can't be loaded.
+    /// Game plugins that depend on any of those won't work.
+    pub fn run_headless<M>(config: &EngineConfig, game_plugins: impl Plugins<M>) -> Self {
+        let mut app = App::new();
+        Self::add_core_plugins(&mut app, config, Vec2UI::new(0, 0));
+        app.add_plugins(game_plugins);
+        Self::finish_setup(app, true)
+    }
+
+    /// Installs everything that doesn't depend on graphics.
+    fn add_core_plugins(app: &mut App, config: &EngineConfig, screen_size: Vec2UI) {
+        let console = Arc::new(Console::new());
+        let console_resource = ConsoleResource(console);
+        let metrics = Arc::new(Metrics::new());
+        #[cfg(feature = "tracy")]
+        metrics.add_sink(crate::metrics::TracyMetricsSink::new());
+        let metrics_resource = MetricsResource(metrics);
+
         app
             .add_plugins(PanicHandlerPlugin::default());
 
@@ -71,17 +144,22 @@ impl Engine {
 
             app.add_plugins(TaskPoolPlugin::default())
             .add_plugins(TimePlugin::default())
-            .insert_resource(Time::<Fixed>::from_hz(TICK_RATE as f64))
+            .insert_resource(Time::<Fixed>::from_hz(config.tick_rate))
             .add_plugins(FrameCountPlugin::default())
             .add_plugins(TransformPlugin::default())
             .add_plugins(HierarchyPlugin::default())
             .add_plugins(InterpolationPlugin::default())
             .add_plugins(InputPlugin::default())
-            .add_plugins(AssetManagerPlugin::<P>::default())
+            .insert_resource(TouchState::new(screen_size))
+            .add_systems(Last, end_touch_frame)
+            .init_resource::<InputMapResource>()
+            .add_systems(PreUpdate, feed_touch_input)
+            .add_systems(Last, end_input_map_frame)
             .insert_resource(console_resource)
-            .add_plugins(RendererPlugin::<P>::new())
-            .add_plugins(game_plugins);
+            .insert_resource(metrics_resource);
+    }
 
+    fn finish_setup(mut app: App, is_headless: bool) -> Self {
         if app.plugins_state() == PluginsState::Ready {
             app.finish();
             app.cleanup();
@@ -89,10 +167,17 @@ impl Engine {
 
         Self {
             app,
-            is_running: true
+            is_running: true,
+            is_headless,
+            gamepads: HashMap::new()
         }
     }
 
+    pub fn is_headless(&self) -> bool {
+        self.is_headless
+    }
+
+    #[profiling::function]
     pub fn frame(&mut self) {
         if !self.is_running {
             warn!("Frame called after engine was stopped.");
@@ -119,20 +204,194 @@ impl Engine {
         app.update();
     }
 
+    /// Pausing stops the fixed update (and everything else that depends on virtual time) but keeps rendering.
+    /// Interpolated transforms hold their state because the fixed time doesn't advance anymore.
+    pub fn set_paused(&mut self, is_paused: bool) {
+        let mut time = self.app.world_mut().resource_mut::<Time<Virtual>>();
+        if is_paused {
+            time.pause();
+        } else {
+            time.unpause();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.app.world().resource::<Time<Virtual>>().is_paused()
+    }
+
+    /// Runs a single fixed update while paused.
+    pub fn step_once(&mut self) {
+        if !self.is_paused() || self.app.plugins_state() != PluginsState::Cleaned {
+            return;
+        }
+        self.run_fixed_update();
+    }
+
+    /// Runs the given number of fixed updates back to back, independent of the wall clock and of pausing.
+    /// Only the fixed schedules run, so the result only depends on the number of steps.
+    /// This is how headless engines are supposed to advance the simulation.
+    pub fn run_fixed_updates(&mut self, count: u32) {
+        if self.app.plugins_state() == PluginsState::Ready {
+            self.app.finish();
+            self.app.cleanup();
+        }
+        if self.app.plugins_state() != PluginsState::Cleaned {
+            warn!("Fixed updates requested before the plugins are ready.");
+            return;
+        }
+        for _ in 0..count {
+            self.run_fixed_update();
+        }
+    }
+
+    fn run_fixed_update(&mut self) {
+        // Same as the fixed main loop does it, the generic time has to be the fixed one during the fixed update.
+        let world = self.app.world_mut();
+        let mut fixed_time = world.resource_mut::<Time<Fixed>>();
+        let timestep = fixed_time.timestep();
+        fixed_time.advance_by(timestep);
+        let fixed_time = fixed_time.as_generic();
+        *world.resource_mut::<Time>() = fixed_time;
+        world.run_schedule(FixedMain);
+        let virtual_time = world.resource::<Time<Virtual>>().as_generic();
+        *world.resource_mut::<Time>() = virtual_time;
+    }
+
     pub fn is_mouse_locked(&self) -> bool {
         false
         //self.input.poll().mouse_locked()
     }
 
-    pub fn dispatch_keyboard_input(&mut self, input: KeyboardInput) {
+    #[profiling::function]
+    pub fn dispatch_keyboard_input<P: Platform>(&mut self, input: KeyboardInput) {
+        if let Some(key) = platform_key(input.key_code) {
+            let event = match input.state {
+                ButtonState::Pressed => Event::<P>::KeyDown(key),
+                ButtonState::Released => Event::<P>::KeyUp(key),
+            };
+            self.process_input_map_event(&event);
+        }
         self.app.world_mut().send_event(input);
     }
 
+    #[profiling::function]
     pub fn dispatch_mouse_motion(&mut self, motion: MouseMotion) {
         self.app.world_mut().send_event(motion);
     }
 
+    /// Forwards the gamepad events of the platform to Bevy and the InputMap. Any other event gets ignored.
+    #[profiling::function]
+    pub fn dispatch_gamepad_event<P: Platform>(&mut self, event: Event<P>) {
+        self.process_input_map_event(&event);
+        match event {
+            Event::GamepadConnected { gamepad, name } => {
+                let world = self.app.world_mut();
+                let entity = *self.gamepads.entry(gamepad).or_insert_with(|| world.spawn_empty().id());
+                let connection_event = GamepadConnectionEvent::new(entity, GamepadConnection::Connected {
+                    name,
+                    vendor_id: None,
+                    product_id: None
+                });
+                world.send_event(RawGamepadEvent::Connection(connection_event.clone()));
+                world.send_event(connection_event);
+            }
+            Event::GamepadDisconnected(gamepad) => {
+                if let Some(entity) = self.gamepads.remove(&gamepad) {
+                    let connection_event = GamepadConnectionEvent::new(entity, GamepadConnection::Disconnected);
+                    let world = self.app.world_mut();
+                    world.send_event(RawGamepadEvent::Connection(connection_event.clone()));
+                    world.send_event(connection_event);
+                }
+            }
+            Event::GamepadButtonDown { gamepad, button } => {
+                self.send_gamepad_button(gamepad, button, 1f32);
+            }
+            Event::GamepadButtonUp { gamepad, button } => {
+                self.send_gamepad_button(gamepad, button, 0f32);
+            }
+            Event::GamepadAxis { gamepad, axis, value } => {
+                let entity = if let Some(entity) = self.gamepads.get(&gamepad) { *entity } else {
+                    warn!("Received axis event for unknown gamepad {}", gamepad);
+                    return;
+                };
+                let axis = match axis {
+                    PlatformGamepadAxis::LeftStickX => GamepadAxis::LeftStickX,
+                    PlatformGamepadAxis::LeftStickY => GamepadAxis::LeftStickY,
+                    PlatformGamepadAxis::RightStickX => GamepadAxis::RightStickX,
+                    PlatformGamepadAxis::RightStickY => GamepadAxis::RightStickY,
+                    PlatformGamepadAxis::LeftTrigger => GamepadAxis::LeftZ,
+                    PlatformGamepadAxis::RightTrigger => GamepadAxis::RightZ,
+                };
+                let axis_event = RawGamepadAxisChangedEvent::new(entity, axis, value);
+                let world = self.app.world_mut();
+                world.send_event(RawGamepadEvent::Axis(axis_event.clone()));
+                world.send_event(axis_event);
+            }
+            _ => {}
+        }
+    }
+
+    /// Forwards the finger events of the platform to the TouchState resource. Any other event gets ignored.
+    #[profiling::function]
+    pub fn dispatch_touch_event<P: Platform>(&mut self, event: Event<P>) {
+        let mut touch_state = self.app.world_mut().resource_mut::<TouchState>();
+        match event {
+            Event::FingerDown(index) => {
+                touch_state.finger_down(index);
+            }
+            Event::FingerMoved { index, position } => {
+                touch_state.finger_moved(index, position);
+            }
+            Event::FingerUp(index) => {
+                touch_state.finger_up(index);
+            }
+            _ => {}
+        }
+    }
+
+    fn process_input_map_event<P: Platform>(&mut self, event: &Event<P>) {
+        self.app.world_mut().resource_mut::<InputMapResource>().0.process_event(event);
+    }
+
+    fn send_gamepad_button(&mut self, gamepad: u32, button: PlatformGamepadButton, value: f32) {
+        let entity = if let Some(entity) = self.gamepads.get(&gamepad) { *entity } else {
+            warn!("Received button event for unknown gamepad {}", gamepad);
+            return;
+        };
+        let button = match button {
+            PlatformGamepadButton::South => GamepadButton::South,
+            PlatformGamepadButton::East => GamepadButton::East,
+            PlatformGamepadButton::West => GamepadButton::West,
+            PlatformGamepadButton::North => GamepadButton::North,
+            PlatformGamepadButton::Select => GamepadButton::Select,
+            PlatformGamepadButton::Mode => GamepadButton::Mode,
+            PlatformGamepadButton::Start => GamepadButton::Start,
+            PlatformGamepadButton::LeftStick => GamepadButton::LeftThumb,
+            PlatformGamepadButton::RightStick => GamepadButton::RightThumb,
+            PlatformGamepadButton::LeftShoulder => GamepadButton::LeftTrigger,
+            PlatformGamepadButton::RightShoulder => GamepadButton::RightTrigger,
+            PlatformGamepadButton::DPadUp => GamepadButton::DPadUp,
+            PlatformGamepadButton::DPadDown => GamepadButton::DPadDown,
+            PlatformGamepadButton::DPadLeft => GamepadButton::DPadLeft,
+            PlatformGamepadButton::DPadRight => GamepadButton::DPadRight,
+        };
+        let button_event = RawGamepadButtonChangedEvent::new(entity, button, value);
+        let world = self.app.world_mut();
+        world.send_event(RawGamepadEvent::Button(button_event.clone()));
+        world.send_event(button_event);
+    }
+
     pub fn window_changed<P: Platform>(&mut self, window_state: WindowState) {
+        {
+            let mut touch_state = self.app.world_mut().resource_mut::<TouchState>();
+            match &window_state {
+                WindowState::Minimized => touch_state.clear(),
+                WindowState::Window(size) | WindowState::Fullscreen(size) => touch_state.set_screen_size(*size),
+            }
+        }
+        if self.is_headless {
+            return;
+        }
         RendererPlugin::<P>::window_changed(&self.app, window_state);
     }
 
@@ -145,7 +404,9 @@ impl Engine {
             return;
         }
         trace!("Stopping engine");
-        RendererPlugin::<P>::stop(&mut self.app);
+        if !self.is_headless {
+            RendererPlugin::<P>::stop(&mut self.app);
+        }
     }
 
     pub fn debug_world(&self) {
@@ -164,3 +425,19 @@ impl Engine {
         &app.world().resource::<AssetManagerECSResource<P>>().0
     }
 }
+
+/// The InputMap only knows the keys the platform abstraction defines.
+fn platform_key(key_code: KeyCode) -> Option<PlatformKey> {
+    match key_code {
+        KeyCode::KeyW => Some(PlatformKey::W),
+        KeyCode::KeyA => Some(PlatformKey::A),
+        KeyCode::KeyS => Some(PlatformKey::S),
+        KeyCode::KeyD => Some(PlatformKey::D),
+        KeyCode::KeyQ => Some(PlatformKey::Q),
+        KeyCode::KeyE => Some(PlatformKey::E),
+        KeyCode::Space => Some(PlatformKey::Space),
+        KeyCode::ControlLeft => Some(PlatformKey::LCtrl),
+        KeyCode::ShiftLeft => Some(PlatformKey::LShift),
+        _ => None
+    }
+}
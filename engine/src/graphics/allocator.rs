@@ -192,6 +192,12 @@ impl<T> Chunk<T>
     pub fn size(&self) -> u64 {
         self.size
     }
+
+    /// An address that identifies the chunk for as long as it's alive.
+    #[cfg(feature = "tracy")]
+    pub fn address(&self) -> *const std::ffi::c_void {
+        Arc::as_ptr(&self.inner) as *const std::ffi::c_void
+    }
 }
 
 impl<T> Drop for Allocation<T>
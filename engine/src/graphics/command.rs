@@ -81,12 +81,23 @@ pub struct CommandBuffer<B: GPUBackend> {
     destroyer: Arc<DeferredDestroyer<B>>,
     acceleration_structure_scratch: Option<TransientBufferSlice<B>>,
     acceleration_structure_scratch_offset: u64,
-    frame: u64
+    frame: u64,
+    stats: CommandBufferStats
+}
+
+/// Counters of the work that was recorded into a command buffer.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CommandBufferStats {
+    pub draw_calls: u64,
+    /// Indirect draws are not included because their triangle count is only known on the GPU.
+    pub triangles: u64
 }
 
 pub struct CommandBufferRecorder<B: GPUBackend> {
     inner: Box<CommandBuffer<B>>,
     sender: Sender<Box<CommandBuffer<B>>>,
+    timestamps: Option<Arc<FrameTimestamps<B>>>,
+    open_labels: SmallVec<[(String, Option<u32>); 8]>,
     no_send_sync: PhantomData<*mut u8>
 }
 
@@ -95,6 +106,12 @@ pub struct FinishedCommandBuffer<B: GPUBackend> {
     pub(super) sender: Sender<Box<CommandBuffer<B>>>
 }
 
+impl<B: GPUBackend> FinishedCommandBuffer<B> {
+    pub fn stats(&self) -> CommandBufferStats {
+        self.inner.stats
+    }
+}
+
 pub enum BufferRef<'a, B: GPUBackend> {
     Transient(&'a TransientBufferSlice<B>),
     Regular(&'a Arc<BufferSlice<B>>)
@@ -119,10 +136,22 @@ impl<B: GPUBackend> CommandBufferRecorder<B> {
         Self {
             inner: cmd_buffer,
             sender,
+            timestamps: None,
+            open_labels: SmallVec::new(),
             no_send_sync: PhantomData
         }
     }
 
+    /// Measures the GPU time of every label that gets recorded from now on.
+    pub(super) fn set_timestamps(&mut self, timestamps: &Arc<FrameTimestamps<B>>) {
+        if timestamps.take_needs_reset() {
+            unsafe {
+                self.inner.cmd_buffer.reset_queries(timestamps.query_pool(), 0, gpu::QueryPool::query_count(timestamps.query_pool()));
+            }
+        }
+        self.timestamps = Some(timestamps.clone());
+    }
+
     pub fn set_vertex_buffer(&mut self, index: u32, buffer: BufferRef<B>, offset: u64) {
         let buffer_handle: &B::Buffer;
         let buffer_offset: u64;
@@ -189,24 +218,69 @@ impl<B: GPUBackend> CommandBufferRecorder<B> {
     pub fn set_push_constant_data<T>(&mut self, data: &[T], visible_for_shader_stage: ShaderType)
         where T: 'static + Send + Sync + Sized + Clone
     {
+        let bytes = unsafe {
+            std::slice::from_raw_parts(data.as_ptr() as *const u8, std::mem::size_of_val(data))
+        };
+        self.set_push_constants(bytes, visible_for_shader_stage)
+            .expect("Push constant data exceeds the maximum push constant size of the device.");
+    }
+
+    /// Maps to push constants on Vulkan, setBytes on Metal and
+    /// a uniform buffer at binding 0 of the very frequent set on WebGPU.
+    pub fn set_push_constants(&mut self, data: &[u8], visible_for_shader_stage: ShaderType) -> Result<(), PushConstantsTooLargeError> {
+        let max_size = self.inner.device.max_push_constant_size();
+        if data.len() > max_size as usize {
+            return Err(PushConstantsTooLargeError {
+                size: data.len() as u32,
+                max_size
+            });
+        }
         unsafe {
             self.inner.cmd_buffer.set_push_constant_data(data, visible_for_shader_stage);
         }
+        Ok(())
     }
 
     pub fn draw(&mut self, vertices: u32, offset: u32) {
+        self.inner.stats.draw_calls += 1;
+        self.inner.stats.triangles += (vertices / 3) as u64;
         unsafe {
             self.inner.cmd_buffer.draw(vertices, offset);
         }
     }
 
     pub fn draw_indexed(&mut self, instances: u32, first_instance: u32, indices: u32, first_index: u32, vertex_offset: i32) {
+        self.inner.stats.draw_calls += 1;
+        self.inner.stats.triangles += (indices / 3) as u64 * instances as u64;
         unsafe {
             self.inner.cmd_buffer.draw_indexed(instances, first_instance, indices, first_index, vertex_offset);
         }
     }
 
-    pub fn draw_indexed_indirect(&mut self, draw_buffer: BufferRef<B>, draw_buffer_offset: u32, count_buffer: BufferRef<B>, count_buffer_offset: u32, max_draw_count: u32, stride: u32) {
+    pub fn draw_indexed_indirect(&mut self, draw_buffer: BufferRef<B>, draw_buffer_offset: u32, draw_count: u32, stride: u32) {
+        self.inner.stats.draw_calls += draw_count as u64;
+        unsafe {
+            let draw_buffer_handle = match draw_buffer {
+                BufferRef::Regular(b) => b.handle(),
+                BufferRef::Transient(b) => b.handle()
+            };
+            self.inner.cmd_buffer.draw_indexed_indirect(draw_buffer_handle, draw_buffer_offset, draw_count, stride);
+        }
+    }
+
+    pub fn draw_indirect(&mut self, draw_buffer: BufferRef<B>, draw_buffer_offset: u32, draw_count: u32, stride: u32) {
+        self.inner.stats.draw_calls += draw_count as u64;
+        unsafe {
+            let draw_buffer_handle = match draw_buffer {
+                BufferRef::Regular(b) => b.handle(),
+                BufferRef::Transient(b) => b.handle()
+            };
+            self.inner.cmd_buffer.draw_indirect(draw_buffer_handle, draw_buffer_offset, draw_count, stride);
+        }
+    }
+
+    pub fn draw_indexed_indirect_count(&mut self, draw_buffer: BufferRef<B>, draw_buffer_offset: u32, count_buffer: BufferRef<B>, count_buffer_offset: u32, max_draw_count: u32, stride: u32) {
+        self.inner.stats.draw_calls += 1;
         unsafe {
             let draw_buffer_handle = match draw_buffer {
                 BufferRef::Regular(b) => b.handle(),
@@ -216,11 +290,12 @@ impl<B: GPUBackend> CommandBufferRecorder<B> {
                 BufferRef::Regular(b) => b.handle(),
                 BufferRef::Transient(b) => b.handle()
             };
-            self.inner.cmd_buffer.draw_indexed_indirect(draw_buffer_handle, draw_buffer_offset, count_buffer_handle, count_buffer_offset, max_draw_count, stride);
+            self.inner.cmd_buffer.draw_indexed_indirect_count(draw_buffer_handle, draw_buffer_offset, count_buffer_handle, count_buffer_offset, max_draw_count, stride);
         }
     }
 
-    pub fn draw_indirect(&mut self, draw_buffer: BufferRef<B>, draw_buffer_offset: u32, count_buffer: BufferRef<B>, count_buffer_offset: u32, max_draw_count: u32, stride: u32) {
+    pub fn draw_indirect_count(&mut self, draw_buffer: BufferRef<B>, draw_buffer_offset: u32, count_buffer: BufferRef<B>, count_buffer_offset: u32, max_draw_count: u32, stride: u32) {
+        self.inner.stats.draw_calls += 1;
         unsafe {
             let draw_buffer_handle = match draw_buffer {
                 BufferRef::Regular(b) => b.handle(),
@@ -230,7 +305,7 @@ impl<B: GPUBackend> CommandBufferRecorder<B> {
                 BufferRef::Regular(b) => b.handle(),
                 BufferRef::Transient(b) => b.handle()
             };
-            self.inner.cmd_buffer.draw_indirect(draw_buffer_handle, draw_buffer_offset, count_buffer_handle, count_buffer_offset, max_draw_count, stride);
+            self.inner.cmd_buffer.draw_indirect_count(draw_buffer_handle, draw_buffer_offset, count_buffer_handle, count_buffer_offset, max_draw_count, stride);
         }
     }
 
@@ -342,9 +417,29 @@ impl<B: GPUBackend> CommandBufferRecorder<B> {
         unsafe {
             self.inner.cmd_buffer.begin_label(label);
         }
+
+        if let Some(timestamps) = self.timestamps.as_ref() {
+            let query = timestamps.allocate_query();
+            if let Some(query) = query {
+                unsafe {
+                    self.inner.cmd_buffer.write_timestamp(timestamps.query_pool(), query);
+                }
+            }
+            self.open_labels.push((label.to_string(), query));
+        }
     }
 
     pub fn end_label(&mut self) {
+        if let Some(timestamps) = self.timestamps.as_ref() {
+            let (label, begin_query) = self.open_labels.pop().expect("Mismatched end_label call");
+            if let Some((begin_query, end_query)) = begin_query.zip(timestamps.allocate_query()) {
+                unsafe {
+                    self.inner.cmd_buffer.write_timestamp(timestamps.query_pool(), end_query);
+                }
+                timestamps.add_scope(label, begin_query, end_query);
+            }
+        }
+
         unsafe {
             self.inner.cmd_buffer.end_label();
         }
@@ -393,7 +488,8 @@ impl<B: GPUBackend> CommandBufferRecorder<B> {
             self.inner.cmd_buffer.finish();
         }
 
-        let CommandBufferRecorder { inner, sender, no_send_sync: _ } = self;
+        debug_assert!(self.open_labels.is_empty());
+        let CommandBufferRecorder { inner, sender, .. } = self;
         FinishedCommandBuffer { inner, sender }
     }
 
@@ -440,7 +536,7 @@ impl<B: GPUBackend> CommandBufferRecorder<B> {
         }, MemoryUsage::MappableGPUMemory, None)?;
 
         unsafe {
-            let ptr_void = buffer.map(false).unwrap();
+            let ptr_void = buffer.map(false).ok_or(OutOfMemoryError {})?;
 
             if required_size < size {
                 let ptr_u8 = (ptr_void as *mut u8).offset(required_size as isize);
@@ -819,6 +915,8 @@ impl<B: GPUBackend> CommandBufferRecorder<B> {
         std::mem::drop(raw_submissions);
 
         for s in submission.drain(..) {
+            self.inner.stats.draw_calls += s.inner.stats.draw_calls;
+            self.inner.stats.triangles += s.inner.stats.triangles;
             let FinishedCommandBuffer { inner, sender } = s;
             sender.send(inner).expect("Failed to reuse inner command buffer");
         }
@@ -842,7 +940,8 @@ impl<B: GPUBackend> CommandBuffer<B> {
             destroyer: destroyer.clone(),
             acceleration_structure_scratch: None,
             acceleration_structure_scratch_offset: 0u64,
-            frame: 0u64
+            frame: 0u64,
+            stats: CommandBufferStats::default()
         }
     }
 
@@ -860,6 +959,7 @@ impl<B: GPUBackend> CommandBuffer<B> {
         self.acceleration_structure_scratch = None;
         self.acceleration_structure_scratch_offset = 0;
         self.frame = frame;
+        self.stats = CommandBufferStats::default();
     }
 }
 
@@ -890,3 +990,45 @@ pub struct ResolveAttachment<'a, B: GPUBackend> {
     pub render_targets: &'a [RenderTarget<'a, B>],
     pub depth_stencil: Option<&'a DepthStencilAttachment<'a, B>>
   }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn as_bytes<T: Copy>(value: &T) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(value as *const T as *const u8, std::mem::size_of::<T>()) }
+    }
+
+    #[test]
+    fn indirect_draw_buffer_layout() {
+        assert_eq!(std::mem::size_of::<DrawIndirectCommand>(), 16);
+        assert_eq!(std::mem::size_of::<DrawIndexedIndirectCommand>(), 20);
+
+        // The layout the modern renderer uses: the draw count followed by tightly packed draws.
+        let draw = DrawIndexedIndirectCommand {
+            index_count: 36,
+            instance_count: 1,
+            first_index: 6,
+            vertex_offset: -3,
+            first_instance: 2,
+        };
+        let mut buffer = Vec::<u8>::new();
+        buffer.extend_from_slice(&1u32.to_ne_bytes());
+        buffer.extend_from_slice(as_bytes(&draw));
+
+        let draw_buffer_offset = 4usize;
+        let stride = std::mem::size_of::<DrawIndexedIndirectCommand>();
+        // WebGPU requires both to be a multiple of 4.
+        assert_eq!(draw_buffer_offset % 4, 0);
+        assert_eq!(stride % 4, 0);
+        assert_eq!(buffer.len(), draw_buffer_offset + stride);
+
+        let read_u32 = |offset: usize| u32::from_ne_bytes(buffer[offset..offset + 4].try_into().unwrap());
+        assert_eq!(read_u32(0), 1);
+        assert_eq!(read_u32(draw_buffer_offset), 36);
+        assert_eq!(read_u32(draw_buffer_offset + 4), 1);
+        assert_eq!(read_u32(draw_buffer_offset + 8), 6);
+        assert_eq!(read_u32(draw_buffer_offset + 12) as i32, -3);
+        assert_eq!(read_u32(draw_buffer_offset + 16), 2);
+    }
+}
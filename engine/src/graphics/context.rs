@@ -22,6 +22,10 @@ pub struct GraphicsContext<B: GPUBackend> {
   prerendered_frames: u32,
   destroyer: ManuallyDrop<Arc<DeferredDestroyer<B>>>,
   global_buffer_allocator: Arc<BufferAllocator<B>>,
+  timestamps: Option<SmallVec<[Arc<FrameTimestamps<B>>; 5]>>,
+  last_frame_timings: Option<GPUFrameTimings>,
+  #[cfg(feature = "tracy")]
+  tracy_gpu_zones: TracyGPUZones,
 }
 
 pub struct ThreadContext<B: GPUBackend> {
@@ -56,6 +60,10 @@ impl<B: GPUBackend> GraphicsContext<B> {
       thread_contexts: ManuallyDrop::new(ThreadLocal::new()),
       prerendered_frames,
       global_buffer_allocator: buffer_allocator.clone(),
+      timestamps: None,
+      last_frame_timings: None,
+      #[cfg(feature = "tracy")]
+      tracy_gpu_zones: TracyGPUZones::new(),
     }
   }
 
@@ -67,6 +75,14 @@ impl<B: GPUBackend> GraphicsContext<B> {
     if new_frame > self.prerendered_frames as u64 {
       let recycled_frame = new_frame - self.prerendered_frames as u64;
       self.fence.await_value(recycled_frame);
+      if let Some(timestamps) = self.timestamps.as_ref() {
+        let frame_timestamps = &timestamps[recycled_frame as usize % timestamps.len()];
+        self.last_frame_timings = frame_timestamps.resolve();
+        #[cfg(feature = "tracy")]
+        if let Some(timings) = self.last_frame_timings.as_ref() {
+          self.tracy_gpu_zones.record(timings);
+        }
+      }
       self.destroyer.destroy_unused(recycled_frame);
       self.global_buffer_allocator.cleanup_unused();
       self.memory_allocator.cleanup_unused();
@@ -115,6 +131,9 @@ impl<B: GPUBackend> GraphicsContext<B> {
     });
     let mut recorder = CommandBufferRecorder::new(cmd_buffer, frame_context.command_pool.sender.clone());
     recorder.begin(self.current_frame, None);
+    if let Some(timestamps) = self.timestamps.as_ref() {
+      recorder.set_timestamps(&timestamps[self.current_frame as usize % timestamps.len()]);
+    }
     recorder
   }
 
@@ -144,6 +163,35 @@ impl<B: GPUBackend> GraphicsContext<B> {
   pub fn prerendered_frames(&self) -> u32 {
     self.prerendered_frames
   }
+
+  /// Enables measuring the GPU time of every label using timestamp queries.
+  /// Does nothing if the device doesn't support timestamp queries.
+  pub fn set_gpu_timings_enabled(&mut self, enabled: bool) {
+    if !enabled {
+      if self.timestamps.is_some() && self.current_frame > 0 {
+        // The GPU might still write to the query pools.
+        self.fence.await_value(self.completed_frame);
+      }
+      self.timestamps = None;
+      self.last_frame_timings = None;
+      return;
+    }
+    if self.timestamps.is_some() || !self.device.supports_timestamp_queries() {
+      return;
+    }
+    self.timestamps = Some((0..self.prerendered_frames)
+      .map(|i| Arc::new(FrameTimestamps::new(&self.device, i as usize)))
+      .collect());
+  }
+
+  pub fn gpu_timings_enabled(&self) -> bool {
+    self.timestamps.is_some()
+  }
+
+  /// The GPU timings of the most recent frame that has finished executing.
+  pub fn last_frame_timings(&self) -> Option<&GPUFrameTimings> {
+    self.last_frame_timings.as_ref()
+  }
 }
 
 impl<B: GPUBackend> Drop for GraphicsContext<B> {
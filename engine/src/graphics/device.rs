@@ -1,6 +1,6 @@
-use std::{mem::ManuallyDrop, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}};
+use std::{mem::ManuallyDrop, path::{Path, PathBuf}, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}};
 
-use log::trace;
+use log::{info, trace, warn};
 use sourcerenderer_core::gpu::{self, Device as GPUDevice};
 use sourcerenderer_core::gpu::RayTracingPipelineInfo;
 
@@ -18,7 +18,8 @@ pub struct Device<B: GPUBackend> {
     has_context: AtomicBool,
     graphics_queue: Queue<B>,
     compute_queue: Option<Queue<B>>,
-    transfer_queue: Option<Queue<B>>
+    transfer_queue: Option<Queue<B>>,
+    pipeline_cache_path: Mutex<Option<PathBuf>>
 }
 
 impl<B: GPUBackend> Device<B> {
@@ -40,9 +41,27 @@ impl<B: GPUBackend> Device<B> {
             graphics_queue: Queue::new(QueueType::Graphics),
             compute_queue: device.compute_queue().map(|_| Queue::new(QueueType::Compute)),
             transfer_queue: device.compute_queue().map(|_| Queue::new(QueueType::Transfer)),
+            pipeline_cache_path: Mutex::new(None),
         }
     }
 
+    /// Loads the pipeline cache from the given file and writes it back there when the device gets dropped.
+    /// Has to be called before any pipelines are created.
+    pub fn load_pipeline_cache(&self, path: &Path) {
+        match std::fs::read(path) {
+            Ok(data) => {
+                if unsafe { self.device.load_pipeline_cache(&data) } {
+                    info!("Loaded pipeline cache from {:?}", path);
+                } else {
+                    warn!("Ignoring incompatible pipeline cache {:?}", path);
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {},
+            Err(e) => warn!("Failed to read pipeline cache {:?}: {:?}", path, e),
+        }
+        *self.pipeline_cache_path.lock().unwrap() = Some(path.to_path_buf());
+    }
+
     pub fn handle(&self) -> &Arc<B::Device> {
         &self.device
     }
@@ -55,6 +74,31 @@ impl<B: GPUBackend> Device<B> {
         &self.destroyer
     }
 
+    pub fn allocated_memory(&self) -> u64 {
+        self.allocator.allocated_memory()
+    }
+
+    /// Returns the budget of every memory heap.
+    /// Uses the numbers reported by the driver if the backend supports that,
+    /// otherwise the memory allocated by the engine is used as an estimate.
+    pub fn memory_budget(&self) -> Vec<MemoryBudget> {
+        let memory_infos = unsafe { self.device.memory_infos() };
+        memory_infos
+            .iter()
+            .enumerate()
+            .map(|(index, info)| {
+                let allocated = self.allocator.allocated_memory_in_heap(index as u32);
+                let used = (info.total - info.available.min(info.total)).max(allocated);
+                MemoryBudget {
+                    memory_kind: info.memory_kind,
+                    total: info.total,
+                    used,
+                    available: info.total.saturating_sub(used)
+                }
+            })
+            .collect()
+    }
+
     pub fn create_context(&self) -> GraphicsContext<B> {
         trace!("Creating graphics context");
         assert!(!self.has_context.swap(true, Ordering::AcqRel));
@@ -209,6 +253,81 @@ impl<B: GPUBackend> Device<B> {
         self.transfer.try_free_unused_buffers();
     }
 
+    /// Copies the contents of the buffer back to the CPU.
+    /// This submits all pending work on the graphics queue and then stalls until the GPU is done with it,
+    /// so it's meant for tools and debugging, not for anything that runs every frame.
+    /// The buffer needs to have been created with BufferUsage::COPY_SRC.
+    ///
+    /// Returns None if the readback buffer could not be allocated or mapped.
+    /// WebGPU can only map buffers asynchronously so it always returns None there.
+    pub fn read_buffer(&self, buffer: &Arc<BufferSlice<B>>) -> Option<Vec<u8>> {
+        let length = buffer.length();
+        let readback_buffer = self.buffer_allocator.get_slice(&BufferInfo {
+            size: align_up_64(length, 4),
+            usage: BufferUsage::COPY_DST,
+            sharing_mode: QueueSharingMode::Concurrent
+        }, MemoryUsage::MainMemoryCached, Some("Readback")).ok()?;
+
+        // Make sure everything that writes to the buffer is submitted before the copy.
+        self.flush(QueueType::Graphics);
+        let fence_value = self.transfer.read_back_buffer(buffer, &readback_buffer);
+        self.flush_transfers();
+        fence_value.await_signal();
+
+        unsafe {
+            let ptr = readback_buffer.map(true)? as *const u8;
+            let data = std::slice::from_raw_parts(ptr, length as usize).to_vec();
+            readback_buffer.unmap(false);
+            Some(data)
+        }
+    }
+
+    /// Copies the first mip level and array layer of the view back to the CPU.
+    /// Returns the tightly packed pixels, their format, width and height.
+    /// The texture has to be in the given layout and gets transitioned back to it afterwards.
+    /// If bgra_to_rgba is set, BGRA8 data gets swizzled to RGBA8.
+    ///
+    /// Stalls just like read_buffer, it's meant for screenshots and bug reports.
+    pub fn capture_texture(&self, texture: &Arc<super::Texture<B>>, view: &TextureViewInfo, layout: TextureLayout, bgra_to_rgba: bool) -> Option<(Vec<u8>, Format, u32, u32)> {
+        let info = texture.info();
+        let mut format = view.format.unwrap_or(info.format);
+        assert!(!format.is_compressed() && !format.is_depth() && !format.is_stencil());
+        let width = (info.width >> view.base_mip_level).max(1);
+        let height = (info.height >> view.base_mip_level).max(1);
+        let packed_row_pitch = (width * format.element_size()) as u64;
+        // WebGPU needs rows to be aligned to 256 bytes, D3D12 would too.
+        let row_pitch = align_up_64(packed_row_pitch, 256);
+
+        let readback_buffer = self.buffer_allocator.get_slice(&BufferInfo {
+            size: row_pitch * height as u64,
+            usage: BufferUsage::COPY_DST,
+            sharing_mode: QueueSharingMode::Concurrent
+        }, MemoryUsage::MainMemoryCached, Some("TextureReadback")).ok()?;
+
+        self.flush(QueueType::Graphics);
+        let fence_value = self.transfer.read_back_texture(texture, layout, view.base_mip_level, view.base_array_layer, &readback_buffer, row_pitch);
+        self.flush_transfers();
+        fence_value.await_signal();
+
+        let mut data = Vec::with_capacity((packed_row_pitch * height as u64) as usize);
+        unsafe {
+            let ptr = readback_buffer.map(true)? as *const u8;
+            for row in 0..height as u64 {
+                let row_ptr = ptr.offset((row * row_pitch) as isize);
+                data.extend_from_slice(std::slice::from_raw_parts(row_ptr, packed_row_pitch as usize));
+            }
+            readback_buffer.unmap(false);
+        }
+
+        if bgra_to_rgba && format == Format::BGRA8UNorm {
+            for pixel in data.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+            format = Format::RGBA8UNorm;
+        }
+        Some((data, format, width, height))
+    }
+
     pub fn insert_texture_into_bindless_heap(&self, texture: &Arc<super::TextureView<B>>) -> Option<BindlessSlot<B>> {
         if !self.supports_bindless() {
             return None;
@@ -227,6 +346,10 @@ impl<B: GPUBackend> Device<B> {
         self.device.supports_indirect()
     }
 
+    pub fn max_push_constant_size(&self) -> u32 {
+        self.device.max_push_constant_size()
+    }
+
     pub fn supports_bindless(&self) -> bool {
         self.device.supports_bindless()
     }
@@ -235,6 +358,10 @@ impl<B: GPUBackend> Device<B> {
         self.device.supports_barycentrics()
     }
 
+    pub fn supports_timestamp_queries(&self) -> bool {
+        self.device.supports_timestamp_queries()
+    }
+
     pub fn supports_ray_tracing(&self) -> bool {
         self.device.supports_ray_tracing()
     }
@@ -315,6 +442,7 @@ impl<B: GPUBackend> Drop for Device<B> {
             ManuallyDrop::drop(&mut self.buffer_allocator);
             ManuallyDrop::drop(&mut self.transfer);
             self.device.wait_for_idle();
+            self.save_pipeline_cache();
             self.destroyer.destroy_unused(u64::MAX);
             ManuallyDrop::drop(&mut self.destroyer);
             ManuallyDrop::drop(&mut self.allocator);
@@ -322,6 +450,21 @@ impl<B: GPUBackend> Drop for Device<B> {
     }
 }
 
+impl<B: GPUBackend> Device<B> {
+    fn save_pipeline_cache(&self) {
+        let path = self.pipeline_cache_path.lock().unwrap();
+        let (Some(path), Some(data)) = (path.as_ref(), self.device.pipeline_cache_data()) else {
+            return;
+        };
+        // Write to a temporary file first so a crash while writing can't leave a truncated cache behind.
+        let temp_path = path.with_extension("tmp");
+        if let Err(e) = std::fs::write(&temp_path, data).and_then(|_| std::fs::rename(&temp_path, path)) {
+            warn!("Failed to write pipeline cache {:?}: {:?}", path, e);
+            let _ = std::fs::remove_file(&temp_path);
+        }
+    }
+}
+
 fn into_bytes<'a, T>(data: &'a [T]) -> &'a [u8] {
     unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, data.len() * std::mem::size_of::<T>()) }
 }
@@ -0,0 +1,225 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Mutex;
+
+use web_time::Duration;
+
+use sourcerenderer_core::gpu::{self, QueryPool as _};
+
+use super::*;
+
+const QUERIES_PER_FRAME: u32 = 512;
+
+struct TimestampScope {
+    name: String,
+    begin_query: u32,
+    end_query: u32,
+}
+
+/// The timestamp queries of a single frame in flight.
+pub struct FrameTimestamps<B: GPUBackend> {
+    query_pool: B::QueryPool,
+    next_query: AtomicU32,
+    needs_reset: AtomicBool,
+    scopes: Mutex<Vec<TimestampScope>>,
+}
+
+impl<B: GPUBackend> FrameTimestamps<B> {
+    pub(super) fn new(device: &B::Device, index: usize) -> Self {
+        Self {
+            query_pool: unsafe { gpu::Device::create_timestamp_query_pool(device, QUERIES_PER_FRAME, Some(&format!("TimestampQueries{}", index))) },
+            next_query: AtomicU32::new(0),
+            needs_reset: AtomicBool::new(true),
+            scopes: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(super) fn query_pool(&self) -> &B::QueryPool {
+        &self.query_pool
+    }
+
+    /// Returns true exactly once after the queries were recycled.
+    /// The caller has to reset the query pool before writing any timestamps.
+    pub(super) fn take_needs_reset(&self) -> bool {
+        self.needs_reset.swap(false, Ordering::AcqRel)
+    }
+
+    /// Returns None if all queries of the frame are used up.
+    pub(super) fn allocate_query(&self) -> Option<u32> {
+        let query = self.next_query.fetch_add(1, Ordering::Relaxed);
+        (query < self.query_pool.query_count()).then_some(query)
+    }
+
+    pub(super) fn add_scope(&self, name: String, begin_query: u32, end_query: u32) {
+        self.scopes.lock().unwrap().push(TimestampScope {
+            name,
+            begin_query,
+            end_query,
+        });
+    }
+
+    /// Reads the results of the frame and prepares the queries to be used again.
+    /// Must only be called once the GPU is done with the frame.
+    pub(super) fn resolve(&self) -> Option<GPUFrameTimings> {
+        let mut scopes = self.scopes.lock().unwrap();
+        let query_count = self.next_query.swap(0, Ordering::Relaxed).min(self.query_pool.query_count());
+        self.needs_reset.store(true, Ordering::Release);
+        if scopes.is_empty() {
+            return None;
+        }
+
+        let mut timestamps = vec![0u64; query_count as usize];
+        let available = unsafe { self.query_pool.read_timestamps(0, &mut timestamps) };
+        if !available {
+            scopes.clear();
+            return None;
+        }
+
+        let mut frame_begin = u64::MAX;
+        let mut frame_end = 0u64;
+        let mut passes = Vec::with_capacity(scopes.len());
+        let mut pass_timestamps = Vec::with_capacity(scopes.len());
+        for scope in scopes.drain(..) {
+            let begin = timestamps[scope.begin_query as usize];
+            let end = timestamps[scope.end_query as usize];
+            frame_begin = frame_begin.min(begin);
+            frame_end = frame_end.max(end);
+            passes.push((scope.name, Duration::from_nanos(end.saturating_sub(begin))));
+            pass_timestamps.push((begin, end));
+        }
+        Some(GPUFrameTimings {
+            frame_time: Duration::from_nanos(frame_end.saturating_sub(frame_begin)),
+            passes,
+            pass_timestamps,
+        })
+    }
+}
+
+/// GPU durations of a completed frame, measured with timestamp queries around every debug label.
+#[derive(Debug, Clone, Default)]
+pub struct GPUFrameTimings {
+    /// The time between the first and the last timestamp of the frame.
+    pub frame_time: Duration,
+    /// The duration of every label in the order they were ended.
+    pub passes: Vec<(String, Duration)>,
+    /// The raw begin and end timestamps of every label in nanoseconds, in the same order as `passes`.
+    pub pass_timestamps: Vec<(u64, u64)>,
+}
+
+#[cfg(any(feature = "tracy", test))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ZoneEvent {
+    Begin(usize),
+    End(usize),
+}
+
+/// Orders the begin and end events of the passes so they are properly nested.
+/// Tracy requires GPU zones to be opened and closed like a stack, the passes are stored in the order they were ended.
+#[cfg(any(feature = "tracy", test))]
+fn nested_zone_events(pass_timestamps: &[(u64, u64)]) -> Vec<ZoneEvent> {
+    let mut order: Vec<usize> = (0..pass_timestamps.len()).collect();
+    // Outer zones start at the same time or earlier and end later than the ones nested in them.
+    order.sort_by_key(|&index| {
+        let (begin, end) = pass_timestamps[index];
+        (begin, std::cmp::Reverse(end))
+    });
+
+    let mut events = Vec::with_capacity(pass_timestamps.len() * 2);
+    let mut open = Vec::<usize>::new();
+    for index in order {
+        let (begin, _) = pass_timestamps[index];
+        while let Some(&top) = open.last() {
+            if pass_timestamps[top].1 > begin {
+                break;
+            }
+            events.push(ZoneEvent::End(top));
+            open.pop();
+        }
+        events.push(ZoneEvent::Begin(index));
+        open.push(index);
+    }
+    while let Some(top) = open.pop() {
+        events.push(ZoneEvent::End(top));
+    }
+    events
+}
+
+/// Shows the GPU timestamps of every frame as GPU zones in the Tracy timeline.
+#[cfg(feature = "tracy")]
+pub struct TracyGPUZones {
+    context: Option<tracy_client::GpuContext>,
+}
+
+#[cfg(feature = "tracy")]
+impl TracyGPUZones {
+    pub fn new() -> Self {
+        Self {
+            context: None
+        }
+    }
+
+    pub fn record(&mut self, timings: &GPUFrameTimings) {
+        let Some(client) = tracy_client::Client::running() else {
+            return;
+        };
+        let Some(&(first_timestamp, _)) = timings.pass_timestamps.iter().min_by_key(|(begin, _)| *begin) else {
+            return;
+        };
+        if self.context.is_none() {
+            // The timestamps are converted to nanoseconds by the backends, so the period is always 1.
+            // The backend isn't known here, so the context type is left unspecified.
+            self.context = client.new_gpu_context(Some("GPU"), tracy_client::GpuContextType::Invalid, first_timestamp as i64, 1f32).ok();
+        }
+        let Some(context) = self.context.as_ref() else {
+            return;
+        };
+
+        let mut spans: Vec<Option<tracy_client::GpuSpan>> = (0..timings.passes.len()).map(|_| None).collect();
+        for event in nested_zone_events(&timings.pass_timestamps) {
+            match event {
+                ZoneEvent::Begin(index) => {
+                    spans[index] = context.span_alloc(&timings.passes[index].0, "", file!(), line!()).ok();
+                }
+                ZoneEvent::End(index) => {
+                    if let Some(mut span) = spans[index].take() {
+                        let (begin, end) = timings.pass_timestamps[index];
+                        span.end_zone();
+                        span.upload_timestamp_start(begin as i64);
+                        span.upload_timestamp_end(end as i64);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zones_are_nested() {
+        // Stored in the order the labels were ended: two passes inside of a frame label, followed by a separate pass.
+        let pass_timestamps = [(10, 20), (20, 40), (0, 50), (60, 70)];
+        assert_eq!(nested_zone_events(&pass_timestamps), vec![
+            ZoneEvent::Begin(2),
+            ZoneEvent::Begin(0),
+            ZoneEvent::End(0),
+            ZoneEvent::Begin(1),
+            ZoneEvent::End(1),
+            ZoneEvent::End(2),
+            ZoneEvent::Begin(3),
+            ZoneEvent::End(3),
+        ]);
+    }
+
+    #[test]
+    fn zones_with_the_same_begin_are_nested_by_length() {
+        let pass_timestamps = [(0, 10), (0, 30)];
+        assert_eq!(nested_zone_events(&pass_timestamps), vec![
+            ZoneEvent::Begin(1),
+            ZoneEvent::Begin(0),
+            ZoneEvent::End(0),
+            ZoneEvent::End(1),
+        ]);
+    }
+}
@@ -2,10 +2,13 @@ use std::sync::Arc;
 
 use bevy_app::Plugin;
 use bevy_ecs::system::Resource;
-use sourcerenderer_core::{gpu::GPUBackend, platform::Window, Platform};
+use log::info;
+use sourcerenderer_core::{gpu::{AdapterType, ColorSpace, GPUBackend, PresentMode}, platform::{Window, IO}, Platform};
 
 use super::{Device, Instance, Swapchain};
 
+const PIPELINE_CACHE_FILE_NAME: &str = "pipeline_cache.bin";
+
 #[derive(Resource)]
 pub struct GPUDeviceResource<B: GPUBackend>(pub Arc<Device<B>>);
 
@@ -20,10 +23,14 @@ pub(crate) fn initialize_graphics<P: Platform>(platform: &P, app: &mut bevy_app:
 
     let surface = platform.window().create_surface(gpu_instance.handle());
 
-    let gpu_adapters = gpu_instance.list_adapters();
-    let gpu_device = gpu_adapters.first().expect("No suitable GPU found").create_device(&surface);
+    let gpu_adapter = gpu_instance.pick_adapter(AdapterType::Discrete, None).expect("No suitable GPU found");
+    info!("Using GPU: {}", gpu_adapter.name());
+    let gpu_device = gpu_adapter.create_device(&surface);
+    if let Some(cache_dir) = P::IO::cache_dir() {
+        gpu_device.load_pipeline_cache(&cache_dir.join(PIPELINE_CACHE_FILE_NAME));
+    }
 
-    let core_swapchain = platform.window().create_swapchain(true, gpu_device.handle(), surface);
+    let core_swapchain = platform.window().create_swapchain(PresentMode::Fifo, ColorSpace::Srgb, gpu_device.handle(), surface);
     let gpu_swapchain = Swapchain::new(core_swapchain, &gpu_device);
 
     let gpu_resource = GPUDeviceResource::<P::GPUBackend>(gpu_device);
@@ -1,8 +1,11 @@
 use std::sync::{Arc, Weak};
 
+use log::{info, warn};
 use smallvec::SmallVec;
 use sourcerenderer_core::gpu::{GPUBackend, Instance as GPUInstance, AdapterType, Adapter as GPUAdapter};
 
+const ADAPTER_ENV_VAR: &str = "SOURCERENDERER_ADAPTER";
+
 pub struct Instance<B: GPUBackend> {
     instance: Arc<B::Instance>,
     adapters: SmallVec<[Adapter<B>; 2]>
@@ -32,6 +35,34 @@ impl<B: GPUBackend> Instance<B> {
         &self.adapters
     }
 
+    /// Picks the adapter whose name contains `name` if there is one.
+    /// If `name` is None, the env var SOURCERENDERER_ADAPTER is used instead.
+    /// Otherwise it picks an adapter of the preferred type and falls back to discrete GPUs first.
+    pub fn pick_adapter(&self, preference: AdapterType, name: Option<&str>) -> Option<&Adapter<B>> {
+        for adapter in &self.adapters {
+            info!("Found GPU: {} ({:?})", adapter.name(), adapter.adapter_type());
+        }
+
+        let name_override = name.map(|name| name.to_string())
+            .or_else(|| std::env::var(ADAPTER_ENV_VAR).ok())
+            .filter(|name| !name.is_empty());
+        if let Some(name_override) = name_override {
+            let lowercase_name = name_override.to_lowercase();
+            let adapter = self.adapters.iter().find(|adapter| adapter.name().to_lowercase().contains(&lowercase_name));
+            if adapter.is_some() {
+                return adapter;
+            }
+            warn!("Could not find GPU with name: {}", name_override);
+        }
+
+        self.adapters
+            .iter()
+            .min_by_key(|adapter| {
+                let adapter_type = adapter.adapter_type();
+                (adapter_type != preference, adapter_type)
+            })
+    }
+
     pub fn handle(&self) -> &B::Instance {
         &self.instance
     }
@@ -43,6 +74,10 @@ pub struct Adapter<B: GPUBackend> {
 }
 
 impl<B: GPUBackend> Adapter<B> {
+    pub fn name(&self) -> &str {
+        unsafe { (*self.adapter).name() }
+    }
+
     pub fn adapter_type(&self) -> AdapterType {
         unsafe { (*self.adapter).adapter_type() }
     }
@@ -26,8 +26,59 @@ pub(super) struct MemoryAllocatorInner<B: GPUBackend> {
     chunks: HashMap<MemoryTypeIndex, Vec<Chunk<B::Heap>>>
 }
 
+impl<B: GPUBackend> MemoryAllocatorInner<B> {
+    fn allocated_memory(&self) -> u64 {
+        self.chunks
+            .values()
+            .flat_map(|chunks| chunks.iter())
+            .map(|chunk| chunk.size())
+            .sum()
+    }
+}
+
+#[cfg(feature = "tracy")]
+fn plot_allocated_memory<B: GPUBackend>(inner: &MemoryAllocatorInner<B>) {
+    if let Some(client) = tracy_client::Client::running() {
+        client.plot(tracy_client::plot_name!("GPU memory (MiB)"), (inner.allocated_memory() >> 20) as f64);
+    }
+}
+
+#[cfg(feature = "tracy")]
+const TRACY_MEMORY_POOL_NAME: &[u8] = b"GPU memory\0";
+
+/// Shows the allocation of a memory chunk in the memory view of Tracy.
+#[cfg(feature = "tracy")]
+fn emit_chunk_allocated<H: Send + Sync>(chunk: &Chunk<H>) {
+    if tracy_client::Client::running().is_none() {
+        return;
+    }
+    unsafe {
+        tracy_client::sys::___tracy_emit_memory_alloc_named(chunk.address(), chunk.size() as usize, 0, TRACY_MEMORY_POOL_NAME.as_ptr() as *const std::ffi::c_char);
+    }
+}
+
+#[cfg(feature = "tracy")]
+fn emit_chunk_freed<H: Send + Sync>(chunk: &Chunk<H>) {
+    if tracy_client::Client::running().is_none() {
+        return;
+    }
+    unsafe {
+        tracy_client::sys::___tracy_emit_memory_free_named(chunk.address(), 0, TRACY_MEMORY_POOL_NAME.as_ptr() as *const std::ffi::c_char);
+    }
+}
+
 const CHUNK_SIZE: u64 = 256 << 20;
 
+/// The memory budget of a single memory heap.
+#[derive(Debug, Clone)]
+pub struct MemoryBudget {
+    pub memory_kind: MemoryKind,
+    /// The amount of memory the application can use without running into trouble.
+    pub total: u64,
+    pub used: u64,
+    pub available: u64,
+}
+
 pub(super) struct MemoryAllocation<H: Send + Sync> {
     allocation: Allocation<H>,
     memory_usage: MemoryUsage
@@ -78,7 +129,11 @@ impl<B: GPUBackend> MemoryAllocator<B> {
         let heap = heap.unwrap();
         let chunk = Chunk::new(heap, CHUNK_SIZE.max(size));
         let allocation = chunk.allocate(size, alignment).unwrap();
+        #[cfg(feature = "tracy")]
+        emit_chunk_allocated(&chunk);
         chunk_list.push(chunk);
+        #[cfg(feature = "tracy")]
+        plot_allocated_memory(&inner);
         Ok(MemoryAllocation {
             allocation,
             memory_usage: self.memory_usage(memory_type_index)
@@ -187,6 +242,23 @@ impl<B: GPUBackend> MemoryAllocator<B> {
         self.is_uma
     }
 
+    /// The total size of all memory chunks that were allocated from the device.
+    pub(super) fn allocated_memory(&self) -> u64 {
+        let guard = self.inner.lock().unwrap();
+        guard.allocated_memory()
+    }
+
+    pub(super) fn allocated_memory_in_heap(&self, memory_index: u32) -> u64 {
+        let memory_types = unsafe { self.device.memory_type_infos() };
+        let guard = self.inner.lock().unwrap();
+        guard.chunks
+            .iter()
+            .filter(|(memory_type_index, _)| memory_types[**memory_type_index as usize].memory_index == memory_index)
+            .flat_map(|(_, chunks)| chunks.iter())
+            .map(|chunk| chunk.size())
+            .sum()
+    }
+
     pub fn cleanup_unused(&self) {
         let mut guard = self.inner.lock().unwrap();
         for (memory_type, chunks) in guard.chunks.iter_mut() {
@@ -197,11 +269,18 @@ impl<B: GPUBackend> MemoryAllocator<B> {
                     return true;
                 }
                 retained_empty += 1;
-                retained_empty < 2
+                let retain = retained_empty < 2;
+                #[cfg(feature = "tracy")]
+                if !retain {
+                    emit_chunk_freed(b);
+                }
+                retain
             });
             if chunks.len() != chunks_count_before {
                 trace!("Freed {} memory chunks in memory_type {}", chunks_count_before - chunks.len(), memory_type);
             }
         }
+        #[cfg(feature = "tracy")]
+        plot_allocated_memory(&guard);
     }
 }
@@ -18,6 +18,7 @@ pub use instance::*;
 pub use pipeline::*;
 pub use util::*;
 pub use graphics_plugin::*;
+pub use gpu_timings::*;
 
 pub use command::PipelineBinding; // why is this necessary?
 
@@ -41,6 +42,7 @@ mod swapchain;
 mod instance;
 mod util;
 mod graphics_plugin;
+mod gpu_timings;
 
 pub use sourcerenderer_core::gpu::{
     LoadOpColor,
@@ -88,6 +90,8 @@ pub use sourcerenderer_core::gpu::{
     SamplerInfo,
     BarrierTextureRange,
     SwapchainError,
+    PresentMode,
+    ColorSpace,
     InputRate,
     FillMode,
     CullMode,
@@ -97,8 +101,11 @@ pub use sourcerenderer_core::gpu::{
     ColorComponents,
     BindingType,
     OutOfMemoryError,
+    MemoryKind,
     QueueOwnershipTransfer,
     BindingInfo,
     ClearColor,
     ClearDepthStencilValue,
+    DrawIndirectCommand,
+    DrawIndexedIndirectCommand,
 };
@@ -1,7 +1,7 @@
 use std::{collections::HashMap, sync::{Arc, Mutex}};
 
 use smallvec::SmallVec;
-use sourcerenderer_core::{gpu::{Backbuffer, Format, GPUBackend, SampleCount, Swapchain as GPUSwapchain, SwapchainError, TextureViewInfo}, Matrix4};
+use sourcerenderer_core::{gpu::{Backbuffer, ColorSpace, Format, GPUBackend, PresentMode, SampleCount, Swapchain as GPUSwapchain, SwapchainError, TextureViewInfo}, Matrix4};
 
 use super::{DeferredDestroyer, Device};
 
@@ -38,6 +38,22 @@ impl<B: GPUBackend> Swapchain<B> {
         self.recreation_count += 1;
     }
 
+    /// Recreates the swapchain, the backend falls back to the closest supported mode.
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) {
+        unsafe { self.swapchain.set_present_mode(present_mode); }
+        self.views.clear();
+        self.recreation_count += 1;
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        self.swapchain.present_mode()
+    }
+
+    /// The tonemapping pass has to encode its output for this color space.
+    pub fn color_space(&self) -> ColorSpace {
+        self.swapchain.color_space()
+    }
+
     pub fn backbuffer_view(&self, backbuffer: &<B::Swapchain as GPUSwapchain<B>>::Backbuffer) -> &Arc<super::TextureView<B>>{
         self.views.get(&backbuffer.key()).unwrap()
     }
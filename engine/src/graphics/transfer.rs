@@ -48,6 +48,11 @@ enum TransferCopy<B: GPUBackend> {
       dst: Arc<BufferSlice<B>>,
       region: gpu::BufferCopyRegion
   },
+  ImageToBuffer {
+      src: Arc<Texture<B>>,
+      dst: Arc<BufferSlice<B>>,
+      region: gpu::BufferTextureCopyRegion
+  },
 }
 
 struct TransferInner<B: GPUBackend> {
@@ -236,6 +241,146 @@ impl<B: GPUBackend> Transfer<B> {
       guard.graphics.used_buffers_slices.push(dst_buffer.clone());
     }
 
+    /// Records a copy of the entire source buffer into a CPU readable buffer.
+    /// Returns the fence value that gets signalled once the copy is done, the copy only gets submitted with the next flush.
+    pub fn read_back_buffer(
+      &self,
+      src_buffer: &Arc<BufferSlice<B>>,
+      dst_buffer: &Arc<BufferSlice<B>>
+    ) -> SharedFenceValuePair<B> {
+      let length = src_buffer.length().min(dst_buffer.length());
+      debug_assert_ne!(length, 0);
+
+      let mut guard = self.inner.lock().unwrap();
+      guard
+        .graphics
+        .pre_barriers
+        .push(OwnedBarrier::BufferBarrier {
+          old_sync: BarrierSync::all(),
+          new_sync: BarrierSync::COPY,
+          old_access: BarrierAccess::MEMORY_WRITE,
+          new_access: BarrierAccess::COPY_READ,
+          buffer: src_buffer.clone(),
+          offset: src_buffer.offset(),
+          length,
+          queue_ownership: None
+      });
+
+      guard.graphics.copies.push(TransferCopy::BufferToBuffer {
+        src: src_buffer.clone(),
+        dst: dst_buffer.clone(),
+        region: gpu::BufferCopyRegion {
+          src_offset: src_buffer.offset(),
+          dst_offset: dst_buffer.offset(),
+          size: length
+        }
+      });
+
+      guard
+        .graphics
+        .post_barriers
+        .push((None, OwnedBarrier::BufferBarrier {
+          old_sync: BarrierSync::COPY,
+          new_sync: BarrierSync::HOST,
+          old_access: BarrierAccess::COPY_WRITE,
+          new_access: BarrierAccess::HOST_READ,
+          buffer: dst_buffer.clone(),
+          offset: dst_buffer.offset(),
+          length,
+          queue_ownership: None
+      }));
+
+      guard.graphics.used_buffers_slices.push(src_buffer.clone());
+      guard.graphics.used_buffers_slices.push(dst_buffer.clone());
+      guard.graphics.fence_value.clone()
+    }
+
+    /// Records a copy of a single subresource of the texture into a CPU readable buffer.
+    /// The texture gets transitioned back to the given layout afterwards.
+    /// Returns the fence value that gets signalled once the copy is done, the copy only gets submitted with the next flush.
+    pub fn read_back_texture(
+      &self,
+      texture: &Arc<Texture<B>>,
+      layout: TextureLayout,
+      mip_level: u32,
+      array_layer: u32,
+      dst_buffer: &Arc<BufferSlice<B>>,
+      row_pitch: u64
+    ) -> SharedFenceValuePair<B> {
+      let info = texture.info();
+      let width = (info.width >> mip_level).max(1);
+      let height = (info.height >> mip_level).max(1);
+      let range = BarrierTextureRange {
+        base_mip_level: mip_level,
+        mip_level_length: 1,
+        base_array_layer: array_layer,
+        array_layer_length: 1
+      };
+
+      let mut guard = self.inner.lock().unwrap();
+      guard
+        .graphics
+        .pre_barriers
+        .push(OwnedBarrier::TextureBarrier {
+          old_sync: BarrierSync::all(),
+          new_sync: BarrierSync::COPY,
+          old_layout: layout,
+          new_layout: TextureLayout::CopySrc,
+          old_access: BarrierAccess::MEMORY_WRITE,
+          new_access: BarrierAccess::COPY_READ,
+          texture: texture.clone(),
+          range: range.clone(),
+          queue_ownership: None
+      });
+
+      guard.graphics.copies.push(TransferCopy::ImageToBuffer {
+        src: texture.clone(),
+        dst: dst_buffer.clone(),
+        region: gpu::BufferTextureCopyRegion {
+          buffer_offset: dst_buffer.offset(),
+          buffer_row_pitch: row_pitch,
+          buffer_slice_pitch: row_pitch * height as u64,
+          texture_subresource: gpu::TextureSubresource {
+            array_layer, mip_level
+          },
+          texture_offset: Vec3UI::new(0u32, 0u32, 0u32),
+          texture_extent: Vec3UI::new(width, height, 1u32),
+        }
+      });
+
+      guard
+        .graphics
+        .post_barriers
+        .push((None, OwnedBarrier::TextureBarrier {
+          old_sync: BarrierSync::COPY,
+          new_sync: BarrierSync::all(),
+          old_layout: TextureLayout::CopySrc,
+          new_layout: layout,
+          old_access: BarrierAccess::empty(),
+          new_access: BarrierAccess::MEMORY_READ | BarrierAccess::MEMORY_WRITE,
+          texture: texture.clone(),
+          range,
+          queue_ownership: None
+      }));
+      guard
+        .graphics
+        .post_barriers
+        .push((None, OwnedBarrier::BufferBarrier {
+          old_sync: BarrierSync::COPY,
+          new_sync: BarrierSync::HOST,
+          old_access: BarrierAccess::COPY_WRITE,
+          new_access: BarrierAccess::HOST_READ,
+          buffer: dst_buffer.clone(),
+          offset: dst_buffer.offset(),
+          length: row_pitch * height as u64,
+          queue_ownership: None
+      }));
+
+      guard.graphics.used_textures.push(texture.clone());
+      guard.graphics.used_buffers_slices.push(dst_buffer.clone());
+      guard.graphics.fence_value.clone()
+    }
+
     pub fn init_buffer(
       &self,
       data: &[u8],
@@ -630,6 +775,16 @@ impl<B: GPUBackend> Transfer<B> {
                         cmd_buffer.cmd_buffer.copy_buffer_to_texture(src.handle(), dst.handle(), &region);
                     }
                 }
+
+                TransferCopy::ImageToBuffer {
+                    src,
+                    dst,
+                    region
+                } => {
+                    unsafe {
+                        cmd_buffer.cmd_buffer.copy_texture_to_buffer(src.handle(), dst.handle(), &region);
+                    }
+                }
             }
 
             if DEBUG_FORCE_FAT_BARRIER {
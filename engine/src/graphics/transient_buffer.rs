@@ -49,9 +49,17 @@ impl<B: GPUBackend> TransientBufferSlice<B> {
 }
 
 const BUFFER_SIZE: u64 = 65536;
+const MAX_BUFFER_SIZE: u64 = 16 << 20;
 const BUFFER_FULL_GAP_THRESHOLD: u64 = 128;
 const UNIQUE_ALLOCATION_THRESHOLD: u64 = 8192;
 
+/// The size of the buffer that gets added once the existing ones are full.
+/// Grows geometrically, the buffers are kept around after the frame is done
+/// so subsequent frames can fit their data into fewer buffers.
+fn grown_buffer_size(largest_buffer_size: u64, requested_size: u64) -> u64 {
+    BUFFER_SIZE.max((largest_buffer_size * 2).min(MAX_BUFFER_SIZE)).max(requested_size)
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
 struct BufferKey {
     buffer_usage: BufferUsage,
@@ -198,8 +206,10 @@ impl<B: GPUBackend> TransientBufferAllocator<B> {
             return Ok(slice);
         }
 
+        // The frame needs more memory than what's left in the existing buffers.
+        let largest_buffer_size = matching_buffers.buffers.iter().map(|buffer| buffer.size).max().unwrap_or(0);
         let mut new_buffer_info = info.clone();
-        new_buffer_info.size = BUFFER_SIZE.max(info.size);
+        new_buffer_info.size = grown_buffer_size(largest_buffer_size, info.size);
 
         let BufferAndAllocation { buffer, allocation } = BufferAllocator::create_buffer(&self.device, &self.allocator, &new_buffer_info, memory_usage, None)?;
 
@@ -222,6 +232,8 @@ impl<B: GPUBackend> TransientBufferAllocator<B> {
         Ok(slice)
     }
 
+    /// Makes all buffers available again.
+    /// Must only be called once the GPU is done with the frame that used them.
     pub fn reset(&self) {
         let mut inner: AtomicRefMut<'_, TransientBufferAllocatorInner<B>> = self.inner.borrow_mut();
         let retained_gpu_memory = inner.retained_size_gpu_memory.unwrap_or(u64::MAX);
@@ -266,3 +278,33 @@ impl<B: GPUBackend> TransientBufferAllocator<B> {
         inner.retained_size_gpu_memory = gpu_size;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grows_when_a_frame_uploads_more_than_the_buffers_hold() {
+        assert_eq!(grown_buffer_size(0, 256), BUFFER_SIZE);
+
+        // Keep uploading slices until the existing buffers can't fit them anymore.
+        let slice_size = 4096u64;
+        let mut buffer_sizes = vec![grown_buffer_size(0, slice_size)];
+        let mut free = buffer_sizes[0];
+        for _ in 0..(3 * BUFFER_SIZE / slice_size) {
+            if free < slice_size {
+                let largest = buffer_sizes.iter().copied().max().unwrap();
+                let size = grown_buffer_size(largest, slice_size);
+                buffer_sizes.push(size);
+                free = size;
+            }
+            free -= slice_size;
+        }
+        assert_eq!(buffer_sizes, vec![BUFFER_SIZE, BUFFER_SIZE * 2]);
+
+        assert_eq!(grown_buffer_size(MAX_BUFFER_SIZE, slice_size), MAX_BUFFER_SIZE);
+        assert_eq!(grown_buffer_size(MAX_BUFFER_SIZE / 2 + 1, slice_size), MAX_BUFFER_SIZE);
+        // Slices that are larger than the maximum still get a buffer that fits them.
+        assert_eq!(grown_buffer_size(BUFFER_SIZE, MAX_BUFFER_SIZE * 2), MAX_BUFFER_SIZE * 2);
+    }
+}
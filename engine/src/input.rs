@@ -1,12 +1,14 @@
+use std::collections::HashMap;
 use std::sync::Mutex;
 
-use bevy_ecs::system::Resource;
-use sourcerenderer_core::input::Key;
+use bevy_ecs::system::{Res, ResMut, Resource};
+use sourcerenderer_core::input::{InputMap, Key, TouchAxis};
 use sourcerenderer_core::platform::Event;
 use sourcerenderer_core::{
     Platform,
     Vec2,
     Vec2I,
+    Vec2UI,
 };
 
 use crate::bitset_core::BitSet;
@@ -38,6 +40,12 @@ impl Input {
             Event::MouseMoved(position) => {
                 input_guard.mouse_pos = position;
             }
+            Event::MouseButtonDown(button) => {
+                input_guard.mouse_buttons.bit_set(button as usize);
+            }
+            Event::MouseButtonUp(button) => {
+                input_guard.mouse_buttons.bit_reset(button as usize);
+            }
             Event::FingerDown(finger_index) => {
                 input_guard.fingers_down |= 1 << finger_index;
             }
@@ -125,3 +133,154 @@ impl InputState {
         self.finger_pos[finger_index as usize]
     }
 }
+
+/// What a finger on the touch screen is used for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FingerRole {
+    /// Acts like a thumb stick, anchored where the finger first touched the screen.
+    Joystick,
+    /// Rotates the camera.
+    Look,
+    /// Every finger that touches the screen after the joystick and look fingers are taken.
+    Unused,
+}
+
+#[derive(Clone, Debug)]
+struct Finger {
+    /// None until the platform told us where the finger is.
+    role: Option<FingerRole>,
+    start_position: Vec2,
+    position: Vec2,
+}
+
+/// Turns the raw finger events of the platform into a virtual joystick and a look delta
+/// so gameplay code can treat touch input like a thumb stick and mouse motion.
+/// Both get fed into the InputMap as `InputBinding::Touch` axes every frame.
+///
+/// The first finger that touches the left half of the screen becomes the joystick,
+/// the first other finger controls the look direction.
+#[derive(Resource, Clone, Debug)]
+pub struct TouchState {
+    fingers: HashMap<u32, Finger>,
+    screen_size: Vec2UI,
+    look_delta: Vec2,
+}
+
+impl TouchState {
+    /// The distance the joystick finger has to move from its start position for full speed,
+    /// relative to the screen height.
+    const JOYSTICK_RADIUS: f32 = 0.1f32;
+
+    pub fn new(screen_size: Vec2UI) -> Self {
+        Self {
+            fingers: HashMap::new(),
+            screen_size,
+            look_delta: Vec2::new(0f32, 0f32),
+        }
+    }
+
+    pub fn set_screen_size(&mut self, screen_size: Vec2UI) {
+        self.screen_size = screen_size;
+    }
+
+    /// The platform sends the position of a new finger as a separate move event,
+    /// so the finger only gets its role once that arrives.
+    pub fn finger_down(&mut self, index: u32) {
+        self.fingers.insert(index, Finger {
+            role: None,
+            start_position: Vec2::new(0f32, 0f32),
+            position: Vec2::new(0f32, 0f32),
+        });
+    }
+
+    pub fn finger_moved(&mut self, index: u32, position: Vec2) {
+        let has_role = |role: FingerRole| self.fingers.values().any(|finger| finger.role == Some(role));
+        let new_role = if !has_role(FingerRole::Joystick) && position.x < self.screen_size.x as f32 * 0.5f32 {
+            FingerRole::Joystick
+        } else if !has_role(FingerRole::Look) {
+            FingerRole::Look
+        } else {
+            FingerRole::Unused
+        };
+
+        let Some(finger) = self.fingers.get_mut(&index) else {
+            return;
+        };
+        match finger.role {
+            None => {
+                finger.role = Some(new_role);
+                finger.start_position = position;
+            }
+            Some(FingerRole::Look) => {
+                self.look_delta += position - finger.position;
+            }
+            _ => {}
+        }
+        finger.position = position;
+    }
+
+    pub fn finger_up(&mut self, index: u32) {
+        self.fingers.remove(&index);
+    }
+
+    /// Drops all fingers, the platform won't send finger up events for fingers that were down
+    /// while the app lost focus.
+    pub fn clear(&mut self) {
+        self.fingers.clear();
+        self.look_delta = Vec2::new(0f32, 0f32);
+    }
+
+    pub fn is_finger_down(&self, index: u32) -> bool {
+        self.fingers.contains_key(&index)
+    }
+
+    pub fn finger_position(&self, index: u32) -> Option<Vec2> {
+        self.fingers.get(&index).filter(|finger| finger.role.is_some()).map(|finger| finger.position)
+    }
+
+    pub fn active_finger_count(&self) -> usize {
+        self.fingers.len()
+    }
+
+    /// The virtual joystick with x pointing right and y pointing forward.
+    /// The length is at most 1.
+    pub fn movement(&self) -> Vec2 {
+        let Some(finger) = self.fingers.values().find(|finger| finger.role == Some(FingerRole::Joystick)) else {
+            return Vec2::new(0f32, 0f32);
+        };
+        let radius = (self.screen_size.y as f32 * Self::JOYSTICK_RADIUS).max(1f32);
+        let offset = (finger.position - finger.start_position) / radius;
+        // Screen coordinates go down, forward is up.
+        Vec2::new(offset.x, -offset.y).clamp_length_max(1f32)
+    }
+
+    /// The distance in pixels the look finger moved since the last frame.
+    pub fn look_delta(&self) -> Vec2 {
+        self.look_delta
+    }
+
+    pub fn end_frame(&mut self) {
+        self.look_delta = Vec2::new(0f32, 0f32);
+    }
+}
+
+pub(crate) fn end_touch_frame(mut touch_state: ResMut<TouchState>) {
+    touch_state.end_frame();
+}
+
+/// The action bindings of the game, fed with the input events the engine receives from the platform.
+#[derive(Resource, Default)]
+pub struct InputMapResource(pub InputMap);
+
+pub(crate) fn feed_touch_input(touch_state: Res<TouchState>, mut input_map: ResMut<InputMapResource>) {
+    let movement = touch_state.movement();
+    let look_delta = touch_state.look_delta();
+    input_map.0.set_touch_axis(TouchAxis::JoystickX, movement.x);
+    input_map.0.set_touch_axis(TouchAxis::JoystickY, movement.y);
+    input_map.0.set_touch_axis(TouchAxis::LookX, look_delta.x);
+    input_map.0.set_touch_axis(TouchAxis::LookY, look_delta.y);
+}
+
+pub(crate) fn end_input_map_frame(mut input_map: ResMut<InputMapResource>) {
+    input_map.0.end_frame();
+}
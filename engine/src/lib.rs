@@ -23,10 +23,13 @@ extern crate smallvec;
 pub use camera::{
     ActiveCamera,
     Camera,
+    Projection,
 };
 
 pub use self::engine::Engine;
 pub use self::engine::WindowState;
+pub use self::engine::EngineConfig;
+pub use self::input::{InputMapResource, TouchState};
 
 mod engine;
 
@@ -34,10 +37,12 @@ pub mod asset;
 pub mod camera;
 pub mod fps_camera;
 pub mod math;
+pub mod metrics;
 mod spinning_cube;
 pub mod transform;
 
 mod input;
+// Disabled until it's ported from legion to bevy_ecs, legion isn't a dependency anymore.
 //mod physics;
 pub mod renderer;
 mod ui;
@@ -3,7 +3,6 @@ use std::marker::PhantomData;
 use sourcerenderer_core::{
     Matrix4,
     Vec3,
-    Vec4,
 };
 
 #[derive(Debug, Clone)]
@@ -25,13 +24,54 @@ impl BoundingBox {
         }
     }
 
+    /// Computes the axis aligned box that encloses the box transformed by the given matrix.
     pub fn transform(&self, matrix: &Matrix4) -> BoundingBox {
-        let a = *matrix * Vec4::new(self.min.x, self.min.y, self.min.z, 1f32);
-        let b = *matrix * Vec4::new(self.max.x, self.max.y, self.max.z, 1f32);
-        BoundingBox::new(
-            Vec3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
-            Vec3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
-        )
+        // Arvo, "Transforming Axis-Aligned Bounding Boxes", Graphics Gems
+        let translation = matrix.col(3);
+        let mut min = Vec3::new(translation.x, translation.y, translation.z);
+        let mut max = min;
+        for column in 0..3 {
+            let axis = matrix.col(column);
+            let axis = Vec3::new(axis.x, axis.y, axis.z);
+            let a = axis * self.min[column];
+            let b = axis * self.max[column];
+            min += a.min(b);
+            max += a.max(b);
+        }
+        BoundingBox::new(min, max)
+    }
+
+    /// Returns the smallest box that encloses both boxes.
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5f32
+    }
+
+    /// Half the size of the box along each axis.
+    pub fn half_extents(&self) -> Vec3 {
+        (self.max - self.min) * 0.5f32
+    }
+
+    /// Like contains but the max corner is part of the box too.
+    pub fn contains_point(&self, point: &Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min.cmple(other.max).all() && other.min.cmple(self.max).all()
+    }
+
+    pub fn intersects_sphere(&self, center: &Vec3, radius: f32) -> bool {
+        let closest_point = center.clamp(self.min, self.max);
+        closest_point.distance_squared(*center) <= radius * radius
+    }
+
+    /// Distance from the point to the closest point of the box, 0 if the point is inside of it.
+    pub fn distance(&self, point: &Vec3) -> f32 {
+        point.clamp(self.min, self.max).distance(*point)
     }
 
     pub fn contains(&self, point: &Vec3) -> bool {
@@ -54,3 +94,51 @@ impl BoundingBox {
         bb
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use sourcerenderer_core::{Quaternion, Vec4};
+
+    use super::*;
+
+    #[test]
+    fn transform_matches_corners() {
+        let bounding_box = BoundingBox::new(Vec3::new(-1f32, 2f32, -3f32), Vec3::new(4f32, 5f32, 0.5f32));
+        let matrices = [
+            Matrix4::IDENTITY,
+            Matrix4::from_translation(Vec3::new(10f32, -2f32, 3f32)),
+            Matrix4::from_scale_rotation_translation(
+                Vec3::new(2f32, 0.5f32, 3f32),
+                Quaternion::from_euler(sourcerenderer_core::EulerRot::XYZ, 0.3f32, -1.2f32, 2.5f32),
+                Vec3::new(-7f32, 1f32, 4f32),
+            ),
+            Matrix4::from_scale(Vec3::new(-1f32, 1f32, -2f32)),
+        ];
+        for matrix in &matrices {
+            let mut min = Vec3::splat(f32::MAX);
+            let mut max = Vec3::splat(f32::MIN);
+            for i in 0..8 {
+                let corner = Vec3::new(
+                    if i & 1 == 0 { bounding_box.min.x } else { bounding_box.max.x },
+                    if i & 2 == 0 { bounding_box.min.y } else { bounding_box.max.y },
+                    if i & 4 == 0 { bounding_box.min.z } else { bounding_box.max.z },
+                );
+                let transformed = *matrix * Vec4::new(corner.x, corner.y, corner.z, 1f32);
+                min = min.min(transformed.truncate());
+                max = max.max(transformed.truncate());
+            }
+
+            let transformed = bounding_box.transform(matrix);
+            assert!(transformed.min.abs_diff_eq(min, 0.0001f32), "{:?} != {:?}", transformed.min, min);
+            assert!(transformed.max.abs_diff_eq(max, 0.0001f32), "{:?} != {:?}", transformed.max, max);
+        }
+    }
+
+    #[test]
+    fn distance() {
+        let bounding_box = BoundingBox::new(Vec3::new(-1f32, -1f32, -1f32), Vec3::new(1f32, 1f32, 1f32));
+        assert_eq!(bounding_box.distance(&Vec3::new(0.5f32, 0f32, -0.5f32)), 0f32);
+        assert_eq!(bounding_box.distance(&Vec3::new(4f32, 0f32, 0f32)), 3f32);
+        assert_eq!(bounding_box.distance(&Vec3::new(4f32, 5f32, 0f32)), 5f32);
+    }
+}
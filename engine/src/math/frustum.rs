@@ -1,11 +1,11 @@
-use bevy_math::Vec4Swizzles;
+use bevy_math::{Affine3A, Vec4Swizzles};
 use sourcerenderer_core::{
     Matrix4,
     Vec3,
     Vec4,
 };
 
-use super::BoundingBox;
+use super::{BoundingBox, Plane};
 
 struct OrientedBoundingBox {
     center: Vec3,
@@ -302,6 +302,73 @@ impl Frustum {
     }
 }
 
+/// The six planes of a view frustum with the normals pointing inwards.
+#[derive(Debug, Clone)]
+pub struct FrustumPlanes {
+    planes: [Plane; 6],
+}
+
+impl FrustumPlanes {
+    /// Extracts the planes from a view projection matrix with a depth range of 0 to 1.
+    /// With a view projection matrix the planes are in world space.
+    pub fn from_view_proj(view_proj: &Matrix4) -> Self {
+        // http://www.cs.otago.ac.nz/postgrads/alexis/planeExtraction.pdf
+        let row_0 = view_proj.row(0);
+        let row_1 = view_proj.row(1);
+        let row_2 = view_proj.row(2);
+        let row_3 = view_proj.row(3);
+        Self {
+            planes: [
+                Plane::from_vec4(row_3 + row_0), // left
+                Plane::from_vec4(row_3 - row_0), // right
+                Plane::from_vec4(row_3 + row_1), // bottom
+                Plane::from_vec4(row_3 - row_1), // top
+                Plane::from_vec4(row_2), // near
+                Plane::from_vec4(row_3 - row_2), // far
+            ],
+        }
+    }
+
+    /// The planes in the order left, right, bottom, top, near, far.
+    pub fn planes(&self) -> &[Plane; 6] {
+        &self.planes
+    }
+
+    pub fn contains_point(&self, point: &Vec3) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(point) >= 0f32)
+    }
+
+    /// Conservative, spheres close to the corners of the frustum can be reported as visible.
+    pub fn intersects_sphere(&self, center: &Vec3, radius: f32) -> bool {
+        self.planes.iter().all(|plane| plane.intersects_sphere(center, radius))
+    }
+
+    /// Tests the bounding box transformed by the given matrix against the planes.
+    /// Conservative, boxes close to the corners of the frustum can be reported as visible.
+    pub fn intersects(&self, bounding_box: &BoundingBox, transform: &Affine3A) -> bool {
+        let local_center = (bounding_box.min + bounding_box.max) * 0.5f32;
+        let local_extents = (bounding_box.max - bounding_box.min) * 0.5f32;
+
+        // Axis aligned box in world space that encloses the transformed box
+        let center = transform.transform_point3(local_center);
+        let matrix = transform.matrix3;
+        let extents = Vec3::new(
+            Vec3::from(matrix.row(0)).abs().dot(local_extents),
+            Vec3::from(matrix.row(1)).abs().dot(local_extents),
+            Vec3::from(matrix.row(2)).abs().dot(local_extents),
+        );
+
+        for plane in &self.planes {
+            let distance = plane.signed_distance(&center);
+            let radius = plane.normal.abs().dot(extents);
+            if distance + radius < 0f32 {
+                return false;
+            }
+        }
+        true
+    }
+}
+
 fn normalize_plane(p: Vec4) -> Vec4 {
     p / p.xyz().length()
 }
@@ -310,3 +377,76 @@ fn normalize_plane(p: Vec4) -> Vec4 {
 // https://bruop.github.io/improved_frustum_culling/
 // http://davidlively.com/programming/graphics/frustum-calculation-and-culling-hopefully-demystified/
 // https://gist.github.com/BruOp/60e862049ac6409d2fd4ec6fa5806b30
+
+#[cfg(test)]
+mod tests {
+    use bevy_math::Quat;
+
+    use super::*;
+
+    /// 90 degree field of view looking down +Z from the origin, near plane at 1 and far plane at 100.
+    fn frustum_planes() -> FrustumPlanes {
+        let proj = Matrix4::perspective_lh(90f32.to_radians(), 1f32, 1f32, 100f32);
+        let view = Matrix4::look_at_lh(Vec3::ZERO, Vec3::Z, Vec3::Y);
+        FrustumPlanes::from_view_proj(&(proj * view))
+    }
+
+    #[test]
+    fn planes() {
+        let frustum = frustum_planes();
+        let epsilon = 0.0001f32;
+        let expected = [
+            Vec3::new(1f32, 0f32, 1f32).normalize(),
+            Vec3::new(-1f32, 0f32, 1f32).normalize(),
+            Vec3::new(0f32, 1f32, 1f32).normalize(),
+            Vec3::new(0f32, -1f32, 1f32).normalize(),
+            Vec3::Z,
+            -Vec3::Z,
+        ];
+        for (plane, normal) in frustum.planes().iter().zip(expected.iter()) {
+            assert!(plane.normal.abs_diff_eq(*normal, epsilon), "{:?} != {:?}", plane.normal, normal);
+        }
+        assert!((frustum.planes()[4].distance + 1f32).abs() < epsilon);
+        // The far plane loses precision because it's the difference of two almost equal rows
+        assert!((frustum.planes()[5].distance - 100f32).abs() < 0.01f32);
+
+        assert!(frustum.contains_point(&Vec3::new(0f32, 0f32, 10f32)));
+        assert!(frustum.contains_point(&Vec3::new(9f32, -9f32, 10f32)));
+        assert!(!frustum.contains_point(&Vec3::new(11f32, 0f32, 10f32)));
+        assert!(!frustum.contains_point(&Vec3::new(0f32, 0f32, 0.5f32)));
+        assert!(!frustum.contains_point(&Vec3::new(0f32, 0f32, 101f32)));
+        assert!(!frustum.contains_point(&Vec3::new(0f32, 0f32, -10f32)));
+    }
+
+    #[test]
+    fn spheres() {
+        let frustum = frustum_planes();
+        assert!(frustum.intersects_sphere(&Vec3::new(0f32, 0f32, 50f32), 1f32));
+        // 2 / sqrt(2) units outside of the right plane
+        assert!(!frustum.intersects_sphere(&Vec3::new(12f32, 0f32, 10f32), 1f32));
+        assert!(frustum.intersects_sphere(&Vec3::new(12f32, 0f32, 10f32), 2f32));
+        assert!(!frustum.intersects_sphere(&Vec3::new(0f32, 0f32, -5f32), 4f32));
+        assert!(frustum.intersects_sphere(&Vec3::new(0f32, 0f32, -5f32), 6.5f32));
+        assert!(!frustum.intersects_sphere(&Vec3::new(0f32, 0f32, 105f32), 4f32));
+    }
+
+    #[test]
+    fn boxes() {
+        let frustum = frustum_planes();
+        let unit_box = BoundingBox::new(Vec3::splat(-1f32), Vec3::splat(1f32));
+        assert!(frustum.intersects(&unit_box, &Affine3A::from_translation(Vec3::new(0f32, 0f32, 10f32))));
+        assert!(frustum.intersects(&unit_box, &Affine3A::from_translation(Vec3::new(10.5f32, 0f32, 10f32))));
+        assert!(!frustum.intersects(&unit_box, &Affine3A::from_translation(Vec3::new(12.5f32, 0f32, 10f32))));
+        assert!(!frustum.intersects(&unit_box, &Affine3A::from_translation(Vec3::new(0f32, 0f32, -10f32))));
+        assert!(!frustum.intersects(&unit_box, &Affine3A::from_translation(Vec3::new(0f32, 0f32, 102f32))));
+        // Scaling the box up makes it reach into the frustum
+        assert!(frustum.intersects(&unit_box, &Affine3A::from_scale_rotation_translation(Vec3::new(3f32, 1f32, 1f32), Quat::IDENTITY, Vec3::new(12.5f32, 0f32, 10f32))));
+
+        // A long box that points along +X, rotated around Y so it points towards or away from the camera
+        let long_box = BoundingBox::new(Vec3::new(0f32, -1f32, -1f32), Vec3::new(10f32, 1f32, 1f32));
+        let translation = Vec3::new(0f32, 0f32, -5f32);
+        assert!(!frustum.intersects(&long_box, &Affine3A::from_translation(translation)));
+        assert!(frustum.intersects(&long_box, &Affine3A::from_rotation_translation(Quat::from_rotation_y(-90f32.to_radians()), translation)));
+        assert!(!frustum.intersects(&long_box, &Affine3A::from_rotation_translation(Quat::from_rotation_y(90f32.to_radians()), translation)));
+    }
+}
@@ -1,4 +1,6 @@
 mod bounding_box;
 pub use self::bounding_box::*;
 mod frustum;
-pub use self::frustum::Frustum;
+pub use self::frustum::{Frustum, FrustumPlanes};
+mod plane;
+pub use self::plane::Plane;
@@ -0,0 +1,96 @@
+use bevy_math::Vec4Swizzles;
+use sourcerenderer_core::{
+    Vec3,
+    Vec4,
+};
+
+use super::BoundingBox;
+
+/// A plane defined by dot(normal, point) + distance = 0.
+/// Points on the side the normal points at have a positive signed distance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+impl Plane {
+    pub fn new(normal: Vec3, distance: f32) -> Self {
+        Self { normal, distance }
+    }
+
+    pub fn from_point_normal(point: &Vec3, normal: &Vec3) -> Self {
+        let normal = normal.normalize();
+        Self {
+            normal,
+            distance: -normal.dot(*point),
+        }
+    }
+
+    /// Creates a plane from the coefficients (a, b, c, d) of ax + by + cz + d = 0 and normalizes it.
+    pub fn from_vec4(coefficients: Vec4) -> Self {
+        let length = coefficients.xyz().length();
+        Self {
+            normal: coefficients.xyz() / length,
+            distance: coefficients.w / length,
+        }
+    }
+
+    pub fn to_vec4(&self) -> Vec4 {
+        Vec4::new(self.normal.x, self.normal.y, self.normal.z, self.distance)
+    }
+
+    pub fn signed_distance(&self, point: &Vec3) -> f32 {
+        self.normal.dot(*point) + self.distance
+    }
+
+    /// Returns whether any part of the sphere is on the positive side of the plane.
+    pub fn intersects_sphere(&self, center: &Vec3, radius: f32) -> bool {
+        self.signed_distance(center) >= -radius
+    }
+
+    /// Returns whether any part of the box is on the positive side of the plane.
+    pub fn intersects_bounding_box(&self, bounding_box: &BoundingBox) -> bool {
+        let radius = self.normal.abs().dot(bounding_box.half_extents());
+        self.signed_distance(&bounding_box.center()) >= -radius
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_distance() {
+        let plane = Plane::from_point_normal(&Vec3::new(0f32, 2f32, 0f32), &Vec3::new(0f32, 4f32, 0f32));
+        assert_eq!(plane.normal, Vec3::Y);
+        assert_eq!(plane.distance, -2f32);
+        assert_eq!(plane.signed_distance(&Vec3::new(5f32, 3f32, -1f32)), 1f32);
+        assert_eq!(plane.signed_distance(&Vec3::new(0f32, -1f32, 0f32)), -3f32);
+
+        let plane = Plane::from_vec4(Vec4::new(0f32, 0f32, 2f32, -4f32));
+        assert_eq!(plane, Plane::new(Vec3::Z, -2f32));
+        assert_eq!(plane.to_vec4(), Vec4::new(0f32, 0f32, 1f32, -2f32));
+    }
+
+    #[test]
+    fn sphere() {
+        let plane = Plane::new(Vec3::X, 0f32);
+        assert!(plane.intersects_sphere(&Vec3::new(3f32, 0f32, 0f32), 1f32));
+        assert!(plane.intersects_sphere(&Vec3::new(-0.5f32, 0f32, 0f32), 1f32));
+        assert!(plane.intersects_sphere(&Vec3::new(-1f32, 0f32, 0f32), 1f32));
+        assert!(!plane.intersects_sphere(&Vec3::new(-1.5f32, 0f32, 0f32), 1f32));
+    }
+
+    #[test]
+    fn bounding_box() {
+        let plane = Plane::from_point_normal(&Vec3::ZERO, &Vec3::new(1f32, 1f32, 0f32));
+        let bounding_box = BoundingBox::new(Vec3::new(-3f32, -3f32, -1f32), Vec3::new(-1f32, -1f32, 1f32));
+        assert!(!plane.intersects_bounding_box(&bounding_box));
+        // The max corner touches the plane
+        let bounding_box = BoundingBox::new(Vec3::new(-2f32, -2f32, -1f32), Vec3::new(0f32, 0f32, 1f32));
+        assert!(plane.intersects_bounding_box(&bounding_box));
+        let bounding_box = BoundingBox::new(Vec3::new(-3f32, 0.5f32, -1f32), Vec3::new(-1f32, 2.5f32, 1f32));
+        assert!(plane.intersects_bounding_box(&bounding_box));
+    }
+}
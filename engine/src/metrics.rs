@@ -0,0 +1,168 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use log::info;
+use web_time::Duration;
+
+/// The metrics of a single frame.
+/// This is the schema every sink receives, so overlays and external tools agree on
+/// what a value means.
+#[derive(Clone, Debug, Default)]
+pub struct FrameMetrics {
+    pub frame: u64,
+    /// Time the renderer spent between the start of the previous frame and the start of this one.
+    pub cpu_frame_time: Duration,
+    /// None if the backend does not provide GPU timestamps or GPU timings are disabled.
+    pub gpu_frame_time: Option<Duration>,
+    /// GPU time of every labeled pass, empty if GPU timings are disabled.
+    pub gpu_pass_times: Vec<(String, Duration)>,
+    pub draw_calls: u64,
+    pub triangles: u64,
+    /// Drawables of the active view that passed culling.
+    pub visible_drawables: u64,
+    /// Drawables of the active view that were rejected by culling.
+    pub culled_drawables: u64,
+    /// Bytes of GPU memory that are currently allocated from the device.
+    pub gpu_memory_allocated: u64,
+}
+
+/// Receives the metrics at the end of every frame.
+pub trait MetricsSink: Send + Sync {
+    fn record(&self, metrics: &FrameMetrics);
+}
+
+/// Collects the counters the engine populates over the course of a frame
+/// and hands them to all registered sinks once the frame is done.
+pub struct Metrics {
+    draw_calls: AtomicU64,
+    triangles: AtomicU64,
+    visible_drawables: AtomicU64,
+    culled_drawables: AtomicU64,
+    gpu_memory_allocated: AtomicU64,
+    sinks: Mutex<Vec<Box<dyn MetricsSink>>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            draw_calls: AtomicU64::new(0),
+            triangles: AtomicU64::new(0),
+            visible_drawables: AtomicU64::new(0),
+            culled_drawables: AtomicU64::new(0),
+            gpu_memory_allocated: AtomicU64::new(0),
+            sinks: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn add_sink(&self, sink: impl MetricsSink + 'static) {
+        self.sinks.lock().unwrap().push(Box::new(sink));
+    }
+
+    pub fn add_draw_calls(&self, draw_calls: u64, triangles: u64) {
+        self.draw_calls.fetch_add(draw_calls, Ordering::Relaxed);
+        self.triangles.fetch_add(triangles, Ordering::Relaxed);
+    }
+
+    pub fn set_culling_results(&self, visible_drawables: u64, culled_drawables: u64) {
+        self.visible_drawables.store(visible_drawables, Ordering::Relaxed);
+        self.culled_drawables.store(culled_drawables, Ordering::Relaxed);
+    }
+
+    pub fn set_gpu_memory_allocated(&self, bytes: u64) {
+        self.gpu_memory_allocated.store(bytes, Ordering::Relaxed);
+    }
+
+    /// Resets the per-frame counters and sends the collected values to all sinks.
+    pub fn end_frame(&self, frame: u64, cpu_frame_time: Duration, gpu_frame_time: Option<Duration>, gpu_pass_times: &[(String, Duration)]) -> FrameMetrics {
+        let metrics = FrameMetrics {
+            frame,
+            cpu_frame_time,
+            gpu_frame_time,
+            gpu_pass_times: gpu_pass_times.to_vec(),
+            draw_calls: self.draw_calls.swap(0, Ordering::Relaxed),
+            triangles: self.triangles.swap(0, Ordering::Relaxed),
+            visible_drawables: self.visible_drawables.swap(0, Ordering::Relaxed),
+            culled_drawables: self.culled_drawables.swap(0, Ordering::Relaxed),
+            gpu_memory_allocated: self.gpu_memory_allocated.load(Ordering::Relaxed),
+        };
+
+        let sinks = self.sinks.lock().unwrap();
+        for sink in sinks.iter() {
+            sink.record(&metrics);
+        }
+        metrics
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writes the metrics to the log every `interval` frames.
+pub struct LogMetricsSink {
+    interval: u64,
+}
+
+impl LogMetricsSink {
+    pub fn new(interval: u64) -> Self {
+        Self {
+            interval: interval.max(1),
+        }
+    }
+}
+
+impl MetricsSink for LogMetricsSink {
+    fn record(&self, metrics: &FrameMetrics) {
+        if metrics.frame % self.interval != 0 {
+            return;
+        }
+        info!(
+            "Frame {}: CPU {:.2}ms, GPU {}, {} draws, {} triangles, {} visible, {} culled, {} MiB GPU memory",
+            metrics.frame,
+            metrics.cpu_frame_time.as_secs_f64() * 1000f64,
+            metrics.gpu_frame_time
+                .map(|time| format!("{:.2}ms", time.as_secs_f64() * 1000f64))
+                .unwrap_or_else(|| "n/a".to_string()),
+            metrics.draw_calls,
+            metrics.triangles,
+            metrics.visible_drawables,
+            metrics.culled_drawables,
+            metrics.gpu_memory_allocated >> 20
+        );
+        for (pass, time) in &metrics.gpu_pass_times {
+            info!("    {}: {:.2}ms", pass, time.as_secs_f64() * 1000f64);
+        }
+    }
+}
+
+/// Plots the metrics in the Tracy timeline.
+#[cfg(feature = "tracy")]
+pub struct TracyMetricsSink {}
+
+#[cfg(feature = "tracy")]
+impl TracyMetricsSink {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[cfg(feature = "tracy")]
+impl MetricsSink for TracyMetricsSink {
+    fn record(&self, metrics: &FrameMetrics) {
+        let client = tracy_client::Client::running();
+        if client.is_none() {
+            return;
+        }
+        let client = client.unwrap();
+        client.plot(tracy_client::plot_name!("CPU frame time (ms)"), metrics.cpu_frame_time.as_secs_f64() * 1000f64);
+        if let Some(gpu_frame_time) = metrics.gpu_frame_time {
+            client.plot(tracy_client::plot_name!("GPU frame time (ms)"), gpu_frame_time.as_secs_f64() * 1000f64);
+        }
+        client.plot(tracy_client::plot_name!("Draw calls"), metrics.draw_calls as f64);
+        client.plot(tracy_client::plot_name!("Triangles"), metrics.triangles as f64);
+        client.plot(tracy_client::plot_name!("Visible drawables"), metrics.visible_drawables as f64);
+        client.plot(tracy_client::plot_name!("Culled drawables"), metrics.culled_drawables as f64);
+    }
+}
@@ -22,6 +22,7 @@ pub struct AssetIntegrator<P: Platform> {
     asset_queue: Mutex<Vec<DelayedAsset<P>>>,
     vertex_buffer: AssetBuffer<P::GPUBackend>,
     index_buffer: AssetBuffer<P::GPUBackend>,
+    texture_streamer: TextureStreamer,
 }
 
 impl<P: Platform> AssetIntegrator<P> {
@@ -45,6 +46,7 @@ impl<P: Platform> AssetIntegrator<P> {
             asset_queue: Mutex::new(Vec::new()),
             vertex_buffer,
             index_buffer,
+            texture_streamer: TextureStreamer::new(),
         }
     }
 
@@ -83,7 +85,8 @@ impl<P: Platform> AssetIntegrator<P> {
         priority: AssetLoadPriority,
         texture_data: &TextureData
     ) -> (RendererTexture<P::GPUBackend>, Option<SharedFenceValuePair<P::GPUBackend>>) {
-        let (view, fence) = self.upload_texture(path, texture_data, priority == AssetLoadPriority::Low);
+        let first_mip = self.texture_streamer.first_mip_to_upload(path, texture_data, priority);
+        let (view, fence) = self.upload_texture(path, texture_data, first_mip, priority == AssetLoadPriority::Low);
         let bindless_index = if self.device.supports_bindless() {
             self.device.insert_texture_into_bindless_heap(&view)
         } else {
@@ -134,42 +137,50 @@ impl<P: Platform> AssetIntegrator<P> {
         }
     }
 
+    /// Only uploads the mip levels starting at `first_mip`, the GPU texture gets created with the size of that mip level.
     fn upload_texture(
         &self,
         path: &str,
         texture: &TextureData,
+        first_mip: u32,
         do_async: bool,
     ) -> (
         Arc<TextureView<P::GPUBackend>>,
         Option<SharedFenceValuePair<P::GPUBackend>>
     ) {
+        let mut info = texture.info.clone();
+        info.width = (info.width >> first_mip).max(1);
+        info.height = (info.height >> first_mip).max(1);
+        info.mip_levels -= first_mip;
+
         let gpu_texture = self
             .device
-            .create_texture(&texture.info, Some(path)).unwrap();
-        let subresources = texture.info.array_length * texture.info.mip_levels;
+            .create_texture(&info, Some(path)).unwrap();
+        let subresources = info.array_length * info.mip_levels;
         let mut fence = Option::<SharedFenceValuePair<P::GPUBackend>>::None;
         for subresource in 0..subresources {
-            let mip_level = subresource % texture.info.mip_levels;
-            let array_index = subresource / texture.info.mip_levels;
+            let mip_level = subresource % info.mip_levels;
+            let array_index = subresource / info.mip_levels;
+            let data_index = (array_index * texture.info.mip_levels + first_mip + mip_level) as usize;
             if do_async {
                 fence = self.device.init_texture_async(
-                    &texture.data[subresource as usize][..],
+                    &texture.data[data_index][..],
                     &gpu_texture,
                     mip_level,
                     array_index
                 ).unwrap();
             } else {
                 self.device
-                    .init_texture(&texture.data[subresource as usize][..], &gpu_texture, mip_level, array_index).unwrap();
+                    .init_texture(&texture.data[data_index][..], &gpu_texture, mip_level, array_index).unwrap();
             }
         }
         let view = self.device.create_texture_view(
             &gpu_texture,
             &TextureViewInfo {
                 base_mip_level: 0,
-                mip_level_length: texture.info.mip_levels,
+                mip_level_length: info.mip_levels,
                 base_array_layer: 0,
-                array_layer_length: 1,
+                array_layer_length: info.array_length,
                 format: None,
             },
             Some(path),
@@ -286,6 +297,10 @@ impl<P: Platform> AssetIntegrator<P> {
         self.index_buffer.bump_frame(context);
     }
 
+    pub(crate) fn texture_streamer(&self) -> &TextureStreamer {
+        &self.texture_streamer
+    }
+
     pub(crate) fn vertex_buffer(&self) -> &Arc<BufferSlice<P::GPUBackend>> {
         self.vertex_buffer.buffer()
     }
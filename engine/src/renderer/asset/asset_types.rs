@@ -130,6 +130,9 @@ impl PartialEq for RendererMaterial {
 }
 
 impl RendererMaterial {
+    /// Water surfaces are only drawn by the water pass of the web render path, the other paths skip them.
+    pub const WATER_SHADER_NAME: &'static str = "water";
+
     pub fn new_pbr(albedo_texture: TextureHandle) -> Self {
         let mut props = HashMap::new();
         props.insert(
@@ -154,6 +157,21 @@ impl RendererMaterial {
     pub fn get(&self, key: &str) -> Option<&RendererMaterialValue> {
         self.properties.get(key)
     }
+
+    pub fn shader_name(&self) -> &str {
+        &self.shader_name
+    }
+
+    pub fn is_water(&self) -> bool {
+        self.shader_name == Self::WATER_SHADER_NAME
+    }
+
+    pub fn textures(&self) -> impl Iterator<Item = TextureHandle> + '_ {
+        self.properties.values().filter_map(|value| match value {
+            RendererMaterialValue::Texture(texture) => Some(*texture),
+            _ => None,
+        })
+    }
 }
 
 impl Eq for RendererMaterial {}
@@ -4,6 +4,7 @@ mod asset_placeholders;
 mod asset_types;
 mod renderer_assets;
 mod shader_manager;
+mod texture_streamer;
 
 pub use asset_buffer::*;
 pub use asset_integrator::*;
@@ -11,4 +12,5 @@ pub use asset_placeholders::*;
 pub use asset_types::*;
 pub use renderer_assets::*;
 use shader_manager::*;
+pub use texture_streamer::TextureStreamer;
 pub use shader_manager::{ComputePipelineHandle, GraphicsPipelineHandle, RayTracingPipelineHandle, GraphicsPipelineInfo, RayTracingPipelineInfo};
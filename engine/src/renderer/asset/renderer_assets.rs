@@ -48,6 +48,10 @@ impl<P: Platform> RendererAssets<P> {
         self.integrator.integrate(asset_manager, &self.shader_manager, path, asset_data, priority)
     }
 
+    pub(crate) fn texture_streamer(&self) -> &TextureStreamer {
+        self.integrator.texture_streamer()
+    }
+
     pub(crate) fn reserve_handle(&self, path: &str, asset_type: AssetType) -> AssetHandle {
         let mut assets = self.assets.write();
         assets.reserve_handle(path, asset_type)
@@ -82,10 +86,18 @@ impl<P: Platform> RendererAssets<P> {
         self.shader_manager.request_graphics_pipeline(asset_manager, info)
     }
 
+    pub(crate) fn request_graphics_pipeline_async(&self, asset_manager: &Arc<AssetManager<P>>, info: &GraphicsPipelineInfo) -> GraphicsPipelineHandle {
+        self.shader_manager.request_graphics_pipeline_async(asset_manager, info)
+    }
+
     pub(crate) fn request_compute_pipeline(&self, asset_manager: &Arc<AssetManager<P>>, shader_path: &str) -> ComputePipelineHandle {
         self.shader_manager.request_compute_pipeline(asset_manager, shader_path)
     }
 
+    pub(crate) fn request_compute_pipeline_async(&self, asset_manager: &Arc<AssetManager<P>>, shader_path: &str) -> ComputePipelineHandle {
+        self.shader_manager.request_compute_pipeline_async(asset_manager, shader_path)
+    }
+
     pub(crate) fn request_ray_tracing_pipeline(&self, asset_manager: &Arc<AssetManager<P>>, info: &RayTracingPipelineInfo) -> RayTracingPipelineHandle {
         self.shader_manager.request_ray_tracing_pipeline(asset_manager, info)
     }
@@ -305,6 +317,10 @@ impl<P: Platform> RendererAssetsReadOnly<'_, P> {
         self.maps.textures.get_value(handle)
     }
 
+    pub fn get_texture_path(&self, handle: TextureHandle) -> Option<&str> {
+        self.maps.textures.get_key(handle).map(|path| path.as_str())
+    }
+
     pub fn get_placeholder_texture_black(&self) -> &RendererTexture<P::GPUBackend> {
         self.placeholders.texture_black()
     }
@@ -1,6 +1,7 @@
 use std::collections::hash_map::Values;
 use std::collections::HashMap;
-use std::hash::Hash;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
@@ -33,6 +34,11 @@ pub trait PipelineCompileTask<P: Platform>: Send + Sync + Clone {
     type TPipeline: Send + Sync;
 
     fn asset_type() -> AssetType;
+    /// Hashes everything that affects the resulting pipeline.
+    /// Used to hand out the same handle for identical pipeline requests.
+    fn pipeline_hash(&self) -> u64;
+    fn is_async(&self) -> bool;
+    fn make_mandatory(&mut self);
     fn pipeline_from_asset_ref<'a>(asset: AssetRef<'a, P>) -> &'a CompiledPipeline<P, Self>;
     fn pipeline_into_asset(self, pipeline: Arc<Self::TPipeline>) -> Asset<P>;
     fn get_task(pipeline: &CompiledPipeline<P, Self>) -> &Self {
@@ -113,6 +119,18 @@ struct StoredBlendInfo {
     pub constants: [f32; 4],
 }
 
+impl Hash for StoredBlendInfo {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.alpha_to_coverage_enabled.hash(state);
+        self.logic_op_enabled.hash(state);
+        self.logic_op.hash(state);
+        self.attachments.hash(state);
+        for constant in &self.constants {
+            constant.to_bits().hash(state);
+        }
+    }
+}
+
 impl<'a> PartialEq<BlendInfo<'a>> for StoredBlendInfo {
     fn eq(&self, other: &BlendInfo<'a>) -> bool {
         self.alpha_to_coverage_enabled == other.alpha_to_coverage_enabled
@@ -123,7 +141,7 @@ impl<'a> PartialEq<BlendInfo<'a>> for StoredBlendInfo {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Hash)]
 struct StoredGraphicsPipelineInfo {
     pub vs: String,
     pub fs: Option<String>,
@@ -188,6 +206,17 @@ impl<P: Platform> PipelineCompileTask<P> for GraphicsCompileTask<P> {
     fn asset_type() -> AssetType {
         AssetType::GraphicsPipeline
     }
+    fn pipeline_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.info.hash(&mut hasher);
+        hasher.finish()
+    }
+    fn is_async(&self) -> bool {
+        self.is_async
+    }
+    fn make_mandatory(&mut self) {
+        self.is_async = false;
+    }
     fn pipeline_from_asset_ref<'a>(asset: AssetRef<'a, P>) -> &'a CompiledPipeline<P, Self> {
         if let AssetRef::<P>::GraphicsPipeline(pipeline) = asset {
             pipeline
@@ -342,6 +371,17 @@ impl<P: Platform> PipelineCompileTask<P> for ComputeCompileTask<P> {
     fn asset_type() -> AssetType {
         AssetType::ComputePipeline
     }
+    fn pipeline_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.path.hash(&mut hasher);
+        hasher.finish()
+    }
+    fn is_async(&self) -> bool {
+        self.is_async
+    }
+    fn make_mandatory(&mut self) {
+        self.is_async = false;
+    }
     fn pipeline_from_asset_ref<'a>(asset: AssetRef<'a, P>) -> &'a CompiledPipeline<P, Self> {
         if let AssetRef::<P>::ComputePipeline(pipeline) = asset {
             pipeline
@@ -465,6 +505,19 @@ impl<P: Platform> PipelineCompileTask<P> for StoredRayTracingPipelineInfo<P> {
     fn asset_type() -> AssetType {
         AssetType::RayTracingPipeline
     }
+    fn pipeline_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.ray_gen_shader.hash(&mut hasher);
+        self.closest_hit_shaders.hash(&mut hasher);
+        self.miss_shaders.hash(&mut hasher);
+        hasher.finish()
+    }
+    fn is_async(&self) -> bool {
+        self.is_async
+    }
+    fn make_mandatory(&mut self) {
+        self.is_async = false;
+    }
     fn pipeline_from_asset_ref<'a>(asset: AssetRef<'a, P>) -> &'a CompiledPipeline<P, Self> {
         if let AssetRef::<P>::RayTracingPipeline(pipeline) = asset {
             pipeline
@@ -587,6 +640,7 @@ where
     T: PipelineCompileTask<P>,
 {
     remaining_compilations: Mutex<HashMap<THandle, T>>,
+    handles_by_hash: Mutex<HashMap<u64, THandle>>,
     cond_var: Condvar,
     _platform: PlatformPhantomData<P>
 }
@@ -600,6 +654,7 @@ where
     fn new() -> Self {
         Self {
             remaining_compilations: Mutex::new(HashMap::new()),
+            handles_by_hash: Mutex::new(HashMap::new()),
             cond_var: Condvar::new(),
             _platform: Default::default()
         }
@@ -618,10 +673,30 @@ impl<P: Platform> ShaderManager<P> {
         }
     }
 
+    /// Requests a graphics pipeline that the renderer needs before it can render the first frame.
     pub fn request_graphics_pipeline(
         &self,
         asset_manager: &Arc<AssetManager<P>>,
         info: &GraphicsPipelineInfo,
+    ) -> GraphicsPipelineHandle {
+        self.request_graphics_pipeline_internal(asset_manager, info, false)
+    }
+
+    /// Requests a graphics pipeline that gets compiled in the background without blocking the renderer.
+    /// Users need to skip draws (or use a fallback pipeline) until the pipeline is available.
+    pub fn request_graphics_pipeline_async(
+        &self,
+        asset_manager: &Arc<AssetManager<P>>,
+        info: &GraphicsPipelineInfo,
+    ) -> GraphicsPipelineHandle {
+        self.request_graphics_pipeline_internal(asset_manager, info, true)
+    }
+
+    fn request_graphics_pipeline_internal(
+        &self,
+        asset_manager: &Arc<AssetManager<P>>,
+        info: &GraphicsPipelineInfo,
+        is_async: bool,
     ) -> GraphicsPipelineHandle {
         let stored_input_layout = StoredVertexLayoutInfo {
             shader_inputs: info.vertex_layout.shader_inputs.iter().cloned().collect(),
@@ -653,7 +728,7 @@ impl<P: Platform> ShaderManager<P> {
             &self.graphics,
             GraphicsCompileTask::<P> {
                 info: stored,
-                is_async: false,
+                is_async,
                 _p: PhantomData,
             },
         )
@@ -663,12 +738,27 @@ impl<P: Platform> ShaderManager<P> {
         &self,
         asset_manager: &Arc<AssetManager<P>>,
         path: &str) -> ComputePipelineHandle {
+        self.request_compute_pipeline_internal(asset_manager, path, false)
+    }
+
+    pub fn request_compute_pipeline_async(
+        &self,
+        asset_manager: &Arc<AssetManager<P>>,
+        path: &str) -> ComputePipelineHandle {
+        self.request_compute_pipeline_internal(asset_manager, path, true)
+    }
+
+    fn request_compute_pipeline_internal(
+        &self,
+        asset_manager: &Arc<AssetManager<P>>,
+        path: &str,
+        is_async: bool) -> ComputePipelineHandle {
         self.request_pipeline_internal(
             asset_manager,
             &self.compute,
             ComputeCompileTask::<P> {
                 path: path.to_string(),
-                is_async: false,
+                is_async,
                 _p: PhantomData,
             },
         )
@@ -706,7 +796,21 @@ impl<P: Platform> ShaderManager<P> {
         THandle: IndexHandle + Hash + PartialEq + Eq + Clone + Copy + Send + Sync + From<AssetHandle>,
         T: PipelineCompileTask<P>,
     {
+        let hash = task.pipeline_hash();
+        let mut handles_by_hash = pipeline_type_manager.handles_by_hash.lock().unwrap();
+        if let Some(existing_handle) = handles_by_hash.get(&hash) {
+            // The same pipeline was already requested, either it's still pending or it's done.
+            if !task.is_async() {
+                let mut remaining = pipeline_type_manager.remaining_compilations.lock().unwrap();
+                if let Some(pending_task) = remaining.get_mut(existing_handle) {
+                    pending_task.make_mandatory();
+                }
+            }
+            return *existing_handle;
+        }
+
         let handle: THandle = asset_manager.reserve_handle_without_path(T::asset_type()).into();
+        handles_by_hash.insert(hash, handle);
         task.request_shaders(asset_manager);
         let mut remaining = pipeline_type_manager.remaining_compilations.lock().unwrap();
         remaining.insert(handle, task);
@@ -768,34 +872,59 @@ impl<P: Platform> ShaderManager<P> {
                 return true;
             }
 
+            pipeline_type_manager.cond_var.notify_all();
+
+            if !Self::supports_background_compilation() {
+                trace!("Compiling pipelines with {:?} {} synchronously", shader.shader_type(), path);
+                Self::compile_pipelines(&self.device, pipeline_type_manager, asset_manager, ready_handles);
+                return true;
+            }
+
             trace!("Queuing compile tasks for pipelines with {:?} {}", shader.shader_type(), path);
             let c_device = self.device.clone();
             let c_manager: Arc<PipelineTypeManager<P, THandle, T>> = pipeline_type_manager.clone();
             let c_asset_manager = asset_manager.clone();
-            c_manager.cond_var.notify_all();
             let task_pool = bevy_tasks::ComputeTaskPool::get();
             let task = task_pool.spawn(async move {
-                for handle in ready_handles.drain(..) {
-                    let task: T;
-                    let shaders: T::TShaders;
-
-                    let assets_read = c_asset_manager.read_renderer_assets();
-                    {
-                        let mut remaining_compilations = c_manager.remaining_compilations.lock().unwrap();
-                        task = remaining_compilations.remove(&handle).unwrap();
-                        shaders = task.collect_shaders_for_compilation(assets_read);
-                    };
-                    let pipeline: Arc<<T as PipelineCompileTask<P>>::TPipeline> = task.compile(shaders, &c_device);
-                    let generic_handle: AssetHandle = handle.into();
-                    c_asset_manager.add_asset_with_handle(AssetWithHandle::combine(generic_handle, T::pipeline_into_asset(task, pipeline)));
-                }
-                c_manager.cond_var.notify_all();
+                Self::compile_pipelines(&c_device, &c_manager, &c_asset_manager, ready_handles);
             });
             task.detach();
             true
         }
     }
 
+    /// Pipeline creation has to happen on the thread that owns the GPU context on WebAssembly.
+    fn supports_background_compilation() -> bool {
+        !cfg!(target_arch = "wasm32")
+    }
+
+    fn compile_pipelines<THandle, T>(
+        device: &Arc<Device<P::GPUBackend>>,
+        pipeline_type_manager: &Arc<PipelineTypeManager<P, THandle, T>>,
+        asset_manager: &Arc<AssetManager<P>>,
+        mut ready_handles: SmallVec<[THandle; 1]>
+    )
+    where
+        THandle: IndexHandle + Hash + PartialEq + Eq + Clone + Copy + Send + Sync + From<AssetHandle> + Into<AssetHandle> + 'static,
+        T: PipelineCompileTask<P> + 'static,
+    {
+        for handle in ready_handles.drain(..) {
+            let task: T;
+            let shaders: T::TShaders;
+
+            let assets_read = asset_manager.read_renderer_assets();
+            {
+                let mut remaining_compilations = pipeline_type_manager.remaining_compilations.lock().unwrap();
+                task = remaining_compilations.remove(&handle).unwrap();
+                shaders = task.collect_shaders_for_compilation(assets_read);
+            };
+            let pipeline: Arc<<T as PipelineCompileTask<P>>::TPipeline> = task.compile(shaders, device);
+            let generic_handle: AssetHandle = handle.into();
+            asset_manager.add_asset_with_handle(AssetWithHandle::combine(generic_handle, T::pipeline_into_asset(task, pipeline)));
+        }
+        pipeline_type_manager.cond_var.notify_all();
+    }
+
     pub fn add_shader(&self, asset_manager: &Arc<AssetManager<P>>, path: &str, shader: &RendererShader<P::GPUBackend>) {
         if !match shader.shader_type() {
             ShaderType::ComputeShader => self.add_shader_type(asset_manager, &self.compute, path, shader),
@@ -812,17 +941,17 @@ impl<P: Platform> ShaderManager<P> {
             let graphics_remaining = self.graphics.remaining_compilations.lock().unwrap();
             graphics_remaining
                 .iter()
-                .any(|(_, t)| !t.is_async)
+                .any(|(_, t)| !t.is_async())
         };
         let has_compute_compiles = {
             let compute_remaining = self.compute.remaining_compilations.lock().unwrap();
             compute_remaining
                 .iter()
-                .any(|(_, t)| !t.is_async)
+                .any(|(_, t)| !t.is_async())
         };
         let has_rt_compiles = {
             let rt_remaining = self.rt.remaining_compilations.lock().unwrap();
-            rt_remaining.iter().any(|(_, t)| !t.is_async)
+            rt_remaining.iter().any(|(_, t)| !t.is_async())
         };
         has_graphics_compiles || has_compute_compiles || has_rt_compiles
     }
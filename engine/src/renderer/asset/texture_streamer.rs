@@ -0,0 +1,164 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use smallvec::SmallVec;
+
+use crate::asset::{AssetLoadPriority, FixedByteSizeCache, TextureData};
+use crate::graphics::TextureDimension;
+
+/// How many of the smallest mip levels get uploaded when a streamed texture gets loaded.
+const INITIAL_RESIDENT_MIPS: u32 = 4;
+/// How much memory the mip levels above the initial ones may take up.
+const DEFAULT_STREAMING_BUDGET: u64 = 256 << 20;
+
+struct StreamedTexture {
+    mip_levels: u32,
+    /// The first mip level that the last upload contained.
+    resident_first_mip: u32,
+    /// The first mip level that should be resident. Differs from `resident_first_mip` while the texture gets reloaded.
+    requested_first_mip: u32,
+    /// Byte size of every mip level, starting with the largest one.
+    mip_sizes: Box<[u64]>,
+}
+
+impl StreamedTexture {
+    fn initial_first_mip(&self) -> u32 {
+        self.mip_levels.saturating_sub(INITIAL_RESIDENT_MIPS)
+    }
+
+    fn high_mips_size(&self, first_mip: u32) -> u64 {
+        self.mip_sizes[first_mip as usize .. self.initial_first_mip() as usize].iter().sum()
+    }
+}
+
+struct TextureStreamerInner {
+    textures: HashMap<String, StreamedTexture>,
+    /// Textures that were loaded with a high priority, those always stay fully resident.
+    fully_resident: HashSet<String>,
+    /// Tracks the mips above the initial ones. Evicting an entry drops the texture back to its initial mips.
+    high_mips: FixedByteSizeCache<String, ()>,
+}
+
+/// Decides which mip levels of a texture get uploaded.
+///
+/// Streamed textures start out with only their smallest mip levels.
+/// The renderer requests higher mips for the textures of visible drawables based on their distance to the camera,
+/// that reloads the texture from its file and replaces it with a larger one that contains the requested mips.
+/// The GPU texture only ever contains the resident mips, so UVs stay valid and sampling never touches missing data.
+/// Only 2D textures with more mips than the initial ones get streamed.
+pub struct TextureStreamer {
+    inner: Mutex<TextureStreamerInner>,
+}
+
+impl TextureStreamer {
+    pub(crate) fn new() -> Self {
+        Self {
+            inner: Mutex::new(TextureStreamerInner {
+                textures: HashMap::new(),
+                fully_resident: HashSet::new(),
+                high_mips: FixedByteSizeCache::new(DEFAULT_STREAMING_BUDGET),
+            }),
+        }
+    }
+
+    /// Returns the first mip level of the texture that should get uploaded.
+    pub(crate) fn first_mip_to_upload(&self, path: &str, texture: &TextureData, priority: AssetLoadPriority) -> u32 {
+        let info = &texture.info;
+        if info.dimension != TextureDimension::Dim2D || info.array_length != 1 || info.mip_levels <= INITIAL_RESIDENT_MIPS {
+            return 0;
+        }
+
+        let mut guard = self.inner.lock().unwrap();
+        let inner = &mut *guard;
+        if inner.fully_resident.contains(path) {
+            return 0;
+        }
+        if priority == AssetLoadPriority::High && !inner.textures.contains_key(path) {
+            inner.fully_resident.insert(path.to_string());
+            return 0;
+        }
+
+        let mip_sizes: Box<[u64]> = texture.data.iter().map(|mip| mip.len() as u64).collect();
+        let streamed_texture = inner.textures.entry(path.to_string()).or_insert_with(|| StreamedTexture {
+            mip_levels: info.mip_levels,
+            resident_first_mip: 0,
+            requested_first_mip: info.mip_levels - INITIAL_RESIDENT_MIPS,
+            mip_sizes: Box::new([]),
+        });
+        if streamed_texture.mip_levels != info.mip_levels {
+            // The file changed, start over.
+            inner.high_mips.remove(&path.to_string());
+            streamed_texture.mip_levels = info.mip_levels;
+            streamed_texture.requested_first_mip = streamed_texture.initial_first_mip();
+        }
+        streamed_texture.mip_sizes = mip_sizes;
+        let first_mip = streamed_texture.requested_first_mip.min(streamed_texture.initial_first_mip());
+        streamed_texture.resident_first_mip = first_mip;
+        first_mip
+    }
+
+    /// Starts a new frame of requests.
+    /// Textures requested within the same frame don't evict each other's mips,
+    /// requests that don't fit into the budget anymore get fewer mips instead.
+    pub(crate) fn begin_frame(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.high_mips.next_frame();
+    }
+
+    /// Requests the given mip level and every smaller one to be resident.
+    /// If those don't fit into the part of the budget that isn't used by other textures requested in the same frame,
+    /// only the largest mips that do fit get requested.
+    /// Returns the paths of the textures that need to be reloaded, that includes the ones that got evicted to stay within the budget.
+    pub(crate) fn request_first_mip(&self, path: &str, first_mip: u32) -> SmallVec<[String; 2]> {
+        let mut reload = SmallVec::<[String; 2]>::new();
+        let mut guard = self.inner.lock().unwrap();
+        let inner = &mut *guard;
+        let key = path.to_string();
+        let high_mips_size = if let Some(streamed_texture) = inner.textures.get_mut(path) {
+            let available_byte_size = inner.high_mips.available_byte_size(&key);
+            let mut first_mip = first_mip.min(streamed_texture.initial_first_mip());
+            while first_mip < streamed_texture.requested_first_mip
+                && streamed_texture.high_mips_size(first_mip) > available_byte_size {
+                first_mip += 1;
+            }
+            if first_mip >= streamed_texture.requested_first_mip {
+                inner.high_mips.touch(&key);
+                return reload;
+            }
+            streamed_texture.requested_first_mip = first_mip;
+            streamed_texture.high_mips_size(first_mip)
+        } else {
+            return reload;
+        };
+
+        reload.push(key.clone());
+        let evicted = inner.high_mips.insert(key, (), high_mips_size);
+        Self::reset_evicted(inner, evicted, &mut reload);
+        reload
+    }
+
+    /// Changes how much memory the mips above the initial ones may take up.
+    /// Returns the paths of the textures that need to be reloaded to get within the budget.
+    pub(crate) fn set_budget(&self, byte_size: u64) -> SmallVec<[String; 2]> {
+        let mut reload = SmallVec::<[String; 2]>::new();
+        let mut inner = self.inner.lock().unwrap();
+        let evicted = inner.high_mips.set_max_byte_size(byte_size);
+        Self::reset_evicted(&mut inner, evicted, &mut reload);
+        reload
+    }
+
+    /// The first mip level of the texture that's currently uploaded, None if the texture isn't streamed.
+    pub(crate) fn resident_first_mip(&self, path: &str) -> Option<u32> {
+        let inner = self.inner.lock().unwrap();
+        inner.textures.get(path).map(|streamed_texture| streamed_texture.resident_first_mip)
+    }
+
+    fn reset_evicted(inner: &mut TextureStreamerInner, evicted: Vec<(String, ())>, reload: &mut SmallVec<[String; 2]>) {
+        for (evicted_path, _) in evicted {
+            if let Some(streamed_texture) = inner.textures.get_mut(&evicted_path) {
+                streamed_texture.requested_first_mip = streamed_texture.initial_first_mip();
+                reload.push(evicted_path);
+            }
+        }
+    }
+}
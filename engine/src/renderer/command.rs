@@ -2,7 +2,9 @@ use bevy_ecs::entity::Entity;
 use bevy_math::Affine3A;
 use sourcerenderer_core::{gpu::GPUBackend, Matrix4, Vec2UI};
 
-use crate::{engine::WindowState, ui::UIDrawData};
+use crate::{camera::Projection, engine::WindowState, ui::UIDrawData};
+
+use super::debug_draw::DebugDrawData;
 
 pub enum RendererCommand<B: GPUBackend> {
     RegisterStatic {
@@ -32,10 +34,14 @@ pub enum RendererCommand<B: GPUBackend> {
     },
     UpdateCameraTransform {
         camera_transform: Affine3A,
-        fov: f32,
+        projection: Projection,
+        z_near: f32,
+        z_far: f32,
     },
     SetLightmap(String),
+    SetEnvironmentMap(String),
     RenderUI(UIDrawData<B>),
+    RenderDebugLines(DebugDrawData),
     EndFrame,
     Quit,
     WindowChanged(WindowState)
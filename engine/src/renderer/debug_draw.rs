@@ -0,0 +1,106 @@
+use bevy_ecs::system::Resource;
+use sourcerenderer_core::{
+    Vec3,
+    Vec4,
+};
+
+const SPHERE_SEGMENTS: u32 = 24;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct DebugLineVertex {
+    pub position: Vec3,
+    /// RGBA8
+    pub color: u32,
+}
+
+/// The lines of a single frame.
+/// The depth tested lines come first, the remaining lines are drawn on top of everything.
+#[derive(Debug, Clone, Default)]
+pub struct DebugDrawData {
+    pub vertices: Vec<DebugLineVertex>,
+    pub depth_tested_vertex_count: u32,
+}
+
+impl DebugDrawData {
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+}
+
+/// Immediate mode debug drawing for gameplay systems.
+/// Everything that gets queued is drawn in the next frame and then discarded.
+#[derive(Resource, Default)]
+pub struct DebugRenderer {
+    depth_tested: Vec<DebugLineVertex>,
+    on_top: Vec<DebugLineVertex>,
+    ignore_depth: bool,
+}
+
+impl DebugRenderer {
+    /// Sets whether the primitives that get queued after this call are visible through geometry.
+    pub fn set_ignore_depth(&mut self, ignore_depth: bool) {
+        self.ignore_depth = ignore_depth;
+    }
+
+    pub fn line(&mut self, a: Vec3, b: Vec3, color: Vec4) {
+        let color = pack_color(color);
+        let vertices = if self.ignore_depth {
+            &mut self.on_top
+        } else {
+            &mut self.depth_tested
+        };
+        vertices.push(DebugLineVertex { position: a, color });
+        vertices.push(DebugLineVertex { position: b, color });
+    }
+
+    pub fn aabb(&mut self, min: Vec3, max: Vec3, color: Vec4) {
+        let corner = |x: bool, y: bool, z: bool| {
+            Vec3::new(
+                if x { max.x } else { min.x },
+                if y { max.y } else { min.y },
+                if z { max.z } else { min.z },
+            )
+        };
+        for a in [false, true] {
+            for b in [false, true] {
+                self.line(corner(false, a, b), corner(true, a, b), color);
+                self.line(corner(a, false, b), corner(a, true, b), color);
+                self.line(corner(a, b, false), corner(a, b, true), color);
+            }
+        }
+    }
+
+    /// Draws a circle around each axis.
+    pub fn sphere(&mut self, center: Vec3, radius: f32, color: Vec4) {
+        for i in 0..SPHERE_SEGMENTS {
+            let angle_a = (i as f32) / (SPHERE_SEGMENTS as f32) * std::f32::consts::TAU;
+            let angle_b = ((i + 1) as f32) / (SPHERE_SEGMENTS as f32) * std::f32::consts::TAU;
+            let (sin_a, cos_a) = angle_a.sin_cos();
+            let (sin_b, cos_b) = angle_b.sin_cos();
+            self.line(center + Vec3::new(cos_a, sin_a, 0f32) * radius, center + Vec3::new(cos_b, sin_b, 0f32) * radius, color);
+            self.line(center + Vec3::new(cos_a, 0f32, sin_a) * radius, center + Vec3::new(cos_b, 0f32, sin_b) * radius, color);
+            self.line(center + Vec3::new(0f32, cos_a, sin_a) * radius, center + Vec3::new(0f32, cos_b, sin_b) * radius, color);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.depth_tested.is_empty() && self.on_top.is_empty()
+    }
+
+    /// Takes all queued lines and leaves the renderer empty for the next frame.
+    pub fn take(&mut self) -> DebugDrawData {
+        let depth_tested_vertex_count = self.depth_tested.len() as u32;
+        let mut vertices = std::mem::take(&mut self.depth_tested);
+        vertices.append(&mut self.on_top);
+        DebugDrawData {
+            vertices,
+            depth_tested_vertex_count,
+        }
+    }
+}
+
+fn pack_color(color: Vec4) -> u32 {
+    let color = (color.clamp(Vec4::ZERO, Vec4::ONE) * 255f32).round();
+    (color.x as u32) | ((color.y as u32) << 8) | ((color.z as u32) << 16) | ((color.w as u32) << 24)
+}
@@ -10,6 +10,7 @@ use sourcerenderer_core::{
 };
 
 use crate::asset::ModelHandle;
+use crate::camera::Projection;
 
 pub struct RendererStaticDrawable {
     pub entity: Entity,
@@ -21,6 +22,29 @@ pub struct RendererStaticDrawable {
     pub can_move: bool,
 }
 
+/// How depth values are written to the depth buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DepthMode {
+    /// Regular hyperbolic depth produced by the projection matrix.
+    #[default]
+    Standard,
+    /// Depth is replaced with log2(1 + w) / log2(1 + far) in the vertex shader.
+    /// Gives much better precision over large distances on backends without float depth buffers.
+    Logarithmic,
+}
+
+/// The curve used to map the HDR scene color to the output range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TonemapOperator {
+    /// x / (1 + x), cheap but washes out bright colors.
+    Reinhard,
+    /// Narkowicz's fit of the ACES filmic curve.
+    #[default]
+    Aces,
+    /// Polynomial approximation of AgX, desaturates very bright colors instead of shifting their hue.
+    AgX,
+}
+
 #[derive(Clone)]
 pub struct View {
     pub camera_position: Vec3,
@@ -29,10 +53,35 @@ pub struct View {
     pub proj_matrix: Matrix4,
     pub old_camera_matrix: Matrix4,
     pub camera_transform: Affine3A,
+    pub projection: Projection,
     pub camera_fov: f32,
     pub near_plane: f32,
     pub far_plane: f32,
     pub aspect_ratio: f32,
+    pub depth_mode: DepthMode,
+    pub tonemap_operator: TonemapOperator,
+    pub exposure: f32,
+    pub bloom_threshold: f32,
+    pub bloom_intensity: f32,
+    /// Disabling SSAO skips all of its passes, lighting then uses a white placeholder texture instead.
+    pub ssao_enabled: bool,
+    /// View space radius of the SSAO sample hemisphere.
+    pub ssao_radius: f32,
+    pub ssao_intensity: f32,
+    pub ssao_samples: u32,
+    /// Radius in texels of the separable SSAO blur, 0 skips the spatial blur.
+    pub ssao_blur_radius: u32,
+    pub ssr_intensity: f32,
+    /// Surfaces rougher than this don't get screen space reflections.
+    pub ssr_roughness_cutoff: f32,
+    /// The maximum number of steps of the linear ray march, the binary search refinement comes on top.
+    pub ssr_steps: u32,
+    /// Disabling TAA also disables the jitter, FSR2 keeps running without it.
+    pub taa_enabled: bool,
+    /// The number of frames since the last camera cut, used to pick the jitter of the frame.
+    pub jitter_frame: u32,
+    /// The camera teleported since the last frame, so temporal passes have to drop their history.
+    pub camera_cut: bool,
     pub old_visible_drawables_bitset: Vec<u32>,
     pub visible_drawables_bitset: Vec<u32>,
     pub drawable_parts: Vec<DrawablePart>,
@@ -47,10 +96,27 @@ impl Default for View {
             old_camera_matrix: Matrix4::default(),
             view_matrix: Matrix4::default(),
             proj_matrix: Matrix4::default(),
+            projection: Projection::Perspective { fov: f32::consts::PI / 2f32 },
             camera_fov: f32::consts::PI / 2f32,
             near_plane: 0.1f32,
             far_plane: 100f32,
             aspect_ratio: 16.0f32 / 9.0f32,
+            depth_mode: DepthMode::Standard,
+            tonemap_operator: TonemapOperator::Aces,
+            exposure: 0.01f32,
+            bloom_threshold: 1f32,
+            bloom_intensity: 0.05f32,
+            ssao_enabled: true,
+            ssao_radius: 0.5f32,
+            ssao_intensity: 1f32,
+            ssao_samples: 32,
+            ssao_blur_radius: 2,
+            ssr_intensity: 1f32,
+            ssr_roughness_cutoff: 0.3f32,
+            ssr_steps: 256,
+            taa_enabled: true,
+            jitter_frame: 0,
+            camera_cut: false,
             drawable_parts: Vec::new(),
             old_visible_drawables_bitset: Vec::new(),
             visible_drawables_bitset: Vec::new(),
@@ -74,7 +140,51 @@ pub(crate) fn make_camera_view(position: Vec3, rotation: Quaternion) -> Matrix4
     )
 }
 
-pub(crate) fn make_camera_proj(fov: f32, aspect_ratio: f32, z_near: f32, z_far: f32) -> Matrix4 {
-    let vertical_fov = 2f32 * ((fov / 2f32).tan() * (1f32 / aspect_ratio)).atan();
-    Matrix4::perspective_lh(vertical_fov, aspect_ratio, z_near, z_far)
+impl View {
+    /// The factor the logarithmic depth shaders multiply log2(1 + w) with.
+    pub fn log_depth_factor(&self) -> f32 {
+        1f32 / (self.far_plane + 1f32).log2()
+    }
+
+    /// Orthographic projections already produce linear depth and have a constant w,
+    /// so logarithmic depth is only applied to perspective views.
+    pub fn uses_logarithmic_depth(&self) -> bool {
+        self.depth_mode == DepthMode::Logarithmic && matches!(self.projection, Projection::Perspective { .. })
+    }
+}
+
+pub(crate) fn make_camera_proj(projection: Projection, aspect_ratio: f32, z_near: f32, z_far: f32) -> Matrix4 {
+    match projection {
+        Projection::Perspective { fov } => {
+            let vertical_fov = 2f32 * ((fov / 2f32).tan() * (1f32 / aspect_ratio)).atan();
+            Matrix4::perspective_lh(vertical_fov, aspect_ratio, z_near, z_far)
+        }
+        Projection::Orthographic { height } => {
+            let half_height = height / 2f32;
+            let half_width = half_height * aspect_ratio;
+            Matrix4::orthographic_lh(-half_width, half_width, -half_height, half_height, z_near, z_far)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orthographic_depth_range() {
+        let z_near = 0.1f32;
+        let z_far = 100f32;
+        let proj = make_camera_proj(Projection::Orthographic { height: 10f32 }, 2f32, z_near, z_far);
+
+        let near = proj.project_point3(Vec3::new(0f32, 0f32, z_near));
+        let far = proj.project_point3(Vec3::new(0f32, 0f32, z_far));
+        assert!(near.z.abs() < 0.0001f32);
+        assert!((far.z - 1f32).abs() < 0.0001f32);
+
+        // The height covers the whole view, the width is derived from the aspect ratio.
+        let corner = proj.project_point3(Vec3::new(10f32, 5f32, z_near));
+        assert!((corner.x - 1f32).abs() < 0.0001f32);
+        assert!((corner.y - 1f32).abs() < 0.0001f32);
+    }
 }
@@ -1,6 +1,7 @@
 mod renderer;
 
 mod command;
+mod debug_draw;
 mod drawable;
 mod ecs;
 mod light;
@@ -9,13 +10,15 @@ mod renderer_resources;
 mod renderer_scene;
 mod renderer_plugin;
 mod renderer_culling;
+mod swapchain_state;
 
 pub(crate) mod passes;
 mod vertex;
 pub mod asset;
 
 pub use self::command::RendererCommand;
-pub use self::drawable::DrawablePart;
+pub use self::debug_draw::{DebugRenderer, DebugDrawData, DebugLineVertex};
+pub use self::drawable::{DrawablePart, DepthMode, TonemapOperator};
 use self::drawable::{
     RendererStaticDrawable,
     View,
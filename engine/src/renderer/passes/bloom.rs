@@ -0,0 +1,278 @@
+use std::sync::Arc;
+
+use sourcerenderer_core::{
+    Platform,
+    Vec2UI,
+};
+
+use crate::asset::AssetManager;
+use crate::renderer::asset::{ComputePipelineHandle, RendererAssetsReadOnly};
+use crate::renderer::drawable::View;
+use crate::renderer::render_path::RenderPassParameters;
+use crate::renderer::renderer_resources::{
+    HistoryResourceEntry,
+    RendererResources,
+};
+
+use crate::graphics::*;
+
+/// Dual filtering bloom as described in "Next Generation Post Processing in Call of Duty: Advanced Warfare".
+/// The scene color gets downsampled into a mip chain using a 13 tap filter,
+/// then the chain gets upsampled again using a tent filter and added onto the scene color.
+pub struct BloomPass {
+    downsample_pipeline: ComputePipelineHandle,
+    upsample_pipeline: ComputePipelineHandle,
+    composite_pipeline: ComputePipelineHandle,
+    mips: u32,
+}
+
+impl BloomPass {
+    pub const BLOOM_DOWNSAMPLE_TEXTURE_NAME: &'static str = "BloomDownsample";
+    pub const BLOOM_UPSAMPLE_TEXTURE_NAME: &'static str = "BloomUpsample";
+    const MAX_MIPS: u32 = 6;
+
+    pub fn new<P: Platform>(
+        resolution: Vec2UI,
+        resources: &mut RendererResources<P::GPUBackend>,
+        asset_manager: &Arc<AssetManager<P>>,
+    ) -> Self {
+        let downsample_pipeline = asset_manager.request_compute_pipeline("shaders/bloom_downsample.comp.json");
+        let upsample_pipeline = asset_manager.request_compute_pipeline("shaders/bloom_upsample.comp.json");
+        let composite_pipeline = asset_manager.request_compute_pipeline("shaders/bloom_composite.comp.json");
+
+        // The first mip is half the render resolution. Mip dimensions get floored, so non power of two sizes work as well.
+        let width = (resolution.x / 2).max(1);
+        let height = (resolution.y / 2).max(1);
+        let mips = (width.min(height).ilog2() + 1).min(Self::MAX_MIPS);
+
+        let mut texture_info = TextureInfo {
+            dimension: TextureDimension::Dim2D,
+            format: Format::RGBA16Float,
+            width,
+            height,
+            depth: 1,
+            mip_levels: mips,
+            array_length: 1,
+            samples: SampleCount::Samples1,
+            usage: TextureUsage::STORAGE | TextureUsage::SAMPLED,
+            supports_srgb: false,
+        };
+        resources.create_texture(Self::BLOOM_DOWNSAMPLE_TEXTURE_NAME, &texture_info, false);
+        // The smallest mip of the downsample chain is the starting point for the upsampling,
+        // so the upsample chain needs one mip less.
+        texture_info.mip_levels = (mips - 1).max(1);
+        resources.create_texture(Self::BLOOM_UPSAMPLE_TEXTURE_NAME, &texture_info, false);
+
+        Self {
+            downsample_pipeline,
+            upsample_pipeline,
+            composite_pipeline,
+            mips,
+        }
+    }
+
+    pub(super) fn is_ready<P: Platform>(&self, assets: &RendererAssetsReadOnly<'_, P>) -> bool {
+        assets.get_compute_pipeline(self.downsample_pipeline).is_some()
+        && assets.get_compute_pipeline(self.upsample_pipeline).is_some()
+        && assets.get_compute_pipeline(self.composite_pipeline).is_some()
+    }
+
+    fn mip_view_info(mip: u32) -> TextureViewInfo {
+        TextureViewInfo {
+            base_mip_level: mip,
+            mip_level_length: 1,
+            base_array_layer: 0,
+            array_layer_length: 1,
+            format: None,
+        }
+    }
+
+    /// Adds the bloom onto the HDR texture with the given name in place.
+    pub fn execute<P: Platform>(
+        &mut self,
+        cmd_buffer: &mut CommandBufferRecorder<P::GPUBackend>,
+        params: &RenderPassParameters<'_, P>,
+        hdr_texture_name: &str,
+        view: &View,
+    ) {
+        if view.bloom_intensity <= 0f32 || self.mips < 2 {
+            return;
+        }
+
+        let (width, height) = {
+            let info = params.resources.texture_info(Self::BLOOM_DOWNSAMPLE_TEXTURE_NAME);
+            (info.width, info.height)
+        };
+
+        cmd_buffer.begin_label("Bloom pass");
+
+        #[repr(C)]
+        #[derive(Debug, Clone)]
+        struct DownsampleConstants {
+            threshold: f32,
+            exposure: f32,
+            is_first_mip: u32,
+        }
+
+        let downsample_pipeline = params.assets.get_compute_pipeline(self.downsample_pipeline).unwrap();
+        cmd_buffer.set_pipeline(PipelineBinding::Compute(&downsample_pipeline));
+        for mip in 0..self.mips {
+            let input = if mip == 0 {
+                params.resources.access_view(
+                    cmd_buffer,
+                    hdr_texture_name,
+                    BarrierSync::COMPUTE_SHADER,
+                    BarrierAccess::SAMPLING_READ,
+                    TextureLayout::Sampled,
+                    false,
+                    &TextureViewInfo::default(),
+                    HistoryResourceEntry::Current,
+                ).clone()
+            } else {
+                params.resources.access_view(
+                    cmd_buffer,
+                    Self::BLOOM_DOWNSAMPLE_TEXTURE_NAME,
+                    BarrierSync::COMPUTE_SHADER,
+                    BarrierAccess::SAMPLING_READ,
+                    TextureLayout::Sampled,
+                    false,
+                    &Self::mip_view_info(mip - 1),
+                    HistoryResourceEntry::Current,
+                ).clone()
+            };
+            let output = params.resources.access_view(
+                cmd_buffer,
+                Self::BLOOM_DOWNSAMPLE_TEXTURE_NAME,
+                BarrierSync::COMPUTE_SHADER,
+                BarrierAccess::STORAGE_WRITE,
+                TextureLayout::Storage,
+                true,
+                &Self::mip_view_info(mip),
+                HistoryResourceEntry::Current,
+            ).clone();
+
+            cmd_buffer.set_push_constant_data(
+                &[DownsampleConstants {
+                    threshold: view.bloom_threshold,
+                    exposure: view.exposure,
+                    is_first_mip: (mip == 0) as u32,
+                }],
+                ShaderType::ComputeShader,
+            );
+            cmd_buffer.bind_storage_texture(BindingFrequency::VeryFrequent, 0, &output);
+            cmd_buffer.bind_sampling_view_and_sampler(
+                BindingFrequency::VeryFrequent,
+                1,
+                &input,
+                params.resources.linear_sampler(),
+            );
+            cmd_buffer.finish_binding();
+            let mip_width = (width >> mip).max(1);
+            let mip_height = (height >> mip).max(1);
+            cmd_buffer.dispatch((mip_width + 7) / 8, (mip_height + 7) / 8, 1);
+        }
+
+        let upsample_pipeline = params.assets.get_compute_pipeline(self.upsample_pipeline).unwrap();
+        cmd_buffer.set_pipeline(PipelineBinding::Compute(&upsample_pipeline));
+        for mip in (0..self.mips - 1).rev() {
+            let lower_mip = if mip == self.mips - 2 {
+                params.resources.access_view(
+                    cmd_buffer,
+                    Self::BLOOM_DOWNSAMPLE_TEXTURE_NAME,
+                    BarrierSync::COMPUTE_SHADER,
+                    BarrierAccess::SAMPLING_READ,
+                    TextureLayout::Sampled,
+                    false,
+                    &Self::mip_view_info(mip + 1),
+                    HistoryResourceEntry::Current,
+                ).clone()
+            } else {
+                params.resources.access_view(
+                    cmd_buffer,
+                    Self::BLOOM_UPSAMPLE_TEXTURE_NAME,
+                    BarrierSync::COMPUTE_SHADER,
+                    BarrierAccess::SAMPLING_READ,
+                    TextureLayout::Sampled,
+                    false,
+                    &Self::mip_view_info(mip + 1),
+                    HistoryResourceEntry::Current,
+                ).clone()
+            };
+            let downsampled_mip = params.resources.access_view(
+                cmd_buffer,
+                Self::BLOOM_DOWNSAMPLE_TEXTURE_NAME,
+                BarrierSync::COMPUTE_SHADER,
+                BarrierAccess::SAMPLING_READ,
+                TextureLayout::Sampled,
+                false,
+                &Self::mip_view_info(mip),
+                HistoryResourceEntry::Current,
+            ).clone();
+            let output = params.resources.access_view(
+                cmd_buffer,
+                Self::BLOOM_UPSAMPLE_TEXTURE_NAME,
+                BarrierSync::COMPUTE_SHADER,
+                BarrierAccess::STORAGE_WRITE,
+                TextureLayout::Storage,
+                true,
+                &Self::mip_view_info(mip),
+                HistoryResourceEntry::Current,
+            ).clone();
+
+            cmd_buffer.bind_storage_texture(BindingFrequency::VeryFrequent, 0, &output);
+            cmd_buffer.bind_sampling_view_and_sampler(
+                BindingFrequency::VeryFrequent,
+                1,
+                &lower_mip,
+                params.resources.linear_sampler(),
+            );
+            cmd_buffer.bind_sampling_view_and_sampler(
+                BindingFrequency::VeryFrequent,
+                2,
+                &downsampled_mip,
+                params.resources.linear_sampler(),
+            );
+            cmd_buffer.finish_binding();
+            let mip_width = (width >> mip).max(1);
+            let mip_height = (height >> mip).max(1);
+            cmd_buffer.dispatch((mip_width + 7) / 8, (mip_height + 7) / 8, 1);
+        }
+
+        let bloom = params.resources.access_view(
+            cmd_buffer,
+            Self::BLOOM_UPSAMPLE_TEXTURE_NAME,
+            BarrierSync::COMPUTE_SHADER,
+            BarrierAccess::SAMPLING_READ,
+            TextureLayout::Sampled,
+            false,
+            &Self::mip_view_info(0),
+            HistoryResourceEntry::Current,
+        );
+        let frame = params.resources.access_view(
+            cmd_buffer,
+            hdr_texture_name,
+            BarrierSync::COMPUTE_SHADER,
+            BarrierAccess::STORAGE_READ | BarrierAccess::STORAGE_WRITE,
+            TextureLayout::Storage,
+            false,
+            &TextureViewInfo::default(),
+            HistoryResourceEntry::Current,
+        );
+
+        let composite_pipeline = params.assets.get_compute_pipeline(self.composite_pipeline).unwrap();
+        cmd_buffer.set_pipeline(PipelineBinding::Compute(&composite_pipeline));
+        cmd_buffer.set_push_constant_data(&[view.bloom_intensity], ShaderType::ComputeShader);
+        cmd_buffer.bind_storage_texture(BindingFrequency::VeryFrequent, 0, &frame);
+        cmd_buffer.bind_sampling_view_and_sampler(
+            BindingFrequency::VeryFrequent,
+            1,
+            &bloom,
+            params.resources.linear_sampler(),
+        );
+        cmd_buffer.finish_binding();
+
+        let info = frame.texture().unwrap().info();
+        cmd_buffer.dispatch((info.width + 7) / 8, (info.height + 7) / 8, 1);
+        cmd_buffer.end_label();
+    }
+}
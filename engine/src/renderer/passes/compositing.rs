@@ -16,8 +16,6 @@ use crate::renderer::renderer_resources::{
 
 use crate::graphics::*;
 
-const USE_CAS: bool = true;
-
 pub struct CompositingPass {
     pipeline: ComputePipelineHandle,
 }
@@ -36,7 +34,7 @@ impl CompositingPass {
             Self::COMPOSITION_TEXTURE_NAME,
             &TextureInfo {
                 dimension: TextureDimension::Dim2D,
-                format: Format::RGBA8UNorm,
+                format: Format::RGBA16Float,
                 width: resolution.x,
                 height: resolution.y,
                 depth: 1,
@@ -75,7 +73,7 @@ impl CompositingPass {
 
         let ssr = params.resources.access_view(
             cmd_buffer,
-            SsrPass::SSR_TEXTURE_NAME,
+            SsrPass::<P::GPUBackend>::SSR_TEXTURE_NAME,
             BarrierSync::COMPUTE_SHADER,
             BarrierAccess::SAMPLING_READ,
             TextureLayout::Sampled,
@@ -100,20 +98,6 @@ impl CompositingPass {
         let pipeline = params.assets.get_compute_pipeline(self.pipeline).unwrap();
         cmd_buffer.set_pipeline(PipelineBinding::Compute(&pipeline));
 
-        #[repr(C)]
-        #[derive(Debug, Clone)]
-        struct Setup {
-            gamma: f32,
-            exposure: f32,
-        }
-        let setup_ubo = cmd_buffer.upload_dynamic_data(
-            &[Setup {
-                gamma: 2.2f32,
-                exposure: 0.01f32,
-            }],
-            BufferUsage::CONSTANT,
-        ).unwrap();
-
         cmd_buffer.bind_storage_texture(BindingFrequency::VeryFrequent, 0, &output);
         cmd_buffer.bind_sampling_view_and_sampler(
             BindingFrequency::VeryFrequent,
@@ -127,13 +111,6 @@ impl CompositingPass {
             &ssr,
             params.resources.linear_sampler(),
         );
-        cmd_buffer.bind_uniform_buffer(
-            BindingFrequency::VeryFrequent,
-            3,
-            BufferRef::Transient(&setup_ubo),
-            0,
-            WHOLE_BUFFER,
-        );
         cmd_buffer.finish_binding();
 
         let info = output.texture().unwrap().info();
@@ -187,10 +187,10 @@ impl<P: Platform> ConservativeRenderer<P> {
                 cluster_count,
                 _padding: 0,
                 swapchain_transform: swapchain.transform(),
-                halton_point: super::taa::scaled_halton_point(
+                halton_point: super::jitter::scaled_jitter_point(
+                    view,
                     rendering_resolution.x,
                     rendering_resolution.y,
-                    (frame % 8) as u32 + 1,
                 ),
                 rt_size: *rendering_resolution,
                 frame: frame as u32
@@ -247,6 +247,11 @@ impl<P: Platform> RenderPath<P> for ConservativeRenderer<P> {
         false
     }
 
+    fn supports_logarithmic_depth(&self) -> bool {
+        // The geometry passes write regular depth and SSAO, SSR and lighting reconstruct positions from it.
+        false
+    }
+
     fn write_occlusion_culling_results(&self, frame: u64, bitset: &mut Vec<u32>) {
         //self.occlusion.write_occlusion_query_results(frame, bitset);
     }
@@ -265,7 +270,6 @@ impl<P: Platform> RenderPath<P> for ConservativeRenderer<P> {
         && self.prepass.is_ready(&assets)
         && self.ssao.is_ready(&assets)
         && self.rt_passes.as_ref().map(|passes| passes.shadows.is_ready(&assets)).unwrap_or(true)
-        && self.geometry.is_ready(&assets)
         && self.blit_pass.is_ready(&assets)
         && self.taa.is_ready(&assets)
         && self.sharpen.is_ready(&assets)
@@ -368,7 +372,6 @@ impl<P: Platform> RenderPath<P> for ConservativeRenderer<P> {
             &mut cmd_buf,
             &params,
             swapchain.transform(),
-            frame_info.frame,
             &camera_buffer,
             &camera_history_buffer
         );
@@ -380,7 +383,8 @@ impl<P: Platform> RenderPath<P> for ConservativeRenderer<P> {
             &camera_buffer,
             self.blue_noise.frame(frame_info.frame),
             self.blue_noise.sampler(),
-            false
+            false,
+            main_view
         );
         if let Some(rt_passes) = self.rt_passes.as_mut() {
             rt_passes.shadows.execute(
@@ -407,7 +411,8 @@ impl<P: Platform> RenderPath<P> for ConservativeRenderer<P> {
             GeometryPass::<P>::GEOMETRY_PASS_TEXTURE_NAME,
             Prepass::DEPTH_TEXTURE_NAME,
             Some("TODO"),
-            false
+            false,
+            main_view,
         );
         self.sharpen
             .execute(&mut cmd_buf, &params);
@@ -479,6 +484,8 @@ impl<P: Platform> RenderPath<P> for ConservativeRenderer<P> {
 
     fn set_ui_data(&mut self, data: crate::ui::UIDrawData<<P as Platform>::GPUBackend>) {
     }
+
+    fn set_debug_draw_data(&mut self, _data: crate::renderer::DebugDrawData) {}
 }
 
 pub fn setup_frame<B: GPUBackend>(cmd_buf: &mut CommandBufferRecorder<B>, frame_bindings: &FrameBindings<B>) {
@@ -165,15 +165,12 @@ impl<P: Platform> GeometryPass<P> {
             render_target_formats: &[texture_info.format],
             depth_stencil_format: Format::D24S8
         };
-        let pipeline = asset_manager.request_graphics_pipeline(&pipeline_info);
+        // The renderer doesn't wait for the pipeline, the pass skips its draws until it's compiled.
+        let pipeline = asset_manager.request_graphics_pipeline_async(&pipeline_info);
 
         Self { sampler, pipeline }
     }
 
-    pub(super) fn is_ready(&self, assets: &RendererAssetsReadOnly<'_, P>) -> bool {
-        assets.get_graphics_pipeline(self.pipeline).is_some()
-    }
-
     #[profiling::function]
     pub(super) fn execute(
         &mut self,
@@ -215,7 +212,8 @@ impl<P: Platform> GeometryPass<P> {
         );
         let prepass_depth = &*prepass_depth_ref;
 
-        let ssao_ref = pass_params.resources.access_view(
+        let ssao_enabled = pass_params.scene.scene.views()[pass_params.scene.active_view_index].ssao_enabled;
+        let ssao_ref = ssao_enabled.then(|| pass_params.resources.access_view(
             cmd_buffer,
             SsaoPass::<P>::SSAO_TEXTURE_NAME,
             BarrierSync::FRAGMENT_SHADER | BarrierSync::COMPUTE_SHADER,
@@ -224,8 +222,8 @@ impl<P: Platform> GeometryPass<P> {
             false,
             &TextureViewInfo::default(),
             HistoryResourceEntry::Current,
-        );
-        let ssao = &*ssao_ref;
+        ));
+        let ssao = ssao_ref.as_deref().unwrap_or(&pass_params.assets.get_placeholder_texture_white().view);
 
         let light_bitmask_buffer_ref = pass_params.resources.access_buffer(
             cmd_buffer,
@@ -286,7 +284,12 @@ impl<P: Platform> GeometryPass<P> {
         const CHUNK_SIZE: usize = 128;
         let view = &pass_params.scene.scene.views()[pass_params.scene.active_view_index];
         let chunk_size = (view.drawable_parts.len() / 15).max(CHUNK_SIZE);
-        let pipeline = pass_params.assets.get_graphics_pipeline(self.pipeline).unwrap();
+        let Some(pipeline) = pass_params.assets.get_graphics_pipeline(self.pipeline) else {
+            // Still compiling, leave the cleared render target.
+            cmd_buffer.end_render_pass();
+            cmd_buffer.end_label();
+            return;
+        };
         let task_pool = bevy_tasks::ComputeTaskPool::get();
         let inner_cmd_buffers: Vec<FinishedCommandBuffer<P::GPUBackend>> = view.drawable_parts.par_chunk_map(task_pool, chunk_size, |_index, chunk| {
                 P::thread_memory_management_pool(|| {
@@ -379,6 +382,9 @@ impl<P: Platform> GeometryPass<P> {
 
                         let range = &mesh.parts[part.part_index];
                         let material = &materials[part.part_index];
+                        if material.is_water() {
+                            continue;
+                        }
 
                         if last_material.as_ref() != Some(material) {
                             #[repr(C)]
@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use sourcerenderer_core::{Matrix4, Platform, Vec2, Vec2I, Vec2UI};
+
+use crate::{asset::AssetManager, renderer::{asset::{GraphicsPipelineHandle, RendererAssetsReadOnly}, render_path::RenderPassParameters, renderer_resources::HistoryResourceEntry, DebugDrawData, DebugLineVertex}};
+use crate::graphics::*;
+use crate::renderer::asset::GraphicsPipelineInfo;
+
+pub struct DebugLinePass {
+    depth_tested_pipeline: GraphicsPipelineHandle,
+    on_top_pipeline: GraphicsPipelineHandle,
+}
+
+impl DebugLinePass {
+    pub fn new<P: Platform>(asset_manager: &Arc<AssetManager<P>>, output_format: Format, depth_format: Format) -> Self {
+        let shader_inputs = [
+            ShaderInputElement {
+                input_assembler_binding: 0,
+                location_vk_mtl: 0,
+                semantic_name_d3d: "aPos".to_string(),
+                semantic_index_d3d: 0,
+                offset: 0,
+                format: Format::RGB32Float,
+            },
+            ShaderInputElement {
+                input_assembler_binding: 0,
+                location_vk_mtl: 1,
+                semantic_name_d3d: "aColor".to_string(),
+                semantic_index_d3d: 0,
+                offset: 12,
+                format: Format::RGBA8UNorm,
+            },
+        ];
+        let input_assembler = [
+            InputAssemblerElement {
+                binding: 0,
+                input_rate: InputRate::PerVertex,
+                stride: std::mem::size_of::<DebugLineVertex>(),
+            },
+        ];
+        let blend_attachments = [
+            AttachmentBlendInfo {
+                blend_enabled: true,
+                src_color_blend_factor: BlendFactor::SrcAlpha,
+                dst_color_blend_factor: BlendFactor::OneMinusSrcAlpha,
+                color_blend_op: BlendOp::Add,
+                src_alpha_blend_factor: BlendFactor::One,
+                dst_alpha_blend_factor: BlendFactor::OneMinusSrcAlpha,
+                alpha_blend_op: BlendOp::Add,
+                write_mask: ColorComponents::all(),
+            }
+        ];
+
+        let mut pipeline_info = GraphicsPipelineInfo {
+            vs: "shaders/debug_lines.vert.json",
+            fs: Some("shaders/debug_lines.frag.json"),
+            vertex_layout: VertexLayoutInfo {
+                shader_inputs: &shader_inputs,
+                input_assembler: &input_assembler,
+            },
+            rasterizer: RasterizerInfo {
+                fill_mode: FillMode::Fill,
+                cull_mode: CullMode::None,
+                front_face: FrontFace::CounterClockwise,
+                sample_count: SampleCount::Samples1,
+            },
+            depth_stencil: DepthStencilInfo {
+                depth_test_enabled: true,
+                depth_write_enabled: false,
+                depth_func: CompareFunc::LessEqual,
+                stencil_enable: false,
+                stencil_read_mask: 0,
+                stencil_write_mask: 0,
+                stencil_front: StencilInfo::default(),
+                stencil_back: StencilInfo::default(),
+            },
+            blend: BlendInfo {
+                attachments: &blend_attachments,
+                ..Default::default()
+            },
+            primitive_type: PrimitiveType::Lines,
+            render_target_formats: &[output_format],
+            depth_stencil_format: depth_format
+        };
+        let depth_tested_pipeline = asset_manager.request_graphics_pipeline(&pipeline_info);
+
+        pipeline_info.depth_stencil.depth_test_enabled = false;
+        let on_top_pipeline = asset_manager.request_graphics_pipeline(&pipeline_info);
+
+        Self {
+            depth_tested_pipeline,
+            on_top_pipeline,
+        }
+    }
+
+    pub(super) fn is_ready<P: Platform>(&self, assets: &RendererAssetsReadOnly<'_, P>) -> bool {
+        assets.get_graphics_pipeline(self.depth_tested_pipeline).is_some()
+            && assets.get_graphics_pipeline(self.on_top_pipeline).is_some()
+    }
+
+    /// Draws the lines on top of the output texture.
+    /// The depth buffer needs to have the same size as the output texture.
+    pub fn execute<P: Platform>(
+        &mut self,
+        command_buffer: &mut CommandBufferRecorder<P::GPUBackend>,
+        pass_params: &RenderPassParameters<'_, P>,
+        output_texture_name: &str,
+        depth_name: &str,
+        view_proj: Matrix4,
+        draw: &DebugDrawData
+    ) {
+        if draw.is_empty() {
+            return;
+        }
+
+        command_buffer.begin_label("Debug lines");
+
+        let vertex_buffer = command_buffer.upload_dynamic_data(&draw.vertices, BufferUsage::VERTEX).unwrap();
+
+        let rtv = pass_params.resources.access_view(
+            command_buffer,
+            output_texture_name,
+            BarrierSync::RENDER_TARGET,
+            BarrierAccess::RENDER_TARGET_READ | BarrierAccess::RENDER_TARGET_WRITE,
+            TextureLayout::RenderTarget,
+            false,
+            &TextureViewInfo::default(),
+            HistoryResourceEntry::Current
+        );
+        let dsv = pass_params.resources.access_view(
+            command_buffer,
+            depth_name,
+            BarrierSync::EARLY_DEPTH | BarrierSync::LATE_DEPTH,
+            BarrierAccess::DEPTH_STENCIL_READ,
+            TextureLayout::DepthStencilRead,
+            false,
+            &TextureViewInfo::default(),
+            HistoryResourceEntry::Current
+        );
+
+        command_buffer.flush_barriers();
+
+        command_buffer.begin_render_pass(&RenderPassBeginInfo {
+            render_targets: &[
+                RenderTarget {
+                    view: &rtv,
+                    load_op: LoadOpColor::Load,
+                    store_op: StoreOp::<P::GPUBackend>::Store
+                }
+            ],
+            depth_stencil: Some(&DepthStencilAttachment {
+                view: &dsv,
+                load_op: LoadOpDepthStencil::Load,
+                store_op: StoreOp::<P::GPUBackend>::Store,
+            })
+        }, RenderpassRecordingMode::Commands);
+
+        let rtv_info = rtv.texture().unwrap().info();
+        let vertex_count = draw.vertices.len() as u32;
+        for (pipeline, first_vertex, vertex_count) in [
+            (self.depth_tested_pipeline, 0, draw.depth_tested_vertex_count),
+            (self.on_top_pipeline, draw.depth_tested_vertex_count, vertex_count - draw.depth_tested_vertex_count),
+        ] {
+            if vertex_count == 0 {
+                continue;
+            }
+
+            let pipeline = pass_params.assets.get_graphics_pipeline(pipeline).unwrap();
+            command_buffer.set_pipeline(PipelineBinding::Graphics(pipeline));
+            command_buffer.set_viewports(&[Viewport {
+                position: Vec2::new(0.0f32, 0.0f32),
+                extent: Vec2::new(rtv_info.width as f32, rtv_info.height as f32),
+                min_depth: 0.0f32,
+                max_depth: 1.0f32,
+            }]);
+            command_buffer.set_scissors(&[Scissor {
+                position: Vec2I::new(0, 0),
+                extent: Vec2UI::new(rtv_info.width, rtv_info.height),
+            }]);
+            command_buffer.set_vertex_buffer(0, BufferRef::Transient(&vertex_buffer), 0);
+            command_buffer.set_push_constant_data(&[view_proj], ShaderType::VertexShader);
+            command_buffer.finish_binding();
+            command_buffer.draw(vertex_count, first_vertex);
+        }
+
+        command_buffer.end_render_pass();
+        command_buffer.end_label();
+    }
+}
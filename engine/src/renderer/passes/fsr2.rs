@@ -29,7 +29,7 @@ use widestring::{
 
 use crate::asset::AssetManager;
 use crate::renderer::asset::{ComputePipelineHandle, RendererAssetsReadOnly};
-use crate::renderer::passes::taa::halton_point;
+use crate::renderer::passes::jitter::jitter_point;
 use crate::renderer::render_path::{FrameInfo, RenderPassParameters};
 use crate::renderer::renderer_resources::{
     HistoryResourceEntry,
@@ -40,7 +40,9 @@ use crate::graphics::*;
 pub struct Fsr2Pass<P: Platform> {
     device: Arc<Device<P::GPUBackend>>,
     context: FfxFsr2Context,
-    scratch_context: *mut AtomicRefCell<ScratchContext<P>>
+    scratch_context: *mut AtomicRefCell<ScratchContext<P>>,
+    resolution: Vec2UI,
+    reset: bool,
 }
 
 unsafe impl<P: Platform> Send for Fsr2Pass<P> {}
@@ -51,7 +53,7 @@ impl<P: Platform> Fsr2Pass<P> {
     pub fn new(
         device: &Arc<Device<P::GPUBackend>>,
         resources: &mut RendererResources<P::GPUBackend>,
-        _resolution: Vec2UI,
+        resolution: Vec2UI,
         swapchain: &Swapchain<P::GPUBackend>,
         asset_manager: &Arc<AssetManager<P>>
     ) -> Self {
@@ -79,11 +81,31 @@ impl<P: Platform> Fsr2Pass<P> {
             asset_manager: asset_manager.clone(),
             shaders
         }));
-        let context_size = std::mem::size_of::<ScratchContext<P>>();
         let context_ptr = Box::into_raw(scratch_context);
 
-        // TODO WTF did I do with the scratch buffer
-        // WTF
+        let context = Self::create_context(device, resources, context_ptr, resolution, swapchain);
+
+        Self {
+            device: device.clone(),
+            context,
+            scratch_context: context_ptr,
+            resolution,
+            reset: true,
+        }
+    }
+
+    /// Creates the FSR2 context and the output texture for the given render resolution and the size of the swapchain.
+    fn create_context(
+        device: &Arc<Device<P::GPUBackend>>,
+        resources: &mut RendererResources<P::GPUBackend>,
+        scratch_context: *mut AtomicRefCell<ScratchContext<P>>,
+        resolution: Vec2UI,
+        swapchain: &Swapchain<P::GPUBackend>
+    ) -> FfxFsr2Context {
+        let context_size = std::mem::size_of::<ScratchContext<P>>();
+
+        // The scratch buffer is only used to pass the scratch context to the callbacks,
+        // it is owned by the pass and outlives the FSR2 context.
         let interface: FfxFsr2Interface = FfxFsr2Interface {
             fpCreateBackendContext: Some(create_backend_context::<P>),
             fpDestroyBackendContext: Some(destroy_backend_context::<P>),
@@ -97,7 +119,7 @@ impl<P: Platform> Fsr2Pass<P> {
             fpDestroyPipeline: Some(destroy_pipeline::<P>),
             fpScheduleGpuJob: Some(schedule_render_job::<P>),
             fpExecuteGpuJobs: Some(execute_render_jobs::<P>),
-            scratchBuffer: context_ptr as *mut c_void,
+            scratchBuffer: scratch_context as *mut c_void,
             scratchBufferSize: context_size as usize,
         };
 
@@ -124,8 +146,8 @@ impl<P: Platform> Fsr2Pass<P> {
                 | FfxFsr2InitializationFlagBits_FFX_FSR2_ENABLE_HIGH_DYNAMIC_RANGE)
                 as u32,
             maxRenderSize: FfxDimensions2D {
-                width: swapchain.width(),
-                height: swapchain.height(),
+                width: resolution.x,
+                height: resolution.y,
             },
             displaySize: FfxDimensions2D {
                 width: swapchain.width(),
@@ -146,16 +168,29 @@ impl<P: Platform> Fsr2Pass<P> {
         }
         let context = unsafe { MaybeUninit::assume_init(context) };
         device.flush_transfers();
+        context
+    }
 
-        Self {
-            device: device.clone(),
-            context,
-            scratch_context: context_ptr,
+    /// Recreates the FSR2 context and the output texture for the new size of the swapchain.
+    /// The render resolution stays the same.
+    pub fn on_swapchain_changed(
+        &mut self,
+        resources: &mut RendererResources<P::GPUBackend>,
+        swapchain: &Swapchain<P::GPUBackend>
+    ) {
+        // FSR2 destroys its internal resources immediately
+        self.device.wait_for_idle();
+        unsafe {
+            let result = ffxFsr2ContextDestroy(&mut self.context as *mut FfxFsr2Context);
+            assert_eq!(result, FFX_OK);
         }
+        self.context = Self::create_context(&self.device, resources, self.scratch_context, self.resolution, swapchain);
+        self.reset = true;
     }
 
     pub(super) fn is_ready(&self, assets: &RendererAssetsReadOnly<'_, P>) -> bool {
-        false
+        let scratch_context = unsafe { &*self.scratch_context }.borrow();
+        scratch_context.shaders.values().all(|pipeline| assets.get_compute_pipeline(*pipeline).is_some())
     }
 
     pub fn execute(
@@ -255,7 +290,8 @@ impl<P: Platform> Fsr2Pass<P> {
             (output_texture.info().width as f32) / (output_texture.info().height as f32);
         let v_fov = 2f32 * ((view.camera_fov * 0.5f32).tan() * aspect_ratio).atan();
 
-        let halton_point = halton_point((frame.frame % 8u64) as u32); // TODO: use FSR2s built in jitter
+        // Has to match the jitter that the geometry passes apply to the projection.
+        let halton_point = jitter_point(view);
 
         unsafe {
             let desc = FfxFsr2DispatchDescription {
@@ -278,7 +314,7 @@ impl<P: Platform> Fsr2Pass<P> {
                 preExposure: 0.5f32,
                 frameTimeDelta: frame.delta.as_secs_f32() * 1000f32,
                 cameraFovAngleVertical: v_fov,
-                reset: false,
+                reset: self.reset || view.camera_cut,
                 jitterOffset: FfxFloatCoords2D {
                     x: halton_point.x,
                     y: halton_point.y,
@@ -303,6 +339,7 @@ impl<P: Platform> Fsr2Pass<P> {
             cmd_buffer.end_label();
             assert_eq!(result, FFX_OK);
         }
+        self.reset = false;
     }
 
     fn jitter(render_dimensions: Vec2UI, frame: u64) -> Vec2 {
@@ -751,9 +788,9 @@ unsafe extern "C" fn register_resource<P: Platform>(
 unsafe extern "C" fn destroy_backend_context<P: Platform>(
     backend_interface: *mut FfxFsr2Interface,
 ) -> FfxErrorCode {
-    let ptr = (*backend_interface).scratchBuffer as *mut AtomicRefCell<ScratchContext<P>>;
-    let _ = Arc::from_raw(ptr);
-
+    // The scratch context is owned by the pass and freed when it gets dropped.
+    let mut context = ScratchContext::<P>::from_interface(backend_interface);
+    context.jobs.clear();
     FFX_OK
 }
 
@@ -0,0 +1,43 @@
+use sourcerenderer_core::Vec2;
+
+use crate::renderer::drawable::View;
+
+/// The number of points of the Halton(2, 3) sequence that get used before it repeats.
+pub(crate) const JITTER_PHASE_COUNT: u32 = 8;
+
+fn halton_sequence(mut index: u32, base: u32) -> f32 {
+    let mut f = 1.0f32;
+    let mut r = 0.0f32;
+
+    while index > 0 {
+        f /= base as f32;
+        r += f * (index as f32 % (base as f32));
+        index = (index as f32 / (base as f32)).floor() as u32;
+    }
+
+    r
+}
+
+fn halton_point(index: u32) -> Vec2 {
+    Vec2::new(
+        halton_sequence(index, 2) - 0.5f32,
+        halton_sequence(index, 3) - 0.5f32,
+    )
+}
+
+/// The subpixel offset of the view for the current frame in pixels, both components are between -0.5 and 0.5.
+/// The geometry passes, TAA and FSR2 all have to use this so they agree on the jitter.
+/// The sequence restarts after a camera cut and there is no jitter if temporal anti aliasing is disabled.
+pub(crate) fn jitter_point(view: &View) -> Vec2 {
+    if !view.taa_enabled {
+        return Vec2::new(0f32, 0f32);
+    }
+    // Index 0 of the Halton sequence is 0, skip it.
+    halton_point(view.jitter_frame % JITTER_PHASE_COUNT + 1)
+}
+
+/// `jitter_point` in clip space units for a render target of the given size.
+pub(crate) fn scaled_jitter_point(view: &View, width: u32, height: u32) -> Vec2 {
+    let jitter = jitter_point(view);
+    Vec2::new(jitter.x / (width as f32 * 0.5f32), jitter.y / (height as f32 * 0.5f32))
+}
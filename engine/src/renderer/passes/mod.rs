@@ -1,17 +1,21 @@
+pub(crate) mod bloom;
 pub(crate) mod blue_noise;
 pub(crate) mod clustering;
 pub(crate) mod compositing;
 pub(crate) mod conservative;
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) mod fsr2;
+pub(crate) mod jitter;
 pub(crate) mod light_binning;
 pub(crate) mod prepass;
 pub(crate) mod sharpen;
 pub(crate) mod ssao;
 pub(crate) mod ssr;
 pub(crate) mod taa;
+pub(crate) mod tonemap;
 pub(crate) mod web;
 pub(crate) mod ui;
+pub(crate) mod debug_lines;
 pub(crate) mod blit;
 pub(crate) mod path_tracing;
 
@@ -17,7 +17,7 @@ use crate::renderer::drawable::View;
 use crate::renderer::light::DirectionalLight;
 use crate::renderer::passes::light_binning;
 use crate::renderer::passes::ssao::SsaoPass;
-use crate::renderer::passes::taa::scaled_halton_point;
+use crate::renderer::passes::jitter::scaled_jitter_point;
 use crate::renderer::asset::*;
 use crate::asset::*;
 use crate::renderer::renderer_resources::{
@@ -233,7 +233,7 @@ impl<P: Platform> GeometryPass<P> {
         _zero_texture_view_black: &Arc<TextureView<P::GPUBackend>>,
         lightmap: &Arc<RendererTexture<P::GPUBackend>>,
         swapchain_transform: Matrix4,
-        frame: u64,
+        _frame: u64,
         camera_buffer: &Arc<BufferSlice<P::GPUBackend>>,
         vertex_buffer: &Arc<BufferSlice<P::GPUBackend>>,
         index_buffer: &Arc<BufferSlice<P::GPUBackend>>,
@@ -294,7 +294,7 @@ impl<P: Platform> GeometryPass<P> {
         );
         let prepass_depth = &*prepass_depth_ref;
 
-        let ssao_ref = barriers.access_view(
+        let ssao_ref = view.ssao_enabled.then(|| barriers.access_view(
             cmd_buffer,
             SsaoPass::<P>::SSAO_TEXTURE_NAME,
             BarrierSync::FRAGMENT_SHADER | BarrierSync::COMPUTE_SHADER,
@@ -303,8 +303,8 @@ impl<P: Platform> GeometryPass<P> {
             false,
             &TextureViewInfo::default(),
             HistoryResourceEntry::Current,
-        );
-        let ssao = &*ssao_ref;
+        ));
+        let ssao = ssao_ref.as_deref().unwrap_or(zero_texture_view);
 
         let light_bitmask_buffer_ref = barriers.access_buffer(
             cmd_buffer,
@@ -368,7 +368,7 @@ impl<P: Platform> GeometryPass<P> {
         let cluster_z_bias = -(cluster_count.z as f32) * (near).log2() / (far / near).log2();
         let per_frame = FrameData {
             swapchain_transform,
-            jitter: scaled_halton_point(rtv_info.width, rtv_info.height, (frame % 8) as u32 + 1),
+            jitter: scaled_jitter_point(view, rtv_info.width, rtv_info.height),
             z_near: near,
             z_far: far,
             rt_size: Vec2UI::new(rtv_info.width, rtv_info.height),
@@ -473,7 +473,7 @@ impl<P: Platform> GeometryPass<P> {
         cmd_buffer.set_index_buffer(BufferRef::Regular(index_buffer), 0, IndexFormat::U32);
 
         cmd_buffer.finish_binding();
-        cmd_buffer.draw_indexed_indirect(BufferRef::Regular(&draw_buffer), 4, BufferRef::Regular(&draw_buffer), 0, DRAW_CAPACITY, 20);
+        cmd_buffer.draw_indexed_indirect_count(BufferRef::Regular(&draw_buffer), 4, BufferRef::Regular(&draw_buffer), 0, DRAW_CAPACITY, std::mem::size_of::<DrawIndexedIndirectCommand>() as u32);
 
         cmd_buffer.end_render_pass();
         cmd_buffer.end_label();
@@ -185,6 +185,9 @@ pub fn upload<P: Platform>(
 
                 let model_part_start = parts.len() as u32;
                 for (index, part) in mesh.parts.iter().enumerate() {
+                    if model_materials[index].is_water() {
+                        continue;
+                    }
                     let material_handle = model.material_handles()[index];
                     let material_index = if let Some(material_index) =
                         material_map.get(&material_handle)
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use smallvec::SmallVec;
 use crate::asset::AssetManager;
-use crate::graphics::{Barrier, BarrierAccess, BarrierSync, BarrierTextureRange, BindingFrequency, BufferRef, BufferUsage, Device, FinishedCommandBuffer, QueueSubmission, QueueType, Swapchain, SwapchainError, TextureInfo, TextureLayout, WHOLE_BUFFER};
+use crate::graphics::{Barrier, BarrierAccess, BarrierSync, BarrierTextureRange, BindingFrequency, BufferRef, BufferUsage, Device, FinishedCommandBuffer, Format, QueueSubmission, QueueType, Swapchain, SwapchainError, TextureInfo, TextureLayout, WHOLE_BUFFER};
 use crate::renderer::asset::RendererAssetsReadOnly;
 use sourcerenderer_core::{
     Matrix4,
@@ -19,15 +19,18 @@ use super::hi_z::HierarchicalZPass;
 use super::light_binning::LightBinningPass;
 use super::rt_shadows::RTShadowPass;
 use super::shading_pass::ShadingPass;
-use super::shadow_map::ShadowMapPass;
+use super::shadow_map::{ShadowMapPass, MAX_SHADOW_CASCADES};
 use super::sharpen::SharpenPass;
 use super::ssao::SsaoPass;
 use super::taa::TAAPass;
+use super::tonemap::TonemapPass;
 use super::visibility_buffer::VisibilityBufferPass;
 use crate::graphics::{GraphicsContext, CommandBufferRecorder};
 use crate::input::Input;
+use crate::renderer::passes::bloom::BloomPass;
 use crate::renderer::passes::blue_noise::BlueNoise;
 use crate::renderer::passes::compositing::CompositingPass;
+use crate::renderer::passes::debug_lines::DebugLinePass;
 use crate::renderer::passes::fsr2::Fsr2Pass;
 use crate::renderer::passes::modern::motion_vectors::MotionVectorPass;
 use crate::renderer::passes::ssr::SsrPass;
@@ -40,12 +43,14 @@ use crate::renderer::renderer_resources::{
     RendererResources,
 };
 use crate::renderer::passes::modern::gpu_scene::SceneBuffers;
+use crate::renderer::DebugDrawData;
 use crate::ui::UIDrawData;
 
 pub struct ModernRenderer<P: Platform> {
     device: Arc<Device<P::GPUBackend>>,
     barriers: RendererResources<P::GPUBackend>,
     ui_data: UIDrawData<P::GPUBackend>,
+    debug_draw_data: DebugDrawData,
 
     clustering_pass: ClusteringPass,
     light_binning_pass: LightBinningPass,
@@ -54,14 +59,17 @@ pub struct ModernRenderer<P: Platform> {
     rt_passes: Option<RTPasses<P>>,
     blue_noise: BlueNoise<P::GPUBackend>,
     hi_z_pass: HierarchicalZPass<P>,
-    ssr_pass: SsrPass,
+    ssr_pass: SsrPass<P::GPUBackend>,
     visibility_buffer: VisibilityBufferPass,
     shading_pass: ShadingPass<P>,
     compositing_pass: CompositingPass,
+    bloom_pass: BloomPass,
+    tonemap_pass: TonemapPass,
     motion_vector_pass: MotionVectorPass,
     anti_aliasing: AntiAliasing<P>,
     shadow_map_pass: ShadowMapPass<P>,
-    ui_pass: UIPass<P>
+    ui_pass: UIPass<P>,
+    debug_line_pass: DebugLinePass
 }
 
 enum AntiAliasing<P: Platform> {
@@ -130,7 +138,7 @@ impl<P: Platform> ModernRenderer<P> {
             &mut init_cmd_buffer,
             VisibilityBufferPass::DEPTH_TEXTURE_NAME,
         );
-        let ssr_pass = SsrPass::new::<P>(resolution, &mut barriers, asset_manager, true);
+        let ssr_pass = SsrPass::new::<P>(device, resolution, &mut barriers, asset_manager, true);
         let shading_pass = ShadingPass::<P>::new(
             device,
             resolution,
@@ -139,6 +147,8 @@ impl<P: Platform> ModernRenderer<P> {
             &mut init_cmd_buffer,
         );
         let compositing_pass = CompositingPass::new::<P>(resolution, &mut barriers, asset_manager);
+        let bloom_pass = BloomPass::new::<P>(resolution, &mut barriers, asset_manager);
+        let tonemap_pass = TonemapPass::new::<P>(resolution, &mut barriers, asset_manager);
         let motion_vector_pass =
             MotionVectorPass::new::<P>(&mut barriers, resolution, asset_manager);
 
@@ -157,9 +167,17 @@ impl<P: Platform> ModernRenderer<P> {
             AntiAliasing::TAA { taa, sharpen }
         };
 
-        let shadow_map = ShadowMapPass::new(device, &mut barriers, &mut init_cmd_buffer, asset_manager);
+        let shadow_map = ShadowMapPass::new(
+            device,
+            &mut barriers,
+            &mut init_cmd_buffer,
+            asset_manager,
+            ShadowMapPass::<P>::DEFAULT_CASCADES_COUNT,
+            ShadowMapPass::<P>::DEFAULT_RESOLUTION
+        );
 
         let ui_pass = UIPass::new(device, asset_manager);
+        let debug_line_pass = DebugLinePass::new(asset_manager, Format::RGBA8UNorm, Format::D24S8);
 
         init_cmd_buffer.flush_barriers();
         device.flush_transfers();
@@ -179,6 +197,7 @@ impl<P: Platform> ModernRenderer<P> {
             device: device.clone(),
             barriers,
             ui_data: UIDrawData::<P::GPUBackend>::default(),
+            debug_draw_data: DebugDrawData::default(),
             clustering_pass: clustering,
             light_binning_pass: light_binning,
             geometry_draw_prep: draw_prep,
@@ -190,10 +209,13 @@ impl<P: Platform> ModernRenderer<P> {
             visibility_buffer,
             shading_pass,
             compositing_pass,
+            bloom_pass,
+            tonemap_pass,
             motion_vector_pass,
             anti_aliasing,
             shadow_map_pass: shadow_map,
             ui_pass,
+            debug_line_pass,
         }
     }
 
@@ -234,7 +256,7 @@ impl<P: Platform> ModernRenderer<P> {
             / (view.far_plane / view.near_plane).log2();
 
         let cascades = self.shadow_map_pass.cascades();
-        let mut gpu_cascade_data: [ShadowCascade; 5] = Default::default();
+        let mut gpu_cascade_data: [ShadowCascade; MAX_SHADOW_CASCADES] = Default::default();
         for i in 0..cascades.len() {
             let gpu_cascade = &mut gpu_cascade_data[i];
             let cascade = &cascades[i];
@@ -264,7 +286,7 @@ impl<P: Platform> ModernRenderer<P> {
             swapchain_transform: Matrix4,
             halton_point: Vec2,
             rt_size: Vec2UI,
-            cascades: [ShadowCascade; 5],
+            cascades: [ShadowCascade; MAX_SHADOW_CASCADES],
             cascade_count: u32,
             frame: u32
         }
@@ -278,10 +300,10 @@ impl<P: Platform> ModernRenderer<P> {
                 cluster_count,
                 _padding: 0,
                 swapchain_transform: swapchain.transform(),
-                halton_point: super::taa::scaled_halton_point(
+                halton_point: super::jitter::scaled_jitter_point(
+                    view,
                     rendering_resolution.x,
                     rendering_resolution.y,
-                    (frame % 8) as u32 + 1,
                 ),
                 rt_size: *rendering_resolution,
                 cascade_count: cascades.len() as u32,
@@ -344,6 +366,11 @@ impl<P: Platform> RenderPath<P> for ModernRenderer<P> {
         true
     }
 
+    fn supports_logarithmic_depth(&self) -> bool {
+        // The visibility buffer, SSAO, SSR and shading reconstruct positions from regular depth.
+        false
+    }
+
     fn write_occlusion_culling_results(&self, _frame: u64, _bitset: &mut Vec<u32>) {}
 
     fn on_swapchain_changed(
@@ -351,6 +378,9 @@ impl<P: Platform> RenderPath<P> for ModernRenderer<P> {
         swapchain: &Swapchain<P::GPUBackend>,
     ) {
         // TODO: resize render targets
+        if let AntiAliasing::FSR2 { fsr } = &mut self.anti_aliasing {
+            fsr.on_swapchain_changed(&mut self.barriers, swapchain);
+        }
     }
 
     fn is_ready(&self, asset_manager: &Arc<AssetManager<P>>) -> bool {
@@ -365,6 +395,8 @@ impl<P: Platform> RenderPath<P> for ModernRenderer<P> {
         && self.visibility_buffer.is_ready(&assets)
         && self.shading_pass.is_ready(&assets)
         && self.compositing_pass.is_ready(&assets)
+        && self.bloom_pass.is_ready(&assets)
+        && self.tonemap_pass.is_ready(&assets)
         && self.motion_vector_pass.is_ready(&assets)
         && match &self.anti_aliasing {
             AntiAliasing::TAA { taa, sharpen } => taa.is_ready(&assets) && sharpen.is_ready(&assets),
@@ -372,6 +404,7 @@ impl<P: Platform> RenderPath<P> for ModernRenderer<P> {
         }
         && self.shadow_map_pass.is_ready(&assets)
         && self.ui_pass.is_ready(&assets)
+        && self.debug_line_pass.is_ready(&assets)
     }
 
     #[profiling::function]
@@ -471,7 +504,8 @@ impl<P: Platform> RenderPath<P> for ModernRenderer<P> {
             &camera_buffer,
             self.blue_noise.frame(frame_info.frame),
             self.blue_noise.sampler(),
-            true
+            true,
+            main_view
         );
         if let Some(rt_passes) = self.rt_passes.as_mut() {
             let blue_noise = &self.blue_noise.frame(frame_info.frame);
@@ -508,19 +542,33 @@ impl<P: Platform> RenderPath<P> for ModernRenderer<P> {
             ShadingPass::<P>::SHADING_TEXTURE_NAME,
             VisibilityBufferPass::DEPTH_TEXTURE_NAME,
             true,
+            main_view,
         );
         self.compositing_pass.execute(
             &mut cmd_buf,
             &params,
             ShadingPass::<P>::SHADING_TEXTURE_NAME,
         );
+        self.bloom_pass.execute(
+            &mut cmd_buf,
+            &params,
+            CompositingPass::COMPOSITION_TEXTURE_NAME,
+            main_view,
+        );
+        self.tonemap_pass.execute(
+            &mut cmd_buf,
+            &params,
+            CompositingPass::COMPOSITION_TEXTURE_NAME,
+            main_view,
+            swapchain.color_space(),
+        );
 
         let output_texture_name = match &mut self.anti_aliasing {
             AntiAliasing::FSR2 { fsr } => {
                 fsr.execute(
                     &mut cmd_buf,
                     &params,
-                    CompositingPass::COMPOSITION_TEXTURE_NAME,
+                    TonemapPass::TONEMAPPED_TEXTURE_NAME,
                     VisibilityBufferPass::DEPTH_TEXTURE_NAME,
                     MotionVectorPass::MOTION_TEXTURE_NAME,
                     frame_info,
@@ -531,16 +579,30 @@ impl<P: Platform> RenderPath<P> for ModernRenderer<P> {
                 taa.execute(
                     &mut cmd_buf,
                     &params,
-                    CompositingPass::COMPOSITION_TEXTURE_NAME,
+                    TonemapPass::TONEMAPPED_TEXTURE_NAME,
                     VisibilityBufferPass::DEPTH_TEXTURE_NAME,
                     None,
                     true,
+                    main_view,
                 );
                 sharpen.execute(&mut cmd_buf, &params);
                 SharpenPass::SHAPENED_TEXTURE_NAME
             }
         };
 
+        // The lines are only depth tested against the depth buffer if it has the size of the output.
+        let debug_draw_data = std::mem::take(&mut self.debug_draw_data);
+        if resolution.x == swapchain.width() && resolution.y == swapchain.height() {
+            self.debug_line_pass.execute(
+                &mut cmd_buf,
+                &params,
+                output_texture_name,
+                VisibilityBufferPass::DEPTH_TEXTURE_NAME,
+                main_view.proj_matrix * main_view.view_matrix,
+                &debug_draw_data
+            );
+        }
+
         self.ui_pass.execute(&mut cmd_buf, &params, output_texture_name, &self.ui_data);
 
         let output_texture = params.resources.access_texture(
@@ -592,4 +654,8 @@ impl<P: Platform> RenderPath<P> for ModernRenderer<P> {
     fn set_ui_data(&mut self, data: crate::ui::UIDrawData<<P as Platform>::GPUBackend>) {
         self.ui_data = data;
     }
+
+    fn set_debug_draw_data(&mut self, data: DebugDrawData) {
+        self.debug_draw_data = data;
+    }
 }
@@ -142,7 +142,8 @@ impl<P: Platform> ShadingPass<P> {
             HistoryResourceEntry::Current,
         );
 
-        let ssao = pass_params.resources.access_view(
+        let ssao_enabled = pass_params.scene.scene.views()[pass_params.scene.active_view_index].ssao_enabled;
+        let ssao_ref = ssao_enabled.then(|| pass_params.resources.access_view(
             cmd_buffer,
             SsaoPass::<P>::SSAO_TEXTURE_NAME,
             BarrierSync::FRAGMENT_SHADER | BarrierSync::COMPUTE_SHADER,
@@ -151,7 +152,8 @@ impl<P: Platform> ShadingPass<P> {
             false,
             &TextureViewInfo::default(),
             HistoryResourceEntry::Current,
-        );
+        ));
+        let ssao = ssao_ref.as_deref().unwrap_or(&pass_params.assets.get_placeholder_texture_white().view);
 
         let rt_shadows: Ref<Arc<TextureView<P::GPUBackend>>>;
         let shadows = if pass_params.device.supports_ray_tracing() {
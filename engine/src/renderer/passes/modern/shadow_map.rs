@@ -21,7 +21,6 @@ use crate::graphics::*;
 
 /*
 TODO:
-- implement multiple cascades
 - filter shadows
 - research shadow map ray marching (UE5)
 - cache shadows of static objects and copy every frame
@@ -29,11 +28,14 @@ TODO:
 - multiple lights
 */
 
+/// The size of the cascade array in the frame constants of the shaders.
+pub const MAX_SHADOW_CASCADES: usize = 5;
+
 pub struct ShadowMapPass<P: Platform> {
     pipeline: GraphicsPipelineHandle,
     draw_prep_pipeline: ComputePipelineHandle,
     shadow_map_res: u32,
-    cascades: SmallVec<[ShadowMapCascade; 5]>,
+    cascades: SmallVec<[ShadowMapCascade; MAX_SHADOW_CASCADES]>,
     _marker: PlatformPhantomData<P>,
 }
 
@@ -49,14 +51,21 @@ impl<P: Platform> ShadowMapPass<P> {
     pub const SHADOW_MAP_NAME: &'static str = "ShadowMap";
     pub const DRAW_BUFFER_NAME: &'static str = "ShadowMapDraws";
     pub const VISIBLE_BITFIELD: &'static str = "ShadowMapVisibility";
+
+    pub const DEFAULT_CASCADES_COUNT: u32 = 4;
+    pub const DEFAULT_RESOLUTION: u32 = 2048;
+
+    /// Creates the shadow map array with one layer per cascade.
+    /// The cascade count is clamped to `MAX_SHADOW_CASCADES`.
     pub fn new(
         _device: &Arc<Device<P::GPUBackend>>,
         resources: &mut RendererResources<P::GPUBackend>,
         _init_cmd_buffer: &mut CommandBufferRecorder<P::GPUBackend>,
         asset_manager: &Arc<AssetManager<P>>,
+        cascades_count: u32,
+        shadow_map_res: u32,
     ) -> Self {
-        let shadow_map_res = 4096;
-        let cascades_count = 5;
+        let cascades_count = cascades_count.clamp(1, MAX_SHADOW_CASCADES as u32);
 
         resources.create_texture(
             &Self::SHADOW_MAP_NAME,
@@ -182,7 +191,7 @@ impl<P: Platform> ShadowMapPass<P> {
 
             let i = cascade_index as u32 + 1u32;
             let m = self.cascades.len() as u32;
-            let log_split = (z_min * (z_max / z_min)).powf(i as f32 / m as f32);
+            let log_split = z_min * (z_max / z_min).powf(i as f32 / m as f32);
             let uniform_split = z_min + (z_max - z_min) * (i as f32 / m as f32);
             let z_end = log_split * lambda + (1.0f32 - lambda) * uniform_split;
 
@@ -263,13 +272,13 @@ impl<P: Platform> ShadowMapPass<P> {
         cmd_buffer: &mut CommandBufferRecorder<P::GPUBackend>,
         pass_params: &RenderPassParameters<'_, P>
     ) {
-        cmd_buffer.begin_label("Shadow map");
-
         let light = pass_params.scene.scene.directional_lights().first();
         if light.is_none() {
             return;
         }
 
+        cmd_buffer.begin_label("Shadow map");
+
         let draw_buffer = pass_params.resources.access_buffer(
             cmd_buffer,
             Self::DRAW_BUFFER_NAME,
@@ -331,7 +340,7 @@ impl<P: Platform> ShadowMapPass<P> {
             cmd_buffer.set_push_constant_data(&[cascade.view_proj], ShaderType::VertexShader);
 
             cmd_buffer.finish_binding();
-            cmd_buffer.draw_indexed_indirect(BufferRef::Regular(&draw_buffer), 4, BufferRef::Regular(&draw_buffer), 0, DRAW_CAPACITY, 20);
+            cmd_buffer.draw_indexed_indirect_count(BufferRef::Regular(&draw_buffer), 4, BufferRef::Regular(&draw_buffer), 0, DRAW_CAPACITY, std::mem::size_of::<DrawIndexedIndirectCommand>() as u32);
 
             cmd_buffer.end_render_pass();
 
@@ -389,7 +398,15 @@ impl<P: Platform> ShadowMapPass<P> {
         let mut min = Vec3::new(-radius, -radius, -radius);
         let mut max = Vec3::new(radius, radius, radius);
 
-        let mut light_view = Matrix4::look_at_lh(center - light.direction, center, Vec3::new(0f32, 1f32, 0f32));
+        // look_at breaks down if the light points along the up vector, so pick another one for lights pointing straight up or down.
+        let light_direction = light.direction.normalize();
+        let up = if light_direction.dot(Vec3::Y).abs() > 0.99f32 {
+            Vec3::Z
+        } else {
+            Vec3::Y
+        };
+        // Move the eye back far enough for the depth range to cover the whole bounding sphere
+        let mut light_view = Matrix4::look_at_lh(center - light_direction * radius, center, up);
 
         // Snap center to texel
         let texels_per_unit = (shadow_map_res as f32) / (radius * 2.0f32);
@@ -399,7 +416,7 @@ impl<P: Platform> ShadowMapPass<P> {
         view_space_center.x = view_space_center.x.floor();
         view_space_center.y = view_space_center.y.floor();
         center = snapping_view_inv.transform_point3(view_space_center);
-        light_view = Matrix4::look_at_lh(center - light.direction, center, Vec3::new(0f32, 1f32, 0f32));
+        light_view = Matrix4::look_at_lh(center - light_direction * radius, center, up);
 
         // Snap left, right, top. bottom to texel
         let world_units_per_texel = (radius * 2f32) / (shadow_map_res as f32);
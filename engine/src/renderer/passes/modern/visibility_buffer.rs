@@ -238,7 +238,7 @@ impl VisibilityBufferPass {
         cmd_buffer.set_index_buffer(params.scene.index_buffer, 0, IndexFormat::U32);
 
         cmd_buffer.finish_binding();
-        cmd_buffer.draw_indexed_indirect(BufferRef::Regular(&draw_buffer), 4, BufferRef::Regular(&draw_buffer), 0, DRAW_CAPACITY, 20);
+        cmd_buffer.draw_indexed_indirect_count(BufferRef::Regular(&draw_buffer), 4, BufferRef::Regular(&draw_buffer), 0, DRAW_CAPACITY, std::mem::size_of::<DrawIndexedIndirectCommand>() as u32);
 
         cmd_buffer.end_render_pass();
         cmd_buffer.end_label();
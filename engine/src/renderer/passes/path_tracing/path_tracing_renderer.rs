@@ -159,10 +159,10 @@ impl<P: Platform> PathTracingRenderer<P> {
                 cluster_count,
                 _padding: 0,
                 swapchain_transform: swapchain.transform(),
-                halton_point: crate::renderer::passes::taa::scaled_halton_point(
+                halton_point: crate::renderer::passes::jitter::scaled_jitter_point(
+                    view,
                     rendering_resolution.x,
                     rendering_resolution.y,
-                    (frame % 8) as u32 + 1,
                 ),
                 rt_size: *rendering_resolution,
                 cascade_count: 0u32,
@@ -225,6 +225,10 @@ impl<P: Platform> RenderPath<P> for PathTracingRenderer<P> {
         true
     }
 
+    fn supports_logarithmic_depth(&self) -> bool {
+        false
+    }
+
     fn write_occlusion_culling_results(&self, _frame: u64, _bitset: &mut Vec<u32>) {}
 
     fn on_swapchain_changed(
@@ -333,4 +337,6 @@ impl<P: Platform> RenderPath<P> for PathTracingRenderer<P> {
     fn set_ui_data(&mut self, data: crate::ui::UIDrawData<<P as Platform>::GPUBackend>) {
         self.ui_data = data;
     }
+
+    fn set_debug_draw_data(&mut self, _data: crate::renderer::DebugDrawData) {}
 }
@@ -13,7 +13,7 @@ use crate::asset::AssetManager;
 use crate::graphics::CommandBufferRecorder;
 
 use crate::renderer::asset::{GraphicsPipelineHandle, GraphicsPipelineInfo, RendererAssetsReadOnly};
-use crate::renderer::passes::taa::scaled_halton_point;
+use crate::renderer::passes::jitter::scaled_jitter_point;
 use crate::renderer::render_path::RenderPassParameters;
 use crate::renderer::renderer_resources::{
     HistoryResourceEntry,
@@ -144,7 +144,6 @@ impl Prepass {
         cmd_buffer: &mut CommandBufferRecorder<P::GPUBackend>,
         pass_params: &RenderPassParameters<'_, P>,
         swapchain_transform: Matrix4,
-        frame: u64,
         camera_buffer: &TransientBufferSlice<P::GPUBackend>,
         camera_history_buffer: &TransientBufferSlice<P::GPUBackend>
     ) {
@@ -179,7 +178,7 @@ impl Prepass {
         let info = depth_buffer.texture().unwrap().info();
         let per_frame = FrameData {
             swapchain_transform,
-            halton_point: scaled_halton_point(info.width, info.height, (frame % 8) as u32 + 1),
+            halton_point: scaled_jitter_point(view, info.width, info.height),
         };
         let transform_constant_buffer =
             cmd_buffer.upload_dynamic_data(&[per_frame], BufferUsage::CONSTANT).unwrap();
@@ -248,6 +247,12 @@ impl Prepass {
                         continue;
                     }
                     let model = model.unwrap();
+                    let is_water = model.material_handles()
+                        .get(part.part_index)
+                        .is_some_and(|material| assets.get_material(*material).is_water());
+                    if is_water {
+                        continue;
+                    }
                     let mesh = assets.get_mesh(model.mesh_handle());
                     if mesh.is_none() {
                         log::info!("Skipping draw because of missing mesh");
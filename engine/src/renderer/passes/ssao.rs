@@ -10,6 +10,7 @@ use sourcerenderer_core::{
     Vec4,
 };
 
+use crate::renderer::drawable::View;
 use crate::renderer::render_path::RenderPassParameters;
 use crate::renderer::renderer_resources::{
     HistoryResourceEntry,
@@ -17,9 +18,18 @@ use crate::renderer::renderer_resources::{
 };
 use crate::renderer::asset::*;
 
+/// Screen space ambient occlusion at half resolution.
+///
+/// Reads the depth buffer that gets passed to `execute`, normals are reconstructed from it.
+/// The blur reprojects the result of the last frame, using the motion vectors
+/// or the primitive ids and barycentrics of the visibility buffer.
+///
+/// When it's disabled in the view, the pass doesn't record any work or barriers
+/// and lighting passes should bind a white texture instead of `SSAO_TEXTURE_NAME`.
 pub struct SsaoPass<P: Platform> {
     pipeline: ComputePipelineHandle,
     kernel: Arc<BufferSlice<P::GPUBackend>>,
+    blur_horizontal_pipeline: ComputePipelineHandle,
     blur_pipeline: ComputePipelineHandle,
 }
 
@@ -27,9 +37,46 @@ fn lerp(a: f32, b: f32, f: f32) -> f32 {
     a + f * (b - a)
 }
 
+const SSAO_INTERNAL_TEXTURE_NAME: &str = "SSAO";
+const SSAO_BLUR_HORIZONTAL_TEXTURE_NAME: &str = "SSAOBlurHorizontal";
+const SSAO_TEXTURE_NAME: &str = "SSAOBlurred";
+
+/// The textures `SsaoPass::execute` accesses, in the order it accesses them.
+/// Empty when SSAO is disabled in the view.
+fn texture_accesses<'a>(
+    view: &View,
+    depth_name: &'a str,
+    motion_name: Option<&'a str>,
+    visibility_buffer: bool,
+) -> Vec<(&'a str, BarrierAccess, HistoryResourceEntry)> {
+    if !view.ssao_enabled {
+        return Vec::new();
+    }
+
+    let mut accesses = vec![
+        (SSAO_INTERNAL_TEXTURE_NAME, BarrierAccess::STORAGE_WRITE, HistoryResourceEntry::Current),
+        (depth_name, BarrierAccess::SAMPLING_READ, HistoryResourceEntry::Current),
+    ];
+    if !visibility_buffer {
+        accesses.push((motion_name.unwrap(), BarrierAccess::SAMPLING_READ, HistoryResourceEntry::Current));
+    } else {
+        accesses.push((super::modern::VisibilityBufferPass::PRIMITIVE_ID_TEXTURE_NAME, BarrierAccess::STORAGE_READ, HistoryResourceEntry::Current));
+        accesses.push((super::modern::VisibilityBufferPass::BARYCENTRICS_TEXTURE_NAME, BarrierAccess::STORAGE_READ, HistoryResourceEntry::Current));
+    }
+    accesses.push((SSAO_INTERNAL_TEXTURE_NAME, BarrierAccess::SAMPLING_READ, HistoryResourceEntry::Current));
+    if view.ssao_blur_radius != 0 {
+        accesses.push((SSAO_BLUR_HORIZONTAL_TEXTURE_NAME, BarrierAccess::STORAGE_WRITE, HistoryResourceEntry::Current));
+        accesses.push((SSAO_BLUR_HORIZONTAL_TEXTURE_NAME, BarrierAccess::SAMPLING_READ, HistoryResourceEntry::Current));
+    }
+    accesses.push((SSAO_TEXTURE_NAME, BarrierAccess::STORAGE_WRITE, HistoryResourceEntry::Current));
+    accesses.push((SSAO_TEXTURE_NAME, BarrierAccess::SAMPLING_READ, HistoryResourceEntry::Past));
+    accesses
+}
+
 impl<P: Platform> SsaoPass<P> {
-    const SSAO_INTERNAL_TEXTURE_NAME: &'static str = "SSAO";
-    pub const SSAO_TEXTURE_NAME: &'static str = "SSAOBlurred";
+    pub const SSAO_TEXTURE_NAME: &'static str = SSAO_TEXTURE_NAME;
+    pub const MAX_SAMPLES: u32 = 64;
+    pub const MAX_BLUR_RADIUS: u32 = 8;
 
     pub fn new(
         device: &Arc<Device<P::GPUBackend>>,
@@ -39,7 +86,24 @@ impl<P: Platform> SsaoPass<P> {
         visibility_buffer: bool,
     ) -> Self {
         resources.create_texture(
-            Self::SSAO_INTERNAL_TEXTURE_NAME,
+            SSAO_INTERNAL_TEXTURE_NAME,
+            &TextureInfo {
+                dimension: TextureDimension::Dim2D,
+                format: Format::R16Float,
+                width: resolution.x / 2,
+                height: resolution.y / 2,
+                depth: 1,
+                mip_levels: 1,
+                array_length: 1,
+                samples: SampleCount::Samples1,
+                usage: TextureUsage::STORAGE | TextureUsage::SAMPLED,
+                supports_srgb: false,
+            },
+            false,
+        );
+
+        resources.create_texture(
+            SSAO_BLUR_HORIZONTAL_TEXTURE_NAME,
             &TextureInfo {
                 dimension: TextureDimension::Dim2D,
                 format: Format::R16Float,
@@ -76,7 +140,9 @@ impl<P: Platform> SsaoPass<P> {
 
         // TODO: Clear history texture
 
-        let kernel = Self::create_hemisphere(device, 64);
+        let kernel = Self::create_hemisphere(device, Self::MAX_SAMPLES);
+
+        let blur_horizontal_pipeline = asset_manager.request_compute_pipeline("shaders/ssao_blur_horizontal.comp.json");
 
         let blur_pipeline = asset_manager.request_compute_pipeline(if !visibility_buffer {
             "shaders/ssao_blur.comp.json"
@@ -87,6 +153,7 @@ impl<P: Platform> SsaoPass<P> {
         Self {
             pipeline,
             kernel,
+            blur_horizontal_pipeline,
             blur_pipeline,
         }
     }
@@ -127,7 +194,9 @@ impl<P: Platform> SsaoPass<P> {
     }
 
     pub(super) fn is_ready(&self, assets: &RendererAssetsReadOnly<'_, P>) -> bool {
-        assets.get_compute_pipeline(self.pipeline).is_some() && assets.get_compute_pipeline(self.blur_pipeline).is_some()
+        assets.get_compute_pipeline(self.pipeline).is_some()
+        && assets.get_compute_pipeline(self.blur_horizontal_pipeline).is_some()
+        && assets.get_compute_pipeline(self.blur_pipeline).is_some()
     }
 
     pub fn execute(
@@ -140,10 +209,16 @@ impl<P: Platform> SsaoPass<P> {
         blue_noise_view: &Arc<TextureView<P::GPUBackend>>,
         blue_noise_sampler: &Arc<Sampler<P::GPUBackend>>,
         visibility_buffer: bool,
+        view: &View,
     ) {
+        if texture_accesses(view, depth_name, motion_name, visibility_buffer).is_empty() {
+            // SSAO is disabled, don't record any work or barriers.
+            return;
+        }
+
         let ssao_uav = pass_params.resources.access_view(
             cmd_buffer,
-            Self::SSAO_INTERNAL_TEXTURE_NAME,
+            SSAO_INTERNAL_TEXTURE_NAME,
             BarrierSync::COMPUTE_SHADER,
             BarrierAccess::STORAGE_WRITE,
             TextureLayout::Storage,
@@ -203,10 +278,26 @@ impl<P: Platform> SsaoPass<P> {
             ));
         }
 
+        #[repr(C)]
+        #[derive(Debug, Clone)]
+        struct SsaoConstants {
+            radius: f32,
+            intensity: f32,
+            sample_count: u32,
+        }
+
         cmd_buffer.begin_label("SSAO pass");
         let pipeline = pass_params.assets.get_compute_pipeline(self.pipeline).unwrap();
         cmd_buffer.set_pipeline(PipelineBinding::Compute(&pipeline));
         cmd_buffer.flush_barriers();
+        cmd_buffer.set_push_constant_data(
+            &[SsaoConstants {
+                radius: view.ssao_radius,
+                intensity: view.ssao_intensity,
+                sample_count: view.ssao_samples.clamp(1, Self::MAX_SAMPLES),
+            }],
+            ShaderType::ComputeShader,
+        );
         cmd_buffer.bind_uniform_buffer(
             BindingFrequency::VeryFrequent,
             0,
@@ -237,9 +328,10 @@ impl<P: Platform> SsaoPass<P> {
         );
 
         std::mem::drop(ssao_uav);
+        let blur_radius = view.ssao_blur_radius.min(Self::MAX_BLUR_RADIUS);
         let ssao_srv = pass_params.resources.access_view(
             cmd_buffer,
-            Self::SSAO_INTERNAL_TEXTURE_NAME,
+            SSAO_INTERNAL_TEXTURE_NAME,
             BarrierSync::COMPUTE_SHADER,
             BarrierAccess::SAMPLING_READ,
             TextureLayout::Sampled,
@@ -248,6 +340,53 @@ impl<P: Platform> SsaoPass<P> {
             HistoryResourceEntry::Current,
         );
 
+        // Without a spatial blur, the vertical pass only does the temporal accumulation on the unblurred SSAO.
+        let blur_input = if blur_radius != 0 {
+            let horizontal_uav = pass_params.resources.access_view(
+                cmd_buffer,
+                SSAO_BLUR_HORIZONTAL_TEXTURE_NAME,
+                BarrierSync::COMPUTE_SHADER,
+                BarrierAccess::STORAGE_WRITE,
+                TextureLayout::Storage,
+                true,
+                &TextureViewInfo::default(),
+                HistoryResourceEntry::Current,
+            );
+            let blur_horizontal_pipeline = pass_params.assets.get_compute_pipeline(self.blur_horizontal_pipeline).unwrap();
+            cmd_buffer.set_pipeline(PipelineBinding::Compute(&blur_horizontal_pipeline));
+            cmd_buffer.flush_barriers();
+            cmd_buffer.set_push_constant_data(&[blur_radius as i32], ShaderType::ComputeShader);
+            cmd_buffer.bind_storage_texture(BindingFrequency::VeryFrequent, 0, &*horizontal_uav);
+            cmd_buffer.bind_sampling_view_and_sampler(
+                BindingFrequency::VeryFrequent,
+                1,
+                &*ssao_srv,
+                pass_params.resources.linear_sampler(),
+            );
+            cmd_buffer.finish_binding();
+            let horizontal_info = horizontal_uav.texture().unwrap().info();
+            cmd_buffer.dispatch(
+                (horizontal_info.width + 7) / 8,
+                (horizontal_info.height + 7) / 8,
+                horizontal_info.depth,
+            );
+            std::mem::drop(horizontal_uav);
+            std::mem::drop(ssao_srv);
+
+            pass_params.resources.access_view(
+                cmd_buffer,
+                SSAO_BLUR_HORIZONTAL_TEXTURE_NAME,
+                BarrierSync::COMPUTE_SHADER,
+                BarrierAccess::SAMPLING_READ,
+                TextureLayout::Sampled,
+                false,
+                &TextureViewInfo::default(),
+                HistoryResourceEntry::Current,
+            )
+        } else {
+            ssao_srv
+        };
+
         let blurred_uav = pass_params.resources.access_view(
             cmd_buffer,
             Self::SSAO_TEXTURE_NAME,
@@ -273,11 +412,12 @@ impl<P: Platform> SsaoPass<P> {
         let blur_pipeline = pass_params.assets.get_compute_pipeline(self.blur_pipeline).unwrap();
         cmd_buffer.set_pipeline(PipelineBinding::Compute(&blur_pipeline));
         cmd_buffer.flush_barriers();
+        cmd_buffer.set_push_constant_data(&[blur_radius as i32], ShaderType::ComputeShader);
         cmd_buffer.bind_storage_texture(BindingFrequency::VeryFrequent, 0, &*blurred_uav);
         cmd_buffer.bind_sampling_view_and_sampler(
             BindingFrequency::VeryFrequent,
             1,
-            &*ssao_srv,
+            &*blur_input,
             pass_params.resources.linear_sampler(),
         );
         cmd_buffer.bind_sampling_view_and_sampler(
@@ -311,3 +451,36 @@ impl<P: Platform> SsaoPass<P> {
         cmd_buffer.end_label();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_ssao_accesses_nothing() {
+        let view = View {
+            ssao_enabled: false,
+            ..Default::default()
+        };
+        assert!(texture_accesses(&view, "depth", Some("motion"), false).is_empty());
+        assert!(texture_accesses(&view, "depth", None, true).is_empty());
+    }
+
+    #[test]
+    fn enabled_ssao_accesses() {
+        let mut view = View {
+            ssao_blur_radius: 0,
+            ..Default::default()
+        };
+        let accesses = texture_accesses(&view, "depth", Some("motion"), false);
+        assert!(accesses.contains(&("depth", BarrierAccess::SAMPLING_READ, HistoryResourceEntry::Current)));
+        assert!(accesses.contains(&("motion", BarrierAccess::SAMPLING_READ, HistoryResourceEntry::Current)));
+        assert!(accesses.iter().all(|(name, _, _)| *name != SSAO_BLUR_HORIZONTAL_TEXTURE_NAME));
+        assert_eq!(accesses.last(), Some(&(SSAO_TEXTURE_NAME, BarrierAccess::SAMPLING_READ, HistoryResourceEntry::Past)));
+
+        view.ssao_blur_radius = 2;
+        let accesses = texture_accesses(&view, "depth", None, true);
+        assert!(accesses.iter().all(|(name, _, _)| *name != "motion"));
+        assert!(accesses.contains(&(SSAO_BLUR_HORIZONTAL_TEXTURE_NAME, BarrierAccess::STORAGE_WRITE, HistoryResourceEntry::Current)));
+    }
+}
@@ -7,6 +7,7 @@ use sourcerenderer_core::{
 };
 
 use crate::asset::AssetManager;
+use crate::renderer::drawable::View;
 use crate::renderer::passes::modern::VisibilityBufferPass;
 use crate::renderer::render_path::RenderPassParameters;
 use crate::renderer::renderer_resources::{
@@ -18,14 +19,22 @@ use crate::renderer::asset::ComputePipelineHandle;
 
 use crate::graphics::*;
 
-pub struct SsrPass {
+/// Screen space reflections, the result gets blended onto the scene by the compositing pass.
+///
+/// Reads the lit scene color, the depth buffer and the visibility buffer for the material roughness.
+/// Rays that miss or leave the screen fall back to the environment map of the scene if it has one.
+/// The result is accumulated over multiple frames using the history of `SSR_TEXTURE_NAME`.
+pub struct SsrPass<B: GPUBackend> {
     pipeline: ComputePipelineHandle,
+    /// Bound when the scene doesn't have an environment map or it isn't loaded yet.
+    fallback_environment_map: Arc<TextureView<B>>,
 }
 
-impl SsrPass {
+impl<B: GPUBackend> SsrPass<B> {
     pub const SSR_TEXTURE_NAME: &'static str = "SSR";
 
-    pub fn new<P: Platform>(
+    pub fn new<P: Platform<GPUBackend = B>>(
+        device: &Arc<Device<B>>,
         resolution: Vec2UI,
         resources: &mut RendererResources<P::GPUBackend>,
         asset_manager: &Arc<AssetManager<P>>,
@@ -45,25 +54,56 @@ impl SsrPass {
                 usage: TextureUsage::STORAGE | TextureUsage::SAMPLED,
                 supports_srgb: false,
             },
-            false,
+            true,
         );
 
         let pipeline = asset_manager.request_compute_pipeline("shaders/ssr.comp.json");
 
-        Self { pipeline }
+        let fallback_texture = device.create_texture(
+            &TextureInfo {
+                dimension: TextureDimension::Cube,
+                format: Format::RGBA8UNorm,
+                width: 1,
+                height: 1,
+                depth: 1,
+                mip_levels: 1,
+                array_length: 6,
+                samples: SampleCount::Samples1,
+                usage: TextureUsage::SAMPLED | TextureUsage::INITIAL_COPY,
+                supports_srgb: false,
+            },
+            Some("SSRFallbackEnvironmentMap"),
+        ).unwrap();
+        for face in 0..6 {
+            device.init_texture(&[0u8; 4], &fallback_texture, 0, face).unwrap();
+        }
+        let fallback_environment_map = device.create_texture_view(
+            &fallback_texture,
+            &TextureViewInfo {
+                array_layer_length: 6,
+                ..Default::default()
+            },
+            Some("SSRFallbackEnvironmentMapView"),
+        );
+
+        Self {
+            pipeline,
+            fallback_environment_map,
+        }
     }
 
     pub(super) fn is_ready<P: Platform>(&self, assets: &RendererAssetsReadOnly<'_, P>) -> bool {
         assets.get_compute_pipeline(self.pipeline).is_some()
     }
 
-    pub fn execute<P: Platform>(
+    pub fn execute<P: Platform<GPUBackend = B>>(
         &mut self,
-        cmd_buffer: &mut CommandBufferRecorder<P::GPUBackend>,
+        cmd_buffer: &mut CommandBufferRecorder<B>,
         params: &RenderPassParameters<'_, P>,
         input_name: &str,
         depth_name: &str,
         visibility_buffer: bool,
+        view: &View,
     ) {
         // TODO: merge back into the original image
         // TODO: specularity map
@@ -101,10 +141,26 @@ impl SsrPass {
             HistoryResourceEntry::Current,
         );
 
+        let history_srv = params.resources.access_view(
+            cmd_buffer,
+            Self::SSR_TEXTURE_NAME,
+            BarrierSync::COMPUTE_SHADER,
+            BarrierAccess::SAMPLING_READ,
+            TextureLayout::Sampled,
+            false,
+            &TextureViewInfo::default(),
+            HistoryResourceEntry::Past,
+        );
+
+        let environment_map = params.scene.scene.environment_map()
+            .and_then(|handle| params.assets.get_texture_opt(handle))
+            .filter(|texture| texture.view.texture().map_or(false, |texture| texture.info().dimension == TextureDimension::Cube))
+            .map(|texture| &texture.view);
+
         let mut ids =
-            Option::<Ref<Arc<TextureView<P::GPUBackend>>>>::None;
+            Option::<Ref<Arc<TextureView<B>>>>::None;
         let mut barycentrics =
-            Option::<Ref<Arc<TextureView<P::GPUBackend>>>>::None;
+            Option::<Ref<Arc<TextureView<B>>>>::None;
 
         if visibility_buffer {
             ids = Some(params.resources.access_view(
@@ -134,6 +190,24 @@ impl SsrPass {
         cmd_buffer.begin_label("SSR pass");
         cmd_buffer.set_pipeline(PipelineBinding::Compute(&pipeline));
         cmd_buffer.flush_barriers();
+
+        #[repr(C)]
+        #[derive(Debug, Clone)]
+        struct SsrConstants {
+            intensity: f32,
+            roughness_cutoff: f32,
+            max_steps: u32,
+            has_environment_map: u32,
+        }
+        cmd_buffer.set_push_constant_data(
+            &[SsrConstants {
+                intensity: view.ssr_intensity,
+                roughness_cutoff: view.ssr_roughness_cutoff,
+                max_steps: view.ssr_steps,
+                has_environment_map: environment_map.is_some() as u32,
+            }],
+            ShaderType::ComputeShader,
+        );
         cmd_buffer.bind_storage_texture(BindingFrequency::VeryFrequent, 0, &ssr_uav);
         cmd_buffer.bind_sampling_view_and_sampler(
             BindingFrequency::VeryFrequent,
@@ -159,6 +233,18 @@ impl SsrPass {
                 barycentrics.as_ref().unwrap(),
             );
         }
+        cmd_buffer.bind_sampling_view_and_sampler(
+            BindingFrequency::VeryFrequent,
+            5,
+            &*history_srv,
+            params.resources.linear_sampler(),
+        );
+        cmd_buffer.bind_sampling_view_and_sampler(
+            BindingFrequency::VeryFrequent,
+            6,
+            environment_map.unwrap_or(&self.fallback_environment_map),
+            params.resources.linear_sampler(),
+        );
         cmd_buffer.finish_binding();
         let ssr_info = ssr_uav.texture().unwrap().info();
         cmd_buffer.dispatch(
@@ -3,12 +3,12 @@ use std::sync::Arc;
 
 use sourcerenderer_core::{
     Platform,
-    Vec2,
     Vec2UI,
 };
 
 use crate::asset::AssetManager;
 use crate::renderer::asset::{ComputePipelineHandle, RendererAssetsReadOnly};
+use crate::renderer::drawable::View;
 use crate::renderer::render_path::RenderPassParameters;
 use crate::renderer::renderer_resources::{
     HistoryResourceEntry,
@@ -17,35 +17,6 @@ use crate::renderer::renderer_resources::{
 use crate::graphics::*;
 use crate::asset::*;
 
-pub(crate) fn scaled_halton_point(width: u32, height: u32, index: u32) -> Vec2 {
-    let width_frac = 1.0f32 / (width as f32 * 0.5f32);
-    let height_frac = 1.0f32 / (height as f32 * 0.5f32);
-    let mut halton_point = halton_point(index);
-    halton_point.x *= width_frac;
-    halton_point.y *= height_frac;
-    halton_point
-}
-
-pub(crate) fn halton_point(index: u32) -> Vec2 {
-    Vec2::new(
-        halton_sequence(index, 2) - 0.5f32,
-        halton_sequence(index, 3) - 0.5f32,
-    )
-}
-
-pub(crate) fn halton_sequence(mut index: u32, base: u32) -> f32 {
-    let mut f = 1.0f32;
-    let mut r = 0.0f32;
-
-    while index > 0 {
-        f /= base as f32;
-        r += f * (index as f32 % (base as f32));
-        index = (index as f32 / (base as f32)).floor() as u32;
-    }
-
-    r
-}
-
 pub struct TAAPass {
     pipeline: ComputePipelineHandle,
 }
@@ -96,6 +67,7 @@ impl TAAPass {
         depth_name: &str,
         motion_name: Option<&str>,
         visibility_buffer: bool,
+        view: &View,
     ) {
         cmd_buf.begin_label("TAA pass");
 
@@ -183,6 +155,26 @@ impl TAAPass {
             HistoryResourceEntry::Current,
         );
 
+        let depth_history_srv = pass_params.resources.access_view(
+            cmd_buf,
+            depth_name,
+            BarrierSync::COMPUTE_SHADER,
+            BarrierAccess::SAMPLING_READ,
+            TextureLayout::Sampled,
+            false,
+            &TextureViewInfo::default(),
+            HistoryResourceEntry::Past,
+        );
+
+        #[repr(C)]
+        #[derive(Debug, Clone)]
+        struct TAAConstants {
+            z_near: f32,
+            z_far: f32,
+            logarithmic_depth: u32,
+            reset_history: u32,
+        }
+
         let pipeline = pass_params.assets.get_compute_pipeline(self.pipeline).unwrap();
         cmd_buf.set_pipeline(PipelineBinding::Compute(&pipeline));
         cmd_buf.bind_sampling_view_and_sampler(
@@ -219,6 +211,22 @@ impl TAAPass {
                 &barycentrics_view.unwrap(),
             );
         }
+        cmd_buf.bind_sampling_view_and_sampler(
+            BindingFrequency::VeryFrequent,
+            6,
+            &*depth_history_srv,
+            pass_params.resources.nearest_sampler(),
+        );
+        // With TAA turned off the pass still runs to keep the output texture valid, it just doesn't use the history.
+        cmd_buf.set_push_constant_data(
+            &[TAAConstants {
+                z_near: view.near_plane,
+                z_far: view.far_plane,
+                logarithmic_depth: view.uses_logarithmic_depth() as u32,
+                reset_history: (view.camera_cut || !view.taa_enabled) as u32,
+            }],
+            ShaderType::ComputeShader,
+        );
         cmd_buf.finish_binding();
 
         let info = taa_uav.texture().unwrap().info();
@@ -0,0 +1,220 @@
+use std::sync::Arc;
+
+use sourcerenderer_core::{
+    Platform,
+    Vec2UI,
+};
+
+use crate::asset::AssetManager;
+use crate::renderer::asset::{ComputePipelineHandle, RendererAssetsReadOnly};
+use crate::renderer::drawable::{TonemapOperator, View};
+use crate::renderer::render_path::RenderPassParameters;
+use crate::renderer::renderer_resources::{
+    HistoryResourceEntry,
+    RendererResources,
+};
+
+use crate::graphics::*;
+
+pub struct TonemapPass {
+    pipeline: ComputePipelineHandle,
+}
+
+impl TonemapPass {
+    pub const TONEMAPPED_TEXTURE_NAME: &'static str = "Tonemapped";
+
+    pub fn new<P: Platform>(
+        resolution: Vec2UI,
+        resources: &mut RendererResources<P::GPUBackend>,
+        asset_manager: &Arc<AssetManager<P>>,
+    ) -> Self {
+        let pipeline = asset_manager.request_compute_pipeline("shaders/tonemap.comp.json");
+
+        resources.create_texture(
+            Self::TONEMAPPED_TEXTURE_NAME,
+            &TextureInfo {
+                dimension: TextureDimension::Dim2D,
+                // 10 bits so PQ encoded output doesn't band.
+                format: Format::RGB10A2UNorm,
+                width: resolution.x,
+                height: resolution.y,
+                depth: 1,
+                mip_levels: 1,
+                array_length: 1,
+                samples: SampleCount::Samples1,
+                usage: TextureUsage::STORAGE | TextureUsage::SAMPLED,
+                supports_srgb: false,
+            },
+            false,
+        );
+
+        Self { pipeline }
+    }
+
+    pub(super) fn is_ready<P: Platform>(&self, assets: &RendererAssetsReadOnly<'_, P>) -> bool {
+        assets.get_compute_pipeline(self.pipeline).is_some()
+    }
+
+    pub fn execute<P: Platform>(
+        &mut self,
+        cmd_buffer: &mut CommandBufferRecorder<P::GPUBackend>,
+        params: &RenderPassParameters<'_, P>,
+        input_name: &str,
+        view: &View,
+        color_space: ColorSpace,
+    ) {
+        let input_image = params.resources.access_view(
+            cmd_buffer,
+            input_name,
+            BarrierSync::COMPUTE_SHADER,
+            BarrierAccess::SAMPLING_READ,
+            TextureLayout::Sampled,
+            false,
+            &TextureViewInfo::default(),
+            HistoryResourceEntry::Current,
+        );
+
+        let output = params.resources.access_view(
+            cmd_buffer,
+            Self::TONEMAPPED_TEXTURE_NAME,
+            BarrierSync::COMPUTE_SHADER,
+            BarrierAccess::STORAGE_WRITE,
+            TextureLayout::Storage,
+            true,
+            &TextureViewInfo::default(),
+            HistoryResourceEntry::Current,
+        );
+
+        cmd_buffer.begin_label("Tonemapping pass");
+
+        let pipeline = params.assets.get_compute_pipeline(self.pipeline).unwrap();
+        cmd_buffer.set_pipeline(PipelineBinding::Compute(&pipeline));
+
+        #[repr(C)]
+        #[derive(Debug, Clone)]
+        struct Setup {
+            gamma: f32,
+            exposure: f32,
+            operator: u32,
+            pq_output: u32,
+        }
+        let setup_ubo = cmd_buffer.upload_dynamic_data(
+            &[Setup {
+                gamma: 2.2f32,
+                exposure: view.exposure,
+                operator: match view.tonemap_operator {
+                    TonemapOperator::Reinhard => 0,
+                    TonemapOperator::Aces => 1,
+                    TonemapOperator::AgX => 2,
+                },
+                // HDR10 swapchains expect PQ encoded BT.2020 colors, everything else gets gamma corrected LDR colors.
+                pq_output: (color_space == ColorSpace::Hdr10) as u32,
+            }],
+            BufferUsage::CONSTANT,
+        ).unwrap();
+
+        cmd_buffer.bind_storage_texture(BindingFrequency::VeryFrequent, 0, &output);
+        cmd_buffer.bind_sampling_view_and_sampler(
+            BindingFrequency::VeryFrequent,
+            1,
+            &input_image,
+            params.resources.linear_sampler(),
+        );
+        cmd_buffer.bind_uniform_buffer(
+            BindingFrequency::VeryFrequent,
+            2,
+            BufferRef::Transient(&setup_ubo),
+            0,
+            WHOLE_BUFFER,
+        );
+        cmd_buffer.finish_binding();
+
+        let info = output.texture().unwrap().info();
+        cmd_buffer.dispatch((info.width + 7) / 8, (info.height + 7) / 8, 1);
+        cmd_buffer.end_label();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sourcerenderer_core::{
+        Matrix3,
+        Vec3,
+    };
+
+    use super::*;
+
+    /// CPU version of the LDR path of tonemap.comp.glsl, keep the two in sync.
+    /// The shader itself and the PQ output for HDR10 swapchains aren't covered by it.
+    fn tonemap_reference(color: Vec3, exposure: f32, operator: TonemapOperator, gamma: f32) -> Vec3 {
+        let x = color * exposure;
+        let tonemapped = match operator {
+            TonemapOperator::Reinhard => x / (Vec3::ONE + x),
+            TonemapOperator::Aces => {
+                let (a, b, c, d, e) = (2.51f32, 0.03f32, 2.43f32, 0.59f32, 0.14f32);
+                ((x * (a * x + b)) / (x * (c * x + d) + e)).clamp(Vec3::ZERO, Vec3::ONE)
+            }
+            TonemapOperator::AgX => agx_reference(x),
+        };
+        tonemapped.powf(1f32 / gamma)
+    }
+
+    // The matrices are copied from the shader as they are
+    #[allow(clippy::excessive_precision)]
+    fn agx_reference(x: Vec3) -> Vec3 {
+        let agx_inset = Matrix3::from_cols_array(&[
+            0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+            0.0784335999999992, 0.878468636469772, 0.0784336,
+            0.0792237451477643, 0.0791661274605434, 0.879142973793104,
+        ]);
+        let agx_outset = Matrix3::from_cols_array(&[
+            1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+            -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+            -0.0990297440797205, -0.0989611768448433, 1.15107367264116,
+        ]);
+        let min_ev = -12.47393f32;
+        let max_ev = 4.026069f32;
+
+        let x = agx_inset * x;
+        let x = Vec3::new(x.x.max(1e-10f32).log2(), x.y.max(1e-10f32).log2(), x.z.max(1e-10f32).log2())
+            .clamp(Vec3::splat(min_ev), Vec3::splat(max_ev));
+        let x = (x - min_ev) / (max_ev - min_ev);
+        let x2 = x * x;
+        let x4 = x2 * x2;
+        let x = 15.5f32 * x4 * x2 - 40.14f32 * x4 * x + 31.96f32 * x4 - 6.868f32 * x2 * x + 0.4298f32 * x2 + 0.1191f32 * x
+            - 0.00232f32;
+        let x = agx_outset * x;
+        x.max(Vec3::ZERO).powf(2.2f32)
+    }
+
+    #[test]
+    fn reinhard_mid_gray() {
+        let mid_gray = Vec3::splat(0.18f32);
+        // 0.18 / 1.18 = 0.1525 before the gamma correction
+        let linear = tonemap_reference(mid_gray, 1f32, TonemapOperator::Reinhard, 1f32);
+        assert!(linear.abs_diff_eq(Vec3::splat(0.152542f32), 0.00001f32));
+        let encoded = tonemap_reference(mid_gray, 1f32, TonemapOperator::Reinhard, 2.2f32);
+        assert!(encoded.abs_diff_eq(Vec3::splat(0.425416f32), 0.00001f32));
+        // Exposure scales the input before the curve
+        let exposed = tonemap_reference(Vec3::splat(0.09f32), 2f32, TonemapOperator::Reinhard, 2.2f32);
+        assert!(exposed.abs_diff_eq(encoded, 0.00001f32));
+    }
+
+    #[test]
+    fn operators_stay_in_range() {
+        for operator in [TonemapOperator::Reinhard, TonemapOperator::Aces, TonemapOperator::AgX] {
+            let mut previous = -1f32;
+            for i in 0..64 {
+                let value = 0.01f32 * 1.25f32.powi(i);
+                let tonemapped = tonemap_reference(Vec3::splat(value), 1f32, operator, 2.2f32);
+                assert!(tonemapped.x >= previous, "{:?} isn't monotonic at {}", operator, value);
+                assert!(tonemapped.x <= 1.0001f32, "{:?} is out of range at {}", operator, value);
+                previous = tonemapped.x;
+            }
+            let black = tonemap_reference(Vec3::ZERO, 1f32, operator, 2.2f32);
+            assert!(black.max_element() < 0.05f32, "{:?} doesn't keep black dark: {:?}", operator, black);
+        }
+        let aces_mid_gray = tonemap_reference(Vec3::splat(0.18f32), 1f32, TonemapOperator::Aces, 1f32);
+        assert!(aces_mid_gray.abs_diff_eq(Vec3::splat(0.266899f32), 0.00001f32));
+    }
+}
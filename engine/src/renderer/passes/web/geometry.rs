@@ -22,6 +22,8 @@ use crate::renderer::renderer_resources::{
 use crate::renderer::renderer_scene::RendererScene;
 use crate::renderer::asset::{GraphicsPipelineHandle, GraphicsPipelineInfo};
 
+use super::water::WaterPass;
+
 use crate::graphics::*;
 
 pub struct GeometryPass<P: Platform> {
@@ -152,15 +154,12 @@ impl<P: Platform> GeometryPass<P> {
             render_target_formats: &[swapchain.format()],
             depth_stencil_format: Format::D32
         };
-        let pipeline = asset_manager.request_graphics_pipeline(&pipeline_info);
+        // The renderer doesn't wait for the pipeline, the pass skips its draws until it's compiled.
+        let pipeline = asset_manager.request_graphics_pipeline_async(&pipeline_info);
 
         Self { pipeline, sampler: Arc::new(sampler) }
     }
 
-    pub(super) fn is_ready(&self, assets: &RendererAssetsReadOnly<'_, P>) -> bool {
-        assets.get_graphics_pipeline(self.pipeline).is_some()
-    }
-
     pub(super) fn execute(
         &mut self,
         cmd_buffer: &mut CommandBufferRecorder<P::GPUBackend>,
@@ -197,16 +196,71 @@ impl<P: Platform> GeometryPass<P> {
             HistoryResourceEntry::Current,
         );
 
+        self.draw(cmd_buffer, scene, view, camera_buffer, backbuffer, &dsv, width, height, assets);
+    }
+
+    /// Renders the scene into one of the offscreen textures of the water pass.
+    /// Water surfaces are skipped just like in the main view.
+    pub(super) fn execute_offscreen(
+        &mut self,
+        cmd_buffer: &mut CommandBufferRecorder<P::GPUBackend>,
+        scene: &RendererScene<P::GPUBackend>,
+        view: &View,
+        camera_buffer: &TransientBufferSlice<P::GPUBackend>,
+        resources: &RendererResources<P::GPUBackend>,
+        color_texture_name: &str,
+        depth_texture_name: &str,
+        width: u32,
+        height: u32,
+        assets: &RendererAssetsReadOnly<'_, P>
+    ) {
+        let rtv = resources.access_view(
+            cmd_buffer,
+            color_texture_name,
+            BarrierSync::RENDER_TARGET,
+            BarrierAccess::RENDER_TARGET_WRITE | BarrierAccess::RENDER_TARGET_READ,
+            TextureLayout::RenderTarget,
+            true,
+            &TextureViewInfo::default(),
+            HistoryResourceEntry::Current,
+        );
+
+        let dsv = resources.access_view(
+            cmd_buffer,
+            depth_texture_name,
+            BarrierSync::EARLY_DEPTH | BarrierSync::LATE_DEPTH,
+            BarrierAccess::DEPTH_STENCIL_READ | BarrierAccess::DEPTH_STENCIL_WRITE,
+            TextureLayout::DepthStencilReadWrite,
+            true,
+            &TextureViewInfo::default(),
+            HistoryResourceEntry::Current,
+        );
+
+        self.draw(cmd_buffer, scene, view, camera_buffer, &rtv, &dsv, width, height, assets);
+    }
+
+    fn draw(
+        &mut self,
+        cmd_buffer: &mut CommandBufferRecorder<P::GPUBackend>,
+        scene: &RendererScene<P::GPUBackend>,
+        view: &View,
+        camera_buffer: &TransientBufferSlice<P::GPUBackend>,
+        rtv: &Arc<TextureView<P::GPUBackend>>,
+        dsv: &Arc<TextureView<P::GPUBackend>>,
+        width: u32,
+        height: u32,
+        assets: &RendererAssetsReadOnly<'_, P>
+    ) {
         cmd_buffer.flush_barriers();
         cmd_buffer.begin_render_pass(
             &RenderPassBeginInfo {
                 render_targets: &[RenderTarget {
-                    view: &backbuffer,
+                    view: rtv,
                     load_op: LoadOpColor::Clear(ClearColor::BLACK),
                     store_op: StoreOp::<P::GPUBackend>::Store,
                 }],
                 depth_stencil: Some(&DepthStencilAttachment {
-                    view: &dsv,
+                    view: dsv,
                     load_op: LoadOpDepthStencil::Clear(ClearDepthStencilValue::DEPTH_ONE),
                     store_op: StoreOp::<P::GPUBackend>::Store,
                 })
@@ -214,7 +268,11 @@ impl<P: Platform> GeometryPass<P> {
             RenderpassRecordingMode::Commands,
         );
 
-        let pipeline: &Arc<GraphicsPipeline<<P as Platform>::GPUBackend>> = assets.get_graphics_pipeline(self.pipeline).expect("Pipeline is not compiled yet");
+        let Some(pipeline) = assets.get_graphics_pipeline(self.pipeline) else {
+            // Still compiling, leave the cleared render targets.
+            cmd_buffer.end_render_pass();
+            return;
+        };
         cmd_buffer.set_pipeline(PipelineBinding::Graphics(&pipeline));
         cmd_buffer.set_viewports(&[Viewport {
             position: Vec2::new(0.0f32, 0.0f32),
@@ -234,7 +292,6 @@ impl<P: Platform> GeometryPass<P> {
         let parts = &view.drawable_parts;
         for part in parts {
             let drawable = &drawables[part.drawable_index];
-            cmd_buffer.set_push_constant_data(&[Matrix4::from(drawable.transform)], ShaderType::VertexShader);
             let model = assets.get_model(drawable.model);
             if model.is_none() {
                 log::info!("Skipping draw because of missing model");
@@ -254,6 +311,11 @@ impl<P: Platform> GeometryPass<P> {
                 .collect();
             let range = &mesh.parts[part.part_index];
             let material = &materials[part.part_index];
+            if material.shader_name() == WaterPass::<P>::SHADER_NAME {
+                // Drawn by the water pass
+                continue;
+            }
+            cmd_buffer.set_push_constant_data(&[Matrix4::from(drawable.transform)], ShaderType::VertexShader);
             let albedo_value = material.get("albedo").unwrap();
             match albedo_value {
                 RendererMaterialValue::Texture(handle) => {
@@ -283,17 +345,5 @@ impl<P: Platform> GeometryPass<P> {
             }
         }
         cmd_buffer.end_render_pass();
-
-        cmd_buffer.barrier(&[Barrier::RawTextureBarrier {
-            old_sync: BarrierSync::RENDER_TARGET,
-            new_sync: BarrierSync::empty(),
-            old_access: BarrierAccess::RENDER_TARGET_WRITE,
-            new_access: BarrierAccess::empty(),
-            old_layout: TextureLayout::RenderTarget,
-            new_layout: TextureLayout::Present,
-            texture: backbuffer_handle,
-            queue_ownership: None,
-            range: BarrierTextureRange::default(),
-        }]);
     }
 }
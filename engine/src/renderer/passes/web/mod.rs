@@ -14,8 +14,10 @@ use crate::renderer::renderer_resources::RendererResources;
 use crate::graphics::*;
 
 mod geometry;
+mod water;
 
 use self::geometry::GeometryPass;
+use self::water::WaterPass;
 
 #[derive(Clone)]
 #[repr(C)]
@@ -33,10 +35,22 @@ struct CameraBuffer {
     fov: f32,
 }
 
+#[derive(Clone)]
+#[repr(C)]
+struct WebCameraBuffer {
+    view_proj: Matrix4,
+    z_near: f32,
+    z_far: f32,
+    log_depth_factor: f32,
+    _padding: f32,
+}
+
 pub struct WebRenderer<P: Platform> {
     device: Arc<Device<P::GPUBackend>>,
     geometry: GeometryPass<P>,
+    water: WaterPass<P>,
     resources: RendererResources<P::GPUBackend>,
+    time: f32,
 }
 
 impl<P: Platform> WebRenderer<P> {
@@ -55,6 +69,12 @@ impl<P: Platform> WebRenderer<P> {
             &mut init_cmd_buffer,
             &mut resources,
         );
+        let water_pass = WaterPass::<P>::new(
+            device,
+            asset_manager,
+            swapchain,
+            &mut resources,
+        );
 
         init_cmd_buffer.flush_barriers();
         device.flush_transfers();
@@ -73,7 +93,9 @@ impl<P: Platform> WebRenderer<P> {
         Self {
             device: device.clone(),
             geometry: geometry_pass,
+            water: water_pass,
             resources,
+            time: 0f32,
         }
     }
 }
@@ -83,6 +105,10 @@ impl<P: Platform> RenderPath<P> for WebRenderer<P> {
         false
     }
 
+    fn supports_logarithmic_depth(&self) -> bool {
+        true
+    }
+
     fn write_occlusion_culling_results(&self, _frame: u64, bitset: &mut Vec<u32>) {
         bitset.fill(!0u32);
     }
@@ -95,7 +121,7 @@ impl<P: Platform> RenderPath<P> for WebRenderer<P> {
 
     fn is_ready(&self, asset_manager: &Arc<AssetManager<P>>) -> bool {
         let assets = asset_manager.read_renderer_assets();
-        self.geometry.is_ready(&assets)
+        self.water.is_ready(&assets)
     }
 
     fn render(
@@ -126,7 +152,65 @@ impl<P: Platform> RenderPath<P> for WebRenderer<P> {
             fov: main_view.camera_fov
         }], BufferUsage::CONSTANT).unwrap();*/
 
-        let camera_buffer = cmd_buffer.upload_dynamic_data(&[main_view.proj_matrix * main_view.view_matrix], BufferUsage::CONSTANT).unwrap();
+        let log_depth_factor = if main_view.uses_logarithmic_depth() {
+            main_view.log_depth_factor()
+        } else {
+            0f32
+        };
+        let camera_buffer = cmd_buffer.upload_dynamic_data(&[WebCameraBuffer {
+            view_proj: main_view.proj_matrix * main_view.view_matrix,
+            z_near: main_view.near_plane,
+            z_far: main_view.far_plane,
+            log_depth_factor,
+            _padding: 0f32,
+        }], BufferUsage::CONSTANT).unwrap();
+
+        self.time += frame_info.delta.as_secs_f32();
+
+        let water_height = WaterPass::<P>::find_water_height(scene.scene, main_view, assets);
+        if let Some(water_height) = water_height {
+            // The oblique near plane doesn't work with logarithmic depth and the water shader
+            // linearizes the refraction depth, so the offscreen views always use standard depth.
+            let reflection_camera_buffer = cmd_buffer.upload_dynamic_data(&[WebCameraBuffer {
+                view_proj: WaterPass::<P>::reflection_view_proj(main_view, water_height),
+                z_near: main_view.near_plane,
+                z_far: main_view.far_plane,
+                log_depth_factor: 0f32,
+                _padding: 0f32,
+            }], BufferUsage::CONSTANT).unwrap();
+            self.geometry.execute_offscreen(
+                &mut cmd_buffer,
+                scene.scene,
+                main_view,
+                &reflection_camera_buffer,
+                &self.resources,
+                WaterPass::<P>::REFLECTION_TEXTURE_NAME,
+                WaterPass::<P>::REFLECTION_DEPTH_TEXTURE_NAME,
+                self.water.width(),
+                self.water.height(),
+                assets,
+            );
+
+            let refraction_camera_buffer = cmd_buffer.upload_dynamic_data(&[WebCameraBuffer {
+                view_proj: main_view.proj_matrix * main_view.view_matrix,
+                z_near: main_view.near_plane,
+                z_far: main_view.far_plane,
+                log_depth_factor: 0f32,
+                _padding: 0f32,
+            }], BufferUsage::CONSTANT).unwrap();
+            self.geometry.execute_offscreen(
+                &mut cmd_buffer,
+                scene.scene,
+                main_view,
+                &refraction_camera_buffer,
+                &self.resources,
+                WaterPass::<P>::REFRACTION_TEXTURE_NAME,
+                WaterPass::<P>::REFRACTION_DEPTH_TEXTURE_NAME,
+                self.water.width(),
+                self.water.height(),
+                assets,
+            );
+        }
 
         let backbuffer_view = swapchain.backbuffer_view(&backbuffer);
         let backbuffer_handle = swapchain.backbuffer_handle(&backbuffer);
@@ -143,6 +227,33 @@ impl<P: Platform> RenderPath<P> for WebRenderer<P> {
             assets,
         );
 
+        if water_height.is_some() {
+            self.water.execute(
+                &mut cmd_buffer,
+                scene.scene,
+                main_view,
+                &camera_buffer,
+                &self.resources,
+                backbuffer_view,
+                swapchain.width(),
+                swapchain.height(),
+                self.time,
+                assets,
+            );
+        }
+
+        cmd_buffer.barrier(&[Barrier::RawTextureBarrier {
+            old_sync: BarrierSync::RENDER_TARGET,
+            new_sync: BarrierSync::empty(),
+            old_access: BarrierAccess::RENDER_TARGET_WRITE,
+            new_access: BarrierAccess::empty(),
+            old_layout: TextureLayout::RenderTarget,
+            new_layout: TextureLayout::Present,
+            texture: backbuffer_handle,
+            queue_ownership: None,
+            range: BarrierTextureRange::default(),
+        }]);
+
         return Ok(RenderPathResult {
             cmd_buffer: cmd_buffer.finish(),
             backbuffer: Some(backbuffer)
@@ -151,4 +262,6 @@ impl<P: Platform> RenderPath<P> for WebRenderer<P> {
 
     fn set_ui_data(&mut self, data: crate::ui::UIDrawData<<P as Platform>::GPUBackend>) {
     }
+
+    fn set_debug_draw_data(&mut self, _data: crate::renderer::DebugDrawData) {}
 }
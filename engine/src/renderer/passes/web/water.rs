@@ -0,0 +1,435 @@
+use std::sync::Arc;
+
+use smallvec::SmallVec;
+use sourcerenderer_core::{
+    Matrix4, Platform, Vec2, Vec2I, Vec2UI, Vec3, Vec4
+};
+
+use crate::asset::AssetManager;
+use crate::renderer::asset::{GraphicsPipelineHandle, GraphicsPipelineInfo, RendererAssetsReadOnly, RendererMaterial, RendererMaterialValue};
+use crate::renderer::drawable::View;
+use crate::renderer::renderer_resources::{
+    HistoryResourceEntry,
+    RendererResources,
+};
+use crate::renderer::renderer_scene::RendererScene;
+
+use crate::graphics::*;
+
+use super::geometry::GeometryPass;
+
+#[derive(Clone)]
+#[repr(C)]
+struct WaterBuffer {
+    camera_position: Vec4,
+    reflect_tint: Vec4,
+    refract_tint: Vec4,
+    fog_color: Vec4,
+    fog_end: f32,
+    time: f32,
+    has_normal_map: u32,
+    _padding: f32,
+}
+
+/// Renders Source water surfaces.
+/// The scene gets rendered into a reflection texture using a camera that is mirrored
+/// at the water plane and into a refraction texture using the regular camera.
+/// The water surface then blends both based on the fresnel term and tints the refraction
+/// based on the distance between the surface and the geometry below it.
+/// The VMT loader creates the water materials, the other render paths skip them.
+pub struct WaterPass<P: Platform> {
+    pipeline: GraphicsPipelineHandle,
+    sampler: Arc<Sampler<P::GPUBackend>>,
+    clamp_sampler: Arc<Sampler<P::GPUBackend>>,
+    width: u32,
+    height: u32,
+}
+
+impl<P: Platform> WaterPass<P> {
+    pub const SHADER_NAME: &'static str = RendererMaterial::WATER_SHADER_NAME;
+    pub const REFLECTION_TEXTURE_NAME: &'static str = "WaterReflection";
+    pub const REFLECTION_DEPTH_TEXTURE_NAME: &'static str = "WaterReflectionDepth";
+    pub const REFRACTION_TEXTURE_NAME: &'static str = "WaterRefraction";
+    pub const REFRACTION_DEPTH_TEXTURE_NAME: &'static str = "WaterRefractionDepth";
+
+    pub(super) fn new(
+        device: &Arc<Device<P::GPUBackend>>,
+        asset_manager: &Arc<AssetManager<P>>,
+        swapchain: &Swapchain<P::GPUBackend>,
+        resources: &mut RendererResources<P::GPUBackend>,
+    ) -> Self {
+        let sampler = device.create_sampler(&SamplerInfo {
+            mag_filter: Filter::Linear,
+            min_filter: Filter::Linear,
+            mip_filter: Filter::Linear,
+            address_mode_u: AddressMode::Repeat,
+            address_mode_v: AddressMode::Repeat,
+            address_mode_w: AddressMode::ClampToEdge,
+            mip_bias: 0.0f32,
+            max_anisotropy: 1f32,
+            compare_op: None,
+            min_lod: 0.0f32,
+            max_lod: None,
+        });
+        let clamp_sampler = device.create_sampler(&SamplerInfo {
+            mag_filter: Filter::Linear,
+            min_filter: Filter::Linear,
+            mip_filter: Filter::Nearest,
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mip_bias: 0.0f32,
+            max_anisotropy: 1f32,
+            compare_op: None,
+            min_lod: 0.0f32,
+            max_lod: None,
+        });
+
+        // Reflections and refractions are blurry anyway, so half resolution is plenty.
+        let width = (swapchain.width() / 2).max(1);
+        let height = (swapchain.height() / 2).max(1);
+        let color_info = TextureInfo {
+            dimension: TextureDimension::Dim2D,
+            format: swapchain.format(),
+            width,
+            height,
+            depth: 1,
+            mip_levels: 1,
+            array_length: 1,
+            samples: SampleCount::Samples1,
+            usage: TextureUsage::RENDER_TARGET | TextureUsage::SAMPLED,
+            supports_srgb: false,
+        };
+        let depth_info = TextureInfo {
+            format: Format::D32,
+            usage: TextureUsage::DEPTH_STENCIL | TextureUsage::SAMPLED,
+            ..color_info
+        };
+        resources.create_texture(Self::REFLECTION_TEXTURE_NAME, &color_info, false);
+        resources.create_texture(Self::REFLECTION_DEPTH_TEXTURE_NAME, &depth_info, false);
+        resources.create_texture(Self::REFRACTION_TEXTURE_NAME, &color_info, false);
+        resources.create_texture(Self::REFRACTION_DEPTH_TEXTURE_NAME, &depth_info, false);
+
+        let shader_file_extension = "json";
+
+        let fs_name = format!("shaders/web_water.web.frag.{}", shader_file_extension);
+        let pipeline_info: GraphicsPipelineInfo = GraphicsPipelineInfo {
+            vs: &format!("shaders/web_water.web.vert.{}", shader_file_extension),
+            fs: Some(&fs_name),
+            primitive_type: PrimitiveType::Triangles,
+            vertex_layout: VertexLayoutInfo {
+                input_assembler: &[InputAssemblerElement {
+                    binding: 0,
+                    stride: 64,
+                    input_rate: InputRate::PerVertex,
+                }],
+                shader_inputs: &[
+                    ShaderInputElement {
+                        input_assembler_binding: 0,
+                        location_vk_mtl: 0,
+                        semantic_name_d3d: String::from(""),
+                        semantic_index_d3d: 0,
+                        offset: 0,
+                        format: Format::RGB32Float,
+                    },
+                    ShaderInputElement {
+                        input_assembler_binding: 0,
+                        location_vk_mtl: 1,
+                        semantic_name_d3d: String::from(""),
+                        semantic_index_d3d: 0,
+                        offset: 16,
+                        format: Format::RGB32Float,
+                    },
+                    ShaderInputElement {
+                        input_assembler_binding: 0,
+                        location_vk_mtl: 2,
+                        semantic_name_d3d: String::from(""),
+                        semantic_index_d3d: 0,
+                        offset: 32,
+                        format: Format::RG32Float,
+                    },
+                ],
+            },
+            rasterizer: RasterizerInfo {
+                fill_mode: FillMode::Fill,
+                cull_mode: CullMode::None,
+                front_face: FrontFace::Clockwise,
+                sample_count: SampleCount::Samples1,
+            },
+            depth_stencil: DepthStencilInfo {
+                depth_test_enabled: true,
+                depth_write_enabled: false,
+                depth_func: CompareFunc::Less,
+                stencil_enable: false,
+                stencil_read_mask: 0u8,
+                stencil_write_mask: 0u8,
+                stencil_front: StencilInfo::default(),
+                stencil_back: StencilInfo::default(),
+            },
+            blend: BlendInfo {
+                alpha_to_coverage_enabled: false,
+                logic_op_enabled: false,
+                logic_op: LogicOp::And,
+                constants: [0f32, 0f32, 0f32, 0f32],
+                attachments: &[AttachmentBlendInfo::default()],
+            },
+            render_target_formats: &[swapchain.format()],
+            depth_stencil_format: Format::D32
+        };
+        let pipeline = asset_manager.request_graphics_pipeline(&pipeline_info);
+
+        Self {
+            pipeline,
+            sampler: Arc::new(sampler),
+            clamp_sampler: Arc::new(clamp_sampler),
+            width,
+            height,
+        }
+    }
+
+    pub(super) fn is_ready(&self, assets: &RendererAssetsReadOnly<'_, P>) -> bool {
+        assets.get_graphics_pipeline(self.pipeline).is_some()
+    }
+
+    pub(super) fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub(super) fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Finds the height of the first visible water surface.
+    /// Source water is always flat, so the top of the bounding box is the water plane.
+    pub(super) fn find_water_height(
+        scene: &RendererScene<P::GPUBackend>,
+        view: &View,
+        assets: &RendererAssetsReadOnly<'_, P>
+    ) -> Option<f32> {
+        let drawables = scene.static_drawables();
+        for part in &view.drawable_parts {
+            let drawable = &drawables[part.drawable_index];
+            let model = assets.get_model(drawable.model);
+            if model.is_none() {
+                continue;
+            }
+            let model = model.unwrap();
+            let material_handle = model.material_handles().get(part.part_index);
+            if material_handle.is_none() {
+                continue;
+            }
+            let material = assets.get_material(*material_handle.unwrap());
+            if material.shader_name() != Self::SHADER_NAME {
+                continue;
+            }
+            let mesh = assets.get_mesh(model.mesh_handle());
+            let bounding_box = mesh.and_then(|mesh| mesh.bounding_box.as_ref());
+            if let Some(bounding_box) = bounding_box {
+                let transformed = bounding_box.transform(&Matrix4::from(drawable.transform));
+                return Some(transformed.max.y);
+            }
+        }
+        None
+    }
+
+    /// Builds the view projection matrix of a camera that is mirrored at the water plane.
+    /// The near plane gets replaced by the water plane so nothing below the surface ends up in the reflection.
+    pub(super) fn reflection_view_proj(view: &View, water_height: f32) -> Matrix4 {
+        let mirror = Matrix4::from_translation(Vec3::new(0f32, water_height, 0f32))
+            * Matrix4::from_scale(Vec3::new(1f32, -1f32, 1f32))
+            * Matrix4::from_translation(Vec3::new(0f32, -water_height, 0f32));
+        let view_matrix = view.view_matrix * mirror;
+        let plane = Vec4::new(0f32, 1f32, 0f32, -water_height);
+        oblique_proj(&view.proj_matrix, &view_matrix, plane) * view_matrix
+    }
+
+    pub(super) fn execute(
+        &mut self,
+        cmd_buffer: &mut CommandBufferRecorder<P::GPUBackend>,
+        scene: &RendererScene<P::GPUBackend>,
+        view: &View,
+        camera_buffer: &TransientBufferSlice<P::GPUBackend>,
+        resources: &RendererResources<P::GPUBackend>,
+        backbuffer: &Arc<TextureView<P::GPUBackend>>,
+        width: u32,
+        height: u32,
+        time: f32,
+        assets: &RendererAssetsReadOnly<'_, P>
+    ) {
+        let reflection = resources.access_view(
+            cmd_buffer,
+            Self::REFLECTION_TEXTURE_NAME,
+            BarrierSync::FRAGMENT_SHADER,
+            BarrierAccess::SAMPLING_READ,
+            TextureLayout::Sampled,
+            false,
+            &TextureViewInfo::default(),
+            HistoryResourceEntry::Current,
+        );
+        let refraction = resources.access_view(
+            cmd_buffer,
+            Self::REFRACTION_TEXTURE_NAME,
+            BarrierSync::FRAGMENT_SHADER,
+            BarrierAccess::SAMPLING_READ,
+            TextureLayout::Sampled,
+            false,
+            &TextureViewInfo::default(),
+            HistoryResourceEntry::Current,
+        );
+        let refraction_depth = resources.access_view(
+            cmd_buffer,
+            Self::REFRACTION_DEPTH_TEXTURE_NAME,
+            BarrierSync::FRAGMENT_SHADER,
+            BarrierAccess::SAMPLING_READ,
+            TextureLayout::Sampled,
+            false,
+            &TextureViewInfo::default(),
+            HistoryResourceEntry::Current,
+        );
+        let dsv = resources.access_view(
+            cmd_buffer,
+            GeometryPass::<P>::DEPTH_TEXTURE_NAME,
+            BarrierSync::EARLY_DEPTH | BarrierSync::LATE_DEPTH,
+            BarrierAccess::DEPTH_STENCIL_READ,
+            TextureLayout::DepthStencilRead,
+            false,
+            &TextureViewInfo::default(),
+            HistoryResourceEntry::Current,
+        );
+
+        cmd_buffer.flush_barriers();
+        cmd_buffer.begin_render_pass(
+            &RenderPassBeginInfo {
+                render_targets: &[RenderTarget {
+                    view: backbuffer,
+                    load_op: LoadOpColor::Load,
+                    store_op: StoreOp::<P::GPUBackend>::Store,
+                }],
+                depth_stencil: Some(&DepthStencilAttachment {
+                    view: &dsv,
+                    load_op: LoadOpDepthStencil::Load,
+                    store_op: StoreOp::<P::GPUBackend>::Store,
+                })
+            },
+            RenderpassRecordingMode::Commands,
+        );
+
+        let pipeline = assets.get_graphics_pipeline(self.pipeline).expect("Pipeline is not compiled yet");
+        cmd_buffer.set_pipeline(PipelineBinding::Graphics(&pipeline));
+        cmd_buffer.set_viewports(&[Viewport {
+            position: Vec2::new(0.0f32, 0.0f32),
+            extent: Vec2::new(width as f32, height as f32),
+            min_depth: 0.0f32,
+            max_depth: 1.0f32,
+        }]);
+        cmd_buffer.set_scissors(&[Scissor {
+            position: Vec2I::new(0, 0),
+            extent: Vec2UI::new(width, height),
+        }]);
+
+        cmd_buffer.bind_uniform_buffer(BindingFrequency::Frame, 0, BufferRef::Transient(camera_buffer), 0, WHOLE_BUFFER);
+        cmd_buffer.bind_sampling_view_and_sampler(BindingFrequency::Frame, 1, &reflection, &self.clamp_sampler);
+        cmd_buffer.bind_sampling_view_and_sampler(BindingFrequency::Frame, 2, &refraction, &self.clamp_sampler);
+        cmd_buffer.bind_sampling_view_and_sampler(BindingFrequency::Frame, 3, &refraction_depth, &self.clamp_sampler);
+
+        let drawables = scene.static_drawables();
+        for part in &view.drawable_parts {
+            let drawable = &drawables[part.drawable_index];
+            let model = assets.get_model(drawable.model);
+            if model.is_none() {
+                continue;
+            }
+            let model = model.unwrap();
+            let mesh = assets.get_mesh(model.mesh_handle());
+            if mesh.is_none() {
+                continue;
+            }
+            let mesh = mesh.unwrap();
+            let materials: SmallVec<[&RendererMaterial; 4]> = model
+                .material_handles()
+                .iter()
+                .map(|handle| assets.get_material(*handle))
+                .collect();
+            let material = &materials[part.part_index];
+            if material.shader_name() != Self::SHADER_NAME {
+                continue;
+            }
+            let range = &mesh.parts[part.part_index];
+
+            let mut has_normal_map = false;
+            if let Some(RendererMaterialValue::Texture(handle)) = material.get("normalmap") {
+                let texture = assets.get_texture(*handle);
+                cmd_buffer.bind_sampling_view_and_sampler(
+                    BindingFrequency::Frequent,
+                    0,
+                    &texture.view,
+                    &self.sampler,
+                );
+                has_normal_map = true;
+            } else {
+                // The binding still needs a valid texture.
+                cmd_buffer.bind_sampling_view_and_sampler(
+                    BindingFrequency::Frequent,
+                    0,
+                    &reflection,
+                    &self.sampler,
+                );
+            }
+            let water_buffer = cmd_buffer.upload_dynamic_data(&[WaterBuffer {
+                camera_position: Vec4::new(view.camera_position.x, view.camera_position.y, view.camera_position.z, 1f32),
+                reflect_tint: material_vec4(material, "reflecttint", Vec4::new(1f32, 1f32, 1f32, 1f32)),
+                refract_tint: material_vec4(material, "refracttint", Vec4::new(1f32, 1f32, 1f32, 1f32)),
+                fog_color: material_vec4(material, "fogcolor", Vec4::new(0.1f32, 0.15f32, 0.15f32, 1f32)),
+                fog_end: match material.get("fogend") {
+                    Some(RendererMaterialValue::Float(fog_end)) => *fog_end,
+                    _ => 200f32,
+                },
+                time,
+                has_normal_map: has_normal_map as u32,
+                _padding: 0f32,
+            }], BufferUsage::CONSTANT).unwrap();
+            cmd_buffer.bind_uniform_buffer(BindingFrequency::Frequent, 1, BufferRef::Transient(&water_buffer), 0, WHOLE_BUFFER);
+            cmd_buffer.set_push_constant_data(&[Matrix4::from(drawable.transform)], ShaderType::VertexShader);
+            cmd_buffer.finish_binding();
+
+            cmd_buffer.set_vertex_buffer(0, BufferRef::Regular(mesh.vertices.buffer()), mesh.vertices.offset() as u64);
+            if let Some(indices) = mesh.indices.as_ref() {
+                cmd_buffer.set_index_buffer(
+                    BufferRef::Regular(indices.buffer()),
+                    indices.offset() as u64,
+                    IndexFormat::U32,
+                );
+                cmd_buffer.draw_indexed(1, 0, range.count, range.start, 0);
+            } else {
+                cmd_buffer.draw(range.count, range.start);
+            }
+        }
+        cmd_buffer.end_render_pass();
+    }
+}
+
+fn material_vec4(material: &RendererMaterial, key: &str, default: Vec4) -> Vec4 {
+    match material.get(key) {
+        Some(RendererMaterialValue::Vec4(value)) => *value,
+        _ => default,
+    }
+}
+
+/// Replaces the near plane of the projection with the given world space clip plane.
+/// See "Oblique View Frustum Depth Projection and Clipping" by Eric Lengyel.
+fn oblique_proj(proj: &Matrix4, view: &Matrix4, world_plane: Vec4) -> Matrix4 {
+    let plane = view.inverse().transpose() * world_plane;
+    if plane.w >= 0f32 {
+        // The camera is on the wrong side of the plane.
+        return *proj;
+    }
+
+    let q = proj.inverse() * Vec4::new(plane.x.signum(), plane.y.signum(), 1f32, 1f32);
+    let c = plane * (1f32 / plane.dot(q));
+    let mut oblique = *proj;
+    oblique.x_axis.z = c.x;
+    oblique.y_axis.z = c.y;
+    oblique.z_axis.z = c.z;
+    oblique.w_axis.z = c.w;
+    oblique
+}
@@ -5,6 +5,7 @@ use sourcerenderer_core::gpu::{self, GPUBackend};
 use sourcerenderer_core::Platform;
 
 use super::asset::{RendererAssetsReadOnly, RendererTexture};
+use super::debug_draw::DebugDrawData;
 use super::renderer_resources::RendererResources;
 use super::renderer_scene::RendererScene;
 use crate::asset::AssetManager;
@@ -39,9 +40,13 @@ pub struct RenderPathResult<B: GPUBackend> {
 
 pub trait RenderPath<P: Platform> : Send {
     fn is_gpu_driven(&self) -> bool;
+    /// Whether every pass of the render path writes and reads depth according to `View::depth_mode`.
+    /// Views only use logarithmic depth if this is true.
+    fn supports_logarithmic_depth(&self) -> bool;
     fn write_occlusion_culling_results(&self, frame: u64, bitset: &mut Vec<u32>);
     fn on_swapchain_changed(&mut self, swapchain: &Swapchain<P::GPUBackend>);
     fn set_ui_data(&mut self, data: UIDrawData<P::GPUBackend>);
+    fn set_debug_draw_data(&mut self, data: DebugDrawData);
     fn is_ready(&self, asset_manager: &Arc<AssetManager<P>>) -> bool;
     fn render(
         &mut self,
@@ -59,9 +64,13 @@ impl<P: Platform> RenderPath<P> for NoOpRenderPath {
     fn is_gpu_driven(&self) -> bool {
         false
     }
+    fn supports_logarithmic_depth(&self) -> bool {
+        false
+    }
     fn write_occlusion_culling_results(&self, _frame: u64, _bitset: &mut Vec<u32>) {}
     fn on_swapchain_changed(&mut self, _swapchain: &Swapchain<<P as Platform>::GPUBackend>) {}
     fn set_ui_data(&mut self, _data: UIDrawData<<P as Platform>::GPUBackend>) {}
+    fn set_debug_draw_data(&mut self, _data: DebugDrawData) {}
     fn is_ready(&self, _asset_manager: &Arc<AssetManager<P>>) -> bool { true }
     fn render(
         &mut self,
@@ -27,21 +27,26 @@ use sourcerenderer_core::{
     Console, Matrix4, Vec2UI, Vec3
 };
 
-use super::drawable::{make_camera_proj, make_camera_view, RendererStaticDrawable};
+use super::debug_draw::DebugDrawData;
+use super::drawable::{make_camera_proj, make_camera_view, DepthMode, RendererStaticDrawable, TonemapOperator};
 use super::ecs::{
     DirectionalLightComponent,
     PointLightComponent,
 };
 use super::light::DirectionalLight;
+use super::passes::ssao::SsaoPass;
 use super::passes::web::WebRenderer;
 use super::render_path::{FrameInfo, NoOpRenderPath, RenderPath, SceneInfo};
-use super::renderer_culling::update_visibility;
+use super::renderer_culling::{request_visible_texture_mips, update_visibility};
 use super::renderer_resources::RendererResources;
 use super::renderer_scene::RendererScene;
+use super::swapchain_state::{SwapchainFrameAction, SwapchainState};
 use super::{PointLight, StaticRenderableComponent};
 use crate::asset::{AssetHandle, AssetManager, AssetType};
+use crate::camera::Projection;
 use crate::engine::WindowState;
 use crate::input::Input;
+use crate::metrics::Metrics;
 use crate::renderer::command::RendererCommand;
 use crate::transform::InterpolatedTransform;
 use crate::ui::UIDrawData;
@@ -78,20 +83,33 @@ pub struct Renderer<P: Platform> {
     context: GraphicsContext<P::GPUBackend>,
     swapchain: Arc<Mutex<Swapchain<P::GPUBackend>>>,
     render_path: Box<dyn RenderPath<P>>,
+    console: Arc<Console>,
+    metrics: Arc<Metrics>,
+    swapchain_state: SwapchainState,
+    /// Keeps the warning about r.log_depth not being supported from getting printed every frame.
+    warned_log_depth_unsupported: bool,
 
     last_frame: Instant,
     frame: u64
 }
 
 impl<P: Platform> Renderer<P> {
+    /// Camera movements further than this within one frame count as a teleport.
+    const CAMERA_CUT_DISTANCE: f32 = 5f32;
+    /// 1 switches the views to logarithmic depth if the render path supports it.
+    const LOG_DEPTH_CVAR: &'static str = "r.log_depth";
+
     pub fn new(
         device: &Arc<Device<P::GPUBackend>>,
         swapchain: Swapchain<P::GPUBackend>,
         asset_manager: &Arc<AssetManager<P>>,
-        _console: &Arc<Console>,
+        console: &Arc<Console>,
+        metrics: &Arc<Metrics>,
     ) -> (Renderer<P>, RendererSender<P::GPUBackend>) {
         info!("Initializing renderer with {} backend", P::GPUBackend::name());
 
+        console.register_cvar(Self::LOG_DEPTH_CVAR, 0u32);
+
         let (sender, receiver) = unbounded::<RendererCommand<P::GPUBackend>>();
         let mut context: GraphicsContext<<P as Platform>::GPUBackend> = device.create_context();
 
@@ -113,6 +131,10 @@ impl<P: Platform> Renderer<P> {
             swapchain: Arc::new(Mutex::new(swapchain)),
             context,
             render_path,
+            console: console.clone(),
+            metrics: metrics.clone(),
+            swapchain_state: SwapchainState::new(),
+            warned_log_depth_unsupported: false,
             last_frame: Instant::now(),
             frame: 0u64
         };
@@ -132,6 +154,7 @@ impl<P: Platform> Renderer<P> {
         &self.device
     }
 
+    #[profiling::function]
     pub fn render(&mut self) {
         self.asset_manager
             .flush_renderer_assets();
@@ -158,6 +181,18 @@ impl<P: Platform> Renderer<P> {
             return;
         }
 
+        self.process_console_cmds();
+
+        match self.swapchain_state.begin_frame() {
+            SwapchainFrameAction::Skip => {
+                // There is nothing to present to, skip rendering until the window gets restored.
+                self.finish_frame();
+                return;
+            }
+            SwapchainFrameAction::Recreate => self.recreate_swapchain(),
+            SwapchainFrameAction::Render => {}
+        }
+
         let delta = Instant::now().duration_since(self.last_frame);
         self.last_frame = Instant::now();
 
@@ -167,6 +202,7 @@ impl<P: Platform> Renderer<P> {
         };
 
         update_visibility(&mut self.scene, &self.asset_manager);
+        request_visible_texture_mips(&self.scene, &self.asset_manager);
 
         let assets = self.asset_manager.read_renderer_assets();
         let scene_info = SceneInfo {
@@ -179,17 +215,22 @@ impl<P: Platform> Renderer<P> {
 
         let mut swapchain_guard = self.swapchain.lock().unwrap();
         self.context.begin_frame();
-        let render_path_result = self.render_path.render(
-            &mut self.context,
-            &mut swapchain_guard,
-            &scene_info,
-            &frame_info,
-            &assets
-        );
+        let render_path_result = {
+            profiling::scope!("RenderPath");
+            self.render_path.render(
+                &mut self.context,
+                &mut swapchain_guard,
+                &scene_info,
+                &frame_info,
+                &assets
+            )
+        };
         let frame_end_signal = self.context.end_frame();
 
         match render_path_result {
             Ok(result) => {
+                let stats = result.cmd_buffer.stats();
+                self.metrics.add_draw_calls(stats.draw_calls, stats.triangles);
                 self.device.submit(QueueType::Graphics, QueueSubmission {
                     command_buffer: result.cmd_buffer,
                     wait_fences: &[],
@@ -198,15 +239,29 @@ impl<P: Platform> Renderer<P> {
                     release_swapchain: result.backbuffer.as_ref().map(|backbuffer| (&self.swapchain, backbuffer))
                 });
                 if let Some(backbuffer) = result.backbuffer {
+                    profiling::scope!("Present");
                     self.device.present(QueueType::Graphics, &self.swapchain, backbuffer);
                 }
             },
-            Err(_swapchain_err) => {
-                todo!("Handle swapchain recreation");
+            Err(SwapchainError::NeedsRecreation) => {
+                // The frame gets dropped, the swapchain is recreated before the next one.
+                trace!("Swapchain is out of date, skipping frame.");
+                self.swapchain_state.swapchain_out_of_date();
+            }
+            Err(SwapchainError::Other) => {
+                log::error!("Failed to acquire backbuffer, skipping frame.");
             }
         }
         std::mem::drop(swapchain_guard);
 
+        for view in self.scene.views_mut() {
+            view.jitter_frame = view.jitter_frame.wrapping_add(1);
+            view.camera_cut = false;
+        }
+
+        self.record_metrics(&frame_info);
+        profiling::finish_frame!();
+
         let c_device = self.device.clone();
         bevy_tasks::ComputeTaskPool::get().spawn(async move {
             c_device.flush(QueueType::Graphics)
@@ -216,12 +271,240 @@ impl<P: Platform> Renderer<P> {
         self.resources.swap_history_resources();
         self.frame += 1;
 
+        self.finish_frame();
+    }
+
+    fn finish_frame(&self) {
         // Dec queued frame counter
         let mut counter_guard = self.state.queued_frames_counter.lock().unwrap();
         *counter_guard -= 1;
         self.state.cond_var.notify_all();
     }
 
+    fn recreate_swapchain(&mut self) {
+        // Frames that are still in flight reference the images of the old swapchain.
+        self.device.wait_for_idle();
+        let mut swapchain_guard = self.swapchain.lock().unwrap();
+        swapchain_guard.recreate();
+        info!("Recreated swapchain: {}x{}", swapchain_guard.width(), swapchain_guard.height());
+        self.render_path.on_swapchain_changed(&swapchain_guard);
+    }
+
+    fn record_metrics(&self, frame_info: &FrameInfo) {
+        if let Some(view) = self.scene.views().first() {
+            let visible_drawables: u64 = view.visible_drawables_bitset
+                .iter()
+                .map(|bits| bits.count_ones() as u64)
+                .sum();
+            let drawables_count = self.scene.static_drawables().len() as u64;
+            self.metrics.set_culling_results(visible_drawables, drawables_count.saturating_sub(visible_drawables));
+        }
+        self.metrics.set_gpu_memory_allocated(self.device.allocated_memory());
+        let gpu_timings = self.context.last_frame_timings();
+        self.metrics.end_frame(
+            frame_info.frame,
+            frame_info.delta,
+            gpu_timings.map(|timings| timings.frame_time),
+            gpu_timings.map(|timings| &timings.passes[..]).unwrap_or(&[])
+        );
+    }
+
+    fn process_console_cmds(&mut self) {
+        let log_depth = self.console.cvar::<u32>(Self::LOG_DEPTH_CVAR).unwrap_or(0) != 0;
+        let log_depth_supported = self.render_path.supports_logarithmic_depth();
+        if log_depth && !log_depth_supported {
+            if !self.warned_log_depth_unsupported {
+                warn!("The active render path doesn't support logarithmic depth");
+                self.warned_log_depth_unsupported = true;
+            }
+        } else {
+            self.warned_log_depth_unsupported = false;
+        }
+        let depth_mode = if log_depth && log_depth_supported { DepthMode::Logarithmic } else { DepthMode::Standard };
+        for view in self.scene.views_mut() {
+            if view.depth_mode != depth_mode {
+                info!("Setting depth mode to {:?}", depth_mode);
+                view.depth_mode = depth_mode;
+            }
+        }
+
+        for cmd in self.console.get_cmds("r") {
+            match cmd.name() {
+                "tonemap" => {
+                    let operator = match cmd.args().first().map(|arg| arg.to_lowercase()).as_deref() {
+                        Some("reinhard") => TonemapOperator::Reinhard,
+                        Some("aces") => TonemapOperator::Aces,
+                        Some("agx") => TonemapOperator::AgX,
+                        _ => {
+                            warn!("Usage: r.tonemap reinhard|aces|agx");
+                            continue;
+                        }
+                    };
+                    info!("Setting tonemap operator to {:?}", operator);
+                    for view in self.scene.views_mut() {
+                        view.tonemap_operator = operator;
+                    }
+                }
+                "exposure" => {
+                    let Some(exposure) = cmd.args().first().and_then(|arg| arg.parse::<f32>().ok()).filter(|exposure| *exposure > 0f32) else {
+                        warn!("Usage: r.exposure <positive number>");
+                        continue;
+                    };
+                    info!("Setting exposure to {}", exposure);
+                    for view in self.scene.views_mut() {
+                        view.exposure = exposure;
+                    }
+                }
+                "bloom_threshold" => {
+                    let Some(threshold) = cmd.args().first().and_then(|arg| arg.parse::<f32>().ok()).filter(|threshold| *threshold >= 0f32) else {
+                        warn!("Usage: r.bloom_threshold <non-negative number>");
+                        continue;
+                    };
+                    info!("Setting bloom threshold to {}", threshold);
+                    for view in self.scene.views_mut() {
+                        view.bloom_threshold = threshold;
+                    }
+                }
+                "bloom_intensity" => {
+                    let Some(intensity) = cmd.args().first().and_then(|arg| arg.parse::<f32>().ok()).filter(|intensity| *intensity >= 0f32) else {
+                        warn!("Usage: r.bloom_intensity <non-negative number>, 0 disables bloom");
+                        continue;
+                    };
+                    info!("Setting bloom intensity to {}", intensity);
+                    for view in self.scene.views_mut() {
+                        view.bloom_intensity = intensity;
+                    }
+                }
+                "ssao" => {
+                    let enabled = cmd.args().first().map(|arg| arg.as_str() != "0").unwrap_or(true);
+                    info!("Setting SSAO enabled: {}", enabled);
+                    for view in self.scene.views_mut() {
+                        view.ssao_enabled = enabled;
+                    }
+                }
+                "ssao_radius" => {
+                    let Some(radius) = cmd.args().first().and_then(|arg| arg.parse::<f32>().ok()).filter(|radius| *radius > 0f32) else {
+                        warn!("Usage: r.ssao_radius <positive number>");
+                        continue;
+                    };
+                    info!("Setting SSAO radius to {}", radius);
+                    for view in self.scene.views_mut() {
+                        view.ssao_radius = radius;
+                    }
+                }
+                "ssao_intensity" => {
+                    let Some(intensity) = cmd.args().first().and_then(|arg| arg.parse::<f32>().ok()).filter(|intensity| *intensity >= 0f32) else {
+                        warn!("Usage: r.ssao_intensity <non-negative number>");
+                        continue;
+                    };
+                    info!("Setting SSAO intensity to {}", intensity);
+                    for view in self.scene.views_mut() {
+                        view.ssao_intensity = intensity;
+                    }
+                }
+                "ssao_samples" => {
+                    let Some(samples) = cmd.args().first().and_then(|arg| arg.parse::<u32>().ok()).filter(|samples| (1..=SsaoPass::<P>::MAX_SAMPLES).contains(samples)) else {
+                        warn!("Usage: r.ssao_samples <1-{}>", SsaoPass::<P>::MAX_SAMPLES);
+                        continue;
+                    };
+                    info!("Setting SSAO sample count to {}", samples);
+                    for view in self.scene.views_mut() {
+                        view.ssao_samples = samples;
+                    }
+                }
+                "ssao_blur_radius" => {
+                    let Some(radius) = cmd.args().first().and_then(|arg| arg.parse::<u32>().ok()).filter(|radius| *radius <= SsaoPass::<P>::MAX_BLUR_RADIUS) else {
+                        warn!("Usage: r.ssao_blur_radius <0-{}>, 0 disables the spatial blur", SsaoPass::<P>::MAX_BLUR_RADIUS);
+                        continue;
+                    };
+                    info!("Setting SSAO blur radius to {}", radius);
+                    for view in self.scene.views_mut() {
+                        view.ssao_blur_radius = radius;
+                    }
+                }
+                "ssr_intensity" => {
+                    let Some(intensity) = cmd.args().first().and_then(|arg| arg.parse::<f32>().ok()).filter(|intensity| *intensity >= 0f32) else {
+                        warn!("Usage: r.ssr_intensity <non-negative number>, 0 disables reflections");
+                        continue;
+                    };
+                    info!("Setting SSR intensity to {}", intensity);
+                    for view in self.scene.views_mut() {
+                        view.ssr_intensity = intensity;
+                    }
+                }
+                "ssr_roughness_cutoff" => {
+                    let Some(cutoff) = cmd.args().first().and_then(|arg| arg.parse::<f32>().ok()).filter(|cutoff| (0f32..=1f32).contains(cutoff)) else {
+                        warn!("Usage: r.ssr_roughness_cutoff <0-1>");
+                        continue;
+                    };
+                    info!("Setting SSR roughness cutoff to {}", cutoff);
+                    for view in self.scene.views_mut() {
+                        view.ssr_roughness_cutoff = cutoff;
+                    }
+                }
+                "ssr_steps" => {
+                    let Some(steps) = cmd.args().first().and_then(|arg| arg.parse::<u32>().ok()).filter(|steps| *steps > 0) else {
+                        warn!("Usage: r.ssr_steps <positive number>");
+                        continue;
+                    };
+                    info!("Setting SSR ray march steps to {}", steps);
+                    for view in self.scene.views_mut() {
+                        view.ssr_steps = steps;
+                    }
+                }
+                "taa" => {
+                    let enabled = cmd.args().first().map(|arg| arg.as_str() != "0").unwrap_or(true);
+                    info!("Setting TAA enabled: {}", enabled);
+                    for view in self.scene.views_mut() {
+                        view.taa_enabled = enabled;
+                        view.camera_cut = true;
+                    }
+                }
+                "gpu_timings" => {
+                    let enabled = cmd.args().first().map(|arg| arg.as_str() != "0").unwrap_or(true);
+                    self.context.set_gpu_timings_enabled(enabled);
+                    if enabled && !self.context.gpu_timings_enabled() {
+                        warn!("GPU timings are not supported by the device");
+                    } else {
+                        info!("Setting GPU timings enabled: {}", enabled);
+                    }
+                }
+                "memory" => {
+                    for (index, budget) in self.device.memory_budget().iter().enumerate() {
+                        info!("Memory heap {} ({:?}): {} MiB used, {} MiB available, {} MiB budget",
+                            index, budget.memory_kind, budget.used >> 20, budget.available >> 20, budget.total >> 20);
+                    }
+                }
+                "vsync" => {
+                    let enabled = cmd.args().first().map(|arg| arg.as_str() != "0").unwrap_or(true);
+                    self.set_present_mode(if enabled { PresentMode::Fifo } else { PresentMode::Immediate });
+                }
+                "present_mode" => {
+                    let present_mode = match cmd.args().first().map(|arg| arg.to_lowercase()).as_deref() {
+                        Some("fifo") => PresentMode::Fifo,
+                        Some("fifo_relaxed") => PresentMode::FifoRelaxed,
+                        Some("mailbox") => PresentMode::Mailbox,
+                        Some("immediate") => PresentMode::Immediate,
+                        _ => {
+                            warn!("Usage: r.present_mode fifo|fifo_relaxed|mailbox|immediate");
+                            continue;
+                        }
+                    };
+                    self.set_present_mode(present_mode);
+                }
+                _ => {
+                    warn!("Unknown renderer command: {}", cmd.name());
+                }
+            }
+        }
+    }
+
+    fn set_present_mode(&mut self, present_mode: PresentMode) {
+        let mut swapchain_guard = self.swapchain.lock().unwrap();
+        swapchain_guard.set_present_mode(present_mode);
+        info!("Requested present mode {:?}, using {:?}", present_mode, swapchain_guard.present_mode());
+    }
+
     fn receive_messages(&mut self) -> ReceiveMessagesResult {
         let message_res = self.receiver.try_recv();
         let mut message_opt: Option<RendererCommand<<P as Platform>::GPUBackend>>;
@@ -249,18 +532,30 @@ impl<P: Platform> Renderer<P> {
 
                 RendererCommand::<P::GPUBackend>::UpdateCameraTransform {
                     camera_transform,
-                    fov,
+                    projection,
+                    z_near,
+                    z_far,
                 } => {
                     let main_view = self.scene.main_view_mut();
                     main_view.camera_transform = camera_transform;
-                    main_view.camera_fov = fov;
+                    main_view.projection = projection;
+                    if let Projection::Perspective { fov } = projection {
+                        main_view.camera_fov = fov;
+                    }
+                    main_view.near_plane = z_near;
+                    main_view.far_plane = z_far;
                     main_view.old_camera_matrix = main_view.proj_matrix * main_view.view_matrix;
                     let (_, rotation, position) = camera_transform.to_scale_rotation_translation();
+                    if position.distance(main_view.camera_position) > Self::CAMERA_CUT_DISTANCE {
+                        // Reprojecting the history of a teleported camera only smears the image.
+                        main_view.camera_cut = true;
+                        main_view.jitter_frame = 0;
+                    }
                     main_view.camera_position = position;
                     main_view.camera_rotation = rotation;
                     main_view.view_matrix = make_camera_view(position, rotation);
                     main_view.proj_matrix = make_camera_proj(
-                        main_view.camera_fov,
+                        main_view.projection,
                         main_view.aspect_ratio,
                         main_view.near_plane,
                         main_view.far_plane,
@@ -349,14 +644,22 @@ impl<P: Platform> Renderer<P> {
                         unreachable!()
                     }
                 }
+                RendererCommand::<P::GPUBackend>::SetEnvironmentMap(path) => {
+                    let handle = self.asset_manager.reserve_handle(&path, AssetType::Texture);
+                    if let AssetHandle::Texture(handle) = handle {
+                        self.scene.set_environment_map(Some(handle));
+                    } else {
+                        unreachable!()
+                    }
+                }
                 RendererCommand::RenderUI(data) => { self.render_path.set_ui_data(data); },
+                RendererCommand::RenderDebugLines(data) => { self.render_path.set_debug_draw_data(data); },
 
                 RendererCommand::WindowChanged(window_state) => {
-                    match window_state {
-                        WindowState::Fullscreen(size) => {},
-                        WindowState::Window(size) => {},
-                        WindowState::Minimized => {}
-                    }
+                    let swapchain_guard = self.swapchain.lock().unwrap();
+                    let swapchain_size = Vec2UI::new(swapchain_guard.width(), swapchain_guard.height());
+                    std::mem::drop(swapchain_guard);
+                    self.swapchain_state.window_changed(&window_state, swapchain_size);
                 }
             }
 
@@ -465,10 +768,12 @@ impl<B: GPUBackend> RendererSender<B> {
         }
     }
 
-    pub fn update_camera_transform(&self, camera_transform: Affine3A, fov: f32) {
+    pub fn update_camera_transform(&self, camera_transform: Affine3A, projection: Projection, z_near: f32, z_far: f32) {
         let result = self.sender.send(RendererCommand::<B>::UpdateCameraTransform {
             camera_transform,
-            fov,
+            projection,
+            z_near,
+            z_far,
         });
         if let Result::Err(err) = result {
             panic!("Sending message to render thread failed {:?}", err);
@@ -503,6 +808,15 @@ impl<B: GPUBackend> RendererSender<B> {
         }
     }
 
+    pub fn update_environment_map(&self, path: &str) {
+        let result = self
+            .sender
+            .send(RendererCommand::<B>::SetEnvironmentMap(path.to_string()));
+        if let Result::Err(err) = result {
+            panic!("Sending message to render thread failed {:?}", err);
+        }
+    }
+
     pub fn wait_until_available(&self, timeout: Duration) {
         let queued_guard = self.state.queued_frames_counter.lock().unwrap();
         #[cfg(not(target_arch = "wasm32"))]
@@ -529,6 +843,13 @@ impl<B: GPUBackend> RendererSender<B> {
         }
     }
 
+    pub fn update_debug_lines(&self, debug_data: DebugDrawData) {
+        let result = self.sender.send(RendererCommand::<B>::RenderDebugLines(debug_data));
+        if let Result::Err(err) = result {
+            panic!("Sending message to render thread failed {:?}", err);
+        }
+    }
+
     pub fn unblock_game_thread(&self) {
         self.state.cond_var.notify_all();
     }
@@ -1,10 +1,13 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use bevy_tasks::ParallelSlice;
 use bitset_core::BitSet;
 use log::trace;
 use smallvec::SmallVec;
 use sourcerenderer_core::{Matrix4, Platform, Vec3};
 
-use crate::{asset::AssetManager, math::{BoundingBox, Frustum}, renderer::DrawablePart};
+use crate::{asset::{AssetManager, TextureHandle}, math::{BoundingBox, FrustumPlanes}, renderer::DrawablePart};
 
 use super::{renderer_scene::RendererScene};
 
@@ -34,13 +37,7 @@ pub(crate) fn update_visibility<P: Platform>(scene: &mut RendererScene<P::GPUBac
             visible_drawables_bitset.resize(drawable_u32_count, 0);
         }
 
-        let frustum = Frustum::new(
-            view_mut.near_plane,
-            view_mut.far_plane,
-            view_mut.camera_fov,
-            view_mut.aspect_ratio,
-        );
-        let camera_matrix = view_mut.view_matrix;
+        let frustum = FrustumPlanes::from_view_proj(&(view_mut.proj_matrix * view_mut.view_matrix));
         let camera_position = view_mut.camera_position;
 
         let task_pool = bevy_tasks::ComputeTaskPool::get();
@@ -53,7 +50,6 @@ pub(crate) fn update_visibility<P: Platform>(scene: &mut RendererScene<P::GPUBac
                 debug_assert_eq!(CHUNK_SIZE % 32, 0);
                 visible_drawables.bit_init(false);
                 for (index, static_mesh) in chunk.iter().enumerate() {
-                    let model_view_matrix = camera_matrix * static_mesh.transform;
                     let model = assets.get_model(static_mesh.model);
                     if model.is_none() {
                         continue;
@@ -65,7 +61,7 @@ pub(crate) fn update_visibility<P: Platform>(scene: &mut RendererScene<P::GPUBac
                     let mesh = mesh.unwrap();
                     let bounding_box = &mesh.bounding_box;
                     let is_visible = if let Some(bounding_box) = bounding_box {
-                        frustum.intersects(bounding_box, &model_view_matrix)
+                        frustum.intersects(bounding_box, &static_mesh.transform)
                     } else {
                         true
                     };
@@ -109,7 +105,7 @@ pub(crate) fn update_visibility<P: Platform>(scene: &mut RendererScene<P::GPUBac
                         && !camera_in_bb
                     {
                         // Mesh was not visible in the previous frame.
-                        println!("Previous frame faile");
+                        trace!("Drawable {} was occluded in the previous frame", drawable_index);
                         continue;
                     }
 
@@ -126,13 +122,14 @@ pub(crate) fn update_visibility<P: Platform>(scene: &mut RendererScene<P::GPUBac
             .iter()
             .enumerate()
             .for_each(|(chunk_index, (chunk_visible_parts, visible_drawables))| {
+                // The offsets are in u32s, not bits. The last chunk might cover fewer drawables than the chunk size.
                 let global_drawable_bit_offset = chunk_index * visible_drawables.len();
                 let global_drawable_bit_end = ((chunk_index + 1) * visible_drawables.len())
-                    .min(visible_drawables_bitset.len() - 1);
-                let slice_len = global_drawable_bit_end - global_drawable_bit_offset + 1;
+                    .min(visible_drawables_bitset.len());
+                let slice_len = global_drawable_bit_end - global_drawable_bit_offset;
                 visible_drawables_bitset
                     [global_drawable_bit_offset..global_drawable_bit_end]
-                    .copy_from_slice(&visible_drawables[..(slice_len - 1)]);
+                    .copy_from_slice(&visible_drawables[..slice_len]);
 
                 visible_parts.extend_from_slice(&chunk_visible_parts[..]);
             });
@@ -141,4 +138,55 @@ pub(crate) fn update_visibility<P: Platform>(scene: &mut RendererScene<P::GPUBac
         view_mut.visible_drawables_bitset = visible_drawables_bitset;
         view_mut.old_visible_drawables_bitset = old_visible;
     }
-}
\ No newline at end of file
+}
+
+/// Visible textures that are closer to the camera than this get all of their mips.
+/// Every time the distance doubles, the largest resident mip level gets halved.
+const TEXTURE_STREAMING_FULL_RESOLUTION_DISTANCE: f32 = 10f32;
+
+/// Requests the mips of the textures used by the drawables that are visible in the main view.
+/// The closer a drawable is to the camera, the larger the requested mips are.
+#[profiling::function]
+pub(crate) fn request_visible_texture_mips<P: Platform>(scene: &RendererScene<P::GPUBackend>, asset_manager: &Arc<AssetManager<P>>) {
+    let view = scene.main_view();
+    let static_meshes = scene.static_drawables();
+
+    let mut requests: Vec<(String, u32)> = {
+        let assets = asset_manager.read_renderer_assets();
+        let mut first_mips = HashMap::<TextureHandle, u32>::new();
+        for part in &view.drawable_parts {
+            let static_mesh = &static_meshes[part.drawable_index];
+            let Some(model) = assets.get_model(static_mesh.model) else {
+                continue;
+            };
+            let Some(material) = model.material_handles().get(part.part_index) else {
+                continue;
+            };
+            let distance = assets.get_mesh(model.mesh_handle())
+                .and_then(|mesh| mesh.bounding_box.as_ref())
+                .map_or(0f32, |bounding_box| {
+                    bounding_box
+                        .transform(&Matrix4::from(static_mesh.transform))
+                        .distance(&view.camera_position)
+                });
+            let first_mip = (distance / TEXTURE_STREAMING_FULL_RESOLUTION_DISTANCE).log2().floor().max(0f32) as u32;
+            for texture in assets.get_material(*material).textures() {
+                let texture_first_mip = first_mips.entry(texture).or_insert(first_mip);
+                *texture_first_mip = (*texture_first_mip).min(first_mip);
+            }
+        }
+        first_mips
+            .into_iter()
+            .filter_map(|(texture, first_mip)| assets.get_texture_path(texture).map(|path| (path.to_string(), first_mip)))
+            .collect()
+    };
+
+    // The closest textures get their mips first, the ones requested later get fewer if the budget runs out.
+    requests.sort_by_key(|(_, first_mip)| *first_mip);
+
+    // The renderer assets must not be locked anymore, requesting mips can reload the texture.
+    asset_manager.begin_texture_streaming_frame();
+    for (path, first_mip) in requests {
+        asset_manager.request_texture_mips(&path, first_mip);
+    }
+}
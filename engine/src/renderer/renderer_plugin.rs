@@ -1,6 +1,5 @@
 use std::marker::PhantomData;
 use std::sync::atomic::AtomicU64;
-use web_time::Duration;
 
 use atomic_refcell::AtomicRefCell;
 use bevy_app::{
@@ -29,6 +28,7 @@ use bevy_ecs::system::{
 use bevy_ecs::world::{Ref, World};
 use bevy_log::trace;
 use bevy_tasks::ComputeTaskPool;
+use bevy_time::{Fixed, Time};
 use bevy_transform::components::GlobalTransform;
 use bevy_utils::synccell::SyncCell;
 use log::{debug, info};
@@ -38,6 +38,7 @@ use sourcerenderer_core::{
 
 use super::renderer::RendererSender;
 use super::{
+    DebugRenderer,
     DirectionalLightComponent,
     PointLightComponent,
     Renderer,
@@ -46,7 +47,8 @@ use super::{
 use crate::asset::AssetManagerECSResource;
 use crate::engine::{
     ConsoleResource,
-    WindowState, TICK_RATE,
+    MetricsResource,
+    WindowState,
 };
 use crate::graphics::{GPUDeviceResource, GPUSwapchainResource};
 use crate::transform::InterpolatedTransform;
@@ -76,6 +78,7 @@ impl<P: Platform> Plugin for RendererPlugin<P> {
             .0;
         let gpu_resources = app.world().resource::<GPUDeviceResource<P::GPUBackend>>();
         let console_resource = app.world().resource::<ConsoleResource>();
+        let metrics_resource = app.world().resource::<MetricsResource>();
         let asset_manager_resource = app.world().resource::<AssetManagerECSResource<P>>();
 
         let (renderer, sender) = Renderer::new(
@@ -83,6 +86,7 @@ impl<P: Platform> Plugin for RendererPlugin<P> {
             swapchain,
             &asset_manager_resource.0,
             &console_resource.0,
+            &metrics_resource.0,
         );
 
         let pre_init_wrapper = PreInitRendererResourceWrapper {
@@ -90,6 +94,7 @@ impl<P: Platform> Plugin for RendererPlugin<P> {
             sender
         };
         app.insert_resource(pre_init_wrapper);
+        app.init_resource::<DebugRenderer>();
     }
 
     fn ready(&self, app: &App) -> bool {
@@ -157,6 +162,7 @@ fn install_renderer_systems<P: Platform>(
             extract_static_renderables::<P>,
             extract_point_lights::<P>,
             extract_directional_lights::<P>,
+            extract_debug_lines::<P>,
         )
             .in_set(ExtractSet),
     );
@@ -197,6 +203,7 @@ fn install_renderer_systems<P: Platform>(
             extract_static_renderables::<P>,
             extract_point_lights::<P>,
             extract_directional_lights::<P>,
+            extract_debug_lines::<P>,
         )
             .in_set(ExtractSet)
             .after(SyncSet),
@@ -249,13 +256,13 @@ fn extract_camera<P: Platform>(
         if camera.interpolate_rotation {
             renderer
                 .sender
-                .update_camera_transform(interpolated.0, camera.fov);
+                .update_camera_transform(interpolated.0, camera.projection, camera.z_near, camera.z_far);
         } else {
             let mut combined_transform = transform.affine();
             combined_transform.translation = interpolated.0.translation;
             renderer
                 .sender
-                .update_camera_transform(combined_transform, camera.fov);
+                .update_camera_transform(combined_transform, camera.projection, camera.z_near, camera.z_far);
         }
     }
 }
@@ -324,6 +331,21 @@ fn extract_directional_lights<P: Platform>(
     }
 }
 
+fn extract_debug_lines<P: Platform>(
+    renderer: Res<RendererResourceWrapper<P>>,
+    mut debug_renderer: ResMut<DebugRenderer>,
+) {
+    if debug_renderer.is_empty() {
+        return;
+    }
+    // Lines are only valid for a single frame, drop them if the renderer doesn't take a new one.
+    let debug_data = debug_renderer.take();
+    if renderer.sender.is_saturated() {
+        return;
+    }
+    renderer.sender.update_debug_lines(debug_data);
+}
+
 fn end_frame<P: Platform>(mut renderer: ResMut<RendererResourceWrapper<P>>) {
     if renderer.sender.is_saturated() {
         return;
@@ -335,8 +357,8 @@ fn end_frame<P: Platform>(mut renderer: ResMut<RendererResourceWrapper<P>>) {
     renderer.renderer.get().render();
 }
 
-fn begin_frame<P: Platform>(renderer: ResMut<RendererResourceWrapper<P>>) {
+fn begin_frame<P: Platform>(renderer: ResMut<RendererResourceWrapper<P>>, fixed_time: Res<Time<Fixed>>) {
     // Unblock regularly so the fixed time systems can run.
     // All rendering systems check if the renderer is saturated before sending new commands.
-    renderer.sender.wait_until_available(Duration::from_micros(1000000u64 / 4u64 / (TICK_RATE as u64)));
+    renderer.sender.wait_until_available(fixed_time.timestep() / 4);
 }
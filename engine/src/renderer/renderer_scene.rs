@@ -31,6 +31,7 @@ pub struct RendererScene<B: GPUBackend> {
     point_light_entity_map: HashMap<Entity, usize>,
     directional_light_entity_map: HashMap<Entity, usize>,
     lightmap: Option<TextureHandle>,
+    environment_map: Option<TextureHandle>,
 }
 
 impl<B: GPUBackend> RendererScene<B> {
@@ -44,6 +45,7 @@ impl<B: GPUBackend> RendererScene<B> {
             point_light_entity_map: HashMap::new(),
             directional_light_entity_map: HashMap::new(),
             lightmap: None,
+            environment_map: None,
         }
     }
 
@@ -184,4 +186,13 @@ impl<B: GPUBackend> RendererScene<B> {
     pub fn lightmap(&self) -> Option<TextureHandle> {
         self.lightmap
     }
+
+    /// A cube texture that's used for reflections that can't be resolved in screen space.
+    pub fn set_environment_map(&mut self, environment_map: Option<TextureHandle>) {
+        self.environment_map = environment_map;
+    }
+
+    pub fn environment_map(&self) -> Option<TextureHandle> {
+        self.environment_map
+    }
 }
@@ -0,0 +1,110 @@
+use sourcerenderer_core::Vec2UI;
+
+use crate::engine::WindowState;
+
+/// What the renderer has to do with the swapchain before rendering a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum SwapchainFrameAction {
+    /// There is nothing to present to, the frame gets skipped.
+    Skip,
+    /// The swapchain has to be recreated before the frame gets rendered.
+    Recreate,
+    Render,
+}
+
+/// Tracks whether the window is minimized and whether the swapchain is out of date.
+/// Only the changes get remembered, all resizes that arrive within one frame
+/// result in a single swapchain recreation at the start of the next frame.
+#[derive(Debug, Default)]
+pub(super) struct SwapchainState {
+    is_minimized: bool,
+    needs_recreation: bool,
+}
+
+impl SwapchainState {
+    pub(super) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(super) fn window_changed(&mut self, window_state: &WindowState, swapchain_size: Vec2UI) {
+        match window_state {
+            WindowState::Fullscreen(size) | WindowState::Window(size) => {
+                if size.x == 0 || size.y == 0 {
+                    self.is_minimized = true;
+                } else {
+                    self.is_minimized = false;
+                    if *size != swapchain_size {
+                        self.needs_recreation = true;
+                    }
+                }
+            }
+            WindowState::Minimized => {
+                self.is_minimized = true;
+            }
+        }
+    }
+
+    /// The render path ran into `SwapchainError::NeedsRecreation`.
+    pub(super) fn swapchain_out_of_date(&mut self) {
+        self.needs_recreation = true;
+    }
+
+    /// Decides what to do with the swapchain at the start of the frame.
+    /// Returns `SwapchainFrameAction::Recreate` only once per pending recreation.
+    pub(super) fn begin_frame(&mut self) -> SwapchainFrameAction {
+        if self.is_minimized {
+            SwapchainFrameAction::Skip
+        } else if std::mem::take(&mut self.needs_recreation) {
+            SwapchainFrameAction::Recreate
+        } else {
+            SwapchainFrameAction::Render
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn out_of_date_recreates_once() {
+        let mut state = SwapchainState::new();
+        assert_eq!(state.begin_frame(), SwapchainFrameAction::Render);
+
+        state.swapchain_out_of_date();
+        assert_eq!(state.begin_frame(), SwapchainFrameAction::Recreate);
+        assert_eq!(state.begin_frame(), SwapchainFrameAction::Render);
+    }
+
+    #[test]
+    fn resizes_get_coalesced() {
+        let mut state = SwapchainState::new();
+        let swapchain_size = Vec2UI::new(1280, 720);
+        state.window_changed(&WindowState::Window(Vec2UI::new(1280, 720)), swapchain_size);
+        assert_eq!(state.begin_frame(), SwapchainFrameAction::Render);
+
+        state.window_changed(&WindowState::Window(Vec2UI::new(800, 600)), swapchain_size);
+        state.window_changed(&WindowState::Window(Vec2UI::new(1024, 768)), swapchain_size);
+        state.swapchain_out_of_date();
+        assert_eq!(state.begin_frame(), SwapchainFrameAction::Recreate);
+        assert_eq!(state.begin_frame(), SwapchainFrameAction::Render);
+    }
+
+    #[test]
+    fn minimized_skips_frames() {
+        let mut state = SwapchainState::new();
+        let swapchain_size = Vec2UI::new(1280, 720);
+        state.window_changed(&WindowState::Minimized, swapchain_size);
+        state.swapchain_out_of_date();
+        assert_eq!(state.begin_frame(), SwapchainFrameAction::Skip);
+        assert_eq!(state.begin_frame(), SwapchainFrameAction::Skip);
+
+        state.window_changed(&WindowState::Window(Vec2UI::new(0, 720)), swapchain_size);
+        assert_eq!(state.begin_frame(), SwapchainFrameAction::Skip);
+
+        // The recreation that was requested while minimized still happens after restoring.
+        state.window_changed(&WindowState::Window(swapchain_size), swapchain_size);
+        assert_eq!(state.begin_frame(), SwapchainFrameAction::Recreate);
+        assert_eq!(state.begin_frame(), SwapchainFrameAction::Render);
+    }
+}
@@ -17,7 +17,7 @@ use crate::asset::{
     AssetManager,
     MeshRange,
 };
-use crate::camera::ActiveCamera;
+use crate::camera::{ActiveCamera, Projection};
 use crate::asset::AssetManagerECSResource;
 use crate::fps_camera::FPSCameraComponent;
 use crate::input::InputState;
@@ -342,8 +342,10 @@ impl<P: Platform> Plugin for SpinningCubePlugin<P> {
 
         let camera = app.world_mut().spawn((
             Camera {
-                fov: f32::consts::PI / 2f32,
+                projection: Projection::Perspective { fov: f32::consts::PI / 2f32 },
                 interpolate_rotation: false,
+                z_near: 0.1f32,
+                z_far: 100f32,
             },
             Transform::from_translation(Vec3::new(0.0f32, 0.0f32, -1.0f32)),
             FPSCameraComponent::default(),
@@ -1,8 +1,9 @@
 use bevy_app::{App, FixedPostUpdate, Plugin, PostUpdate, PreUpdate, Update};
-use bevy_ecs::{component::Component, entity::Entity, query::{Added, Without}, system::{Commands, Query, Res}};
+use bevy_ecs::{component::Component, entity::Entity, query::{Added, Without}, schedule::IntoSystemConfigs, system::{Commands, Query, Res}};
 use bevy_math::{Affine3A, VectorSpace};
 use bevy_time::{Fixed, Time};
 use bevy_transform::components::{GlobalTransform, Transform};
+use bevy_transform::systems::{propagate_transforms, sync_simple_transforms};
 
 #[derive(Component)]
 pub struct PreviousGlobalTransform(pub Affine3A);
@@ -16,7 +17,13 @@ pub struct InterpolationPlugin;
 impl Plugin for InterpolationPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(PreUpdate, add_global_transform);
-        app.add_systems(FixedPostUpdate, update_previous_global_transform);
+        // The global transforms are only updated in PostUpdate by default.
+        // Propagate them after every fixed update too so the previous transform
+        // always holds the state of the last tick, even if multiple ticks run in one frame.
+        app.add_systems(FixedPostUpdate, (
+            update_previous_global_transform,
+            (sync_simple_transforms, propagate_transforms)
+        ).chain());
         app.add_systems(PostUpdate, interpolate_transform_matrix);
     }
 }
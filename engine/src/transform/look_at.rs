@@ -0,0 +1,73 @@
+use bevy_transform::components::Transform;
+use sourcerenderer_core::{
+    Matrix3,
+    Matrix4,
+    Quaternion,
+    Vec3,
+};
+
+/// Returns the rotation that points the engine's forward axis (+Z) from eye towards target.
+/// Matches the left handed convention of the camera view matrix.
+/// bevy's `Transform::looking_at` uses -Z as forward and can not be used for that.
+pub fn looking_at(eye: Vec3, target: Vec3, up: Vec3) -> Quaternion {
+    let forward = (target - eye).normalize();
+    let right = up.cross(forward).normalize();
+    let up = forward.cross(right);
+    Quaternion::from_mat3(&Matrix3::from_cols(right, up, forward))
+}
+
+/// Creates a transform at eye with a unit scale that looks at target.
+pub fn transform_looking_at(eye: Vec3, target: Vec3, up: Vec3) -> Transform {
+    Transform::from_translation(eye).with_rotation(looking_at(eye, target, up))
+}
+
+/// Composes a matrix from the translation, rotation and scale of the transform.
+pub fn to_matrix(transform: &Transform) -> Matrix4 {
+    Matrix4::from_scale_rotation_translation(transform.scale, transform.rotation, transform.translation)
+}
+
+/// Decomposes a matrix into translation, rotation and non-uniform scale.
+/// The matrix must not contain shear or perspective.
+pub fn from_matrix(matrix: &Matrix4) -> Transform {
+    let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+    Transform {
+        translation,
+        rotation,
+        scale,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 0.0001f32;
+
+    #[test]
+    fn matrix_round_trip() {
+        let transform = Transform {
+            translation: Vec3::new(1f32, -2f32, 30f32),
+            rotation: Quaternion::from_euler(sourcerenderer_core::EulerRot::YXZ, 0.7f32, -0.3f32, 1.9f32),
+            scale: Vec3::new(2f32, 0.5f32, 3f32),
+        };
+        let matrix = to_matrix(&transform);
+        let round_trip = from_matrix(&matrix);
+        assert!(round_trip.translation.abs_diff_eq(transform.translation, EPSILON));
+        assert!(round_trip.scale.abs_diff_eq(transform.scale, EPSILON));
+        // q and -q are the same rotation
+        assert!(round_trip.rotation.dot(transform.rotation).abs() > 1f32 - EPSILON);
+        assert!(to_matrix(&round_trip).abs_diff_eq(matrix, EPSILON));
+    }
+
+    #[test]
+    fn looks_at_target() {
+        let eye = Vec3::new(3f32, 1f32, -2f32);
+        let target = Vec3::new(-4f32, 5f32, 6f32);
+        let transform = transform_looking_at(eye, target, Vec3::Y);
+        assert_eq!(transform.scale, Vec3::ONE);
+        assert!((transform.rotation * Vec3::Z).abs_diff_eq((target - eye).normalize(), EPSILON));
+        // Stays upright, so the right axis is horizontal
+        assert!((transform.rotation * Vec3::X).y.abs() < EPSILON);
+        assert!((transform.rotation * Vec3::Y).y > 0f32);
+    }
+}
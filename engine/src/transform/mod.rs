@@ -1,5 +1,12 @@
 mod interpolation;
+mod look_at;
 pub use interpolation::{
     InterpolatedTransform,
     InterpolationPlugin
 };
+pub use look_at::{
+    looking_at,
+    transform_looking_at,
+    to_matrix,
+    from_matrix
+};
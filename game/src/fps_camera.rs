@@ -5,13 +5,12 @@ use bevy_ecs::event::EventReader;
 use bevy_ecs::query::{Has, With};
 use bevy_ecs::query::{QueryFilter};
 use bevy_ecs::system::{Query, Res};
-use bevy_input::keyboard::{KeyCode, KeyboardInput};
+use bevy_input::keyboard::KeyboardInput;
 use bevy_input::mouse::MouseMotion;
-use bevy_input::ButtonInput;
 use bevy_math::{Vec2, Vec3A};
 use bevy_time::{Fixed, Time};
 use bevy_transform::components::Transform;
-use sourcerenderer_core::input::Key;
+use sourcerenderer_core::input::{GamepadAxis, InputBinding, InputMap, Key, TouchAxis};
 use sourcerenderer_core::{
     Platform,
     Quaternion,
@@ -19,7 +18,31 @@ use sourcerenderer_core::{
     Vec3,
 };
 
-use sourcerenderer_engine::Camera;
+use sourcerenderer_engine::{Camera, InputMapResource};
+
+pub(crate) const MOVE_FORWARD: &str = "move_forward";
+pub(crate) const MOVE_BACKWARD: &str = "move_backward";
+pub(crate) const MOVE_LEFT: &str = "move_left";
+pub(crate) const MOVE_RIGHT: &str = "move_right";
+pub(crate) const MOVE_UP: &str = "move_up";
+pub(crate) const MOVE_DOWN: &str = "move_down";
+pub(crate) const LOOK_X: &str = "look_x";
+pub(crate) const LOOK_Y: &str = "look_y";
+
+pub(crate) fn bind_default_actions(input_map: &mut InputMap) {
+    input_map.bind(MOVE_FORWARD, InputBinding::Key(Key::W));
+    input_map.bind(MOVE_FORWARD, InputBinding::GamepadAxis(GamepadAxis::LeftStickY));
+    input_map.bind(MOVE_FORWARD, InputBinding::Touch(TouchAxis::JoystickY));
+    input_map.bind(MOVE_BACKWARD, InputBinding::Key(Key::S));
+    input_map.bind(MOVE_RIGHT, InputBinding::Key(Key::D));
+    input_map.bind(MOVE_RIGHT, InputBinding::GamepadAxis(GamepadAxis::LeftStickX));
+    input_map.bind(MOVE_RIGHT, InputBinding::Touch(TouchAxis::JoystickX));
+    input_map.bind(MOVE_LEFT, InputBinding::Key(Key::A));
+    input_map.bind(MOVE_UP, InputBinding::Key(Key::Q));
+    input_map.bind(MOVE_DOWN, InputBinding::Key(Key::E));
+    input_map.bind(LOOK_X, InputBinding::Touch(TouchAxis::LookX));
+    input_map.bind(LOOK_Y, InputBinding::Touch(TouchAxis::LookY));
+}
 
 pub fn install<P: Platform>(app: &mut App) {
     app.add_systems(Update, (retrieve_fps_camera_rotation::<P>, fps_camera_movement::<P>));
@@ -35,7 +58,6 @@ struct FPSCamera {
     sensitivity: f32,
     pitch: f32,
     yaw: f32,
-    last_touch_position: Vec2,
 }
 
 impl Default for FPSCamera {
@@ -44,70 +66,60 @@ impl Default for FPSCamera {
             sensitivity: 10.0f32,
             pitch: 0f32,
             yaw: 0f32,
-            last_touch_position: Vec2::new(0f32, 0f32),
         }
     }
 }
 
-fn fps_camera_rotation(mouse: &MouseMotion, fps_camera: &mut FPSCamera) -> Quaternion {
-    let touch_position = Vec2::new(0f32, 0f32);
-    let touch_delta = if fps_camera.last_touch_position.x.abs() > 0.1f32
-        && fps_camera.last_touch_position.y.abs() > 0.1f32
-        && touch_position.x.abs() > 0.1f32
-        && touch_position.y.abs() > 0.1f32
-    {
-        touch_position - fps_camera.last_touch_position
-    } else {
-        Vec2::new(0f32, 0f32)
-    };
-    fps_camera.pitch += mouse.delta.y as f32 / 20_000f32 * fps_camera.sensitivity;
-    fps_camera.yaw += mouse.delta.x as f32 / 20_000f32 * fps_camera.sensitivity;
-    fps_camera.pitch -= touch_delta.y / 20_000f32 * fps_camera.sensitivity;
-    fps_camera.yaw -= touch_delta.x / 20_000f32 * fps_camera.sensitivity;
+fn fps_camera_rotation(delta: Vec2, fps_camera: &mut FPSCamera) -> Quaternion {
+    fps_camera.pitch += delta.y / 20_000f32 * fps_camera.sensitivity;
+    fps_camera.yaw += delta.x / 20_000f32 * fps_camera.sensitivity;
 
     fps_camera.pitch = fps_camera
         .pitch
         .max(-std::f32::consts::FRAC_PI_2 + 0.01f32)
         .min(std::f32::consts::FRAC_PI_2 - 0.01f32);
 
-    fps_camera.last_touch_position = touch_position;
     Quaternion::from_euler(bevy_math::EulerRot::XYZ, fps_camera.pitch, fps_camera.yaw, 0f32)
 }
 
 pub(crate) fn retrieve_fps_camera_rotation<P: Platform>(
     mut mouse_motion: EventReader<MouseMotion>,
+    input_map: Res<InputMapResource>,
     mut query: Query<(&mut Transform, &mut FPSCameraComponent), With<Camera>>,
 ) {
+    // Dragging the view should move the camera the other way than moving the mouse.
+    let mut delta = -Vec2::new(input_map.0.axis_value(LOOK_X), input_map.0.axis_value(LOOK_Y));
+    for event in mouse_motion.read() {
+        delta += event.delta;
+    }
+    if delta.x.abs() < 0.00001f32 && delta.y.abs() < 0.00001f32 {
+        return;
+    }
     for (mut transform, mut fps_camera) in query.iter_mut() {
-        for event in mouse_motion.read() {
-            transform.rotation = fps_camera_rotation(event, &mut fps_camera.fps_camera);
-        }
+        transform.rotation = fps_camera_rotation(delta, &mut fps_camera.fps_camera);
     }
 }
 
 pub(crate) fn fps_camera_movement<P: Platform>(
-    keyboard: Res<ButtonInput<KeyCode>>,
+    input_map: Res<InputMapResource>,
     mut query: Query<&mut Transform, (With<Camera>, With<FPSCameraComponent>)>,
     tick_rate: Res<Time<Fixed>>,
 ) {
-    let mut movement_vector = Vec3::new(0f32, 0f32, 0f32);
-    if keyboard.pressed(KeyCode::KeyW) {
-        movement_vector.z += 1f32;
-    }
-    if keyboard.pressed(KeyCode::KeyS) {
-        movement_vector.z -= 1f32;
-    }
-    if keyboard.pressed(KeyCode::KeyA) {
-        movement_vector.x -= 1f32;
-    }
-    if keyboard.pressed(KeyCode::KeyD) {
-        movement_vector.x += 1f32;
-    }
-    if keyboard.pressed(KeyCode::KeyQ) {
-        movement_vector.y += 1f32;
-    }
-    if keyboard.pressed(KeyCode::KeyE) {
-        movement_vector.y -= 1f32;
+    let input_map = &input_map.0;
+    let mut movement_vector = Vec3::new(
+        input_map.axis_value(MOVE_RIGHT) - input_map.axis_value(MOVE_LEFT),
+        input_map.axis_value(MOVE_UP) - input_map.axis_value(MOVE_DOWN),
+        input_map.axis_value(MOVE_FORWARD) - input_map.axis_value(MOVE_BACKWARD),
+    );
+
+    // The virtual joystick and the gamepad sticks are analog, keep their length to walk slower.
+    let mut speed = 1f32;
+    let horizontal_length = Vec2::new(movement_vector.x, movement_vector.z).length();
+    if horizontal_length < 0.1f32 {
+        movement_vector.x = 0f32;
+        movement_vector.z = 0f32;
+    } else {
+        speed = horizontal_length.min(1f32);
     }
 
     for mut transform in query.iter_mut() {
@@ -124,7 +136,7 @@ pub(crate) fn fps_camera_movement<P: Platform>(
             || movement_vector.z.abs() > 0.00001f32
         {
             movement_vector = movement_vector.normalize();
-            transform.translation += movement_vector * 8f32 * tick_rate.timestep().as_secs_f32();
+            transform.translation += movement_vector * speed * 8f32 * tick_rate.timestep().as_secs_f32();
         }
     }
 }
@@ -2,9 +2,9 @@ use std::{marker::PhantomData, sync::Arc};
 
 use bevy_app::{App, FixedUpdate, Plugin, Update};
 use sourcerenderer_core::Platform;
-use sourcerenderer_engine::{asset::{loaders::GltfContainer, AssetLoadPriority, AssetManager, AssetType}, Engine};
+use sourcerenderer_engine::{asset::{loaders::GltfContainer, AssetLoadPriority, AssetManager, AssetType}, Engine, InputMapResource};
 
-use crate::{fps_camera::{fps_camera_movement, retrieve_fps_camera_rotation}, spinning_cube::SpinningCubePlugin};
+use crate::{fps_camera::{bind_default_actions, fps_camera_movement, retrieve_fps_camera_rotation}, spinning_cube::SpinningCubePlugin};
 
 pub struct GamePlugin<P: Platform>(PhantomData<P>);
 
@@ -30,6 +30,8 @@ impl<P: Platform> Plugin for GamePlugin<P> {
             asset_manager.request_asset("bistro_sun.glb/scene/Scene", AssetType::Level, AssetLoadPriority::High);*/
         }
 
+        bind_default_actions(&mut app.world_mut().resource_mut::<InputMapResource>().0);
+
         app
             .add_systems(FixedUpdate, fps_camera_movement::<P>)
             .add_systems(Update, retrieve_fps_camera_rotation::<P>)
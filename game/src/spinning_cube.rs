@@ -21,7 +21,7 @@ use sourcerenderer_engine::asset::{
     AssetManager,
     MeshRange,
 };
-use sourcerenderer_engine::camera::ActiveCamera;
+use sourcerenderer_engine::camera::{ActiveCamera, Projection};
 use crate::fps_camera::FPSCameraComponent;
 use sourcerenderer_engine::math::BoundingBox;
 use sourcerenderer_engine::renderer::{
@@ -350,8 +350,10 @@ impl<P: Platform> Plugin for SpinningCubePlugin<P> {
 
         let camera = app.world_mut().spawn((
             Camera {
-                fov: f32::consts::PI / 2f32,
+                projection: Projection::Perspective { fov: f32::consts::PI / 2f32 },
                 interpolate_rotation: false,
+                z_near: 0.1f32,
+                z_far: 100f32,
             },
             Transform::from_translation(Vec3::new(0.0f32, 1.0f32, -1.0f32)),
             FPSCameraComponent::default(),
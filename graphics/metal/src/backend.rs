@@ -21,6 +21,7 @@ impl gpu::GPUBackend for MTLBackend {
     type Swapchain = MTLSwapchain;
     type Fence = MTLFence;
     type Heap = MTLHeap;
+    type QueryPool = MTLQueryPool;
     type Shader = MTLShader;
     type GraphicsPipeline = MTLGraphicsPipeline;
     type ComputePipeline = MTLComputePipeline;
@@ -1,30 +1,69 @@
 use metal;
 
+use sourcerenderer_core::gpu;
+
 use super::*;
 
+/// Max number of textures a shader can access through an argument buffer on Tier 1 hardware.
+const TIER1_MAX_TEXTURES: u32 = 128;
+/// Max number of textures a shader can access through an argument buffer on Tier 2 hardware.
+const TIER2_MAX_TEXTURES: u32 = 500_000;
+
+/// The bindless texture table.
+/// Writing resource ids straight into the argument buffer requires Tier 2 argument buffers and Metal 3,
+/// the slots match the bindless descriptor set on Vulkan.
 pub(crate) struct MTLBindlessArgumentBuffer {
-    argument_buffer: metal::Buffer
+    argument_buffer: Option<metal::Buffer>,
+    capacity: u32
 }
 
 impl MTLBindlessArgumentBuffer {
-    pub(crate) fn new(device: &metal::DeviceRef, size: usize) -> Self {
-        let buffer = device.new_buffer((std::mem::size_of::<metal::MTLResourceID>() * size) as u64, metal::MTLResourceOptions::StorageModeShared);
+    pub(crate) fn new(device: &metal::DeviceRef, size: u32) -> Self {
+        let tier = device.argument_buffers_support();
+        let max_textures = match tier {
+            metal::MTLArgumentBuffersTier::Tier1 => TIER1_MAX_TEXTURES,
+            metal::MTLArgumentBuffersTier::Tier2 => TIER2_MAX_TEXTURES,
+        };
+        let capacity = size.min(max_textures);
+
+        // Shaders declare the bindless texture array with a fixed size,
+        // so a smaller table is not usable.
+        let is_supported = matches!(tier, metal::MTLArgumentBuffersTier::Tier2)
+            && device.supports_family(metal::MTLGPUFamily::Metal3)
+            && capacity >= gpu::BINDLESS_TEXTURE_COUNT;
+        if !is_supported {
+            return Self {
+                argument_buffer: None,
+                capacity: 0
+            };
+        }
+
+        let buffer = device.new_buffer((std::mem::size_of::<metal::MTLResourceID>() * capacity as usize) as u64, metal::MTLResourceOptions::StorageModeShared);
         buffer.set_label("Bindless textures");
         Self {
-            argument_buffer: buffer
+            argument_buffer: Some(buffer),
+            capacity
         }
     }
 
-    pub(crate) fn insert(&self, texture_view: &MTLTextureView, slot: u32) {
+    pub(crate) fn is_supported(&self) -> bool {
+        self.argument_buffer.is_some()
+    }
+
+    /// Writes the texture into the given slot.
+    /// The texture has to be resident when it gets accessed, which is the case for every texture that was allocated from a heap.
+    pub(crate) fn write_texture(&self, slot: u32, texture_view: &MTLTextureView) {
+        assert!(slot < self.capacity);
+        let argument_buffer = self.argument_buffer.as_ref().unwrap();
         unsafe {
-            let ptr = self.argument_buffer.contents();
+            let ptr = argument_buffer.contents();
             let mut resource_id_ptr: *mut metal::MTLResourceID = std::mem::transmute(ptr);
             resource_id_ptr = resource_id_ptr.offset(slot as isize);
             *resource_id_ptr = texture_view.handle().gpu_resource_id();
         }
     }
 
-    pub(crate) fn handle(&self) -> &metal::BufferRef {
-        &self.argument_buffer
+    pub(crate) fn handle(&self) -> Option<&metal::BufferRef> {
+        self.argument_buffer.as_deref()
     }
 }
@@ -452,11 +452,34 @@ impl gpu::CommandBuffer<MTLBackend> for MTLCommandBuffer {
         }
     }
 
-    unsafe fn draw_indexed_indirect(&mut self, draw_buffer: &MTLBuffer, draw_buffer_offset: u32, count_buffer: &MTLBuffer, count_buffer_offset: u32, max_draw_count: u32, stride: u32) {
+    unsafe fn draw_indexed_indirect(&mut self, draw_buffer: &MTLBuffer, draw_buffer_offset: u32, draw_count: u32, stride: u32) {
+        let index_buffer = self.index_buffer.as_ref()
+            .expect("No index buffer bound");
+        let encoder = self.get_render_pass_encoder();
+        for i in 0..draw_count {
+            encoder.draw_indexed_primitives_indirect(
+                self.primitive_type,
+                index_format_to_mtl(index_buffer.format),
+                &index_buffer.buffer,
+                index_buffer.offset as u64,
+                draw_buffer.handle(),
+                (draw_buffer_offset + i * stride) as u64
+            );
+        }
+    }
+
+    unsafe fn draw_indirect(&mut self, draw_buffer: &MTLBuffer, draw_buffer_offset: u32, draw_count: u32, stride: u32) {
+        let encoder = self.get_render_pass_encoder();
+        for i in 0..draw_count {
+            encoder.draw_primitives_indirect(self.primitive_type, draw_buffer.handle(), (draw_buffer_offset + i * stride) as u64);
+        }
+    }
+
+    unsafe fn draw_indexed_indirect_count(&mut self, draw_buffer: &MTLBuffer, draw_buffer_offset: u32, count_buffer: &MTLBuffer, count_buffer_offset: u32, max_draw_count: u32, stride: u32) {
        self.multi_draw_indirect(true, draw_buffer, draw_buffer_offset, count_buffer, count_buffer_offset, max_draw_count, stride);
     }
 
-    unsafe fn draw_indirect(&mut self, draw_buffer: &MTLBuffer, draw_buffer_offset: u32, count_buffer: &MTLBuffer, count_buffer_offset: u32, max_draw_count: u32, stride: u32) {
+    unsafe fn draw_indirect_count(&mut self, draw_buffer: &MTLBuffer, draw_buffer_offset: u32, count_buffer: &MTLBuffer, count_buffer_offset: u32, max_draw_count: u32, stride: u32) {
         self.multi_draw_indirect(false, draw_buffer, draw_buffer_offset, count_buffer, count_buffer_offset, max_draw_count, stride);
     }
 
@@ -513,7 +536,7 @@ impl gpu::CommandBuffer<MTLBackend> for MTLCommandBuffer {
             self.binding.finish(MTLEncoderRef::Compute(encoder), self.resource_map.as_ref().expect("Need to bind a shader before finishing binding."));
             let bindless_map = &self.resource_map.as_ref().unwrap().bindless_argument_buffer_binding;
             if let Some(bindless_binding) = bindless_map.get(&gpu::ShaderType::ComputeShader) {
-                encoder.set_buffer(*bindless_binding as u64, Some(self.shared.bindless.handle()), 0);
+                encoder.set_buffer(*bindless_binding as u64, self.shared.bindless.handle(), 0);
             }
         }
 
@@ -522,10 +545,10 @@ impl gpu::CommandBuffer<MTLBackend> for MTLCommandBuffer {
                 self.binding.finish(MTLEncoderRef::Graphics(rp), self.resource_map.as_ref().expect("Need to bind a shader before finishing binding."));
                 let bindless_map = &self.resource_map.as_ref().unwrap().bindless_argument_buffer_binding;
                 if let Some(bindless_binding) = bindless_map.get(&gpu::ShaderType::VertexShader) {
-                    rp.set_vertex_buffer(*bindless_binding as u64, Some(self.shared.bindless.handle()), 0);
+                    rp.set_vertex_buffer(*bindless_binding as u64, self.shared.bindless.handle(), 0);
                 }
                 if let Some(bindless_binding) = bindless_map.get(&gpu::ShaderType::FragmentShader) {
-                    rp.set_fragment_buffer(*bindless_binding as u64, Some(self.shared.bindless.handle()), 0);
+                    rp.set_fragment_buffer(*bindless_binding as u64, self.shared.bindless.handle(), 0);
                 }
             }
             _ => {}
@@ -540,6 +563,10 @@ impl gpu::CommandBuffer<MTLBackend> for MTLCommandBuffer {
         self.handle().pop_debug_group();
     }
 
+    unsafe fn reset_queries(&mut self, _query_pool: &MTLQueryPool, _first_query: u32, _query_count: u32) {}
+
+    unsafe fn write_timestamp(&mut self, _query_pool: &MTLQueryPool, _query_index: u32) {}
+
     unsafe fn dispatch(&mut self, group_count_x: u32, group_count_y: u32, group_count_z: u32) {
         let compute_encoder = self.get_compute_encoder();
         compute_encoder.dispatch_thread_groups(metal::MTLSize::new(group_count_x as u64, group_count_y as u64, group_count_z as u64), metal::MTLSize::new(8, 8, 1));
@@ -619,6 +646,42 @@ impl gpu::CommandBuffer<MTLBackend> for MTLCommandBuffer {
         );
     }
 
+    unsafe fn copy_texture_to_buffer(&mut self, src: &MTLTexture, dst: &MTLBuffer, region: &gpu::BufferTextureCopyRegion) {
+        let blit_encoder = self.get_blit_encoder();
+        let format = src.info().format;
+        let row_pitch = if region.buffer_row_pitch != 0 {
+            region.buffer_row_pitch
+        } else {
+            (align_up_32(region.texture_extent.x, format.block_size().x) / format.block_size().x * format.element_size()) as u64
+        };
+        let slice_pitch = if region.buffer_slice_pitch != 0 {
+            region.buffer_slice_pitch
+        } else {
+            (align_up_32(region.texture_extent.y, format.block_size().y) / format.block_size().y) as u64 * row_pitch
+        };
+
+        blit_encoder.copy_from_texture_to_buffer(
+            src.handle(),
+            region.texture_subresource.array_layer as u64,
+            region.texture_subresource.mip_level as u64,
+            metal::MTLOrigin {
+                x: region.texture_offset.x as u64,
+                y: region.texture_offset.y as u64,
+                z: region.texture_offset.z as u64
+            },
+            metal::MTLSize {
+                width: region.texture_extent.x as u64,
+                height: region.texture_extent.y as u64,
+                depth: region.texture_extent.z as u64
+            },
+            dst.handle(),
+            region.buffer_offset,
+            row_pitch,
+            slice_pitch,
+            metal::MTLBlitOption::empty()
+        );
+    }
+
     unsafe fn copy_buffer(&mut self, src: &MTLBuffer, dst: &MTLBuffer, region: &gpu::BufferCopyRegion) {
         let blit_encoder = self.get_blit_encoder();
         blit_encoder.copy_from_buffer(src.handle(), region.src_offset, dst.handle(), region.dst_offset, region.size);
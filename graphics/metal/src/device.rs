@@ -47,7 +47,7 @@ impl MTLDevice {
             infos[2].memory_kind = gpu::MemoryKind::VRAM;
         }
 
-        let bindless = MTLBindlessArgumentBuffer::new(&device, gpu::BINDLESS_TEXTURE_COUNT as usize);
+        let bindless = MTLBindlessArgumentBuffer::new(&device, gpu::BINDLESS_TEXTURE_COUNT);
         let shared = Arc::new(MTLShared::new(device, bindless));
 
         Self {
@@ -241,7 +241,7 @@ impl gpu::Device<MTLBackend> for MTLDevice {
     }
 
     unsafe fn insert_texture_into_bindless_heap(&self, slot: u32, texture: &MTLTextureView) {
-        self.shared.bindless.insert(texture, slot);
+        self.shared.bindless.write_texture(slot, texture);
     }
 
     fn graphics_queue(&self) -> &MTLQueue {
@@ -257,7 +257,7 @@ impl gpu::Device<MTLBackend> for MTLDevice {
     }
 
     fn supports_bindless(&self) -> bool {
-        true
+        self.shared.bindless.is_supported()
     }
 
     fn supports_ray_tracing(&self) -> bool {
@@ -276,6 +276,27 @@ impl gpu::Device<MTLBackend> for MTLDevice {
         self.device.supports_shader_barycentric_coordinates()
     }
 
+    fn supports_timestamp_queries(&self) -> bool {
+        false
+    }
+
+    fn max_push_constant_size(&self) -> u32 {
+        // Apple recommends setBytes for data smaller than 4KB.
+        4096
+    }
+
+    unsafe fn create_timestamp_query_pool(&self, query_count: u32, _name: Option<&str>) -> MTLQueryPool {
+        MTLQueryPool::new(query_count)
+    }
+
+    unsafe fn load_pipeline_cache(&self, _data: &[u8]) -> bool {
+        false
+    }
+
+    fn pipeline_cache_data(&self) -> Option<Vec<u8>> {
+        None
+    }
+
     unsafe fn get_bottom_level_acceleration_structure_size(&self, info: &gpu::BottomLevelAccelerationStructureInfo<MTLBackend>) -> gpu::AccelerationStructureSizes {
         MTLAccelerationStructure::bottom_level_size(&self.device, info)
     }
@@ -42,8 +42,16 @@ impl MTLAdapter {
 }
 
 impl gpu::Adapter<MTLBackend> for MTLAdapter {
+    fn name(&self) -> &str {
+        self.device.name()
+    }
+
     fn adapter_type(&self) -> gpu::AdapterType {
-        gpu::AdapterType::Integrated
+        if self.device.has_unified_memory() || self.device.is_low_power() {
+            gpu::AdapterType::Integrated
+        } else {
+            gpu::AdapterType::Discrete
+        }
     }
 
     fn create_device(&self, surface: &MTLSurface) -> MTLDevice {
@@ -14,6 +14,7 @@ pub(crate) use self::{
     renderpass::*,
     shared::*,
     bindless::*,
+    query::*,
 };
 
 pub use self::{
@@ -40,3 +41,4 @@ mod binding;
 mod renderpass;
 mod shared;
 mod bindless;
+mod query;
@@ -0,0 +1,22 @@
+use sourcerenderer_core::gpu;
+
+/// Timestamp queries aren't implemented for Metal yet, see `supports_timestamp_queries`.
+pub struct MTLQueryPool {
+    query_count: u32,
+}
+
+impl MTLQueryPool {
+    pub(crate) fn new(query_count: u32) -> Self {
+        Self { query_count }
+    }
+}
+
+impl gpu::QueryPool for MTLQueryPool {
+    fn query_count(&self) -> u32 {
+        self.query_count
+    }
+
+    unsafe fn read_timestamps(&self, _first_query: u32, _results: &mut [u64]) -> bool {
+        false
+    }
+}
@@ -46,14 +46,16 @@ pub struct MTLSwapchain {
     device: metal::Device,
     width: u32,
     height: u32,
-    format: Format
+    format: Format,
+    present_mode: gpu::PresentMode
 }
 
 const IMAGE_COUNT: u32 = 3;
 
 impl MTLSwapchain {
-    pub fn new(surface: MTLSurface, device: &metal::DeviceRef, extents: Option<(u32, u32)>) -> Self {
+    pub fn new(surface: MTLSurface, device: &metal::DeviceRef, extents: Option<(u32, u32)>, present_mode: gpu::PresentMode) -> Self {
         surface.layer.set_device(device);
+        let present_mode = Self::apply_present_mode(&surface, present_mode);
         assert!(IMAGE_COUNT == 2 || IMAGE_COUNT == 3);
         surface.layer.set_maximum_drawable_count(IMAGE_COUNT as u64);
 
@@ -73,7 +75,27 @@ impl MTLSwapchain {
             device: device.to_owned(),
             width,
             height,
-            format
+            format,
+            present_mode
+        }
+    }
+
+    /// CAMetalLayer can only turn vsync on or off, everything else falls back to one of those.
+    /// Turning off vsync is only supported on macOS.
+    fn apply_present_mode(surface: &MTLSurface, present_mode: gpu::PresentMode) -> gpu::PresentMode {
+        let vsync = match present_mode {
+            gpu::PresentMode::Immediate | gpu::PresentMode::Mailbox => false,
+            gpu::PresentMode::Fifo | gpu::PresentMode::FifoRelaxed => true
+        };
+        #[cfg(target_os = "macos")]
+        {
+            surface.layer.set_display_sync_enabled(vsync);
+            if vsync { gpu::PresentMode::Fifo } else { gpu::PresentMode::Immediate }
+        }
+        #[cfg(not(target_os = "macos"))]
+        {
+            let _ = (surface, vsync);
+            gpu::PresentMode::Fifo
         }
     }
 
@@ -105,6 +127,19 @@ impl gpu::Swapchain<MTLBackend> for MTLSwapchain {
 
     unsafe fn recreate(&mut self) {}
 
+    unsafe fn set_present_mode(&mut self, present_mode: gpu::PresentMode) {
+        self.present_mode = Self::apply_present_mode(&self.surface, present_mode);
+    }
+
+    fn present_mode(&self) -> gpu::PresentMode {
+        self.present_mode
+    }
+
+    fn color_space(&self) -> gpu::ColorSpace {
+        // HDR output requires setting up the CAMetalLayer color space which isn't implemented yet.
+        gpu::ColorSpace::Srgb
+    }
+
     fn format(&self) -> gpu::Format {
         self.format
     }
@@ -1,3 +1,4 @@
+use log::warn;
 use metal;
 use metal::foreign_types::ForeignType;
 
@@ -63,6 +64,7 @@ pub(crate) fn format_from_metal(format: metal::MTLPixelFormat) -> Format {
         metal::MTLPixelFormat::RGBA16Float => gpu::Format::RGBA16Float,
         metal::MTLPixelFormat::BGRA8Unorm => gpu::Format::BGRA8UNorm,
         metal::MTLPixelFormat::RGBA8Unorm_sRGB => gpu::Format::RGBA8Srgb,
+        metal::MTLPixelFormat::RGB10A2Unorm => gpu::Format::RGB10A2UNorm,
         _ => panic!("Unsupported texture format")
     }
 }
@@ -347,7 +349,10 @@ impl MTLSampler {
         if let Some(compare_op) = info.compare_op {
             descriptor.set_compare_function(compare_op_to_mtl(compare_op));
         }
-        descriptor.set_lod_max_clamp(info.min_lod);
+        if info.mip_bias != 0f32 {
+            warn!("Metal samplers don't support a mip bias, it needs to be applied in the shader.");
+        }
+        descriptor.set_lod_min_clamp(info.min_lod);
         if let Some(max) = info.max_lod {
             descriptor.set_lod_max_clamp(max);
         }
@@ -66,6 +66,7 @@ pub struct VkAdapter {
     physical_device: vk::PhysicalDevice,
     properties: vk::PhysicalDeviceProperties,
     extensions: VkAdapterExtensionSupport,
+    name: String,
 }
 
 impl VkAdapter {
@@ -107,11 +108,16 @@ impl VkAdapter {
             };
         }
 
+        let name = unsafe { CStr::from_ptr(properties.device_name.as_ptr()) }
+            .to_string_lossy()
+            .into_owned();
+
         VkAdapter {
             instance: instance.clone(),
             physical_device,
             properties,
             extensions,
+            name,
         }
     }
 
@@ -612,6 +618,10 @@ impl gpu::Adapter<VkBackend> for VkAdapter {
         };
     }
 
+    fn name(&self) -> &str {
+        &self.name
+    }
+
     fn adapter_type(&self) -> gpu::AdapterType {
         match self.properties.device_type {
             vk::PhysicalDeviceType::DISCRETE_GPU => gpu::AdapterType::Discrete,
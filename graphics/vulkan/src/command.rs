@@ -613,6 +613,17 @@ impl gpu::CommandBuffer<VkBackend> for VkCommandBuffer {
         }
     }
 
+    unsafe fn reset_queries(&mut self, query_pool: &VkQueryPool, first_query: u32, query_count: u32) {
+        debug_assert_eq!(self.state.load(), VkCommandBufferState::Recording);
+        debug_assert!(!self.is_in_render_pass);
+        self.device.cmd_reset_query_pool(self.cmd_buffer, query_pool.handle(), first_query, query_count);
+    }
+
+    unsafe fn write_timestamp(&mut self, query_pool: &VkQueryPool, query_index: u32) {
+        debug_assert_eq!(self.state.load(), VkCommandBufferState::Recording);
+        self.device.cmd_write_timestamp2(self.cmd_buffer, vk::PipelineStageFlags2::ALL_COMMANDS, query_pool.handle(), query_index);
+    }
+
     unsafe fn execute_inner(&mut self, submissions: &[&VkCommandBuffer]) {
         debug_assert_eq!(self.state.load(), VkCommandBufferState::Recording);
         if submissions.is_empty() {
@@ -1151,6 +1162,84 @@ impl gpu::CommandBuffer<VkBackend> for VkCommandBuffer {
     }
 
     unsafe fn draw_indexed_indirect(
+        &mut self,
+        draw_buffer: &VkBuffer,
+        draw_buffer_offset: u32,
+        draw_count: u32,
+        stride: u32,
+    ) {
+        debug_assert_eq!(self.state.load(), VkCommandBufferState::Recording);
+        debug_assert!(self.pipeline.is_some());
+        debug_assert!(
+            if let BoundPipeline::Graphics { .. } = self.pipeline.as_ref().unwrap() { true } else { false }
+        );
+        debug_assert!(
+            self.is_in_render_pass || self.command_buffer_type == gpu::CommandBufferType::Secondary
+        );
+        unsafe {
+            if self.device.features.contains(VkFeatures::ADVANCED_INDIRECT) {
+                self.device.cmd_draw_indexed_indirect(
+                    self.cmd_buffer,
+                    draw_buffer.handle(),
+                    draw_buffer_offset as u64,
+                    draw_count,
+                    stride,
+                );
+            } else {
+                // Without multiDrawIndirect the draw count has to be 0 or 1.
+                for i in 0..draw_count {
+                    self.device.cmd_draw_indexed_indirect(
+                        self.cmd_buffer,
+                        draw_buffer.handle(),
+                        draw_buffer_offset as u64 + i as u64 * stride as u64,
+                        1,
+                        stride,
+                    );
+                }
+            }
+        }
+    }
+
+    unsafe fn draw_indirect(
+        &mut self,
+        draw_buffer: &VkBuffer,
+        draw_buffer_offset: u32,
+        draw_count: u32,
+        stride: u32,
+    ) {
+        debug_assert_eq!(self.state.load(), VkCommandBufferState::Recording);
+        debug_assert!(self.pipeline.is_some());
+        debug_assert!(
+            if let BoundPipeline::Graphics { .. } = self.pipeline.as_ref().unwrap() { true } else { false }
+        );
+        debug_assert!(
+            self.is_in_render_pass || self.command_buffer_type == gpu::CommandBufferType::Secondary
+        );
+        unsafe {
+            if self.device.features.contains(VkFeatures::ADVANCED_INDIRECT) {
+                self.device.cmd_draw_indirect(
+                    self.cmd_buffer,
+                    draw_buffer.handle(),
+                    draw_buffer_offset as u64,
+                    draw_count,
+                    stride,
+                );
+            } else {
+                // See draw_indexed_indirect
+                for i in 0..draw_count {
+                    self.device.cmd_draw_indirect(
+                        self.cmd_buffer,
+                        draw_buffer.handle(),
+                        draw_buffer_offset as u64 + i as u64 * stride as u64,
+                        1,
+                        stride,
+                    );
+                }
+            }
+        }
+    }
+
+    unsafe fn draw_indexed_indirect_count(
         &mut self,
         draw_buffer: &VkBuffer,
         draw_buffer_offset: u32,
@@ -1159,6 +1248,13 @@ impl gpu::CommandBuffer<VkBackend> for VkCommandBuffer {
         max_draw_count: u32,
         stride: u32,
     ) {
+        if !self.device.features.contains(VkFeatures::ADVANCED_INDIRECT) {
+            // drawIndirectCount is missing, draw the maximum and rely on the unused draws being empty.
+            unsafe {
+                self.draw_indexed_indirect(draw_buffer, draw_buffer_offset, max_draw_count, stride);
+            }
+            return;
+        }
         debug_assert_eq!(self.state.load(), VkCommandBufferState::Recording);
         debug_assert!(self.pipeline.is_some());
         debug_assert!(
@@ -1181,7 +1277,7 @@ impl gpu::CommandBuffer<VkBackend> for VkCommandBuffer {
         }
     }
 
-    unsafe fn draw_indirect(
+    unsafe fn draw_indirect_count(
         &mut self,
         draw_buffer: &VkBuffer,
         draw_buffer_offset: u32,
@@ -1190,6 +1286,13 @@ impl gpu::CommandBuffer<VkBackend> for VkCommandBuffer {
         max_draw_count: u32,
         stride: u32,
     ) {
+        if !self.device.features.contains(VkFeatures::ADVANCED_INDIRECT) {
+            // See draw_indexed_indirect_count
+            unsafe {
+                self.draw_indirect(draw_buffer, draw_buffer_offset, max_draw_count, stride);
+            }
+            return;
+        }
         debug_assert_eq!(self.state.load(), VkCommandBufferState::Recording);
         debug_assert!(self.pipeline.is_some());
         debug_assert!(
@@ -1472,6 +1575,40 @@ impl gpu::CommandBuffer<VkBackend> for VkCommandBuffer {
         self.device.cmd_copy_buffer_to_image(self.cmd_buffer, src.handle(), dst.handle(), vk::ImageLayout::TRANSFER_DST_OPTIMAL, &[copy]);
     }
 
+    unsafe fn copy_texture_to_buffer(&mut self, src: &VkTexture, dst: &VkBuffer, region: &gpu::BufferTextureCopyRegion) {
+        debug_assert_eq!(self.state.load(), VkCommandBufferState::Recording);
+        debug_assert!(!self.is_in_render_pass);
+        let format = src.info().format;
+        let texels_width = if region.buffer_row_pitch != 0 {
+            (region.buffer_row_pitch as u32) * format.block_size().x / format.element_size()
+        } else {
+            0
+        };
+        let texels_height = if region.buffer_slice_pitch != 0 && region.buffer_row_pitch != 0 {
+            (region.buffer_slice_pitch / region.buffer_row_pitch) as u32 * format.block_size().y
+        } else {
+            0
+        };
+
+        let copy = vk::BufferImageCopy {
+            image_subresource: texture_subresource_to_vk_layers(&region.texture_subresource, format, 1),
+            buffer_offset: region.buffer_offset,
+            buffer_row_length: texels_width,
+            buffer_image_height: texels_height,
+            image_offset: vk::Offset3D {
+                x: region.texture_offset.x as i32,
+                y: region.texture_offset.y as i32,
+                z: region.texture_offset.z as i32
+            },
+            image_extent: vk::Extent3D {
+                width: region.texture_extent.x,
+                height: region.texture_extent.y,
+                depth: region.texture_extent.z,
+            }
+        };
+        self.device.cmd_copy_image_to_buffer(self.cmd_buffer, src.handle(), vk::ImageLayout::TRANSFER_SRC_OPTIMAL, dst.handle(), &[copy]);
+    }
+
     unsafe fn finish(&mut self) {
         debug_assert_eq!(self.state.load(), VkCommandBufferState::Recording);
         if self.is_in_render_pass {
@@ -210,6 +210,10 @@ impl gpu::Device<VkBackend> for VkDevice {
         self.device.features.contains(VkFeatures::MIN_MAX_FILTER)
     }
 
+    fn max_push_constant_size(&self) -> u32 {
+        self.device.properties.limits.max_push_constants_size
+    }
+
     unsafe fn insert_texture_into_bindless_heap(&self, slot: u32, texture: &VkTextureView) {
         if let Some(bindless_set) = self.shared.bindless_texture_descriptor_set() {
             bindless_set.write_texture_descriptor(slot, texture);
@@ -220,6 +224,47 @@ impl gpu::Device<VkBackend> for VkDevice {
         self.device.features.contains(VkFeatures::BARYCENTRICS)
     }
 
+    fn supports_timestamp_queries(&self) -> bool {
+        self.device.properties.limits.timestamp_compute_and_graphics == vk::TRUE
+    }
+
+    unsafe fn create_timestamp_query_pool(&self, query_count: u32, name: Option<&str>) -> VkQueryPool {
+        VkQueryPool::new(&self.device, vk::QueryType::TIMESTAMP, query_count, name)
+    }
+
+    unsafe fn load_pipeline_cache(&self, data: &[u8]) -> bool {
+        // Some drivers crash on caches from a different device instead of ignoring them, so validate the header first.
+        const HEADER_SIZE: usize = std::mem::size_of::<vk::PipelineCacheHeaderVersionOne>();
+        if data.len() < HEADER_SIZE {
+            return false;
+        }
+        let header = std::ptr::read_unaligned(data.as_ptr() as *const vk::PipelineCacheHeaderVersionOne);
+        let properties = &self.device.properties;
+        if header.header_size as usize != HEADER_SIZE
+            || header.header_version != vk::PipelineCacheHeaderVersion::ONE
+            || header.vendor_id != properties.vendor_id
+            || header.device_id != properties.device_id
+            || header.pipeline_cache_uuid != properties.pipeline_cache_uuid {
+            return false;
+        }
+
+        let loaded_cache = self.device.create_pipeline_cache(&vk::PipelineCacheCreateInfo {
+            initial_data_size: data.len(),
+            p_initial_data: data.as_ptr() as *const c_void,
+            ..Default::default()
+        }, None);
+        let Ok(loaded_cache) = loaded_cache else {
+            return false;
+        };
+        let result = self.device.merge_pipeline_caches(self.device.pipeline_cache, &[loaded_cache]);
+        self.device.destroy_pipeline_cache(loaded_cache, None);
+        result.is_ok()
+    }
+
+    fn pipeline_cache_data(&self) -> Option<Vec<u8>> {
+        unsafe { self.device.get_pipeline_cache_data(self.device.pipeline_cache) }.ok()
+    }
+
     unsafe fn memory_infos(&self) -> Vec<gpu::MemoryInfo> {
         let mut memory_infos = Vec::<gpu::MemoryInfo>::new();
 
@@ -40,6 +40,7 @@ pub fn format_to_vk(format: gpu::Format, supports_d24: bool) -> vk::Format {
         gpu::Format::R16SNorm => vk::Format::R16_SNORM,
         gpu::Format::RG16SInt => vk::Format::R16G16_SINT,
         gpu::Format::R16SInt => vk::Format::R16_SINT,
+        gpu::Format::RGB10A2UNorm => vk::Format::A2B10G10R10_UNORM_PACK32,
         _ => vk::Format::UNDEFINED,
     }
 }
@@ -11,10 +11,34 @@ use std::{
 };
 
 use ash::vk;
+use log::{error, info, trace, warn};
 use sourcerenderer_core::gpu;
 
 use super::*;
 
+/// Configures which messages of the debug messenger get logged.
+#[derive(Debug, Clone)]
+pub struct ValidationConfig {
+    pub severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    pub message_types: vk::DebugUtilsMessageTypeFlagsEXT,
+    /// Triggers a debug breakpoint on the first validation error.
+    pub break_on_error: bool,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            severity: vk::DebugUtilsMessageSeverityFlagsEXT::INFO
+                | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
+                | vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+            message_types: vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
+                | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
+            break_on_error: false,
+        }
+    }
+}
+
 pub struct VkInstance {
     raw: Arc<RawVkInstance>,
     adapters: Vec<VkAdapter>,
@@ -22,12 +46,17 @@ pub struct VkInstance {
 
 impl VkInstance {
     pub fn new(instance_extensions: &[&str], debug_layers: bool) -> Self {
+        Self::new_with_validation_config(instance_extensions, debug_layers, ValidationConfig::default())
+    }
+
+    pub fn new_with_validation_config(instance_extensions: &[&str], debug_layers: bool, validation_config: ValidationConfig) -> Self {
         let entry: ash::Entry = unsafe { ash::Entry::load().unwrap() };
 
         let extensions = unsafe { entry.enumerate_instance_extension_properties(None) }.unwrap();
         let layers = unsafe { entry.enumerate_instance_layer_properties() }.unwrap();
         let mut supports_khronos_validation = false;
         let mut supports_debug_utils = false;
+        let mut supports_swapchain_colorspace = false;
         for layer in &layers {
             let name = unsafe { CStr::from_ptr(&layer.layer_name as *const c_char) };
             match name.to_str().unwrap() {
@@ -43,6 +72,9 @@ impl VkInstance {
             if name == debug_utils_name {
                 supports_debug_utils = true;
             }
+            if name == ash::ext::swapchain_colorspace::NAME {
+                supports_swapchain_colorspace = true;
+            }
         }
 
         let app_name = CString::new("Dreieck").unwrap();
@@ -60,7 +92,7 @@ impl VkInstance {
             if supports_khronos_validation {
                 layer_names_c.push(CString::new("VK_LAYER_KHRONOS_validation").unwrap());
             } else {
-                println!("Validation layers not installed");
+                warn!("Validation layers not installed");
             }
         }
 
@@ -76,7 +108,11 @@ impl VkInstance {
         if supports_debug_utils {
             extension_names_c.push(CString::from(ash::ext::debug_utils::NAME));
         } else {
-            println!("Vulkan debug utils are unsupported");
+            warn!("Vulkan debug utils are unsupported");
+        }
+        if supports_swapchain_colorspace {
+            // Exposes the HDR color spaces in the surface formats.
+            extension_names_c.push(CString::from(ash::ext::swapchain_colorspace::NAME));
         }
         let extension_names_ptr: Vec<*const c_char> = extension_names_c
             .iter()
@@ -106,19 +142,15 @@ impl VkInstance {
 
             let debug_utils = if supports_debug_utils {
                 let debug_utils_instance = ash::ext::debug_utils::Instance::new(&entry, &instance);
+                let validation_config = Box::new(validation_config);
                 let debug_messenger = debug_utils_instance
                     .create_debug_utils_messenger(
                         &vk::DebugUtilsMessengerCreateInfoEXT {
                             flags: vk::DebugUtilsMessengerCreateFlagsEXT::empty(),
-                            message_severity: vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE
-                                | vk::DebugUtilsMessageSeverityFlagsEXT::INFO
-                                | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
-                                | vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
-                            message_type: vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
-                                | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
-                                | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
+                            message_severity: validation_config.severity,
+                            message_type: validation_config.message_types,
                             pfn_user_callback: Some(VkInstance::debug_callback),
-                            p_user_data: std::ptr::null_mut(),
+                            p_user_data: validation_config.as_ref() as *const ValidationConfig as *mut c_void,
                             ..Default::default()
                         },
                         None,
@@ -127,6 +159,7 @@ impl VkInstance {
                 Some(RawInstanceVkDebugUtils {
                     debug_messenger,
                     debug_utils_instance,
+                    validation_config,
                 })
             } else {
                 None
@@ -157,37 +190,33 @@ impl VkInstance {
         message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
         message_types: vk::DebugUtilsMessageTypeFlagsEXT,
         p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
-        _p_user_data: *mut c_void,
+        p_user_data: *mut c_void,
     ) -> vk::Bool32 {
         let callback_data_opt = p_callback_data.as_ref();
         if callback_data_opt.is_none() {
             return vk::FALSE;
         }
         let callback_data = callback_data_opt.unwrap();
-
-        if message_severity == vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE {
-            return vk::FALSE;
-        }
+        let validation_config = (p_user_data as *const ValidationConfig).as_ref().unwrap();
 
         if callback_data.message_id_number == 688222058 {
             // False positive about setting the viewport & scissor for ray tracing pipelines
             return vk::FALSE;
         }
 
-        if message_severity != vk::DebugUtilsMessageSeverityFlagsEXT::INFO || message_severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR) {
-            println!(
-                "VK: {:?} - {:?}: {:?}",
-                message_severity,
-                message_types,
-                CStr::from_ptr(callback_data.p_message)
-            );
+        let message = CStr::from_ptr(callback_data.p_message).to_string_lossy();
+        if message_severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR) {
+            error!("VK: {:?}: {}", message_types, message);
+            if validation_config.break_on_error && message_types.contains(vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION) {
+                error!("{}", std::backtrace::Backtrace::force_capture());
+                debug_break();
+            }
+        } else if message_severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING) {
+            warn!("VK: {:?}: {}", message_types, message);
+        } else if message_severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::INFO) {
+            info!("VK: {:?}: {}", message_types, message);
         } else {
-            println!(
-                "VK: {:?} - {:?}: {:?}",
-                message_severity,
-                message_types,
-                CStr::from_ptr(callback_data.p_message)
-            );
+            trace!("VK: {:?}: {}", message_types, message);
         }
         vk::FALSE
     }
@@ -198,3 +227,16 @@ impl gpu::Instance<VkBackend> for VkInstance {
         &self.adapters
     }
 }
+
+fn debug_break() {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    unsafe {
+        std::arch::asm!("int3");
+    }
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        std::arch::asm!("brk #0xf000");
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    panic!("Vulkan validation error");
+}
@@ -15,7 +15,7 @@ extern crate thread_local;
 mod raw;
 
 // pub use self::bindless::*;
-//pub use self::query::*;
+pub use self::query::*;
 pub use self::queue::*;
 pub use self::{
     adapter::*,
@@ -49,7 +49,7 @@ mod device;
 mod format;
 mod instance;
 mod pipeline;
-//mod query; // NEEDS REDESIGN
+mod query;
 mod queue;
 mod renderpass;
 mod rt;
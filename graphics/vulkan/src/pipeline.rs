@@ -671,7 +671,7 @@ impl VkPipeline {
 
         let pipeline = unsafe {
             vk_device
-                .create_graphics_pipelines(vk::PipelineCache::null(), &[pipeline_create_info], None)
+                .create_graphics_pipelines(device.pipeline_cache, &[pipeline_create_info], None)
                 .unwrap()[0]
         };
 
@@ -738,7 +738,7 @@ impl VkPipeline {
         };
         let pipeline = unsafe {
             device
-                .create_compute_pipelines(vk::PipelineCache::null(), &[pipeline_create_info], None)
+                .create_compute_pipelines(device.pipeline_cache, &[pipeline_create_info], None)
                 .unwrap()[0]
         };
 
@@ -813,7 +813,7 @@ impl VkPipeline {
         };
         let pipeline = unsafe {
             device
-                .create_compute_pipelines(vk::PipelineCache::null(), &[pipeline_create_info], None)
+                .create_compute_pipelines(device.pipeline_cache, &[pipeline_create_info], None)
                 .unwrap()[0]
         };
 
@@ -968,7 +968,7 @@ impl VkPipeline {
         let pipeline = unsafe {
             rt.rt_pipelines.create_ray_tracing_pipelines(
                 vk::DeferredOperationKHR::null(),
-                vk::PipelineCache::null(),
+                device.pipeline_cache,
                 &[vk_info],
                 None,
             )
@@ -1,82 +1,21 @@
-use std::collections::HashMap;
-use std::hash::Hash;
-use std::sync::{
-    Arc,
-    Mutex,
-};
+use std::ffi::CString;
+use std::sync::Arc;
 
 use ash::vk;
-use sourcerenderer_core::atomic_refcell::AtomicRefCell;
+use ash::vk::Handle as _;
+use sourcerenderer_core::gpu;
 
 use crate::raw::RawVkDevice;
 
-pub struct VkQueryAllocator {
-    device: Arc<RawVkDevice>,
-    inner: AtomicRefCell<VkQueryAllocatorInner>,
-}
-
-pub struct VkQueryAllocatorInner {
-    pools: HashMap<vk::QueryType, Vec<Arc<VkQueryPool>>>,
-}
-
-impl VkQueryAllocator {
-    pub fn new(device: &Arc<RawVkDevice>) -> Self {
-        Self {
-            device: device.clone(),
-            inner: AtomicRefCell::new(VkQueryAllocatorInner {
-                pools: HashMap::new(),
-            }),
-        }
-    }
-
-    pub fn get(&self, query_type: vk::QueryType, count: u32) -> VkQueryRange {
-        let mut inner = self.inner.borrow_mut();
-        let pools_map = &mut inner.pools;
-        let type_pools = pools_map.entry(query_type).or_default();
-        if let Some((pool, index)) = type_pools
-            .iter()
-            .find_map(|pool| pool.get(count).map(|index| (pool.clone(), index)))
-        {
-            return VkQueryRange { pool, index, count };
-        }
-
-        let new_pool = VkQueryPool::new(&self.device, query_type, count.max(4096));
-        let index = new_pool.get(count).unwrap();
-        let new_pool = Arc::new(new_pool);
-        let range = VkQueryRange {
-            pool: new_pool.clone(),
-            index,
-            count,
-        };
-        type_pools.push(new_pool);
-        range
-    }
-
-    pub fn reset(&self) {
-        let mut inner = self.inner.borrow_mut();
-        let pools_map = &mut inner.pools;
-        for pools in pools_map.values() {
-            for pool in pools {
-                pool.reset();
-            }
-        }
-    }
-}
-
 pub struct VkQueryPool {
     device: Arc<RawVkDevice>,
     query_pool: vk::QueryPool,
     query_count: u32,
-    inner: Mutex<VkQueryPoolInner>,
-}
-
-pub struct VkQueryPoolInner {
-    used_queries: u32,
-    is_reset: bool,
+    timestamp_period: f32,
 }
 
 impl VkQueryPool {
-    pub fn new(device: &Arc<RawVkDevice>, query_type: vk::QueryType, query_count: u32) -> Self {
+    pub fn new(device: &Arc<RawVkDevice>, query_type: vk::QueryType, query_count: u32, name: Option<&str>) -> Self {
         let query_pool = unsafe {
             device.create_query_pool(
                 &vk::QueryPoolCreateInfo {
@@ -90,76 +29,60 @@ impl VkQueryPool {
             )
         }
         .unwrap();
+
+        if let Some(name) = name {
+            if let Some(debug_utils) = device.debug_utils.as_ref() {
+                let name_cstring = CString::new(name).unwrap();
+                unsafe {
+                    debug_utils
+                        .set_debug_utils_object_name(&vk::DebugUtilsObjectNameInfoEXT {
+                            object_type: vk::ObjectType::QUERY_POOL,
+                            object_handle: query_pool.as_raw(),
+                            p_object_name: name_cstring.as_ptr(),
+                            ..Default::default()
+                        })
+                        .unwrap();
+                }
+            }
+        }
+
         Self {
             query_pool,
             device: device.clone(),
             query_count,
-            inner: Mutex::new(VkQueryPoolInner {
-                used_queries: 0,
-                is_reset: false,
-            }),
+            timestamp_period: device.properties.limits.timestamp_period,
         }
     }
 
-    pub fn is_reset(&self) -> bool {
-        let inner = self.inner.lock().unwrap();
-        inner.is_reset
-    }
-
-    pub fn mark_reset(&self) {
-        let mut inner = self.inner.lock().unwrap();
-        inner.is_reset = true;
+    #[inline(always)]
+    pub fn handle(&self) -> vk::QueryPool {
+        self.query_pool
     }
+}
 
-    pub fn query_count(&self) -> u32 {
+impl gpu::QueryPool for VkQueryPool {
+    fn query_count(&self) -> u32 {
         self.query_count
     }
 
-    pub fn reset(&self) {
-        let mut inner = self.inner.lock().unwrap();
-        // Requires host_query_reset
-        // FIXME
-        /*unsafe {
-          self.device.reset_query_pool(self.query_pool, 0, self.query_count);
+    unsafe fn read_timestamps(&self, first_query: u32, results: &mut [u64]) -> bool {
+        debug_assert!(first_query + results.len() as u32 <= self.query_count);
+        let result = self.device.get_query_pool_results(
+            self.query_pool,
+            first_query,
+            results,
+            vk::QueryResultFlags::TYPE_64,
+        );
+        if result.is_err() {
+            return false;
         }
-        inner.is_reset = true;*/
-
-        inner.used_queries = 0;
-        // Reset happens on the GPU, so assume the query is used until vkCmdResetQueryPool is called
-        inner.is_reset = false;
-    }
-
-    pub fn get(&self, count: u32) -> Option<u32> {
-        let mut inner = self.inner.lock().unwrap();
-
-        if self.query_count - inner.used_queries < count {
-            return None;
+        for timestamp in results {
+            *timestamp = (*timestamp as f64 * self.timestamp_period as f64) as u64;
         }
-
-        let query_index = inner.used_queries;
-        inner.used_queries += count;
-        Some(query_index)
-    }
-
-    pub fn handle(&self) -> &vk::QueryPool {
-        &self.query_pool
-    }
-}
-
-impl Hash for VkQueryPool {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.query_pool.hash(state);
+        true
     }
 }
 
-impl PartialEq for VkQueryPool {
-    fn eq(&self, other: &Self) -> bool {
-        self.query_pool == other.query_pool
-    }
-}
-
-impl Eq for VkQueryPool {}
-
 impl Drop for VkQueryPool {
     fn drop(&mut self) {
         unsafe {
@@ -167,10 +90,3 @@ impl Drop for VkQueryPool {
         }
     }
 }
-
-#[derive(PartialEq, Eq, Hash)]
-pub struct VkQueryRange {
-    pub(crate) pool: Arc<VkQueryPool>,
-    pub(crate) index: u32,
-    pub(crate) count: u32,
-}
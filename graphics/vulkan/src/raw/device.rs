@@ -51,6 +51,7 @@ pub struct RawVkDevice {
     pub supported_pipeline_stages: vk::PipelineStageFlags2,
     pub supported_access_flags: vk::AccessFlags2,
     pub host_image_copy: Option<ash::ext::host_image_copy::Device>,
+    pub pipeline_cache: vk::PipelineCache,
 }
 
 unsafe impl Send for RawVkDevice {}
@@ -192,6 +193,10 @@ impl RawVkDevice {
             None
         };
 
+        let pipeline_cache = unsafe {
+            device.create_pipeline_cache(&vk::PipelineCacheCreateInfo::default(), None)
+        }.unwrap();
+
         Self {
             device,
             physical_device,
@@ -213,7 +218,8 @@ impl RawVkDevice {
             properties13: unsafe { std::mem::transmute(properties13) },
             supported_pipeline_stages,
             supported_access_flags,
-            host_image_copy
+            host_image_copy,
+            pipeline_cache,
         }
     }
 
@@ -250,6 +256,7 @@ impl Deref for RawVkDevice {
 impl Drop for RawVkDevice {
     fn drop(&mut self) {
         unsafe {
+            self.device.destroy_pipeline_cache(self.pipeline_cache, None);
             self.device.destroy_device(None);
         }
     }
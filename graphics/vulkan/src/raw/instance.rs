@@ -2,9 +2,13 @@ use std::ops::Deref;
 
 use ash::vk;
 
+use crate::ValidationConfig;
+
 pub struct RawInstanceVkDebugUtils {
     pub debug_utils_instance: ash::ext::debug_utils::Instance,
     pub debug_messenger: vk::DebugUtilsMessengerEXT,
+    /// Referenced by the debug messenger callback, so it has to outlive the messenger.
+    pub validation_config: Box<ValidationConfig>,
 }
 
 impl Drop for RawInstanceVkDebugUtils {
@@ -12,6 +12,7 @@ use ash::{
     khr::swapchain::Device as SwapchainDevice,
     vk,
 };
+use log::warn;
 use smallvec::SmallVec;
 use sourcerenderer_core::{
     gpu::*,
@@ -61,7 +62,11 @@ pub struct VkSwapchain {
     instance: Arc<RawVkInstance>,
     surface: VkSurface,
     device: Arc<RawVkDevice>,
-    vsync: bool,
+    requested_present_mode: PresentMode,
+    present_mode: PresentMode,
+    requested_color_space: ColorSpace,
+    color_space: ColorSpace,
+    pre_rotation: bool,
     cond_var: Condvar,
 }
 
@@ -72,9 +77,11 @@ impl VkSwapchain {
         surface: &VkSurface,
         width: u32,
         height: u32,
-        vsync: bool,
+        present_mode: PresentMode,
+        color_space: ColorSpace,
+        pre_rotation: bool,
         old_swapchain: Option<&vk::SwapchainKHR>
-    ) -> (vk::SwapchainKHR, SmallVec<[VkTexture; 5]>, Matrix4, u32) {
+    ) -> (vk::SwapchainKHR, SmallVec<[VkTexture; 5]>, Matrix4, u32, PresentMode, ColorSpace) {
         unsafe {
             let physical_device = device.physical_device;
             let present_modes = match surface.get_present_modes(&physical_device) {
@@ -88,7 +95,7 @@ impl VkSwapchain {
                     }
                 },
             };
-            let present_mode = VkSwapchain::pick_present_mode(vsync, &present_modes);
+            let present_mode = VkSwapchain::pick_present_mode(present_mode, &present_modes);
 
             let capabilities = match surface.get_capabilities(&physical_device) {
                 Ok(capabilities) => capabilities,
@@ -112,9 +119,9 @@ impl VkSwapchain {
                     }
                 },
             };
-            let format = VkSwapchain::pick_format(&formats);
+            let (format, color_space) = VkSwapchain::pick_format(&formats, color_space);
 
-            let extent = VkSwapchain::pick_extent(&capabilities, width, height);
+            let mut extent = VkSwapchain::pick_extent(&capabilities, width, height);
 
             if extent.width == 0 || extent.height == 0 {
                 panic!("Zero extents");
@@ -130,7 +137,7 @@ impl VkSwapchain {
                 panic!("Rendering to the surface is not supported.");
             }
 
-            let (_matrix, _transform) = match capabilities.current_transform {
+            let (rotation_matrix, rotation_transform) = match capabilities.current_transform {
                 vk::SurfaceTransformFlagsKHR::ROTATE_90 => (
                     Matrix4::from_euler(EulerRot::XYZ, 0f32, 0f32, -std::f32::consts::FRAC_PI_2),
                     vk::SurfaceTransformFlagsKHR::ROTATE_90,
@@ -169,9 +176,27 @@ impl VkSwapchain {
                 _ => (Matrix4::IDENTITY, vk::SurfaceTransformFlagsKHR::IDENTITY),
             };
 
-            // TODO: Rendering is broken with actual pretransform
-            let matrix = Matrix4::IDENTITY;
-            let transform = vk::SurfaceTransformFlagsKHR::IDENTITY;
+            // Without pre-rotation the compositor has to rotate every frame when the display is rotated
+            // which costs a lot of performance and battery on phones.
+            // With it, the renderer has to apply the swapchain transform itself.
+            let (matrix, transform) = if pre_rotation {
+                (rotation_matrix, rotation_transform)
+            } else {
+                (Matrix4::IDENTITY, vk::SurfaceTransformFlagsKHR::IDENTITY)
+            };
+            let swaps_axes = transform.intersects(
+                vk::SurfaceTransformFlagsKHR::ROTATE_90
+                | vk::SurfaceTransformFlagsKHR::ROTATE_270
+                | vk::SurfaceTransformFlagsKHR::HORIZONTAL_MIRROR_ROTATE_90
+                | vk::SurfaceTransformFlagsKHR::HORIZONTAL_MIRROR_ROTATE_270
+            );
+            if swaps_axes && capabilities.current_extent.width != u32::MAX {
+                // The images have to use the native orientation of the display.
+                extent = vk::Extent2D {
+                    width: extent.height,
+                    height: extent.width,
+                };
+            }
 
             let image_count = VkSwapchain::pick_image_count(&capabilities, 3);
 
@@ -244,26 +269,38 @@ impl VkSwapchain {
                 })
                 .collect();
 
-            (swapchain, textures, matrix, capabilities.max_image_count)
+            (swapchain, textures, matrix, capabilities.max_image_count, present_mode_from_vk(present_mode), color_space)
         }
     }
 
+    /// pre_rotation makes the swapchain use the current transform of the surface instead of letting the compositor rotate the image.
+    /// The renderer then has to apply transform() to its output.
     pub fn new(
-        vsync: bool,
+        present_mode: PresentMode,
+        color_space: ColorSpace,
+        pre_rotation: bool,
         width: u32,
         height: u32,
         device: &Arc<RawVkDevice>,
         surface: VkSurface,
     ) -> Result<Self, SwapchainError> {
         let swapchain_device = SwapchainDevice::new(&device.instance.instance, &device.device);
-        let (swapchain, textures, matrix, max_image_count) = Self::create_swapchain_and_textures(
+        let (swapchain, textures, matrix, max_image_count, chosen_present_mode, chosen_color_space) = Self::create_swapchain_and_textures(
             device, &swapchain_device,
             &surface,
             width,
             height,
-            vsync,
+            present_mode,
+            color_space,
+            pre_rotation,
             None
         );
+        if chosen_present_mode != present_mode {
+            warn!("Present mode {:?} is not supported, using {:?} instead.", present_mode, chosen_present_mode);
+        }
+        if chosen_color_space != color_space {
+            warn!("Color space {:?} is not supported, using {:?} instead.", color_space, chosen_color_space);
+        }
 
         let acquire_semaphores: SmallVec<[VkBinarySemaphore; 5]> = (0..max_image_count)
             .map(|_i| VkBinarySemaphore::new(device))
@@ -287,7 +324,11 @@ impl VkSwapchain {
             instance: device.instance.clone(),
             surface,
             device: device.clone(),
-            vsync,
+            requested_present_mode: present_mode,
+            present_mode: chosen_present_mode,
+            requested_color_space: color_space,
+            color_space: chosen_color_space,
+            pre_rotation,
         })
     }
 
@@ -317,23 +358,39 @@ impl VkSwapchain {
         }
     }
 
-    pub fn pick_format(formats: &[vk::SurfaceFormatKHR]) -> vk::SurfaceFormatKHR {
+    /// Picks a format for the requested color space, falls back to an 8 bit sRGB format if there is none.
+    pub fn pick_format(formats: &[vk::SurfaceFormatKHR], color_space: ColorSpace) -> (vk::SurfaceFormatKHR, ColorSpace) {
         if formats.len() == 1 && formats[0].format == vk::Format::UNDEFINED {
-            vk::SurfaceFormatKHR {
+            return (vk::SurfaceFormatKHR {
                 format: vk::Format::B8G8R8A8_UNORM,
                 color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
-            }
-        } else {
-            *formats
+            }, ColorSpace::Srgb);
+        }
+
+        let hdr_format = match color_space {
+            ColorSpace::Hdr10 => formats
                 .iter()
-                .find(|&format| {
-                    (format.format == vk::Format::B8G8R8A8_UNORM
-                        && format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR)
-                        || (format.format == vk::Format::R8G8B8A8_UNORM
-                            && format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR)
-                })
-                .expect("No compatible format found")
+                .find(|&format| format.format == vk::Format::A2B10G10R10_UNORM_PACK32
+                    && format.color_space == vk::ColorSpaceKHR::HDR10_ST2084_EXT),
+            ColorSpace::ScRgbLinear => formats
+                .iter()
+                .find(|&format| format.format == vk::Format::R16G16B16A16_SFLOAT
+                    && format.color_space == vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT),
+            ColorSpace::Srgb => None,
+        };
+        if let Some(hdr_format) = hdr_format {
+            return (*hdr_format, color_space);
         }
+
+        (*formats
+            .iter()
+            .find(|&format| {
+                (format.format == vk::Format::B8G8R8A8_UNORM
+                    && format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR)
+                    || (format.format == vk::Format::R8G8B8A8_UNORM
+                        && format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR)
+            })
+            .expect("No compatible format found"), ColorSpace::Srgb)
     }
 
     pub fn pick_image_count(capabilities: &vk::SurfaceCapabilitiesKHR, preferred: u32) -> u32 {
@@ -344,23 +401,21 @@ impl VkSwapchain {
         image_count
     }
 
+    /// Picks the requested present mode or the closest one that is supported.
+    /// FIFO is the only mode that's guaranteed to be available.
     unsafe fn pick_present_mode(
-        vsync: bool,
+        present_mode: PresentMode,
         present_modes: &[vk::PresentModeKHR],
     ) -> vk::PresentModeKHR {
-        if !vsync {
-            if let Some(mode) = present_modes
-                .iter()
-                .find(|&&mode| mode == vk::PresentModeKHR::IMMEDIATE)
-            {
-                return *mode;
-            }
-
-            if let Some(mode) = present_modes
-                .iter()
-                .find(|&&mode| mode == vk::PresentModeKHR::MAILBOX)
-            {
-                return *mode;
+        let preferred: &[vk::PresentModeKHR] = match present_mode {
+            PresentMode::Immediate => &[vk::PresentModeKHR::IMMEDIATE, vk::PresentModeKHR::MAILBOX],
+            PresentMode::Mailbox => &[vk::PresentModeKHR::MAILBOX, vk::PresentModeKHR::IMMEDIATE],
+            PresentMode::FifoRelaxed => &[vk::PresentModeKHR::FIFO_RELAXED],
+            PresentMode::Fifo => &[],
+        };
+        for preferred_mode in preferred {
+            if present_modes.contains(preferred_mode) {
+                return *preferred_mode;
             }
         }
 
@@ -467,10 +522,29 @@ impl Swapchain<VkBackend> for VkSwapchain {
         let width = info.width;
         let height = info.height;
 
-        let (swapchain, textures, matrix, _) = Self::create_swapchain_and_textures(&self.device, &self.swapchain_device, &self.surface, width, height, self.vsync, Some(&self.swapchain));
+        let (swapchain, textures, matrix, _, present_mode, color_space) = Self::create_swapchain_and_textures(&self.device, &self.swapchain_device, &self.surface, width, height, self.requested_present_mode, self.requested_color_space, self.pre_rotation, Some(&self.swapchain));
         self.swapchain = swapchain;
         self.textures = textures;
         self.transform_matrix = matrix;
+        self.present_mode = present_mode;
+        self.color_space = color_space;
+        self.state = VkSwapchainState::Okay;
+    }
+
+    unsafe fn set_present_mode(&mut self, present_mode: PresentMode) {
+        self.requested_present_mode = present_mode;
+        self.recreate();
+        if self.present_mode != present_mode {
+            warn!("Present mode {:?} is not supported, using {:?} instead.", present_mode, self.present_mode);
+        }
+    }
+
+    fn present_mode(&self) -> PresentMode {
+        self.present_mode
+    }
+
+    fn color_space(&self) -> ColorSpace {
+        self.color_space
     }
 
     unsafe fn next_backbuffer(&mut self) -> Result<VkBackbufferIndices, SwapchainError> {
@@ -559,6 +633,8 @@ fn surface_vk_format_to_core(format: vk::Format) -> Format {
     match format {
         vk::Format::B8G8R8A8_UNORM => Format::BGRA8UNorm,
         vk::Format::R8G8B8A8_UNORM => Format::RGBA8UNorm,
+        vk::Format::A2B10G10R10_UNORM_PACK32 => Format::RGB10A2UNorm,
+        vk::Format::R16G16B16A16_SFLOAT => Format::RGBA16Float,
         _ => panic!("Unsupported format: {:?}", format),
     }
 }
@@ -613,3 +689,12 @@ impl Drop for VkBinarySemaphore {
         }
     }
 }
+
+fn present_mode_from_vk(present_mode: vk::PresentModeKHR) -> PresentMode {
+    match present_mode {
+        vk::PresentModeKHR::IMMEDIATE => PresentMode::Immediate,
+        vk::PresentModeKHR::MAILBOX => PresentMode::Mailbox,
+        vk::PresentModeKHR::FIFO_RELAXED => PresentMode::FifoRelaxed,
+        _ => PresentMode::Fifo,
+    }
+}
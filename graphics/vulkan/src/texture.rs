@@ -49,7 +49,11 @@ impl VkTexture {
     pub(crate) fn build_create_info(device: &RawVkDevice, mut target: Pin<&mut VkImageCreateInfoCollection>, info: &gpu::TextureInfo) {
         let mut supports_direct_copy = device.features.contains(VkFeatures::HOST_IMAGE_COPY);
         target.create_info = vk::ImageCreateInfo {
-            flags: vk::ImageCreateFlags::empty(),
+            flags: if info.dimension == gpu::TextureDimension::Cube || info.dimension == gpu::TextureDimension::CubeArray {
+                vk::ImageCreateFlags::CUBE_COMPATIBLE
+            } else {
+                vk::ImageCreateFlags::empty()
+            },
             tiling: vk::ImageTiling::OPTIMAL,
             initial_layout: vk::ImageLayout::UNDEFINED,
             sharing_mode: vk::SharingMode::EXCLUSIVE,
@@ -77,13 +81,17 @@ impl VkTexture {
         debug_assert!(
             info.array_length == 1
                 || (info.dimension == gpu::TextureDimension::Dim1DArray
-                    || info.dimension == gpu::TextureDimension::Dim2DArray)
+                    || info.dimension == gpu::TextureDimension::Dim2DArray
+                    || info.dimension == gpu::TextureDimension::CubeArray)
+                || (info.dimension == gpu::TextureDimension::Cube && info.array_length == 6)
         );
         debug_assert!(info.depth == 1 || info.dimension == gpu::TextureDimension::Dim3D);
         debug_assert!(
             info.height == 1
                 || (info.dimension == gpu::TextureDimension::Dim2D
                     || info.dimension == gpu::TextureDimension::Dim2DArray
+                    || info.dimension == gpu::TextureDimension::Cube
+                    || info.dimension == gpu::TextureDimension::CubeArray
                     || info.dimension == gpu::TextureDimension::Dim3D)
         );
 
@@ -408,9 +416,18 @@ impl VkTextureView {
                 gpu::TextureDimension::Dim2D => vk::ImageViewType::TYPE_2D,
                 gpu::TextureDimension::Dim3D => vk::ImageViewType::TYPE_3D,
                 gpu::TextureDimension::Dim1DArray => vk::ImageViewType::TYPE_1D_ARRAY,
-                gpu::TextureDimension::Dim2DArray
-                    | gpu::TextureDimension::Cube
-                    | gpu::TextureDimension::CubeArray => vk::ImageViewType::TYPE_2D_ARRAY,
+                gpu::TextureDimension::Dim2DArray => vk::ImageViewType::TYPE_2D_ARRAY,
+                // Views of individual faces have to be regular 2D array views.
+                gpu::TextureDimension::Cube => if info.array_layer_length == 6 {
+                    vk::ImageViewType::CUBE
+                } else {
+                    vk::ImageViewType::TYPE_2D_ARRAY
+                },
+                gpu::TextureDimension::CubeArray => if info.array_layer_length % 6 == 0 {
+                    vk::ImageViewType::CUBE_ARRAY
+                } else {
+                    vk::ImageViewType::TYPE_2D_ARRAY
+                },
             },
             format: format_to_vk(format, device.supports_d24),
             components: vk::ComponentMapping {
@@ -512,12 +529,12 @@ impl VkSampler {
     pub fn new(device: &Arc<RawVkDevice>, info: &gpu::SamplerInfo) -> Self {
         let mut sampler_create_info = vk::SamplerCreateInfo {
             mag_filter: filter_to_vk(info.mag_filter),
-            min_filter: filter_to_vk(info.mag_filter),
+            min_filter: filter_to_vk(info.min_filter),
             mipmap_mode: filter_to_vk_mip(info.mip_filter),
             address_mode_u: address_mode_to_vk(info.address_mode_u),
             address_mode_v: address_mode_to_vk(info.address_mode_v),
-            address_mode_w: address_mode_to_vk(info.address_mode_u),
-            mip_lod_bias: info.mip_bias,
+            address_mode_w: address_mode_to_vk(info.address_mode_w),
+            mip_lod_bias: info.mip_bias.clamp(-device.properties.limits.max_sampler_lod_bias, device.properties.limits.max_sampler_lod_bias),
             anisotropy_enable: (info.max_anisotropy.abs() >= 1.0f32) as u32,
             max_anisotropy: info.max_anisotropy,
             compare_enable: info.compare_op.is_some() as u32,
@@ -25,6 +25,11 @@ unsafe impl Send for WebGPUAdapter {}
 unsafe impl Sync for WebGPUAdapter {}
 
 impl Adapter<WebGPUBackend> for WebGPUAdapter {
+    fn name(&self) -> &str {
+        // The browser doesn't necessarily expose the actual GPU.
+        "WebGPU"
+    }
+
     fn adapter_type(&self) -> sourcerenderer_core::gpu::AdapterType {
         AdapterType::Other
     }
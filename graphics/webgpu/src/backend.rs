@@ -1,6 +1,6 @@
 use sourcerenderer_core::gpu::GPUBackend;
 
-use crate::{adapter::WebGPUAdapter, buffer::WebGPUBuffer, command::{WebGPUCommandBuffer, WebGPUCommandPool}, pipeline::{WebGPUComputePipeline, WebGPUGraphicsPipeline, WebGPUShader}, queue::{WebGPUFence, WebGPUQueue}, sampler::WebGPUSampler, stubs::{WebGPUAccelerationStructure, WebGPUHeap}, surface::WebGPUSurface, swapchain::WebGPUSwapchain, texture::{WebGPUTexture, WebGPUTextureView}, WebGPUDevice, WebGPUInstance};
+use crate::{adapter::WebGPUAdapter, buffer::WebGPUBuffer, command::{WebGPUCommandBuffer, WebGPUCommandPool}, pipeline::{WebGPUComputePipeline, WebGPUGraphicsPipeline, WebGPUShader}, queue::{WebGPUFence, WebGPUQueue}, sampler::WebGPUSampler, query::WebGPUQueryPool, stubs::{WebGPUAccelerationStructure, WebGPUHeap}, surface::WebGPUSurface, swapchain::WebGPUSwapchain, texture::{WebGPUTexture, WebGPUTextureView}, WebGPUDevice, WebGPUInstance};
 
 pub struct WebGPUBackend();
 
@@ -23,6 +23,7 @@ impl GPUBackend for WebGPUBackend {
     type Fence = WebGPUFence;
     type Queue = WebGPUQueue;
     type Heap = WebGPUHeap;
+    type QueryPool = WebGPUQueryPool;
     type AccelerationStructure = WebGPUAccelerationStructure;
 
     fn name() -> &'static str {
@@ -118,7 +118,11 @@ impl WebGPUBindGroupLayout {
                 WebGPUResourceBindingType::SampledTextureAndSampler => panic!("WebGPU does not support combined image and sampler"),
                 WebGPUResourceBindingType::Sampler => {
                     let sampler = GpuSamplerBindingLayout::new();
-                    sampler.set_type(GpuSamplerBindingType::Filtering);
+                    sampler.set_type(if binding.sampling_type == gpu::SamplingType::Depth {
+                        GpuSamplerBindingType::Comparison
+                    } else {
+                        GpuSamplerBindingType::Filtering
+                    });
                     entry.set_sampler(&sampler);
                 },
             }
@@ -764,6 +768,26 @@ impl WebGPUBindingManager {
         frequency: gpu::BindingFrequency,
         slot: u32,
         binding: WebGPUBoundResourceRef,
+    ) {
+        // The shader compiler moves the bindings of the very frequent set up by one
+        // to make room for the uniform buffer that replaces the push constants.
+        let slot = if frequency == gpu::BindingFrequency::VeryFrequent {
+            slot + 1
+        } else {
+            slot
+        };
+        self.bind_slot(frequency, slot, binding);
+    }
+
+    pub(crate) fn bind_push_constants(&mut self, buffer: WebGPUBufferBindingInfo) {
+        self.bind_slot(gpu::BindingFrequency::VeryFrequent, 0, WebGPUBoundResourceRef::UniformBuffer(buffer));
+    }
+
+    fn bind_slot(
+        &mut self,
+        frequency: gpu::BindingFrequency,
+        slot: u32,
+        binding: WebGPUBoundResourceRef,
     ) {
         let bindings_table = &mut self.bindings[frequency as usize];
         let existing_binding = &mut bindings_table[slot as usize];
@@ -137,6 +137,23 @@ impl Buffer for WebGPUBuffer {
         assert_eq!(offset % 8, 0);
         assert_eq!(length % 4, 0);
 
+        if invalidate && self.info.usage == BufferUsage::COPY_DST {
+            // Readback buffers can only be mapped asynchronously.
+            // Only return the data if somebody already did that.
+            let buffer = self.buffer.borrow();
+            if buffer.map_state() != web_sys::GpuBufferMapState::Mapped {
+                return None;
+            }
+            let mut memory_opt: std::cell::RefMut<'_, Option<Box<[u8]>>> = self.rust_memory.borrow_mut();
+            let mut memory = vec![0u8; self.info.size as usize].into_boxed_slice();
+            let mapped_range = buffer.get_mapped_range().unwrap();
+            Uint8Array::new_with_byte_offset_and_length(&mapped_range, offset as u32, length as u32)
+                .copy_to(&mut memory[offset as usize .. offset as usize + length as usize]);
+            let ptr = memory.as_mut_ptr().byte_offset(offset as isize);
+            *memory_opt = Some(memory);
+            return Some(std::mem::transmute(ptr));
+        }
+
         let mut memory_opt: std::cell::RefMut<'_, Option<Box<[u8]>>> = self.rust_memory.borrow_mut();
         if (&*memory_opt).is_none() {
             assert!(!self.keep_rust_memory);
@@ -194,6 +211,9 @@ impl Buffer for WebGPUBuffer {
                     &memory[offset as usize .. offset as usize + length as usize]
                 ).unwrap();
             }
+        } else if self.info.usage == BufferUsage::COPY_DST && buffer.map_state() == web_sys::GpuBufferMapState::Mapped {
+            // Done reading back, the GPU can write to the buffer again.
+            buffer.unmap();
         }
         if !self.keep_rust_memory {
             // Free mapping copy
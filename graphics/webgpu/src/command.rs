@@ -1,11 +1,10 @@
 use std::sync::Arc;
 
-use js_sys::{wasm_bindgen::JsValue, Array, Uint32Array};
-use log::warn;
+use js_sys::{wasm_bindgen::JsValue, Array, Uint32Array, Uint8Array};
 use sourcerenderer_core::{align_up_32, gpu::{self, Buffer, LoadOpDepthStencil, ResolveAttachment, StoreOp, Texture, TextureView}};
-use web_sys::{GpuCommandBuffer, GpuCommandEncoder, GpuComputePassEncoder, GpuDevice, GpuExtent3dDict, GpuIndexFormat, GpuLoadOp, GpuRenderBundle, GpuRenderBundleEncoder, GpuRenderBundleEncoderDescriptor, GpuRenderPassColorAttachment, GpuRenderPassDepthStencilAttachment, GpuRenderPassDescriptor, GpuRenderPassEncoder, GpuStoreOp, GpuTexelCopyBufferInfo, GpuTexelCopyTextureInfo};
+use web_sys::{GpuBuffer, GpuBufferDescriptor, GpuCommandBuffer, GpuCommandEncoder, GpuComputePassDescriptor, GpuComputePassEncoder, GpuComputePassTimestampWrites, GpuDevice, GpuExtent3dDict, GpuIndexFormat, GpuLoadOp, GpuRenderBundle, GpuRenderBundleEncoder, GpuRenderBundleEncoderDescriptor, GpuRenderPassColorAttachment, GpuRenderPassDepthStencilAttachment, GpuRenderPassDescriptor, GpuRenderPassEncoder, GpuStoreOp, GpuTexelCopyBufferInfo, GpuTexelCopyTextureInfo};
 
-use crate::{binding::{self, WebGPUBindingManager, WebGPUBoundResourceRef, WebGPUBufferBindingInfo, WebGPUHashableSampler, WebGPUHashableTextureView, WebGPUPipelineLayout}, buffer::WebGPUBuffer, pipeline::sample_count_to_webgpu, sampler::WebGPUSampler, stubs::WebGPUAccelerationStructure, texture::{format_to_webgpu, WebGPUTexture, WebGPUTextureView}, WebGPUBackend};
+use crate::{binding::{self, WebGPUBindingManager, WebGPUBoundResourceRef, WebGPUBufferBindingInfo, WebGPUHashableSampler, WebGPUHashableTextureView, WebGPUPipelineLayout}, buffer::WebGPUBuffer, pipeline::sample_count_to_webgpu, sampler::WebGPUSampler, query::{map_readback_buffer, WebGPUQueryPool, WebGPUTimestampQueries}, stubs::WebGPUAccelerationStructure, texture::{format_to_webgpu, WebGPUTexture, WebGPUTextureView}, WebGPUBackend};
 
 enum WebGPUPassEncoder {
     None,
@@ -21,10 +20,12 @@ struct WebGPURecordingCommandBuffer {
     command_encoder: GpuCommandEncoder,
     pass_encoder: WebGPUPassEncoder,
     pipeline_layout: Option<Arc<WebGPUPipelineLayout>>,
+    written_queries: Vec<WebGPUTimestampQueries>,
 }
 
 struct WebGPUFinishedCommandBuffer {
-    command_buffer: GpuCommandBuffer
+    command_buffer: GpuCommandBuffer,
+    readback_buffers: Vec<GpuBuffer>,
 }
 
 struct WebGPURenderBundleCommandBuffer {
@@ -114,6 +115,15 @@ impl WebGPUCommandBuffer {
         }
     }
 
+    /// Maps the buffers that query results were copied to. Has to be called after the command buffer was submitted.
+    pub(crate) fn map_query_results(&self) {
+        if let WebGPUCommandBufferHandle::Finished(command_buffer) = &self.handle {
+            for buffer in &command_buffer.readback_buffers {
+                map_readback_buffer(buffer);
+            }
+        }
+    }
+
     fn get_recording(&self) -> &WebGPURecordingCommandBuffer {
         match &self.handle {
             WebGPUCommandBufferHandle::Recording(cmd_buffer) => cmd_buffer,
@@ -261,9 +271,24 @@ impl gpu::CommandBuffer<WebGPUBackend> for WebGPUCommandBuffer {
         render_pass_encoder.set_scissor_rect(scissor.position.x as u32, scissor.position.y as u32, scissor.extent.x, scissor.extent.y);
     }
 
-    unsafe fn set_push_constant_data<T>(&mut self, _data: &[T], _visible_for_shader_stage: gpu::ShaderType)
+    unsafe fn set_push_constant_data<T>(&mut self, data: &[T], _visible_for_shader_stage: gpu::ShaderType)
         where T: 'static + Send + Sync + Sized + Clone {
-        todo!()
+        // WebGPU has no push constants, the shader compiler turns them into a uniform buffer
+        // at binding 0 of the very frequent set. So the data goes into a small buffer that gets bound there.
+        let size = std::mem::size_of_val(data) as u32;
+        let buffer_size = align_up_32(size, 16);
+        let descriptor = GpuBufferDescriptor::new(buffer_size as f64, web_sys::gpu_buffer_usage::UNIFORM);
+        descriptor.set_mapped_at_creation(true);
+        let buffer = self.device.create_buffer(&descriptor).unwrap();
+        let mapped_range = buffer.get_mapped_range().unwrap();
+        Uint8Array::new_with_byte_offset_and_length(&mapped_range, 0, size)
+            .copy_from(std::slice::from_raw_parts(data.as_ptr() as *const u8, size as usize));
+        buffer.unmap();
+        self.binding_manager.bind_push_constants(WebGPUBufferBindingInfo {
+            buffer,
+            offset: 0,
+            length: buffer_size as u64,
+        });
     }
 
     unsafe fn draw(&mut self, vertices: u32, offset: u32) {
@@ -290,32 +315,55 @@ impl gpu::CommandBuffer<WebGPUBackend> for WebGPUCommandBuffer {
         }
     }
 
-    unsafe fn draw_indexed_indirect(&mut self, draw_buffer: &WebGPUBuffer, draw_buffer_offset: u32, _count_buffer: &WebGPUBuffer, _count_buffer_offset: u32, _max_draw_count: u32, _stride: u32) {
+    unsafe fn draw_indexed_indirect(&mut self, draw_buffer: &WebGPUBuffer, draw_buffer_offset: u32, draw_count: u32, stride: u32) {
+        // WebGPU has no multi draw indirect, so every draw is issued separately.
+        debug_assert_eq!(draw_buffer_offset % 4, 0);
+        debug_assert_eq!(stride % 4, 0);
+        debug_assert!(draw_buffer.info().usage.contains(gpu::BufferUsage::INDIRECT));
         if !self.is_inner {
             let cmd_buffer = self.get_recording_mut();
             let render_pass_encoder = cmd_buffer.get_render_encoder();
-            warn!("WebGPU does not support multi draw indirect");
-            render_pass_encoder.draw_indexed_indirect_with_u32(&draw_buffer.handle(), draw_buffer_offset);
+            for i in 0..draw_count {
+                render_pass_encoder.draw_indexed_indirect_with_u32(&draw_buffer.handle(), draw_buffer_offset + i * stride);
+            }
         } else {
             let render_bundle_encoder = self.get_encoder_inner();
-            warn!("WebGPU does not support multi draw indirect");
-            render_bundle_encoder.draw_indexed_indirect_with_u32(&draw_buffer.handle(), draw_buffer_offset);
+            for i in 0..draw_count {
+                render_bundle_encoder.draw_indexed_indirect_with_u32(&draw_buffer.handle(), draw_buffer_offset + i * stride);
+            }
         }
     }
 
-    unsafe fn draw_indirect(&mut self, draw_buffer: &WebGPUBuffer, draw_buffer_offset: u32, _count_buffer: &WebGPUBuffer, _count_buffer_offset: u32, _max_draw_count: u32, _stride: u32) {
+    unsafe fn draw_indirect(&mut self, draw_buffer: &WebGPUBuffer, draw_buffer_offset: u32, draw_count: u32, stride: u32) {
+        // See draw_indexed_indirect
+        debug_assert_eq!(draw_buffer_offset % 4, 0);
+        debug_assert_eq!(stride % 4, 0);
+        debug_assert!(draw_buffer.info().usage.contains(gpu::BufferUsage::INDIRECT));
         if !self.is_inner {
             let cmd_buffer = self.get_recording_mut();
             let render_pass_encoder = cmd_buffer.get_render_encoder();
-            warn!("WebGPU does not support multi draw indirect");
-            render_pass_encoder.draw_indirect_with_u32(&draw_buffer.handle(), draw_buffer_offset);
+            for i in 0..draw_count {
+                render_pass_encoder.draw_indirect_with_u32(&draw_buffer.handle(), draw_buffer_offset + i * stride);
+            }
         } else {
             let render_bundle_encoder = self.get_encoder_inner();
-            warn!("WebGPU does not support multi draw indirect");
-            render_bundle_encoder.draw_indirect_with_u32(&draw_buffer.handle(), draw_buffer_offset);
+            for i in 0..draw_count {
+                render_bundle_encoder.draw_indirect_with_u32(&draw_buffer.handle(), draw_buffer_offset + i * stride);
+            }
         }
     }
 
+    unsafe fn draw_indexed_indirect_count(&mut self, draw_buffer: &WebGPUBuffer, draw_buffer_offset: u32, _count_buffer: &WebGPUBuffer, _count_buffer_offset: u32, max_draw_count: u32, stride: u32) {
+        // WebGPU has no draw count buffers,
+        // so this issues max_draw_count draws and relies on unused draws having an index count of 0.
+        self.draw_indexed_indirect(draw_buffer, draw_buffer_offset, max_draw_count, stride);
+    }
+
+    unsafe fn draw_indirect_count(&mut self, draw_buffer: &WebGPUBuffer, draw_buffer_offset: u32, _count_buffer: &WebGPUBuffer, _count_buffer_offset: u32, max_draw_count: u32, stride: u32) {
+        // See draw_indexed_indirect_count
+        self.draw_indirect(draw_buffer, draw_buffer_offset, max_draw_count, stride);
+    }
+
     unsafe fn bind_sampling_view(&mut self, frequency: gpu::BindingFrequency, binding: u32, texture: &WebGPUTextureView) {
         self.binding_manager.bind(frequency, binding, WebGPUBoundResourceRef::SampledTexture(WebGPUHashableTextureView::from(texture)));
     }
@@ -475,10 +523,41 @@ impl gpu::CommandBuffer<WebGPUBackend> for WebGPUCommandBuffer {
         }
     }
 
+    unsafe fn reset_queries(&mut self, _query_pool: &WebGPUQueryPool, _first_query: u32, _query_count: u32) {
+        // WebGPU queries don't need to be reset.
+    }
+
+    unsafe fn write_timestamp(&mut self, query_pool: &WebGPUQueryPool, query_index: u32) {
+        let Some(queries) = query_pool.queries() else {
+            return;
+        };
+        if self.is_inner {
+            // Render bundles can't write timestamps.
+            return;
+        }
+        let recording = self.get_recording_mut();
+        if let WebGPUPassEncoder::Render(_, _) = &recording.pass_encoder {
+            // Timestamps can only be written at the beginning and end of a pass.
+            return;
+        }
+        recording.ensure_no_active_pass();
+        // There is no standalone timestamp command, so use an empty compute pass.
+        let timestamp_writes = GpuComputePassTimestampWrites::new(queries.query_set());
+        timestamp_writes.set_beginning_of_pass_write_index(query_index);
+        let descriptor = GpuComputePassDescriptor::new();
+        descriptor.set_timestamp_writes(&timestamp_writes);
+        recording.command_encoder.begin_compute_pass_with_descriptor(&descriptor).end();
+        if !recording.written_queries.iter().any(|written| written.query_set() == queries.query_set()) {
+            recording.written_queries.push(queries.clone());
+        }
+    }
+
     unsafe fn dispatch(&mut self, group_count_x: u32, group_count_y: u32, group_count_z: u32) {
         if self.is_inner {
             panic!("Not supported in inner command buffer");
         }
+        let max_workgroups = self.device.limits().max_compute_workgroups_per_dimension();
+        debug_assert!(group_count_x <= max_workgroups && group_count_y <= max_workgroups && group_count_z <= max_workgroups);
         let cmd_buffer = self.get_recording_mut();
         let compute_pass_encoder = cmd_buffer.get_compute_encoder();
         compute_pass_encoder.dispatch_workgroups_with_workgroup_count_y_and_workgroup_count_z(group_count_x, group_count_y, group_count_z);
@@ -565,7 +644,8 @@ impl gpu::CommandBuffer<WebGPUBackend> for WebGPUCommandBuffer {
             self.handle = WebGPUCommandBufferHandle::Recording(WebGPURecordingCommandBuffer {
                 command_encoder: cmd_buffer.command_encoder,
                 pass_encoder: WebGPUPassEncoder::None,
-                pipeline_layout: None
+                pipeline_layout: None,
+                written_queries: Vec::new(),
             });
         } else {
             unreachable!()
@@ -574,12 +654,16 @@ impl gpu::CommandBuffer<WebGPUBackend> for WebGPUCommandBuffer {
 
     unsafe fn finish(&mut self) {
         if !self.is_inner {
-            let cmd_buffer = {
+            let (cmd_buffer, readback_buffers) = {
                 let cmd_encoder = self.get_recording_mut();
                 cmd_encoder.ensure_no_active_pass();
-                cmd_encoder.command_encoder.finish()
+                let readback_buffers: Vec<GpuBuffer> = cmd_encoder.written_queries
+                    .iter()
+                    .filter_map(|queries| queries.resolve(&cmd_encoder.command_encoder))
+                    .collect();
+                (cmd_encoder.command_encoder.finish(), readback_buffers)
             };
-            self.handle = WebGPUCommandBufferHandle::Finished(WebGPUFinishedCommandBuffer { command_buffer: cmd_buffer });
+            self.handle = WebGPUCommandBufferHandle::Finished(WebGPUFinishedCommandBuffer { command_buffer: cmd_buffer, readback_buffers });
         } else {
             let render_bundle_encoder = self.get_encoder_inner();
             let render_bundle = render_bundle_encoder.finish();
@@ -630,6 +714,51 @@ impl gpu::CommandBuffer<WebGPUBackend> for WebGPUCommandBuffer {
         recording.command_encoder.copy_buffer_to_texture_with_gpu_extent_3d_dict(&src_info, &dst_info, &copy_size).unwrap();
     }
 
+    unsafe fn copy_texture_to_buffer(&mut self, src: &WebGPUTexture, dst: &WebGPUBuffer, region: &gpu::BufferTextureCopyRegion) {
+        let recording = self.get_recording_mut();
+        recording.ensure_no_active_pass();
+
+        let format = src.info().format;
+        let row_pitch = if region.buffer_row_pitch != 0 {
+            region.buffer_row_pitch
+        } else {
+            (align_up_32(region.texture_extent.x, format.block_size().x) / format.block_size().x * format.element_size()) as u64
+        };
+        let slice_pitch = if region.buffer_slice_pitch != 0 {
+            region.buffer_slice_pitch
+        } else {
+            (align_up_32(region.texture_extent.y, format.block_size().y) / format.block_size().y) as u64 * row_pitch
+        };
+        assert_eq!(slice_pitch % row_pitch, 0);
+        // WebGPU requires the rows to be aligned to 256 bytes when copying from a texture.
+        assert_eq!(row_pitch % 256, 0);
+
+        let src_info = GpuTexelCopyTextureInfo::new(src.handle());
+        src_info.set_mip_level(region.texture_subresource.mip_level);
+        let origin = Array::new_with_length(3);
+        origin.set(0, JsValue::from(region.texture_offset.x as f64));
+        origin.set(1, JsValue::from(region.texture_offset.y as f64));
+        let copy_size = GpuExtent3dDict::new(region.texture_extent.x);
+        copy_size.set_height(region.texture_extent.y);
+        if src.info().dimension == gpu::TextureDimension::Dim3D {
+            assert_eq!(region.texture_subresource.array_layer, 0);
+            copy_size.set_depth_or_array_layers(region.texture_extent.z);
+            origin.set(2, JsValue::from(region.texture_offset.z as f64));
+        } else {
+            assert_eq!(region.texture_extent.z, 1);
+            assert_eq!(region.texture_offset.z, 0);
+            copy_size.set_depth_or_array_layers(1);
+            origin.set(2, JsValue::from(region.texture_subresource.array_layer as f64));
+        }
+        src_info.set_origin(&origin);
+
+        let dst_info = GpuTexelCopyBufferInfo::new(&dst.handle());
+        dst_info.set_offset(region.buffer_offset as f64);
+        dst_info.set_bytes_per_row(row_pitch as u32);
+        dst_info.set_rows_per_image((slice_pitch / row_pitch) as u32);
+        recording.command_encoder.copy_texture_to_buffer_with_gpu_extent_3d_dict(&src_info, &dst_info, &copy_size).unwrap();
+    }
+
     unsafe fn copy_buffer(&mut self, src: &WebGPUBuffer, dst: &WebGPUBuffer, region: &gpu::BufferCopyRegion) {
         let recording = self.get_recording_mut();
         recording.ensure_no_active_pass();
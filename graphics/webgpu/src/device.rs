@@ -3,7 +3,7 @@ use smallvec::{SmallVec, smallvec};
 use sourcerenderer_core::{align_up_32, gpu::{self, Texture as _, TextureLayout}};
 use web_sys::{GpuAdapter, GpuDevice, GpuQueue, GpuTexelCopyTextureInfo, GpuTexelCopyBufferLayout, GpuExtent3dDict};
 
-use crate::{WebGPUBackend, WebGPUBuffer, WebGPUComputePipeline, WebGPUFence, WebGPUGraphicsPipeline, WebGPUHeap, WebGPUQueue, WebGPUSampler, WebGPUShader, WebGPUShared, WebGPUTexture, WebGPUTextureView};
+use crate::{WebGPUBackend, WebGPUBuffer, WebGPUComputePipeline, WebGPUFence, WebGPUGraphicsPipeline, WebGPUHeap, WebGPUQueryPool, WebGPUQueue, WebGPUSampler, WebGPUShader, WebGPUShared, WebGPUTexture, WebGPUTextureView};
 
 pub struct WebGPUDevice {
     device: GpuDevice,
@@ -158,7 +158,8 @@ impl gpu::Device<WebGPUBackend> for WebGPUDevice {
     }
 
     fn supports_indirect(&self) -> bool {
-        true
+        // Indirect draws use the first instance to look up the draw.
+        self.device.features().has("indirect-first-instance")
     }
 
     fn supports_min_max_filter(&self) -> bool {
@@ -169,6 +170,27 @@ impl gpu::Device<WebGPUBackend> for WebGPUDevice {
         false
     }
 
+    fn supports_timestamp_queries(&self) -> bool {
+        self.device.features().has("timestamp-query")
+    }
+
+    fn max_push_constant_size(&self) -> u32 {
+        // Push constants are emulated with a uniform buffer, stick to what Vulkan guarantees.
+        128
+    }
+
+    unsafe fn create_timestamp_query_pool(&self, query_count: u32, name: Option<&str>) -> WebGPUQueryPool {
+        WebGPUQueryPool::new(&self.device, query_count, name)
+    }
+
+    unsafe fn load_pipeline_cache(&self, _data: &[u8]) -> bool {
+        false
+    }
+
+    fn pipeline_cache_data(&self) -> Option<Vec<u8>> {
+        None
+    }
+
     unsafe fn get_bottom_level_acceleration_structure_size(&self, _info: &gpu::BottomLevelAccelerationStructureInfo<WebGPUBackend>) -> gpu::AccelerationStructureSizes {
         panic!("WebGPU does not support bindless")
     }
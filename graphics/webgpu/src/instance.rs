@@ -2,11 +2,19 @@ use std::{error::Error, fmt::{Debug, Display}};
 
 use log::{error, warn};
 use sourcerenderer_core::gpu::Instance;
-use web_sys::{GpuAdapter, GpuDevice, Navigator};
+use js_sys::{wasm_bindgen::JsValue, Array};
+use web_sys::{GpuAdapter, GpuDevice, GpuDeviceDescriptor, Navigator};
 use wasm_bindgen_futures::*;
 
 use crate::{adapter::WebGPUAdapter, WebGPUBackend};
 
+/// Features that get enabled if the adapter supports them.
+const OPTIONAL_FEATURES: &[&str] = &[
+    // Without it indirect draws with a first instance other than 0 are skipped.
+    "indirect-first-instance",
+    "timestamp-query",
+];
+
 pub struct WebGPUInstanceAsyncInitResult {
     adapter: GpuAdapter,
     device: GpuDevice
@@ -55,7 +63,19 @@ impl WebGPUInstance {
             return Err(WebGPUInstanceInitError::new("Failed to retrieve WebGPU adapter"));
         }
 
-        let device_future = JsFuture::from(adapter.request_device());
+        let adapter_features = adapter.features();
+        let required_features = Array::new();
+        for feature in OPTIONAL_FEATURES {
+            if adapter_features.has(feature) {
+                required_features.push(&JsValue::from_str(feature));
+            } else {
+                warn!("WebGPU adapter does not support {}", feature);
+            }
+        }
+        let device_descriptor = GpuDeviceDescriptor::new();
+        device_descriptor.set_required_features(&required_features);
+
+        let device_future = JsFuture::from(adapter.request_device_with_descriptor(&device_descriptor));
         let device: GpuDevice = device_future
             .await
             .map_err(|_| WebGPUInstanceInitError::new("Failed to retrieve WebGPU device"))?
@@ -13,6 +13,7 @@ mod command;
 mod binding;
 mod shared;
 mod device;
+mod query;
 
 pub use backend::*;
 pub use instance::*;
@@ -30,3 +31,4 @@ pub(crate) use command::*;
 pub(crate) use binding::*;
 pub(crate) use shared::*;
 pub(crate) use stubs::*;
+pub(crate) use query::*;
@@ -52,7 +52,8 @@ impl WebGPUShader {
                         gpu::ShaderType::ComputeShader => web_sys::gpu_shader_stage::COMPUTE,
                         _ => panic!("Unsupported shader type in WebGPU")
                     },
-                    index: binding.binding,
+                    // The bindings of the very frequent set got moved up by one to make room for the push constants.
+                    index: if set_index == gpu::BindingFrequency::VeryFrequent as usize { binding.binding + 1 } else { binding.binding },
                     writable: binding.writable,
                     descriptor_type: match binding.resource_type {
                         gpu::ResourceType::UniformBuffer => WebGPUResourceBindingType::UniformBuffer,
@@ -77,6 +78,25 @@ impl WebGPUShader {
                 binding_infos[set_index].push(binding_info);
             }
         }
+        if shader.push_constant_size != 0 {
+            binding_infos[gpu::BindingFrequency::VeryFrequent as usize].insert(0, WebGPUBindGroupEntryInfo {
+                name: "PushConstants".to_string(),
+                shader_stage: match shader.shader_type {
+                    gpu::ShaderType::VertexShader => web_sys::gpu_shader_stage::VERTEX,
+                    gpu::ShaderType::FragmentShader => web_sys::gpu_shader_stage::FRAGMENT,
+                    gpu::ShaderType::ComputeShader => web_sys::gpu_shader_stage::COMPUTE,
+                    _ => panic!("Unsupported shader type in WebGPU")
+                },
+                index: 0,
+                writable: false,
+                descriptor_type: WebGPUResourceBindingType::UniformBuffer,
+                has_dynamic_offset: false,
+                sampling_type: gpu::SamplingType::Float,
+                texture_dimension: gpu::TextureDimension::Dim2D,
+                is_multisampled: false,
+                storage_format: gpu::Format::Unknown,
+            });
+        }
 
         Self {
             module,
@@ -138,6 +158,7 @@ fn format_to_vertex_format(format: gpu::Format) -> GpuVertexFormat {
         gpu::Format::R16UInt => GpuVertexFormat::Uint16,
         gpu::Format::R16SNorm => GpuVertexFormat::Snorm16,
         gpu::Format::R16SInt => GpuVertexFormat::Sint16,
+        gpu::Format::RGB10A2UNorm => panic!("Unsupported vertex format"),
         gpu::Format::D16 => panic!("Unsupported vertex format"),
         gpu::Format::D16S8 => panic!("Unsupported vertex format"),
         gpu::Format::D32 => panic!("Unsupported vertex format"),
@@ -0,0 +1,116 @@
+use js_sys::BigUint64Array;
+use sourcerenderer_core::gpu;
+use web_sys::{gpu_buffer_usage, gpu_map_mode, GpuBuffer, GpuBufferDescriptor, GpuBufferMapState, GpuCommandEncoder, GpuDevice, GpuQuerySet, GpuQuerySetDescriptor, GpuQueryType};
+
+#[derive(Clone)]
+pub(crate) struct WebGPUTimestampQueries {
+    query_set: GpuQuerySet,
+    resolve_buffer: GpuBuffer,
+    readback_buffer: GpuBuffer,
+    query_count: u32,
+}
+
+impl WebGPUTimestampQueries {
+    pub(crate) fn query_set(&self) -> &GpuQuerySet {
+        &self.query_set
+    }
+
+    /// Copies the results into the readback buffer. Returns the readback buffer which has to be mapped after the command buffer was submitted.
+    /// Does nothing if the readback buffer is still in use.
+    pub(crate) fn resolve(&self, command_encoder: &GpuCommandEncoder) -> Option<GpuBuffer> {
+        if self.readback_buffer.map_state() != GpuBufferMapState::Unmapped {
+            return None;
+        }
+        let size = self.query_count * std::mem::size_of::<u64>() as u32;
+        command_encoder.resolve_query_set_with_u32(&self.query_set, 0, self.query_count, &self.resolve_buffer, 0);
+        command_encoder.copy_buffer_to_buffer_with_u32_and_u32_and_u32(&self.resolve_buffer, 0, &self.readback_buffer, 0, size);
+        Some(self.readback_buffer.clone())
+    }
+}
+
+/// WebGPU can only read back query results asynchronously.
+/// The results get resolved at the end of every command buffer that wrote a timestamp,
+/// the readback buffer gets mapped after submission and is ready by the time the frame is recycled.
+///
+/// Without the timestamp-query feature the pool is empty and never returns results.
+pub struct WebGPUQueryPool {
+    queries: Option<WebGPUTimestampQueries>,
+    query_count: u32,
+}
+
+unsafe impl Send for WebGPUQueryPool {}
+unsafe impl Sync for WebGPUQueryPool {}
+
+impl WebGPUQueryPool {
+    pub(crate) fn new(device: &GpuDevice, query_count: u32, name: Option<&str>) -> Self {
+        if !device.features().has("timestamp-query") {
+            return Self {
+                queries: None,
+                query_count,
+            };
+        }
+
+        let query_set_descriptor = GpuQuerySetDescriptor::new(query_count, GpuQueryType::Timestamp);
+        if let Some(name) = name {
+            query_set_descriptor.set_label(name);
+        }
+        let query_set = device.create_query_set(&query_set_descriptor).unwrap();
+
+        let size = query_count as f64 * std::mem::size_of::<u64>() as f64;
+        let resolve_buffer = device.create_buffer(&GpuBufferDescriptor::new(size, gpu_buffer_usage::QUERY_RESOLVE | gpu_buffer_usage::COPY_SRC)).unwrap();
+        let readback_buffer = device.create_buffer(&GpuBufferDescriptor::new(size, gpu_buffer_usage::MAP_READ | gpu_buffer_usage::COPY_DST)).unwrap();
+
+        Self {
+            queries: Some(WebGPUTimestampQueries {
+                query_set,
+                resolve_buffer,
+                readback_buffer,
+                query_count,
+            }),
+            query_count,
+        }
+    }
+
+    pub(crate) fn queries(&self) -> Option<&WebGPUTimestampQueries> {
+        self.queries.as_ref()
+    }
+}
+
+pub(crate) fn map_readback_buffer(buffer: &GpuBuffer) {
+    if buffer.map_state() == GpuBufferMapState::Unmapped {
+        // The promise only tells us when the buffer is mapped, that's checked using the map state instead.
+        let _ = buffer.map_async(gpu_map_mode::READ);
+    }
+}
+
+impl gpu::QueryPool for WebGPUQueryPool {
+    fn query_count(&self) -> u32 {
+        self.query_count
+    }
+
+    unsafe fn read_timestamps(&self, first_query: u32, results: &mut [u64]) -> bool {
+        let Some(queries) = self.queries.as_ref() else {
+            return false;
+        };
+        if queries.readback_buffer.map_state() != GpuBufferMapState::Mapped {
+            return false;
+        }
+        debug_assert!(first_query + results.len() as u32 <= self.query_count);
+        let mapped_range = queries.readback_buffer.get_mapped_range().unwrap();
+        // WebGPU timestamps are in nanoseconds already.
+        BigUint64Array::new_with_byte_offset_and_length(&mapped_range, first_query * std::mem::size_of::<u64>() as u32, results.len() as u32)
+            .copy_to(results);
+        queries.readback_buffer.unmap();
+        true
+    }
+}
+
+impl Drop for WebGPUQueryPool {
+    fn drop(&mut self) {
+        if let Some(queries) = self.queries.as_ref() {
+            queries.query_set.destroy();
+            queries.resolve_buffer.destroy();
+            queries.readback_buffer.destroy();
+        }
+    }
+}
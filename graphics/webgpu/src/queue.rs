@@ -44,6 +44,9 @@ impl gpu::Queue<WebGPUBackend> for WebGPUQueue {
                 array.set(index as u32, cmd_buffer.handle().into());
             }
             self.queue.submit(&array);
+            for cmd_buffer in submission.command_buffers {
+                cmd_buffer.map_query_results();
+            }
             for pair in submission.signal_fences {
                 if pair.fence.value.load(Ordering::Acquire) < pair.value {
                     pair.fence.value.store(pair.value, Ordering::Release);
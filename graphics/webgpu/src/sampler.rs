@@ -1,3 +1,4 @@
+use log::warn;
 use sourcerenderer_core::gpu::{self, SamplerInfo};
 use web_sys::{GpuAddressMode, GpuDevice, GpuFilterMode, GpuMipmapFilterMode, GpuSampler, GpuSamplerDescriptor};
 
@@ -47,6 +48,9 @@ impl WebGPUSampler {
         descriptor.set_address_mode_v(address_mode_to_webgpu(info.address_mode_v));
         descriptor.set_address_mode_w(address_mode_to_webgpu(info.address_mode_w));
         descriptor.set_max_anisotropy(info.max_anisotropy as u16);
+        if info.mip_bias != 0f32 {
+            warn!("WebGPU samplers don't support a mip bias, it needs to be applied in the shader.");
+        }
         descriptor.set_lod_min_clamp(info.min_lod);
         if let Some(max_lod) = info.max_lod {
             descriptor.set_lod_max_clamp(max_lod);
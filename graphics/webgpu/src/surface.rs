@@ -6,6 +6,7 @@ use web_sys::{gpu_texture_usage, Gpu, GpuCanvasConfiguration, GpuCanvasContext,
 use crate::texture::format_to_webgpu;
 
 pub struct WebGPUSurface {
+    canvas: OffscreenCanvas,
     canvas_context: GpuCanvasContext,
     texture_info: TextureInfo
 }
@@ -55,6 +56,7 @@ impl WebGPUSurface {
         context.configure(&config).unwrap();
 
         Ok(Self {
+            canvas,
             canvas_context: context,
             texture_info
         })
@@ -67,4 +69,11 @@ impl WebGPUSurface {
     pub fn texture_info(&self) -> &TextureInfo {
         &self.texture_info
     }
+
+    /// The canvas context hands out textures with the current size of the canvas,
+    /// so the size only needs to be read again after the canvas got resized.
+    pub(crate) fn update_size(&mut self) {
+        self.texture_info.width = self.canvas.width();
+        self.texture_info.height = self.canvas.height();
+    }
 }
@@ -1,7 +1,7 @@
 use std::sync::atomic::{AtomicU32, Ordering};
 
 use smallvec::SmallVec;
-use sourcerenderer_core::{gpu::{Backbuffer, Format, SampleCount, Swapchain, SwapchainError, Texture, TextureDimension, TextureInfo, TextureUsage}, Matrix4};
+use sourcerenderer_core::{gpu::{Backbuffer, ColorSpace, Format, PresentMode, SampleCount, Swapchain, SwapchainError, Texture, TextureDimension, TextureInfo, TextureUsage}, Matrix4};
 use web_sys::{GpuDevice, GpuTexture, GpuTextureFormat};
 
 use crate::{buffer, surface::WebGPUSurface, texture::WebGPUTexture, WebGPUBackend};
@@ -54,7 +54,21 @@ impl WebGPUSwapchain {
 impl Swapchain<WebGPUBackend> for WebGPUSwapchain {
     type Backbuffer = WebGPUBackbuffer;
 
-    unsafe fn recreate(&mut self) {}
+    unsafe fn recreate(&mut self) {
+        self.surface.update_size();
+    }
+
+    unsafe fn set_present_mode(&mut self, _present_mode: PresentMode) {
+        // The browser always presents in sync with the display.
+    }
+
+    fn present_mode(&self) -> PresentMode {
+        PresentMode::Fifo
+    }
+
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::Srgb
+    }
 
     unsafe fn next_backbuffer(&mut self) -> Result<WebGPUBackbuffer, SwapchainError> {
         let web_texture = self.surface.canvas_context().get_current_texture()
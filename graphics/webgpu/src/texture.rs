@@ -32,6 +32,7 @@ pub(crate) fn format_to_webgpu(format: Format) -> GpuTextureFormat {
         Format::R16UInt => GpuTextureFormat::R16uint,
         Format::R16SNorm => panic!("Unsupported format"),
         Format::R16SInt => GpuTextureFormat::R16sint,
+        Format::RGB10A2UNorm => GpuTextureFormat::Rgb10a2unorm,
         Format::D16 => GpuTextureFormat::Depth16unorm,
         Format::D16S8 => GpuTextureFormat::Depth24plusStencil8,
         Format::D32 => GpuTextureFormat::Depth32float,
@@ -127,3 +127,72 @@ impl<T: Read + ?Sized> PrimitiveRead for T {
     Ok(f64::from_le_bytes(buffer))
   }
 }
+
+/// The byte order of a file. PC files are little endian, console files (Xbox 360, PS3) are big endian.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum Endianness {
+  #[default]
+  Little,
+  Big
+}
+
+/// Like PrimitiveRead but with the byte order chosen at runtime.
+pub trait EndianRead {
+  fn read_u16_endian(&mut self, endianness: Endianness) -> IOResult<u16>;
+  fn read_u32_endian(&mut self, endianness: Endianness) -> IOResult<u32>;
+  fn read_u64_endian(&mut self, endianness: Endianness) -> IOResult<u64>;
+  fn read_i16_endian(&mut self, endianness: Endianness) -> IOResult<i16>;
+  fn read_i32_endian(&mut self, endianness: Endianness) -> IOResult<i32>;
+  fn read_i64_endian(&mut self, endianness: Endianness) -> IOResult<i64>;
+  fn read_f32_endian(&mut self, endianness: Endianness) -> IOResult<f32>;
+  fn read_f64_endian(&mut self, endianness: Endianness) -> IOResult<f64>;
+}
+
+impl<T: Read + ?Sized> EndianRead for T {
+  fn read_u16_endian(&mut self, endianness: Endianness) -> IOResult<u16> {
+    let mut buffer = [0u8; 2];
+    self.read_exact(&mut buffer)?;
+    Ok(match endianness {
+      Endianness::Little => u16::from_le_bytes(buffer),
+      Endianness::Big => u16::from_be_bytes(buffer)
+    })
+  }
+
+  fn read_u32_endian(&mut self, endianness: Endianness) -> IOResult<u32> {
+    let mut buffer = [0u8; 4];
+    self.read_exact(&mut buffer)?;
+    Ok(match endianness {
+      Endianness::Little => u32::from_le_bytes(buffer),
+      Endianness::Big => u32::from_be_bytes(buffer)
+    })
+  }
+
+  fn read_u64_endian(&mut self, endianness: Endianness) -> IOResult<u64> {
+    let mut buffer = [0u8; 8];
+    self.read_exact(&mut buffer)?;
+    Ok(match endianness {
+      Endianness::Little => u64::from_le_bytes(buffer),
+      Endianness::Big => u64::from_be_bytes(buffer)
+    })
+  }
+
+  fn read_i16_endian(&mut self, endianness: Endianness) -> IOResult<i16> {
+    self.read_u16_endian(endianness).map(|value| value as i16)
+  }
+
+  fn read_i32_endian(&mut self, endianness: Endianness) -> IOResult<i32> {
+    self.read_u32_endian(endianness).map(|value| value as i32)
+  }
+
+  fn read_i64_endian(&mut self, endianness: Endianness) -> IOResult<i64> {
+    self.read_u64_endian(endianness).map(|value| value as i64)
+  }
+
+  fn read_f32_endian(&mut self, endianness: Endianness) -> IOResult<f32> {
+    self.read_u32_endian(endianness).map(f32::from_bits)
+  }
+
+  fn read_f64_endian(&mut self, endianness: Endianness) -> IOResult<f64> {
+    self.read_u64_endian(endianness).map(f64::from_bits)
+  }
+}
@@ -1,5 +1,6 @@
-use sourcerenderer_core::Platform;
+use sourcerenderer_core::{Platform, Vec2UI};
 use sourcerenderer_core::platform::FileWatcher;
+use sourcerenderer_core::gpu::{ColorSpace, PresentMode};
 use std::sync::Arc;
 use sourcerenderer_core::platform::{Window, ThreadHandle};
 use std::error::Error;
@@ -65,6 +66,10 @@ impl FileWatcher for AndroidFileWatcher {
   }
 }
 
+/// Pre-rotation avoids the compositor rotating every frame on a rotated display,
+/// but the renderer doesn't apply the swapchain transform to all of its output yet.
+const USE_PRE_ROTATION: bool = false;
+
 pub struct AndroidWindow {
   native_window: NativeWindow
 }
@@ -79,6 +84,11 @@ impl AndroidWindow {
   pub(crate) fn native_window(&self) -> &NativeWindow {
     &self.native_window
   }
+
+  /// Rotating the device usually keeps the window and only swaps its width and height.
+  pub(crate) fn size(&self) -> Vec2UI {
+    Vec2UI::new(self.width(), self.height())
+  }
 }
 
 impl Drop for AndroidWindow {
@@ -104,9 +114,9 @@ impl Window<AndroidPlatform> for AndroidWindow {
     Arc::new(VkSurface::new(instance_raw, surface, surface_loader))
   }
 
-  fn create_swapchain(&self, vsync: bool, device: &VkDevice, surface: &Arc<VkSurface>) -> Arc<VkSwapchain> {
+  fn create_swapchain(&self, present_mode: PresentMode, color_space: ColorSpace, device: &VkDevice, surface: &Arc<VkSurface>) -> Arc<VkSwapchain> {
     let device_inner = device.inner();
-    return VkSwapchain::new(vsync, self.native_window.width() as u32, self.native_window.height() as u32, device_inner, surface).unwrap();
+    return VkSwapchain::new(present_mode, color_space, USE_PRE_ROTATION, self.native_window.width() as u32, self.native_window.height() as u32, device_inner, surface).unwrap();
   }
 
   fn width(&self) -> u32 {
@@ -120,6 +130,11 @@ impl Window<AndroidPlatform> for AndroidWindow {
       ANativeWindow_getHeight(self.native_window.ptr().as_ptr()) as u32
     }
   }
+
+  fn scale_factor(&self) -> f32 {
+    // The native window is always sized in pixels, the display density isn't available here.
+    1f32
+  }
 }
 
 pub struct StdThreadHandle(std::thread::JoinHandle<()>);
@@ -113,6 +113,12 @@ impl IO for AndroidIO {
   fn new_file_watcher(_sender: Sender<String>) -> Self::FileWatcher {
     AndroidFileWatcher {}
   }
+
+  fn cache_dir() -> Option<PathBuf> {
+    // The internal files directory of the app, the working directory isn't writable on Android.
+    let root_path = unsafe { (&*(ROOT_PATH.as_ptr())).clone() };
+    Some(PathBuf::from(root_path))
+  }
 }
 
 pub enum AndroidFile {
@@ -100,7 +100,8 @@ fn engine_from_long<'a>(engine_ptr: jlong) -> RefMut<'a, EngineWrapper> {
 
 struct EngineWrapper {
   engine: Engine<AndroidPlatform>,
-  platform: AndroidPlatform
+  platform: AndroidPlatform,
+  window_size: Vec2UI
 }
 
 #[no_mangle]
@@ -153,9 +154,11 @@ pub extern "system" fn Java_de_kobin_sourcerenderer_MainActivity_startEngineNati
   let native_window_nonnull = NonNull::new(native_window_ptr).expect("Null surface provided");
   let native_window = unsafe { NativeWindow::from_ptr(native_window_nonnull) };
   let platform = AndroidPlatform::new(native_window);
+  let window_size = platform.window().size();
   let engine = Box::new(RefCell::new(EngineWrapper {
     engine: Engine::run(&platform),
-    platform
+    platform,
+    window_size
   }));
   println!("Engine started");
   unsafe {
@@ -182,7 +185,17 @@ pub extern "system" fn Java_de_kobin_sourcerenderer_MainActivity_onSurfaceChange
     if &native_window != wrapper.platform.window().native_window() {
       wrapper.platform.change_window(AndroidWindow::new(native_window));
       wrapper.engine.dispatch_event(Event::SurfaceChanged(wrapper.platform.window().create_surface(wrapper.engine.instance().clone())));
-      wrapper.engine.dispatch_event(Event::WindowSizeChanged(Vec2UI::new(wrapper.platform.window().width(), wrapper.platform.window().height())));
+      let size = wrapper.platform.window().size();
+      wrapper.window_size = size;
+      wrapper.engine.dispatch_event(Event::WindowSizeChanged(size));
+    } else {
+      // Rotating the device keeps the window, the swapchain only needs to be recreated with the new size.
+      let size = wrapper.platform.window().size();
+      if size != wrapper.window_size {
+        println!("Window size changed from {}x{} to {}x{}", wrapper.window_size.x, wrapper.window_size.y, size.x, size.y);
+        wrapper.window_size = size;
+        wrapper.engine.dispatch_event(Event::WindowSizeChanged(size));
+      }
     }
   }
 }
@@ -204,29 +217,29 @@ pub extern "system" fn Java_de_kobin_sourcerenderer_MainActivity_onTouchInputNat
   const ANDROID_EVENT_TYPE_UP: i32 = 1;
   const ANDROID_EVENT_TYPE_MOVE: i32 = 2;
 
-  let wrapper = engine_from_long(engine_ptr);
-  let engine = &wrapper.engine;
+  let mut wrapper = engine_from_long(engine_ptr);
+  let engine = &mut wrapper.engine;
 
   {
     match event_type {
       ANDROID_EVENT_TYPE_POINTER_DOWN |
       ANDROID_EVENT_TYPE_DOWN => {
-        engine.dispatch_event(Event::FingerDown(finger_index as u32));
-        engine.dispatch_event(Event::FingerMoved {
+        engine.dispatch_touch_event::<AndroidPlatform>(Event::FingerDown(finger_index as u32));
+        engine.dispatch_touch_event::<AndroidPlatform>(Event::FingerMoved {
           index: finger_index as u32,
           position: Vec2::new(x, y)
         });
       }
       ANDROID_EVENT_TYPE_POINTER_UP |
       ANDROID_EVENT_TYPE_UP => {
-        engine.dispatch_event(Event::FingerMoved {
+        engine.dispatch_touch_event::<AndroidPlatform>(Event::FingerMoved {
           index: finger_index as u32,
           position: Vec2::new(x, y)
         });
-        engine.dispatch_event(Event::FingerUp(finger_index as u32));
+        engine.dispatch_touch_event::<AndroidPlatform>(Event::FingerUp(finger_index as u32));
       }
       ANDROID_EVENT_TYPE_MOVE => {
-        engine.dispatch_event(Event::FingerMoved {
+        engine.dispatch_touch_event::<AndroidPlatform>(Event::FingerMoved {
           index: finger_index as u32,
           position: Vec2::new(x, y)
         });
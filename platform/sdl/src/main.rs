@@ -8,7 +8,7 @@ extern crate sourcerenderer_engine;
 #[macro_use]
 extern crate lazy_static;
 
-pub use sdl_platform::SDLPlatform;
+pub use sdl_platform::{FullscreenMode, SDLPlatform};
 use sourcerenderer_engine::Engine;
 
 mod sdl_platform;
@@ -2,6 +2,7 @@ use std::error::Error;
 
 use metal::foreign_types::ForeignTypeRef;
 use sdl2::video::WindowBuilder;
+use sourcerenderer_core::gpu::{ColorSpace, PresentMode};
 use sourcerenderer_metal::{MTLBackend, MTLDevice, MTLInstance, MTLSurface, MTLSwapchain};
 
 use raw_window_handle::HasWindowHandle;
@@ -28,8 +29,8 @@ pub(crate) fn create_surface(sdl_window_handle: &sdl2::video::Window, graphics_i
     MTLSurface::new(graphics_instance, layer_ref)
 }
 
-pub(crate) fn create_swapchain(_vsync: bool, width: u32, height: u32, device: &MTLDevice, surface: MTLSurface) -> MTLSwapchain {
-    MTLSwapchain::new(surface, device.handle(), Some((width, height)))
+pub(crate) fn create_swapchain(present_mode: PresentMode, _color_space: ColorSpace, width: u32, height: u32, device: &MTLDevice, surface: MTLSurface) -> MTLSwapchain {
+    MTLSwapchain::new(surface, device.handle(), Some((width, height)), present_mode)
 }
 
 pub(crate) fn prepare_window(window_builder: &mut WindowBuilder) {
@@ -7,7 +7,7 @@ use std::path::{
 };
 
 use crossbeam_channel::Sender;
-use log::debug;
+use log::{debug, warn};
 use notify::{
     recommended_watcher,
     RecommendedWatcher,
@@ -17,13 +17,31 @@ use sdl2::event::{
     Event as SDLEvent,
     WindowEvent,
 };
-use sdl2::keyboard::Scancode;
+use sdl2::controller::{
+    Axis as SDLAxis,
+    Button as SDLButton,
+    GameController,
+};
+use sdl2::keyboard::{
+    Mod,
+    Scancode,
+};
+use sdl2::video::{
+    FullscreenType,
+    WindowPos,
+};
 use sdl2::{
     EventPump,
+    GameControllerSubsystem,
     Sdl,
     VideoSubsystem,
 };
+use sourcerenderer_core::input::{
+    GamepadAxis,
+    GamepadButton,
+};
 use sourcerenderer_core::platform::{
+    Event,
     FileWatcher,
     Platform,
     ThreadHandle,
@@ -31,6 +49,7 @@ use sourcerenderer_core::platform::{
     IO
 };
 use sourcerenderer_core::{
+    gpu::{ColorSpace, PresentMode},
     Vec2I,
     Vec2UI,
     Vec2
@@ -59,23 +78,45 @@ lazy_static! {
     };
 }
 
+/// Axis values closer to the center than this get reported as 0.
+const GAMEPAD_AXIS_DEAD_ZONE: f32 = 0.15f32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FullscreenMode {
+    Windowed,
+    /// A window covering the whole display at the desktop resolution
+    BorderlessFullscreen,
+    /// Takes over the display, uses the current window size as the display mode
+    ExclusiveFullscreen,
+}
+
 pub struct SDLPlatform {
     sdl_context: Sdl,
     video_subsystem: VideoSubsystem,
+    game_controller_subsystem: GameControllerSubsystem,
     event_pump: EventPump,
     window: SDLWindow,
     mouse_pos: Vec2I,
+    /// Open controllers by joystick instance id, SDL only sends events for opened ones
+    game_controllers: HashMap<u32, GameController>,
+    gamepad_axes: HashMap<(u32, GamepadAxis), f32>,
+    /// Set when the fullscreen mode changed, the window state gets dispatched with the next events
+    window_state_changed: bool,
 }
 
 pub struct SDLWindow {
     window: sdl2::video::Window,
     is_active: bool,
+    fullscreen_mode: FullscreenMode,
+    /// Position and size of the window before it went fullscreen
+    windowed_rect: Option<((i32, i32), (u32, u32))>,
 }
 
 impl SDLPlatform {
     pub fn new() -> Box<SDLPlatform> {
         let sdl_context = sdl2::init().unwrap();
         let video_subsystem = sdl_context.video().unwrap();
+        let game_controller_subsystem = sdl_context.game_controller().unwrap();
         let event_pump = sdl_context.event_pump().unwrap();
 
         let window = SDLWindow::new(&sdl_context, &video_subsystem);
@@ -83,9 +124,13 @@ impl SDLPlatform {
         Box::new(SDLPlatform {
             sdl_context,
             video_subsystem,
+            game_controller_subsystem,
             event_pump,
             window,
             mouse_pos: Vec2I::new(0, 0),
+            game_controllers: HashMap::new(),
+            gamepad_axes: HashMap::new(),
+            window_state_changed: false,
         })
     }
 
@@ -103,7 +148,7 @@ impl SDLPlatform {
                 } => {
                     let key = SCANCODE_TO_KEY.get(&keycode).copied();
                     if let Some(key) = key {
-                        engine.dispatch_keyboard_input(KeyboardInput {
+                        engine.dispatch_keyboard_input::<SDLPlatform>(KeyboardInput {
                             key_code: key,
                             logical_key: Key::Dead(None),
                             state: ButtonState::Released,
@@ -112,13 +157,39 @@ impl SDLPlatform {
                         });
                     }
                 }
+                SDLEvent::KeyDown {
+                    scancode: Some(Scancode::Return),
+                    keymod,
+                    repeat: false,
+                    ..
+                } if keymod.intersects(Mod::LALTMOD | Mod::RALTMOD) => {
+                    let mode = if self.window.fullscreen_mode() == FullscreenMode::Windowed {
+                        FullscreenMode::BorderlessFullscreen
+                    } else {
+                        FullscreenMode::Windowed
+                    };
+                    self.set_fullscreen(mode);
+                }
+                SDLEvent::KeyDown {
+                    scancode: Some(Scancode::Pause),
+                    repeat: false,
+                    ..
+                } => {
+                    engine.set_paused(!engine.is_paused());
+                }
+                SDLEvent::KeyDown {
+                    scancode: Some(Scancode::F10),
+                    ..
+                } => {
+                    engine.step_once();
+                }
                 SDLEvent::KeyDown {
                     scancode: Some(keycode),
                     ..
                 } => {
                     let key = SCANCODE_TO_KEY.get(&keycode).copied();
                     if let Some(key) = key {
-                        engine.dispatch_keyboard_input(KeyboardInput {
+                        engine.dispatch_keyboard_input::<SDLPlatform>(KeyboardInput {
                             key_code: key,
                             logical_key: Key::Dead(None),
                             state: ButtonState::Pressed,
@@ -134,22 +205,64 @@ impl SDLPlatform {
                         delta: Vec2::new(xrel as f32, yrel as f32)
                     });
                 }
+                SDLEvent::ControllerDeviceAdded { which, .. } => {
+                    // Already connected controllers also get reported when SDL starts up.
+                    match self.game_controller_subsystem.open(which) {
+                        Ok(controller) => {
+                            let gamepad = controller.instance_id();
+                            engine.dispatch_gamepad_event::<SDLPlatform>(Event::GamepadConnected {
+                                gamepad,
+                                name: controller.name()
+                            });
+                            self.game_controllers.insert(gamepad, controller);
+                        }
+                        Err(e) => {
+                            warn!("Failed to open game controller {}: {:?}", which, e);
+                        }
+                    }
+                }
+                SDLEvent::ControllerDeviceRemoved { which, .. } => {
+                    if self.game_controllers.remove(&which).is_some() {
+                        self.gamepad_axes.retain(|(gamepad, _), _| *gamepad != which);
+                        engine.dispatch_gamepad_event::<SDLPlatform>(Event::GamepadDisconnected(which));
+                    }
+                }
+                SDLEvent::ControllerButtonDown { which, button, .. } => {
+                    if let Some(button) = sdl_button_to_gamepad_button(button) {
+                        engine.dispatch_gamepad_event::<SDLPlatform>(Event::GamepadButtonDown {
+                            gamepad: which,
+                            button
+                        });
+                    }
+                }
+                SDLEvent::ControllerButtonUp { which, button, .. } => {
+                    if let Some(button) = sdl_button_to_gamepad_button(button) {
+                        engine.dispatch_gamepad_event::<SDLPlatform>(Event::GamepadButtonUp {
+                            gamepad: which,
+                            button
+                        });
+                    }
+                }
+                SDLEvent::ControllerAxisMotion { which, axis, value, .. } => {
+                    let (axis, value) = sdl_axis_to_gamepad_axis(axis, value);
+                    // Only dispatch actual changes, so the noise inside the dead zone doesn't produce events.
+                    let previous_value = self.gamepad_axes.insert((which, axis), value);
+                    if previous_value != Some(value) {
+                        engine.dispatch_gamepad_event::<SDLPlatform>(Event::GamepadAxis {
+                            gamepad: which,
+                            axis,
+                            value
+                        });
+                    }
+                }
                 SDLEvent::Window {
                     window_id: _,
                     timestamp: _,
                     win_event,
                 } => match win_event {
-                    WindowEvent::Resized(width, height) => {
-                        engine.window_changed::<SDLPlatform>(WindowState::Window(Vec2UI::new(
-                            width as u32,
-                            height as u32,
-                        )));
-                    }
-                    WindowEvent::SizeChanged(width, height) => {
-                        engine.window_changed::<SDLPlatform>(WindowState::Window(Vec2UI::new(
-                            width as u32,
-                            height as u32,
-                        )));
+                    WindowEvent::Resized(_, _) | WindowEvent::SizeChanged(_, _) => {
+                        self.window_state_changed = false;
+                        engine.window_changed::<SDLPlatform>(self.window.window_state());
                     }
                     WindowEvent::Close => {
                         engine.stop::<SDLPlatform>();
@@ -160,9 +273,23 @@ impl SDLPlatform {
             }
             event_opt = self.event_pump.poll_event()
         }
+
+        // Switching to exclusive fullscreen at the same size doesn't produce a size change event.
+        if self.window_state_changed {
+            self.window_state_changed = false;
+            engine.window_changed::<SDLPlatform>(self.window.window_state());
+        }
         true
     }
 
+    /// Switches the window between windowed and fullscreen.
+    /// The size and position of the window get restored when going back to windowed.
+    pub fn set_fullscreen(&mut self, mode: FullscreenMode) {
+        if self.window.set_fullscreen(mode) {
+            self.window_state_changed = true;
+        }
+    }
+
     pub(crate) fn update_mouse_lock(&self, is_locked: bool) {
         let mouse_util = self.sdl_context.mouse();
         mouse_util.set_relative_mouse_mode(is_locked);
@@ -173,6 +300,46 @@ impl SDLPlatform {
     }
 }
 
+fn sdl_button_to_gamepad_button(button: SDLButton) -> Option<GamepadButton> {
+    match button {
+        SDLButton::A => Some(GamepadButton::South),
+        SDLButton::B => Some(GamepadButton::East),
+        SDLButton::X => Some(GamepadButton::West),
+        SDLButton::Y => Some(GamepadButton::North),
+        SDLButton::Back => Some(GamepadButton::Select),
+        SDLButton::Guide => Some(GamepadButton::Mode),
+        SDLButton::Start => Some(GamepadButton::Start),
+        SDLButton::LeftStick => Some(GamepadButton::LeftStick),
+        SDLButton::RightStick => Some(GamepadButton::RightStick),
+        SDLButton::LeftShoulder => Some(GamepadButton::LeftShoulder),
+        SDLButton::RightShoulder => Some(GamepadButton::RightShoulder),
+        SDLButton::DPadUp => Some(GamepadButton::DPadUp),
+        SDLButton::DPadDown => Some(GamepadButton::DPadDown),
+        SDLButton::DPadLeft => Some(GamepadButton::DPadLeft),
+        SDLButton::DPadRight => Some(GamepadButton::DPadRight),
+        _ => None,
+    }
+}
+
+/// Normalizes the raw axis value, applies the dead zone and flips the Y axes so they point up.
+fn sdl_axis_to_gamepad_axis(axis: SDLAxis, value: i16) -> (GamepadAxis, f32) {
+    let value = (value as f32 / i16::MAX as f32).clamp(-1f32, 1f32);
+    // Rescale the remaining range so the value still starts at 0 right outside of the dead zone.
+    let value = if value.abs() < GAMEPAD_AXIS_DEAD_ZONE {
+        0f32
+    } else {
+        value.signum() * (value.abs() - GAMEPAD_AXIS_DEAD_ZONE) / (1f32 - GAMEPAD_AXIS_DEAD_ZONE)
+    };
+    match axis {
+        SDLAxis::LeftX => (GamepadAxis::LeftStickX, value),
+        SDLAxis::LeftY => (GamepadAxis::LeftStickY, -value),
+        SDLAxis::RightX => (GamepadAxis::RightStickX, value),
+        SDLAxis::RightY => (GamepadAxis::RightStickY, -value),
+        SDLAxis::TriggerLeft => (GamepadAxis::LeftTrigger, value),
+        SDLAxis::TriggerRight => (GamepadAxis::RightTrigger, value),
+    }
+}
+
 impl SDLWindow {
     pub fn new(
         _sdl_context: &Sdl,
@@ -180,6 +347,8 @@ impl SDLWindow {
     ) -> SDLWindow {
         let mut window_builder = video_subsystem.window("sourcerenderer", 1920, 1080);
         window_builder.position_centered();
+        // Lets the drawable size differ from the window size, otherwise the swapchain gets upscaled on HiDPI displays.
+        window_builder.allow_highdpi();
         //window_builder.fullscreen();
 
         sdl_gpu::prepare_window(&mut window_builder);
@@ -188,6 +357,52 @@ impl SDLWindow {
         SDLWindow {
             window,
             is_active: true,
+            fullscreen_mode: FullscreenMode::Windowed,
+            windowed_rect: None,
+        }
+    }
+
+    pub fn fullscreen_mode(&self) -> FullscreenMode {
+        self.fullscreen_mode
+    }
+
+    fn set_fullscreen(&mut self, mode: FullscreenMode) -> bool {
+        if mode == self.fullscreen_mode {
+            return false;
+        }
+        if self.fullscreen_mode == FullscreenMode::Windowed {
+            self.windowed_rect = Some((self.window.position(), self.window.size()));
+        }
+
+        let fullscreen_type = match mode {
+            FullscreenMode::Windowed => FullscreenType::Off,
+            FullscreenMode::BorderlessFullscreen => FullscreenType::Desktop,
+            FullscreenMode::ExclusiveFullscreen => FullscreenType::True,
+        };
+        if let Err(e) = self.window.set_fullscreen(fullscreen_type) {
+            warn!("Failed to switch window to {:?}: {}", mode, e);
+            return false;
+        }
+        self.fullscreen_mode = mode;
+
+        if mode == FullscreenMode::Windowed {
+            if let Some(((x, y), (width, height))) = self.windowed_rect.take() {
+                if let Err(e) = self.window.set_size(width, height) {
+                    warn!("Failed to restore window size: {}", e);
+                }
+                self.window.set_position(WindowPos::Positioned(x), WindowPos::Positioned(y));
+            }
+        }
+        true
+    }
+
+    fn window_state(&self) -> WindowState {
+        let (width, height) = self.window.drawable_size();
+        let size = Vec2UI::new(width, height);
+        if self.fullscreen_mode == FullscreenMode::Windowed {
+            WindowState::Window(size)
+        } else {
+            WindowState::Fullscreen(size)
         }
     }
 
@@ -231,12 +446,13 @@ impl Window<SDLPlatform> for SDLWindow {
 
     fn create_swapchain(
         &self,
-        vsync: bool,
+        present_mode: PresentMode,
+        color_space: ColorSpace,
         device: &<<SDLPlatform as sourcerenderer_core::Platform>::GPUBackend as sourcerenderer_core::gpu::GPUBackend>::Device,
         surface: <<SDLPlatform as sourcerenderer_core::Platform>::GPUBackend as sourcerenderer_core::gpu::GPUBackend>::Surface
      ) -> <<SDLPlatform as sourcerenderer_core::Platform>::GPUBackend as sourcerenderer_core::gpu::GPUBackend>::Swapchain {
         let (width, height) = self.window.drawable_size();
-        sdl_gpu::create_swapchain(vsync, width, height, device, surface)
+        sdl_gpu::create_swapchain(present_mode, color_space, width, height, device, surface)
     }
 
     fn width(&self) -> u32 {
@@ -246,6 +462,14 @@ impl Window<SDLPlatform> for SDLWindow {
     fn height(&self) -> u32 {
         self.window.drawable_size().1
     }
+
+    fn scale_factor(&self) -> f32 {
+        let (width, _) = self.window.size();
+        if width == 0 {
+            return 1f32;
+        }
+        self.window.drawable_size().0 as f32 / width as f32
+    }
 }
 
 pub struct StdIO {}
@@ -274,6 +498,17 @@ impl IO for StdIO {
         let base_path = std::env::current_dir().unwrap_or_else(|_e| PathBuf::new());
         NotifyFileWatcher::new(sender, &base_path)
     }
+
+    fn cache_dir() -> Option<PathBuf> {
+        // The working directory isn't necessarily writable, SDL picks the per user directory of the OS.
+        match sdl2::filesystem::pref_path("K0bin", "SourceRenderer") {
+            Ok(path) => Some(PathBuf::from(path)),
+            Err(e) => {
+                warn!("Failed to find a cache directory: {:?}", e);
+                None
+            }
+        }
+    }
 }
 
 pub struct NotifyFileWatcher {
@@ -327,3 +562,40 @@ impl ThreadHandle for StdThreadHandle {
         self.0.join()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use sourcerenderer_core::input::{InputBinding, InputMap, Key as PlatformKey};
+
+    use super::*;
+
+    // Lives here because the platform events need an actual platform type.
+    #[test]
+    fn just_pressed_lasts_one_frame() {
+        let mut input = InputMap::new();
+        input.bind("jump", InputBinding::Key(PlatformKey::Space));
+        input.bind("jump", InputBinding::GamepadButton(GamepadButton::South));
+
+        input.process_event(&Event::<SDLPlatform>::KeyDown(PlatformKey::Space));
+        assert!(input.just_pressed("jump"));
+        assert!(input.is_pressed("jump"));
+        input.end_frame();
+
+        // Still held down, but no longer new
+        assert!(!input.just_pressed("jump"));
+        assert!(input.is_pressed("jump"));
+        input.process_event(&Event::<SDLPlatform>::KeyDown(PlatformKey::Space));
+        assert!(!input.just_pressed("jump"));
+        input.end_frame();
+
+        input.process_event(&Event::<SDLPlatform>::KeyUp(PlatformKey::Space));
+        assert!(!input.is_pressed("jump"));
+        input.end_frame();
+
+        input.process_event(&Event::<SDLPlatform>::GamepadButtonDown { gamepad: 0, button: GamepadButton::South });
+        assert!(input.just_pressed("jump"));
+        assert!(!input.just_pressed("crouch"));
+        input.end_frame();
+        assert!(!input.just_pressed("jump"));
+    }
+}
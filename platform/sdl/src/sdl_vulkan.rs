@@ -1,7 +1,7 @@
 use std::error::Error;
 
 use sdl2::video::WindowBuilder;
-use sourcerenderer_core::{platform::Window, Platform};
+use sourcerenderer_core::{gpu::{ColorSpace, PresentMode}, platform::Window, Platform};
 use sourcerenderer_vulkan::{VkBackend, VkDevice, VkInstance, VkSurface, VkSwapchain};
 
 use crate::{sdl_platform::{SDLWindow, StdIO, StdThreadHandle}, SDLPlatform};
@@ -34,10 +34,12 @@ pub(crate) fn create_surface(sdl_window_handle: &sdl2::video::Window, graphics_i
     )
 }
 
-pub(crate) fn create_swapchain(vsync: bool, width: u32, height: u32, device: &VkDevice, surface: VkSurface) -> VkSwapchain {
+pub(crate) fn create_swapchain(present_mode: PresentMode, color_space: ColorSpace, width: u32, height: u32, device: &VkDevice, surface: VkSurface) -> VkSwapchain {
     let device_inner = device.inner();
     VkSwapchain::new(
-        vsync,
+        present_mode,
+        color_space,
+        false,
         width,
         height,
         device_inner,
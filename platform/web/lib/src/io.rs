@@ -1,14 +1,17 @@
+use std::collections::{HashMap, VecDeque};
 use std::future::Future;
 use std::io::{Read, Seek, SeekFrom, Result as IOResult, Error as IOError, ErrorKind};
 use std::pin::{pin, Pin};
 use std::process::Output;
 use std::sync::{Arc, Mutex, MutexGuard};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::task::{Context, Poll};
 
 use futures_lite::io::Cursor;
 use futures_lite::{AsyncRead, AsyncSeek};
+use js_sys::{Reflect, Uint8Array};
 use log::info;
+use wasm_bindgen::JsValue;
 
 use sourcerenderer_core::platform::{IO, FileWatcher};
 
@@ -27,33 +30,191 @@ impl<T, F> Future for ForceSendFuture<T, F>
 unsafe impl<T, F> Send for ForceSendFuture<T, F>
     where F: Future<Output = T> {}
 
+fn js_error_to_io_error(js_val: JsValue) -> IOError {
+    let response_code_opt = js_val.as_f64();
+    if response_code_opt.is_none() {
+        IOError::new(ErrorKind::Other, format!("Response code: {:?}", js_val))
+    } else {
+        let response_code = response_code_opt.unwrap() as u32;
+        match response_code {
+            404 => IOError::new(ErrorKind::NotFound, format!("Response code: {}", response_code)),
+            _ => IOError::new(ErrorKind::Other, format!("Response code: {}", response_code)),
+        }
+    }
+}
+
+fn copy_to_wasm(buffer: &Uint8Array) -> Box<[u8]> {
+    let mut wasm_copy = Vec::<u8>::with_capacity(buffer.length() as usize);
+    unsafe { wasm_copy.set_len(buffer.length() as usize); }
+    buffer.copy_to(&mut wasm_copy[..]);
+    wasm_copy.into_boxed_slice()
+}
+
+/// Files that are at least this big get streamed using range requests instead of downloading them entirely.
+/// Mostly relevant for VPK archives and maps which are often hundreds of megabytes
+/// while loading a level only touches a fraction of that.
+const RANGE_STREAMING_THRESHOLD: u64 = 4 << 20;
+
+/// Files are fetched in aligned chunks of this size, so overlapping reads never download the same bytes twice.
+const RANGE_CHUNK_SIZE: u64 = 256 << 10;
+
+/// Maximum amount of chunks kept per file, the oldest one gets evicted first.
+const MAX_CACHED_CHUNKS: usize = 64;
+
+pub enum WebFile {
+    Whole(Cursor<Box<[u8]>>),
+    Ranged(RangedWebFile),
+}
+
+impl AsyncRead for WebFile {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<IOResult<usize>> {
+        match self.get_mut() {
+            WebFile::Whole(cursor) => Pin::new(cursor).poll_read(cx, buf),
+            WebFile::Ranged(file) => Pin::new(file).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncSeek for WebFile {
+    fn poll_seek(self: Pin<&mut Self>, cx: &mut Context<'_>, pos: SeekFrom) -> Poll<IOResult<u64>> {
+        match self.get_mut() {
+            WebFile::Whole(cursor) => Pin::new(cursor).poll_seek(cx, pos),
+            WebFile::Ranged(file) => Pin::new(file).poll_seek(cx, pos),
+        }
+    }
+}
+
+type RangeFuture = Pin<Box<dyn Future<Output = Result<Uint8Array, JsValue>>>>;
+
+/// A file that only downloads the parts that actually get read using HTTP range requests.
+pub struct RangedWebFile {
+    path: String,
+    length: u64,
+    position: u64,
+    chunks: HashMap<u64, Box<[u8]>>,
+    chunk_order: VecDeque<u64>,
+    pending: Option<(u64, RangeFuture)>,
+}
+
+// There are no threads on the web.
+unsafe impl Send for RangedWebFile {}
+unsafe impl Sync for RangedWebFile {}
+
+impl RangedWebFile {
+    fn new(path: String, length: u64) -> Self {
+        Self {
+            path,
+            length,
+            position: 0,
+            chunks: HashMap::new(),
+            chunk_order: VecDeque::new(),
+            pending: None,
+        }
+    }
+
+    fn insert_chunk(&mut self, chunk_index: u64, data: Box<[u8]>) {
+        if self.chunk_order.len() >= MAX_CACHED_CHUNKS {
+            if let Some(evicted) = self.chunk_order.pop_front() {
+                self.chunks.remove(&evicted);
+            }
+        }
+        self.chunks.insert(chunk_index, data);
+        self.chunk_order.push_back(chunk_index);
+    }
+}
+
+impl AsyncRead for RangedWebFile {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<IOResult<usize>> {
+        let this = self.get_mut();
+        if this.position >= this.length || buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        let chunk_index = this.position / RANGE_CHUNK_SIZE;
+        loop {
+            if let Some(chunk) = this.chunks.get(&chunk_index) {
+                let offset_in_chunk = (this.position - chunk_index * RANGE_CHUNK_SIZE) as usize;
+                if offset_in_chunk >= chunk.len() {
+                    return Poll::Ready(Err(IOError::new(ErrorKind::UnexpectedEof, "Range response was shorter than expected")));
+                }
+                let read = buf.len().min(chunk.len() - offset_in_chunk);
+                buf[..read].copy_from_slice(&chunk[offset_in_chunk..offset_in_chunk + read]);
+                this.position += read as u64;
+                return Poll::Ready(Ok(read));
+            }
+
+            if this.pending.as_ref().map(|(pending_index, _)| *pending_index != chunk_index).unwrap_or(true) {
+                // Either nothing is in flight or the file was seeked to a different chunk in the meantime.
+                let path = this.path.clone();
+                let start = chunk_index * RANGE_CHUNK_SIZE;
+                let length = RANGE_CHUNK_SIZE.min(this.length - start);
+                let future: RangeFuture = Box::pin(async move {
+                    crate::fetch_asset_range(&path, start as f64, length as f64).await
+                });
+                this.pending = Some((chunk_index, future));
+            }
+
+            let (_, future) = this.pending.as_mut().unwrap();
+            match future.as_mut().poll(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(result) => {
+                    this.pending = None;
+                    let buffer = result.map_err(js_error_to_io_error)?;
+                    this.insert_chunk(chunk_index, copy_to_wasm(&buffer));
+                }
+            }
+        }
+    }
+}
+
+impl AsyncSeek for RangedWebFile {
+    fn poll_seek(self: Pin<&mut Self>, _cx: &mut Context<'_>, pos: SeekFrom) -> Poll<IOResult<u64>> {
+        let this = self.get_mut();
+        let new_position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => this.length.checked_add_signed(offset),
+            SeekFrom::Current(offset) => this.position.checked_add_signed(offset),
+        };
+        let Some(new_position) = new_position else {
+            return Poll::Ready(Err(IOError::new(ErrorKind::InvalidInput, "Invalid seek to a negative position")));
+        };
+        this.position = new_position;
+        Poll::Ready(Ok(new_position))
+    }
+}
+
 pub struct WebIO {}
 
+impl WebIO {
+    async fn fetch_whole_asset(path: &str) -> IOResult<WebFile> {
+        let future = crate::fetch_asset(path);
+        let send_future = ForceSendFuture(future);
+        let buffer = send_future.await.map_err(js_error_to_io_error)?;
+        Ok(WebFile::Whole(Cursor::new(copy_to_wasm(&buffer))))
+    }
+}
+
 impl IO for WebIO {
-    type File = Cursor<Box<[u8]>>;
+    type File = WebFile;
     type FileWatcher = NopWatcher;
 
     async fn open_asset<P: AsRef<Path> + Send>(path: P) -> IOResult<Self::File> {
         log::trace!("Loading web file: {:?}", path.as_ref());
-        let future = crate::fetch_asset(path.as_ref().to_str().unwrap());
-        let send_future = ForceSendFuture(future);
-        let buffer_res = send_future.await;
-        let buffer = buffer_res.map_err(|js_val| {
-            let response_code_opt = js_val.as_f64();
-            if response_code_opt.is_none() {
-                IOError::new(ErrorKind::Other, format!("Response code: {:?}", js_val))
-            } else {
-                let response_code = response_code_opt.unwrap() as u32;
-                match response_code {
-                    404 => IOError::new(ErrorKind::NotFound, format!("Response code: {}", response_code)),
-                    _ => IOError::new(ErrorKind::Other, format!("Response code: {}", response_code)),
-                }
+        let path = path.as_ref().to_str().unwrap().to_string();
+
+        let head_future = ForceSendFuture(crate::fetch_asset_head(&path));
+        let head = head_future.await.map_err(js_error_to_io_error)?;
+        let length = Reflect::get(&head, &JsValue::from_str("length")).ok().and_then(|length| length.as_f64());
+        let accepts_ranges = Reflect::get(&head, &JsValue::from_str("acceptsRanges")).ok().and_then(|accepts_ranges| accepts_ranges.as_bool()).unwrap_or(false);
+
+        match length {
+            Some(length) if accepts_ranges && length as u64 >= RANGE_STREAMING_THRESHOLD => {
+                info!("Streaming {} using range requests, file size: {} MiB", &path, length as u64 >> 20);
+                Ok(WebFile::Ranged(RangedWebFile::new(path, length as u64)))
             }
-        })?;
-        let mut wasm_copy = Vec::<u8>::with_capacity(buffer.length() as usize);
-        unsafe { wasm_copy.set_len(buffer.length() as usize); }
-        buffer.copy_to(&mut wasm_copy[..]);
-        Ok(Cursor::new(wasm_copy.into_boxed_slice()))
+            // The server doesn't support range requests or didn't tell us the size.
+            _ => Self::fetch_whole_asset(&path).await,
+        }
     }
 
     async fn asset_exists<P: AsRef<Path> + Send>(path: P) -> bool {
@@ -75,6 +236,10 @@ impl IO for WebIO {
     fn new_file_watcher(_sender: crossbeam_channel::Sender<String>) -> Self::FileWatcher {
         NopWatcher {}
     }
+
+    fn cache_dir() -> Option<PathBuf> {
+        None
+    }
 }
 
 pub struct NopWatcher {}
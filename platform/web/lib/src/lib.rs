@@ -1,7 +1,8 @@
 use js_sys::{Promise, Uint8Array};
 use log::info;
 use platform::WebPlatform;
-use sourcerenderer_engine::Engine as ActualEngine;
+use sourcerenderer_core::Platform;
+use sourcerenderer_engine::{Engine as ActualEngine, WindowState};
 use sourcerenderer_game::GamePlugin;
 use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 use web_sys::{Navigator, OffscreenCanvas};
@@ -13,7 +14,8 @@ mod utils;
 
 #[wasm_bindgen]
 pub struct Engine {
-    engine: ActualEngine
+    engine: ActualEngine,
+    canvas: OffscreenCanvas
 }
 
 #[wasm_bindgen]
@@ -21,6 +23,20 @@ impl Engine {
     pub fn frame(&mut self) {
         self.engine.frame();
     }
+
+    /// Has to be called by the main thread whenever the canvas element changes its size
+    /// or the page moves to a display with a different devicePixelRatio.
+    /// The worker can't observe either of those.
+    pub fn resize(&mut self, css_width: f64, css_height: f64, device_pixel_ratio: f64) {
+        let size = window::backing_store_size(css_width, css_height, device_pixel_ratio);
+        if size.x == self.canvas.width() && size.y == self.canvas.height() {
+            return;
+        }
+        info!("Resizing canvas to {}x{}", size.x, size.y);
+        self.canvas.set_width(size.x);
+        self.canvas.set_height(size.y);
+        self.engine.window_changed::<WebPlatform>(WindowState::Window(size));
+    }
 }
 
 
@@ -37,7 +53,8 @@ pub async fn start_engine(navigator: Navigator, canvas: OffscreenCanvas) -> Engi
   let engine = ActualEngine::run(&platform, GamePlugin::<WebPlatform>::default());
 
   let wrapper = Engine {
-    engine
+    engine,
+    canvas: platform.window().canvas().clone()
   };
   wrapper
 }
@@ -46,4 +63,11 @@ pub async fn start_engine(navigator: Navigator, canvas: OffscreenCanvas) -> Engi
 extern "C" {
   #[wasm_bindgen(js_name = "fetchAsset", catch)]
   pub async fn fetch_asset(path: &str) -> Result<Uint8Array, JsValue>;
+
+  /// Resolves to an object with the fields length and acceptsRanges.
+  #[wasm_bindgen(js_name = "fetchAssetHead", catch)]
+  pub async fn fetch_asset_head(path: &str) -> Result<JsValue, JsValue>;
+
+  #[wasm_bindgen(js_name = "fetchAssetRange", catch)]
+  pub async fn fetch_asset_range(path: &str, offset: f64, length: f64) -> Result<Uint8Array, JsValue>;
 }
@@ -8,3 +8,37 @@ export async function fetchAsset(path: string): Promise<Uint8Array> {
     const buffer = await response.arrayBuffer();
     return new Uint8Array(buffer);
 }
+
+export interface AssetHead {
+    length: number|null,
+    acceptsRanges: boolean,
+}
+
+export async function fetchAssetHead(path: string): Promise<AssetHead> {
+    const url = new URL("./assets/" + path, location.origin)
+    const response = await fetch(url, { method: "HEAD" });
+    if (response.status != 200) {
+        throw response.status;
+    }
+    const length = response.headers.get("Content-Length");
+    return {
+        length: length !== null ? parseInt(length) : null,
+        acceptsRanges: response.headers.get("Accept-Ranges") === "bytes",
+    };
+}
+
+export async function fetchAssetRange(path: string, offset: number, length: number): Promise<Uint8Array> {
+    const url = new URL("./assets/" + path, location.origin)
+    console.trace("Fetching: " + url + " bytes " + offset + " - " + (offset + length - 1));
+    const response = await fetch(url, { headers: { "Range": "bytes=" + offset + "-" + (offset + length - 1) } });
+    if (response.status == 200) {
+        // The server ignored the range header and sent the whole file.
+        const buffer = await response.arrayBuffer();
+        return new Uint8Array(buffer, offset, Math.min(length, buffer.byteLength - offset));
+    }
+    if (response.status != 206) {
+        throw response.status;
+    }
+    const buffer = await response.arrayBuffer();
+    return new Uint8Array(buffer);
+}
@@ -1,5 +1,6 @@
 use sourcerenderer_core::platform::Window;
-use sourcerenderer_core::gpu::{Instance, Device};
+use sourcerenderer_core::Vec2UI;
+use sourcerenderer_core::gpu::{ColorSpace, Instance, Device, PresentMode};
 use sourcerenderer_webgpu::{WebGPUDevice, WebGPUInstance, WebGPUSurface, WebGPUSwapchain};
 use web_sys::OffscreenCanvas;
 
@@ -15,6 +16,18 @@ impl WebWindow {
             canvas
         }
     }
+
+    pub(crate) fn canvas(&self) -> &OffscreenCanvas {
+        &self.canvas
+    }
+}
+
+/// Converts the CSS size of the canvas element to the size of its backing store in physical pixels.
+pub(crate) fn backing_store_size(css_width: f64, css_height: f64, device_pixel_ratio: f64) -> Vec2UI {
+    Vec2UI::new(
+        ((css_width * device_pixel_ratio).round() as u32).max(1),
+        ((css_height * device_pixel_ratio).round() as u32).max(1),
+    )
 }
 
 impl Window<WebPlatform> for WebWindow {
@@ -22,7 +35,7 @@ impl Window<WebPlatform> for WebWindow {
         WebGPUSurface::new(graphics_instance.device(), self.canvas.clone()).unwrap()
     }
 
-    fn create_swapchain(&self, _vsync: bool, device: &WebGPUDevice, surface: WebGPUSurface) -> WebGPUSwapchain {
+    fn create_swapchain(&self, _present_mode: PresentMode, _color_space: ColorSpace, device: &WebGPUDevice, surface: WebGPUSurface) -> WebGPUSwapchain {
         WebGPUSwapchain::new(device.handle(), surface)
     }
 
@@ -33,4 +46,9 @@ impl Window<WebPlatform> for WebWindow {
     fn height(&self) -> u32 {
         self.canvas.height()
     }
+
+    fn scale_factor(&self) -> f32 {
+        // The offscreen canvas has no access to the devicePixelRatio of the page.
+        1f32
+    }
 }
@@ -6,7 +6,31 @@ function main() {
   worker.onmessage = (_e: MessageEvent) => {
     worker.postMessage({ canvas: offscreenCanvas }, [offscreenCanvas]);
     console.log("Sent canvas to worker");
+    observeCanvasSize(canvas, worker);
   };
 }
 
-main();
\ No newline at end of file
+// The worker can neither see the canvas element nor the devicePixelRatio,
+// so the main thread has to tell it about size changes.
+function observeCanvasSize(canvas: HTMLCanvasElement, worker: Worker) {
+  const sendSize = () => {
+    const rect = canvas.getBoundingClientRect();
+    worker.postMessage({ resize: { width: rect.width, height: rect.height, devicePixelRatio: window.devicePixelRatio } });
+  };
+
+  new ResizeObserver(() => sendSize()).observe(canvas);
+
+  // The media query only matches the current ratio, so it has to be recreated every time it changes.
+  const watchDevicePixelRatio = () => {
+    const query = matchMedia(`(resolution: ${window.devicePixelRatio}dppx)`);
+    query.addEventListener("change", () => {
+      sendSize();
+      watchDevicePixelRatio();
+    }, { once: true });
+  };
+  watchDevicePixelRatio();
+
+  sendSize();
+}
+
+main();
@@ -1,7 +1,21 @@
 import { Engine, startEngine } from "../../../lib/pkg/sourcerenderer_web";
 
+interface CanvasSize {
+    width: number,
+    height: number,
+    devicePixelRatio: number,
+}
+
+// Resize messages can arrive while the engine is still starting up.
+let pendingSize: CanvasSize|null = null;
+
 onmessage = async (msg: MessageEvent) => {
     console.log("Receiving msg");
+    if (msg.data.resize) {
+        pendingSize = msg.data.resize as CanvasSize;
+        applyPendingSize();
+        return;
+    }
     let canvas = msg.data.canvas as OffscreenCanvas;
     await init(canvas);
 };
@@ -12,11 +26,20 @@ let engine: Engine|null = null;
 
 async function init(canvas: OffscreenCanvas) {
     engine = await startEngine(navigator, canvas);
+    applyPendingSize();
     requestAnimationFrame((_time) => {
         renderFrame();
     });
 }
 
+function applyPendingSize() {
+    if (!engine || !pendingSize) {
+        return;
+    }
+    engine.resize(pendingSize.width, pendingSize.height, pendingSize.devicePixelRatio);
+    pendingSize = null;
+}
+
 onerror = (_e) => {
     engine?.free();
     engine = null;
@@ -3,7 +3,10 @@ extern crate io_util;
 pub mod lump;
 pub mod lump_data;
 mod map_header;
+mod map;
+mod patch;
 
 pub use self::io_util::*;
 pub use self::map_header::MapHeader;
+pub use self::map::Map;
 pub use self::lump_data::*;
@@ -1,6 +1,8 @@
 use std::io::{Read, Result as IOResult, Error as IOError, ErrorKind};
 use crate::lump_data::{LumpData, LumpType};
 use crate::PrimitiveRead;
+use crate::lump_data::Vertex;
+use nalgebra::Vector2;
 
 pub enum FaceType {
   Polygon = 1,
@@ -13,24 +15,72 @@ pub struct Face {
   pub texture: i32,
   pub effect: i32,
   pub face_type: FaceType,
+  pub first_vertex: i32,
   pub vertex_count: i32,
   pub mesh_vert: i32,
   pub mesh_vert_count: i32,
+  /// Index into the lightmap lump, negative if the face isn't lightmapped.
+  /// The lightmap UVs are stored in the second texture coordinate of the vertices.
   pub lightmap_index: i32,
   pub lightmap_start: [i32; 2],
   pub lightmap_size: [i32; 2],
   pub lightmap_origin: [f32; 3],
-  pub lightmap_vecs: [f32; 3],
+  pub lightmap_vecs: [[f32; 3]; 2],
+  pub normal: [f32; 3],
+  /// The dimensions of the control point grid of patches.
   pub size: [i32; 2]
 }
 
+impl Face {
+  /// Index into the textures lump, None if the face doesn't reference a texture.
+  pub fn texture_index(&self) -> Option<usize> {
+    if self.texture >= 0 {
+      Some(self.texture as usize)
+    } else {
+      None
+    }
+  }
+
+  pub fn lightmap(&self) -> Option<usize> {
+    if self.lightmap_index >= 0 {
+      Some(self.lightmap_index as usize)
+    } else {
+      None
+    }
+  }
+
+  /// Returns the lightmap UVs of the vertices of this face.
+  pub fn lightmap_tex_coords(&self, vertices: &[Vertex]) -> Vec<Vector2<f32>> {
+    let start = self.first_vertex.max(0) as usize;
+    let end = (start + self.vertex_count.max(0) as usize).min(vertices.len());
+    vertices.get(start .. end)
+      .map(|vertices| vertices.iter().map(|vertex| vertex.tex_coord[1]).collect())
+      .unwrap_or_default()
+  }
+
+  /// Tessellates a patch face into a triangle list, `vertices` is the whole vertex lump.
+  /// Every 3x3 section of the control point grid gets split into `level` x `level` quads.
+  /// Returns empty buffers for faces that aren't patches or have an invalid control point grid.
+  pub fn tessellate_patch(&self, vertices: &[Vertex], level: u32) -> (Vec<Vertex>, Vec<u32>) {
+    if !matches!(self.face_type, FaceType::Patch) || self.size[0] <= 0 || self.size[1] <= 0 {
+      return (Vec::new(), Vec::new());
+    }
+    let start = self.first_vertex.max(0) as usize;
+    let control_points = match vertices.get(start .. start + self.vertex_count.max(0) as usize) {
+      Some(control_points) => control_points,
+      None => return (Vec::new(), Vec::new())
+    };
+    crate::patch::tessellate_patch(control_points, self.size[0] as usize, self.size[1] as usize, level)
+  }
+}
+
 impl LumpData for Face {
   fn lump_type() -> LumpType {
     LumpType::Faces
   }
 
   fn element_size(_version: i32) -> usize {
-    72
+    104
   }
 
   fn read(reader: &mut dyn Read, _version: i32) -> IOResult<Self> {
@@ -45,6 +95,7 @@ impl LumpData for Face {
         return Err(IOError::new(ErrorKind::Other, "Invalid face type"));
       }
     };
+    let first_vertex = reader.read_i32()?;
     let vertex_count = reader.read_i32()?;
     let mesh_vert = reader.read_i32()?;
     let mesh_vert_count = reader.read_i32()?;
@@ -52,12 +103,17 @@ impl LumpData for Face {
     let lightmap_start = [reader.read_i32()?, reader.read_i32()?];
     let lightmap_size = [reader.read_i32()?, reader.read_i32()?];
     let lightmap_origin = [reader.read_f32()?, reader.read_f32()?, reader.read_f32()?];
-    let lightmap_vecs = [reader.read_f32()?, reader.read_f32()?, reader.read_f32()?];
+    let lightmap_vecs = [
+      [reader.read_f32()?, reader.read_f32()?, reader.read_f32()?],
+      [reader.read_f32()?, reader.read_f32()?, reader.read_f32()?]
+    ];
+    let normal = [reader.read_f32()?, reader.read_f32()?, reader.read_f32()?];
     let size = [reader.read_i32()?, reader.read_i32()?];
     Ok(Self {
       texture,
       effect,
       face_type,
+      first_vertex,
       vertex_count,
       mesh_vert,
       mesh_vert_count,
@@ -66,6 +122,7 @@ impl LumpData for Face {
       lightmap_size,
       lightmap_origin,
       lightmap_vecs,
+      normal,
       size
     })
   }
@@ -0,0 +1,47 @@
+use std::io::{Read, Result as IOResult};
+use crate::lump_data::{LumpData, LumpType};
+use crate::PrimitiveRead;
+use nalgebra::Vector3;
+
+pub struct Leaf {
+  /// The visibility cluster of the leaf, negative if the leaf is outside of the map.
+  pub cluster: i32,
+  pub area: i32,
+  pub min: Vector3<i32>,
+  pub max: Vector3<i32>,
+  pub first_leaf_face: i32,
+  pub leaf_face_count: i32,
+  pub first_leaf_brush: i32,
+  pub leaf_brush_count: i32
+}
+
+impl LumpData for Leaf {
+  fn lump_type() -> LumpType {
+    LumpType::Leafs
+  }
+
+  fn element_size(_version: i32) -> usize {
+    48
+  }
+
+  fn read(reader: &mut dyn Read, _version: i32) -> IOResult<Self> {
+    let cluster = reader.read_i32()?;
+    let area = reader.read_i32()?;
+    let min = Vector3::<i32>::new(reader.read_i32()?, reader.read_i32()?, reader.read_i32()?);
+    let max = Vector3::<i32>::new(reader.read_i32()?, reader.read_i32()?, reader.read_i32()?);
+    let first_leaf_face = reader.read_i32()?;
+    let leaf_face_count = reader.read_i32()?;
+    let first_leaf_brush = reader.read_i32()?;
+    let leaf_brush_count = reader.read_i32()?;
+    Ok(Self {
+      cluster,
+      area,
+      min,
+      max,
+      first_leaf_face,
+      leaf_face_count,
+      first_leaf_brush,
+      leaf_brush_count
+    })
+  }
+}
@@ -0,0 +1,76 @@
+use std::io::{Read, Result as IOResult};
+use crate::lump_data::{LumpData, LumpType};
+
+pub const LIGHTMAP_SIZE: u32 = 128;
+
+/// The overbright factor Quake 3 uses with its default settings (r_mapOverBrightBits 2 on hardware with gamma control).
+pub const DEFAULT_OVERBRIGHT: f32 = 2f32;
+
+/// A 128x128 lightmap with 8 bit RGB texels.
+pub struct Lightmap {
+  pub data: Box<[u8]>
+}
+
+impl Lightmap {
+  /// Returns the lightmap as RGB8 with the overbright factor applied.
+  /// Texels that get brighter than 255 are scaled down as a whole to preserve their hue, the same way Quake 3 does it.
+  pub fn to_rgb8(&self, overbright: f32) -> Vec<u8> {
+    let mut output = Vec::<u8>::with_capacity(self.data.len());
+    for texel in self.data.chunks_exact(3) {
+      let mut color = [texel[0] as f32 * overbright, texel[1] as f32 * overbright, texel[2] as f32 * overbright];
+      let max = color[0].max(color[1]).max(color[2]);
+      if max > 255f32 {
+        let scale = 255f32 / max;
+        for channel in &mut color {
+          *channel *= scale;
+        }
+      }
+      output.extend(color.iter().map(|channel| channel.round().clamp(0f32, 255f32) as u8));
+    }
+    output
+  }
+}
+
+impl LumpData for Lightmap {
+  fn lump_type() -> LumpType {
+    LumpType::Lightmaps
+  }
+
+  fn element_size(_version: i32) -> usize {
+    (LIGHTMAP_SIZE * LIGHTMAP_SIZE * 3) as usize
+  }
+
+  fn read(reader: &mut dyn Read, version: i32) -> IOResult<Self> {
+    let mut data = vec![0u8; Self::element_size(version)];
+    reader.read_exact(&mut data)?;
+    Ok(Self {
+      data: data.into_boxed_slice()
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn to_rgb8() {
+    let lightmap = Lightmap {
+      data: vec![
+        10, 20, 30,
+        200, 100, 50,
+        0, 0, 0,
+        255, 255, 255
+      ].into_boxed_slice()
+    };
+    let rgb = lightmap.to_rgb8(DEFAULT_OVERBRIGHT);
+    assert_eq!(rgb.len(), lightmap.data.len());
+    assert_eq!(&rgb[0..3], &[20, 40, 60]);
+    // 400, 200, 100 gets scaled down to keep the ratio between the channels.
+    assert_eq!(&rgb[3..6], &[255, 128, 64]);
+    assert_eq!(&rgb[6..9], &[0, 0, 0]);
+    assert_eq!(&rgb[9..12], &[255, 255, 255]);
+
+    assert_eq!(&lightmap.to_rgb8(1f32)[3..6], &[200, 100, 50]);
+  }
+}
@@ -1,12 +1,20 @@
 use std::io::{Read, Result as IOResult};
 
 pub use crate::lump_data::brush_model::BrushModel;
-pub use crate::lump_data::face::Face;
+pub use crate::lump_data::face::{Face, FaceType};
 pub use crate::lump_data::vertex::Vertex;
+pub use crate::lump_data::leaf::Leaf;
+pub use crate::lump_data::vis_data::VisData;
+pub use crate::lump_data::lightmap::{Lightmap, LIGHTMAP_SIZE, DEFAULT_OVERBRIGHT};
+pub use crate::lump_data::texture::{Texture, SurfaceFlags, ContentFlags};
 
 mod brush_model;
 mod face;
 mod vertex;
+mod lightmap;
+mod leaf;
+mod vis_data;
+mod texture;
 
 #[derive(Clone, Copy, Debug)]
 #[repr(u8)]
@@ -0,0 +1,137 @@
+use std::io::{Read, Result as IOResult, Error as IOError, ErrorKind};
+use bitflags::bitflags;
+use crate::lump_data::{LumpData, LumpType};
+use crate::{PrimitiveRead, StringRead};
+
+const NAME_LENGTH: u32 = 64;
+
+bitflags! {
+  /// Surface flags as defined in surfaceflags.h of the Quake 3 source code.
+  #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+  pub struct SurfaceFlags: u32 {
+    const NO_DAMAGE = 0x1;
+    const SLICK = 0x2;
+    const SKY = 0x4;
+    const LADDER = 0x8;
+    const NO_IMPACT = 0x10;
+    const NO_MARKS = 0x20;
+    const FLESH = 0x40;
+    const NO_DRAW = 0x80;
+    const HINT = 0x100;
+    const SKIP = 0x200;
+    const NO_LIGHTMAP = 0x400;
+    const POINT_LIGHT = 0x800;
+    const METAL_STEPS = 0x1000;
+    const NO_STEPS = 0x2000;
+    const NON_SOLID = 0x4000;
+    const LIGHT_FILTER = 0x8000;
+    const ALPHA_SHADOW = 0x10000;
+    const NO_DLIGHT = 0x20000;
+    const DUST = 0x40000;
+  }
+}
+
+bitflags! {
+  /// Content flags as defined in surfaceflags.h of the Quake 3 source code.
+  #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+  pub struct ContentFlags: u32 {
+    const SOLID = 0x1;
+    const LAVA = 0x8;
+    const SLIME = 0x10;
+    const WATER = 0x20;
+    const FOG = 0x40;
+    const NOT_TEAM1 = 0x80;
+    const NOT_TEAM2 = 0x100;
+    const NO_BOT_CLIP = 0x200;
+    const AREA_PORTAL = 0x8000;
+    const PLAYER_CLIP = 0x10000;
+    const MONSTER_CLIP = 0x20000;
+    const TELEPORTER = 0x40000;
+    const JUMP_PAD = 0x80000;
+    const CLUSTER_PORTAL = 0x100000;
+    const DO_NOT_ENTER = 0x200000;
+    const BOT_CLIP = 0x400000;
+    const MOVER = 0x800000;
+    const ORIGIN = 0x1000000;
+    const BODY = 0x2000000;
+    const CORPSE = 0x4000000;
+    const DETAIL = 0x8000000;
+    const STRUCTURAL = 0x10000000;
+    const TRANSLUCENT = 0x20000000;
+    const TRIGGER = 0x40000000;
+    const NO_DROP = 0x80000000;
+  }
+}
+
+/// A shader reference of the textures lump, faces and brush sides point to these.
+pub struct Texture {
+  /// The shader path without an extension, for example "textures/base_wall/concrete".
+  pub name: String,
+  pub surface_flags: SurfaceFlags,
+  pub content_flags: ContentFlags
+}
+
+impl Texture {
+  /// Sky and nodraw surfaces don't get rendered as regular geometry.
+  pub fn is_drawn(&self) -> bool {
+    !self.surface_flags.intersects(SurfaceFlags::SKY | SurfaceFlags::NO_DRAW)
+  }
+}
+
+impl LumpData for Texture {
+  fn lump_type() -> LumpType {
+    LumpType::Textures
+  }
+
+  fn element_size(_version: i32) -> usize {
+    72
+  }
+
+  fn read(reader: &mut dyn Read, _version: i32) -> IOResult<Self> {
+    let name = reader.read_fixed_length_null_terminated_string(NAME_LENGTH)
+      .map_err(|_| IOError::new(ErrorKind::InvalidData, "Invalid texture name"))?;
+    // Unknown flags are kept, tools and mods define their own.
+    let surface_flags = SurfaceFlags::from_bits_retain(reader.read_u32()?);
+    let content_flags = ContentFlags::from_bits_retain(reader.read_u32()?);
+    Ok(Self {
+      name,
+      surface_flags,
+      content_flags
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Cursor;
+
+  use super::*;
+
+  fn texture_record(name: &str, surface_flags: u32, content_flags: u32) -> Vec<u8> {
+    let mut data = vec![0u8; NAME_LENGTH as usize];
+    data[.. name.len()].copy_from_slice(name.as_bytes());
+    data.extend_from_slice(&surface_flags.to_le_bytes());
+    data.extend_from_slice(&content_flags.to_le_bytes());
+    assert_eq!(data.len(), Texture::element_size(0x2e));
+    data
+  }
+
+  #[test]
+  fn read_sky() {
+    let data = texture_record("textures/skies/toxicsky", 0x4 | 0x400, 0x1);
+    let texture = Texture::read(&mut Cursor::new(data), 0x2e).unwrap();
+    assert_eq!(texture.name, "textures/skies/toxicsky");
+    assert!(texture.surface_flags.contains(SurfaceFlags::SKY));
+    assert!(texture.surface_flags.contains(SurfaceFlags::NO_LIGHTMAP));
+    assert_eq!(texture.content_flags, ContentFlags::SOLID);
+    assert!(!texture.is_drawn());
+  }
+
+  #[test]
+  fn read_regular() {
+    let data = texture_record("textures/base_wall/concrete", 0, 0x1);
+    let texture = Texture::read(&mut Cursor::new(data), 0x2e).unwrap();
+    assert_eq!(texture.surface_flags, SurfaceFlags::empty());
+    assert!(texture.is_drawn());
+  }
+}
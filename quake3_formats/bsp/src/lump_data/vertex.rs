@@ -6,6 +6,7 @@ use crate::PrimitiveRead;
 #[derive(Clone, Debug)]
 pub struct Vertex {
   pub position: Vector3<f32>,
+  /// The surface UVs followed by the lightmap UVs.
   pub tex_coord: [Vector2<f32>; 2],
   pub normal: Vector3<f32>,
   pub color: [u8; 4],
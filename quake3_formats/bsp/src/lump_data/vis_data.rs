@@ -0,0 +1,107 @@
+use std::io::{Read, Result as IOResult, Error as IOError, ErrorKind};
+use crate::PrimitiveRead;
+
+/// The potentially visible set of every cluster, stored as an uncompressed bit array per cluster.
+pub struct VisData {
+  pub vecs_count: i32,
+  pub vec_size: i32,
+  pub vecs: Box<[u8]>
+}
+
+impl VisData {
+  pub fn read(reader: &mut dyn Read, length: u32) -> IOResult<Self> {
+    if length == 0 {
+      return Ok(Self {
+        vecs_count: 0,
+        vec_size: 0,
+        vecs: Box::new([])
+      });
+    }
+
+    let vecs_count = reader.read_i32()?;
+    let vec_size = reader.read_i32()?;
+    if vecs_count < 0 || vec_size < 0 || vecs_count as u64 * vec_size as u64 + 8 > length as u64 {
+      return Err(IOError::new(ErrorKind::InvalidData, "Vis data is larger than the lump."));
+    }
+    let mut vecs = vec![0u8; vecs_count as usize * vec_size as usize];
+    reader.read_exact(&mut vecs)?;
+    Ok(Self {
+      vecs_count,
+      vec_size,
+      vecs: vecs.into_boxed_slice()
+    })
+  }
+
+  /// Checks whether the cluster `to` can potentially be seen from the cluster `from`.
+  /// Maps without vis data consider everything visible, the same goes for a `from` cluster outside of the map.
+  /// Leafs with a negative cluster are never visible.
+  pub fn cluster_visible(&self, from: i32, to: i32) -> bool {
+    if to < 0 {
+      return false;
+    }
+    if from < 0 || self.vecs.is_empty() || from >= self.vecs_count {
+      return true;
+    }
+    let byte = to as usize >> 3;
+    if byte >= self.vec_size as usize {
+      return false;
+    }
+    self.vecs[from as usize * self.vec_size as usize + byte] & (1 << (to & 7)) != 0
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Builds a lump with one uncompressed PVS per cluster.
+  fn build_lump(vec_size: i32, pvs: &[&[u8]]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&(pvs.len() as i32).to_le_bytes());
+    data.extend_from_slice(&vec_size.to_le_bytes());
+    for cluster_pvs in pvs {
+      assert_eq!(cluster_pvs.len(), vec_size as usize);
+      data.extend_from_slice(cluster_pvs);
+    }
+    data
+  }
+
+  #[test]
+  fn two_clusters() {
+    // Cluster 0 only sees itself, cluster 1 sees both.
+    let data = build_lump(1, &[&[0b01], &[0b11]]);
+    let vis_data = VisData::read(&mut &data[..], data.len() as u32).unwrap();
+    assert_eq!(vis_data.vecs_count, 2);
+    assert!(vis_data.cluster_visible(0, 0));
+    assert!(!vis_data.cluster_visible(0, 1));
+    assert!(vis_data.cluster_visible(1, 0));
+    assert!(vis_data.cluster_visible(1, 1));
+    assert!(!vis_data.cluster_visible(0, -1));
+    assert!(vis_data.cluster_visible(-1, 1));
+    assert!(vis_data.cluster_visible(2, 0));
+    assert!(!vis_data.cluster_visible(0, 8));
+  }
+
+  #[test]
+  fn multiple_bytes_per_cluster() {
+    // Cluster 0 sees cluster 9, cluster 1 sees cluster 0 and 15.
+    let data = build_lump(2, &[&[0, 0b10], &[0b1, 0b1000_0000]]);
+    let vis_data = VisData::read(&mut &data[..], data.len() as u32).unwrap();
+    let visible = |from: i32| -> Vec<i32> { (0..16).filter(|to| vis_data.cluster_visible(from, *to)).collect() };
+    assert_eq!(visible(0), vec![9]);
+    assert_eq!(visible(1), vec![0, 15]);
+  }
+
+  #[test]
+  fn no_vis_data() {
+    let vis_data = VisData::read(&mut &[0u8; 0][..], 0).unwrap();
+    assert!(vis_data.cluster_visible(0, 7));
+    assert!(!vis_data.cluster_visible(0, -1));
+  }
+
+  #[test]
+  fn vis_data_larger_than_lump() {
+    let data = build_lump(2, &[&[0, 0], &[0, 0]]);
+    assert!(VisData::read(&mut &data[..], data.len() as u32 - 1).is_err());
+  }
+}
@@ -0,0 +1,83 @@
+use std::io::{Seek, SeekFrom, Read, Result as IOResult};
+use crate::map_header::MapHeader;
+use crate::lump_data::{LumpData, LumpType, BrushModel, Face, Vertex, Lightmap, Leaf, VisData, Texture};
+
+pub struct Map<R: Read + Seek> {
+  pub name: String,
+  header: MapHeader,
+  reader: R,
+}
+
+impl<R: Read + Seek> Map<R> {
+  pub fn read(name: &str, mut reader: R) -> IOResult<Map<R>> {
+    reader.seek(SeekFrom::Start(0))?;
+    let header = MapHeader::read(&mut reader)?;
+    Ok(Map {
+      name: name.to_owned(),
+      header,
+      reader,
+    })
+  }
+
+  pub fn header(&self) -> &MapHeader {
+    &self.header
+  }
+
+  pub fn read_brush_models(&mut self) -> IOResult<Vec<BrushModel>> {
+    self.read_lump_data()
+  }
+
+  pub fn read_textures(&mut self) -> IOResult<Vec<Texture>> {
+    self.read_lump_data()
+  }
+
+  pub fn read_faces(&mut self) -> IOResult<Vec<Face>> {
+    self.read_lump_data()
+  }
+
+  pub fn read_vertices(&mut self) -> IOResult<Vec<Vertex>> {
+    self.read_lump_data()
+  }
+
+  pub fn read_lightmaps(&mut self) -> IOResult<Vec<Lightmap>> {
+    self.read_lump_data()
+  }
+
+  pub fn read_leafs(&mut self) -> IOResult<Vec<Leaf>> {
+    self.read_lump_data()
+  }
+
+  pub fn read_vis_data(&mut self) -> IOResult<VisData> {
+    let lump = &self.header.lumps[LumpType::VisData as usize];
+    self.reader.seek(SeekFrom::Start(lump.file_offset as u64))?;
+    VisData::read(&mut self.reader, lump.file_length.max(0) as u32)
+  }
+
+  /// Returns the visibility cluster of the leaf, negative if the leaf doesn't belong to one.
+  /// Read the leafs directly when doing many queries to avoid reading the lump every time.
+  pub fn leaf_cluster(&mut self, leaf_index: i32) -> IOResult<i32> {
+    let leafs = self.read_leafs()?;
+    Ok(leafs.get(leaf_index.max(0) as usize).filter(|_| leaf_index >= 0).map_or(-1, |leaf| leaf.cluster))
+  }
+
+  /// Checks the PVS to determine whether the cluster `to` can potentially be seen from the cluster `from`.
+  /// Use [`VisData::cluster_visible`] directly when doing many queries to avoid reading the lump every time.
+  pub fn cluster_visible(&mut self, from_cluster: i32, to_cluster: i32) -> IOResult<bool> {
+    let vis_data = self.read_vis_data()?;
+    Ok(vis_data.cluster_visible(from_cluster, to_cluster))
+  }
+
+  fn read_lump_data<T: LumpData>(&mut self) -> IOResult<Vec<T>> {
+    let index = T::lump_type() as usize;
+    let lump = &self.header.lumps[index];
+    self.reader.seek(SeekFrom::Start(lump.file_offset as u64))?;
+
+    let element_count = lump.file_length / T::element_size(self.header.version) as i32;
+    let mut elements: Vec<T> = Vec::with_capacity(element_count.max(0) as usize);
+    for _ in 0..element_count {
+      let element = T::read(&mut self.reader, self.header.version)?;
+      elements.push(element);
+    }
+    Ok(elements)
+  }
+}
@@ -0,0 +1,147 @@
+use nalgebra::{Vector2, Vector3};
+
+use crate::lump_data::Vertex;
+
+/// Tessellates the control point grid of a patch face.
+/// The grid consists of 3x3 biquadratic bezier patches that share their edge control points,
+/// every patch gets subdivided into `level` x `level` quads. Shared edges use the same vertices.
+/// The triangles use the same clockwise winding as the mesh vertices of regular faces.
+pub(crate) fn tessellate_patch(control_points: &[Vertex], width: usize, height: usize, level: u32) -> (Vec<Vertex>, Vec<u32>) {
+  if width < 3 || height < 3 || width & 1 == 0 || height & 1 == 0 || control_points.len() < width * height {
+    return (Vec::new(), Vec::new());
+  }
+  let level = level.max(1) as usize;
+  let patches_x = (width - 1) / 2;
+  let patches_y = (height - 1) / 2;
+  let grid_width = patches_x * level + 1;
+  let grid_height = patches_y * level + 1;
+
+  let mut vertices = Vec::<Vertex>::with_capacity(grid_width * grid_height);
+  for grid_y in 0..grid_height {
+    // The last row belongs to the last patch, not to one after it
+    let patch_y = (grid_y / level).min(patches_y - 1);
+    let t_y = (grid_y - patch_y * level) as f32 / level as f32;
+    for grid_x in 0..grid_width {
+      let patch_x = (grid_x / level).min(patches_x - 1);
+      let t_x = (grid_x - patch_x * level) as f32 / level as f32;
+      let first_control_point = patch_y * 2 * width + patch_x * 2;
+      vertices.push(evaluate_patch(control_points, width, first_control_point, t_x, t_y));
+    }
+  }
+
+  let mut indices = Vec::<u32>::with_capacity((grid_width - 1) * (grid_height - 1) * 6);
+  for y in 0..grid_height - 1 {
+    for x in 0..grid_width - 1 {
+      let a = (y * grid_width + x) as u32;
+      let b = a + 1;
+      let c = a + grid_width as u32;
+      let d = c + 1;
+      indices.extend_from_slice(&[a, c, b, b, c, d]);
+    }
+  }
+
+  // The orientation of the control point grid isn't fixed, so compare the triangles against the vertex normals.
+  let mut orientation = 0f32;
+  for triangle in indices.chunks_exact(3) {
+    let (a, b, c) = (&vertices[triangle[0] as usize], &vertices[triangle[1] as usize], &vertices[triangle[2] as usize]);
+    let triangle_normal = (b.position - a.position).cross(&(c.position - a.position));
+    orientation += triangle_normal.dot(&(a.normal + b.normal + c.normal));
+  }
+  // Clockwise triangles have a normal pointing away from the vertex normals.
+  if orientation > 0f32 {
+    for triangle in indices.chunks_exact_mut(3) {
+      triangle.swap(1, 2);
+    }
+  }
+
+  (vertices, indices)
+}
+
+fn evaluate_patch(control_points: &[Vertex], width: usize, first_control_point: usize, t_x: f32, t_y: f32) -> Vertex {
+  let weights_x = quadratic_bezier_weights(t_x);
+  let weights_y = quadratic_bezier_weights(t_y);
+
+  let mut position = Vector3::<f32>::zeros();
+  let mut tex_coord = [Vector2::<f32>::zeros(); 2];
+  let mut normal = Vector3::<f32>::zeros();
+  let mut color = [0f32; 4];
+  for (row, weight_y) in weights_y.iter().enumerate() {
+    for (column, weight_x) in weights_x.iter().enumerate() {
+      let control_point = &control_points[first_control_point + row * width + column];
+      let weight = weight_x * weight_y;
+      position += control_point.position * weight;
+      tex_coord[0] += control_point.tex_coord[0] * weight;
+      tex_coord[1] += control_point.tex_coord[1] * weight;
+      normal += control_point.normal * weight;
+      for (channel, control_point_channel) in color.iter_mut().zip(control_point.color.iter()) {
+        *channel += *control_point_channel as f32 * weight;
+      }
+    }
+  }
+
+  Vertex {
+    position,
+    tex_coord,
+    normal: normal.try_normalize(f32::EPSILON).unwrap_or(normal),
+    color: [
+      color[0].round().clamp(0f32, 255f32) as u8,
+      color[1].round().clamp(0f32, 255f32) as u8,
+      color[2].round().clamp(0f32, 255f32) as u8,
+      color[3].round().clamp(0f32, 255f32) as u8
+    ]
+  }
+}
+
+fn quadratic_bezier_weights(t: f32) -> [f32; 3] {
+  let inverse_t = 1f32 - t;
+  [inverse_t * inverse_t, 2f32 * t * inverse_t, t * t]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn flat_grid(width: usize, height: usize) -> Vec<Vertex> {
+    let mut control_points = Vec::with_capacity(width * height);
+    for y in 0..height {
+      for x in 0..width {
+        control_points.push(Vertex {
+          position: Vector3::new(x as f32, y as f32, 0f32),
+          tex_coord: [Vector2::zeros(); 2],
+          normal: Vector3::new(0f32, 0f32, 1f32),
+          color: [255; 4]
+        });
+      }
+    }
+    control_points
+  }
+
+  #[test]
+  fn tessellate_3x3() {
+    let control_points = flat_grid(3, 3);
+    for level in 1..6u32 {
+      let (vertices, indices) = tessellate_patch(&control_points, 3, 3, level);
+      assert_eq!(vertices.len(), ((level + 1) * (level + 1)) as usize);
+      assert_eq!(indices.len(), (level * level * 6) as usize);
+      assert!(indices.iter().all(|index| (*index as usize) < vertices.len()));
+      assert_eq!(vertices.first().unwrap().position, control_points.first().unwrap().position);
+      assert_eq!(vertices.last().unwrap().position, control_points.last().unwrap().position);
+    }
+  }
+
+  #[test]
+  fn shared_edges() {
+    let control_points = flat_grid(5, 3);
+    let level = 4;
+    let (vertices, _) = tessellate_patch(&control_points, 5, 3, level);
+    assert_eq!(vertices.len(), ((2 * level + 1) * (level + 1)) as usize);
+  }
+
+  #[test]
+  fn invalid_grid() {
+    let control_points = flat_grid(4, 3);
+    let (vertices, indices) = tessellate_patch(&control_points, 4, 3, 4);
+    assert!(vertices.is_empty());
+    assert!(indices.is_empty());
+  }
+}
@@ -0,0 +1,85 @@
+use bevy_math::Vec3;
+
+use crate::lump_data::{Brush, BrushContents, BrushSide, Plane};
+
+const PLANE_EPSILON: f32 = 0.01f32;
+const VERTEX_MERGE_EPSILON: f32 = 0.01f32;
+
+/// A convex volume built from the bounding planes of a brush.
+#[derive(Clone, Debug)]
+pub struct ConvexHull {
+  pub brush_index: usize,
+  pub vertices: Vec<Vec3>,
+  pub contents: BrushContents,
+}
+
+impl ConvexHull {
+  /// Whether the brush should block movement.
+  /// Triggers, water and other volumes only get tagged with their contents.
+  pub fn is_solid(&self) -> bool {
+    self.contents.intersects(BrushContents::SOLID | BrushContents::WINDOW | BrushContents::GRATE | BrushContents::PLAYER_CLIP | BrushContents::MONSTER_CLIP)
+  }
+
+  pub(crate) fn from_brush(brush_index: usize, brush: &Brush, brush_sides: &[BrushSide], planes: &[Plane]) -> Option<ConvexHull> {
+    if brush.sides_count < 4 || brush.first_side < 0 {
+      return None;
+    }
+    let first_side = brush.first_side as usize;
+    let sides_end = first_side + brush.sides_count as usize;
+    if sides_end > brush_sides.len() {
+      return None;
+    }
+
+    let brush_planes: Vec<&Plane> = brush_sides[first_side..sides_end]
+      .iter()
+      .filter_map(|side| planes.get(side.plane_number as usize))
+      .collect();
+
+    // Every vertex of the brush lies on the intersection of three of its planes.
+    let mut vertices = Vec::<Vec3>::new();
+    for i in 0..brush_planes.len() {
+      for j in (i + 1)..brush_planes.len() {
+        for k in (j + 1)..brush_planes.len() {
+          let point = intersect_planes(brush_planes[i], brush_planes[j], brush_planes[k]);
+          if point.is_none() {
+            continue;
+          }
+          let point = point.unwrap();
+
+          // Planes face outwards, so points inside the brush are behind all of them.
+          let is_inside = brush_planes.iter().all(|plane| plane.normal.dot(point) - plane.dist <= PLANE_EPSILON);
+          if !is_inside {
+            continue;
+          }
+
+          let is_duplicate = vertices.iter().any(|vertex| vertex.distance_squared(point) < VERTEX_MERGE_EPSILON * VERTEX_MERGE_EPSILON);
+          if !is_duplicate {
+            vertices.push(point);
+          }
+        }
+      }
+    }
+
+    if vertices.len() < 4 {
+      return None;
+    }
+
+    Some(ConvexHull {
+      brush_index,
+      vertices,
+      contents: brush.contents,
+    })
+  }
+}
+
+fn intersect_planes(p1: &Plane, p2: &Plane, p3: &Plane) -> Option<Vec3> {
+  let n2_x_n3 = p2.normal.cross(p3.normal);
+  let denominator = p1.normal.dot(n2_x_n3);
+  if denominator.abs() < 1e-6f32 {
+    return None;
+  }
+  let point = (p1.dist * n2_x_n3
+    + p2.dist * p3.normal.cross(p1.normal)
+    + p3.dist * p1.normal.cross(p2.normal)) / denominator;
+  Some(point)
+}
@@ -0,0 +1,185 @@
+use std::io::{Result as IOResult, Error as IOError, ErrorKind};
+
+use bevy_math::Vec3;
+
+use crate::lump_data::{DispInfo, DispVert, Edge, Face, SurfaceEdge, Vertex};
+
+/// Builds the fully tessellated mesh of a displacement.
+/// Vertex `i` of the result belongs to the `DispVert` at `disp_info.disp_vert_start + i`,
+/// so the blend alpha can be looked up there.
+///
+/// VBSP already snaps the edges of neighboring displacements together,
+/// so the neighbor data only matters for lower tessellation levels and isn't used here.
+pub(crate) fn build_displacement(disp_info: &DispInfo, faces: &[Face], surface_edges: &[SurfaceEdge], edges: &[Edge], vertices: &[Vertex], disp_verts: &[DispVert]) -> IOResult<(Vec<Vertex>, Vec<u32>)> {
+  let face = faces.get(disp_info.map_face as usize)
+    .ok_or_else(|| IOError::new(ErrorKind::InvalidData, "Displacement references an invalid face."))?;
+  if face.edges_count != 4 {
+    return Err(IOError::new(ErrorKind::InvalidData, format!("Displacement face has {} edges, expected 4.", face.edges_count)));
+  }
+
+  let mut corners = [Vec3::ZERO; 4];
+  for (i, corner) in corners.iter_mut().enumerate() {
+    let surface_edge = surface_edges.get(face.first_edge as usize + i)
+      .ok_or_else(|| IOError::new(ErrorKind::InvalidData, "Displacement face references an invalid surface edge."))?;
+    let edge = edges.get(surface_edge.index.unsigned_abs() as usize)
+      .ok_or_else(|| IOError::new(ErrorKind::InvalidData, "Displacement face references an invalid edge."))?;
+    // Negative surface edges use the edge in reverse
+    let vertex_index = if surface_edge.index >= 0 { edge.vertex_index[0] } else { edge.vertex_index[1] };
+    *corner = vertices.get(vertex_index as usize)
+      .ok_or_else(|| IOError::new(ErrorKind::InvalidData, "Displacement face references an invalid vertex."))?
+      .position;
+  }
+
+  // The face winding is used for the triangles, so compute the normal before reordering the corners.
+  let face_normal = (corners[1] - corners[0]).cross(corners[2] - corners[0]);
+
+  // The grid starts at the corner closest to the start position, the other corners follow in winding order.
+  let start_corner = corners.iter()
+    .enumerate()
+    .min_by(|(_, a), (_, b)| a.distance_squared(disp_info.start_position).total_cmp(&b.distance_squared(disp_info.start_position)))
+    .map(|(index, _)| index)
+    .unwrap();
+  corners.rotate_left(start_corner);
+
+  let size = (1usize << disp_info.power) + 1;
+  let disp_vert_start = disp_info.disp_vert_start.max(0) as usize;
+  let disp_verts = disp_verts.get(disp_vert_start .. disp_vert_start + size * size)
+    .ok_or_else(|| IOError::new(ErrorKind::InvalidData, "Displacement references invalid displacement vertices."))?;
+
+  let mut base_positions = Vec::<Vec3>::with_capacity(size * size);
+  let mut mesh_vertices = Vec::<Vertex>::with_capacity(size * size);
+  for y in 0..size {
+    let t_y = y as f32 / (size - 1) as f32;
+    let left = corners[0].lerp(corners[1], t_y);
+    let right = corners[3].lerp(corners[2], t_y);
+    for x in 0..size {
+      let t_x = x as f32 / (size - 1) as f32;
+      let base_position = left.lerp(right, t_x);
+      let disp_vert = &disp_verts[y * size + x];
+      base_positions.push(base_position);
+      mesh_vertices.push(Vertex {
+        position: base_position + disp_vert.vec * disp_vert.dist
+      });
+    }
+  }
+
+  let mut indices = Vec::<u32>::with_capacity((size - 1) * (size - 1) * 6);
+  for y in 0..size - 1 {
+    for x in 0..size - 1 {
+      let a = (y * size + x) as u32;
+      let b = a + 1;
+      let c = a + size as u32;
+      let d = c + 1;
+      // The diagonal alternates like a checkerboard, the same way the engine tessellates displacements.
+      if (x + y) % 2 == 0 {
+        indices.extend_from_slice(&[a, c, d, a, d, b]);
+      } else {
+        indices.extend_from_slice(&[a, c, b, b, c, d]);
+      }
+    }
+  }
+
+  // Depending on the start corner the grid may be mirrored compared to the face, flip the triangles to match its winding.
+  let (a, b, c) = (indices[0] as usize, indices[1] as usize, indices[2] as usize);
+  let grid_normal = (base_positions[b] - base_positions[a]).cross(base_positions[c] - base_positions[a]);
+  if grid_normal.dot(face_normal) < 0f32 {
+    for triangle in indices.chunks_exact_mut(3) {
+      triangle.swap(1, 2);
+    }
+  }
+
+  Ok((mesh_vertices, indices))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::lump_data::LumpData;
+
+  fn disp_info(start_position: Vec3, power: i32) -> DispInfo {
+    let mut data = vec![0u8; DispInfo::element_size(0)];
+    data[0..4].copy_from_slice(&start_position.x.to_le_bytes());
+    data[4..8].copy_from_slice(&start_position.y.to_le_bytes());
+    data[8..12].copy_from_slice(&start_position.z.to_le_bytes());
+    data[20..24].copy_from_slice(&power.to_le_bytes());
+    DispInfo::read(&mut &data[..], 0).unwrap()
+  }
+
+  fn quad_face() -> Face {
+    Face {
+      plane_index: 0,
+      size: 0,
+      is_on_node: false,
+      first_edge: 0,
+      edges_count: 4,
+      texture_info: 0,
+      displacement_info: 0,
+      surface_fog_volume_id: -1,
+      styles: [0; 4],
+      light_offset: -1,
+      area: 0f32,
+      lightmap_texture_mins_in_luxels: [0; 2],
+      lightmap_texture_size_in_luxels: [0; 2],
+      original_face: -1,
+      primitives_count: 0,
+      first_primitive_id: 0,
+      smoothing_group: 0
+    }
+  }
+
+  #[test]
+  fn flat_displacement_matches_base_quad() {
+    let vertices = [
+      Vertex { position: Vec3::new(0f32, 0f32, 0f32) },
+      Vertex { position: Vec3::new(0f32, 64f32, 0f32) },
+      Vertex { position: Vec3::new(64f32, 64f32, 0f32) },
+      Vertex { position: Vec3::new(64f32, 0f32, 0f32) },
+    ];
+    let edges: Vec<Edge> = (0..4u16).map(|i| Edge { vertex_index: [i, (i + 1) % 4] }).collect();
+    let surface_edges: Vec<SurfaceEdge> = (0..4).map(|i| SurfaceEdge { index: i }).collect();
+    let disp_verts: Vec<DispVert> = (0..25).map(|_| DispVert { vec: Vec3::Z, dist: 0f32, alpha: 0f32 }).collect();
+    let face_normal = (vertices[1].position - vertices[0].position).cross(vertices[2].position - vertices[0].position);
+
+    for start_position in vertices.iter().map(|vertex| vertex.position) {
+      let (mesh_vertices, indices) = build_displacement(&disp_info(start_position, 2), &[quad_face()], &surface_edges, &edges, &vertices, &disp_verts).unwrap();
+      assert_eq!(mesh_vertices.len(), 25);
+      assert_eq!(indices.len(), 4 * 4 * 6);
+      assert_eq!(mesh_vertices[0].position, start_position);
+
+      // Every vertex lies on the 16 unit grid that subdivides the base quad.
+      for vertex in &mesh_vertices {
+        let position = vertex.position;
+        assert_eq!(position.z, 0f32);
+        assert!((0f32..=64f32).contains(&position.x) && (0f32..=64f32).contains(&position.y));
+        assert_eq!(position.x % 16f32, 0f32);
+        assert_eq!(position.y % 16f32, 0f32);
+      }
+      for x in 0..5 {
+        for y in 0..5 {
+          let position = Vec3::new(x as f32 * 16f32, y as f32 * 16f32, 0f32);
+          assert!(mesh_vertices.iter().any(|vertex| vertex.position == position));
+        }
+      }
+
+      // The triangles cover the quad exactly once and keep the winding of the face.
+      let mut area = 0f32;
+      for triangle in indices.chunks_exact(3) {
+        let a = mesh_vertices[triangle[0] as usize].position;
+        let b = mesh_vertices[triangle[1] as usize].position;
+        let c = mesh_vertices[triangle[2] as usize].position;
+        let normal = (b - a).cross(c - a);
+        assert!(normal.dot(face_normal) > 0f32);
+        area += normal.length() / 2f32;
+      }
+      assert_eq!(area, 64f32 * 64f32);
+    }
+  }
+
+  #[test]
+  fn displacement_needs_quad() {
+    let mut face = quad_face();
+    face.edges_count = 3;
+    let result = build_displacement(&disp_info(Vec3::ZERO, 2), &[face], &[], &[], &[], &[]);
+    assert_eq!(result.err().map(|e| e.kind()), Some(ErrorKind::InvalidData));
+  }
+}
@@ -9,10 +9,13 @@ pub use self::lump::Lump;
 pub use self::map_header::MapHeader;
 pub use self::map::Map;
 pub use self::lump_data::*;
+pub use self::convex_hull::ConvexHull;
 
 mod lump;
 mod lump_data;
 mod map_header;
 mod map;
+mod convex_hull;
+mod displacement;
 
 pub(crate) use io_util::*;
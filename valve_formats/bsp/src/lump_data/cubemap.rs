@@ -0,0 +1,76 @@
+use std::io::{Read, Result as IOResult};
+use bevy_math::Vec3;
+use crate::lump_data::{LumpData, LumpType};
+use crate::PrimitiveRead;
+
+/// The resolution Source uses for cubemaps that don't specify a size.
+const DEFAULT_CUBEMAP_RESOLUTION: u32 = 32;
+
+/// An env_cubemap placed by the map author.
+#[derive(Clone, Debug)]
+pub struct Cubemap {
+  pub origin: Vec3,
+  /// 0 means the default size, otherwise the resolution is 1 << (size - 1).
+  pub size: i32
+}
+
+impl Cubemap {
+  /// The edge length of a cubemap face in pixels.
+  pub fn resolution(&self) -> u32 {
+    if self.size <= 0 {
+      DEFAULT_CUBEMAP_RESOLUTION
+    } else {
+      1u32 << (self.size - 1).min(31)
+    }
+  }
+}
+
+impl LumpData for Cubemap {
+  fn lump_type() -> LumpType {
+    LumpType::Cubemaps
+  }
+  fn lump_type_hdr() -> Option<LumpType> {
+    None
+  }
+
+  fn element_size(_version: i32) -> usize {
+    16
+  }
+
+  fn read(reader: &mut dyn Read, _version: i32) -> IOResult<Self> {
+    // The origin is stored as integers.
+    let origin = Vec3::new(reader.read_i32()? as f32, reader.read_i32()? as f32, reader.read_i32()? as f32);
+    let size = reader.read_i32()?;
+    Ok(Self {
+      origin,
+      size
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn two_cubemaps() {
+    let mut data = Vec::new();
+    for value in [16i32, -32, 64, 0, -128, 256, 8, 7] {
+      data.extend_from_slice(&value.to_le_bytes());
+    }
+    assert_eq!(data.len(), Cubemap::element_size(0) * 2);
+
+    let mut reader = &data[..];
+    let first = Cubemap::read(&mut reader, 0).unwrap();
+    let second = Cubemap::read(&mut reader, 0).unwrap();
+    assert!(reader.is_empty());
+
+    assert_eq!(first.origin, Vec3::new(16f32, -32f32, 64f32));
+    assert_eq!(first.size, 0);
+    assert_eq!(first.resolution(), DEFAULT_CUBEMAP_RESOLUTION);
+
+    assert_eq!(second.origin, Vec3::new(-128f32, 256f32, 8f32));
+    assert_eq!(second.size, 7);
+    assert_eq!(second.resolution(), 64);
+  }
+}
@@ -1,15 +1,22 @@
 use std::collections::HashMap;
-use std::io::{Read, Result as IOResult};
-use crate::StringRead;
+use std::io::{Read, Result as IOResult, Error as IOError, ErrorKind};
+use bevy_math::Vec3;
+use log::warn;
+use crate::{StringRead, StringReadError};
 
 pub struct Entities {
   pub entities: Vec<Entity>
 }
 
 impl Entities {
+  /// Parses the entity lump.
+  /// Malformed entity blocks are skipped with a warning instead of failing the whole lump.
   pub fn read(read: &mut dyn Read) -> IOResult<Entities> {
     let mut entities = Vec::<Entity>::new();
-    let text = read.read_null_terminated_string().unwrap();
+    let text = read.read_null_terminated_string().map_err(|e| match e {
+      StringReadError::IOError(e) => e,
+      StringReadError::StringConstructionError(e) => IOError::new(ErrorKind::InvalidData, e)
+    })?;
 
     let mut remaining_text = text.as_str();
     loop {
@@ -19,10 +26,27 @@ impl Entities {
       }
       let block_begin = block_begin.unwrap();
       remaining_text = &remaining_text[block_begin + 1..];
-      let block_end = remaining_text.find('}').expect("Unfinished block");
+      let block_end = find_block_end(remaining_text);
+      if block_end.is_none() {
+        warn!("Skipping unfinished entity block at the end of the entity lump.");
+        break;
+      }
+      let block_end = block_end.unwrap();
       let block = &remaining_text[..block_end];
+      remaining_text = &remaining_text[block_end + 1..];
+
+      let key_values = parse_key_value(block, true);
+      if key_values.is_none() {
+        warn!("Skipping malformed entity block: {}", block.trim());
+        continue;
+      }
+      let key_values = key_values.unwrap();
+      if !key_values.contains_key("classname") {
+        warn!("Skipping entity without a classname: {}", block.trim());
+        continue;
+      }
       entities.push(Entity {
-        key_values: parse_key_value(block, true)
+        key_values
       });
     }
 
@@ -30,6 +54,10 @@ impl Entities {
       entities
     })
   }
+
+  pub fn iter(&self) -> impl Iterator<Item = &Entity> {
+    self.entities.iter()
+  }
 }
 
 pub struct Entity {
@@ -42,8 +70,30 @@ impl Entity {
     self.key_values.get(&lower_key).map(|s| s.as_str())
   }
 
+  /// Parses a value that consists of three numbers separated by spaces, like an origin ("0 64 128").
+  pub fn get_vec3(&self, key: &str) -> Option<Vec3> {
+    let mut components = self.get(key)?.split_whitespace().map(|component| component.parse::<f32>());
+    let x = components.next()?.ok()?;
+    let y = components.next()?.ok()?;
+    let z = components.next()?.ok()?;
+    Some(Vec3::new(x, y, z))
+  }
+
+  pub fn get_f32(&self, key: &str) -> Option<f32> {
+    self.get(key)?.trim().parse::<f32>().ok()
+  }
+
+  pub fn key_values(&self) -> &HashMap<String, String> {
+    &self.key_values
+  }
+
+  pub fn classname(&self) -> &str {
+    // Entities without a classname are skipped when parsing
+    self.key_values.get("classname").unwrap().as_str()
+  }
+
   pub fn class_name(&self) -> EntityClass {
-    let class_name = self.key_values.get("classname").unwrap().as_str();
+    let class_name = self.classname();
     match class_name {
       "prop_detail" => EntityClass::PropDetail,
       "prop_static" => EntityClass::PropStatic,
@@ -58,14 +108,40 @@ impl Entity {
   }
 }
 
-pub fn parse_key_value(text: &str, turn_keys_lower_case: bool) -> HashMap<String, String> {
+/// Finds the closing brace of a block, braces inside of quoted values are ignored.
+fn find_block_end(text: &str) -> Option<usize> {
+  let mut is_in_quotes = false;
+  for (index, character) in text.char_indices() {
+    match character {
+      '"' => is_in_quotes = !is_in_quotes,
+      '}' if !is_in_quotes => return Some(index),
+      _ => {}
+    }
+  }
+  None
+}
+
+/// Parses the quoted key value pairs of a block.
+/// Returns None if the block contains anything else or a key is missing its value.
+pub fn parse_key_value(text: &str, turn_keys_lower_case: bool) -> Option<HashMap<String, String>> {
   let mut data = HashMap::<String, String>::new();
-  let text = text.replace("\r\n", "\n");
-  let lines = text.trim().split('\n');
-  for line in lines {
-    let space_pos = line.find(' ').unwrap();
-    let key = (&line[..space_pos]).trim().trim_matches('\"').trim();
-    let value = (&line[space_pos + 1..]).trim().trim_matches('\"').trim();
+  let mut tokens = Vec::<&str>::new();
+  let mut remaining_text = text.trim_start();
+  while !remaining_text.is_empty() {
+    if !remaining_text.starts_with('"') {
+      return None;
+    }
+    let token_end = remaining_text[1..].find('"')? + 1;
+    tokens.push(&remaining_text[1..token_end]);
+    remaining_text = remaining_text[token_end + 1..].trim_start();
+  }
+  if tokens.len() % 2 != 0 {
+    return None;
+  }
+
+  for key_value in tokens.chunks_exact(2) {
+    let key = key_value[0].trim();
+    let value = key_value[1].trim();
     let owned_key = if turn_keys_lower_case {
       key.to_lowercase()
     } else {
@@ -73,7 +149,7 @@ pub fn parse_key_value(text: &str, turn_keys_lower_case: bool) -> HashMap<String
     };
     data.insert(owned_key, value.to_string());
   }
-  data
+  Some(data)
 }
 
 #[derive(Eq, PartialEq, Hash, Debug)]
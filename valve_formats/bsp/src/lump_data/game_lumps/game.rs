@@ -1,6 +1,6 @@
 use std::io::{Read, Result as IOResult, Seek, SeekFrom, Cursor, Error as IOError, ErrorKind};
 use crate::io_util::PrimitiveRead;
-use crate::lump_data::game_lumps::StaticPropDict;
+use crate::lump_data::game_lumps::{StaticPropDict, StaticProp};
 
 pub struct GameLumps {
   game_lumps: Box<[GameLump]>
@@ -36,6 +36,11 @@ impl GameLumps {
 
     Err(IOError::new(ErrorKind::Other, "Game lump not found"))
   }
+
+  /// Reads the props that are placed with prop_static, their model names are resolved through the model dictionary.
+  pub fn static_props<R: Read + Seek>(&self, read: &mut R) -> IOResult<Vec<StaticProp>> {
+    Ok(self.read_static_prop_dict(read)?.props.into_vec())
+  }
 }
 
 pub struct GameLump {
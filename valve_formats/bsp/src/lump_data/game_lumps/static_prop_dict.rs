@@ -16,6 +16,10 @@ impl StaticPropDict {
   }
 
   pub fn read(read: &mut dyn Read, version: u16) -> IOResult<Self> {
+    if !(4..=11).contains(&version) {
+      return Err(IOError::new(ErrorKind::Unsupported, format!("Unsupported static prop lump version: {}", version)));
+    }
+
     let dict_entries = read.read_i32()?;
     let mut names = Vec::<String>::with_capacity(dict_entries as usize);
     for _ in 0..dict_entries {
@@ -32,7 +36,11 @@ impl StaticPropDict {
     let prop_count = read.read_i32()?;
     let mut props = Vec::<StaticProp>::with_capacity(prop_count as usize);
     for _ in 0..prop_count {
-      props.push(StaticProp::read(read, version)?);
+      let mut prop = StaticProp::read(read, version)?;
+      prop.model_name = names.get(prop.prop_type as usize)
+        .ok_or_else(|| IOError::new(ErrorKind::InvalidData, format!("Static prop references invalid model {}.", prop.prop_type)))?
+        .clone();
+      props.push(prop);
     }
 
     Ok(Self {
@@ -58,6 +66,8 @@ bitflags! {
 }
 
 pub struct StaticProp {
+  /// The path of the model, resolved through the model name dictionary.
+  pub model_name: String,
   pub origin: Vec3,
   pub angles: Vec3,
 
@@ -84,6 +94,9 @@ pub struct StaticProp {
 }
 
 impl StaticProp {
+  /// Reads a single prop, the struct grew with almost every version:
+  /// v4: 56 bytes, v5: + forced fade scale, v6: + DX levels, v7: + diffuse modulation,
+  /// v8: CPU and GPU levels replace the DX levels, v9: + X360 flag, v10: + extended flags, v11: + uniform scale
   pub fn read(read: &mut dyn Read, version: u16) -> IOResult<Self> {
     let origin = Vec3::new(read.read_f32()?, read.read_f32()?, read.read_f32()?);
     let angles = Vec3::new(read.read_f32()?, read.read_f32()?, read.read_f32()?);
@@ -122,12 +135,13 @@ impl StaticProp {
       if version >= 7 {
         diffuse_modulation = ColorRGBExp32::read(read)?;
       }
-
-      disable_x360 = read.read_u8()? != 0;
-      for _ in 0..3 {
-        read.read_u8()?;
+      if version >= 9 {
+        // Padded to 4 bytes
+        disable_x360 = read.read_u8()? != 0;
+        for _ in 0..3 {
+          read.read_u8()?;
+        }
       }
-
       if version >= 10 {
         flags_ex = read.read_u32()?;
       }
@@ -138,6 +152,7 @@ impl StaticProp {
     }
 
     Ok(Self {
+      model_name: String::new(),
       origin,
       angles,
       prop_type,
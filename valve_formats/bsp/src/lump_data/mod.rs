@@ -2,6 +2,7 @@ use std::io::{Read, Result as IOResult};
 
 pub use crate::lump_data::brush_model::BrushModel;
 pub use crate::lump_data::brush_side::BrushSide;
+pub use crate::lump_data::cubemap::Cubemap;
 pub use crate::lump_data::disp_info::*;
 pub use crate::lump_data::disp_tri::DispTri;
 pub use crate::lump_data::disp_vert::DispVert;
@@ -22,9 +23,9 @@ pub use crate::lump_data::vertex_normal::VertexNormal;
 pub use crate::lump_data::vertex_normal_index::VertexNormalIndex;
 pub use crate::lump_data::visibility::Visibility;
 pub use crate::game_lumps::GameLumps;
-pub use crate::lump_data::entity::Entities;
+pub use crate::lump_data::entity::{Entities, Entity, EntityClass};
 
-pub use self::brush::Brush;
+pub use self::brush::{Brush, BrushContents};
 pub use self::leaf::Leaf;
 pub use self::node::Node;
 
@@ -54,6 +55,7 @@ mod lighting;
 mod visibility;
 pub mod game_lumps;
 mod entity;
+mod cubemap;
 
 #[derive(Clone, Copy, Debug)]
 #[repr(u8)]
@@ -1,5 +1,6 @@
 use std::io::{Read, Result as IOResult};
-use crate::lump_data::{LumpData, LumpType};
+use bevy_math::Vec3;
+use crate::lump_data::{LumpData, LumpType, Plane};
 use crate::PrimitiveRead;
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -14,6 +15,29 @@ pub struct Node {
   pub padding: u16,
 }
 
+impl Node {
+  /// Walks the BSP tree from the root node to the leaf that contains `point` and returns its index.
+  /// Child indices below zero reference leafs as `-1 - leaf_index`.
+  pub fn find_leaf(nodes: &[Node], planes: &[Plane], point: Vec3) -> i32 {
+    let mut index = 0i32;
+    while index >= 0 {
+      let node = nodes.get(index as usize);
+      if node.is_none() {
+        return 0;
+      }
+      let node = node.unwrap();
+      let plane = planes.get(node.plane_number as usize);
+      if plane.is_none() {
+        return 0;
+      }
+      let plane = plane.unwrap();
+      let distance = plane.normal.dot(point) - plane.dist;
+      index = if distance >= 0f32 { node.children[0] } else { node.children[1] };
+    }
+    -1 - index
+  }
+}
+
 impl LumpData for Node {
   fn lump_type() -> LumpType {
     LumpType::Nodes
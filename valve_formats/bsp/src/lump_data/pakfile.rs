@@ -1,5 +1,6 @@
 use zip::ZipArchive;
 use std::{io::Cursor, collections::HashMap};
+use std::io::{Result as IOResult, Error as IOError, ErrorKind};
 use crate::RawDataRead;
 
 pub struct PakFile {
@@ -8,8 +9,9 @@ pub struct PakFile {
 }
 
 impl PakFile {
-  pub(crate) fn new(data: Box<[u8]>) -> Self {
-    let archive = ZipArchive::new(Cursor::new(data)).unwrap();
+  pub(crate) fn new(data: Box<[u8]>) -> IOResult<Self> {
+    let archive = ZipArchive::new(Cursor::new(data))
+      .map_err(|e| IOError::new(ErrorKind::InvalidData, format!("Failed to open the pakfile: {}", e)))?;
     let file_names: Vec<String> = archive.file_names().map(|s| s.to_string()).collect();
     let mut depth_file_names = HashMap::<String, String>::new();
     for file_name in file_names {
@@ -18,17 +20,26 @@ impl PakFile {
         depth_file_names.insert(file_name[..depth_index].to_string(), file_name.to_string() + ".vmt");
       }
     }
-    Self {
+    Ok(Self {
       archive,
       depth_file_names,
-    }
+    })
+  }
+
+  /// The names of all files embedded in the map.
+  pub fn entry_names(&self) -> impl Iterator<Item = &str> {
+    self.archive.file_names()
+  }
+
+  pub fn entries_count(&self) -> usize {
+    self.archive.len()
   }
 
   pub fn contains_entry(&mut self, name: &str) -> bool {
     let lower_case_name = name.to_lowercase();
     let mut actual_name = Option::<String>::None;
     for zip_file_name in self.archive.file_names() {
-      if zip_file_name == lower_case_name {
+      if zip_file_name.to_lowercase() == lower_case_name {
         actual_name = Some(zip_file_name.to_string());
       }
     }
@@ -52,7 +52,8 @@ impl LumpData for TextureInfo {
     let lightmap_vecs_s = Vec4::new(reader.read_f32()?, reader.read_f32()?, reader.read_f32()?, reader.read_f32()?);
     let lightmap_vecs_t = Vec4::new(reader.read_f32()?, reader.read_f32()?, reader.read_f32()?, reader.read_f32()?);
     let flags_bits = reader.read_i32()?;
-    let flags = SurfaceFlags::from_bits(flags_bits).unwrap();
+    // Compilers and newer games set flags that are not part of the list above, keep them instead of panicking
+    let flags = SurfaceFlags::from_bits_retain(flags_bits);
     let texture_data = reader.read_i32()?;
 
     Ok(Self {
@@ -1,30 +1,148 @@
 use std::io::{Read, Result as IOResult};
-use crate::PrimitiveRead;
+use crate::{PrimitiveRead, RawDataRead};
+
+const PVS: usize = 0;
+const PAS: usize = 1;
 
 pub struct Visibility {
   pub num_clusters: i32,
-  pub byte_offsets: [Box<[i32]>; 2]
+  /// The offsets of the compressed PVS and PAS of every cluster, relative to the start of the lump.
+  pub byte_offsets: [Box<[i32]>; 2],
+  /// The entire lump, including the header.
+  data: Box<[u8]>
 }
 
 impl Visibility {
-  pub fn read(reader: &mut dyn Read) -> IOResult<Self> {
-    let num_clusters = reader.read_i32()?;
-    let mut byte_offsets: [Box<[i32]>; 2] = [
-      Box::new([0i32; 0]),
-      Box::new([0i32; 0]),
-    ];
-
-    for offsets in &mut byte_offsets {
-      let mut offsets_vec = Vec::with_capacity(num_clusters as usize);
-      for _ in 0 .. num_clusters {
-        offsets_vec.push(reader.read_i32()?);
-      }
-      *offsets = offsets_vec.into_boxed_slice();
+  pub fn read(reader: &mut dyn Read, length: u32) -> IOResult<Self> {
+    if length == 0 {
+      // Maps compiled without VVIS have no visibility data, everything is visible.
+      return Ok(Self {
+        num_clusters: 0,
+        byte_offsets: [Box::new([0i32; 0]), Box::new([0i32; 0])],
+        data: Box::new([0u8; 0])
+      });
+    }
+
+    let data = reader.read_data(length as usize)?;
+    let mut header = &data[..];
+    let num_clusters = header.read_i32()?.max(0);
+    let mut offsets = [Vec::with_capacity(num_clusters as usize), Vec::with_capacity(num_clusters as usize)];
+    // The PVS and PAS offsets of every cluster are stored next to each other
+    for _ in 0 .. num_clusters {
+      offsets[PVS].push(header.read_i32()?);
+      offsets[PAS].push(header.read_i32()?);
     }
+    let [pvs_offsets, pas_offsets] = offsets;
 
     Ok(Self {
       num_clusters,
-      byte_offsets
+      byte_offsets: [pvs_offsets.into_boxed_slice(), pas_offsets.into_boxed_slice()],
+      data
     })
   }
-}
\ No newline at end of file
+
+  /// Checks whether anything in `to_cluster` can potentially be seen from `from_cluster`.
+  /// Clusters outside of the map (-1) are never visible. Maps without visibility data treat every cluster as visible.
+  pub fn is_cluster_visible(&self, from_cluster: i32, to_cluster: i32) -> bool {
+    if from_cluster < 0 || to_cluster < 0 {
+      return false;
+    }
+    if self.num_clusters == 0 || from_cluster == to_cluster {
+      return true;
+    }
+    if from_cluster >= self.num_clusters || to_cluster >= self.num_clusters {
+      return false;
+    }
+
+    // The bit vector is run length encoded: a zero byte is followed by the number of zero bytes it stands for.
+    let target_byte = (to_cluster >> 3) as usize;
+    let target_bit = 1u8 << (to_cluster & 7);
+    let mut position = self.byte_offsets[PVS][from_cluster as usize].max(0) as usize;
+    let mut byte_index = 0usize;
+    while byte_index <= target_byte {
+      let value = self.data.get(position).copied();
+      if value.is_none() {
+        return false;
+      }
+      let value = value.unwrap();
+      if value == 0 {
+        let zero_count = self.data.get(position + 1).copied().unwrap_or(0) as usize;
+        byte_index += zero_count.max(1);
+        position += 2;
+      } else {
+        if byte_index == target_byte {
+          return value & target_bit != 0;
+        }
+        byte_index += 1;
+        position += 1;
+      }
+    }
+    false
+  }
+
+  /// Decompresses the PVS of a cluster into one bool per cluster.
+  pub fn visible_clusters(&self, from_cluster: i32) -> Vec<bool> {
+    (0 .. self.num_clusters).map(|to_cluster| self.is_cluster_visible(from_cluster, to_cluster)).collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Builds a lump with one compressed PVS per cluster and an empty PAS.
+  fn build_lump(pvs: &[&[u8]]) -> Vec<u8> {
+    let header_size = 4 + pvs.len() * 8;
+    let mut data = Vec::new();
+    data.extend_from_slice(&(pvs.len() as i32).to_le_bytes());
+    let mut offset = header_size;
+    for cluster_pvs in pvs {
+      data.extend_from_slice(&(offset as i32).to_le_bytes());
+      data.extend_from_slice(&(header_size as i32).to_le_bytes());
+      offset += cluster_pvs.len();
+    }
+    for cluster_pvs in pvs {
+      data.extend_from_slice(cluster_pvs);
+    }
+    data
+  }
+
+  #[test]
+  fn two_clusters() {
+    // Cluster 0 only sees itself, the PVS of cluster 1 is a single run of one zero byte.
+    let data = build_lump(&[&[0b01], &[0, 1]]);
+    let visibility = Visibility::read(&mut &data[..], data.len() as u32).unwrap();
+    assert_eq!(visibility.num_clusters, 2);
+    assert!(visibility.is_cluster_visible(0, 0));
+    assert!(!visibility.is_cluster_visible(0, 1));
+    assert!(!visibility.is_cluster_visible(1, 0));
+    assert!(visibility.is_cluster_visible(1, 1));
+    assert!(!visibility.is_cluster_visible(-1, 0));
+    assert!(!visibility.is_cluster_visible(0, 2));
+
+    let data = build_lump(&[&[0b11], &[0b11]]);
+    let visibility = Visibility::read(&mut &data[..], data.len() as u32).unwrap();
+    assert_eq!(visibility.visible_clusters(0), vec![true, true]);
+    assert_eq!(visibility.visible_clusters(1), vec![true, true]);
+  }
+
+  #[test]
+  fn zero_runs() {
+    // Cluster 0 skips the first two bytes with a run and sees cluster 20, cluster 1 sees cluster 0 and 9.
+    let mut pvs: Vec<&[u8]> = vec![&[0, 2, 0b1_0000], &[0b1, 0b10, 0, 1]];
+    pvs.resize(24, &[0, 3]);
+    let data = build_lump(&pvs);
+    let visibility = Visibility::read(&mut &data[..], data.len() as u32).unwrap();
+    let visible: Vec<usize> = visibility.visible_clusters(0).iter().enumerate().filter(|(_, visible)| **visible).map(|(cluster, _)| cluster).collect();
+    assert_eq!(visible, vec![0, 20]);
+    let visible: Vec<usize> = visibility.visible_clusters(1).iter().enumerate().filter(|(_, visible)| **visible).map(|(cluster, _)| cluster).collect();
+    assert_eq!(visible, vec![0, 1, 9]);
+    assert_eq!(visibility.visible_clusters(5).iter().filter(|visible| **visible).count(), 1);
+  }
+
+  #[test]
+  fn no_visibility_data() {
+    let visibility = Visibility::read(&mut &[0u8; 0][..], 0).unwrap();
+    assert!(visibility.is_cluster_visible(0, 7));
+  }
+}
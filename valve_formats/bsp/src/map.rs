@@ -6,8 +6,11 @@ use crate::lump_data::{Brush, Node, Leaf, Face,
                        TextureData, SurfaceEdge, Vertex,
                        VertexNormalIndex, VertexNormal,
                        TextureDataStringTable, TextureStringData};
-use crate::{LumpType, BrushModel, RawDataRead, PakFile, DispTri, DispInfo, DispVert, Lighting, Visibility, GameLumps, Entities};
-use crate::lump_data::game_lumps::StaticPropDict;
+use crate::{LumpType, BrushModel, Cubemap, RawDataRead, PakFile, DispTri, DispInfo, DispVert, Lighting, Visibility, GameLumps, Entities};
+use crate::lump_data::game_lumps::{StaticPropDict, StaticProp};
+use crate::ConvexHull;
+use bevy_math::Vec3;
+use crate::displacement::build_displacement;
 
 pub struct Map<R: Read + Seek> {
   pub name: String,
@@ -131,25 +134,58 @@ impl<R: Read + Seek> Map<R> {
     self.read_lump_data()
   }
 
-  pub fn read_pakfile(&mut self) -> IOResult<PakFile> {
+  /// Reads the zip archive with the files that are embedded in the map.
+  /// Returns None if the map doesn't have one.
+  pub fn read_pakfile(&mut self) -> IOResult<Option<PakFile>> {
     let index = LumpType::PakFile as usize;
     let lump = self.header.lumps[index];
+    if lump.file_length <= 0 {
+      return Ok(None);
+    }
     self.reader.seek(SeekFrom::Start(lump.file_offset as u64))?;
     let data = self.reader.read_data(lump.file_length as usize)?;
-    Ok(PakFile::new(data))
+    PakFile::new(data).map(Some)
+  }
+
+  /// Reads the env_cubemap positions, they can be used to place reflection probes.
+  pub fn read_cubemaps(&mut self) -> IOResult<Vec<Cubemap>> {
+    self.read_lump_data()
   }
 
   pub fn read_visibility(&mut self) -> IOResult<Visibility> {
     let index = LumpType::Visibility as usize;
     let lump = self.header.lumps[index];
     self.reader.seek(SeekFrom::Start(lump.file_offset as u64))?;
-    Visibility::read(&mut self.reader)
+    Visibility::read(&mut self.reader, lump.file_length.max(0) as u32)
+  }
+
+  /// Walks the BSP tree of the world to find the leaf that contains `point`.
+  /// Use [`Node::find_leaf`] directly when doing many queries to avoid reading the lumps every time.
+  pub fn leaf_for_point(&mut self, point: Vec3) -> IOResult<i32> {
+    let nodes = self.read_nodes()?;
+    let planes = self.read_planes()?;
+    Ok(Node::find_leaf(&nodes, &planes, point))
+  }
+
+  /// Checks the PVS to determine whether the leaf `to` can potentially be seen from the leaf `from`.
+  /// Use [`Visibility::is_cluster_visible`] directly when doing many queries to avoid reading the lumps every time.
+  pub fn is_leaf_visible(&mut self, from: i32, to: i32) -> IOResult<bool> {
+    let leafs = self.read_leafs()?;
+    let visibility = self.read_visibility()?;
+    let from_cluster = leafs.get(from.max(0) as usize).filter(|_| from >= 0).map_or(-1, |leaf| leaf.cluster as i32);
+    let to_cluster = leafs.get(to.max(0) as usize).filter(|_| to >= 0).map_or(-1, |leaf| leaf.cluster as i32);
+    Ok(visibility.is_cluster_visible(from_cluster, to_cluster))
   }
 
   pub fn read_static_props(&mut self) -> IOResult<StaticPropDict> {
     self.game_lumps.read_static_prop_dict(&mut self.reader)
   }
 
+  /// Reads the prop_static placements with their resolved model names.
+  pub fn read_static_prop_list(&mut self) -> IOResult<Vec<StaticProp>> {
+    self.game_lumps.static_props(&mut self.reader)
+  }
+
   pub fn read_entities(&mut self) -> IOResult<Entities> {
     let index = LumpType::Entities as usize;
     let lump = self.header.lumps[index];
@@ -157,6 +193,44 @@ impl<R: Read + Seek> Map<R> {
     Entities::read(&mut self.reader)
   }
 
+  /// Builds a convex hull for every brush in the map.
+  /// Non-solid brushes (triggers, water, ...) are included as well, use [`ConvexHull::is_solid`] to filter them.
+  pub fn build_brush_colliders(&mut self) -> IOResult<Vec<ConvexHull>> {
+    let brushes = self.read_brushes()?;
+    let brush_sides = self.read_brush_sides()?;
+    let planes = self.read_planes()?;
+    Ok(brushes
+      .iter()
+      .enumerate()
+      .filter_map(|(index, brush)| ConvexHull::from_brush(index, brush, &brush_sides, &planes))
+      .collect())
+  }
+
+  /// Builds the fully tessellated mesh of a displacement.
+  /// Vertex `i` belongs to the `DispVert` at `disp_info.disp_vert_start + i`.
+  pub fn build_displacement(&mut self, disp_info: &DispInfo) -> IOResult<(Vec<Vertex>, Vec<u32>)> {
+    let faces = self.read_faces()?;
+    let surface_edges = self.read_surface_edges()?;
+    let edges = self.read_edges()?;
+    let vertices = self.read_vertices()?;
+    let disp_verts = self.read_disp_verts()?;
+    build_displacement(disp_info, &faces, &surface_edges, &edges, &vertices, &disp_verts)
+  }
+
+  /// Builds the meshes of all displacements in the map, in the order of the displacement info lump.
+  pub fn build_displacements(&mut self) -> IOResult<Vec<(Vec<Vertex>, Vec<u32>)>> {
+    let disp_infos = self.read_disp_infos()?;
+    let faces = self.read_faces()?;
+    let surface_edges = self.read_surface_edges()?;
+    let edges = self.read_edges()?;
+    let vertices = self.read_vertices()?;
+    let disp_verts = self.read_disp_verts()?;
+    disp_infos
+      .iter()
+      .map(|disp_info| build_displacement(disp_info, &faces, &surface_edges, &edges, &vertices, &disp_verts))
+      .collect()
+  }
+
   fn read_lump_data<T: LumpData>(&mut self) -> IOResult<Vec<T>> {
     let mut lump_type = T::lump_type();
     let lump_type_hdr = T::lump_type_hdr();
@@ -1,9 +1,13 @@
 use crate::lump::Lump;
 
-use std::io::{Read, Result as IOResult};
+use std::io::{Read, Result as IOResult, Error as IOError, ErrorKind};
 use crate::PrimitiveRead;
 
 const LUMP_COUNT: usize = 64;
+const IDENTIFIER: [u8; 4] = *b"VBSP";
+/// Console maps (.360.bsp) are big endian, so the identifier is reversed.
+/// The lumps of those would all have to be byte swapped, so they get rejected.
+const IDENTIFIER_BIG_ENDIAN: [u8; 4] = *b"PSBV";
 
 pub struct MapHeader {
   pub identifier: i32,
@@ -13,7 +17,14 @@ pub struct MapHeader {
 
 impl MapHeader {
   pub fn read(reader: &mut dyn Read) -> IOResult<MapHeader> {
-    let identifier = reader.read_i32()?;
+    let mut identifier_bytes = [0u8; 4];
+    reader.read_exact(&mut identifier_bytes)?;
+    match identifier_bytes {
+      IDENTIFIER => {},
+      IDENTIFIER_BIG_ENDIAN => return Err(IOError::new(ErrorKind::Unsupported, "Big endian console maps are not supported")),
+      _ => return Err(IOError::new(ErrorKind::InvalidData, "File is not a Source map"))
+    }
+    let identifier = i32::from_le_bytes(IDENTIFIER);
     let version = reader.read_i32()?;
     let mut lumps: [Lump; LUMP_COUNT] = [
       Lump {
@@ -35,3 +46,36 @@ impl MapHeader {
     })
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn header(identifier: &[u8; 4]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(identifier);
+    data.extend_from_slice(&20i32.to_le_bytes());
+    for i in 0..LUMP_COUNT as i32 {
+      for value in [i * 16, 16, 0, 0] {
+        data.extend_from_slice(&value.to_le_bytes());
+      }
+    }
+    data
+  }
+
+  #[test]
+  fn read_little_endian() {
+    let header = MapHeader::read(&mut &header(b"VBSP")[..]).unwrap();
+    assert_eq!(header.version, 20);
+    assert_eq!(header.lumps[3].file_offset, 48);
+    assert_eq!(header.lumps[3].file_length, 16);
+  }
+
+  #[test]
+  fn reject_big_endian() {
+    let result = MapHeader::read(&mut &header(b"PSBV")[..]);
+    assert_eq!(result.err().map(|e| e.kind()), Some(ErrorKind::Unsupported));
+    let result = MapHeader::read(&mut &header(b"IBSP")[..]);
+    assert_eq!(result.err().map(|e| e.kind()), Some(ErrorKind::InvalidData));
+  }
+}
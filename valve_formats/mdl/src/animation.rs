@@ -0,0 +1,244 @@
+use std::io::{Read, Seek, SeekFrom, Result as IOResult, Error as IOError, ErrorKind};
+
+use bevy_math::{EulerRot, Quat, Vec3};
+
+use crate::{AnimDesc, Bone, PrimitiveRead};
+
+const ANIM_DESC_SIZE: u64 = 100;
+
+/// mstudioanimdesc_t flags
+const STUDIO_DELTA: i32 = 0x0004;
+const STUDIO_ALLZEROS: i32 = 0x0020;
+const STUDIO_FRAMEANIM: i32 = 0x0040;
+
+/// mstudioanim_t flags
+const STUDIO_ANIM_RAWPOS: u8 = 0x01;
+const STUDIO_ANIM_RAWROT: u8 = 0x02;
+const STUDIO_ANIM_ANIMPOS: u8 = 0x04;
+const STUDIO_ANIM_ANIMROT: u8 = 0x08;
+const STUDIO_ANIM_DELTA: u8 = 0x10;
+const STUDIO_ANIM_RAWROT2: u8 = 0x20;
+
+/// The pose of a single bone relative to its parent.
+#[derive(Clone, Copy)]
+pub(crate) struct BonePose {
+  pub position: Vec3,
+  pub rotation: Quat
+}
+
+pub(crate) fn read_anim_desc<R: Read + Seek>(reader: &mut R, model_start: u64, local_anim_offset: i32, index: i32) -> IOResult<(u64, AnimDesc)> {
+  let anim_desc_start = model_start + local_anim_offset as u64 + index as u64 * ANIM_DESC_SIZE;
+  reader.seek(SeekFrom::Start(anim_desc_start))?;
+  Ok((anim_desc_start, AnimDesc::read(reader)?))
+}
+
+/// Decodes the bone poses of two neighboring frames of an animation and blends between them.
+/// Supported are the RLE compressed per component values and the raw Quaternion48, Quaternion64 and Vector48 encodings.
+/// Animations stored in external .ani blocks and the frame based animations of newer games are not supported.
+pub(crate) fn sample_anim_desc<R: Read + Seek>(reader: &mut R, anim_desc_start: u64, anim_desc: &AnimDesc, bones: &[Bone], frame: f32, out: &mut [BonePose]) -> IOResult<()> {
+  let is_delta = anim_desc.flags & STUDIO_DELTA != 0;
+  for (bone, pose) in bones.iter().zip(out.iter_mut()) {
+    *pose = if is_delta {
+      BonePose { position: Vec3::ZERO, rotation: Quat::IDENTITY }
+    } else {
+      BonePose { position: bone.position, rotation: bone.quaternion }
+    };
+  }
+
+  if anim_desc.flags & STUDIO_ALLZEROS != 0 {
+    return Ok(());
+  }
+  if anim_desc.flags & STUDIO_FRAMEANIM != 0 {
+    return Err(IOError::new(ErrorKind::Unsupported, "Frame based animations are not supported."));
+  }
+
+  let last_frame = (anim_desc.frames_count - 1).max(0) as u32;
+  let frame = frame.clamp(0f32, last_frame as f32);
+  let mut frame_index = frame.floor() as u32;
+  let blend = frame - frame_index as f32;
+
+  // Long animations are split into sections that are stored separately.
+  let (anim_block, anim_start) = if anim_desc.section_frames != 0 {
+    let section = frame_index / anim_desc.section_frames as u32;
+    reader.seek(SeekFrom::Start(anim_desc_start + anim_desc.section_index as u64 + section as u64 * 8))?;
+    let anim_block = reader.read_i32()?;
+    let anim_index = reader.read_i32()?;
+    frame_index -= section * anim_desc.section_frames as u32;
+    (anim_block, anim_desc_start + anim_index as u64)
+  } else {
+    (anim_desc.anim_block, anim_desc_start + anim_desc.anim_index as u64)
+  };
+  if anim_block != 0 {
+    return Err(IOError::new(ErrorKind::Unsupported, "Animations in external animation blocks are not supported."));
+  }
+
+  let mut anim_start = anim_start;
+  loop {
+    reader.seek(SeekFrom::Start(anim_start))?;
+    let bone_index = reader.read_u8()? as usize;
+    let flags = reader.read_u8()?;
+    let next_offset = reader.read_i16()?;
+    if bone_index >= bones.len() || bone_index >= out.len() {
+      return Err(IOError::new(ErrorKind::InvalidData, format!("Animation references invalid bone {}.", bone_index)));
+    }
+    let bone = &bones[bone_index];
+    let is_delta = flags & STUDIO_ANIM_DELTA != 0;
+
+    let rotation_start = anim_start + 4;
+    let (rotation, position_start) = if flags & STUDIO_ANIM_RAWROT != 0 {
+      (Some(read_quaternion48(reader)?), rotation_start + 6)
+    } else if flags & STUDIO_ANIM_RAWROT2 != 0 {
+      (Some(read_quaternion64(reader)?), rotation_start + 8)
+    } else if flags & STUDIO_ANIM_ANIMROT != 0 {
+      let (angles, next_angles) = read_anim_vector(reader, rotation_start, frame_index, last_frame, bone.rot_scale)?;
+      let base = if is_delta { Vec3::ZERO } else { bone.rotation };
+      let rotation = euler_to_quat(base + angles);
+      let next_rotation = euler_to_quat(base + next_angles);
+      (Some(rotation.slerp(next_rotation, blend)), rotation_start + 6)
+    } else {
+      (None, rotation_start)
+    };
+
+    reader.seek(SeekFrom::Start(position_start))?;
+    let position = if flags & STUDIO_ANIM_RAWPOS != 0 {
+      Some(read_vector48(reader)?)
+    } else if flags & STUDIO_ANIM_ANIMPOS != 0 {
+      let (position, next_position) = read_anim_vector(reader, position_start, frame_index, last_frame, bone.pos_scale)?;
+      let base = if is_delta { Vec3::ZERO } else { bone.position };
+      Some(base + position.lerp(next_position, blend))
+    } else {
+      None
+    };
+
+    let pose = &mut out[bone_index];
+    if let Some(rotation) = rotation {
+      pose.rotation = rotation;
+    }
+    if let Some(position) = position {
+      pose.position = position;
+    }
+
+    if next_offset == 0 {
+      break;
+    }
+    anim_start = (anim_start as i64 + next_offset as i64) as u64;
+  }
+  Ok(())
+}
+
+fn euler_to_quat(angles: Vec3) -> Quat {
+  // Valve's RadianEuler is applied as roll (x), pitch (y), yaw (z)
+  Quat::from_euler(EulerRot::ZYX, angles.z, angles.y, angles.x)
+}
+
+/// Reads a mstudioanim_valueptr_t and decodes the three components for the frame and the one after it.
+fn read_anim_vector<R: Read + Seek>(reader: &mut R, value_ptr_start: u64, frame: u32, last_frame: u32, scale: Vec3) -> IOResult<(Vec3, Vec3)> {
+  reader.seek(SeekFrom::Start(value_ptr_start))?;
+  let offsets = [reader.read_i16()?, reader.read_i16()?, reader.read_i16()?];
+  let mut value = [0f32; 3];
+  let mut next_value = [0f32; 3];
+  for i in 0..3 {
+    if offsets[i] == 0 {
+      continue;
+    }
+    let values_start = (value_ptr_start as i64 + offsets[i] as i64) as u64;
+    let (v1, v2) = extract_anim_value(reader, values_start, frame, frame < last_frame)?;
+    value[i] = v1 as f32 * scale[i];
+    next_value[i] = v2 as f32 * scale[i];
+  }
+  Ok((Vec3::from_array(value), Vec3::from_array(next_value)))
+}
+
+/// Decodes the run length encoded mstudioanimvalue_t stream.
+/// Every run starts with a header containing the number of stored values and the number of frames it covers,
+/// frames beyond the stored values repeat the last one.
+fn extract_anim_value<R: Read + Seek>(reader: &mut R, values_start: u64, frame: u32, has_next_frame: bool) -> IOResult<(i16, i16)> {
+  let mut run_start = values_start;
+  let mut k = frame;
+  loop {
+    reader.seek(SeekFrom::Start(run_start))?;
+    let valid = reader.read_u8()? as u32;
+    let total = reader.read_u8()? as u32;
+    if total == 0 {
+      return Ok((0, 0));
+    }
+    if total > k {
+      let value = read_run_value(reader, run_start, valid, k)?;
+      let next_value = if !has_next_frame {
+        value
+      } else if k + 1 < total {
+        read_run_value(reader, run_start, valid, k + 1)?
+      } else {
+        // The next frame is the first one of the next run
+        let next_run_start = run_start + (valid as u64 + 1) * 2;
+        reader.seek(SeekFrom::Start(next_run_start))?;
+        let next_valid = reader.read_u8()? as u32;
+        let next_total = reader.read_u8()? as u32;
+        if next_total == 0 || next_valid == 0 {
+          value
+        } else {
+          read_run_value(reader, next_run_start, next_valid, 0)?
+        }
+      };
+      return Ok((value, next_value));
+    }
+    k -= total;
+    run_start += (valid as u64 + 1) * 2;
+  }
+}
+
+fn read_run_value<R: Read + Seek>(reader: &mut R, run_start: u64, valid: u32, index: u32) -> IOResult<i16> {
+  let value_index = if index < valid { index + 1 } else { valid };
+  reader.seek(SeekFrom::Start(run_start + value_index as u64 * 2))?;
+  reader.read_i16()
+}
+
+fn read_quaternion48<R: Read>(reader: &mut R) -> IOResult<Quat> {
+  let x = reader.read_u16()?;
+  let y = reader.read_u16()?;
+  let z_w = reader.read_u16()?;
+  let x = (x as f32 - 32768f32) / 32768f32;
+  let y = (y as f32 - 32768f32) / 32768f32;
+  let z = ((z_w & 0x7FFF) as f32 - 16384f32) / 16384f32;
+  let mut w = (1f32 - x * x - y * y - z * z).max(0f32).sqrt();
+  if z_w & 0x8000 != 0 {
+    w = -w;
+  }
+  Ok(Quat::from_xyzw(x, y, z, w))
+}
+
+fn read_quaternion64<R: Read>(reader: &mut R) -> IOResult<Quat> {
+  let packed = reader.read_u64()?;
+  let x = (packed & 0x1FFFFF) as f32;
+  let y = ((packed >> 21) & 0x1FFFFF) as f32;
+  let z = ((packed >> 42) & 0x1FFFFF) as f32;
+  let x = (x - 1048576f32) / 1048576.5f32;
+  let y = (y - 1048576f32) / 1048576.5f32;
+  let z = (z - 1048576f32) / 1048576.5f32;
+  let mut w = (1f32 - x * x - y * y - z * z).max(0f32).sqrt();
+  if packed >> 63 != 0 {
+    w = -w;
+  }
+  Ok(Quat::from_xyzw(x, y, z, w))
+}
+
+fn read_vector48<R: Read>(reader: &mut R) -> IOResult<Vec3> {
+  Ok(Vec3::new(
+    f16_to_f32(reader.read_u16()?),
+    f16_to_f32(reader.read_u16()?),
+    f16_to_f32(reader.read_u16()?)
+  ))
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+  let sign = if half & 0x8000 != 0 { -1f32 } else { 1f32 };
+  let exponent = ((half >> 10) & 0x1F) as i32;
+  let mantissa = (half & 0x3FF) as f32;
+  if exponent == 0 {
+    sign * mantissa * 2f32.powi(-24)
+  } else if exponent == 0x1F {
+    if mantissa == 0f32 { sign * f32::INFINITY } else { f32::NAN }
+  } else {
+    sign * (1f32 + mantissa / 1024f32) * 2f32.powi(exponent - 15)
+  }
+}
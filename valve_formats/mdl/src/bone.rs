@@ -1,4 +1,4 @@
-use std::io::{Read, Result as IOResult};
+use std::io::{Read, Result as IOResult, Error as IOError, ErrorKind};
 
 use bevy_math::{Mat4, Quat, Vec3};
 
@@ -22,6 +22,7 @@ pub struct Bone {
   pub flags: i32,
   pub proc_type: i32,
   pub proc_index: i32,
+  pub physics_bone: i32,
   pub surface_prop_index: i32,
   pub contents: i32
 }
@@ -36,24 +37,29 @@ impl Bone {
     }
 
     let position = Vec3::new(read.read_f32()?, read.read_f32()?, read.read_f32()?);
-    let axis = Vec3::new(read.read_f32()?, read.read_f32()?, read.read_f32()?);
-    let scale = read.read_f32()?;
-    let quaternion = Quat::from_scaled_axis(axis * scale);
+    let quaternion = Quat::from_xyzw(read.read_f32()?, read.read_f32()?, read.read_f32()?, read.read_f32()?);
     let rotation = Vec3::new(read.read_f32()?, read.read_f32()?, read.read_f32()?);
 
     let pos_scale = Vec3::new(read.read_f32()?, read.read_f32()?, read.read_f32()?);
     let rot_scale = Vec3::new(read.read_f32()?, read.read_f32()?, read.read_f32()?);
 
-    let pose_to_bone = Mat4::from_cols_slice(&[read.read_f32()?, read.read_f32()?, read.read_f32()?, read.read_f32()?,
-                                      read.read_f32()?, read.read_f32()?, read.read_f32()?, read.read_f32()?,
-                                      read.read_f32()?, read.read_f32()?, read.read_f32()?, read.read_f32()?]);
-    let axis = Vec3::new(read.read_f32()?, read.read_f32()?, read.read_f32()?);
-    let scale = read.read_f32()?;
-    let alignment = Quat::from_scaled_axis(axis * scale);
+    // Stored as a row major 3x4 matrix
+    let mut rows = [0f32; 12];
+    for value in rows.iter_mut() {
+      *value = read.read_f32()?;
+    }
+    let pose_to_bone = Mat4::from_cols_array(&[
+      rows[0], rows[4], rows[8], 0f32,
+      rows[1], rows[5], rows[9], 0f32,
+      rows[2], rows[6], rows[10], 0f32,
+      rows[3], rows[7], rows[11], 1f32
+    ]);
+    let alignment = Quat::from_xyzw(read.read_f32()?, read.read_f32()?, read.read_f32()?, read.read_f32()?);
 
     let flags = read.read_i32()?;
     let proc_type = read.read_i32()?;
     let proc_index = read.read_i32()?;
+    let physics_bone = read.read_i32()?;
     let surface_prop_index = read.read_i32()?;
     let contents = read.read_i32()?;
 
@@ -75,8 +81,51 @@ impl Bone {
       flags,
       proc_type,
       proc_index,
+      physics_bone,
       surface_prop_index,
       contents
     })
   }
+
+  /// The transform of the bone relative to its parent in the bind pose.
+  pub fn local_bind_matrix(&self) -> Mat4 {
+    Mat4::from_rotation_translation(self.quaternion, self.position)
+  }
+
+  /// Composes the model space bind pose transforms of all bones by walking the parent indices.
+  /// Root bones have a parent index of -1.
+  pub fn world_bind_matrices(bones: &[Bone]) -> IOResult<Vec<Mat4>> {
+    let mut matrices: Vec<Option<Mat4>> = vec![None; bones.len()];
+    let mut chain = Vec::<usize>::new();
+    for bone_index in 0..bones.len() {
+      // Collect all ancestors that haven't been computed yet, starting at the bone itself.
+      let mut current = bone_index;
+      chain.clear();
+      let parent_matrix = loop {
+        if let Some(matrix) = matrices[current] {
+          break matrix;
+        }
+        if chain.len() > bones.len() {
+          return Err(IOError::new(ErrorKind::InvalidData, format!("Bone {} is part of a cycle.", bone_index)));
+        }
+        chain.push(current);
+
+        let parent = bones[current].parent;
+        if parent < 0 {
+          break Mat4::IDENTITY;
+        }
+        if parent as usize >= bones.len() {
+          return Err(IOError::new(ErrorKind::InvalidData, format!("Bone {} has an invalid parent {}.", current, parent)));
+        }
+        current = parent as usize;
+      };
+
+      let mut matrix = parent_matrix;
+      for &index in chain.iter().rev() {
+        matrix *= bones[index].local_bind_matrix();
+        matrices[index] = Some(matrix);
+      }
+    }
+    Ok(matrices.into_iter().map(|matrix| matrix.unwrap()).collect())
+  }
 }
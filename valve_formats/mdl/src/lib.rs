@@ -3,6 +3,8 @@
 #[macro_use]
 extern crate bitflags;
 extern crate io_util;
+extern crate sourcerenderer_vvd;
+extern crate sourcerenderer_vtx;
 
 mod header;
 mod header2;
@@ -17,12 +19,14 @@ mod model_file;
 mod body_part;
 mod model;
 mod mesh;
+mod studio_model;
+mod animation;
 
 pub use self::io_util::*;
 pub use self::header::{Header, StudioHDRFlags};
 pub use self::header2::Header2;
 pub use self::texture::Texture;
-pub use self::skin_replacement::{SkinReplacementTableEntry, SkinReplacementTable};
+pub use self::skin_replacement::SkinReplacementTable;
 pub use self::bone::Bone;
 pub use self::bone_controller::BoneController;
 pub use self::hitbox_set::HitboxSet;
@@ -32,3 +36,4 @@ pub use self::model_file::ModelFile;
 pub use self::body_part::BodyPart;
 pub use self::model::{Model, ModelVertexData};
 pub use self::mesh::{Mesh, MeshVertexData};
+pub use self::studio_model::{StudioModel, StudioModelLOD};
@@ -1,11 +1,17 @@
 use std::io::{Read, Seek, Result as IOResult, Error as IOError, SeekFrom, ErrorKind};
+
+use bevy_math::Mat4;
+
 use crate::header::Header;
 use crate::header2::Header2;
-use crate::{Bone, BoneController, HitboxSet, AnimDesc, SequenceDesc, Texture, StringRead, PrimitiveRead, BodyPart, Model, Mesh};
+use crate::{Bone, BoneController, HitboxSet, AnimDesc, SequenceDesc, Texture, StringRead, PrimitiveRead, BodyPart, Model, Mesh, SkinReplacementTable};
 
 pub struct ModelFile<R: Read + Seek> {
   header: Header,
   secondary_header: Header2,
+  bones: Vec<Bone>,
+  texture_names: Vec<String>,
+  skin_table: SkinReplacementTable,
   reader: R,
   start_offset: u64
 }
@@ -19,30 +25,65 @@ impl<R: Read + Seek> ModelFile<R> {
       return Err(IOError::new(ErrorKind::Other, "Not a MDL file."));
     }
 
-    let mut textures = Vec::<Texture>::with_capacity(header.texture_count as usize);
+    let mut texture_names = Vec::<String>::with_capacity(header.texture_count as usize);
     reader.seek(SeekFrom::Start(start + header.texture_offset as u64))?;
     for _ in 0..header.texture_count {
-      textures.push(Texture::read(&mut reader)?);
+      let texture_start = reader.seek(SeekFrom::Current(0))?;
+      let texture = Texture::read(&mut reader)?;
+      let texture_next = reader.seek(SeekFrom::Current(0))?;
+      reader.seek(SeekFrom::Start(texture_start + texture.name_offset as u64))?;
+      let name = reader.read_null_terminated_string()
+        .map_err(|_| IOError::new(ErrorKind::InvalidData, "Invalid texture name"))?;
+      texture_names.push(name);
+      reader.seek(SeekFrom::Start(texture_next))?;
     }
 
+    reader.seek(SeekFrom::Start(start + header.skin_reference_index as u64))?;
+    let skin_table = SkinReplacementTable::read(&mut reader, header.skin_reference_family_count, header.skin_reference_count)?;
+
     reader.seek(SeekFrom::Start(start + header.studio_hdr2_index as u64))?;
     let header2 = Header2::read(&mut reader)?;
 
+    let mut bones = Vec::<Bone>::with_capacity(header.bone_count as usize);
+    reader.seek(SeekFrom::Start(start + header.bone_offset as u64))?;
+    for _ in 0..header.bone_count {
+      bones.push(Bone::read(&mut reader)?);
+    }
+
     Ok(Self {
       header,
       secondary_header: header2,
+      bones,
+      texture_names,
+      skin_table,
       reader,
       start_offset: start
     })
   }
 
-  pub fn bones(&mut self) -> IOResult<Vec<Bone>> {
-    let mut bones = Vec::<Bone>::with_capacity(self.header.bone_count as usize);
-    self.reader.seek(SeekFrom::Start(self.start_offset + self.header.bone_offset as u64))?;
-    for _ in 0..self.header.bone_count {
-      bones.push(Bone::read(&mut self.reader)?);
-    }
-    Ok(bones)
+  pub fn bones(&self) -> &[Bone] {
+    &self.bones
+  }
+
+  /// The model space bind pose transform of every bone, see `Bone::world_bind_matrices`.
+  pub fn bind_pose(&self) -> IOResult<Vec<Mat4>> {
+    Bone::world_bind_matrices(&self.bones)
+  }
+
+  /// The texture names as stored in the model, they are relative to one of the `texture_dirs`.
+  pub fn texture_names(&self) -> &[String] {
+    &self.texture_names
+  }
+
+  pub fn skin_families(&self) -> usize {
+    self.skin_table.family_count()
+  }
+
+  /// Resolves the skin replacement table for the given skin family.
+  /// The result is indexed by the material index of the meshes.
+  /// Returns None if the skin family doesn't exist or references a texture that doesn't exist.
+  pub fn materials_for_skin(&self, skin: usize) -> Option<Vec<&str>> {
+    self.skin_table.materials(skin, &self.texture_names)
   }
 
   pub fn bone_controllers(&mut self) -> IOResult<Vec<BoneController>> {
@@ -145,6 +186,14 @@ impl<R: Read + Seek> ModelFile<R> {
     Ok(meshes)
   }
 
+  pub(crate) fn start_offset(&self) -> u64 {
+    self.start_offset
+  }
+
+  pub(crate) fn reader_and_bones(&mut self) -> (&mut R, &[Bone]) {
+    (&mut self.reader, &self.bones)
+  }
+
   pub fn header(&self) -> &Header {
     &self.header
   }
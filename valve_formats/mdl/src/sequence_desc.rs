@@ -1,8 +1,12 @@
-use std::io::{Read, Result as IOResult};
+use std::io::{Read, Seek, SeekFrom, Result as IOResult, Error as IOError, ErrorKind};
 
-use bevy_math::Vec3;
+use bevy_math::{Mat4, Quat, Vec3};
 
-use crate::PrimitiveRead;
+use crate::{PrimitiveRead, ModelFile};
+use crate::animation::{self, BonePose};
+
+/// mstudioseqdesc_t flags
+const STUDIO_LOOPING: i32 = 0x0001;
 
 pub struct SequenceDesc {
   pub base_ptr: i32,
@@ -172,3 +176,45 @@ impl SequenceDesc {
     })
   }
 }
+
+impl SequenceDesc {
+  /// Whether the sequence starts over once it reaches the end.
+  pub fn is_looping(&self) -> bool {
+    self.flags & STUDIO_LOOPING != 0
+  }
+
+  /// Evaluates the first animation of the sequence at the given time and writes the
+  /// local (parent relative) transform of every bone to `out`.
+  /// Frames get interpolated, looping sequences wrap around and all others hold the last frame.
+  /// Animations in external .ani files and the frame based animations of newer games return `ErrorKind::Unsupported`.
+  pub fn sample<R: Read + Seek>(&self, mdl: &mut ModelFile<R>, time_secs: f32, out: &mut [Mat4]) -> IOResult<()> {
+    let bones_count = mdl.bones().len();
+    if out.len() < bones_count {
+      return Err(IOError::new(ErrorKind::InvalidInput, "Output has fewer entries than the model has bones."));
+    }
+
+    // The base pointer is the negative offset of the sequence in the MDL.
+    let sequence_start = mdl.start_offset() + (-self.base_ptr) as u64;
+    let local_anim_offset = mdl.header().local_anim_offset;
+    let start_offset = mdl.start_offset();
+    let (reader, bones) = mdl.reader_and_bones();
+    reader.seek(SeekFrom::Start(sequence_start + self.anim_index_index as u64))?;
+    let anim_index = reader.read_i16()?;
+    let (anim_desc_start, anim_desc) = animation::read_anim_desc(reader, start_offset, local_anim_offset, anim_index as i32)?;
+
+    let last_frame = (anim_desc.frames_count - 1).max(0) as f32;
+    let mut frame = time_secs.max(0f32) * anim_desc.fps;
+    if self.is_looping() && last_frame > 0f32 {
+      frame %= last_frame;
+    } else {
+      frame = frame.min(last_frame);
+    }
+
+    let mut poses = vec![BonePose { position: Vec3::ZERO, rotation: Quat::IDENTITY }; bones_count];
+    animation::sample_anim_desc(reader, anim_desc_start, &anim_desc, bones, frame, &mut poses)?;
+    for (matrix, pose) in out.iter_mut().zip(poses.iter()) {
+      *matrix = Mat4::from_rotation_translation(pose.rotation, pose.position);
+    }
+    Ok(())
+  }
+}
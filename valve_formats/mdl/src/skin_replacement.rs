@@ -1,49 +1,92 @@
-use std::io::{Read, Result as IOResult, Cursor, ErrorKind};
-use std::collections::HashMap;
+use std::io::{Read, Result as IOResult};
 
 use crate::PrimitiveRead;
 
-pub struct SkinReplacementTableEntry {
-  pub main_body: u16,
-  pub trimming: u16
-}
-
+/// Maps the material index of every mesh to a texture index for every skin family.
+/// Stored as `skin_reference_family_count` rows of `skin_reference_count` texture indices.
 pub struct SkinReplacementTable {
-  table: HashMap<u16, SkinReplacementTableEntry>
+  families: Vec<Box<[u16]>>
 }
 
 impl SkinReplacementTable {
   pub fn read(read: &mut dyn Read, skin_families_count: i32, skins_count: i32) -> IOResult<Self> {
-    let len = (skin_families_count * skins_count * 2) as usize;
-    let mut data = Vec::with_capacity(len);
-    unsafe {
-      data.set_len(len);
-    }
-    read.read_exact(&mut data)?;
-
-    let mut table = HashMap::<u16, SkinReplacementTableEntry>::new();
-    let mut i = 0;
-    let mut cursor = Cursor::new(&data);
-    loop {
-      let main_body_res = cursor.read_u16();
-      if let Err(e) = main_body_res {
-        if e.kind() == ErrorKind::UnexpectedEof {
-          break;
-        } else {
-          return Err(e);
-        }
+    let mut families = Vec::<Box<[u16]>>::with_capacity(skin_families_count.max(0) as usize);
+    for _ in 0..skin_families_count.max(0) {
+      let mut family = Vec::<u16>::with_capacity(skins_count.max(0) as usize);
+      for _ in 0..skins_count.max(0) {
+        family.push(read.read_u16()?);
       }
-      let main_body = main_body_res.unwrap();
-      let trimming = cursor.read_u16()?;
-      table.insert(i, SkinReplacementTableEntry {
-        main_body,
-        trimming
-      });
-      i += 1;
+      families.push(family.into_boxed_slice());
     }
 
     Ok(Self {
-      table
+      families
     })
   }
-}
\ No newline at end of file
+
+  pub fn family_count(&self) -> usize {
+    self.families.len()
+  }
+
+  /// The texture index of every material slot for the given skin family.
+  pub fn family(&self, skin: usize) -> Option<&[u16]> {
+    self.families.get(skin).map(|family| family.as_ref())
+  }
+
+  /// Looks up the texture name of every material slot for the given skin family.
+  /// Returns None if the skin family doesn't exist or references a texture that doesn't exist.
+  pub fn materials<'a>(&self, skin: usize, texture_names: &'a [String]) -> Option<Vec<&'a str>> {
+    let family = self.family(skin)?;
+    family.iter()
+      .map(|texture_index| texture_names.get(*texture_index as usize).map(|name| name.as_str()))
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Cursor;
+
+  use super::*;
+
+  fn texture_names() -> Vec<String> {
+    ["body", "head", "body_red", "head_red"].iter().map(|name| name.to_string()).collect()
+  }
+
+  #[test]
+  fn two_families() {
+    let mut data = Vec::new();
+    for texture_index in [0u16, 1, 1, 2, 3, 1] {
+      data.extend_from_slice(&texture_index.to_le_bytes());
+    }
+    let mut cursor = Cursor::new(data);
+    let table = SkinReplacementTable::read(&mut cursor, 2, 3).unwrap();
+    assert_eq!(cursor.position(), 12);
+
+    assert_eq!(table.family_count(), 2);
+    assert_eq!(table.family(0), Some(&[0u16, 1, 1][..]));
+    assert_eq!(table.family(1), Some(&[2u16, 3, 1][..]));
+
+    let texture_names = texture_names();
+    assert_eq!(table.materials(0, &texture_names), Some(vec!["body", "head", "head"]));
+    assert_eq!(table.materials(1, &texture_names), Some(vec!["body_red", "head_red", "head"]));
+  }
+
+  #[test]
+  fn out_of_range() {
+    let mut data = Vec::new();
+    for texture_index in [0u16, 4] {
+      data.extend_from_slice(&texture_index.to_le_bytes());
+    }
+    let table = SkinReplacementTable::read(&mut Cursor::new(data), 2, 1).unwrap();
+    let texture_names = texture_names();
+
+    assert!(table.family(2).is_none());
+    assert!(table.materials(2, &texture_names).is_none());
+    // The second family references a texture that doesn't exist.
+    assert!(table.materials(1, &texture_names).is_none());
+
+    // The table is shorter than the header says.
+    assert!(SkinReplacementTable::read(&mut Cursor::new(vec![0u8; 2]), 2, 1).is_err());
+  }
+}
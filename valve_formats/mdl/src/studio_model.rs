@@ -0,0 +1,411 @@
+use std::collections::BTreeMap;
+use std::io::{Cursor, Seek, SeekFrom, Result as IOResult, Error as IOError, ErrorKind};
+
+use sourcerenderer_vvd::{Header as VVDHeader, Vertex, VertexFileFixup};
+use sourcerenderer_vtx::{
+  Header as VTXHeader,
+  BodyPartHeader,
+  ModelHeader,
+  ModelLODHeader,
+  MeshHeader,
+  StripGroupHeader,
+  StripHeader,
+  Vertex as VTXVertex
+};
+
+use crate::{Header, BodyPart, Model, Mesh, PrimitiveRead};
+
+const MDL_ID: i32 = 0x54534449;
+const VVD_ID: i32 = 0x56534449;
+
+const BODY_PART_SIZE: u64 = 16;
+const MESH_SIZE: u64 = 116;
+
+const VTX_BODY_PART_HEADER_SIZE: u64 = 8;
+const VTX_MODEL_LOD_HEADER_SIZE: u64 = 12;
+const VTX_MESH_HEADER_SIZE: u64 = 9;
+
+const STRIP_IS_TRILIST: u8 = 0x01;
+const STRIP_IS_TRISTRIP: u8 = 0x02;
+
+/// The geometry of a single LOD of a studio model.
+pub struct StudioModelLOD {
+  /// The distance factor at which the engine switches to this LOD.
+  pub switch_point: f32,
+  pub vertices: Vec<Vertex>,
+  /// Triangle list indices into `vertices`, keyed by the MDL material index.
+  pub indices: BTreeMap<i32, Vec<u32>>
+}
+
+/// Renderable geometry built from the MDL, VVD and VTX files of a model.
+/// Only the default body group (the first model of every body part) is included.
+pub struct StudioModel {
+  pub header: Header,
+  pub lods: Vec<StudioModelLOD>
+}
+
+impl StudioModel {
+  pub fn load(mdl: &[u8], vvd: &[u8], vtx: &[u8]) -> IOResult<Self> {
+    let mut mdl = Cursor::new(mdl);
+    let mut vvd = Cursor::new(vvd);
+    let mut vtx = Cursor::new(vtx);
+
+    let header = Header::read(&mut mdl)?;
+    if header.id != MDL_ID {
+      return Err(IOError::other("Not a MDL file."));
+    }
+    let vvd_header = VVDHeader::read(&mut vvd)?;
+    if vvd_header.id != VVD_ID {
+      return Err(IOError::other("Not a VVD file."));
+    }
+    let vtx_header = VTXHeader::read(&mut vtx)?;
+    if vvd_header.checksum != header.checksum || vtx_header.checksum != header.checksum {
+      return Err(IOError::new(ErrorKind::InvalidData, "MDL, VVD and VTX checksums don't match."));
+    }
+
+    let vertices = Self::read_vertices(&mut vvd, &vvd_header)?;
+
+    // MDL v49 added topology data to the end of strip groups and strips.
+    let (strip_group_header_size, strip_header_size) = if header.version >= 49 { (33u64, 35u64) } else { (25u64, 27u64) };
+
+    let mut body_part_models = Vec::<Option<(Model, Vec<Mesh>)>>::with_capacity(header.body_part_count as usize);
+    for body_part_index in 0..header.body_part_count as u64 {
+      let body_part_start = header.body_part_offset as u64 + body_part_index * BODY_PART_SIZE;
+      mdl.seek(SeekFrom::Start(body_part_start))?;
+      let body_part = BodyPart::read(&mut mdl)?;
+      if body_part.models_count <= 0 {
+        body_part_models.push(None);
+        continue;
+      }
+
+      let model_start = body_part_start + body_part.model_index;
+      mdl.seek(SeekFrom::Start(model_start))?;
+      let model = Model::read(&mut mdl)?;
+      let mut meshes = Vec::<Mesh>::with_capacity(model.meshes_count as usize);
+      for mesh_index in 0..model.meshes_count as u64 {
+        mdl.seek(SeekFrom::Start(model_start + model.mesh_index + mesh_index * MESH_SIZE))?;
+        meshes.push(Mesh::read(&mut mdl)?);
+      }
+      body_part_models.push(Some((model, meshes)));
+    }
+
+    let mut lods = Vec::<StudioModelLOD>::with_capacity(vtx_header.lods_count.max(0) as usize);
+    for lod_index in 0..vtx_header.lods_count.max(0) as u64 {
+      let mut lod = StudioModelLOD {
+        switch_point: 0f32,
+        vertices: Vec::new(),
+        indices: BTreeMap::new()
+      };
+
+      for (body_part_index, body_part_model) in body_part_models.iter().enumerate() {
+        if body_part_model.is_none() || body_part_index as i32 >= vtx_header.body_parts_count {
+          continue;
+        }
+        let (model, meshes) = body_part_model.as_ref().unwrap();
+
+        let body_part_start = vtx_header.body_parts_offset as u64 + body_part_index as u64 * VTX_BODY_PART_HEADER_SIZE;
+        vtx.seek(SeekFrom::Start(body_part_start))?;
+        let vtx_body_part = BodyPartHeader::read(&mut vtx)?;
+        if vtx_body_part.models_count <= 0 {
+          continue;
+        }
+
+        let vtx_model_start = body_part_start + vtx_body_part.model_offset as u64;
+        vtx.seek(SeekFrom::Start(vtx_model_start))?;
+        let vtx_model = ModelHeader::read(&mut vtx)?;
+        if lod_index as i32 >= vtx_model.lods_count {
+          continue;
+        }
+
+        let lod_start = vtx_model_start + vtx_model.lod_offset as u64 + lod_index * VTX_MODEL_LOD_HEADER_SIZE;
+        vtx.seek(SeekFrom::Start(lod_start))?;
+        let vtx_lod = ModelLODHeader::read(&mut vtx)?;
+        lod.switch_point = vtx_lod.switch_point;
+
+        let model_first_vertex = model.vertex_index as u32 / Vertex::SIZE;
+        for (mesh_index, mesh) in meshes.iter().enumerate().take(vtx_lod.meshes_count.max(0) as usize) {
+          let mesh_start = lod_start + vtx_lod.mesh_offset as u64 + mesh_index as u64 * VTX_MESH_HEADER_SIZE;
+          vtx.seek(SeekFrom::Start(mesh_start))?;
+          let vtx_mesh = MeshHeader::read(&mut vtx)?;
+          let mesh_first_vertex = model_first_vertex as usize + mesh.vertex_offset as usize;
+          let indices = lod.indices.entry(mesh.material).or_default();
+
+          for strip_group_index in 0..vtx_mesh.strip_groups_count.max(0) as u64 {
+            let strip_group_start = mesh_start + vtx_mesh.strip_group_header_offset as u64 + strip_group_index * strip_group_header_size;
+            vtx.seek(SeekFrom::Start(strip_group_start))?;
+            let strip_group = StripGroupHeader::read(&mut vtx)?;
+
+            let base_vertex = lod.vertices.len() as u32;
+            vtx.seek(SeekFrom::Start(strip_group_start + strip_group.vert_offset as u64))?;
+            for _ in 0..strip_group.verts_count {
+              let vtx_vertex = VTXVertex::read(&mut vtx)?;
+              let vertex = vertices.get(mesh_first_vertex + vtx_vertex.orig_mesh_vert_id as usize)
+                .ok_or_else(|| IOError::new(ErrorKind::InvalidData, "VTX vertex references a vertex outside of the VVD."))?;
+              lod.vertices.push(vertex.clone());
+            }
+
+            vtx.seek(SeekFrom::Start(strip_group_start + strip_group.indices_offset as u64))?;
+            let mut strip_group_indices = Vec::<u32>::with_capacity(strip_group.indices_count.max(0) as usize);
+            for _ in 0..strip_group.indices_count {
+              let index = vtx.read_u16()? as u32;
+              if index >= strip_group.verts_count as u32 {
+                return Err(IOError::new(ErrorKind::InvalidData, "Strip group index is out of bounds."));
+              }
+              strip_group_indices.push(base_vertex + index);
+            }
+
+            for strip_index in 0..strip_group.strips_count.max(0) as u64 {
+              vtx.seek(SeekFrom::Start(strip_group_start + strip_group.strips_offset as u64 + strip_index * strip_header_size))?;
+              let strip = StripHeader::read(&mut vtx)?;
+              let start = strip.index_offset.max(0) as usize;
+              let end = start + strip.indices_count.max(0) as usize;
+              if end > strip_group_indices.len() {
+                return Err(IOError::new(ErrorKind::InvalidData, "Strip indices are out of bounds."));
+              }
+              let strip_indices = &strip_group_indices[start .. end];
+
+              if strip.flags & STRIP_IS_TRISTRIP != 0 {
+                for i in 2..strip_indices.len() {
+                  let (a, b, c) = (strip_indices[i - 2], strip_indices[i - 1], strip_indices[i]);
+                  if a == b || b == c || a == c {
+                    // Degenerate triangle used to stitch strips together
+                    continue;
+                  }
+                  if i % 2 == 0 {
+                    indices.extend_from_slice(&[a, b, c]);
+                  } else {
+                    indices.extend_from_slice(&[b, a, c]);
+                  }
+                }
+              } else {
+                debug_assert!(strip.flags & STRIP_IS_TRILIST != 0 || strip.flags == 0);
+                indices.extend_from_slice(strip_indices);
+              }
+            }
+          }
+        }
+      }
+
+      lod.indices.retain(|_, indices| !indices.is_empty());
+      lods.push(lod);
+    }
+
+    Ok(Self {
+      header,
+      lods
+    })
+  }
+
+  /// Reads the VVD vertices and brings them into the order the meshes of the root LOD expect.
+  fn read_vertices(vvd: &mut Cursor<&[u8]>, vvd_header: &VVDHeader) -> IOResult<Vec<Vertex>> {
+    let vertices_count = vvd_header.lod_vertexes_count[0].max(0) as usize;
+    let mut vertices = Vec::<Vertex>::with_capacity(vertices_count);
+    vvd.seek(SeekFrom::Start(vvd_header.vertex_data_start as u64))?;
+    for _ in 0..vertices_count {
+      vertices.push(Vertex::read(vvd)?);
+    }
+
+    let mut fixups = Vec::<VertexFileFixup>::with_capacity(vvd_header.fixups_count.max(0) as usize);
+    vvd.seek(SeekFrom::Start(vvd_header.fixup_table_start as u64))?;
+    for _ in 0..vvd_header.fixups_count {
+      fixups.push(VertexFileFixup::read(vvd)?);
+    }
+
+    // Every LOD indexes into the vertices of the root LOD, the lower LODs just use fewer of them.
+    Ok(VertexFileFixup::apply(&fixups, &vertices, 0))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const CHECKSUM: i32 = 42;
+
+  #[derive(Default)]
+  struct Writer {
+    data: Vec<u8>
+  }
+
+  impl Writer {
+    fn pos(&self) -> i32 {
+      self.data.len() as i32
+    }
+    fn i32(&mut self, value: i32) {
+      self.data.extend_from_slice(&value.to_le_bytes());
+    }
+    fn f32(&mut self, value: f32) {
+      self.data.extend_from_slice(&value.to_le_bytes());
+    }
+    fn u16(&mut self, value: u16) {
+      self.data.extend_from_slice(&value.to_le_bytes());
+    }
+    fn zeroes(&mut self, count: usize) {
+      self.data.resize(self.data.len() + count, 0);
+    }
+    fn patch_i32(&mut self, at: i32, value: i32) {
+      self.data[at as usize .. at as usize + 4].copy_from_slice(&value.to_le_bytes());
+    }
+  }
+
+  /// One body part with one model and a single mesh that uses material 0.
+  fn build_mdl() -> Vec<u8> {
+    let mut mdl = Writer::default();
+    mdl.i32(MDL_ID);
+    mdl.i32(48);
+    mdl.i32(CHECKSUM);
+    mdl.zeroes(232 - 12);
+    mdl.i32(1); // body part count
+    mdl.i32(400); // body part offset
+    mdl.zeroes(400 - 240);
+
+    let body_part_start = mdl.pos();
+    mdl.i32(0);
+    mdl.i32(1); // models count
+    mdl.i32(1);
+    mdl.i32(BODY_PART_SIZE as i32); // model offset
+
+    let model_start = mdl.pos();
+    mdl.zeroes(64);
+    mdl.i32(0);
+    mdl.f32(0f32);
+    mdl.i32(1); // meshes count
+    mdl.i32(140); // mesh offset
+    mdl.i32(4); // vertices count
+    mdl.i32(0); // vertex offset
+    mdl.zeroes(140 - 88);
+    assert_eq!(mdl.pos() - model_start, 140);
+    assert_eq!(model_start - body_part_start, BODY_PART_SIZE as i32);
+
+    mdl.i32(0); // material
+    mdl.zeroes(MESH_SIZE as usize - 4);
+    mdl.data
+  }
+
+  /// Stores the vertices out of order and uses the fixup table to bring them back into LOD order.
+  fn build_vvd() -> Vec<u8> {
+    let mut vvd = Writer::default();
+    vvd.i32(VVD_ID);
+    vvd.i32(4);
+    vvd.i32(CHECKSUM);
+    vvd.i32(2); // LOD count
+    vvd.i32(4);
+    vvd.i32(3);
+    vvd.zeroes(6 * 4);
+    vvd.i32(2); // fixups count
+    vvd.i32(64); // fixup table start
+    vvd.i32(64 + 2 * 12); // vertex data start
+    vvd.i32(0);
+
+    // The vertices used by both LODs are stored after the ones that only LOD 0 uses.
+    vvd.i32(1); // LOD
+    vvd.i32(2); // source vertex id
+    vvd.i32(2); // vertices count
+    vvd.i32(0);
+    vvd.i32(0);
+    vvd.i32(2);
+
+    for i in 0..4 {
+      vvd.zeroes(16);
+      vvd.f32(i as f32);
+      vvd.zeroes(48 - 20);
+    }
+    vvd.data
+  }
+
+  /// LOD 0 is a quad made of 4 vertices, LOD 1 a single triangle made of 3 vertices.
+  fn build_vtx() -> Vec<u8> {
+    let lods: [(&[u16], &[u16]); 2] = [
+      (&[0, 1, 2, 3], &[0, 1, 2, 0, 2, 3]),
+      (&[0, 1, 2], &[0, 1, 2])
+    ];
+
+    let mut vtx = Writer::default();
+    vtx.i32(7);
+    vtx.i32(24);
+    vtx.u16(53);
+    vtx.u16(9);
+    vtx.i32(3);
+    vtx.i32(CHECKSUM);
+    vtx.i32(lods.len() as i32);
+    vtx.i32(0);
+    vtx.i32(1); // body parts count
+    vtx.i32(36); // body parts offset
+
+    vtx.i32(1); // models count
+    vtx.i32(8); // model offset
+    vtx.i32(lods.len() as i32);
+    vtx.i32(8); // LOD offset
+
+    let lod_start = vtx.pos();
+    for _ in 0..lods.len() {
+      vtx.i32(1); // meshes count
+      vtx.i32(0); // patched below
+      vtx.f32(0f32);
+    }
+
+    for (lod_index, (vertices, indices)) in lods.iter().enumerate() {
+      let lod_header = lod_start + lod_index as i32 * VTX_MODEL_LOD_HEADER_SIZE as i32;
+      let mesh_start = vtx.pos();
+      vtx.patch_i32(lod_header + 4, mesh_start - lod_header);
+      vtx.i32(1); // strip groups count
+      vtx.i32(VTX_MESH_HEADER_SIZE as i32);
+      vtx.data.push(0);
+
+      let strip_group_start = vtx.pos();
+      vtx.zeroes(25);
+      let verts_offset = vtx.pos();
+      for vertex in vertices.iter() {
+        vtx.zeroes(4);
+        vtx.u16(*vertex);
+        vtx.zeroes(3);
+      }
+      let indices_offset = vtx.pos();
+      for index in indices.iter() {
+        vtx.u16(*index);
+      }
+      let strip_offset = vtx.pos();
+      vtx.i32(indices.len() as i32);
+      vtx.i32(0);
+      vtx.i32(vertices.len() as i32);
+      vtx.i32(0);
+      vtx.u16(0);
+      vtx.data.push(STRIP_IS_TRILIST);
+      vtx.zeroes(8);
+
+      vtx.patch_i32(strip_group_start, vertices.len() as i32);
+      vtx.patch_i32(strip_group_start + 4, verts_offset - strip_group_start);
+      vtx.patch_i32(strip_group_start + 8, indices.len() as i32);
+      vtx.patch_i32(strip_group_start + 12, indices_offset - strip_group_start);
+      vtx.patch_i32(strip_group_start + 16, 1);
+      vtx.patch_i32(strip_group_start + 20, strip_offset - strip_group_start);
+    }
+    // The strip group header reads one byte past its size.
+    vtx.zeroes(1);
+    vtx.data
+  }
+
+  #[test]
+  fn vertex_counts_per_lod() {
+    let model = StudioModel::load(&build_mdl(), &build_vvd(), &build_vtx()).unwrap();
+    assert_eq!(model.lods.len(), 2);
+
+    assert_eq!(model.lods[0].vertices.len(), 4);
+    assert_eq!(model.lods[0].indices[&0], vec![0, 1, 2, 0, 2, 3]);
+    assert_eq!(model.lods[1].vertices.len(), 3);
+    assert_eq!(model.lods[1].indices[&0], vec![0, 1, 2]);
+
+    // The fixups moved the vertices that were stored at 2 and 3 to the front.
+    let positions: Vec<f32> = model.lods[0].vertices.iter().map(|v| v.vec_position.x).collect();
+    assert_eq!(positions, vec![2f32, 3f32, 0f32, 1f32]);
+    let positions: Vec<f32> = model.lods[1].vertices.iter().map(|v| v.vec_position.x).collect();
+    assert_eq!(positions, vec![2f32, 3f32, 0f32]);
+  }
+
+  #[test]
+  fn mismatched_checksum() {
+    let mut vvd = build_vvd();
+    vvd[8] = 0;
+    assert_eq!(StudioModel::load(&build_mdl(), &vvd, &build_vtx()).err().unwrap().kind(), ErrorKind::InvalidData);
+  }
+}
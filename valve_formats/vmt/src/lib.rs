@@ -1,8 +1,10 @@
 use io_util;
 
 use std::collections::HashMap;
+use std::iter::Peekable;
 use std::io::{Read, Error as IOError};
 use io_util::RawDataRead;
+use bevy_math::Vec3;
 
 pub const SHADER_LIGHT_MAPPED_GENERIC: &str = "lightmappedgeneric";
 pub const SHADER_VERTEX_LIT_GENERIC: &str = "vertexlitgeneric";
@@ -10,86 +12,154 @@ pub const SHADER_UNLIT_GENERIC: &str = "unlitgeneric";
 pub const SHADER_ENVMAP_TINT: &str = "envmaptint";
 pub const SHADER_WORLD_VERTEX_TRANSITION: &str = "worldvertextransition";
 pub const SHADER_WATER: &str = "water";
+pub const SUPPORTED_SHADERS: &[&str] = &[
+  SHADER_LIGHT_MAPPED_GENERIC,
+  SHADER_VERTEX_LIT_GENERIC,
+  SHADER_UNLIT_GENERIC,
+  SHADER_ENVMAP_TINT,
+  SHADER_WORLD_VERTEX_TRANSITION,
+  SHADER_WATER
+];
 pub const BASE_TEXTURE_NAME: &str = "basetexture";
 pub const PATCH: &str = "patch";
 pub const PATCH_INCLUDE: &str = "include";
-#[allow(dead_code)]
 const PATCH_INSERT: &str = "insert";
+const PATCH_REPLACE: &str = "replace";
 
 #[derive(Debug)]
 pub enum VMTError {
   IOError(IOError),
-  FileError(String)
+  FileError(String),
+  UnsupportedShader(String)
+}
+
+/// A value or a nested block of a material, for example `Proxies { ... }`.
+/// The children of a block keep their order and duplicate keys, a material can use the same proxy more than once.
+#[derive(Debug, Clone)]
+pub enum VMTBlock {
+  Value(String),
+  Block(Vec<(String, VMTBlock)>)
+}
+
+impl VMTBlock {
+  /// The first child with the key.
+  pub fn get(&self, key: &str) -> Option<&VMTBlock> {
+    self.get_all(key).next()
+  }
+
+  /// All children with the key in the order they appear in the material.
+  pub fn get_all<'a>(&'a self, key: &str) -> impl Iterator<Item = &'a VMTBlock> + 'a {
+    let key = normalize_key(key);
+    let children: &'a [(String, VMTBlock)] = match self {
+      VMTBlock::Block(children) => children,
+      VMTBlock::Value(_) => &[]
+    };
+    children.iter()
+      .filter(move |(child_key, _)| *child_key == key)
+      .map(|(_, child)| child)
+  }
+
+  pub fn as_value(&self) -> Option<&str> {
+    match self {
+      VMTBlock::Value(value) => Some(value.as_str()),
+      VMTBlock::Block(_) => None
+    }
+  }
+
+  pub fn as_block(&self) -> Option<&[(String, VMTBlock)]> {
+    match self {
+      VMTBlock::Block(children) => Some(children),
+      VMTBlock::Value(_) => None
+    }
+  }
 }
 
 pub struct VMTMaterial {
   shader_name: String,
-  values: HashMap<String, String>
+  values: HashMap<String, String>,
+  blocks: HashMap<String, VMTBlock>
 }
 
 impl VMTMaterial {
   pub fn new(reader: &mut dyn Read, length: u32) -> Result<Self, VMTError> {
-    let mut values = HashMap::<String, String>::new();
-
     let data = reader.read_data(length as usize).map_err(VMTError::IOError)?;
-    let mut text = String::from_utf8(data.to_vec()).map_err(|_e| VMTError::FileError("Could not read text".to_string()))?;
-    text = text.replace("\r\n", "\n");
-    text = text.replace('\t', " ");
-    text = text.trim_end_matches('\0').to_string();
-    let block_start = text.find('{').ok_or_else(|| VMTError::FileError("Could not find start of material block".to_string()))?;
-
-    let mut shader_name: String = String::new();
-    let shader_name_lines = (&text[..block_start]).split('\n');
-    for line in shader_name_lines {
-      let line = remove_line_comments(line);
-      if !line.trim().is_empty() {
-        shader_name = line.replace("\"", "").trim().to_lowercase();
-      }
-    }
+    let text = String::from_utf8(data.to_vec()).map_err(|_e| VMTError::FileError("Could not read text".to_string()))?;
+    let text = text.trim_end_matches('\0');
 
-    if shader_name != SHADER_LIGHT_MAPPED_GENERIC
-      && shader_name != PATCH
-      && shader_name != SHADER_UNLIT_GENERIC
-      && shader_name != SHADER_VERTEX_LIT_GENERIC
-      && shader_name != SHADER_ENVMAP_TINT
-      && shader_name != SHADER_WORLD_VERTEX_TRANSITION
-      && shader_name != SHADER_WATER {
-      println!("Found unsupported shader: \"{}\"", shader_name);
+    let tokens = tokenize(text);
+    let mut tokens_iter = tokens.iter().peekable();
+    let shader_name = match tokens_iter.next() {
+      Some(Token::String { text, .. }) => text.to_lowercase(),
+      _ => return Err(VMTError::FileError("Could not find shader name".to_string()))
+    };
+    if !matches!(tokens_iter.next(), Some(Token::BlockStart)) {
+      return Err(VMTError::FileError("Could not find start of material block".to_string()));
     }
+    let root = parse_block(&mut tokens_iter)?;
 
-    let block_end = text.find('}').ok_or_else(|| VMTError::FileError("Could not find end of material block".to_string()))?;
-    let block = &text[block_start .. block_end];
-    let lines = block.split('\n');
-    for line in lines {
-      let trimmed_line = line.trim().replace(&['$', '%', '"', '\''][..], "");
-      if trimmed_line.is_empty() || trimmed_line == "{" || trimmed_line == "}" || trimmed_line == PATCH_INCLUDE {
-        continue;
-      }
-
-      let key_end_opt = trimmed_line.find(' ');
-      if key_end_opt.is_none() {
-        continue;
+    let mut values = HashMap::<String, String>::new();
+    let mut blocks = HashMap::<String, VMTBlock>::new();
+    for (key, value) in root {
+      match value {
+        VMTBlock::Value(value) => { values.insert(key, value); },
+        block => { blocks.insert(key, block); }
       }
-      let key_end = key_end_opt.unwrap();
-      let key = (&trimmed_line[.. key_end]).trim().to_lowercase();
-      let value = remove_line_comments(&trimmed_line[key_end + 1 ..]).trim().to_string();
-      values.insert(key, value);
     }
 
     Ok(Self {
       shader_name,
-      values
+      values,
+      blocks
     })
   }
 
+  /// The key is matched like the keys of the material, case insensitive and without the leading `$` or `%`.
   pub fn get_value(&self, key: &str) -> Option<&str> {
-    self.values.get(key).map(|v| v.as_str())
+    self.values.get(&normalize_key(key)).map(|v| v.as_str())
+  }
+
+  /// Source writes booleans as 1 or 0, sometimes quoted.
+  pub fn get_bool(&self, key: &str) -> Option<bool> {
+    self.get_value(key).and_then(parse_bool)
+  }
+
+  pub fn get_int(&self, key: &str) -> Option<i32> {
+    self.get_value(key).and_then(|value| trim_quotes(value).parse::<i32>().ok())
+  }
+
+  pub fn get_f32(&self, key: &str) -> Option<f32> {
+    self.get_value(key).and_then(|value| trim_quotes(value).parse::<f32>().ok())
+  }
+
+  /// Parses vectors written as "[x y z]" or colors written as "{r g b}".
+  /// The components of the curly brace form are in the 0-255 range and get normalized.
+  pub fn get_vec3(&self, key: &str) -> Option<Vec3> {
+    self.get_value(key).and_then(parse_vec3)
+  }
+
+  pub fn get_block(&self, name: &str) -> Option<&VMTBlock> {
+    self.blocks.get(&normalize_key(name))
   }
 
   pub fn get_shader(&self) -> &str {
     self.shader_name.as_str()
   }
 
+  /// Patches are always supported because they get applied to their base material.
+  pub fn is_supported_shader(&self) -> bool {
+    self.is_patch() || SUPPORTED_SHADERS.contains(&self.shader_name.as_str())
+  }
+
+  /// Materials with unknown shaders still get parsed so their values can be inspected.
+  /// Callers that need to render the material can use this to decide whether to fall back to a default material.
+  pub fn check_shader_supported(&self) -> Result<(), VMTError> {
+    if self.is_supported_shader() {
+      Ok(())
+    } else {
+      Err(VMTError::UnsupportedShader(self.shader_name.clone()))
+    }
+  }
+
   pub fn get_base_texture_name(&self) -> Option<&str> {
     self.get_value(BASE_TEXTURE_NAME)
   }
@@ -102,19 +172,280 @@ impl VMTMaterial {
     self.shader_name == PATCH
   }
 
-  pub fn apply_patch(&mut self, patch: &VMTMaterial) {
+  /// The normalized path of the material that the patch gets applied to, None if the material isn't a patch.
+  /// That's the path `resolve` passes to its callback.
+  pub fn patch_base_path(&self) -> Option<String> {
+    if !self.is_patch() {
+      return None;
+    }
+    self.get_patch_base().map(|base_path| trim_quotes(base_path).replace('\\', "/").to_lowercase())
+  }
+
+  /// Applies the insert and replace sections of the patch.
+  /// Inserted keys are always written, replaced keys are only written if the material already contains them.
+  /// All other keys of the patch are ignored, just like in Source.
+  pub fn apply_patch(&mut self, patch: &VMTMaterial) -> Result<(), VMTError> {
     if !patch.is_patch() {
-      panic!("Material must be a patch");
+      return Err(VMTError::FileError(format!("Material must be a patch, found shader: {}", patch.shader_name)));
+    }
+
+    for section in &[PATCH_INSERT, PATCH_REPLACE] {
+      let section_block = patch.get_block(section).and_then(|block| block.as_block());
+      if section_block.is_none() {
+        continue;
+      }
+      for (key, value) in section_block.unwrap() {
+        let exists = self.values.contains_key(key) || self.blocks.contains_key(key);
+        if *section == PATCH_REPLACE && !exists {
+          continue;
+        }
+        match value {
+          VMTBlock::Value(value) => {
+            self.blocks.remove(key);
+            self.values.insert(key.clone(), value.clone());
+          },
+          block => {
+            self.values.remove(key);
+            self.blocks.insert(key.clone(), block.clone());
+          }
+        }
+      }
+    }
+    Ok(())
+  }
+
+  /// Turns a patch into a regular material by loading its base with the callback and applying the patch.
+  /// Bases that are patches themselves get resolved too. Materials that aren't patches are returned as is.
+  pub fn resolve(self, mut reader_for: impl FnMut(&str) -> Option<Vec<u8>>) -> Result<VMTMaterial, VMTError> {
+    let mut visited = Vec::<String>::new();
+    self.resolve_with_visited(&mut reader_for, &mut visited)
+  }
+
+  fn resolve_with_visited(self, reader_for: &mut dyn FnMut(&str) -> Option<Vec<u8>>, visited: &mut Vec<String>) -> Result<VMTMaterial, VMTError> {
+    if !self.is_patch() {
+      return Ok(self);
+    }
+
+    let base_path = self.patch_base_path()
+      .ok_or_else(|| VMTError::FileError("Patch material has no include".to_string()))?;
+    if visited.contains(&base_path) {
+      return Err(VMTError::FileError(format!("Patch cycle detected at: {}", base_path)));
     }
 
-    self.values.extend(patch.values.iter().map(|(key, value)| (key.clone(), value.clone())));
+    let data = reader_for(&base_path)
+      .ok_or_else(|| VMTError::FileError(format!("Could not load patch base: {}", base_path)))?;
+    visited.push(base_path);
+    let base = VMTMaterial::new(&mut &data[..], data.len() as u32)?;
+    let mut base = base.resolve_with_visited(reader_for, visited)?;
+    base.apply_patch(&self)?;
+    Ok(base)
   }
 }
 
-fn remove_line_comments(text: &str) -> &str {
-  let comment_start = text.find("//");
-  if comment_start.is_none() {
-    return text;
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+  BlockStart,
+  BlockEnd,
+  String {
+    text: &'a str,
+    line: u32
+  }
+}
+
+/// Splits the material into strings and braces.
+/// Comments and braces are only recognized outside of quotes, so quoted values
+/// can contain `//` or colors like "{255 255 255}".
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+  let mut tokens = Vec::<Token>::new();
+  let bytes = text.as_bytes();
+  let mut line = 0u32;
+  let mut index = 0usize;
+  while index < bytes.len() {
+    match bytes[index] {
+      b'\n' => {
+        line += 1;
+        index += 1;
+      }
+      b' ' | b'\t' | b'\r' => {
+        index += 1;
+      }
+      b'/' if bytes.get(index + 1) == Some(&b'/') => {
+        while index < bytes.len() && bytes[index] != b'\n' {
+          index += 1;
+        }
+      }
+      b'{' => {
+        tokens.push(Token::BlockStart);
+        index += 1;
+      }
+      b'}' => {
+        tokens.push(Token::BlockEnd);
+        index += 1;
+      }
+      b'"' => {
+        let start = index + 1;
+        let mut end = start;
+        while end < bytes.len() && bytes[end] != b'"' && bytes[end] != b'\n' {
+          end += 1;
+        }
+        tokens.push(Token::String { text: &text[start..end], line });
+        // Skip the closing quote, an unterminated string ends at the line break.
+        index = if end < bytes.len() && bytes[end] == b'"' { end + 1 } else { end };
+      }
+      _ => {
+        let start = index;
+        while index < bytes.len() && !matches!(bytes[index], b' ' | b'\t' | b'\r' | b'\n' | b'{' | b'}' | b'"') {
+          index += 1;
+        }
+        tokens.push(Token::String { text: &text[start..index], line });
+      }
+    }
+  }
+  tokens
+}
+
+fn normalize_key(key: &str) -> String {
+  key.trim().trim_start_matches(&['$', '%'][..]).to_lowercase()
+}
+
+fn parse_block<'a>(tokens: &mut Peekable<impl Iterator<Item = &'a Token<'a>>>) -> Result<Vec<(String, VMTBlock)>, VMTError> {
+  let mut values = Vec::<(String, VMTBlock)>::new();
+  while let Some(token) = tokens.next() {
+    match token {
+      Token::BlockEnd => {
+        return Ok(values);
+      }
+      Token::BlockStart => {
+        // A block without a name, keep it so the braces stay balanced.
+        let block = parse_block(tokens)?;
+        values.push((String::new(), VMTBlock::Block(block)));
+      }
+      Token::String { text: key, line: key_line } => {
+        let key = normalize_key(key);
+        match tokens.peek() {
+          Some(Token::BlockStart) => {
+            tokens.next();
+            let block = parse_block(tokens)?;
+            values.push((key, VMTBlock::Block(block)));
+          }
+          Some(Token::String { text: value, line: value_line }) if value_line == key_line => {
+            values.push((key, VMTBlock::Value(value.trim().to_string())));
+            tokens.next();
+          }
+          _ => {
+            // A key without a value.
+            values.push((key, VMTBlock::Value(String::new())));
+          }
+        }
+      }
+    }
+  }
+  Err(VMTError::FileError("Could not find end of material block".to_string()))
+}
+
+fn trim_quotes(value: &str) -> &str {
+  value.trim().trim_matches(&['"', '\''][..]).trim()
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+  let value = trim_quotes(value);
+  match value.to_lowercase().as_str() {
+    "1" | "true" => Some(true),
+    "0" | "false" => Some(false),
+    _ => value.parse::<f32>().ok().map(|number| number != 0f32)
+  }
+}
+
+fn parse_vec3(value: &str) -> Option<Vec3> {
+  let value = trim_quotes(value);
+  let (inner, scale) = if let Some(inner) = value.strip_prefix('{') {
+    (inner.strip_suffix('}')?, 1f32 / 255f32)
+  } else if let Some(inner) = value.strip_prefix('[') {
+    (inner.strip_suffix(']')?, 1f32)
+  } else {
+    // A single number is used for all components.
+    let scalar = value.parse::<f32>().ok()?;
+    return Some(Vec3::splat(scalar));
+  };
+
+  let mut components = [0f32; 3];
+  let mut count = 0usize;
+  for component in inner.split_whitespace() {
+    if count == components.len() {
+      return None;
+    }
+    components[count] = component.parse::<f32>().ok()?;
+    count += 1;
+  }
+  if count != components.len() {
+    return None;
+  }
+  Some(Vec3::from_array(components) * scale)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn tokenize_comment_in_quotes() {
+    let tokens = tokenize("\"$basetexture\" \"http://example//path\" // comment\n}");
+    assert_eq!(tokens, vec![
+      Token::String { text: "$basetexture", line: 0 },
+      Token::String { text: "http://example//path", line: 0 },
+      Token::BlockEnd
+    ]);
+  }
+
+  #[test]
+  fn tokenize_braces_in_quotes() {
+    let tokens = tokenize("LightmappedGeneric\n{\n$color \"{255 0 0}\"// comment\n}");
+    assert_eq!(tokens, vec![
+      Token::String { text: "LightmappedGeneric", line: 0 },
+      Token::BlockStart,
+      Token::String { text: "$color", line: 2 },
+      Token::String { text: "{255 0 0}", line: 2 },
+      Token::BlockEnd
+    ]);
+  }
+
+  fn material(text: &str) -> VMTMaterial {
+    VMTMaterial::new(&mut text.as_bytes(), text.len() as u32).unwrap()
+  }
+
+  #[test]
+  fn resolve_patch() {
+    let patch = material("patch\n{\ninclude \"materials/base.vmt\"\ninsert\n{\n$envmap env_cubemap\n}\nreplace\n{\n$basetexture patched\n$detail ignored\n}\n}");
+    let resolved = patch.resolve(|path| {
+      assert_eq!(path, "materials/base.vmt");
+      Some(b"LightmappedGeneric\n{\n$basetexture base\n}".to_vec())
+    }).unwrap();
+    assert_eq!(resolved.get_shader(), SHADER_LIGHT_MAPPED_GENERIC);
+    assert_eq!(resolved.get_value("basetexture"), Some("patched"));
+    assert_eq!(resolved.get_value("envmap"), Some("env_cubemap"));
+    assert_eq!(resolved.get_value("detail"), None);
+  }
+
+  #[test]
+  fn apply_non_patch() {
+    let mut base = material("LightmappedGeneric\n{\n$basetexture base\n}");
+    let other = material("UnlitGeneric\n{\n$basetexture other\n}");
+    assert!(matches!(base.apply_patch(&other), Err(VMTError::FileError(_))));
+    assert_eq!(base.get_value("basetexture"), Some("base"));
+  }
+
+  #[test]
+  fn repeated_proxies() {
+    let material = material("VertexLitGeneric\n{\n$BaseTexture base\nProxies\n{\nSine\n{\nsinemin 0\nresultVar $alpha\n}\nSine\n{\nsinemin 1\nresultVar \"$color[0]\"\n}\n}\n}");
+    assert_eq!(material.get_value("$basetexture"), Some("base"));
+    assert_eq!(material.get_value("%BaseTexture"), Some("base"));
+
+    let proxies = material.get_block("Proxies").unwrap();
+    assert_eq!(proxies.as_block().unwrap().len(), 2);
+    let results: Vec<&str> = proxies.get_all("sine")
+      .map(|proxy| proxy.get("resultvar").and_then(|value| value.as_value()).unwrap())
+      .collect();
+    assert_eq!(results, vec!["$alpha", "$color[0]"]);
+    assert_eq!(proxies.get("Sine").and_then(|proxy| proxy.get("$sinemin")).and_then(|value| value.as_value()), Some("0"));
   }
-  &text[..comment_start.unwrap()]
 }
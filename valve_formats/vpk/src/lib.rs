@@ -37,7 +37,10 @@ mod utilities;
 mod package;
 mod archive_md5_section_entry;
 mod package_entry;
+mod package_builder;
 
 pub use self::package::Package;
 pub use self::package::PackageError;
+pub use self::package::PackageEntryReader;
 pub use self::package_entry::PackageEntry;
+pub use self::package_builder::PackageBuilder;
@@ -1,9 +1,8 @@
-use std::io::{Read, BufReader, Seek, Error as IOError, Result as IOResult, SeekFrom};
+use std::io::{Read, BufReader, Seek, Error as IOError, ErrorKind, Result as IOResult, SeekFrom};
 use package_entry::PackageEntry;
 use std::collections::HashMap;
 use archive_md5_section_entry::ArchiveMD5SectionEntry;
 use io_util::{PrimitiveRead, StringRead, StringReadError, RawDataRead};
-use crc::{self, Crc};
 use utilities::AsnKeyParser;
 use rsa::{BigUint, Pkcs1v15Encrypt};
 use rand::rngs::OsRng;
@@ -12,7 +11,9 @@ use std::sync::Mutex;
 #[derive(Debug)]
 pub enum PackageError {
   IOError(IOError),
-  FileError(String)
+  FileError(String),
+  /// The checksum of the contained file didn't match. Contains the path of the file or archive.
+  ChecksumMismatch(String)
 }
 
 pub struct Package<R>
@@ -70,6 +71,9 @@ pub const MAGIC: u32 = 0x55AA1234;
 /// Always '/' as per Valve's vpk implementation.
 pub const DIRECTORY_SEPARATOR: &str = "/";
 
+/// Valve stores files without a directory or an extension with a space as the name of the tree node.
+pub(crate) const EMPTY_NAME: &str = " ";
+
 impl<R> Package<R>
   where R : Read + Seek {
   /// Gets the File Name
@@ -261,6 +265,7 @@ impl<R> Package<R>
   }
 
   pub fn find_entry_in_dir_with_extension(&self, directory: &str, file_name: &str, file_extension: &str) -> Option<&PackageEntry> {
+    let file_extension = if file_extension.is_empty() { EMPTY_NAME } else { file_extension };
     if !self.entries.contains_key(file_extension) {
       return None;
     }
@@ -273,12 +278,22 @@ impl<R> Package<R>
 
     // If the directory is empty after trimming, set it to a space to match Valve's behaviour
     if trimmed_directory.is_empty() {
-      trimmed_directory = " ";
+      trimmed_directory = EMPTY_NAME;
     }
 
     self.entries[file_extension].iter().find(|x| x.directory_name.as_str() == trimmed_directory && x.file_name.as_str() == file_name)
   }
 
+  /// Opens a reader for the entry that only reads the requested parts of the file.
+  /// The preload bytes and the data in the archive are exposed as one contiguous stream.
+  pub fn open_entry<'a>(&'a self, entry: &'a PackageEntry) -> PackageEntryReader<'a, R> {
+    PackageEntryReader {
+      package: self,
+      entry,
+      position: 0
+    }
+  }
+
   pub fn read_entry(&self, entry: &PackageEntry, validate_crc: bool) -> Result<Box<[u8]>, PackageError> {
     let output_size = entry.small_data.len() + entry.len as usize;
     let mut output = Vec::with_capacity(output_size);
@@ -306,9 +321,8 @@ impl<R> Package<R>
       }
     }
 
-    let crc = Crc::<u32>::new(&crc::CRC_32_ISCSI);
-    if validate_crc && entry.crc32 != crc.checksum(&output) {
-      return Err(PackageError::FileError("CRC32 mismatch for read data.".to_string()));
+    if validate_crc && !entry.verify_crc(&output) {
+      return Err(PackageError::ChecksumMismatch(entry.full_path()));
     }
 
     Ok(output.into_boxed_slice())
@@ -380,6 +394,22 @@ impl<R> Package<R>
     Ok(type_entries)
   }
 
+  /// Verifies the package checksums, the archive checksums and the CRC32 of every file.
+  /// Returns `PackageError::ChecksumMismatch` with the path of the first file that's corrupt.
+  pub fn verify(&self) -> Result<(), PackageError> {
+    if self.version == 2 {
+      self.verify_hashes()?;
+      self.verify_archive_hashes()?;
+    }
+
+    for entries in self.entries.values() {
+      for entry in entries {
+        self.read_entry(entry, true)?;
+      }
+    }
+    Ok(())
+  }
+
   /// Verify checksums and signatures provided in the VPK
   pub fn verify_hashes(&self) -> Result<(), PackageError> {
     if self.version != 2 {
@@ -389,20 +419,25 @@ impl<R> Package<R>
     {
       let mut reader = self.reader.lock().unwrap();
       reader.seek(SeekFrom::Start(0)).map_err(PackageError::IOError)?;
-      let mut buffer = reader.read_data((self.header_size + self.tree_size + self.file_data_section_size + self.archive_md5_section_size + 32) as usize).map_err(PackageError::IOError)?;
+      let buffer = reader.read_data((self.header_size + self.tree_size + self.file_data_section_size + self.archive_md5_section_size + 32) as usize).map_err(PackageError::IOError)?;
       let mut hash = md5::compute(&buffer);
       if hash.0 != self.whole_file_checksum {
-        return Err(PackageError::FileError(format!("Package checksum mismatch ({:?} != expected {:?}).", &hash, &self.whole_file_checksum)));
+        return Err(PackageError::ChecksumMismatch(format!("{}_dir.vpk", self.file_name)));
       }
 
-      reader.seek(SeekFrom::Start((self.header_size + self.tree_size + self.file_data_section_size) as u64)).map_err(PackageError::IOError)?;
-      reader.read_exact(&mut buffer[..self.archive_md5_section_size as usize]).map_err(PackageError::IOError)?;
-      hash = md5::compute(&buffer[..self.archive_md5_section_size as usize]);
-      if hash.0 != self.whole_file_checksum {
-        return Err(PackageError::FileError(format!("Archive MD5 entries checksum mismatch ({:?} != expected {:?}).", &hash, &self.archive_md5_entries_checksum)));
+      let tree_start = self.header_size as usize;
+      let tree_end = tree_start + self.tree_size as usize;
+      hash = md5::compute(&buffer[tree_start .. tree_end]);
+      if hash.0 != self.tree_checksum {
+        return Err(PackageError::FileError(format!("Tree checksum mismatch ({:?} != expected {:?}).", &hash, &self.tree_checksum)));
       }
 
-      // TODO: verify archive checksums
+      let archive_md5_section_start = tree_end + self.file_data_section_size as usize;
+      let archive_md5_section_end = archive_md5_section_start + self.archive_md5_section_size as usize;
+      hash = md5::compute(&buffer[archive_md5_section_start .. archive_md5_section_end]);
+      if hash.0 != self.archive_md5_entries_checksum {
+        return Err(PackageError::FileError(format!("Archive MD5 entries checksum mismatch ({:?} != expected {:?}).", &hash, &self.archive_md5_entries_checksum)));
+      }
     }
 
     if self.public_key.is_empty() || self.signature.is_empty() {
@@ -416,6 +451,29 @@ impl<R> Package<R>
     Ok(())
   }
 
+  /// Verifies the MD5 checksums of the chunks of the numbered archives.
+  pub fn verify_archive_hashes(&self) -> Result<(), PackageError> {
+    for archive_md5_entry in &self.archive_md5_entries {
+      let data = if archive_md5_entry.archive_index == 0x7FFF {
+        let offset = self.header_size + self.tree_size + archive_md5_entry.offset;
+        let mut reader = self.reader.lock().unwrap();
+        reader.seek(SeekFrom::Start(offset as u64)).map_err(PackageError::IOError)?;
+        reader.read_data(archive_md5_entry.length as usize).map_err(PackageError::IOError)?
+      } else {
+        let mut files = self.archive_files.lock().unwrap();
+        let file = files.get_mut(&(archive_md5_entry.archive_index as u16))
+          .ok_or_else(|| PackageError::FileError(format!("Archive {} is missing.", archive_md5_entry.archive_index)))?;
+        file.seek(SeekFrom::Start(archive_md5_entry.offset as u64)).map_err(PackageError::IOError)?;
+        file.read_data(archive_md5_entry.length as usize).map_err(PackageError::IOError)?
+      };
+
+      if md5::compute(&data).0 != archive_md5_entry.checksum {
+        return Err(PackageError::ChecksumMismatch(format!("{}_{:03}.vpk", self.file_name, archive_md5_entry.archive_index)));
+      }
+    }
+    Ok(())
+  }
+
   pub fn is_signature_valid(&self) -> bool {
     let mut reader = self.reader.lock().unwrap();
     let seek_res = reader.seek(SeekFrom::Start(0));
@@ -503,3 +561,169 @@ impl<R> Package<R>
     Ok((public_key, signature))
   }
 }
+
+pub struct PackageEntryReader<'a, R>
+  where R : Read + Seek {
+  package: &'a Package<R>,
+  entry: &'a PackageEntry,
+  position: u64
+}
+
+impl<R> Read for PackageEntryReader<'_, R>
+  where R : Read + Seek {
+  fn read(&mut self, buf: &mut [u8]) -> IOResult<usize> {
+    let total_len = self.entry.total_len() as u64;
+    if self.position >= total_len || buf.is_empty() {
+      return Ok(0);
+    }
+
+    let small_data_len = self.entry.small_data.len() as u64;
+    if self.position < small_data_len {
+      let start = self.position as usize;
+      let len = buf.len().min(small_data_len as usize - start);
+      buf[.. len].copy_from_slice(&self.entry.small_data[start .. start + len]);
+      self.position += len as u64;
+      return Ok(len);
+    }
+
+    let position_in_archive = self.position - small_data_len;
+    let len = buf.len().min((total_len - self.position) as usize);
+    let buf = &mut buf[.. len];
+    let read = if self.entry.archive_index != 0x7FFF {
+      if !self.package.is_dir_vpk {
+        return Err(IOError::new(ErrorKind::InvalidData, "Given VPK is not a _dir, but entry is referencing an external archive."));
+      }
+
+      let mut files = self.package.archive_files.lock().unwrap();
+      let file = files.get_mut(&self.entry.archive_index)
+        .ok_or_else(|| IOError::new(ErrorKind::NotFound, format!("Archive {} is missing.", self.entry.archive_index)))?;
+      file.seek(SeekFrom::Start(self.entry.offset as u64 + position_in_archive))?;
+      file.read(buf)?
+    } else {
+      let offset = (self.package.header_size + self.package.tree_size + self.entry.offset) as u64 + position_in_archive;
+      let mut reader = self.package.reader.lock().unwrap();
+      reader.seek(SeekFrom::Start(offset))?;
+      reader.read(buf)?
+    };
+    self.position += read as u64;
+    Ok(read)
+  }
+}
+
+impl<R> Seek for PackageEntryReader<'_, R>
+  where R : Read + Seek {
+  fn seek(&mut self, pos: SeekFrom) -> IOResult<u64> {
+    let position = match pos {
+      SeekFrom::Start(offset) => Some(offset),
+      SeekFrom::End(offset) => (self.entry.total_len() as u64).checked_add_signed(offset),
+      SeekFrom::Current(offset) => self.position.checked_add_signed(offset)
+    };
+    if let Some(position) = position {
+      self.position = position;
+      Ok(position)
+    } else {
+      Err(IOError::new(ErrorKind::InvalidInput, "Seeking to a negative position."))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Cursor;
+
+  use super::*;
+
+  const PRELOAD_LEN: usize = 100;
+  const ARCHIVE_PADDING: usize = 16;
+
+  fn test_data(len: usize, seed: u32) -> Vec<u8> {
+    (0..len as u32).map(|i| (i.wrapping_mul(31).wrapping_add(seed) >> 2) as u8).collect()
+  }
+
+  fn write_entry(tree: &mut Vec<u8>, name: &str, data: &[u8], archive_index: u16, offset: u32) {
+    tree.extend_from_slice(name.as_bytes());
+    tree.push(0);
+    tree.extend_from_slice(&PackageEntry::compute_crc(data).to_le_bytes());
+    tree.extend_from_slice(&(PRELOAD_LEN as u16).to_le_bytes());
+    tree.extend_from_slice(&archive_index.to_le_bytes());
+    tree.extend_from_slice(&offset.to_le_bytes());
+    tree.extend_from_slice(&((data.len() - PRELOAD_LEN) as u32).to_le_bytes());
+    tree.extend_from_slice(&0xFFFFu16.to_le_bytes());
+    tree.extend_from_slice(&data[.. PRELOAD_LEN]);
+  }
+
+  /// A VPK v1 with two entries that have preload data, one continues in an archive, the other one in the dir file.
+  fn build_package(archived: &[u8], embedded: &[u8]) -> Package<Cursor<Vec<u8>>> {
+    let mut tree = Vec::<u8>::new();
+    tree.extend_from_slice(b"bin\0data\0");
+    write_entry(&mut tree, "archived", archived, 0, ARCHIVE_PADDING as u32);
+    write_entry(&mut tree, "embedded", embedded, 0x7FFF, 0);
+    tree.extend_from_slice(&[0, 0, 0]);
+
+    let mut dir = Vec::<u8>::new();
+    dir.extend_from_slice(&MAGIC.to_le_bytes());
+    dir.extend_from_slice(&1u32.to_le_bytes());
+    dir.extend_from_slice(&(tree.len() as u32).to_le_bytes());
+    dir.extend_from_slice(&tree);
+    dir.extend_from_slice(&embedded[PRELOAD_LEN ..]);
+
+    let mut archive = vec![0xFFu8; ARCHIVE_PADDING];
+    archive.extend_from_slice(&archived[PRELOAD_LEN ..]);
+    archive.extend_from_slice(&[0xFFu8; ARCHIVE_PADDING]);
+
+    Package::read("pak01_dir.vpk", Cursor::new(dir), move |name| {
+      if name == "pak01_000.vpk" {
+        Ok(Cursor::new(archive.clone()))
+      } else {
+        Err(IOError::new(ErrorKind::NotFound, name.to_string()))
+      }
+    }).unwrap()
+  }
+
+  fn read_chunked(reader: &mut impl Read) -> Vec<u8> {
+    let mut output = Vec::<u8>::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+      let read = reader.read(&mut chunk).unwrap();
+      if read == 0 {
+        break;
+      }
+      output.extend_from_slice(&chunk[.. read]);
+    }
+    output
+  }
+
+  #[test]
+  fn open_entry_in_chunks() {
+    let archived = test_data(10_000, 1);
+    let embedded = test_data(5_000, 7);
+    let package = build_package(&archived, &embedded);
+
+    for (path, data) in [("data/archived.bin", &archived), ("data/embedded.bin", &embedded)] {
+      let entry = package.find_entry(path).unwrap();
+      let full = package.read_entry(entry, true).unwrap();
+      assert_eq!(&full[..], &data[..]);
+
+      let mut reader = package.open_entry(entry);
+      let chunked = read_chunked(&mut reader);
+      assert_eq!(chunked, &full[..]);
+    }
+  }
+
+  #[test]
+  fn seek_across_preload() {
+    let archived = test_data(10_000, 1);
+    let embedded = test_data(5_000, 7);
+    let package = build_package(&archived, &embedded);
+    let entry = package.find_entry("data/archived.bin").unwrap();
+    let mut reader = package.open_entry(entry);
+
+    reader.seek(SeekFrom::Start(PRELOAD_LEN as u64 - 10)).unwrap();
+    let mut buffer = [0u8; 20];
+    reader.read_exact(&mut buffer).unwrap();
+    assert_eq!(&buffer[..], &archived[PRELOAD_LEN - 10 .. PRELOAD_LEN + 10]);
+
+    reader.seek(SeekFrom::End(-5)).unwrap();
+    assert_eq!(read_chunked(&mut reader), &archived[archived.len() - 5 ..]);
+  }
+}
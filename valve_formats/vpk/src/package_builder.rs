@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+use std::io::{Write, Cursor, Result as IOResult};
+
+use package::{Package, PackageError, MAGIC, DIRECTORY_SEPARATOR, EMPTY_NAME};
+use package_entry::PackageEntry;
+
+/// The default maximum size of a numbered archive, matches the size Valve uses.
+pub const DEFAULT_MAX_ARCHIVE_SIZE: u32 = 200 << 20;
+
+/// The size of the archive regions that get an entry in the archive MD5 section.
+const ARCHIVE_MD5_CHUNK_SIZE: u32 = 1 << 20;
+
+struct PendingFile {
+  file_name: String,
+  data: Box<[u8]>,
+  crc32: u32,
+  archive_index: u16,
+  offset: u32
+}
+
+/// Creates a VPK v2 package consisting of a _dir.vpk file and numbered archives.
+pub struct PackageBuilder {
+  /// Files grouped by extension and directory, the same structure as the directory tree.
+  files: BTreeMap<String, BTreeMap<String, Vec<PendingFile>>>,
+  max_archive_size: u32
+}
+
+impl PackageBuilder {
+  pub fn new() -> Self {
+    Self {
+      files: BTreeMap::new(),
+      max_archive_size: DEFAULT_MAX_ARCHIVE_SIZE
+    }
+  }
+
+  /// Sets the size at which the file data gets split into the next numbered archive.
+  /// Files are never split, so a single file that is larger than this gets an archive of its own.
+  pub fn set_max_archive_size(&mut self, max_archive_size: u32) {
+    self.max_archive_size = max_archive_size.max(1);
+  }
+
+  pub fn add_file(&mut self, path: &str, data: &[u8]) {
+    let path = path.replace('\\', DIRECTORY_SEPARATOR).to_lowercase();
+    let path = path.trim_start_matches(DIRECTORY_SEPARATOR);
+    let last_separator = path.rfind(DIRECTORY_SEPARATOR);
+    let (directory, file_name) = if let Some(last_separator) = last_separator {
+      (&path[.. last_separator], &path[last_separator + 1 ..])
+    } else {
+      ("", path)
+    };
+    let dot = file_name.rfind('.');
+    let (file_name, extension) = if let Some(dot) = dot {
+      (&file_name[.. dot], &file_name[dot + 1 ..])
+    } else {
+      (file_name, "")
+    };
+
+    let extension = if extension.is_empty() { EMPTY_NAME } else { extension };
+    let directory = if directory.is_empty() { EMPTY_NAME } else { directory };
+
+    let files = self.files
+      .entry(extension.to_string())
+      .or_default()
+      .entry(directory.to_string())
+      .or_default();
+    files.retain(|file| file.file_name != file_name);
+    files.push(PendingFile {
+      file_name: file_name.to_string(),
+      data: data.to_vec().into_boxed_slice(),
+      crc32: PackageEntry::compute_crc(data),
+      archive_index: 0,
+      offset: 0
+    });
+  }
+
+  /// Writes the directory file to `dir_output` and calls `create_file_callback` with the name of every numbered archive.
+  /// The file name follows the same rules as `Package::read`, so both "pak01" and "pak01_dir.vpk" result in "pak01_000.vpk".
+  pub fn write<D: Write, W: Write, F: FnMut(&str) -> IOResult<W>>(&mut self, file_name: &str, dir_output: &mut D, mut create_file_callback: F) -> Result<(), PackageError> {
+    let (file_name, _) = Package::<Cursor<&[u8]>>::sanitize_file_name(file_name);
+    let archive_sizes = self.assign_archives();
+    let tree = self.write_tree();
+
+    let mut archive_md5_entries = Vec::<u8>::new();
+    for (archive_index, archive_size) in archive_sizes.iter().enumerate() {
+      let mut archive_data = Vec::<u8>::with_capacity(*archive_size as usize);
+      for directories in self.files.values() {
+        for files in directories.values() {
+          for file in files.iter().filter(|file| file.archive_index as usize == archive_index) {
+            debug_assert_eq!(file.offset as usize, archive_data.len());
+            archive_data.extend_from_slice(&file.data);
+          }
+        }
+      }
+
+      let mut archive = create_file_callback(&format!("{}_{:03}.vpk", file_name, archive_index)).map_err(PackageError::IOError)?;
+      archive.write_all(&archive_data).map_err(PackageError::IOError)?;
+      archive.flush().map_err(PackageError::IOError)?;
+
+      for (chunk_index, chunk) in archive_data.chunks(ARCHIVE_MD5_CHUNK_SIZE as usize).enumerate() {
+        archive_md5_entries.extend_from_slice(&(archive_index as u32).to_le_bytes());
+        archive_md5_entries.extend_from_slice(&(chunk_index as u32 * ARCHIVE_MD5_CHUNK_SIZE).to_le_bytes());
+        archive_md5_entries.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+        archive_md5_entries.extend_from_slice(&md5::compute(chunk).0);
+      }
+    }
+
+    let mut dir_data = Vec::<u8>::new();
+    dir_data.extend_from_slice(&MAGIC.to_le_bytes());
+    dir_data.extend_from_slice(&2u32.to_le_bytes());
+    dir_data.extend_from_slice(&(tree.len() as u32).to_le_bytes());
+    // All file data lives in the numbered archives
+    dir_data.extend_from_slice(&0u32.to_le_bytes());
+    dir_data.extend_from_slice(&(archive_md5_entries.len() as u32).to_le_bytes());
+    dir_data.extend_from_slice(&48u32.to_le_bytes());
+    // Unsigned
+    dir_data.extend_from_slice(&0u32.to_le_bytes());
+    dir_data.extend_from_slice(&tree);
+    dir_data.extend_from_slice(&archive_md5_entries);
+    dir_data.extend_from_slice(&md5::compute(&tree).0);
+    dir_data.extend_from_slice(&md5::compute(&archive_md5_entries).0);
+    let whole_file_checksum = md5::compute(&dir_data).0;
+    dir_data.extend_from_slice(&whole_file_checksum);
+
+    dir_output.write_all(&dir_data).map_err(PackageError::IOError)?;
+    dir_output.flush().map_err(PackageError::IOError)?;
+    Ok(())
+  }
+
+  /// Distributes the files over the numbered archives in tree order and returns the size of each archive.
+  fn assign_archives(&mut self) -> Vec<u32> {
+    let mut archive_sizes = Vec::<u32>::new();
+    let mut archive_index = 0u16;
+    let mut offset = 0u32;
+    for directories in self.files.values_mut() {
+      for files in directories.values_mut() {
+        for file in files {
+          let len = file.data.len() as u32;
+          if offset != 0 && offset + len > self.max_archive_size {
+            archive_sizes.push(offset);
+            archive_index += 1;
+            offset = 0;
+          }
+          file.archive_index = archive_index;
+          file.offset = offset;
+          offset += len;
+        }
+      }
+    }
+    if offset != 0 || archive_sizes.is_empty() {
+      archive_sizes.push(offset);
+    }
+    archive_sizes
+  }
+
+  fn write_tree(&self) -> Vec<u8> {
+    let mut tree = Vec::<u8>::new();
+    for (extension, directories) in &self.files {
+      write_null_terminated(&mut tree, extension);
+      for (directory, files) in directories {
+        write_null_terminated(&mut tree, directory);
+        for file in files {
+          write_null_terminated(&mut tree, &file.file_name);
+          tree.extend_from_slice(&file.crc32.to_le_bytes());
+          // No preload data
+          tree.extend_from_slice(&0u16.to_le_bytes());
+          tree.extend_from_slice(&file.archive_index.to_le_bytes());
+          tree.extend_from_slice(&file.offset.to_le_bytes());
+          tree.extend_from_slice(&(file.data.len() as u32).to_le_bytes());
+          tree.extend_from_slice(&0xFFFFu16.to_le_bytes());
+        }
+        tree.push(0);
+      }
+      tree.push(0);
+    }
+    tree.push(0);
+    tree
+  }
+}
+
+impl Default for PackageBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+fn write_null_terminated(output: &mut Vec<u8>, text: &str) {
+  output.extend_from_slice(text.as_bytes());
+  output.push(0);
+}
+
+#[cfg(test)]
+mod tests {
+  use std::cell::RefCell;
+  use std::collections::HashMap;
+  use std::io::{Cursor, Write, Result as IOResult, Error as IOError, ErrorKind};
+
+  use super::*;
+
+  struct ArchiveWriter<'a> {
+    name: String,
+    archives: &'a RefCell<HashMap<String, Vec<u8>>>
+  }
+
+  impl Write for ArchiveWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> IOResult<usize> {
+      self.archives.borrow_mut().entry(self.name.clone()).or_default().extend_from_slice(buf);
+      Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> IOResult<()> {
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn round_trip() {
+    let files: [(&str, Vec<u8>); 5] = [
+      ("materials/brick/wall.vmt", b"LightmappedGeneric { }".to_vec()),
+      ("materials/brick/wall.vtf", (0..3000u32).map(|i| i as u8).collect()),
+      ("maps/test.bsp", vec![0xAB; 5000]),
+      ("root.txt", b"no directory".to_vec()),
+      ("readme", b"no extension".to_vec())
+    ];
+
+    let mut builder = PackageBuilder::new();
+    builder.set_max_archive_size(4096);
+    for (path, data) in &files {
+      builder.add_file(path, data);
+    }
+
+    let archives = RefCell::new(HashMap::<String, Vec<u8>>::new());
+    let mut dir = Vec::<u8>::new();
+    builder.write("pak01_dir.vpk", &mut dir, |name| Ok(ArchiveWriter {
+      name: name.to_string(),
+      archives: &archives
+    })).unwrap();
+    let archives = archives.into_inner();
+    assert!(archives.len() > 1);
+    assert!(archives.contains_key("pak01_000.vpk"));
+    assert!(archives.contains_key("pak01_001.vpk"));
+
+    let package = Package::read("pak01_dir.vpk", Cursor::new(dir), |name| {
+      archives.get(name)
+        .map(|data| Cursor::new(data.clone()))
+        .ok_or_else(|| IOError::new(ErrorKind::NotFound, name.to_string()))
+    }).unwrap();
+    package.verify().unwrap();
+
+    for (path, data) in &files {
+      let entry = package.find_entry(path).unwrap_or_else(|| panic!("Missing entry: {}", path));
+      assert_eq!(&entry.full_path(), path);
+      let entry_data = package.read_entry(entry, true).unwrap();
+      assert_eq!(&entry_data[..], &data[..]);
+    }
+    assert!(package.find_entry("readme.txt").is_none());
+  }
+}
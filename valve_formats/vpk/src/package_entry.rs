@@ -1,3 +1,6 @@
+use crc::{self, Crc};
+use package::EMPTY_NAME;
+
 pub struct PackageEntry {
   /// File name of this entry
   pub file_name: String,
@@ -8,7 +11,7 @@ pub struct PackageEntry {
   pub directory_name: String,
 
   /// The file extension
-  /// If the file has no extension, this is an empty string
+  /// If the file has no extension, this is a single space
   pub type_name: String,
 
   /// The CRC32 checksum of this entry
@@ -28,12 +31,23 @@ pub struct PackageEntry {
 }
 
 impl PackageEntry {
+  pub(crate) fn compute_crc(data: &[u8]) -> u32 {
+    // Valve uses the standard CRC-32 that's also used by zip.
+    let crc = Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+    crc.checksum(data)
+  }
+
+  /// Checks the CRC32 of the complete file contents (preload data followed by the data stored in the archive).
+  pub fn verify_crc(&self, data: &[u8]) -> bool {
+    data.len() == self.total_len() as usize && Self::compute_crc(data) == self.crc32
+  }
+
   pub fn total_len(&self) -> u32 {
     self.len + self.small_data.len() as u32
   }
 
   pub fn full_file_name(&self) -> String {
-    if self.type_name == " " {
+    if self.type_name == EMPTY_NAME {
       self.file_name.clone()
     } else {
       self.file_name.clone() + "." + &self.type_name
@@ -41,10 +55,10 @@ impl PackageEntry {
   }
 
   pub fn full_path(&self) -> String {
-    if self.directory_name == " " {
+    if self.directory_name == EMPTY_NAME {
       return self.full_file_name();
     }
-    self.directory_name.clone() + &self.full_file_name()
+    self.directory_name.clone() + "/" + &self.full_file_name()
   }
 }
 
@@ -0,0 +1,664 @@
+//! Software decoders for the BC6H and BC7 block compressed formats.
+//! Both formats store 4x4 pixel blocks in 16 bytes.
+
+use std::convert::TryInto;
+
+const BLOCK_SIZE: usize = 16;
+
+/// Subset of every pixel for the 2 subset partitions, one bit per pixel.
+const PARTITIONS_2: [u16; 64] = [
+  0xCCCC, 0x8888, 0xEEEE, 0xECC8, 0xC880, 0xFEEC, 0xFEC8, 0xEC80,
+  0xC800, 0xFFEC, 0xFE80, 0xE800, 0xFFE8, 0xFF00, 0xFFF0, 0xF000,
+  0xF710, 0x008E, 0x7100, 0x08CE, 0x008C, 0x7310, 0x3100, 0x8CCE,
+  0x088C, 0x3110, 0x6666, 0x366C, 0x17E8, 0x0FF0, 0x718E, 0x399C,
+  0xAAAA, 0xF0F0, 0x5A5A, 0x33CC, 0x3C3C, 0x55AA, 0x9696, 0xA55A,
+  0x73CE, 0x13C8, 0x324C, 0x3BDC, 0x6996, 0xC33C, 0x9966, 0x0660,
+  0x0272, 0x04E4, 0x4E40, 0x2720, 0xC936, 0x936C, 0x39C6, 0x639C,
+  0x9336, 0x9CC6, 0x817E, 0xE718, 0xCCF0, 0x0FCC, 0x7744, 0xEE22
+];
+
+/// Subset of every pixel for the 3 subset partitions.
+const PARTITIONS_3: [[u8; 16]; 64] = [
+  [0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 1, 2, 2, 2, 2],
+  [0, 0, 0, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 2, 1],
+  [0, 0, 0, 0, 2, 0, 0, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+  [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 1, 0, 1, 1, 1],
+  [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2],
+  [0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 2, 2],
+  [0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1],
+  [0, 0, 1, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+  [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2],
+  [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2],
+  [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+  [0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2],
+  [0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2],
+  [0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2],
+  [0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+  [0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0, 2, 2, 2, 0],
+  [0, 0, 0, 1, 0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2],
+  [0, 1, 1, 1, 0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0],
+  [0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2],
+  [0, 0, 2, 2, 0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1],
+  [0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2, 0, 2, 2, 2],
+  [0, 0, 0, 1, 0, 0, 0, 1, 2, 2, 2, 1, 2, 2, 2, 1],
+  [0, 0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2],
+  [0, 0, 0, 0, 1, 1, 0, 0, 2, 2, 1, 0, 2, 2, 1, 0],
+  [0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1, 0, 0, 0, 0],
+  [0, 0, 1, 2, 0, 0, 1, 2, 1, 1, 2, 2, 2, 2, 2, 2],
+  [0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1, 0, 1, 1, 0],
+  [0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1],
+  [0, 0, 2, 2, 1, 1, 0, 2, 1, 1, 0, 2, 0, 0, 2, 2],
+  [0, 1, 1, 0, 0, 1, 1, 0, 2, 0, 0, 2, 2, 2, 2, 2],
+  [0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1],
+  [0, 0, 0, 0, 2, 0, 0, 0, 2, 2, 1, 1, 2, 2, 2, 1],
+  [0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+  [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 2, 0, 0, 1, 1],
+  [0, 0, 1, 1, 0, 0, 1, 2, 0, 0, 2, 2, 0, 2, 2, 2],
+  [0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0],
+  [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0],
+  [0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0],
+  [0, 1, 2, 0, 2, 0, 1, 2, 1, 2, 0, 1, 0, 1, 2, 0],
+  [0, 0, 1, 1, 2, 2, 0, 0, 1, 1, 2, 2, 0, 0, 1, 1],
+  [0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0, 1, 1],
+  [0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+  [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1],
+  [0, 0, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2, 1, 1, 2, 2],
+  [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 1, 1],
+  [0, 2, 2, 0, 1, 2, 2, 1, 0, 2, 2, 0, 1, 2, 2, 1],
+  [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 0, 1, 0, 1],
+  [0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1],
+  [0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2],
+  [0, 2, 2, 2, 0, 1, 1, 1, 0, 2, 2, 2, 0, 1, 1, 1],
+  [0, 0, 0, 2, 1, 1, 1, 2, 0, 0, 0, 2, 1, 1, 1, 2],
+  [0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2],
+  [0, 2, 2, 2, 0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2],
+  [0, 0, 0, 2, 1, 1, 1, 2, 1, 1, 1, 2, 0, 0, 0, 2],
+  [0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2],
+  [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2],
+  [0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2, 2, 2, 2, 2],
+  [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2],
+  [0, 0, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2],
+  [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2],
+  [0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 1],
+  [0, 2, 2, 2, 1, 2, 2, 2, 0, 2, 2, 2, 1, 2, 2, 2],
+  [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2],
+  [0, 1, 1, 1, 2, 0, 1, 1, 2, 2, 0, 1, 2, 2, 2, 0]
+];
+
+/// Anchor index of the second subset of the 2 subset partitions.
+const ANCHORS_2: [u8; 64] = [
+  15, 15, 15, 15, 15, 15, 15, 15,
+  15, 15, 15, 15, 15, 15, 15, 15,
+  15, 2, 8, 2, 2, 8, 8, 15,
+  2, 8, 2, 2, 8, 8, 2, 2,
+  15, 15, 6, 8, 2, 8, 15, 15,
+  2, 8, 2, 2, 2, 15, 15, 6,
+  6, 2, 6, 8, 15, 15, 2, 2,
+  15, 15, 15, 15, 15, 2, 2, 15
+];
+
+/// Anchor index of the second subset of the 3 subset partitions.
+const ANCHORS_3_SECOND: [u8; 64] = [
+  3, 3, 15, 15, 8, 3, 15, 15,
+  8, 8, 6, 6, 6, 5, 3, 3,
+  3, 3, 8, 15, 3, 3, 6, 10,
+  5, 8, 8, 6, 8, 5, 15, 15,
+  8, 15, 3, 5, 6, 10, 8, 15,
+  15, 3, 15, 5, 15, 15, 15, 15,
+  3, 15, 5, 5, 5, 8, 5, 10,
+  5, 10, 8, 13, 15, 12, 3, 3
+];
+
+/// Anchor index of the third subset of the 3 subset partitions.
+const ANCHORS_3_THIRD: [u8; 64] = [
+  15, 8, 8, 3, 15, 15, 3, 8,
+  15, 15, 15, 15, 15, 15, 15, 8,
+  15, 8, 15, 3, 15, 8, 15, 8,
+  3, 15, 6, 10, 15, 15, 10, 8,
+  15, 3, 15, 10, 10, 8, 9, 10,
+  6, 15, 8, 15, 3, 6, 6, 8,
+  15, 3, 15, 15, 15, 15, 15, 15,
+  15, 15, 15, 15, 3, 15, 15, 8
+];
+
+const WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+struct BitReader {
+  bits: u128,
+  position: u32
+}
+
+impl BitReader {
+  fn new(block: &[u8; BLOCK_SIZE]) -> Self {
+    Self {
+      bits: u128::from_le_bytes(*block),
+      position: 0
+    }
+  }
+
+  fn read(&mut self, count: u32) -> u32 {
+    if count == 0 {
+      return 0;
+    }
+    let value = (self.bits >> self.position) as u32 & ((1u64 << count) - 1) as u32;
+    self.position += count;
+    value
+  }
+}
+
+fn subset_of(subsets_count: u32, partition: usize, pixel: usize) -> usize {
+  match subsets_count {
+    2 => ((PARTITIONS_2[partition] >> pixel) & 1) as usize,
+    3 => PARTITIONS_3[partition][pixel] as usize,
+    _ => 0
+  }
+}
+
+fn is_anchor(subsets_count: u32, partition: usize, pixel: usize) -> bool {
+  match subsets_count {
+    2 => pixel == 0 || pixel == ANCHORS_2[partition] as usize,
+    3 => pixel == 0 || pixel == ANCHORS_3_SECOND[partition] as usize || pixel == ANCHORS_3_THIRD[partition] as usize,
+    _ => pixel == 0
+  }
+}
+
+fn weights(index_bits: u32) -> &'static [u32] {
+  match index_bits {
+    2 => &WEIGHTS_2,
+    3 => &WEIGHTS_3,
+    _ => &WEIGHTS_4
+  }
+}
+
+struct BC7Mode {
+  subsets: u32,
+  partition_bits: u32,
+  rotation_bits: u32,
+  index_selection_bits: u32,
+  color_bits: u32,
+  alpha_bits: u32,
+  endpoint_p_bits: bool,
+  shared_p_bits: bool,
+  index_bits: u32,
+  secondary_index_bits: u32
+}
+
+const BC7_MODES: [BC7Mode; 8] = [
+  BC7Mode { subsets: 3, partition_bits: 4, rotation_bits: 0, index_selection_bits: 0, color_bits: 4, alpha_bits: 0, endpoint_p_bits: true, shared_p_bits: false, index_bits: 3, secondary_index_bits: 0 },
+  BC7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 6, alpha_bits: 0, endpoint_p_bits: false, shared_p_bits: true, index_bits: 3, secondary_index_bits: 0 },
+  BC7Mode { subsets: 3, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 0, endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 },
+  BC7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 0, endpoint_p_bits: true, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 },
+  BC7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 1, color_bits: 5, alpha_bits: 6, endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 3 },
+  BC7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 0, color_bits: 7, alpha_bits: 8, endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 2 },
+  BC7Mode { subsets: 1, partition_bits: 0, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 7, endpoint_p_bits: true, shared_p_bits: false, index_bits: 4, secondary_index_bits: 0 },
+  BC7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 5, endpoint_p_bits: true, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 }
+];
+
+fn expand_to_8_bits(value: u32, bits: u32) -> u8 {
+  let value = value << (8 - bits);
+  (value | (value >> bits)) as u8
+}
+
+fn interpolate_u8(e0: u8, e1: u8, weight: u32) -> u8 {
+  (((64 - weight) * e0 as u32 + weight * e1 as u32 + 32) >> 6) as u8
+}
+
+/// Decodes a single BC7 block into 16 RGBA8 pixels in row major order.
+/// Blocks using the reserved mode decode to transparent black.
+pub fn decode_bc7_block(block: &[u8; BLOCK_SIZE]) -> [[u8; 4]; 16] {
+  let mut pixels = [[0u8; 4]; 16];
+  let mut reader = BitReader::new(block);
+
+  let mut mode_index = 0usize;
+  while mode_index < 8 && reader.read(1) == 0 {
+    mode_index += 1;
+  }
+  if mode_index == 8 {
+    return pixels;
+  }
+  let mode = &BC7_MODES[mode_index];
+
+  let partition = reader.read(mode.partition_bits) as usize;
+  let rotation = reader.read(mode.rotation_bits);
+  let index_selection = reader.read(mode.index_selection_bits);
+
+  // Endpoints are stored channel by channel: all red values first, then green, blue and alpha.
+  let endpoints_count = (mode.subsets * 2) as usize;
+  let mut endpoints = [[0u32; 4]; 6];
+  for channel in 0..3 {
+    for endpoint in endpoints.iter_mut().take(endpoints_count) {
+      endpoint[channel] = reader.read(mode.color_bits);
+    }
+  }
+  for endpoint in endpoints.iter_mut().take(endpoints_count) {
+    endpoint[3] = if mode.alpha_bits != 0 { reader.read(mode.alpha_bits) } else { 255 };
+  }
+
+  let mut color_bits = mode.color_bits;
+  let mut alpha_bits = mode.alpha_bits;
+  if mode.endpoint_p_bits || mode.shared_p_bits {
+    let mut p_bits = [0u32; 6];
+    if mode.endpoint_p_bits {
+      for p_bit in p_bits.iter_mut().take(endpoints_count) {
+        *p_bit = reader.read(1);
+      }
+    } else {
+      for subset in 0..mode.subsets as usize {
+        let p_bit = reader.read(1);
+        p_bits[subset * 2] = p_bit;
+        p_bits[subset * 2 + 1] = p_bit;
+      }
+    }
+    for (endpoint, p_bit) in endpoints.iter_mut().zip(p_bits.iter()).take(endpoints_count) {
+      for value in endpoint.iter_mut().take(3) {
+        *value = (*value << 1) | p_bit;
+      }
+      if mode.alpha_bits != 0 {
+        endpoint[3] = (endpoint[3] << 1) | p_bit;
+      }
+    }
+    color_bits += 1;
+    if mode.alpha_bits != 0 {
+      alpha_bits += 1;
+    }
+  }
+
+  let mut endpoints_u8 = [[0u8; 4]; 6];
+  for (endpoint, endpoint_u8) in endpoints.iter().zip(endpoints_u8.iter_mut()).take(endpoints_count) {
+    for channel in 0..3 {
+      endpoint_u8[channel] = expand_to_8_bits(endpoint[channel], color_bits);
+    }
+    endpoint_u8[3] = if alpha_bits != 0 { expand_to_8_bits(endpoint[3], alpha_bits) } else { 255 };
+  }
+
+  let mut indices = [0u32; 16];
+  for (pixel, index) in indices.iter_mut().enumerate() {
+    let bits = if is_anchor(mode.subsets, partition, pixel) { mode.index_bits - 1 } else { mode.index_bits };
+    *index = reader.read(bits);
+  }
+  let mut secondary_indices = [0u32; 16];
+  if mode.secondary_index_bits != 0 {
+    for (pixel, index) in secondary_indices.iter_mut().enumerate() {
+      let bits = if pixel == 0 { mode.secondary_index_bits - 1 } else { mode.secondary_index_bits };
+      *index = reader.read(bits);
+    }
+  }
+
+  for (pixel_index, pixel) in pixels.iter_mut().enumerate() {
+    let subset = subset_of(mode.subsets, partition, pixel_index);
+    let e0 = &endpoints_u8[subset * 2];
+    let e1 = &endpoints_u8[subset * 2 + 1];
+
+    if mode.secondary_index_bits == 0 {
+      let weight = weights(mode.index_bits)[indices[pixel_index] as usize];
+      for channel in 0..4 {
+        pixel[channel] = interpolate_u8(e0[channel], e1[channel], weight);
+      }
+    } else {
+      let (color_index, color_index_bits, alpha_index, alpha_index_bits) = if index_selection == 0 {
+        (indices[pixel_index], mode.index_bits, secondary_indices[pixel_index], mode.secondary_index_bits)
+      } else {
+        (secondary_indices[pixel_index], mode.secondary_index_bits, indices[pixel_index], mode.index_bits)
+      };
+      let color_weight = weights(color_index_bits)[color_index as usize];
+      let alpha_weight = weights(alpha_index_bits)[alpha_index as usize];
+      for channel in 0..3 {
+        pixel[channel] = interpolate_u8(e0[channel], e1[channel], color_weight);
+      }
+      pixel[3] = interpolate_u8(e0[3], e1[3], alpha_weight);
+    }
+
+    match rotation {
+      1 => pixel.swap(0, 3),
+      2 => pixel.swap(1, 3),
+      3 => pixel.swap(2, 3),
+      _ => {}
+    }
+  }
+  pixels
+}
+
+/// Endpoint component of a BC6H bit layout entry.
+#[derive(Clone, Copy)]
+enum Field {
+  RW, GW, BW,
+  RX, GX, BX,
+  RY, GY, BY,
+  RZ, GZ, BZ,
+  D
+}
+
+/// A run of bits of one field. The bits are stored from `first` to `last`,
+/// ranges that go from a higher to a lower bit are stored reversed.
+struct BitRun(Field, u8, u8);
+
+struct BC6HMode {
+  mode: u32,
+  transformed: bool,
+  endpoint_bits: u32,
+  delta_bits: [u32; 3],
+  layout: &'static [BitRun]
+}
+
+use self::Field::*;
+
+const BC6H_MODES: [BC6HMode; 14] = [
+  BC6HMode { mode: 0x00, transformed: true, endpoint_bits: 10, delta_bits: [5, 5, 5], layout: &[
+    BitRun(GY, 4, 4), BitRun(BY, 4, 4), BitRun(BZ, 4, 4), BitRun(RW, 0, 9), BitRun(GW, 0, 9), BitRun(BW, 0, 9),
+    BitRun(RX, 0, 4), BitRun(GZ, 4, 4), BitRun(GY, 0, 3), BitRun(GX, 0, 4), BitRun(BZ, 0, 0), BitRun(GZ, 0, 3),
+    BitRun(BX, 0, 4), BitRun(BZ, 1, 1), BitRun(BY, 0, 3), BitRun(RY, 0, 4), BitRun(BZ, 2, 2), BitRun(RZ, 0, 4),
+    BitRun(BZ, 3, 3), BitRun(D, 0, 4)
+  ] },
+  BC6HMode { mode: 0x01, transformed: true, endpoint_bits: 7, delta_bits: [6, 6, 6], layout: &[
+    BitRun(GY, 5, 5), BitRun(GZ, 4, 4), BitRun(GZ, 5, 5), BitRun(RW, 0, 6), BitRun(BZ, 0, 0), BitRun(BZ, 1, 1),
+    BitRun(BY, 4, 4), BitRun(GW, 0, 6), BitRun(BY, 5, 5), BitRun(BZ, 2, 2), BitRun(GY, 4, 4), BitRun(BW, 0, 6),
+    BitRun(BZ, 3, 3), BitRun(BZ, 5, 5), BitRun(BZ, 4, 4), BitRun(RX, 0, 5), BitRun(GY, 0, 3), BitRun(GX, 0, 5),
+    BitRun(GZ, 0, 3), BitRun(BX, 0, 5), BitRun(BY, 0, 3), BitRun(RY, 0, 5), BitRun(RZ, 0, 5), BitRun(D, 0, 4)
+  ] },
+  BC6HMode { mode: 0x02, transformed: true, endpoint_bits: 11, delta_bits: [5, 4, 4], layout: &[
+    BitRun(RW, 0, 9), BitRun(GW, 0, 9), BitRun(BW, 0, 9), BitRun(RX, 0, 4), BitRun(RW, 10, 10), BitRun(GY, 0, 3),
+    BitRun(GX, 0, 3), BitRun(GW, 10, 10), BitRun(BZ, 0, 0), BitRun(GZ, 0, 3), BitRun(BX, 0, 3), BitRun(BW, 10, 10),
+    BitRun(BZ, 1, 1), BitRun(BY, 0, 3), BitRun(RY, 0, 4), BitRun(BZ, 2, 2), BitRun(RZ, 0, 4), BitRun(BZ, 3, 3),
+    BitRun(D, 0, 4)
+  ] },
+  BC6HMode { mode: 0x06, transformed: true, endpoint_bits: 11, delta_bits: [4, 5, 4], layout: &[
+    BitRun(RW, 0, 9), BitRun(GW, 0, 9), BitRun(BW, 0, 9), BitRun(RX, 0, 3), BitRun(RW, 10, 10), BitRun(GZ, 4, 4),
+    BitRun(GY, 0, 3), BitRun(GX, 0, 4), BitRun(GW, 10, 10), BitRun(GZ, 0, 3), BitRun(BX, 0, 3), BitRun(BW, 10, 10),
+    BitRun(BZ, 1, 1), BitRun(BY, 0, 3), BitRun(RY, 0, 3), BitRun(BZ, 0, 0), BitRun(BZ, 2, 2), BitRun(RZ, 0, 3),
+    BitRun(GY, 4, 4), BitRun(BZ, 3, 3), BitRun(D, 0, 4)
+  ] },
+  BC6HMode { mode: 0x0A, transformed: true, endpoint_bits: 11, delta_bits: [4, 4, 5], layout: &[
+    BitRun(RW, 0, 9), BitRun(GW, 0, 9), BitRun(BW, 0, 9), BitRun(RX, 0, 3), BitRun(RW, 10, 10), BitRun(BY, 4, 4),
+    BitRun(GY, 0, 3), BitRun(GX, 0, 3), BitRun(GW, 10, 10), BitRun(BZ, 0, 0), BitRun(GZ, 0, 3), BitRun(BX, 0, 4),
+    BitRun(BW, 10, 10), BitRun(BY, 0, 3), BitRun(RY, 0, 3), BitRun(BZ, 1, 1), BitRun(BZ, 2, 2), BitRun(RZ, 0, 3),
+    BitRun(BZ, 4, 4), BitRun(BZ, 3, 3), BitRun(D, 0, 4)
+  ] },
+  BC6HMode { mode: 0x0E, transformed: true, endpoint_bits: 9, delta_bits: [5, 5, 5], layout: &[
+    BitRun(RW, 0, 8), BitRun(BY, 4, 4), BitRun(GW, 0, 8), BitRun(GY, 4, 4), BitRun(BW, 0, 8), BitRun(BZ, 4, 4),
+    BitRun(RX, 0, 4), BitRun(GZ, 4, 4), BitRun(GY, 0, 3), BitRun(GX, 0, 4), BitRun(BZ, 0, 0), BitRun(GZ, 0, 3),
+    BitRun(BX, 0, 4), BitRun(BZ, 1, 1), BitRun(BY, 0, 3), BitRun(RY, 0, 4), BitRun(BZ, 2, 2), BitRun(RZ, 0, 4),
+    BitRun(BZ, 3, 3), BitRun(D, 0, 4)
+  ] },
+  BC6HMode { mode: 0x12, transformed: true, endpoint_bits: 8, delta_bits: [6, 5, 5], layout: &[
+    BitRun(RW, 0, 7), BitRun(GZ, 4, 4), BitRun(BY, 4, 4), BitRun(GW, 0, 7), BitRun(BZ, 2, 2), BitRun(GY, 4, 4),
+    BitRun(BW, 0, 7), BitRun(BZ, 3, 3), BitRun(BZ, 4, 4), BitRun(RX, 0, 5), BitRun(GY, 0, 3), BitRun(GX, 0, 4),
+    BitRun(BZ, 0, 0), BitRun(GZ, 0, 3), BitRun(BX, 0, 4), BitRun(BZ, 1, 1), BitRun(BY, 0, 3), BitRun(RY, 0, 5),
+    BitRun(RZ, 0, 5), BitRun(D, 0, 4)
+  ] },
+  BC6HMode { mode: 0x16, transformed: true, endpoint_bits: 8, delta_bits: [5, 6, 5], layout: &[
+    BitRun(RW, 0, 7), BitRun(BZ, 0, 0), BitRun(BY, 4, 4), BitRun(GW, 0, 7), BitRun(GY, 5, 5), BitRun(GY, 4, 4),
+    BitRun(BW, 0, 7), BitRun(GZ, 5, 5), BitRun(BZ, 4, 4), BitRun(RX, 0, 4), BitRun(GZ, 4, 4), BitRun(GY, 0, 3),
+    BitRun(GX, 0, 5), BitRun(GZ, 0, 3), BitRun(BX, 0, 4), BitRun(BZ, 1, 1), BitRun(BY, 0, 3), BitRun(RY, 0, 4),
+    BitRun(BZ, 2, 2), BitRun(RZ, 0, 4), BitRun(BZ, 3, 3), BitRun(D, 0, 4)
+  ] },
+  BC6HMode { mode: 0x1A, transformed: true, endpoint_bits: 8, delta_bits: [5, 5, 6], layout: &[
+    BitRun(RW, 0, 7), BitRun(BZ, 1, 1), BitRun(BY, 4, 4), BitRun(GW, 0, 7), BitRun(BY, 5, 5), BitRun(GY, 4, 4),
+    BitRun(BW, 0, 7), BitRun(BZ, 5, 5), BitRun(BZ, 4, 4), BitRun(RX, 0, 4), BitRun(GZ, 4, 4), BitRun(GY, 0, 3),
+    BitRun(GX, 0, 4), BitRun(BZ, 0, 0), BitRun(GZ, 0, 3), BitRun(BX, 0, 5), BitRun(BY, 0, 3), BitRun(RY, 0, 4),
+    BitRun(BZ, 2, 2), BitRun(RZ, 0, 4), BitRun(BZ, 3, 3), BitRun(D, 0, 4)
+  ] },
+  BC6HMode { mode: 0x1E, transformed: false, endpoint_bits: 6, delta_bits: [6, 6, 6], layout: &[
+    BitRun(RW, 0, 5), BitRun(GZ, 4, 4), BitRun(BZ, 0, 0), BitRun(BZ, 1, 1), BitRun(BY, 4, 4), BitRun(GW, 0, 5),
+    BitRun(GY, 5, 5), BitRun(BY, 5, 5), BitRun(BZ, 2, 2), BitRun(GY, 4, 4), BitRun(BW, 0, 5), BitRun(GZ, 5, 5),
+    BitRun(BZ, 3, 3), BitRun(BZ, 5, 5), BitRun(BZ, 4, 4), BitRun(RX, 0, 5), BitRun(GY, 0, 3), BitRun(GX, 0, 5),
+    BitRun(GZ, 0, 3), BitRun(BX, 0, 5), BitRun(BY, 0, 3), BitRun(RY, 0, 5), BitRun(RZ, 0, 5), BitRun(D, 0, 4)
+  ] },
+  BC6HMode { mode: 0x03, transformed: false, endpoint_bits: 10, delta_bits: [10, 10, 10], layout: &[
+    BitRun(RW, 0, 9), BitRun(GW, 0, 9), BitRun(BW, 0, 9), BitRun(RX, 0, 9), BitRun(GX, 0, 9), BitRun(BX, 0, 9)
+  ] },
+  BC6HMode { mode: 0x07, transformed: true, endpoint_bits: 11, delta_bits: [9, 9, 9], layout: &[
+    BitRun(RW, 0, 9), BitRun(GW, 0, 9), BitRun(BW, 0, 9), BitRun(RX, 0, 8), BitRun(RW, 10, 10), BitRun(GX, 0, 8),
+    BitRun(GW, 10, 10), BitRun(BX, 0, 8), BitRun(BW, 10, 10)
+  ] },
+  BC6HMode { mode: 0x0B, transformed: true, endpoint_bits: 12, delta_bits: [8, 8, 8], layout: &[
+    BitRun(RW, 0, 9), BitRun(GW, 0, 9), BitRun(BW, 0, 9), BitRun(RX, 0, 7), BitRun(RW, 11, 10), BitRun(GX, 0, 7),
+    BitRun(GW, 11, 10), BitRun(BX, 0, 7), BitRun(BW, 11, 10)
+  ] },
+  BC6HMode { mode: 0x0F, transformed: true, endpoint_bits: 16, delta_bits: [4, 4, 4], layout: &[
+    BitRun(RW, 0, 9), BitRun(GW, 0, 9), BitRun(BW, 0, 9), BitRun(RX, 0, 3), BitRun(RW, 15, 10), BitRun(GX, 0, 3),
+    BitRun(GW, 15, 10), BitRun(BX, 0, 3), BitRun(BW, 15, 10)
+  ] }
+];
+
+fn sign_extend(value: i32, bits: u32) -> i32 {
+  let shift = 32 - bits;
+  (value << shift) >> shift
+}
+
+fn unquantize_bc6h(value: i32, bits: u32, signed: bool) -> i32 {
+  if !signed {
+    if bits >= 15 || value == 0 {
+      value
+    } else if value == (1 << bits) - 1 {
+      0xFFFF
+    } else {
+      ((value << 16) + 0x8000) >> bits
+    }
+  } else {
+    if bits >= 16 {
+      return value;
+    }
+    let is_negative = value < 0;
+    let magnitude = value.abs();
+    let unquantized = if magnitude == 0 {
+      0
+    } else if magnitude >= (1 << (bits - 1)) - 1 {
+      0x7FFF
+    } else {
+      ((magnitude << 15) + 0x4000) >> (bits - 1)
+    };
+    if is_negative { -unquantized } else { unquantized }
+  }
+}
+
+fn finish_unquantize_bc6h(value: i32, signed: bool) -> u16 {
+  if !signed {
+    ((value * 31) >> 6) as u16
+  } else if value < 0 {
+    0x8000 | ((-value * 31) >> 5) as u16
+  } else {
+    ((value * 31) >> 5) as u16
+  }
+}
+
+/// Decodes a single BC6H block into 16 RGBA16F pixels (as half float bit patterns) in row major order.
+/// Alpha is always 1. Blocks using one of the reserved modes decode to black.
+pub fn decode_bc6h_block(block: &[u8; BLOCK_SIZE], signed: bool) -> [[u16; 4]; 16] {
+  const HALF_ONE: u16 = 0x3C00;
+  let mut pixels = [[0u16, 0u16, 0u16, HALF_ONE]; 16];
+  let mut reader = BitReader::new(block);
+
+  let mut mode_value = reader.read(2);
+  if mode_value >= 2 {
+    mode_value |= reader.read(3) << 2;
+  }
+  let mode = BC6H_MODES.iter().find(|mode| mode.mode == mode_value);
+  if mode.is_none() {
+    return pixels;
+  }
+  let mode = mode.unwrap();
+
+  // w, x, y and z are the endpoints of the block, each with an r, g and b value.
+  let mut fields = [0i32; 13];
+  for BitRun(field, first, last) in mode.layout {
+    let (first, last) = (*first as i32, *last as i32);
+    let step = if last >= first { 1 } else { -1 };
+    let mut bit = first;
+    loop {
+      fields[*field as usize] |= (reader.read(1) as i32) << bit;
+      if bit == last {
+        break;
+      }
+      bit += step;
+    }
+  }
+
+  let subsets_count = if mode.layout.iter().any(|run| matches!(run.0, D)) { 2 } else { 1 };
+  let partition = fields[D as usize] as usize;
+  let endpoint_bits = mode.endpoint_bits;
+  let mut endpoints = [[0i32; 3]; 4];
+  for (endpoint_index, endpoint) in endpoints.iter_mut().enumerate().take(subsets_count * 2) {
+    for channel in 0..3 {
+      endpoint[channel] = fields[endpoint_index * 3 + channel];
+    }
+  }
+
+  if signed {
+    for value in endpoints[0].iter_mut() {
+      *value = sign_extend(*value, endpoint_bits);
+    }
+  }
+  if mode.transformed || signed {
+    for endpoint in endpoints.iter_mut().take(subsets_count * 2).skip(1) {
+      for (value, delta_bits) in endpoint.iter_mut().zip(mode.delta_bits.iter()) {
+        *value = sign_extend(*value, *delta_bits);
+      }
+    }
+  }
+  if mode.transformed {
+    let base = endpoints[0];
+    for endpoint in endpoints.iter_mut().take(subsets_count * 2).skip(1) {
+      for (value, base_value) in endpoint.iter_mut().zip(base.iter()) {
+        *value = (base_value + *value) & ((1 << endpoint_bits) - 1);
+        if signed {
+          *value = sign_extend(*value, endpoint_bits);
+        }
+      }
+    }
+  }
+
+  for endpoint in endpoints.iter_mut().take(subsets_count * 2) {
+    for value in endpoint.iter_mut() {
+      *value = unquantize_bc6h(*value, endpoint_bits, signed);
+    }
+  }
+
+  let index_bits = if subsets_count == 2 { 3 } else { 4 };
+  for (pixel_index, pixel) in pixels.iter_mut().enumerate() {
+    let subset = if subsets_count == 2 { subset_of(2, partition, pixel_index) } else { 0 };
+    let bits = if is_anchor(subsets_count as u32, partition, pixel_index) { index_bits - 1 } else { index_bits };
+    let weight = weights(index_bits)[reader.read(bits) as usize] as i32;
+    let e0 = &endpoints[subset * 2];
+    let e1 = &endpoints[subset * 2 + 1];
+    for channel in 0..3 {
+      let value = ((64 - weight) * e0[channel] + weight * e1[channel] + 32) >> 6;
+      pixel[channel] = finish_unquantize_bc6h(value, signed);
+    }
+  }
+  pixels
+}
+
+fn decode_blocks<T: Copy + Default, F: Fn(&[u8; BLOCK_SIZE]) -> [[T; 4]; 16]>(data: &[u8], width: u32, height: u32, decode_block: F) -> Option<Vec<T>> {
+  let blocks_x = width.div_ceil(4) as usize;
+  let blocks_y = height.div_ceil(4) as usize;
+  if data.len() < blocks_x * blocks_y * BLOCK_SIZE {
+    return None;
+  }
+
+  let (width, height) = (width as usize, height as usize);
+  let mut output = vec![T::default(); width * height * 4];
+  for block_y in 0..blocks_y {
+    for block_x in 0..blocks_x {
+      let block_start = (block_y * blocks_x + block_x) * BLOCK_SIZE;
+      let block: &[u8; BLOCK_SIZE] = data[block_start .. block_start + BLOCK_SIZE].try_into().unwrap();
+      let pixels = decode_block(block);
+      for (pixel_index, pixel) in pixels.iter().enumerate() {
+        let x = block_x * 4 + pixel_index % 4;
+        let y = block_y * 4 + pixel_index / 4;
+        if x >= width || y >= height {
+          continue;
+        }
+        let offset = (y * width + x) * 4;
+        output[offset .. offset + 4].copy_from_slice(pixel);
+      }
+    }
+  }
+  Some(output)
+}
+
+/// Decodes a BC7 image to tightly packed RGBA8 pixels.
+pub fn decode_bc7(data: &[u8], width: u32, height: u32) -> Option<Vec<u8>> {
+  decode_blocks(data, width, height, decode_bc7_block)
+}
+
+/// Decodes a BC6H image to tightly packed RGBA16F pixels.
+pub fn decode_bc6h(data: &[u8], width: u32, height: u32, signed: bool) -> Option<Vec<u16>> {
+  decode_blocks(data, width, height, |block| decode_bc6h_block(block, signed))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[derive(Default)]
+  struct BitWriter {
+    bits: u128,
+    position: u32
+  }
+
+  impl BitWriter {
+    fn write(&mut self, value: u32, count: u32) {
+      self.bits |= (value as u128 & ((1u128 << count) - 1)) << self.position;
+      self.position += count;
+    }
+
+    fn finish(self) -> [u8; BLOCK_SIZE] {
+      assert_eq!(self.position, 128);
+      self.bits.to_le_bytes()
+    }
+  }
+
+  #[test]
+  fn bc7_mode_6() {
+    let mut writer = BitWriter::default();
+    writer.write(1 << 6, 7);
+    // Endpoints R0 R1 G0 G1 B0 B1 A0 A1, then the P bits of both endpoints.
+    for value in [0x00, 0x7F, 0x7F, 0x00, 0x20, 0x20, 0x7F, 0x7F] {
+      writer.write(value, 7);
+    }
+    writer.write(0, 1);
+    writer.write(1, 1);
+    // Every pixel uses its own index as the weight index, the anchor pixel only has 3 bits.
+    writer.write(0, 3);
+    for index in 1..16 {
+      writer.write(index, 4);
+    }
+
+    let pixels = decode_bc7_block(&writer.finish());
+    assert_eq!(pixels[0], [0, 254, 64, 254]);
+    assert_eq!(pixels[7], [120, 135, 64, 254]);
+    assert_eq!(pixels[8], [135, 120, 65, 255]);
+    assert_eq!(pixels[15], [255, 1, 65, 255]);
+  }
+
+  #[test]
+  fn bc7_reserved_mode() {
+    assert_eq!(decode_bc7_block(&[0u8; BLOCK_SIZE]), [[0u8; 4]; 16]);
+  }
+
+  #[test]
+  fn bc6h_mode_11() {
+    const HALF_MAX: u16 = 0x7BFF;
+    // 512 out of 10 bits is about 1.5
+    const HALF_MIDDLE: u16 = 0x3E0F;
+
+    let mut writer = BitWriter::default();
+    writer.write(0x03, 5);
+    // Untransformed 10 bit endpoints RW GW BW RX GX BX.
+    for value in [0, 512, 1023, 1023, 512, 0] {
+      writer.write(value, 10);
+    }
+    writer.write(0, 3);
+    for index in 1..16 {
+      writer.write(index, 4);
+    }
+
+    let pixels = decode_bc6h_block(&writer.finish(), false);
+    assert_eq!(pixels[0], [0, HALF_MIDDLE, HALF_MAX, 0x3C00]);
+    assert_eq!(pixels[7], [0x3A20, HALF_MIDDLE, 0x41DF, 0x3C00]);
+    assert_eq!(pixels[8], [0x41DF, HALF_MIDDLE, 0x3A20, 0x3C00]);
+    assert_eq!(pixels[15], [HALF_MAX, HALF_MIDDLE, 0, 0x3C00]);
+  }
+}
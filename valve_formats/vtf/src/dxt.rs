@@ -0,0 +1,231 @@
+//! Software encoder and decoder for the DXT1 and DXT5 block compressed formats.
+//! DXT1 stores 4x4 pixel blocks in 8 bytes, DXT5 adds 8 bytes of alpha in front of that.
+//! The encoder only fits the endpoints to the bounding box of the block, it's meant for tooling, not for quality.
+
+use std::convert::TryInto;
+
+const DXT1_BLOCK_SIZE: usize = 8;
+const DXT5_BLOCK_SIZE: usize = 16;
+
+fn pack_565(color: [u8; 3]) -> u16 {
+  ((color[0] as u16 >> 3) << 11) | ((color[1] as u16 >> 2) << 5) | (color[2] as u16 >> 3)
+}
+
+fn unpack_565(color: u16) -> [u8; 3] {
+  let r = ((color >> 11) & 0x1F) as u8;
+  let g = ((color >> 5) & 0x3F) as u8;
+  let b = (color & 0x1F) as u8;
+  [(r << 3) | (r >> 2), (g << 2) | (g >> 4), (b << 3) | (b >> 2)]
+}
+
+fn color_palette(color0: u16, color1: u16, four_colors: bool) -> [[u8; 4]; 4] {
+  let c0 = unpack_565(color0);
+  let c1 = unpack_565(color1);
+  let mut palette = [[0u8; 4]; 4];
+  palette[0] = [c0[0], c0[1], c0[2], 255];
+  palette[1] = [c1[0], c1[1], c1[2], 255];
+  for channel in 0..3 {
+    let (e0, e1) = (c0[channel] as u32, c1[channel] as u32);
+    if four_colors {
+      palette[2][channel] = ((2 * e0 + e1 + 1) / 3) as u8;
+      palette[3][channel] = ((e0 + 2 * e1 + 1) / 3) as u8;
+    } else {
+      palette[2][channel] = ((e0 + e1) / 2) as u8;
+    }
+  }
+  palette[2][3] = 255;
+  // The fourth color of the 3 color mode is transparent black.
+  palette[3][3] = if four_colors { 255 } else { 0 };
+  palette
+}
+
+fn alpha_palette(alpha0: u8, alpha1: u8) -> [u8; 8] {
+  let (a0, a1) = (alpha0 as u32, alpha1 as u32);
+  let mut palette = [alpha0, alpha1, 0, 0, 0, 0, 0, 255];
+  if alpha0 > alpha1 {
+    for i in 1..7u32 {
+      palette[i as usize + 1] = (((7 - i) * a0 + i * a1 + 3) / 7) as u8;
+    }
+  } else {
+    for i in 1..5u32 {
+      palette[i as usize + 1] = (((5 - i) * a0 + i * a1 + 2) / 5) as u8;
+    }
+    palette[6] = 0;
+  }
+  palette
+}
+
+fn color_distance(a: &[u8], b: &[u8]) -> u32 {
+  (0..3).map(|channel| {
+    let diff = a[channel] as i32 - b[channel] as i32;
+    (diff * diff) as u32
+  }).sum()
+}
+
+/// Always uses the 4 color mode, DXT5 doesn't have the 3 color mode and DXT1 doesn't need it without alpha.
+fn encode_color_block(pixels: &[[u8; 4]; 16]) -> [u8; DXT1_BLOCK_SIZE] {
+  let mut min = [255u8; 3];
+  let mut max = [0u8; 3];
+  for pixel in pixels {
+    for channel in 0..3 {
+      min[channel] = min[channel].min(pixel[channel]);
+      max[channel] = max[channel].max(pixel[channel]);
+    }
+  }
+
+  let mut color0 = pack_565(max);
+  let mut color1 = pack_565(min);
+  if color0 < color1 {
+    std::mem::swap(&mut color0, &mut color1);
+  }
+
+  let mut indices = 0u32;
+  if color0 != color1 {
+    let palette = color_palette(color0, color1, true);
+    for (pixel_index, pixel) in pixels.iter().enumerate() {
+      let index = (0..4u32)
+        .min_by_key(|&index| color_distance(pixel, &palette[index as usize]))
+        .unwrap();
+      indices |= index << (pixel_index * 2);
+    }
+  }
+
+  let mut block = [0u8; DXT1_BLOCK_SIZE];
+  block[0..2].copy_from_slice(&color0.to_le_bytes());
+  block[2..4].copy_from_slice(&color1.to_le_bytes());
+  block[4..8].copy_from_slice(&indices.to_le_bytes());
+  block
+}
+
+fn encode_alpha_block(pixels: &[[u8; 4]; 16]) -> [u8; 8] {
+  let alpha0 = pixels.iter().map(|pixel| pixel[3]).max().unwrap();
+  let alpha1 = pixels.iter().map(|pixel| pixel[3]).min().unwrap();
+
+  let mut indices = 0u64;
+  if alpha0 != alpha1 {
+    let palette = alpha_palette(alpha0, alpha1);
+    for (pixel_index, pixel) in pixels.iter().enumerate() {
+      let index = (0..8u64)
+        .min_by_key(|&index| (pixel[3] as i32 - palette[index as usize] as i32).abs())
+        .unwrap();
+      indices |= index << (pixel_index * 3);
+    }
+  }
+
+  let mut block = [0u8; 8];
+  block[0] = alpha0;
+  block[1] = alpha1;
+  block[2..8].copy_from_slice(&indices.to_le_bytes()[0..6]);
+  block
+}
+
+pub fn encode_dxt1_block(pixels: &[[u8; 4]; 16]) -> [u8; DXT1_BLOCK_SIZE] {
+  encode_color_block(pixels)
+}
+
+pub fn encode_dxt5_block(pixels: &[[u8; 4]; 16]) -> [u8; DXT5_BLOCK_SIZE] {
+  let mut block = [0u8; DXT5_BLOCK_SIZE];
+  block[0..8].copy_from_slice(&encode_alpha_block(pixels));
+  block[8..16].copy_from_slice(&encode_color_block(pixels));
+  block
+}
+
+pub fn decode_dxt1_block(block: &[u8; DXT1_BLOCK_SIZE]) -> [[u8; 4]; 16] {
+  let color0 = u16::from_le_bytes([block[0], block[1]]);
+  let color1 = u16::from_le_bytes([block[2], block[3]]);
+  let palette = color_palette(color0, color1, color0 > color1);
+  let indices = u32::from_le_bytes(block[4..8].try_into().unwrap());
+  let mut pixels = [[0u8; 4]; 16];
+  for (pixel_index, pixel) in pixels.iter_mut().enumerate() {
+    *pixel = palette[((indices >> (pixel_index * 2)) & 0x3) as usize];
+  }
+  pixels
+}
+
+pub fn decode_dxt5_block(block: &[u8; DXT5_BLOCK_SIZE]) -> [[u8; 4]; 16] {
+  let alpha_palette = alpha_palette(block[0], block[1]);
+  let mut alpha_index_bytes = [0u8; 8];
+  alpha_index_bytes[0..6].copy_from_slice(&block[2..8]);
+  let alpha_indices = u64::from_le_bytes(alpha_index_bytes);
+
+  let color0 = u16::from_le_bytes([block[8], block[9]]);
+  let color1 = u16::from_le_bytes([block[10], block[11]]);
+  let palette = color_palette(color0, color1, true);
+  let indices = u32::from_le_bytes(block[12..16].try_into().unwrap());
+
+  let mut pixels = [[0u8; 4]; 16];
+  for (pixel_index, pixel) in pixels.iter_mut().enumerate() {
+    *pixel = palette[((indices >> (pixel_index * 2)) & 0x3) as usize];
+    pixel[3] = alpha_palette[((alpha_indices >> (pixel_index * 3)) & 0x7) as usize];
+  }
+  pixels
+}
+
+/// Splits tightly packed RGBA8 pixels into 4x4 blocks, pixels outside of the image repeat the edge.
+fn encode_blocks<const N: usize, F: Fn(&[[u8; 4]; 16]) -> [u8; N]>(rgba: &[u8], width: u32, height: u32, encode_block: F) -> Vec<u8> {
+  let blocks_x = width.div_ceil(4) as usize;
+  let blocks_y = height.div_ceil(4) as usize;
+  let (width, height) = (width as usize, height as usize);
+  let mut output = Vec::with_capacity(blocks_x * blocks_y * N);
+  for block_y in 0..blocks_y {
+    for block_x in 0..blocks_x {
+      let mut pixels = [[0u8; 4]; 16];
+      for (pixel_index, pixel) in pixels.iter_mut().enumerate() {
+        let x = (block_x * 4 + pixel_index % 4).min(width - 1);
+        let y = (block_y * 4 + pixel_index / 4).min(height - 1);
+        let offset = (y * width + x) * 4;
+        pixel.copy_from_slice(&rgba[offset .. offset + 4]);
+      }
+      output.extend_from_slice(&encode_block(&pixels));
+    }
+  }
+  output
+}
+
+fn decode_blocks<const N: usize, F: Fn(&[u8; N]) -> [[u8; 4]; 16]>(data: &[u8], width: u32, height: u32, decode_block: F) -> Option<Vec<u8>> {
+  let blocks_x = width.div_ceil(4) as usize;
+  let blocks_y = height.div_ceil(4) as usize;
+  if data.len() < blocks_x * blocks_y * N {
+    return None;
+  }
+
+  let (width, height) = (width as usize, height as usize);
+  let mut output = vec![0u8; width * height * 4];
+  for block_y in 0..blocks_y {
+    for block_x in 0..blocks_x {
+      let block_start = (block_y * blocks_x + block_x) * N;
+      let block: &[u8; N] = data[block_start .. block_start + N].try_into().unwrap();
+      let pixels = decode_block(block);
+      for (pixel_index, pixel) in pixels.iter().enumerate() {
+        let x = block_x * 4 + pixel_index % 4;
+        let y = block_y * 4 + pixel_index / 4;
+        if x >= width || y >= height {
+          continue;
+        }
+        let offset = (y * width + x) * 4;
+        output[offset .. offset + 4].copy_from_slice(pixel);
+      }
+    }
+  }
+  Some(output)
+}
+
+/// Encodes tightly packed RGBA8 pixels to DXT1, the alpha channel is ignored.
+pub fn encode_dxt1(rgba: &[u8], width: u32, height: u32) -> Vec<u8> {
+  encode_blocks(rgba, width, height, encode_dxt1_block)
+}
+
+/// Encodes tightly packed RGBA8 pixels to DXT5.
+pub fn encode_dxt5(rgba: &[u8], width: u32, height: u32) -> Vec<u8> {
+  encode_blocks(rgba, width, height, encode_dxt5_block)
+}
+
+/// Decodes a DXT1 image to tightly packed RGBA8 pixels.
+pub fn decode_dxt1(data: &[u8], width: u32, height: u32) -> Option<Vec<u8>> {
+  decode_blocks(data, width, height, decode_dxt1_block)
+}
+
+/// Decodes a DXT5 image to tightly packed RGBA8 pixels.
+pub fn decode_dxt5(data: &[u8], width: u32, height: u32) -> Option<Vec<u8>> {
+  decode_blocks(data, width, height, decode_dxt5_block)
+}
@@ -0,0 +1,273 @@
+use std::io::{Write, Result as IOResult, Error as IOError, ErrorKind};
+
+use crate::dxt;
+use crate::header::EXPECTED_SIGNATURE;
+use crate::image_format::calculate_image_size;
+use crate::{ImageFormat, TextureFlags};
+
+/// The size of the 7.3+ header without the resource dictionary.
+const HEADER_SIZE: u32 = 80;
+const RESOURCE_ENTRY_SIZE: u32 = 8;
+const RESOURCE_THUMBNAIL: u32 = 0x01;
+const RESOURCE_IMAGE: u32 = 0x30;
+
+/// Valve limits the thumbnail to 16 pixels on the longer side.
+const MAX_THUMBNAIL_SIZE: u32 = 16;
+
+/// Writes 2D textures as VTF 7.4 files.
+/// The mip chain gets generated with a box filter unless `TextureFlags::NO_MIP` is set.
+/// The thumbnail is always DXT1 like the ones written by vtex.
+pub struct VtfEncoder {
+  width: u32,
+  height: u32,
+  format: ImageFormat,
+  flags: TextureFlags,
+  /// RGBA8 pixels of every mip level, starting with the full resolution image.
+  mips: Vec<Vec<u8>>
+}
+
+impl VtfEncoder {
+  /// `rgba` has to contain tightly packed RGBA8 pixels, the dimensions have to be powers of 2.
+  /// Supported formats are DXT1, DXT5 and RGBA8888.
+  pub fn new(width: u32, height: u32, rgba: &[u8], format: ImageFormat, flags: TextureFlags) -> IOResult<Self> {
+    if !matches!(format, ImageFormat::DXT1 | ImageFormat::DXT5 | ImageFormat::RGBA8888) {
+      return Err(IOError::new(ErrorKind::InvalidInput, "Unsupported image format"));
+    }
+    if !width.is_power_of_two() || !height.is_power_of_two() || width > u16::MAX as u32 || height > u16::MAX as u32 {
+      return Err(IOError::new(ErrorKind::InvalidInput, "Texture dimensions must be powers of 2"));
+    }
+    if rgba.len() != (width * height * 4) as usize {
+      return Err(IOError::new(ErrorKind::InvalidInput, "Pixel data does not match the texture dimensions"));
+    }
+    if flags.intersects(TextureFlags::ENV_MAP | TextureFlags::RENDER_TARGET | TextureFlags::DEPTH_RENDER_TARGET) {
+      return Err(IOError::new(ErrorKind::InvalidInput, "Only 2D textures can be encoded"));
+    }
+
+    let mut mips = vec![rgba.to_vec()];
+    let (mut mip_width, mut mip_height) = (width, height);
+    while mip_width > 1 || mip_height > 1 {
+      let mip = downsample(mips.last().unwrap(), mip_width, mip_height);
+      mips.push(mip);
+      mip_width = (mip_width / 2).max(1);
+      mip_height = (mip_height / 2).max(1);
+    }
+
+    let mut flags = flags;
+    flags.remove(TextureFlags::ONE_BIT_ALPHA | TextureFlags::EIGHT_BIT_ALPHA);
+    if format != ImageFormat::DXT1 && rgba.chunks_exact(4).any(|pixel| pixel[3] != 255) {
+      flags.insert(TextureFlags::EIGHT_BIT_ALPHA);
+    }
+
+    Ok(Self {
+      width,
+      height,
+      format,
+      flags,
+      mips
+    })
+  }
+
+  pub fn mip_count(&self) -> u32 {
+    if self.flags.contains(TextureFlags::NO_MIP) {
+      1
+    } else {
+      self.mips.len() as u32
+    }
+  }
+
+  fn mip_dimensions(&self, mip: u32) -> (u32, u32) {
+    ((self.width >> mip).max(1), (self.height >> mip).max(1))
+  }
+
+  /// The thumbnail uses the largest mip level that fits into 16x16.
+  fn thumbnail_mip(&self) -> u32 {
+    let mut mip = 0;
+    while self.width >> mip > MAX_THUMBNAIL_SIZE || self.height >> mip > MAX_THUMBNAIL_SIZE {
+      mip += 1;
+    }
+    mip
+  }
+
+  fn encode_image(&self, mip: u32, format: ImageFormat) -> Vec<u8> {
+    let (width, height) = self.mip_dimensions(mip);
+    let rgba = &self.mips[mip as usize];
+    match format {
+      ImageFormat::DXT1 => dxt::encode_dxt1(rgba, width, height),
+      ImageFormat::DXT5 => dxt::encode_dxt5(rgba, width, height),
+      ImageFormat::RGBA8888 => rgba.clone(),
+      _ => unreachable!()
+    }
+  }
+
+  /// The average linear color of the texture, used by vrad for bounced light.
+  fn reflectivity(&self) -> [f32; 3] {
+    let pixels = &self.mips[0];
+    let mut sum = [0f64; 3];
+    for pixel in pixels.chunks_exact(4) {
+      for channel in 0..3 {
+        sum[channel] += (pixel[channel] as f64 / 255f64).powf(2.2f64);
+      }
+    }
+    let count = (pixels.len() / 4).max(1) as f64;
+    [(sum[0] / count) as f32, (sum[1] / count) as f32, (sum[2] / count) as f32]
+  }
+
+  pub fn write<W: Write>(&self, output: &mut W) -> IOResult<()> {
+    let thumbnail_mip = self.thumbnail_mip();
+    let (thumbnail_width, thumbnail_height) = self.mip_dimensions(thumbnail_mip);
+    let thumbnail = self.encode_image(thumbnail_mip, ImageFormat::DXT1);
+    debug_assert_eq!(thumbnail.len() as u32, calculate_image_size(thumbnail_width, thumbnail_height, 1, ImageFormat::DXT1));
+
+    let header_size = HEADER_SIZE + 2 * RESOURCE_ENTRY_SIZE;
+    let thumbnail_offset = header_size;
+    let image_offset = thumbnail_offset + thumbnail.len() as u32;
+    let reflectivity = self.reflectivity();
+
+    let mut data = Vec::<u8>::new();
+    data.extend_from_slice(&EXPECTED_SIGNATURE.to_le_bytes());
+    data.extend_from_slice(&7u32.to_le_bytes());
+    data.extend_from_slice(&4u32.to_le_bytes());
+    data.extend_from_slice(&header_size.to_le_bytes());
+    data.extend_from_slice(&(self.width as u16).to_le_bytes());
+    data.extend_from_slice(&(self.height as u16).to_le_bytes());
+    data.extend_from_slice(&self.flags.bits().to_le_bytes());
+    data.extend_from_slice(&1u16.to_le_bytes()); // frames
+    data.extend_from_slice(&0u16.to_le_bytes()); // first frame
+    data.extend_from_slice(&[0u8; 4]);
+    for value in &reflectivity {
+      data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(&[0u8; 4]);
+    data.extend_from_slice(&1f32.to_le_bytes()); // bumpmap scale
+    data.extend_from_slice(&(self.format as u32).to_le_bytes());
+    data.push(self.mip_count() as u8);
+    data.extend_from_slice(&(ImageFormat::DXT1 as u32).to_le_bytes());
+    data.push(thumbnail_width as u8);
+    data.push(thumbnail_height as u8);
+    data.extend_from_slice(&1u16.to_le_bytes()); // depth
+    data.extend_from_slice(&[0u8; 3]);
+    data.extend_from_slice(&2u32.to_le_bytes()); // resource count
+    data.extend_from_slice(&[0u8; 8]);
+    debug_assert_eq!(data.len() as u32, HEADER_SIZE);
+
+    data.extend_from_slice(&RESOURCE_THUMBNAIL.to_le_bytes());
+    data.extend_from_slice(&thumbnail_offset.to_le_bytes());
+    data.extend_from_slice(&RESOURCE_IMAGE.to_le_bytes());
+    data.extend_from_slice(&image_offset.to_le_bytes());
+    data.extend_from_slice(&thumbnail);
+
+    // Mip maps are stored from the smallest to the largest one.
+    for mip in (0..self.mip_count()).rev() {
+      data.extend_from_slice(&self.encode_image(mip, self.format));
+    }
+
+    output.write_all(&data)
+  }
+}
+
+/// Halves the size of the image using a box filter, dimensions that are already 1 stay 1.
+fn downsample(rgba: &[u8], width: u32, height: u32) -> Vec<u8> {
+  let (width, height) = (width as usize, height as usize);
+  let new_width = (width / 2).max(1);
+  let new_height = (height / 2).max(1);
+  let mut output = Vec::with_capacity(new_width * new_height * 4);
+  for y in 0..new_height {
+    for x in 0..new_width {
+      let x0 = (x * 2).min(width - 1);
+      let x1 = (x * 2 + 1).min(width - 1);
+      let y0 = (y * 2).min(height - 1);
+      let y1 = (y * 2 + 1).min(height - 1);
+      for channel in 0..4 {
+        let sum = rgba[(y0 * width + x0) * 4 + channel] as u32
+          + rgba[(y0 * width + x1) * 4 + channel] as u32
+          + rgba[(y1 * width + x0) * 4 + channel] as u32
+          + rgba[(y1 * width + x1) * 4 + channel] as u32;
+        output.push(((sum + 2) / 4) as u8);
+      }
+    }
+  }
+  output
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Cursor;
+
+  use super::*;
+  use crate::VtfTexture;
+
+  const SIZE: u32 = 16;
+  /// DXT quantizes the endpoints to 5:6:5 and only stores 4 colors per block.
+  const DXT_TOLERANCE: u8 = 12;
+
+  /// A diagonal gradient whose channels all increase together,
+  /// so the colors of every block lie on the diagonal of their bounding box.
+  fn gradient() -> Vec<u8> {
+    let mut rgba = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+      for x in 0..SIZE {
+        let t = x + y;
+        rgba.extend_from_slice(&[(t * 8) as u8, (32 + t * 6) as u8, (64 + t * 4) as u8, (255 - x * 8) as u8]);
+      }
+    }
+    rgba
+  }
+
+  fn round_trip(format: ImageFormat, flags: TextureFlags) -> VtfTexture<Cursor<Vec<u8>>> {
+    let encoder = VtfEncoder::new(SIZE, SIZE, &gradient(), format, flags).unwrap();
+    let mut data = Vec::<u8>::new();
+    encoder.write(&mut data).unwrap();
+    let texture = VtfTexture::new(Cursor::new(data)).unwrap();
+    assert_eq!(texture.header().high_res_image_format, format);
+    assert_eq!(texture.mip_dimensions(0), (SIZE, SIZE));
+    texture
+  }
+
+  fn max_error(a: &[u8], b: &[u8], channels: usize) -> u8 {
+    assert_eq!(a.len(), b.len());
+    a.chunks_exact(4).zip(b.chunks_exact(4))
+      .flat_map(|(a, b)| (0..channels).map(move |channel| a[channel].abs_diff(b[channel])))
+      .max()
+      .unwrap()
+  }
+
+  #[test]
+  fn round_trip_rgba8888() {
+    let mut texture = round_trip(ImageFormat::RGBA8888, TextureFlags::empty());
+    assert_eq!(texture.mip_count(), 5);
+    assert!(texture.header().flags.contains(TextureFlags::EIGHT_BIT_ALPHA));
+    assert_eq!(texture.decode_to_rgba8(0, 0, 0).unwrap(), gradient());
+
+    let mut mip = gradient();
+    for level in 1..5u32 {
+      let size = SIZE >> (level - 1);
+      mip = downsample(&mip, size, size);
+      assert_eq!(texture.decode_to_rgba8(level, 0, 0).unwrap(), mip);
+    }
+  }
+
+  #[test]
+  fn round_trip_dxt1() {
+    let mut texture = round_trip(ImageFormat::DXT1, TextureFlags::empty());
+    assert!(!texture.header().flags.contains(TextureFlags::EIGHT_BIT_ALPHA));
+    let decoded = texture.decode_to_rgba8(0, 0, 0).unwrap();
+    let error = max_error(&decoded, &gradient(), 3);
+    assert!(error <= DXT_TOLERANCE, "Max error: {}", error);
+  }
+
+  #[test]
+  fn round_trip_dxt5() {
+    let mut texture = round_trip(ImageFormat::DXT5, TextureFlags::empty());
+    assert!(texture.header().flags.contains(TextureFlags::EIGHT_BIT_ALPHA));
+    let decoded = texture.decode_to_rgba8(0, 0, 0).unwrap();
+    let error = max_error(&decoded, &gradient(), 4);
+    assert!(error <= DXT_TOLERANCE, "Max error: {}", error);
+  }
+
+  #[test]
+  fn no_mip() {
+    let mut texture = round_trip(ImageFormat::RGBA8888, TextureFlags::NO_MIP);
+    assert_eq!(texture.mip_count(), 1);
+    assert_eq!(texture.decode_to_rgba8(0, 0, 0).unwrap(), gradient());
+  }
+}
@@ -3,7 +3,7 @@ use crate::texture_flags::TextureFlags;
 use std::io::{Read, Result as IOResult, Error as IOError, Seek, SeekFrom, ErrorKind};
 use io_util::PrimitiveRead;
 
-const EXPECTED_SIGNATURE: u32 = 0x00465456;
+pub(crate) const EXPECTED_SIGNATURE: u32 = 0x00465456;
 
 pub struct Header {
   /// File signature ("VTF\0"). (or as little-endian integer, 0x00465456)
@@ -76,11 +76,18 @@ impl Header {
     let reflectivity = [reader.read_f32()?, reader.read_f32()?, reader.read_f32()?];
     reader.seek(SeekFrom::Current(4))?;
     let bumpmap_scale = reader.read_f32()?;
-    let high_res_image_format: ImageFormat = unsafe { std::mem::transmute(reader.read_u32()?) };
+    let high_res_image_format = ImageFormat::from_u32(reader.read_u32()?)
+      .ok_or_else(|| IOError::new(ErrorKind::InvalidData, "Unknown image format"))?;
     let mipmap_count = reader.read_u8()?;
-    let low_res_image_format: ImageFormat =  unsafe { std::mem::transmute(reader.read_u32()?) };
-    let low_res_image_width = reader.read_u8()?;
-    let low_res_image_height = reader.read_u8()?;
+    let low_res_image_format = ImageFormat::from_u32(reader.read_u32()?);
+    let mut low_res_image_width = reader.read_u8()?;
+    let mut low_res_image_height = reader.read_u8()?;
+    if low_res_image_format.is_none() {
+      // Textures without a thumbnail store -1 as the format
+      low_res_image_width = 0;
+      low_res_image_height = 0;
+    }
+    let low_res_image_format = low_res_image_format.unwrap_or(ImageFormat::DXT1);
 
     let depth = if version[0] > 7 || version[0] == 7 && version[1] >= 2 {
       reader.read_u16()?
@@ -90,7 +97,10 @@ impl Header {
 
     let num_resources = if version[0] > 7 || version[0] == 7 && version[1] >= 3 {
       reader.seek(SeekFrom::Current(3))?;
-      reader.read_u32()?
+      let num_resources = reader.read_u32()?;
+      // The header is padded to 80 bytes, the resource dictionary comes after that.
+      reader.seek(SeekFrom::Current(8))?;
+      num_resources
     } else {
       0u32
     };
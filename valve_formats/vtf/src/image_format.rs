@@ -29,7 +29,48 @@ pub enum ImageFormat {
   UVWQ8888,
   RGBA16161616F,
   RGBA16161616,
-  UV1X8888
+  UV1X8888,
+  // The BC formats were added by later branches of the engine and aren't part of the original enum.
+  // These ids follow Strata Source and VTFEdit Reloaded.
+  BC7 = 70,
+  BC6H = 71
+}
+
+impl ImageFormat {
+  pub fn from_u32(value: u32) -> Option<Self> {
+    Some(match value {
+      0 => ImageFormat::RGBA8888,
+      1 => ImageFormat::ABGR8888,
+      2 => ImageFormat::RGB8888,
+      3 => ImageFormat::BGR888,
+      4 => ImageFormat::RGB565,
+      5 => ImageFormat::I8,
+      6 => ImageFormat::IA88,
+      7 => ImageFormat::P8,
+      8 => ImageFormat::A8,
+      9 => ImageFormat::RGB888Bluescreen,
+      10 => ImageFormat::BGR888Bluescreen,
+      11 => ImageFormat::ARGB8888,
+      12 => ImageFormat::BGRA8888,
+      13 => ImageFormat::DXT1,
+      14 => ImageFormat::DXT3,
+      15 => ImageFormat::DXT5,
+      16 => ImageFormat::BGRX8888,
+      17 => ImageFormat::BGR565,
+      18 => ImageFormat::BGRX5551,
+      19 => ImageFormat::BGRA4444,
+      20 => ImageFormat::DXT1OneBitAlpha,
+      21 => ImageFormat::BGRA5551,
+      22 => ImageFormat::UV88,
+      23 => ImageFormat::UVWQ8888,
+      24 => ImageFormat::RGBA16161616F,
+      25 => ImageFormat::RGBA16161616,
+      26 => ImageFormat::UV1X8888,
+      70 => ImageFormat::BC7,
+      71 => ImageFormat::BC6H,
+      _ => return None
+    })
+  }
 }
 
 pub enum FormatSizeInfo {
@@ -174,13 +215,13 @@ lazy_static! {
         total_bits_per_block: 8
       }
     });
-    m.insert(ImageFormat::DXT1, ImageFormatInfo {
+    m.insert(ImageFormat::DXT3, ImageFormatInfo {
       is_supported: true,
       size_info: FormatSizeInfo::Block {
         block_width: 4,
         block_height: 4,
         block_depth: 1,
-        total_bits_per_block: 8
+        total_bits_per_block: 16
       }
     });
     m.insert(ImageFormat::DXT5, ImageFormatInfo {
@@ -192,6 +233,24 @@ lazy_static! {
         total_bits_per_block: 16
       }
     });
+    m.insert(ImageFormat::BC7, ImageFormatInfo {
+      is_supported: true,
+      size_info: FormatSizeInfo::Block {
+        block_width: 4,
+        block_height: 4,
+        block_depth: 1,
+        total_bits_per_block: 16
+      }
+    });
+    m.insert(ImageFormat::BC6H, ImageFormatInfo {
+      is_supported: true,
+      size_info: FormatSizeInfo::Block {
+        block_width: 4,
+        block_height: 4,
+        block_depth: 1,
+        total_bits_per_block: 16
+      }
+    });
     m.insert(ImageFormat::RGB565, ImageFormatInfo {
       is_supported: true,
       size_info: FormatSizeInfo::Pixel {
@@ -250,16 +309,18 @@ pub(crate) fn is_image_format_supported(format: ImageFormat) -> bool {
   IMAGE_FORMAT_INFO_MAP.get(&format).map_or(false, |format_info| format_info.is_supported)
 }
 
+/// Returns the size of the image in bytes.
 pub(crate) fn calculate_image_size(width: u32, height: u32, depth: u32, format: ImageFormat) -> u32 {
   let info = IMAGE_FORMAT_INFO_MAP.get(&format).expect("Unsupported format");
   match info.size_info {
     FormatSizeInfo::Pixel { total_bits_per_pixel, .. } => {
-      total_bits_per_pixel as u32 * width * height * depth
+      (total_bits_per_pixel as u32 * width * height * depth).div_ceil(8)
     }
     FormatSizeInfo::Block { block_width, block_height, block_depth, total_bits_per_block } => {
       ((width + block_width as u32 - 1) / block_width as u32)
       * ((height + block_height as u32 - 1) / block_height as u32)
       * ((depth + block_depth as u32 - 1) / block_depth as u32)
+      // Despite the name, the block sizes are stored in bytes
       * total_bits_per_block as u32
     }
   }
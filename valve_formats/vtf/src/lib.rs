@@ -13,9 +13,15 @@ mod image_format;
 mod texture_flags;
 mod thumbnail;
 mod texture;
+mod bc_decode;
+mod dxt;
+mod encoder;
 
 pub use self::image_format::ImageFormat;
 pub use self::texture_data::*;
 pub use self::header::Header;
 pub use self::texture_flags::TextureFlags;
 pub use self::texture::VtfTexture;
+pub use self::bc_decode::{decode_bc7_block, decode_bc6h_block};
+pub use self::dxt::{encode_dxt1_block, encode_dxt5_block, decode_dxt1_block, decode_dxt5_block};
+pub use self::encoder::VtfEncoder;
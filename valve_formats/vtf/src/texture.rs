@@ -7,12 +7,14 @@ use crate::image_format::{is_image_format_supported, calculate_image_size};
 use crate::{MipMap, Face, Slice};
 use std::cmp::max;
 use crate::Frame;
+use crate::{ImageFormat, TextureFlags, bc_decode, dxt};
 
 pub struct VtfTexture<R: Read + Seek> {
   reader: R,
   header: Header,
   resource_offsets: HashMap<Resource, u32>,
-  thumbnail: Option<Thumbnail>
+  thumbnail: Option<Thumbnail>,
+  image_data: Option<Box<[u8]>>
 }
 
 #[derive(Hash, PartialEq, Eq, Copy, Clone, Debug)]
@@ -46,47 +48,100 @@ impl<R: Read + Seek> VtfTexture<R> {
       reader,
       header,
       resource_offsets,
-      thumbnail
+      thumbnail,
+      image_data: None
     })
   }
 
-  fn calculate_mip_offset(&self, level: u32) -> Option<u64> {
-    if level > self.header.mipmap_count as u32 {
+  /// Mip maps are stored from the smallest to the largest one, so the offset is the size of all smaller mips.
+  fn calculate_mip_offset(&self, mip: u32) -> Option<u64> {
+    if mip >= self.mip_count() {
       return None;
     }
-    let mut offset = *self.resource_offsets.get(&Resource::Image).unwrap() as u64;
-
-    // I'm sure there's a way to simply calculate that with math but this works just fine
-    for level in 0 .. level {
-      let reversed_level = self.header.mipmap_count as u32 - 1 - level;
-
-      let level_width = max(1, self.header.width >> reversed_level) as u32;
-      let level_height = max(1, self.header.height >> reversed_level) as u32;
-      let level_image_size = calculate_image_size(level_width, level_height, 1, self.header.high_res_image_format) as u64;
-      let frames_count = self.header.frames as u64;
-      let faces_count = 1u64; // TODO
-      let slices_count = max(1, self.header.depth as u64); // does this perhaps scale with the mip level in some cases?
-      offset += level_image_size * frames_count * faces_count * slices_count;
+    let offset = *self.resource_offsets.get(&Resource::Image)? as u64;
+    Some(offset + (mip + 1 .. self.mip_count()).map(|smaller_mip| self.mip_size(smaller_mip)).sum::<u64>())
+  }
+
+  pub fn mip_count(&self) -> u32 {
+    max(1, self.header.mipmap_count as u32)
+  }
+
+  pub fn frame_count(&self) -> u32 {
+    max(1, self.header.frames as u32)
+  }
+
+  /// Returns 6 for cube maps and 1 otherwise.
+  /// Cube maps of VTF versions before 7.5 store an additional sphere map as the 7th face.
+  pub fn face_count(&self) -> u32 {
+    if !self.header.flags.contains(TextureFlags::ENV_MAP) {
+      1
+    } else if self.header.version[0] == 7 && self.header.version[1] < 5 && self.header.first_frame != 0xFFFF {
+      7
+    } else {
+      6
     }
-    Some(offset)
+  }
+
+  /// Returns the depth of the given mip level, `mip` 0 is the full resolution image.
+  pub fn slice_count(&self, mip: u32) -> u32 {
+    max(1, self.header.depth as u32 >> mip)
+  }
+
+  /// Returns the width and height of the given mip level, `mip` 0 is the full resolution image.
+  pub fn mip_dimensions(&self, mip: u32) -> (u32, u32) {
+    (max(1, self.header.width as u32 >> mip), max(1, self.header.height as u32 >> mip))
+  }
+
+  fn slice_size(&self, mip: u32) -> u64 {
+    let (width, height) = self.mip_dimensions(mip);
+    calculate_image_size(width, height, 1, self.header.high_res_image_format) as u64
+  }
+
+  fn mip_size(&self, mip: u32) -> u64 {
+    self.slice_size(mip) * self.frame_count() as u64 * self.face_count() as u64 * self.slice_count(mip) as u64
+  }
+
+  /// Returns the data of a single subresource, `mip` 0 is the full resolution image.
+  /// The image data of the texture gets read on the first call.
+  pub fn raw_data(&mut self, mip: u32, frame: u32, face: u32, slice: u32) -> Option<&[u8]> {
+    if mip >= self.mip_count() || frame >= self.frame_count() || face >= self.face_count() || slice >= self.slice_count(mip) {
+      return None;
+    }
+
+    if self.image_data.is_none() {
+      let image_offset = *self.resource_offsets.get(&Resource::Image)? as u64;
+      let image_size: u64 = (0..self.mip_count()).map(|mip| self.mip_size(mip)).sum();
+      self.reader.seek(SeekFrom::Start(image_offset)).ok()?;
+      self.image_data = Some(self.reader.read_data(image_size as usize).ok()?);
+    }
+
+    // Every mip map contains all frames, every frame all faces and every face all slices.
+    let image_offset = *self.resource_offsets.get(&Resource::Image)? as u64;
+    let mut offset = self.calculate_mip_offset(mip)? - image_offset;
+    let slice_size = self.slice_size(mip);
+    let slices_count = self.slice_count(mip) as u64;
+    let faces_count = self.face_count() as u64;
+    offset += ((frame as u64 * faces_count + face as u64) * slices_count + slice as u64) * slice_size;
+
+    let image_data = self.image_data.as_ref().unwrap();
+    image_data.get(offset as usize .. (offset + slice_size) as usize)
   }
 
   pub fn header(&self) -> &Header {
     &self.header
   }
 
-  pub fn read_mip_map(&mut self, level: u32) -> Option<MipMap> {
-    let offset = self.calculate_mip_offset(level)?;
+  /// Reads all frames, faces and slices of a mip level, `mip` 0 is the full resolution image.
+  pub fn read_mip_map(&mut self, mip: u32) -> Option<MipMap> {
+    let offset = self.calculate_mip_offset(mip)?;
     self.reader.seek(SeekFrom::Start(offset)).ok()?;
 
-    let reversed_level = self.header.mipmap_count as u32 - 1 - level;
-    let level_width = max(1, self.header.width >> reversed_level) as u32;
-    let level_height = max(1, self.header.height >> reversed_level) as u32;
-    let level_image_size = calculate_image_size(level_width, level_height, 1, self.header.high_res_image_format);
+    let (level_width, level_height) = self.mip_dimensions(mip);
+    let level_image_size = self.slice_size(mip);
 
-    let frames_count = self.header.frames;
-    let faces_count = 1; // TODO
-    let slices_count = max(1, self.header.depth); // does this perhaps scale with the mip level in some cases?
+    let frames_count = self.frame_count();
+    let faces_count = self.face_count();
+    let slices_count = self.slice_count(mip);
 
     let mut frames = Vec::<Frame>::with_capacity(frames_count as usize);
     for _frame in 0..frames_count {
@@ -116,6 +171,40 @@ impl<R: Read + Seek> VtfTexture<R> {
     })
   }
 
+  /// Reads a single image and converts it to tightly packed RGBA8 pixels.
+  /// `mip` 0 is the full resolution image.
+  /// Returns None if the image doesn't exist or the format can't be converted to RGBA8.
+  pub fn decode_to_rgba8(&mut self, mip: u32, frame: u32, face: u32) -> Option<Vec<u8>> {
+    let format = self.header.high_res_image_format;
+    let (width, height) = self.mip_dimensions(mip);
+    let data = self.raw_data(mip, frame, face, 0)?;
+    match format {
+      ImageFormat::BC7 => bc_decode::decode_bc7(data, width, height),
+      ImageFormat::DXT1 => dxt::decode_dxt1(data, width, height),
+      ImageFormat::DXT5 => dxt::decode_dxt5(data, width, height),
+      ImageFormat::RGBA8888 => Some(data.to_vec()),
+      ImageFormat::BGRA8888 => Some(data.chunks_exact(4).flat_map(|p| [p[2], p[1], p[0], p[3]]).collect()),
+      ImageFormat::ABGR8888 => Some(data.chunks_exact(4).flat_map(|p| [p[3], p[2], p[1], p[0]]).collect()),
+      ImageFormat::BGRX8888 => Some(data.chunks_exact(4).flat_map(|p| [p[2], p[1], p[0], 255]).collect()),
+      ImageFormat::BGR888 => Some(data.chunks_exact(3).flat_map(|p| [p[2], p[1], p[0], 255]).collect()),
+      _ => None
+    }
+  }
+
+  /// Reads a single image and converts it to tightly packed RGBA16F pixels, stored as half float bit patterns.
+  /// `mip` 0 is the full resolution image.
+  /// Returns None if the image doesn't exist or the format can't be converted to RGBA16F.
+  pub fn decode_to_rgba16f(&mut self, mip: u32, frame: u32, face: u32) -> Option<Vec<u16>> {
+    let format = self.header.high_res_image_format;
+    let (width, height) = self.mip_dimensions(mip);
+    let data = self.raw_data(mip, frame, face, 0)?;
+    match format {
+      ImageFormat::BC6H => bc_decode::decode_bc6h(data, width, height, false),
+      ImageFormat::RGBA16161616F => Some(data.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect()),
+      _ => None
+    }
+  }
+
   fn read_resource_offsets(reader: &mut R, header: &Header) -> IOResult<HashMap<Resource, u32>> {
     let has_thumbnail = header.low_res_image_width != 0
       && header.low_res_image_height != 0
@@ -149,4 +238,87 @@ impl<R: Read + Seek> VtfTexture<R> {
 
     Ok(resource_offsets)
   }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Cursor;
+
+  use super::*;
+  use crate::header::EXPECTED_SIGNATURE;
+
+  const IMAGE_OFFSET: u32 = 88;
+
+  /// Builds a 7.4 RGBA8888 texture with 4x4, 2x2 and 1x1 mips and two frames.
+  /// Every byte of a subresource is `mip * 16 + frame`.
+  fn build_vtf() -> Vec<u8> {
+    let mut data = Vec::<u8>::new();
+    data.extend_from_slice(&EXPECTED_SIGNATURE.to_le_bytes());
+    data.extend_from_slice(&7u32.to_le_bytes());
+    data.extend_from_slice(&4u32.to_le_bytes());
+    data.extend_from_slice(&IMAGE_OFFSET.to_le_bytes());
+    data.extend_from_slice(&4u16.to_le_bytes());
+    data.extend_from_slice(&4u16.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&2u16.to_le_bytes()); // frames
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data.extend_from_slice(&[0u8; 4 + 12 + 4]);
+    data.extend_from_slice(&1f32.to_le_bytes());
+    data.extend_from_slice(&(ImageFormat::RGBA8888 as u32).to_le_bytes());
+    data.push(3); // mips
+    // No thumbnail
+    data.extend_from_slice(&u32::MAX.to_le_bytes());
+    data.extend_from_slice(&[0u8; 2]);
+    data.extend_from_slice(&1u16.to_le_bytes());
+    data.extend_from_slice(&[0u8; 3]);
+    data.extend_from_slice(&1u32.to_le_bytes()); // resources
+    data.extend_from_slice(&[0u8; 8]);
+    assert_eq!(data.len(), 80);
+
+    data.extend_from_slice(&[0x30, 0, 0, 0]);
+    data.extend_from_slice(&IMAGE_OFFSET.to_le_bytes());
+    assert_eq!(data.len(), IMAGE_OFFSET as usize);
+
+    for mip in (0..3u32).rev() {
+      let size = 4usize >> mip;
+      for frame in 0..2u32 {
+        data.resize(data.len() + size * size * 4, (mip * 16 + frame) as u8);
+      }
+    }
+    data
+  }
+
+  #[test]
+  fn subresource_offsets() {
+    let mut texture = VtfTexture::new(Cursor::new(build_vtf())).unwrap();
+    assert_eq!(texture.mip_count(), 3);
+    assert_eq!(texture.frame_count(), 2);
+    assert_eq!(texture.face_count(), 1);
+
+    for mip in 0..3u32 {
+      let (width, height) = texture.mip_dimensions(mip);
+      assert_eq!((width, height), (4 >> mip, 4 >> mip));
+      for frame in 0..2u32 {
+        let expected = vec![(mip * 16 + frame) as u8; (width * height * 4) as usize];
+        assert_eq!(texture.raw_data(mip, frame, 0, 0).unwrap(), &expected[..]);
+      }
+    }
+    assert!(texture.raw_data(3, 0, 0, 0).is_none());
+    assert!(texture.raw_data(0, 2, 0, 0).is_none());
+  }
+
+  #[test]
+  fn read_mip_map_matches_raw_data() {
+    let mut texture = VtfTexture::new(Cursor::new(build_vtf())).unwrap();
+    for mip in 0..3u32 {
+      let mip_map = texture.read_mip_map(mip).unwrap();
+      assert_eq!((mip_map.width, mip_map.height), texture.mip_dimensions(mip));
+      assert_eq!(mip_map.frames.len(), 2);
+      for (frame, frame_data) in mip_map.frames.iter().enumerate() {
+        let raw_data = texture.raw_data(mip, frame as u32, 0, 0).unwrap();
+        assert_eq!(&frame_data.faces[0].slices[0].data[..], raw_data);
+      }
+    }
+    assert!(texture.read_mip_map(3).is_none());
+  }
+}
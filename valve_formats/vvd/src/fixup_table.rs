@@ -1,6 +1,6 @@
 use std::io::{Read, Result as IOResult};
 
-use crate::PrimitiveRead;
+use crate::{PrimitiveRead, Vertex};
 
 pub struct VertexFileFixup {
   pub lod: i32,
@@ -19,4 +19,24 @@ impl VertexFileFixup {
       vertices_count: vertexes_count
     })
   }
+
+  /// Builds the vertex list for the given root LOD.
+  /// Every fixup copies a range of the original vertices. `lod` is the last LOD that still uses that range,
+  /// so all fixups with a lower LOD get dropped. Models without fixups already store the vertices in the right order.
+  pub fn apply(fixups: &[VertexFileFixup], vertices: &[Vertex], root_lod: i32) -> Vec<Vertex> {
+    if fixups.is_empty() {
+      return vertices.to_vec();
+    }
+
+    let mut fixed_vertices = Vec::<Vertex>::with_capacity(vertices.len());
+    for fixup in fixups {
+      if fixup.lod < root_lod {
+        continue;
+      }
+      let start = (fixup.source_vertex_id.max(0) as usize).min(vertices.len());
+      let end = (start + fixup.vertices_count.max(0) as usize).min(vertices.len());
+      fixed_vertices.extend_from_slice(&vertices[start .. end]);
+    }
+    fixed_vertices
+  }
 }
@@ -13,6 +13,9 @@ pub struct Vertex {
 }
 
 impl Vertex {
+  /// The size of a vertex in the file. Model vertex offsets in the MDL are in bytes.
+  pub const SIZE: u32 = 48;
+
   pub fn read(read: &mut dyn Read) -> IOResult<Self> {
     let bone_weights = BoneWeight::read(read)?;
     let vec_position = Vec3::new(read.read_f32()?, read.read_f32()?, read.read_f32()?);